@@ -61,6 +61,12 @@ fn main() {
             .include("src/ui")
             .compile("wizard_darwin");
 
+        // Build the recording indicator Objective-C library
+        cc::Build::new()
+            .file("src/ui/indicator_darwin.m")
+            .flag("-fobjc-arc")
+            .compile("indicator_darwin");
+
         configure_sparkle();
 
         // Link frameworks
@@ -143,6 +149,7 @@ fn main() {
     println!("cargo:rerun-if-changed=src/ui/updater_darwin.m");
     println!("cargo:rerun-if-changed=src/ui/wizard_darwin.h");
     println!("cargo:rerun-if-changed=src/ui/wizard_darwin.m");
+    println!("cargo:rerun-if-changed=src/ui/indicator_darwin.m");
 }
 
 // The version the Windows auto-updater compares (and that the appcast carries)
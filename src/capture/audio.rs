@@ -0,0 +1,380 @@
+//! Global audio sources (system audio and microphone) for embedded libobs
+//!
+//! Display/window capture sources carry no audio on Windows and Linux, and on macOS
+//! ScreenCaptureKit only provides the captured app's/display's own audio. Desktop audio
+//! and the microphone are therefore separate OBS input sources bound to the global
+//! audio output channels, the same way OBS Studio wires "Desktop Audio" and "Mic/Aux".
+
+use anyhow::{Context as _, Result};
+use libobs_wrapper::context::ObsContext;
+use libobs_wrapper::unsafe_send::Sendable;
+use std::ffi::{c_void, CStr, CString};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::{debug, info, warn};
+
+/// OBS output channel for desktop audio (OBS Studio's "Desktop Audio")
+const SYSTEM_AUDIO_CHANNEL: u32 = 1;
+/// OBS output channel for the microphone (OBS Studio's "Mic/Aux")
+const MICROPHONE_CHANNEL: u32 = 3;
+
+/// `device_id` value that makes an OBS audio source follow the OS default device
+pub const DEFAULT_AUDIO_DEVICE: &str = "default";
+
+/// Settings key holding the device on every platform's audio capture source
+const DEVICE_ID_KEY: &str = "device_id";
+
+#[cfg(target_os = "macos")]
+const INPUT_SOURCE_ID: &str = "coreaudio_input_capture";
+#[cfg(target_os = "windows")]
+const INPUT_SOURCE_ID: &str = "wasapi_input_capture";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const INPUT_SOURCE_ID: &str = "pulse_input_capture";
+
+/// System audio source. None on macOS, where system audio comes from the
+/// ScreenCaptureKit capture source itself (`RecordingConfig::enable_audio`).
+#[cfg(target_os = "macos")]
+const OUTPUT_SOURCE_ID: Option<&str> = None;
+#[cfg(target_os = "windows")]
+const OUTPUT_SOURCE_ID: Option<&str> = Some("wasapi_output_capture");
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const OUTPUT_SOURCE_ID: Option<&str> = Some("pulse_output_capture");
+
+/// An audio input device as reported by OBS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioDevice {
+    /// Value for the source's `device_id` setting
+    pub id: String,
+    /// Human-readable device name
+    pub name: String,
+}
+
+/// Resolve the configured microphone to the `device_id` setting of the input source.
+/// Accepts a device id exactly or a device name case-insensitively; "default" or an
+/// empty string follows the OS default. An unknown device falls back to the default.
+pub fn microphone_device_id(requested: &str, devices: &[AudioDevice]) -> String {
+    let requested = requested.trim();
+    if requested.is_empty() || requested.eq_ignore_ascii_case(DEFAULT_AUDIO_DEVICE) {
+        return DEFAULT_AUDIO_DEVICE.to_string();
+    }
+    if let Some(device) = devices.iter().find(|d| d.id == requested).or_else(|| {
+        devices
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(requested))
+    }) {
+        return device.id.clone();
+    }
+    warn!(
+        "Microphone '{}' not found among {} audio input device(s); using the default device",
+        requested,
+        devices.len()
+    );
+    DEFAULT_AUDIO_DEVICE.to_string()
+}
+
+/// List the audio input devices OBS can capture from. Requires an initialized context.
+pub fn list_audio_devices(context: &ObsContext) -> Result<Vec<AudioDevice>> {
+    let runtime = context.runtime().clone();
+    let devices = libobs_wrapper::run_with_obs!(runtime, move || unsafe {
+        let mut devices = Vec::new();
+        let source_id = CString::new(INPUT_SOURCE_ID).unwrap();
+        let key = CString::new(DEVICE_ID_KEY).unwrap();
+        let props = libobs::obs_get_source_properties(source_id.as_ptr());
+        if props.is_null() {
+            return devices;
+        }
+        let prop = libobs::obs_properties_get(props, key.as_ptr());
+        if !prop.is_null() {
+            for i in 0..libobs::obs_property_list_item_count(prop) {
+                let name = libobs::obs_property_list_item_name(prop, i);
+                let id = libobs::obs_property_list_item_string(prop, i);
+                if name.is_null() || id.is_null() {
+                    continue;
+                }
+                devices.push(AudioDevice {
+                    id: CStr::from_ptr(id).to_string_lossy().into_owned(),
+                    name: CStr::from_ptr(name).to_string_lossy().into_owned(),
+                });
+            }
+        }
+        libobs::obs_properties_destroy(props);
+        devices
+    })
+    .context("Failed to enumerate audio devices")?;
+    Ok(devices)
+}
+
+/// Highest peak level (dBFS) a volmeter reported since the last `take`. Written from
+/// OBS's audio thread, so it is kept as the f32's bits; NaN means no reading yet.
+struct PeakLevel(AtomicU32);
+
+impl PeakLevel {
+    fn new() -> Self {
+        Self(AtomicU32::new(f32::NAN.to_bits()))
+    }
+
+    fn record(&self, db: f32) {
+        if db.is_nan() {
+            return;
+        }
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                let current = f32::from_bits(bits);
+                (current.is_nan() || db > current).then_some(db.to_bits())
+            });
+    }
+
+    fn take(&self) -> Option<f32> {
+        let db = f32::from_bits(self.0.swap(f32::NAN.to_bits(), Ordering::Relaxed));
+        (!db.is_nan()).then_some(db)
+    }
+}
+
+/// Volmeter callback: keep the loudest channel's peak. Unused channels report -inf.
+unsafe extern "C" fn record_peak(
+    param: *mut c_void,
+    _magnitude: *const f32,
+    peak: *const f32,
+    _input_peak: *const f32,
+) {
+    if param.is_null() || peak.is_null() {
+        return;
+    }
+    let level = &*(param as *const PeakLevel);
+    let peaks = std::slice::from_raw_parts(peak, libobs::MAX_AUDIO_CHANNELS as usize);
+    level.record(peaks.iter().copied().fold(f32::NEG_INFINITY, f32::max));
+}
+
+/// An OBS volmeter attached to one audio source, and the peak it accumulates
+struct AudioMeter {
+    volmeter: Sendable<*mut libobs::obs_volmeter_t>,
+    /// Boxed so the callback's pointer to it stays valid
+    level: Box<PeakLevel>,
+    /// Output channel of the metered source
+    channel: u32,
+}
+
+/// Peak levels (dBFS) the meters reported since the previous `take_peaks`. None where
+/// nothing was metered (no such source, or no audio processed yet); silence reads as -inf.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioPeaks {
+    /// Loudest across every metered source
+    pub overall: Option<f32>,
+    /// The microphone's alone
+    pub microphone: Option<f32>,
+}
+
+/// Desktop-audio and microphone sources bound to the global output channels, each with a
+/// level meter. Unbinds and releases them on drop, so it must be dropped before the OBS
+/// context is torn down.
+pub struct GlobalAudioSources {
+    context: ObsContext,
+    sources: Vec<(u32, Sendable<*mut libobs::obs_source_t>)>,
+    meters: Vec<AudioMeter>,
+}
+
+impl GlobalAudioSources {
+    /// Create the requested sources. `system` adds desktop audio where the platform has a
+    /// separate output-capture source; `microphone` is a resolved `device_id` (see
+    /// `microphone_device_id`).
+    pub fn new(context: &ObsContext, system: bool, microphone: Option<&str>) -> Result<Self> {
+        let mut audio = Self {
+            context: context.clone(),
+            sources: Vec::new(),
+            meters: Vec::new(),
+        };
+
+        if system {
+            match OUTPUT_SOURCE_ID {
+                Some(source_id) => audio.add(
+                    SYSTEM_AUDIO_CHANNEL,
+                    source_id,
+                    "system_audio",
+                    DEFAULT_AUDIO_DEVICE,
+                )?,
+                None => debug!("System audio is captured by the screen capture source"),
+            }
+        }
+
+        if let Some(device_id) = microphone {
+            audio.add(MICROPHONE_CHANNEL, INPUT_SOURCE_ID, "microphone", device_id)?;
+        }
+
+        Ok(audio)
+    }
+
+    fn add(&mut self, channel: u32, source_id: &str, name: &str, device_id: &str) -> Result<()> {
+        let source_id_c = CString::new(source_id)?;
+        let name_c = CString::new(name)?;
+        let key_c = CString::new(DEVICE_ID_KEY)?;
+        let device_c = CString::new(device_id)?;
+        let runtime = self.context.runtime().clone();
+
+        let source = libobs_wrapper::run_with_obs!(runtime, move || unsafe {
+            let settings = libobs::obs_data_create();
+            libobs::obs_data_set_string(settings, key_c.as_ptr(), device_c.as_ptr());
+            let source = libobs::obs_source_create(
+                source_id_c.as_ptr(),
+                name_c.as_ptr(),
+                settings,
+                std::ptr::null_mut(),
+            );
+            libobs::obs_data_release(settings);
+            if !source.is_null() {
+                libobs::obs_set_output_source(channel, source);
+            }
+            Sendable(source)
+        })?;
+
+        if source.0.is_null() {
+            anyhow::bail!("Failed to create {} audio source '{}'", source_id, name);
+        }
+        info!(
+            "Capturing audio from {} (device: {}, channel {})",
+            source_id, device_id, channel
+        );
+        let raw_source = source.0;
+        self.sources.push((channel, source));
+        self.add_meter(raw_source, channel, name);
+        Ok(())
+    }
+
+    /// Attach a volmeter to `source`, bound to `channel`. Best-effort: without it the
+    /// source is recorded but not level-checked.
+    fn add_meter(&mut self, source: *mut libobs::obs_source_t, channel: u32, name: &str) {
+        let level = Box::new(PeakLevel::new());
+        let param = &*level as *const PeakLevel as usize;
+        let source = Sendable(source);
+        let runtime = self.context.runtime().clone();
+        let volmeter = libobs_wrapper::run_with_obs!(runtime, (source), move || unsafe {
+            let volmeter = libobs::obs_volmeter_create(libobs::obs_fader_type_OBS_FADER_LOG);
+            if volmeter.is_null() {
+                return Sendable(volmeter);
+            }
+            if !libobs::obs_volmeter_attach_source(volmeter, source) {
+                libobs::obs_volmeter_destroy(volmeter);
+                return Sendable(std::ptr::null_mut());
+            }
+            libobs::obs_volmeter_add_callback(volmeter, Some(record_peak), param as *mut c_void);
+            Sendable(volmeter)
+        });
+        match volmeter {
+            Ok(volmeter) if !volmeter.0.is_null() => self.meters.push(AudioMeter {
+                volmeter,
+                level,
+                channel,
+            }),
+            Ok(_) => warn!("Failed to attach a level meter to audio source '{}'", name),
+            Err(e) => warn!(
+                "Failed to attach a level meter to audio source '{}': {}",
+                name, e
+            ),
+        }
+    }
+
+    /// Peak levels since the previous call
+    pub fn take_peaks(&self) -> AudioPeaks {
+        let mut peaks = AudioPeaks::default();
+        for meter in &self.meters {
+            let Some(db) = meter.level.take() else {
+                continue;
+            };
+            peaks.overall = Some(peaks.overall.map_or(db, |overall| overall.max(db)));
+            if meter.channel == MICROPHONE_CHANNEL {
+                peaks.microphone = Some(db);
+            }
+        }
+        peaks
+    }
+}
+
+impl Drop for GlobalAudioSources {
+    fn drop(&mut self) {
+        // Meters go first: their callbacks point into `level`, which must outlive them.
+        for meter in self.meters.drain(..) {
+            let runtime = self.context.runtime().clone();
+            let volmeter = meter.volmeter;
+            let param = &*meter.level as *const PeakLevel as usize;
+            if let Err(e) = libobs_wrapper::run_with_obs!(runtime, (volmeter), move || unsafe {
+                libobs::obs_volmeter_remove_callback(
+                    volmeter,
+                    Some(record_peak),
+                    param as *mut c_void,
+                );
+                libobs::obs_volmeter_detach_source(volmeter);
+                libobs::obs_volmeter_destroy(volmeter);
+            }) {
+                // The callback may still fire; leak its target rather than free it.
+                warn!("Failed to release audio level meter: {}", e);
+                std::mem::forget(meter.level);
+            }
+        }
+        for (channel, source) in self.sources.drain(..) {
+            let runtime = self.context.runtime().clone();
+            if let Err(e) = libobs_wrapper::run_with_obs!(runtime, (source), move || unsafe {
+                libobs::obs_set_output_source(channel, std::ptr::null_mut());
+                libobs::obs_source_release(source);
+            }) {
+                warn!(
+                    "Failed to release audio source on channel {}: {}",
+                    channel, e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDevice {
+                id: "BuiltInMicrophoneDevice".to_string(),
+                name: "MacBook Pro Microphone".to_string(),
+            },
+            AudioDevice {
+                id: "AppleUSBAudioEngine:Blue:Yeti".to_string(),
+                name: "Yeti Stereo Microphone".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn peak_level_keeps_the_loudest_reading_until_taken() {
+        let level = PeakLevel::new();
+        assert_eq!(level.take(), None);
+        level.record(f32::NEG_INFINITY);
+        level.record(-30.0);
+        level.record(f32::NAN);
+        level.record(-45.0);
+        assert_eq!(level.take(), Some(-30.0));
+        assert_eq!(level.take(), None);
+        level.record(f32::NEG_INFINITY);
+        assert_eq!(level.take(), Some(f32::NEG_INFINITY));
+    }
+
+    #[test]
+    fn microphone_device_id_resolves_names_and_ids() {
+        let devices = devices();
+        assert_eq!(
+            microphone_device_id("yeti stereo microphone", &devices),
+            "AppleUSBAudioEngine:Blue:Yeti"
+        );
+        assert_eq!(
+            microphone_device_id("BuiltInMicrophoneDevice", &devices),
+            "BuiltInMicrophoneDevice"
+        );
+        assert_eq!(
+            microphone_device_id("Default", &devices),
+            DEFAULT_AUDIO_DEVICE
+        );
+        assert_eq!(microphone_device_id("", &devices), DEFAULT_AUDIO_DEVICE);
+        // A device that is unplugged (or mistyped) falls back to the default.
+        assert_eq!(
+            microphone_device_id("Headset", &devices),
+            DEFAULT_AUDIO_DEVICE
+        );
+    }
+}
@@ -27,14 +27,22 @@ use libobs_wrapper::utils::{ObsPath, StartupPaths};
 use std::collections::{HashMap, HashSet};
 #[cfg(not(target_os = "linux"))]
 use std::convert::Infallible;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tracing::{debug, info, warn};
 
+use crate::config::{CaptureLayout, ExistingOutputPolicy, TargetAppMatcher};
 use crate::crash::log_critical_operation;
 
+use super::audio::{list_audio_devices, microphone_device_id, AudioPeaks, GlobalAudioSources};
+use super::error::CaptureError;
 use super::frontmost::get_frontmost_app;
-use super::recording::{calculate_output_dimensions, RecordingConfig, RecordingOutput};
+use super::obs_log::ObsFileLogger;
+use super::recording::{
+    calculate_output_dimensions, list_video_encoders, select_video_encoder, RecordingConfig,
+    RecordingOutput,
+};
+use super::scene_layout::layout_items;
 use super::sources::{get_main_display_resolution, get_main_display_uuid, ScreenCaptureSource};
 use super::CaptureState;
 
@@ -53,6 +61,48 @@ pub struct RecordingSession {
     pub start_time_ns: u64,
 }
 
+/// Which requested apps `setup_capture` created a capture source for. A source that fails
+/// is skipped rather than failing the setup, so the rest keep recording; `failed` is what
+/// the user has to be told about.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetupOutcome {
+    /// Apps a capture source was created for
+    pub created: Vec<String>,
+    /// Apps left without a capture source, with why
+    pub failed: Vec<(String, String)>,
+}
+
+impl SetupOutcome {
+    /// Note how creating `app`'s source went, handing the source back when it was created
+    fn record<T>(&mut self, app: &str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(source) => {
+                self.created.push(app.to_string());
+                Some(source)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to create capture source for '{}': {}. Skipping.",
+                    app, e
+                );
+                self.failed.push((app.to_string(), format!("{:#}", e)));
+                None
+            }
+        }
+    }
+
+    /// The apps in `failed`, for a notification
+    pub fn failed_apps(&self) -> Vec<String> {
+        self.failed.iter().map(|(app, _)| app.clone()).collect()
+    }
+}
+
+/// Longest `shutdown` waits for a stopped recording output to finish writing
+pub const SHUTDOWN_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often `shutdown` checks whether the output has finished
+const SHUTDOWN_FLUSH_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
 /// Manages the embedded libobs context with screen capture and recording
 pub struct CaptureContext {
     /// The libobs context (None if not yet initialized)
@@ -100,17 +150,29 @@ pub struct CaptureContext {
     output_directory: PathBuf,
     /// Recording configuration
     recording_config: RecordingConfig,
+    /// What `start_recording` does when the output file already exists
+    on_existing: ExistingOutputPolicy,
+    /// Microphone to capture (device id/name or "default"; None = no microphone)
+    microphone: Option<String>,
+    /// Global desktop-audio/microphone sources. Must be dropped before `context`.
+    audio_sources: Option<GlobalAudioSources>,
     /// The canvas (base) dimensions in pixels that OBS is currently compositing into, captured
     /// whenever the video info is (re)built. Recorded in the segment metadata as the true frame
     /// size (with multi-monitor on this is the normalized envelope, not the main display).
     canvas_dims: (u32, u32),
     /// Target apps for capture (stored for recreation after display changes)
     target_apps: Vec<String>,
+    /// `target_apps` compiled, so glob/regex entries match concrete app ids
+    target_matcher: TargetAppMatcher,
     /// Restore tokens for portal-backed display capture (Linux/Wayland), keyed by
     /// `DISPLAY_CAPTURE_KEY`.
     restore_tokens: HashMap<String, String>,
+    /// Requested apps the last `setup_capture` couldn't create a source for
+    failed_apps: Vec<String>,
     /// Whether macOS should keep only one tracked application's source active at a time
     single_active_app_capture: bool,
+    /// How multi-source app capture arranges its sources on the canvas
+    layout: CaptureLayout,
     /// Currently active application capture target when single-active mode is enabled
     active_capture_app: Option<String>,
     /// Windows/macOS monitor-level fit last applied to the active source, used to skip
@@ -211,7 +273,12 @@ impl CaptureContext {
             }
         }
 
-        Ok(Self {
+        Ok(Self::uninitialized(output_directory))
+    }
+
+    /// A context with nothing created yet: no libobs, sources or recording
+    fn uninitialized(output_directory: PathBuf) -> Self {
+        Self {
             context: None,
             scene: None,
             capture_sources: Vec::new(),
@@ -230,10 +297,16 @@ impl CaptureContext {
             state: Arc::new(RwLock::new(CaptureState::default())),
             output_directory,
             recording_config: RecordingConfig::default(),
+            on_existing: ExistingOutputPolicy::default(),
+            microphone: None,
+            audio_sources: None,
             canvas_dims: (0, 0),
             target_apps: Vec::new(),
+            target_matcher: TargetAppMatcher::default(),
             restore_tokens: HashMap::new(),
+            failed_apps: Vec::new(),
             single_active_app_capture: false,
+            layout: CaptureLayout::default(),
             active_capture_app: None,
             #[cfg(any(target_os = "windows", target_os = "macos"))]
             last_monitor_fit: None,
@@ -241,7 +314,7 @@ impl CaptureContext {
             mac_multi_monitor_capture: false,
             #[cfg(target_os = "macos")]
             last_display_uuid: HashMap::new(),
-        })
+        }
     }
 
     /// Bootstrap OBS binaries (macOS/Windows only; Linux uses system or bundled libobs).
@@ -266,20 +339,25 @@ impl CaptureContext {
         #[cfg(not(target_os = "macos"))]
         let options = ObsBootstrapperOptions::default().set_update(false);
 
-        // Do not auto-update OBS at runtime. Only install when missing.
-        let obs_present = ObsBootstrapper::is_valid_installation_with_options(&options)
-            .context("Failed to check OBS installation")?;
-        if obs_present {
-            debug!("OBS installation already present; skipping runtime update checks");
-            return Ok(ObsBootstrapperResult::None);
-        }
-
-        ObsBootstrapper::bootstrap_with_handler(
-            &options,
-            Box::new(ObsBootstrapNotificationHandler::new(notify_download)),
+        // Do not auto-update OBS at runtime. Only install when missing. A failed download
+        // (typically: offline) falls back to whatever install is already on disk.
+        let install_dir = obs_install_dir();
+        let installed = install_or_use_cached(
+            ObsBootstrapper::is_valid_installation_with_options(&options)
+                .context("Failed to check OBS installation"),
+            async {
+                ObsBootstrapper::bootstrap_with_handler(
+                    &options,
+                    Box::new(ObsBootstrapNotificationHandler::new(notify_download)),
+                )
+                .await
+                .context("Failed to bootstrap OBS binaries")
+            },
+            || install_dir.as_deref().is_some_and(cached_install_usable),
+            install_dir.as_deref(),
         )
-        .await
-        .context("Failed to bootstrap OBS binaries")
+        .await?;
+        Ok(installed.unwrap_or(ObsBootstrapperResult::None))
     }
 
     /// Pre-populate the target app list before [`initialize`](Self::initialize) so the capture
@@ -288,6 +366,7 @@ impl CaptureContext {
     /// the authoritative list later; this is idempotent with it.
     pub fn set_target_apps(&mut self, target_apps: &[String]) {
         self.target_apps = target_apps.to_vec();
+        self.target_matcher = TargetAppMatcher::new(target_apps);
     }
 
     /// Compute the recording canvas (base) and encoded output dimensions.
@@ -379,7 +458,7 @@ impl CaptureContext {
     /// This configures the video output based on `recording_config`:
     /// - Output resolution is downscaled to max_output_height while preserving aspect ratio
     /// - FPS is set from recording_config.fps
-    pub fn initialize(&mut self) -> Result<()> {
+    pub fn initialize(&mut self) -> Result<(), CaptureError> {
         if self.context.is_some() {
             debug!("libobs context already initialized");
             return Ok(());
@@ -417,10 +496,34 @@ impl CaptureContext {
         if let Some(paths) = obs_startup_paths_from_env() {
             startup_info = startup_info.set_startup_paths(paths);
         }
+        if let Some(dir) = obs_log_dir() {
+            startup_info = startup_info.set_logger(Box::new(ObsFileLogger::new(&dir)));
+        }
         log_critical_operation("initialize: calling ObsContext::new()");
-        let context = ObsContext::new(startup_info).context("Failed to create OBS context")?;
+        let context = ObsContext::new(startup_info)
+            .context("Failed to create OBS context")
+            .map_err(CaptureError::Obs)?;
         log_critical_operation("initialize: ObsContext::new() completed");
 
+        let encoders = list_video_encoders(&context).map_err(CaptureError::Obs)?;
+        let Some(encoder) = select_video_encoder(self.recording_config.codec_preference, &encoders)
+        else {
+            return Err(CaptureError::NoVideoEncoder {
+                available: encoders,
+            });
+        };
+        info!(
+            "Video encoder: {} ({:?}, {})",
+            encoder.id,
+            encoder.codec,
+            if encoder.hardware {
+                "hardware"
+            } else {
+                "software"
+            }
+        );
+        self.recording_config.video_encoder = Some(encoder);
+
         info!("libobs context initialized successfully");
         self.context = Some(context);
 
@@ -434,6 +537,62 @@ impl CaptureContext {
         self.single_active_app_capture = enabled;
     }
 
+    /// Choose how multi-source app capture arranges its sources (`capture.layout`).
+    /// Applied by the next `setup_capture`.
+    pub fn set_capture_layout(&mut self, layout: CaptureLayout) {
+        self.layout = layout;
+    }
+
+    /// Choose what `start_recording` does when the output file already exists
+    /// (`recording.on_existing`).
+    pub fn set_existing_output_policy(&mut self, policy: ExistingOutputPolicy) {
+        self.on_existing = policy;
+    }
+
+    /// Choose audio capture: `system` for desktop audio, `microphone` for an input device
+    /// (id, name, or "default"). Applied by the next `setup_capture`.
+    pub fn set_audio_capture(&mut self, system: bool, microphone: Option<String>) {
+        self.recording_config.enable_audio = system;
+        self.microphone = microphone;
+    }
+
+    /// (Re)create the global desktop-audio/microphone sources. Audio is best-effort: a
+    /// failure is logged and recording continues video-only.
+    fn setup_audio_sources(&mut self) {
+        self.audio_sources = None;
+        let system = self.recording_config.enable_audio;
+        if !system && self.microphone.is_none() {
+            return;
+        }
+        let Some(context) = self.context.as_ref() else {
+            return;
+        };
+        let microphone = self.microphone.as_deref().map(|requested| {
+            let devices = list_audio_devices(context).unwrap_or_else(|e| {
+                warn!("{:#}", e);
+                Vec::new()
+            });
+            for device in &devices {
+                info!("Audio input device: {} (id: {})", device.name, device.id);
+            }
+            microphone_device_id(requested, &devices)
+        });
+        match GlobalAudioSources::new(context, system, microphone.as_deref()) {
+            Ok(sources) => self.audio_sources = Some(sources),
+            Err(e) => warn!("Audio capture unavailable, recording video only: {:#}", e),
+        }
+    }
+
+    /// Peak levels of the captured desktop audio and microphone since the previous call.
+    /// Empty when no audio source is metered; on macOS that includes system audio, which
+    /// the screen capture source carries.
+    pub fn audio_peaks(&self) -> AudioPeaks {
+        self.audio_sources
+            .as_ref()
+            .map(GlobalAudioSources::take_peaks)
+            .unwrap_or_default()
+    }
+
     /// Enable/disable the macOS multi-monitor capture path (normalized canvas + per-display
     /// fit). Set from `config.capture.mac_multi_monitor_capture` at startup. No-op off macOS.
     pub fn set_mac_multi_monitor_capture(&mut self, enabled: bool) {
@@ -506,11 +665,7 @@ impl CaptureContext {
         }
 
         let frontmost = get_frontmost_app()?;
-        if self
-            .target_apps
-            .iter()
-            .any(|app| Self::canonical_app_id(app) == Self::canonical_app_id(&frontmost.bundle_id))
-        {
+        if self.target_matcher.matches(&frontmost.bundle_id) {
             Some(Self::canonical_app_id(&frontmost.bundle_id))
         } else {
             None
@@ -550,7 +705,7 @@ impl CaptureContext {
     /// Create all per-app scenes and a blank scene for single-active-app mode.
     /// Each tracked app gets its own scene with one SCK source. All sources run
     /// simultaneously; switching apps just activates the target scene on channel 0.
-    fn setup_app_scenes(&mut self, initial_active_app: Option<&str>) -> Result<()> {
+    fn setup_app_scenes(&mut self, initial_active_app: Option<&str>) -> Result<SetupOutcome> {
         if !self.is_initialized() {
             anyhow::bail!("OBS context not initialized");
         }
@@ -620,6 +775,7 @@ impl CaptureContext {
             .context("Failed to get main display UUID for application capture")?;
         let capture_audio = self.recording_config.enable_audio;
         let target_apps = self.target_apps.clone();
+        let target_matcher = self.target_matcher.clone();
         let restore_tokens = self.restore_tokens.clone();
 
         let context = self
@@ -665,11 +821,13 @@ impl CaptureContext {
                 target_apps.iter().cloned().collect()
             }
         };
-        // Only consulted on macOS (see the per-app skip below).
-        #[cfg(not(target_os = "macos"))]
-        let _ = &running_bundles;
+        // Glob/regex entries name no app themselves: expand them to the matching running
+        // apps (macOS). Elsewhere, and for apps launched later, matching apps get a scene
+        // lazily on first focus via `needs_scene_for_app`.
+        let scene_apps = expand_target_apps(&target_apps, &running_bundles, &target_matcher);
 
-        for bundle_id in &target_apps {
+        let mut outcome = SetupOutcome::default();
+        for bundle_id in &scene_apps {
             // macOS: ScreenCaptureKit sources for apps not running at startup must be created
             // in a fresh OBS context (the engine restarts the process to do so), so skip them
             // here and create them lazily. XComposite (X11) has no such constraint — we
@@ -700,7 +858,7 @@ impl CaptureContext {
                 .context("Failed to create scene")?;
 
             let source_name = format!("app_capture_{}", bundle_id);
-            match ScreenCaptureSource::new_application_capture(
+            let created = ScreenCaptureSource::new_application_capture(
                 context,
                 &mut scene,
                 &source_name,
@@ -708,26 +866,19 @@ impl CaptureContext {
                 &display_uuid,
                 capture_audio,
                 restore_tokens.get(bundle_id).map(|s| s.as_str()),
-            ) {
-                Ok(source) => {
-                    // Key scenes by the canonical id so frontmost-derived lookups
-                    // (also canonical) match regardless of how target_apps is cased.
-                    // On macOS/Linux `canonical_app_id` is the identity, so this is the
-                    // raw bundle id / process name there.
-                    let canonical_id = Self::canonical_app_id(bundle_id);
-                    if initial_active_app == Some(canonical_id.as_str()) {
-                        Self::activate_scene(&mut scene)?;
-                        self.active_capture_app = Some(canonical_id.clone());
-                    }
-                    info!("Created app scene for '{}'", bundle_id);
-                    self.app_scenes.insert(canonical_id, (scene, source));
-                }
-                Err(e) => {
-                    warn!(
-                        "Failed to create capture source for '{}': {}. Skipping.",
-                        bundle_id, e
-                    );
+            );
+            if let Some(source) = outcome.record(bundle_id, created) {
+                // Key scenes by the canonical id so frontmost-derived lookups
+                // (also canonical) match regardless of how target_apps is cased.
+                // On macOS/Linux `canonical_app_id` is the identity, so this is the
+                // raw bundle id / process name there.
+                let canonical_id = Self::canonical_app_id(bundle_id);
+                if initial_active_app == Some(canonical_id.as_str()) {
+                    Self::activate_scene(&mut scene)?;
+                    self.active_capture_app = Some(canonical_id.clone());
                 }
+                info!("Created app scene for '{}'", bundle_id);
+                self.app_scenes.insert(canonical_id, (scene, source));
             }
         }
 
@@ -755,12 +906,12 @@ impl CaptureContext {
             self.active_capture_app
         );
         self.update_capture_state_flags();
-        Ok(())
+        Ok(outcome)
     }
 
     /// Set up capture for display capture mode or legacy multi-source mode.
     /// On Linux, per-app capture must use `setup_app_scenes`; this path is display-only.
-    fn setup_display_or_multi_capture(&mut self) -> Result<usize> {
+    fn setup_display_or_multi_capture(&mut self) -> Result<SetupOutcome> {
         if !self.is_initialized() {
             anyhow::bail!("OBS context not initialized");
         }
@@ -790,7 +941,10 @@ impl CaptureContext {
         let capture_audio = self.recording_config.enable_audio;
         let target_apps = self.target_apps.clone();
         let restore_tokens = self.restore_tokens.clone();
+        let layout = self.layout;
+        let canvas = self.canvas_dims;
         let mut capture_sources = Vec::new();
+        let mut outcome = SetupOutcome::default();
 
         let context = self
             .context
@@ -841,8 +995,17 @@ impl CaptureContext {
                 .context("Failed to get main display UUID for application capture")?;
 
             for (i, bundle_id) in target_apps.iter().enumerate() {
+                if TargetAppMatcher::is_pattern(bundle_id) {
+                    outcome.record::<()>(
+                        bundle_id,
+                        Err(anyhow::anyhow!(
+                            "multi-source capture needs concrete app ids, not patterns"
+                        )),
+                    );
+                    continue;
+                }
                 let source_name = format!("app_capture_{}", i);
-                match ScreenCaptureSource::new_application_capture(
+                let created = ScreenCaptureSource::new_application_capture(
                     context,
                     &mut scene,
                     &source_name,
@@ -850,20 +1013,13 @@ impl CaptureContext {
                     &display_uuid,
                     capture_audio,
                     restore_tokens.get(bundle_id).map(|s| s.as_str()),
-                ) {
-                    Ok(source) => {
-                        debug!(
-                            "Created capture source '{}' for '{}'",
-                            source_name, bundle_id
-                        );
-                        capture_sources.push(source);
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Failed to create capture source for '{}': {}. Skipping.",
-                            bundle_id, e
-                        );
-                    }
+                );
+                if let Some(source) = outcome.record(bundle_id, created) {
+                    debug!(
+                        "Created capture source '{}' for '{}'",
+                        source_name, bundle_id
+                    );
+                    capture_sources.push(source);
                 }
             }
 
@@ -873,14 +1029,14 @@ impl CaptureContext {
                     target_apps
                 );
             }
+            Self::apply_scene_layout(&scene, &capture_sources, layout, canvas);
         }
 
-        let count = capture_sources.len();
         Self::activate_scene(&mut scene)?;
         self.capture_sources = capture_sources;
         self.scene = Some(scene);
         self.update_capture_state_flags();
-        Ok(count)
+        Ok(outcome)
     }
 
     /// Set up capture sources and scene for specific applications
@@ -889,29 +1045,39 @@ impl CaptureContext {
     /// specified, creates a display-capture source instead.
     /// Must be called after `initialize()`.
     ///
+    /// Apps whose source can't be created are skipped and listed in the returned
+    /// outcome; it only fails when no requested app could be set up at all.
+    ///
     /// # Arguments
     /// * `target_apps` - List of bundle identifiers to capture (e.g., ["com.apple.Safari", "com.microsoft.VSCode"])
     pub fn setup_capture(
         &mut self,
         target_apps: &[String],
         restore_tokens: &HashMap<String, String>,
-    ) -> Result<()> {
-        self.target_apps = target_apps.to_vec();
+    ) -> Result<SetupOutcome> {
+        self.set_target_apps(target_apps);
         self.restore_tokens = restore_tokens.clone();
+        self.failed_apps.clear();
 
-        if self.use_single_active_app_capture() {
+        let outcome = if self.use_single_active_app_capture() {
             let initial_active_app = self.select_initial_active_app();
-            self.setup_app_scenes(initial_active_app.as_deref())?;
+            self.setup_app_scenes(initial_active_app.as_deref())?
         } else {
-            let count = self.setup_display_or_multi_capture()?;
+            let outcome = self.setup_display_or_multi_capture()?;
             if target_apps.is_empty() {
                 info!("Capture scene configured for display capture");
             } else {
-                info!("Created {} application capture sources", count);
+                info!(
+                    "Created {} application capture sources",
+                    outcome.created.len()
+                );
             }
-        }
+            outcome
+        };
+        self.setup_audio_sources();
 
-        Ok(())
+        self.failed_apps = outcome.failed_apps();
+        Ok(outcome)
     }
 
     /// Fully destroy and recreate capture sources after a display configuration change
@@ -938,7 +1104,8 @@ impl CaptureContext {
             info!("Fully recreated {} app scene(s)", count);
             Ok(count)
         } else {
-            let count = self.setup_display_or_multi_capture()?;
+            self.setup_display_or_multi_capture()?;
+            let count = self.capture_sources.len();
             info!("Fully recreated {} capture source(s)", count);
             Ok(count)
         }
@@ -1035,6 +1202,7 @@ impl CaptureContext {
         self.capture_sources.clear();
         self.app_scenes.clear();
         self.blank_scene = None;
+        self.audio_sources = None;
         log_critical_operation("reinitialize_for_display_change: dropping scene");
         self.scene = None;
         log_critical_operation("reinitialize_for_display_change: dropping recording");
@@ -1060,14 +1228,53 @@ impl CaptureContext {
             .context("Failed to reinitialize OBS context")?;
         self.fully_recreate_sources()
             .context("Failed to re-setup capture after reinit")?;
+        self.setup_audio_sources();
         log_critical_operation("reinitialize_for_display_change: completed successfully");
 
         Ok(())
     }
 
-    /// Set the recording configuration
+    /// Set the recording configuration. The encoder resolved at `initialize` is kept.
     pub fn set_recording_config(&mut self, config: RecordingConfig) {
+        let video_encoder = self.recording_config.video_encoder;
         self.recording_config = config;
+        self.recording_config.video_encoder = video_encoder;
+    }
+
+    /// OBS id of the video encoder the current recording uses, else the one resolved at
+    /// `initialize`, or None before it
+    pub fn video_encoder(&self) -> Option<String> {
+        let resolved = self
+            .recording_config
+            .video_encoder
+            .map(|encoder| encoder.id);
+        self.recording
+            .as_ref()
+            .and_then(|recording| recording.video_encoder())
+            .or(resolved)
+            .map(str::to_string)
+    }
+
+    /// Encode the next recording outputs at a fixed `kbps` video bitrate instead of the
+    /// quality (CRF) mode. Takes effect at the next `start_recording`.
+    pub fn set_video_bitrate(&mut self, kbps: u32) {
+        self.recording_config.video_bitrate = kbps;
+        self.recording_config.crf = None;
+    }
+
+    /// Current `(fps, max output height)`. Changes made with `set_video_settings` take
+    /// effect at the next `reset_video_and_recreate_sources`.
+    pub fn video_settings(&self) -> (u32, u32) {
+        (
+            self.recording_config.fps,
+            self.recording_config.max_output_height,
+        )
+    }
+
+    /// Set the fps and max output height used the next time the video is (re)built
+    pub fn set_video_settings(&mut self, (fps, max_output_height): (u32, u32)) {
+        self.recording_config.fps = fps;
+        self.recording_config.max_output_height = max_output_height;
     }
 
     /// The current recording canvas (base) dimensions in pixels — what OBS composites into,
@@ -1116,6 +1323,32 @@ impl CaptureContext {
         (None, Vec::new())
     }
 
+    /// Global point bounds of the display the video shows, for
+    /// `input.restrict_to_capture_region`: the active display on the macOS multi-monitor
+    /// path, otherwise the main display (what the display capture shows). `None` on other
+    /// platforms, where input positions and capture bounds don't share a coordinate space.
+    pub fn capture_region(&self) -> Option<crate::input::filter::CaptureRegion> {
+        #[cfg(target_os = "macos")]
+        {
+            let (active, all) = self.capture_layout_metadata();
+            let display = match active {
+                Some(display) => display,
+                None if all.is_empty() => super::mac_geometry::describe_all_displays()
+                    .into_iter()
+                    .find(|m| m.is_main)?,
+                None => all.into_iter().find(|m| m.is_main)?,
+            };
+            return Some(crate::input::filter::CaptureRegion {
+                x: display.x as f64,
+                y: display.y as f64,
+                width: display.width as f64,
+                height: display.height as f64,
+            });
+        }
+        #[allow(unreachable_code)]
+        None
+    }
+
     /// UUID of the display the active app is currently captured on (macOS multi-monitor), for
     /// reporting the active display and detecting a follow-focus switch (even between two
     /// same-resolution monitors). Prefers the retarget cache; before the first poll retargets
@@ -1140,41 +1373,48 @@ impl CaptureContext {
         None
     }
 
-    /// Generate output path for a new recording session
-    fn generate_output_path(&self, session_id: &str) -> PathBuf {
-        let extension = match self.recording_config.format {
-            libobs_simple::output::simple::OutputFormat::QuickTime
-            | libobs_simple::output::simple::OutputFormat::HybridMov
-            | libobs_simple::output::simple::OutputFormat::FragmentedMOV => "mov",
-            libobs_simple::output::simple::OutputFormat::MatroskaVideo => "mkv",
-            libobs_simple::output::simple::OutputFormat::FlashVideo => "flv",
-            libobs_simple::output::simple::OutputFormat::MpegTs => "ts",
-            _ => "mp4",
-        };
-
-        self.output_directory
-            .join(format!("recording_{}.{}", session_id, extension))
+    /// Session ID and output path for a new recording session, applying `on_existing` when
+    /// a file is already there. A suffix goes on the session ID, so the input log written
+    /// under that ID stays paired with the video.
+    fn generate_output_path(&self, session_id: &str) -> Result<(String, PathBuf), CaptureError> {
+        let extension = self.recording_config.extension();
+        let session_id = resolve_existing_output(
+            &self.output_directory,
+            session_id,
+            extension,
+            self.on_existing,
+        )?;
+        let path = self
+            .output_directory
+            .join(format!("recording_{}.{}", session_id, extension));
+        Ok((session_id, path))
     }
 
     /// Start recording a new session
     ///
-    /// Returns the session ID and output path.
-    pub fn start_recording(&mut self, session_id: String) -> Result<RecordingSession> {
+    /// Returns the session ID (which `on_existing = "suffix"` may have changed) and output path.
+    pub fn start_recording(
+        &mut self,
+        session_id: String,
+    ) -> Result<RecordingSession, CaptureError> {
         if self.recording.is_some() {
-            anyhow::bail!("Recording already in progress");
+            return Err(CaptureError::AlreadyRecording);
         }
 
         let context = self
             .context
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("OBS context not initialized"))?
+            .ok_or(CaptureError::NotInitialized)?
             .clone();
 
-        let output_path = self.generate_output_path(&session_id);
+        let (session_id, output_path) = self.generate_output_path(&session_id)?;
 
         // Ensure output directory exists
         if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent).context("Failed to create output directory")?;
+            std::fs::create_dir_all(parent).map_err(|source| CaptureError::OutputDirectory {
+                path: parent.to_path_buf(),
+                source,
+            })?;
         }
 
         info!(
@@ -1185,14 +1425,19 @@ impl CaptureContext {
         // Create and start recording
         let mut recording =
             RecordingOutput::new(context.clone(), output_path.clone(), &self.recording_config)
-                .context("Failed to create recording output")?;
+                .context("Failed to create recording output")
+                .map_err(CaptureError::Obs)?;
 
-        recording.start().context("Failed to start recording")?;
+        recording
+            .start()
+            .context("Failed to start recording")
+            .map_err(CaptureError::Obs)?;
 
         // Get the start timestamp from OBS
         let start_time_ns = context
             .get_video_frame_time()
-            .context("Failed to get video frame time")?;
+            .context("Failed to get video frame time")
+            .map_err(CaptureError::Obs)?;
 
         let session = RecordingSession {
             session_id,
@@ -1217,7 +1462,7 @@ impl CaptureContext {
     /// Stop the current recording session
     ///
     /// Returns the completed session info with output path.
-    pub fn stop_recording(&mut self) -> Result<Option<RecordingSession>> {
+    pub fn stop_recording(&mut self) -> Result<Option<RecordingSession>, CaptureError> {
         let recording = match self.recording.take() {
             Some(r) => r,
             None => {
@@ -1231,7 +1476,10 @@ impl CaptureContext {
         info!("Stopping recording...");
 
         let mut recording = recording;
-        let output_path = recording.stop().context("Failed to stop recording")?;
+        let output_path = recording
+            .stop()
+            .context("Failed to stop recording")
+            .map_err(CaptureError::Obs)?;
 
         info!("Recording stopped: {:?}", output_path);
 
@@ -1246,6 +1494,44 @@ impl CaptureContext {
         Ok(session)
     }
 
+    /// Stop any recording, wait for its output to finish writing, and release libobs.
+    /// Called on the engine's shutdown path so failures surface to the caller; `Drop`
+    /// repeats the same steps best-effort for exits that skip it. Safe to call more than
+    /// once. Blocks for up to `SHUTDOWN_FLUSH_TIMEOUT` plus the libobs teardown.
+    pub fn shutdown(&mut self) -> Result<()> {
+        log_critical_operation("CaptureContext::shutdown: starting");
+
+        if let Some(recording) = self.recording.as_mut() {
+            info!("Stopping recording for shutdown...");
+            recording.stop().context("Failed to stop recording")?;
+            // The muxer writes the file's index/trailer after the stop signal; releasing
+            // libobs before it is done would leave the last segment unreadable.
+            let deadline = std::time::Instant::now() + SHUTDOWN_FLUSH_TIMEOUT;
+            while recording.is_active().unwrap_or(false) {
+                if std::time::Instant::now() >= deadline {
+                    warn!(
+                        "Recording output still active after {:?}; releasing it anyway",
+                        SHUTDOWN_FLUSH_TIMEOUT
+                    );
+                    break;
+                }
+                std::thread::sleep(SHUTDOWN_FLUSH_POLL);
+            }
+        }
+        // Clears the session and capture state; the output is already stopped.
+        self.stop_recording()?;
+
+        // Audio sources hold OBS references; release them before the context goes away.
+        self.audio_sources = None;
+        if self.context.take().is_some() {
+            info!("Shutting down libobs context...");
+            log_critical_operation("CaptureContext::shutdown: dropping ObsContext");
+        }
+
+        log_critical_operation("CaptureContext::shutdown: completed");
+        Ok(())
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         self.recording.as_ref().map_or(false, |r| r.is_recording())
@@ -1308,6 +1594,11 @@ impl CaptureContext {
         self.active_capture_app.as_deref()
     }
 
+    /// Requested apps left without a capture source by the last `setup_capture`
+    pub fn failed_capture_apps(&self) -> &[String] {
+        &self.failed_apps
+    }
+
     /// The capture mode this context routes to, mirroring `setup_capture` exactly:
     /// "single_active_app" (follow-focus per-app), "display" (full-screen display
     /// capture; no target apps), or "multi_source_app" (legacy multi-source per-app).
@@ -1327,10 +1618,7 @@ impl CaptureContext {
     pub fn needs_scene_for_app(&self, bundle_id: &str) -> bool {
         let canonical = Self::canonical_app_id(bundle_id);
         self.use_single_active_app_capture()
-            && self
-                .target_apps
-                .iter()
-                .any(|a| Self::canonical_app_id(a) == canonical)
+            && self.target_matcher.matches(bundle_id)
             && !self.app_scenes.contains_key(&canonical)
     }
 
@@ -1501,6 +1789,46 @@ impl CaptureContext {
         }
     }
 
+    /// Place the multi-source scene's app-capture items per `capture.layout`: each item is
+    /// fit (scale-inner bounds, centered) inside its box from `layout_items`, so sources
+    /// that have not reported a size yet still land correctly once they do.
+    fn apply_scene_layout(
+        scene: &ObsSceneRef,
+        sources: &[ScreenCaptureSource],
+        layout: CaptureLayout,
+        canvas: (u32, u32),
+    ) {
+        use libobs_wrapper::enums::{obs_alignment, ObsBoundsType};
+        use libobs_wrapper::graphics::Vec2;
+        use libobs_wrapper::scenes::ObsTransformInfoBuilder;
+
+        for (source, place) in sources
+            .iter()
+            .zip(layout_items(layout, sources.len(), canvas))
+        {
+            let info = ObsTransformInfoBuilder::new()
+                .set_pos(Vec2::new(place.x, place.y))
+                .set_alignment(obs_alignment::LEFT | obs_alignment::TOP)
+                .set_bounds_type(ObsBoundsType::ScaleInner)
+                .set_bounds_alignment(obs_alignment::CENTER)
+                .set_bounds(Vec2::new(place.width, place.height))
+                .build(0, 0);
+            if let Err(e) = scene.set_transform_info(source.source(), &info) {
+                warn!("Failed to apply capture layout to a scene item: {}", e);
+                continue;
+            }
+            #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+            Self::set_area_scale_filter(scene, source.source());
+        }
+        debug!(
+            "Applied {:?} layout to {} capture source(s) on a {}x{} canvas",
+            layout,
+            sources.len(),
+            canvas.0,
+            canvas.1
+        );
+    }
+
     /// Multi-monitor per-app placement: draw the active app's captured window at its real
     /// on-monitor position, scaled by its monitor's 1080-short-edge normalization (4K window
     /// 0.5×, FHD 1.0×, ultrawide 0.75×, …), matching the Windows behaviour. Window + monitor
@@ -2138,6 +2466,79 @@ impl ObsBootstrapStatusHandler for ObsBootstrapNotificationHandler {
     }
 }
 
+/// Where the bootstrapper installs OBS: the external runtime dir on macOS, next to the
+/// executable on Windows.
+#[cfg(not(target_os = "linux"))]
+fn obs_install_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        obs_runtime_root()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(PathBuf::from))
+    }
+}
+
+/// Where libobs writes its own log files (through `ObsFileLogger`): `logs/` under the OBS
+/// runtime root (the self-provisioned bundle on Linux, the bootstrap install dir elsewhere).
+pub fn obs_log_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    let root = self_provisioned_bundle_root();
+    #[cfg(not(target_os = "linux"))]
+    let root = obs_install_dir();
+    root.map(|root| root.join("logs"))
+}
+
+/// An install libobs can start from, even if the bootstrapper would rather refresh it: the
+/// libobs effects data is there. Same heuristic as the Linux `bundle_is_present`.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn cached_install_usable(install_dir: &std::path::Path) -> bool {
+    install_dir.join("data/libobs/default.effect").exists()
+}
+
+/// Install OBS when `is_valid` says it is missing, via `download`. If the download fails
+/// (no network) but `cached_usable` finds an install to fall back on, start with that
+/// instead of aborting. Returns `None` when an existing install is used.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+async fn install_or_use_cached<R>(
+    is_valid: Result<bool>,
+    download: impl std::future::Future<Output = Result<R>>,
+    cached_usable: impl FnOnce() -> bool,
+    install_dir: Option<&std::path::Path>,
+) -> Result<Option<R>> {
+    match is_valid {
+        Ok(true) => {
+            debug!("OBS installation already present; skipping runtime update checks");
+            return Ok(None);
+        }
+        Ok(false) => {}
+        Err(e) => warn!("{:#}; trying to install OBS", e),
+    }
+
+    match download.await {
+        Ok(result) => Ok(Some(result)),
+        Err(e) if cached_usable() => {
+            warn!("OBS download failed ({:#}); using the cached install", e);
+            Ok(None)
+        }
+        Err(e) => {
+            let location = install_dir
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_else(|| "the OBS runtime directory".to_string());
+            Err(e.context(format!(
+                "Failed to download OBS binaries and no usable install was found in {}. \
+                 Connect to the internet and restart crowd-cast, or place an OBS install there.",
+                location
+            )))
+        }
+    }
+}
+
+/// Best-effort fallback for exits that skip [`CaptureContext::shutdown`]: same steps,
+/// but errors can only be logged and nothing waits for the output to finish writing.
 impl Drop for CaptureContext {
     fn drop(&mut self) {
         log_critical_operation("CaptureContext::drop: starting");
@@ -2151,6 +2552,9 @@ impl Drop for CaptureContext {
             }
         }
 
+        // Audio sources hold OBS references; release them before the context goes away.
+        self.audio_sources = None;
+
         if self.context.is_some() {
             info!("Shutting down libobs context...");
             log_critical_operation("CaptureContext::drop: dropping ObsContext (CRITICAL)");
@@ -2159,3 +2563,168 @@ impl Drop for CaptureContext {
         log_critical_operation("CaptureContext::drop: completed");
     }
 }
+
+/// The concrete app ids to pre-create scenes for: every literal `target_apps` entry, plus
+/// any running app matched by a glob/regex entry (sorted, for a stable scene order).
+fn expand_target_apps(
+    target_apps: &[String],
+    running_bundles: &HashSet<String>,
+    matcher: &TargetAppMatcher,
+) -> Vec<String> {
+    let mut apps: Vec<String> = target_apps
+        .iter()
+        .filter(|app| !TargetAppMatcher::is_pattern(app))
+        .cloned()
+        .collect();
+    if apps.len() < target_apps.len() {
+        let mut matched: Vec<&String> = running_bundles
+            .iter()
+            .filter(|bundle| !TargetAppMatcher::is_pattern(bundle) && matcher.matches(bundle))
+            .filter(|bundle| !apps.contains(bundle))
+            .collect();
+        matched.sort();
+        apps.extend(matched.into_iter().cloned());
+    }
+    apps
+}
+
+/// Apply `policy` when `recording_<session_id>.<extension>` may already exist in `dir`:
+/// fail, reuse the ID (the output truncates the file when it opens), or pick the first
+/// `<session_id>_N` whose video and input log are both free. Returns the ID to record under.
+fn resolve_existing_output(
+    dir: &Path,
+    session_id: &str,
+    extension: &str,
+    policy: ExistingOutputPolicy,
+) -> Result<String, CaptureError> {
+    let taken = |id: &str| {
+        dir.join(format!("recording_{}.{}", id, extension)).exists()
+            || dir.join(format!("input_{}.msgpack", id)).exists()
+    };
+    if !taken(session_id) {
+        return Ok(session_id.to_string());
+    }
+    let path = dir.join(format!("recording_{}.{}", session_id, extension));
+    match policy {
+        ExistingOutputPolicy::Error => Err(CaptureError::OutputExists(path)),
+        ExistingOutputPolicy::Overwrite => {
+            warn!("Overwriting existing recording {:?}", path);
+            Ok(session_id.to_string())
+        }
+        ExistingOutputPolicy::Suffix => {
+            let candidate = (1u32..)
+                .map(|n| format!("{}_{}", session_id, n))
+                .find(|candidate| !taken(candidate))
+                .expect("unbounded suffix search");
+            warn!(
+                "Recording {:?} already exists; recording segment {} instead",
+                path, candidate
+            );
+            Ok(candidate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn offline() -> Result<&'static str> {
+        anyhow::bail!("dns error: failed to lookup address")
+    }
+
+    #[test]
+    fn setup_outcome_lists_the_app_whose_source_failed() {
+        let mut outcome = SetupOutcome::default();
+        assert_eq!(outcome.record("com.apple.Safari", Ok(1)), Some(1));
+        assert_eq!(
+            outcome.record::<u32>(
+                "com.microsoft.VSCode",
+                Err(anyhow::anyhow!("no shareable content for this app"))
+            ),
+            None
+        );
+
+        assert_eq!(outcome.created, ["com.apple.Safari"]);
+        assert_eq!(
+            outcome.failed,
+            [(
+                "com.microsoft.VSCode".to_string(),
+                "no shareable content for this app".to_string()
+            )]
+        );
+        assert_eq!(outcome.failed_apps(), ["com.microsoft.VSCode"]);
+    }
+
+    #[test]
+    fn shutdown_leaves_nothing_recording() {
+        let mut ctx = CaptureContext::uninitialized(std::env::temp_dir());
+        ctx.shutdown().unwrap();
+        assert!(!ctx.is_recording());
+        assert!(!ctx.is_initialized());
+        // Idempotent, so a second call (or the Drop fallback) has nothing left to do.
+        ctx.shutdown().unwrap();
+        assert!(!ctx.is_recording());
+    }
+
+    #[test]
+    fn existing_output_file_follows_policy() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-output-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("recording_session.mp4");
+        let resolve = |policy| resolve_existing_output(&dir, "session", "mp4", policy);
+
+        // A free ID is used as is under every policy.
+        for policy in [
+            ExistingOutputPolicy::Error,
+            ExistingOutputPolicy::Overwrite,
+            ExistingOutputPolicy::Suffix,
+        ] {
+            assert_eq!(resolve(policy).unwrap(), "session");
+        }
+
+        std::fs::write(&path, b"earlier segment").unwrap();
+        assert!(matches!(
+            resolve(ExistingOutputPolicy::Error),
+            Err(CaptureError::OutputExists(p)) if p == path
+        ));
+        assert_eq!(resolve(ExistingOutputPolicy::Overwrite).unwrap(), "session");
+        // The suffix renames the segment, so video and input log move together.
+        assert_eq!(resolve(ExistingOutputPolicy::Suffix).unwrap(), "session_1");
+        // Suffixes already taken by either file are skipped.
+        std::fs::write(dir.join("input_session_1.msgpack"), b"").unwrap();
+        std::fs::write(dir.join("recording_session_2.mp4"), b"").unwrap();
+        assert_eq!(resolve(ExistingOutputPolicy::Suffix).unwrap(), "session_3");
+        // The existing recording is left untouched.
+        assert_eq!(std::fs::read(&path).unwrap(), b"earlier segment");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn offline_bootstrap_falls_back_to_cached_install() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-obs-{}", std::process::id()));
+
+        // Offline with a cached install: proceed with it.
+        let result = install_or_use_cached(Ok(false), offline(), || true, Some(&dir)).await;
+        assert_eq!(result.unwrap(), None);
+
+        // Offline with nothing cached: fail, pointing at the runtime dir.
+        let err = install_or_use_cached(Ok(false), offline(), || false, Some(&dir))
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains(&dir.display().to_string()));
+
+        // A valid install never touches the network; online installs report their result.
+        let result = install_or_use_cached(Ok(true), offline(), || false, None).await;
+        assert_eq!(result.unwrap(), None);
+        let result =
+            install_or_use_cached(Ok(false), async { Ok("restart") }, || false, None).await;
+        assert_eq!(result.unwrap(), Some("restart"));
+
+        assert!(!cached_install_usable(&dir));
+        std::fs::create_dir_all(dir.join("data/libobs")).unwrap();
+        std::fs::write(dir.join("data/libobs/default.effect"), b"").unwrap();
+        assert!(cached_install_usable(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
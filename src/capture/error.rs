@@ -0,0 +1,48 @@
+//! Typed capture failures
+//!
+//! The libobs lifecycle calls (`initialize`, `start_recording`, `stop_recording`) return a
+//! [`CaptureError`] so callers can tell misuse of the context (no context yet, a recording
+//! already running) apart from libobs itself failing, which is fatal to the capture session.
+//! Everything beneath them still builds errors with anyhow; those arrive as
+//! [`CaptureError::Obs`].
+
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    /// `initialize` hasn't created the libobs context yet
+    #[error("OBS context not initialized")]
+    NotInitialized,
+    /// `start_recording` while a recording is running
+    #[error("Recording already in progress")]
+    AlreadyRecording,
+    /// The recording's output directory couldn't be created
+    #[error("Failed to create output directory {path:?}")]
+    OutputDirectory {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The output file already exists and `recording.on_existing` is `error`
+    #[error("Output file {0:?} already exists")]
+    OutputExists(PathBuf),
+    /// OBS offers no video encoder the recording output can use (no hardware encoder and
+    /// no x264). Found at `initialize`, so it surfaces at setup rather than at first record.
+    #[error(
+        "No usable video encoder (OBS offers: {}). Install GPU drivers with hardware \
+         encoding (NVENC, AMF, QSV, VAAPI) or an OBS build that includes the obs-x264 plugin",
+        describe_encoders(.available)
+    )]
+    NoVideoEncoder { available: Vec<String> },
+    /// libobs failed: creating the context, or creating, starting or stopping the output
+    #[error("{0:#}")]
+    Obs(anyhow::Error),
+}
+
+fn describe_encoders(available: &[String]) -> String {
+    if available.is_empty() {
+        "none".to_string()
+    } else {
+        available.join(", ")
+    }
+}
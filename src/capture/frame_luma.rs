@@ -0,0 +1,106 @@
+//! Luma probe on OBS's rendered output (`recording.content_warmup_ms`)
+//!
+//! ScreenCaptureKit, and the portal stream on Linux, can hand OBS black frames for a
+//! second or more after a capture source is created, so a recording's first seconds may
+//! be black. The probe taps OBS's raw video output, scaled down to a thumbnail in I420 so
+//! the first plane is luma, and keeps the brightest luma of the latest frame. Recording
+//! start polls it to hold input capture until the frames show something.
+
+use anyhow::Result;
+use libobs_wrapper::context::ObsContext;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::warn;
+
+/// Peak luma at or below which a frame counts as black. Black is 16 in the limited-range
+/// thumbnail; anything on screen (text, an icon, a window edge) lifts the peak well past it.
+pub const BLACK_PEAK_LUMA: u8 = 32;
+
+/// Size of the thumbnail OBS scales frames to for the probe
+const PROBE_WIDTH: u32 = 64;
+const PROBE_HEIGHT: u32 = 36;
+
+/// Stored while no frame has arrived yet
+const NO_FRAME: u32 = u32::MAX;
+
+/// Peak luma of the latest frame, written from OBS's video thread
+struct LatestLuma(AtomicU32);
+
+/// Raw video callback: record the brightest luma sample of the thumbnail.
+unsafe extern "C" fn record_frame(param: *mut c_void, frame: *mut libobs::video_data) {
+    if param.is_null() || frame.is_null() {
+        return;
+    }
+    let frame = &*frame;
+    let plane = frame.data[0];
+    if plane.is_null() {
+        return;
+    }
+    let stride = frame.linesize[0] as usize;
+    let mut peak = 0u8;
+    for row in 0..PROBE_HEIGHT as usize {
+        let line = std::slice::from_raw_parts(plane.add(row * stride), PROBE_WIDTH as usize);
+        peak = peak.max(line.iter().copied().max().unwrap_or(0));
+    }
+    let latest = &*(param as *const LatestLuma);
+    latest.0.store(u32::from(peak), Ordering::Relaxed);
+}
+
+/// A raw video callback on OBS's output that tracks how bright the latest frame is.
+/// Removes the callback on drop, so it must be dropped before the OBS context is torn down.
+pub struct FrameLumaProbe {
+    context: ObsContext,
+    /// Boxed so the callback's pointer to it stays valid
+    latest: Box<LatestLuma>,
+}
+
+impl FrameLumaProbe {
+    /// Start watching the frames OBS renders
+    pub fn attach(context: &ObsContext) -> Result<Self> {
+        let latest = Box::new(LatestLuma(AtomicU32::new(NO_FRAME)));
+        let param = &*latest as *const LatestLuma as usize;
+        let runtime = context.runtime().clone();
+        libobs_wrapper::run_with_obs!(runtime, move || unsafe {
+            let conversion = libobs::video_scale_info {
+                format: libobs::video_format_VIDEO_FORMAT_I420,
+                width: PROBE_WIDTH,
+                height: PROBE_HEIGHT,
+                range: libobs::video_range_type_VIDEO_RANGE_PARTIAL,
+                colorspace: libobs::video_colorspace_VIDEO_CS_DEFAULT,
+            };
+            libobs::obs_add_raw_video_callback(
+                &conversion,
+                Some(record_frame),
+                param as *mut c_void,
+            );
+        })?;
+        Ok(Self {
+            context: context.clone(),
+            latest,
+        })
+    }
+
+    /// Brightest luma in the latest rendered frame; None until a frame has arrived
+    pub fn peak_luma(&self) -> Option<u8> {
+        match self.latest.0.load(Ordering::Relaxed) {
+            NO_FRAME => None,
+            peak => Some(peak as u8),
+        }
+    }
+}
+
+impl Drop for FrameLumaProbe {
+    fn drop(&mut self) {
+        let runtime = self.context.runtime().clone();
+        let param = &*self.latest as *const LatestLuma as usize;
+        if let Err(e) = libobs_wrapper::run_with_obs!(runtime, move || unsafe {
+            libobs::obs_remove_raw_video_callback(Some(record_frame), param as *mut c_void);
+        }) {
+            // The callback may still fire; leak its target rather than free it.
+            warn!("Failed to remove the frame luma probe: {}", e);
+            let latest =
+                std::mem::replace(&mut self.latest, Box::new(LatestLuma(AtomicU32::new(0))));
+            std::mem::forget(latest);
+        }
+    }
+}
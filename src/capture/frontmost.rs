@@ -34,6 +34,36 @@ pub fn get_frontmost_app() -> Option<AppInfo> {
     }
 }
 
+/// Frame of the focused window of process `pid`, in global points. macOS only (read via the
+/// Accessibility API); `None` elsewhere or when the window can't be read.
+pub fn get_focused_window_geometry(pid: u32) -> Option<crate::data::WindowGeometryEvent> {
+    #[cfg(target_os = "macos")]
+    {
+        super::mac_geometry::focused_window_frame(pid)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Title of the focused window of process `pid`. macOS only (read via the Accessibility
+/// API); `None` elsewhere or when the title can't be read.
+pub fn get_focused_window_title(pid: u32) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        super::mac_geometry::focused_window_title(pid)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
 // ============================================================================
 // macOS Implementation
 // ============================================================================
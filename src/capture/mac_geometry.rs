@@ -280,6 +280,163 @@ pub fn window_display_for_pid(pid: u32) -> Option<DisplayTarget> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Focused-window frame (for `WindowGeometry` events).
+//
+// Read through the Accessibility API rather than CGWindowList: the app's AXFocusedWindow is
+// the window the user is actually on, even when another of its windows is stacked above it
+// (e.g. a floating palette). Needs the Accessibility permission the input capture already
+// requires; without it every lookup fails and the caller simply logs nothing. AX calls are
+// synchronous IPC into the target app, so each element gets a short messaging timeout: a
+// hung app fails the lookup instead of blocking the caller for the system default (~6s).
+// ---------------------------------------------------------------------------
+
+/// Longest an AX call waits on the target app before failing (seconds)
+const AX_MESSAGING_TIMEOUT_SECS: f32 = 0.25;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementCreateApplication(pid: i32) -> *const c_void;
+    fn AXUIElementSetMessagingTimeout(element: *const c_void, timeout_secs: f32) -> i32;
+    fn AXUIElementCopyAttributeValue(
+        element: *const c_void,
+        attribute: *const c_void,
+        value: *mut *const c_void,
+    ) -> i32;
+    // `Boolean` (unsigned char) — model as u8, as above.
+    fn AXValueGetValue(value: *const c_void, the_type: u32, out: *mut c_void) -> u8;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFStringCreateWithCString(
+        alloc: *const c_void,
+        c_str: *const i8,
+        encoding: u32,
+    ) -> *const c_void;
+    fn CFGetTypeID(cf: *const c_void) -> usize;
+    fn CFStringGetTypeID() -> usize;
+    fn CFStringGetLength(string: *const c_void) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(length: isize, encoding: u32) -> isize;
+    fn CFStringGetCString(string: *const c_void, buffer: *mut i8, size: isize, encoding: u32)
+        -> u8;
+}
+
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+const K_AX_VALUE_CG_POINT_TYPE: u32 = 1;
+const K_AX_VALUE_CG_SIZE_TYPE: u32 = 2;
+
+/// AX element for process `pid`, with `AX_MESSAGING_TIMEOUT_SECS` set. The caller owns (and
+/// must `CFRelease`) it.
+unsafe fn create_app_element(pid: u32) -> Option<*const c_void> {
+    let app = AXUIElementCreateApplication(pid as i32);
+    if app.is_null() {
+        return None;
+    }
+    AXUIElementSetMessagingTimeout(app, AX_MESSAGING_TIMEOUT_SECS);
+    Some(app)
+}
+
+/// Copy attribute `name` (a NUL-terminated `kAX*Attribute` name) of an AX element. The caller
+/// owns (and must `CFRelease`) the returned value. `None` on any AX error.
+unsafe fn copy_ax_attribute(element: *const c_void, name: &[u8]) -> Option<*const c_void> {
+    let attribute = CFStringCreateWithCString(
+        std::ptr::null(),
+        name.as_ptr() as *const i8,
+        K_CF_STRING_ENCODING_UTF8,
+    );
+    if attribute.is_null() {
+        return None;
+    }
+    let mut value: *const c_void = std::ptr::null();
+    let err = AXUIElementCopyAttributeValue(element, attribute, &mut value);
+    CFRelease(attribute);
+    (err == 0 && !value.is_null()).then_some(value)
+}
+
+/// Read a `CGPoint`/`CGSize`-typed AX attribute of `element` into `out`.
+unsafe fn read_ax_value<T>(
+    element: *const c_void,
+    name: &[u8],
+    the_type: u32,
+    out: &mut T,
+) -> bool {
+    let Some(value) = copy_ax_attribute(element, name) else {
+        return false;
+    };
+    let ok = AXValueGetValue(value, the_type, out as *mut T as *mut c_void) != 0;
+    CFRelease(value);
+    ok
+}
+
+/// Frame of process `pid`'s focused window in global POINTS (top-left origin of the main
+/// display, the same space as [`describe_display`] bounds). `None` if the app has no focused
+/// window, doesn't answer within `AX_MESSAGING_TIMEOUT_SECS`, or Accessibility access is
+/// missing.
+pub fn focused_window_frame(pid: u32) -> Option<crate::data::WindowGeometryEvent> {
+    unsafe {
+        let app = create_app_element(pid)?;
+        let window = copy_ax_attribute(app, b"AXFocusedWindow\0");
+        CFRelease(app);
+        let window = window?;
+        let mut origin = CGPoint { x: 0.0, y: 0.0 };
+        let mut size = CGSize {
+            width: 0.0,
+            height: 0.0,
+        };
+        let ok = read_ax_value(
+            window,
+            b"AXPosition\0",
+            K_AX_VALUE_CG_POINT_TYPE,
+            &mut origin,
+        ) && read_ax_value(window, b"AXSize\0", K_AX_VALUE_CG_SIZE_TYPE, &mut size);
+        CFRelease(window);
+        ok.then(|| crate::data::WindowGeometryEvent {
+            x: origin.x.round() as i32,
+            y: origin.y.round() as i32,
+            width: size.width.round() as i32,
+            height: size.height.round() as i32,
+        })
+    }
+}
+
+/// Title of process `pid`'s focused window (`AXTitle`). `None` if the app has no focused
+/// window, the window has no title, the app doesn't answer within
+/// `AX_MESSAGING_TIMEOUT_SECS`, or Accessibility access is missing.
+pub fn focused_window_title(pid: u32) -> Option<String> {
+    unsafe {
+        let app = create_app_element(pid)?;
+        let window = copy_ax_attribute(app, b"AXFocusedWindow\0");
+        CFRelease(app);
+        let window = window?;
+        let title = copy_ax_attribute(window, b"AXTitle\0");
+        CFRelease(window);
+        let title = title?;
+        if CFGetTypeID(title) != CFStringGetTypeID() {
+            CFRelease(title);
+            return None;
+        }
+        let size =
+            CFStringGetMaximumSizeForEncoding(CFStringGetLength(title), K_CF_STRING_ENCODING_UTF8)
+                + 1;
+        let mut buffer = vec![0u8; usize::try_from(size).unwrap_or(0)];
+        let ok = !buffer.is_empty()
+            && CFStringGetCString(
+                title,
+                buffer.as_mut_ptr() as *mut i8,
+                size,
+                K_CF_STRING_ENCODING_UTF8,
+            ) != 0;
+        CFRelease(title);
+        if !ok {
+            return None;
+        }
+        let len = buffer.iter().position(|&b| b == 0)?;
+        buffer.truncate(len);
+        String::from_utf8(buffer).ok()
+    }
+}
+
 /// Describe a display for the recording metadata: UUID + name + global POINT bounds
 /// (`CGDisplayBounds`) + backing pixel size + is_main. `None` if its UUID or pixel size is
 /// unreadable.
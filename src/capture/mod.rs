@@ -10,18 +10,23 @@
 //! - Ability to fix ScreenCaptureKit issues directly
 
 mod apps;
+mod audio;
 mod context;
+mod error;
 #[cfg(target_os = "linux")]
 pub(crate) mod focus;
+mod frame_luma;
 mod frontmost;
 #[cfg(target_os = "macos")]
 mod mac_geometry;
+mod obs_log;
 #[cfg(target_os = "linux")]
 pub(crate) mod gnome_screencast;
 #[cfg(target_os = "linux")]
 pub(crate) mod monitor_layout;
 mod recording;
 mod recovery;
+mod scene_layout;
 mod sources;
 #[cfg(target_os = "windows")]
 mod window_geometry;
@@ -63,14 +68,25 @@ pub fn is_single_active_capable() -> bool {
 }
 
 pub use apps::{list_capturable_apps, list_running_apps};
-pub use context::{CaptureContext, RecordingSession};
-pub use frontmost::{get_frontmost_app, AppInfo};
+pub use audio::AudioPeaks;
+pub use context::{
+    obs_log_dir, CaptureContext, RecordingSession, SetupOutcome, SHUTDOWN_FLUSH_TIMEOUT,
+};
+pub use error::CaptureError;
+pub use frame_luma::{FrameLumaProbe, BLACK_PEAK_LUMA};
+pub use frontmost::{
+    get_focused_window_geometry, get_focused_window_title, get_frontmost_app, AppInfo,
+};
 pub use recording::{
     calculate_output_dimensions, RecordingConfig, RecordingOutput, RecordingOutputBuilder,
     RecordingState, VideoCodecPreference,
 };
-pub use recovery::{get_display_name, get_display_uuid, DisplayChangeEvent, DisplayMonitor};
-pub use sources::{get_main_display_resolution, get_main_display_uuid, ScreenCaptureSource};
+pub use recovery::{
+    get_display_name, get_display_uuid, DisplayChangeEvent, DisplayInfo, DisplayMonitor,
+};
+pub use sources::{
+    get_main_display_resolution, get_main_display_scale, get_main_display_uuid, ScreenCaptureSource,
+};
 // Linux/Wayland display-capture restore-token persistence (handled in main): the reserved
 // map key and the session predicate used to gate the one-time monitor-pick wait.
 #[cfg(target_os = "linux")]
@@ -0,0 +1,117 @@
+//! libobs log files (`obs_log_dir()`)
+//!
+//! libobs logs through the handler the wrapper installs, which by default only prints to
+//! the console. `ObsFileLogger` writes each line to a per-run `<date> <time>.txt` file
+//! (the naming OBS Studio uses) in the libobs log directory, so `crowd-cast-agent
+//! diagnostics` has something to bundle, and forwards it to the agent log as well.
+
+use libobs_wrapper::enums::ObsLogLevel;
+use libobs_wrapper::logger::ObsLogger;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+/// libobs log files kept in the log directory; older ones are deleted at startup
+const OBS_LOG_FILES_KEPT: usize = 10;
+
+#[derive(Debug)]
+pub(super) struct ObsFileLogger {
+    /// `None` when the file couldn't be created; lines then only reach the agent log
+    file: Option<File>,
+}
+
+impl ObsFileLogger {
+    /// Start a new log file in `dir` (created if needed), pruning the oldest ones
+    pub(super) fn new(dir: &Path) -> Self {
+        let path = dir.join(format!(
+            "{}.txt",
+            chrono::Local::now().format("%Y-%m-%d %H-%M-%S")
+        ));
+        let file = std::fs::create_dir_all(dir)
+            .and_then(|()| File::create(&path))
+            .map_err(|e| warn!("Failed to create libobs log file {:?}: {}", path, e))
+            .ok();
+        if file.is_some() {
+            info!("Writing libobs log to {:?}", path);
+        }
+        prune_old_logs(dir, OBS_LOG_FILES_KEPT);
+        Self { file }
+    }
+}
+
+impl ObsLogger for ObsFileLogger {
+    fn log(&mut self, level: ObsLogLevel, msg: String) {
+        match level {
+            ObsLogLevel::Error => error!(target: "libobs", "{}", msg),
+            ObsLogLevel::Warning => warn!(target: "libobs", "{}", msg),
+            ObsLogLevel::Info => info!(target: "libobs", "{}", msg),
+            ObsLogLevel::Debug => debug!(target: "libobs", "{}", msg),
+        }
+        if let Some(file) = self.file.as_mut() {
+            let line = format!(
+                "{} {:?}: {}\n",
+                chrono::Local::now().format("%H:%M:%S%.3f"),
+                level,
+                msg
+            );
+            if file.write_all(line.as_bytes()).is_err() {
+                // Disk full or the file went away: stop trying, the agent log still has it.
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// Delete all but the `keep` newest `.txt` logs in `dir`. Names sort by time.
+fn prune_old_logs(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    for path in logs.drain(..excess) {
+        if let Err(e) = std::fs::remove_file(&path) {
+            debug!("Failed to delete old libobs log {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_lines_and_keeps_only_the_newest_logs() {
+        let dir = std::env::temp_dir().join(format!("cc_obs_log_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for day in 1..=3 {
+            std::fs::write(dir.join(format!("2020-01-0{} 10-00-00.txt", day)), b"").unwrap();
+        }
+        std::fs::write(dir.join("notes.md"), b"").unwrap();
+
+        prune_old_logs(&dir, 2);
+        assert!(!dir.join("2020-01-01 10-00-00.txt").exists());
+        assert!(dir.join("2020-01-02 10-00-00.txt").exists());
+        assert!(dir.join("notes.md").exists());
+
+        let mut logger = ObsFileLogger::new(&dir);
+        logger.log(ObsLogLevel::Warning, "no audio device".to_string());
+        drop(logger);
+        let newest = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .max()
+            .unwrap();
+        let text = std::fs::read_to_string(newest).unwrap();
+        assert!(text.ends_with("Warning: no audio device\n"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
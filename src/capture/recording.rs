@@ -1,18 +1,22 @@
 //! Recording output management for embedded libobs
 //!
 //! Handles creating and managing recording outputs with proper encoder configuration.
-//! Uses HEVC hardware encoding (VideoToolbox on macOS) when available, falling back
-//! to H.264 hardware encoding, then software encoding.
+//! The encoder is picked once at startup from the encoders OBS offers
+//! ([`select_video_encoder`]): HEVC hardware encoding (VideoToolbox on macOS) when
+//! available, falling back to H.264 hardware encoding, then x264 software encoding.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use libobs_simple::output::simple::{
     HardwareCodec, HardwarePreset, OutputFormat, SimpleOutputBuilder,
 };
 use libobs_wrapper::context::ObsContext;
 use libobs_wrapper::data::output::ObsOutputRef;
+use libobs_wrapper::data::ObsData;
+use libobs_wrapper::unsafe_send::Sendable;
 use libobs_wrapper::utils::ObsPath;
-use std::path::PathBuf;
-use tracing::{debug, info};
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
 
 /// Calculate output dimensions with aspect-preserving downscale
 ///
@@ -77,6 +81,126 @@ pub enum VideoCodecPreference {
     Av1Preferred,
 }
 
+/// Codec of a video encoder OBS offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    Hevc,
+    Av1,
+}
+
+impl VideoCodec {
+    fn hardware_codec(self) -> HardwareCodec {
+        match self {
+            VideoCodec::H264 => HardwareCodec::H264,
+            VideoCodec::Hevc => HardwareCodec::HEVC,
+            VideoCodec::Av1 => HardwareCodec::AV1,
+        }
+    }
+}
+
+/// OBS hardware encoder ids, most preferred first within each codec: NVENC (OBS 31+ and
+/// older ids), AMF, QSV, VideoToolbox, VAAPI
+const HARDWARE_ENCODERS: &[(&str, VideoCodec)] = &[
+    ("obs_nvenc_hevc_tex", VideoCodec::Hevc),
+    ("jim_hevc_nvenc", VideoCodec::Hevc),
+    ("h265_texture_amf", VideoCodec::Hevc),
+    ("obs_qsv11_hevc", VideoCodec::Hevc),
+    (
+        "com.apple.videotoolbox.videoencoder.ave.hevc",
+        VideoCodec::Hevc,
+    ),
+    ("hevc_ffmpeg_vaapi_tex", VideoCodec::Hevc),
+    ("obs_nvenc_h264_tex", VideoCodec::H264),
+    ("jim_nvenc", VideoCodec::H264),
+    ("h264_texture_amf", VideoCodec::H264),
+    ("obs_qsv11_v2", VideoCodec::H264),
+    (
+        "com.apple.videotoolbox.videoencoder.ave.avc",
+        VideoCodec::H264,
+    ),
+    ("ffmpeg_vaapi_tex", VideoCodec::H264),
+    ("obs_nvenc_av1_tex", VideoCodec::Av1),
+    ("jim_av1_nvenc", VideoCodec::Av1),
+    ("av1_texture_amf", VideoCodec::Av1),
+    ("obs_qsv11_av1", VideoCodec::Av1),
+    ("av1_ffmpeg_vaapi_tex", VideoCodec::Av1),
+];
+
+/// Software encoders to fall back to when no hardware encoder is usable, most preferred
+/// first. The output builder drives x264 only, so OBS's other software encoders (OpenH264,
+/// SVT-AV1, AOM) can't stand in for it.
+const SOFTWARE_ENCODERS: &[(&str, VideoCodec)] = &[("obs_x264", VideoCodec::H264)];
+
+/// The video encoder recordings use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoEncoderChoice {
+    /// OBS encoder id, e.g. `jim_hevc_nvenc` or `obs_x264`
+    pub id: &'static str,
+    pub codec: VideoCodec,
+    pub hardware: bool,
+}
+
+/// Pick the encoder for `preference` among the encoder ids OBS offers: a hardware encoder
+/// of the preferred codec, else of a more widely supported codec (AV1, then HEVC, then
+/// H.264), else a software encoder. None when OBS offers nothing usable.
+pub fn select_video_encoder(
+    preference: VideoCodecPreference,
+    available: &[String],
+) -> Option<VideoEncoderChoice> {
+    let codecs: &[VideoCodec] = match preference {
+        VideoCodecPreference::HevcPreferred => &[VideoCodec::Hevc, VideoCodec::H264],
+        VideoCodecPreference::H264Preferred => &[VideoCodec::H264],
+        VideoCodecPreference::Av1Preferred => {
+            &[VideoCodec::Av1, VideoCodec::Hevc, VideoCodec::H264]
+        }
+    };
+    let offered = |id: &str| available.iter().any(|a| a == id);
+    let hardware = codecs.iter().find_map(|&codec| {
+        HARDWARE_ENCODERS
+            .iter()
+            .find(|&&(id, c)| c == codec && offered(id))
+    });
+    if let Some(&(id, codec)) = hardware {
+        return Some(VideoEncoderChoice {
+            id,
+            codec,
+            hardware: true,
+        });
+    }
+    SOFTWARE_ENCODERS
+        .iter()
+        .find(|&&(id, _)| offered(id))
+        .map(|&(id, codec)| VideoEncoderChoice {
+            id,
+            codec,
+            hardware: false,
+        })
+}
+
+/// Ids of the video encoders OBS has registered. Hardware encoder plugins only register
+/// the encoders the machine's GPU and drivers support. Requires an initialized context.
+pub fn list_video_encoders(context: &ObsContext) -> Result<Vec<String>> {
+    let runtime = context.runtime().clone();
+    let encoders = libobs_wrapper::run_with_obs!(runtime, move || unsafe {
+        let mut encoders = Vec::new();
+        let mut idx = 0;
+        let mut id: *const std::os::raw::c_char = std::ptr::null();
+        while libobs::obs_enum_encoder_types(idx, &mut id) {
+            idx += 1;
+            if id.is_null()
+                || libobs::obs_get_encoder_type(id) != libobs::obs_encoder_type_OBS_ENCODER_VIDEO
+            {
+                continue;
+            }
+            encoders.push(CStr::from_ptr(id).to_string_lossy().into_owned());
+        }
+        encoders
+    })
+    .context("Failed to enumerate video encoders")?;
+    Ok(encoders)
+}
+
 /// Recording configuration
 #[derive(Debug, Clone)]
 pub struct RecordingConfig {
@@ -101,6 +225,12 @@ pub struct RecordingConfig {
     /// When set, supported encoders use CRF instead of fixed bitrate.
     /// Recommended: 75-85 for screen recording.
     pub crf: Option<u32>,
+    /// Write an HLS playlist cut into pieces of this many seconds instead of a single
+    /// file (`None` = single file)
+    pub hls_segment_secs: Option<u32>,
+    /// Encoder resolved from what OBS offers at startup (`select_video_encoder`). `None`
+    /// leaves the choice to `codec_preference` alone.
+    pub video_encoder: Option<VideoEncoderChoice>,
 }
 
 impl Default for RecordingConfig {
@@ -124,6 +254,8 @@ impl Default for RecordingConfig {
             fps: 30,
             // CRF quality 80 - sharp text at any resolution
             crf: Some(80),
+            hls_segment_secs: None,
+            video_encoder: None,
         }
     }
 }
@@ -142,6 +274,8 @@ impl RecordingConfig {
             max_output_height: 0,
             fps: 30,
             crf: Some(90),
+            hls_segment_secs: None,
+            video_encoder: None,
         }
     }
 
@@ -158,6 +292,8 @@ impl RecordingConfig {
             max_output_height: 720,
             fps: 30,
             crf: Some(65),
+            hls_segment_secs: None,
+            video_encoder: None,
         }
     }
 
@@ -174,6 +310,8 @@ impl RecordingConfig {
             max_output_height: 720,
             fps: 30,
             crf: Some(80),
+            hls_segment_secs: None,
+            video_encoder: None,
         }
     }
 
@@ -188,6 +326,72 @@ impl RecordingConfig {
         self.enable_audio = false;
         self
     }
+
+    /// Switch to the fragmented variant of the current container so a segment cut short by
+    /// a crash or power loss is still playable up to the last written fragment. Plain
+    /// MP4/MOV only become readable once the moov atom is written at stop.
+    ///
+    /// Tradeoff: fragmented files carry a small per-fragment index overhead (slightly
+    /// larger files) and some older players/editors handle them less well. Matroska is
+    /// already resilient and is left unchanged.
+    pub fn crash_resilient(mut self) -> Self {
+        self.format = match self.format {
+            OutputFormat::QuickTime | OutputFormat::HybridMov | OutputFormat::FragmentedMOV => {
+                OutputFormat::FragmentedMOV
+            }
+            OutputFormat::MatroskaVideo => OutputFormat::MatroskaVideo,
+            _ => OutputFormat::FragmentedMP4,
+        };
+        self
+    }
+
+    /// Record an HLS playlist (`.m3u8`) with MPEG-TS pieces of about `segment_secs` each,
+    /// which can be uploaded and consumed while the recording is still running. Overrides
+    /// the container format, including `crash_resilient`: a TS piece is complete as soon
+    /// as the playlist lists it.
+    pub fn hls(mut self, segment_secs: u32) -> Self {
+        self.format = OutputFormat::MpegTs;
+        self.hls_segment_secs = Some(segment_secs.max(1));
+        self
+    }
+
+    /// File extension of the file a recording is started on: the playlist for HLS,
+    /// otherwise the container's
+    pub fn extension(&self) -> &'static str {
+        if self.hls_segment_secs.is_some() {
+            HLS_PLAYLIST_EXTENSION
+        } else {
+            output_extension(self.format)
+        }
+    }
+}
+
+/// Extension of an HLS recording's playlist
+const HLS_PLAYLIST_EXTENSION: &str = "m3u8";
+
+/// `muxer_settings` that make OBS's ffmpeg muxer write `playlist` as an HLS event
+/// playlist: pieces of `segment_secs` named `<playlist stem>.<n>.ts` next to it, every
+/// piece kept in the list, and `#EXT-X-ENDLIST` appended when the recording stops.
+///
+/// The muxer splits its settings on spaces, so the piece path is single-quoted.
+fn hls_muxer_settings(playlist: &Path, segment_secs: u32) -> String {
+    let pieces = playlist.with_extension("%05d.ts");
+    let quoted = pieces.to_string_lossy().replace('\'', "'\\''");
+    format!(
+        "hls_time={} hls_list_size=0 hls_playlist_type=event hls_segment_filename='{}'",
+        segment_secs, quoted
+    )
+}
+
+/// File extension for recordings written in `format`
+pub fn output_extension(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::QuickTime | OutputFormat::HybridMov | OutputFormat::FragmentedMOV => "mov",
+        OutputFormat::MatroskaVideo => "mkv",
+        OutputFormat::FlashVideo => "flv",
+        OutputFormat::MpegTs => "ts",
+        _ => "mp4",
+    }
 }
 
 /// Manages a recording output
@@ -195,12 +399,15 @@ pub struct RecordingOutput {
     output: ObsOutputRef,
     state: RecordingState,
     output_path: PathBuf,
+    /// OBS id of the video encoder the output was built with, as libobs reports it
+    video_encoder: Option<String>,
 }
 
 impl RecordingOutput {
     /// Create a new recording output with the specified configuration
     ///
-    /// This will automatically select the best available encoder:
+    /// Encodes with the codec of `config.video_encoder` when it has been resolved, else
+    /// with the best available encoder for `codec_preference`:
     /// - macOS: VideoToolbox HEVC/H.264
     /// - Windows/Linux: NVENC, AMF, QSV (in order of availability)
     /// - Fallback: x264 software encoding
@@ -214,10 +421,14 @@ impl RecordingOutput {
             output_path, config.codec_preference, config.video_bitrate
         );
 
-        let codec = match config.codec_preference {
-            VideoCodecPreference::HevcPreferred => HardwareCodec::HEVC,
-            VideoCodecPreference::H264Preferred => HardwareCodec::H264,
-            VideoCodecPreference::Av1Preferred => HardwareCodec::AV1,
+        let codec = match (config.video_encoder, config.codec_preference) {
+            (Some(encoder), _) => {
+                debug!("Using video encoder {}", encoder.id);
+                encoder.codec.hardware_codec()
+            }
+            (None, VideoCodecPreference::HevcPreferred) => HardwareCodec::HEVC,
+            (None, VideoCodecPreference::H264Preferred) => HardwareCodec::H264,
+            (None, VideoCodecPreference::Av1Preferred) => HardwareCodec::AV1,
         };
 
         // Build the output with hardware encoder selection
@@ -227,11 +438,27 @@ impl RecordingOutput {
             .ok_or_else(|| anyhow::anyhow!("Invalid output path (non-UTF8): {:?}", output_path))?;
         let obs_path = ObsPath::new(output_path_str);
 
+        let hls_settings = match config.hls_segment_secs {
+            Some(segment_secs) => {
+                let mut settings = ObsData::new(context.runtime())
+                    .context("Failed to allocate ObsData for HLS muxer settings")?;
+                settings
+                    .set_string(
+                        "muxer_settings",
+                        hls_muxer_settings(&output_path, segment_secs),
+                    )
+                    .context("Failed to set HLS muxer settings")?;
+                Some(settings)
+            }
+            None => None,
+        };
+
         // Build the output with hardware encoder selection
         // Note: Audio encoder is always created (required by OBS outputs), but actual
         // audio capture is controlled at the source level via ScreenCaptureSource.
         // When config.enable_audio is false, no audio sources are added, so the
         // audio track will be silent.
+        let runtime = context.runtime().clone();
         let mut builder = SimpleOutputBuilder::new(context, "recording", obs_path)
             .video_bitrate(config.video_bitrate)
             .audio_bitrate(config.audio_bitrate)
@@ -242,10 +469,38 @@ impl RecordingOutput {
             builder = builder.crf(crf);
         }
 
-        let output = builder
+        let mut output = builder
             .build()
             .map_err(|e| anyhow::anyhow!("Failed to create recording output: {}", e))?;
 
+        if let Some(settings) = hls_settings {
+            output
+                .update_settings(settings)
+                .map_err(|e| anyhow::anyhow!("Failed to apply HLS muxer settings: {}", e))?;
+            debug!("Recording to an HLS playlist");
+        }
+
+        // The builder only takes a codec and picks the encoder itself, which needn't be
+        // the one `select_video_encoder` chose: ask libobs which one it attached.
+        let output_ptr = Sendable(output.as_ptr());
+        let video_encoder = libobs_wrapper::run_with_obs!(runtime, (output_ptr), move || unsafe {
+            let encoder = libobs::obs_output_get_video_encoder(output_ptr);
+            if encoder.is_null() {
+                return None;
+            }
+            let id = libobs::obs_encoder_get_id(encoder);
+            (!id.is_null()).then(|| CStr::from_ptr(id).to_string_lossy().into_owned())
+        })
+        .context("Failed to read the recording's video encoder")?;
+        match (&video_encoder, config.video_encoder) {
+            (Some(actual), Some(chosen)) if actual != chosen.id => warn!(
+                "Recording with video encoder {} instead of the selected {}",
+                actual, chosen.id
+            ),
+            (Some(actual), _) => debug!("Recording with video encoder {}", actual),
+            (None, _) => warn!("Recording output has no video encoder attached"),
+        }
+
         info!(
             "Recording output configured successfully (audio capture: {})",
             if config.enable_audio {
@@ -263,6 +518,7 @@ impl RecordingOutput {
             output,
             state: RecordingState::Stopped,
             output_path,
+            video_encoder,
         })
     }
 
@@ -271,6 +527,11 @@ impl RecordingOutput {
         Self::new(context, output_path, &RecordingConfig::default())
     }
 
+    /// OBS id of the video encoder this output records with
+    pub fn video_encoder(&self) -> Option<&str> {
+        self.video_encoder.as_deref()
+    }
+
     /// Start recording
     pub fn start(&mut self) -> Result<()> {
         if self.state == RecordingState::Recording {
@@ -444,3 +705,95 @@ impl RecordingOutputBuilder {
         RecordingOutput::new(self.context, self.output_path, &self.config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn encoder_falls_back_from_hardware_to_software() {
+        // A VM without GPU encoders: OBS only offers software encoders.
+        let software_only = ids(&["ffmpeg_svt_av1", "ffmpeg_openh264", "obs_x264"]);
+        for preference in [
+            VideoCodecPreference::HevcPreferred,
+            VideoCodecPreference::H264Preferred,
+            VideoCodecPreference::Av1Preferred,
+        ] {
+            assert_eq!(
+                select_video_encoder(preference, &software_only),
+                Some(VideoEncoderChoice {
+                    id: "obs_x264",
+                    codec: VideoCodec::H264,
+                    hardware: false,
+                })
+            );
+        }
+
+        // Hardware of the preferred codec wins; otherwise the next codec's hardware.
+        let nvenc = ids(&["obs_x264", "jim_nvenc", "jim_hevc_nvenc"]);
+        let hevc = select_video_encoder(VideoCodecPreference::HevcPreferred, &nvenc).unwrap();
+        assert_eq!(
+            (hevc.id, hevc.codec, hevc.hardware),
+            ("jim_hevc_nvenc", VideoCodec::Hevc, true)
+        );
+        let av1 = select_video_encoder(VideoCodecPreference::Av1Preferred, &nvenc).unwrap();
+        assert_eq!(av1.id, "jim_hevc_nvenc");
+        let h264 = select_video_encoder(VideoCodecPreference::H264Preferred, &nvenc).unwrap();
+        assert_eq!(h264.id, "jim_nvenc");
+
+        // Nothing the output can drive: no encoder rather than a failure at first record.
+        assert_eq!(
+            select_video_encoder(
+                VideoCodecPreference::HevcPreferred,
+                &ids(&["ffmpeg_openh264", "ffmpeg_aom_av1"])
+            ),
+            None
+        );
+        assert_eq!(
+            select_video_encoder(VideoCodecPreference::H264Preferred, &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn crash_resilient_selects_fragmented_format() {
+        let config = RecordingConfig::default().crash_resilient();
+        assert!(matches!(config.format, OutputFormat::FragmentedMP4));
+        assert_eq!(output_extension(config.format), "mp4");
+
+        let mov = RecordingConfig {
+            format: OutputFormat::QuickTime,
+            ..Default::default()
+        }
+        .crash_resilient();
+        assert!(matches!(mov.format, OutputFormat::FragmentedMOV));
+        assert_eq!(output_extension(mov.format), "mov");
+    }
+
+    #[test]
+    fn hls_records_a_playlist_of_ts_pieces() {
+        let config = RecordingConfig::default().crash_resilient().hls(4);
+        assert!(matches!(config.format, OutputFormat::MpegTs));
+        assert_eq!(config.extension(), "m3u8");
+        assert_eq!(RecordingConfig::default().extension(), "mp4");
+
+        let settings = hls_muxer_settings(
+            Path::new("/Users/me/Application Support/rec/recording_s_seg0001.m3u8"),
+            4,
+        );
+        assert_eq!(
+            settings,
+            "hls_time=4 hls_list_size=0 hls_playlist_type=event \
+             hls_segment_filename='/Users/me/Application Support/rec/recording_s_seg0001.%05d.ts'"
+        );
+        // A quote in the path closes the quoting, escapes itself, and reopens it.
+        assert!(
+            hls_muxer_settings(Path::new("/tmp/it's/recording_a.m3u8"), 2)
+                .ends_with("hls_segment_filename='/tmp/it'\\''s/recording_a.%05d.ts'")
+        );
+    }
+}
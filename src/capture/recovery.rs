@@ -28,6 +28,53 @@ pub enum DisplayChangeEvent {
     AllDisconnected,
 }
 
+/// A connected display, as offered by the tray's "Select Display" picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayInfo {
+    /// Display id to pass to `EngineCommand::SwitchToDisplay`
+    pub id: u32,
+    /// Human-readable name
+    pub name: String,
+    /// Backing pixel resolution, `(0, 0)` if unreadable
+    pub resolution: (u32, u32),
+    /// Whether capture is currently following this display
+    pub is_current: bool,
+}
+
+/// Raw per-display facts a `DisplayInfo` is built from
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+struct DisplayEntry {
+    id: u32,
+    uuid: Option<String>,
+    name: String,
+    resolution: (u32, u32),
+}
+
+/// Build picker entries. The current display is matched by UUID when one is known, since
+/// display ids are reassigned across reconnects; otherwise by id.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn display_infos(
+    entries: Vec<DisplayEntry>,
+    current_id: Option<u32>,
+    current_uuid: Option<&str>,
+) -> Vec<DisplayInfo> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let is_current = match current_uuid {
+                Some(uuid) => entry.uuid.as_deref() == Some(uuid),
+                None => current_id == Some(entry.id),
+            };
+            DisplayInfo {
+                id: entry.id,
+                name: entry.name,
+                resolution: entry.resolution,
+                is_current,
+            }
+        })
+        .collect()
+}
+
 /// Monitor for display connection changes
 #[cfg(target_os = "macos")]
 pub struct DisplayMonitor {
@@ -103,6 +150,24 @@ impl DisplayMonitor {
         &self.last_display_ids
     }
 
+    /// Enumerate the connected displays for the manual display picker
+    pub fn list_displays(&self) -> Vec<DisplayInfo> {
+        let entries = Self::get_display_ids()
+            .into_iter()
+            .map(|id| DisplayEntry {
+                id,
+                uuid: get_display_uuid(id),
+                name: get_display_name(id),
+                resolution: super::mac_geometry::display_pixel_size(id).unwrap_or((0, 0)),
+            })
+            .collect();
+        display_infos(
+            entries,
+            self.original_display_id,
+            self.original_display_uuid.as_deref(),
+        )
+    }
+
     /// Check for display changes and return what kind of change occurred
     pub fn check_for_changes(&mut self) -> Option<DisplayChangeEvent> {
         let current_ids = Self::get_display_ids();
@@ -353,6 +418,12 @@ impl DisplayMonitor {
         &[]
     }
 
+    /// Switching displays by id is macOS-only (capture follows the whole monitor layout
+    /// here), so there is nothing to pick from.
+    pub fn list_displays(&self) -> Vec<DisplayInfo> {
+        Vec::new()
+    }
+
     pub fn check_for_changes(&mut self) -> Option<DisplayChangeEvent> {
         let current = monitor_signature();
         // Ignore transient empty enumerations (seen mid-reconfigure) so we don't reset
@@ -405,6 +476,10 @@ impl DisplayMonitor {
         &[]
     }
 
+    pub fn list_displays(&self) -> Vec<DisplayInfo> {
+        Vec::new()
+    }
+
     pub fn check_for_changes(&mut self) -> Option<DisplayChangeEvent> {
         None
     }
@@ -430,3 +505,65 @@ pub fn get_display_name(_display_id: u32) -> String {
 pub fn get_display_uuid(_display_id: u32) -> Option<String> {
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, uuid: Option<&str>, name: &str) -> DisplayEntry {
+        DisplayEntry {
+            id,
+            uuid: uuid.map(str::to_string),
+            name: name.to_string(),
+            resolution: (2940, 1912),
+        }
+    }
+
+    #[test]
+    fn display_infos_mark_current_by_uuid_then_id() {
+        let entries = || {
+            vec![
+                entry(1, Some("BUILTIN-UUID"), "Built-in Display"),
+                entry(5, Some("DELL-UUID"), "External Display 5"),
+                entry(7, None, "Display 7"),
+            ]
+        };
+
+        // The original display came back under a new id: the UUID still identifies it.
+        let infos = display_infos(entries(), Some(3), Some("DELL-UUID"));
+        assert_eq!(
+            infos,
+            vec![
+                DisplayInfo {
+                    id: 1,
+                    name: "Built-in Display".to_string(),
+                    resolution: (2940, 1912),
+                    is_current: false,
+                },
+                DisplayInfo {
+                    id: 5,
+                    name: "External Display 5".to_string(),
+                    resolution: (2940, 1912),
+                    is_current: true,
+                },
+                DisplayInfo {
+                    id: 7,
+                    name: "Display 7".to_string(),
+                    resolution: (2940, 1912),
+                    is_current: false,
+                },
+            ]
+        );
+
+        // Without a UUID the id decides; with neither, nothing is current.
+        let current: Vec<u32> = display_infos(entries(), Some(7), None)
+            .into_iter()
+            .filter(|d| d.is_current)
+            .map(|d| d.id)
+            .collect();
+        assert_eq!(current, vec![7]);
+        assert!(display_infos(entries(), None, None)
+            .iter()
+            .all(|d| !d.is_current));
+    }
+}
@@ -0,0 +1,107 @@
+//! Scene-item placement for multi-source app capture
+//!
+//! With single-active mode off, every target app's capture source sits in one scene. Left
+//! at libobs's defaults they all draw at the canvas origin at their native size, so only the
+//! top one is visible (and a window larger than the canvas is cropped). [`layout_items`]
+//! gives each source a bounding box on the canvas per `capture.layout`; the scene items use
+//! scale-inner bounds, so OBS fits each source inside its box (aspect preserved, centered)
+//! whatever size the source turns out to be — including sources still reporting 0x0.
+
+use crate::config::CaptureLayout;
+
+/// Bounding box for one scene item: top-left position and size, in canvas pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ItemPlacement {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Grid (columns, rows) for `count` tiles: as close to square as possible, never taller
+/// than wide (2 → 2x1, 3–4 → 2x2, 5–6 → 3x2).
+fn grid_dims(count: usize) -> (usize, usize) {
+    let cols = (count as f64).sqrt().ceil().max(1.0) as usize;
+    (cols, count.div_ceil(cols))
+}
+
+/// Bounding boxes for `count` sources on a `canvas` (width, height), in source order.
+/// `Single` fits every source to the whole canvas; `Tile` fills the grid row by row.
+pub fn layout_items(layout: CaptureLayout, count: usize, canvas: (u32, u32)) -> Vec<ItemPlacement> {
+    let (canvas_w, canvas_h) = (canvas.0 as f32, canvas.1 as f32);
+    match layout {
+        CaptureLayout::Single => vec![
+            ItemPlacement {
+                x: 0.0,
+                y: 0.0,
+                width: canvas_w,
+                height: canvas_h,
+            };
+            count
+        ],
+        CaptureLayout::Tile => {
+            let (cols, rows) = grid_dims(count);
+            let cell_w = canvas_w / cols as f32;
+            let cell_h = canvas_h / rows as f32;
+            (0..count)
+                .map(|i| ItemPlacement {
+                    x: (i % cols) as f32 * cell_w,
+                    y: (i / cols) as f32 * cell_h,
+                    width: cell_w,
+                    height: cell_h,
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANVAS: (u32, u32) = (1920, 1080);
+
+    fn place(x: f32, y: f32, width: f32, height: f32) -> ItemPlacement {
+        ItemPlacement {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn layout_items_for_n_sources() {
+        assert!(layout_items(CaptureLayout::Tile, 0, CANVAS).is_empty());
+
+        // One app fills the canvas under either layout.
+        let full = place(0.0, 0.0, 1920.0, 1080.0);
+        assert_eq!(layout_items(CaptureLayout::Single, 1, CANVAS), vec![full]);
+        assert_eq!(layout_items(CaptureLayout::Tile, 1, CANVAS), vec![full]);
+        assert_eq!(
+            layout_items(CaptureLayout::Single, 3, CANVAS),
+            vec![full; 3]
+        );
+
+        assert_eq!(
+            layout_items(CaptureLayout::Tile, 2, CANVAS),
+            vec![
+                place(0.0, 0.0, 960.0, 1080.0),
+                place(960.0, 0.0, 960.0, 1080.0)
+            ]
+        );
+        // Three apps use a 2x2 grid, leaving the last cell empty.
+        assert_eq!(
+            layout_items(CaptureLayout::Tile, 3, CANVAS),
+            vec![
+                place(0.0, 0.0, 960.0, 540.0),
+                place(960.0, 0.0, 960.0, 540.0),
+                place(0.0, 540.0, 960.0, 540.0),
+            ]
+        );
+        let five = layout_items(CaptureLayout::Tile, 5, CANVAS);
+        assert_eq!(five.len(), 5);
+        assert_eq!(five[2], place(1280.0, 0.0, 640.0, 540.0));
+        assert_eq!(five[4], place(640.0, 540.0, 640.0, 540.0));
+    }
+}
@@ -959,3 +959,73 @@ pub fn get_main_display_resolution() -> Result<(u32, u32)> {
 pub fn get_main_display_resolution() -> Result<(u32, u32)> {
     anyhow::bail!("Display resolution detection not available on this platform")
 }
+
+/// Get the backing scale factor of the main display (pixels per point).
+///
+/// On macOS this is the current mode's pixel width over its point width — 2.0 on a
+/// Retina panel at its default setting, fractional for "scaled" modes. `None` if the
+/// mode can't be read.
+#[cfg(target_os = "macos")]
+pub fn get_main_display_scale() -> Option<f64> {
+    use core_graphics::display::CGDisplay;
+    use std::ffi::c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGDisplayCopyDisplayMode(display: u32) -> *const c_void;
+        fn CGDisplayModeGetPixelWidth(mode: *const c_void) -> usize;
+        fn CGDisplayModeGetWidth(mode: *const c_void) -> usize;
+        fn CGDisplayModeRelease(mode: *const c_void);
+    }
+
+    unsafe {
+        let mode = CGDisplayCopyDisplayMode(CGDisplay::main().id);
+        if mode.is_null() {
+            return None;
+        }
+        let pixels = CGDisplayModeGetPixelWidth(mode);
+        let points = CGDisplayModeGetWidth(mode);
+        CGDisplayModeRelease(mode);
+        (pixels > 0 && points > 0).then(|| pixels as f64 / points as f64)
+    }
+}
+
+/// Get the scale factor of the primary monitor on Windows (effective DPI / 96).
+///
+/// The process is per-monitor DPI aware (set in `main`), so `GetDpiForMonitor` reports
+/// the monitor's real scaling setting rather than the virtualized 96 DPI.
+#[cfg(target_os = "windows")]
+pub fn get_main_display_scale() -> Option<f64> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTOPRIMARY};
+    use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    unsafe {
+        let monitor = MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY);
+        let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+        GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).ok()?;
+        (dpi_x > 0).then(|| dpi_x as f64 / 96.0)
+    }
+}
+
+/// Get the scale factor of the main display (Linux).
+///
+/// On Wayland this is the integer `wl_output` scale of the output
+/// `get_main_display_resolution` reports. X11 has no per-display scale in the core
+/// protocol, so a pure X11 session is always 1.0.
+#[cfg(target_os = "linux")]
+pub fn get_main_display_scale() -> Option<f64> {
+    if crate::capture::x11_windows::is_pure_x11_session() {
+        Some(1.0)
+    } else if is_wayland_session() {
+        crate::capture::wayland_output::wayland_output_scale().map(f64::from)
+    } else {
+        None
+    }
+}
+
+/// Get the scale factor of the main display (unsupported-platform fallback)
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub fn get_main_display_scale() -> Option<f64> {
+    None
+}
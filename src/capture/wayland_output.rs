@@ -5,7 +5,8 @@
 //! has no "primary output" concept, so we deterministically pick the largest current mode
 //! (the common single-output case is unambiguous; this is a defined selection policy, not a
 //! fallback). Returns `None` if no output reports a current mode, so the caller fails closed
-//! rather than guessing a size.
+//! rather than guessing a size. The same output's integer `wl_output` scale is the display
+//! scale factor reported by `get_main_display_scale`.
 #![cfg(target_os = "linux")]
 
 use wayland_client::globals::{registry_queue_init, GlobalListContents};
@@ -13,10 +14,18 @@ use wayland_client::protocol::wl_output::{self, WlOutput};
 use wayland_client::protocol::wl_registry::WlRegistry;
 use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
 
+/// One bound output's state, filled in by its events during the roundtrip.
+struct OutputInfo {
+    /// (width, height) of the CURRENT mode, in physical pixels.
+    current_mode: Option<(u32, u32)>,
+    /// Integer buffer scale (`wl_output.scale`, version 2+); 1 until reported.
+    scale: i32,
+}
+
 #[derive(Default)]
 struct Outputs {
-    /// (width, height) of every output's CURRENT mode, in physical pixels.
-    current_modes: Vec<(u32, u32)>,
+    /// Indexed by the `usize` user data each `wl_output` proxy was bound with.
+    outputs: Vec<OutputInfo>,
 }
 
 impl Dispatch<WlRegistry, GlobalListContents> for Outputs {
@@ -31,38 +40,43 @@ impl Dispatch<WlRegistry, GlobalListContents> for Outputs {
     }
 }
 
-impl Dispatch<WlOutput, ()> for Outputs {
+impl Dispatch<WlOutput, usize> for Outputs {
     fn event(
         state: &mut Self,
         _: &WlOutput,
         event: wl_output::Event,
-        _: &(),
+        index: &usize,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        // `Mode` carries physical pixel dimensions; only the one flagged CURRENT is live.
-        if let wl_output::Event::Mode {
-            flags,
-            width,
-            height,
-            ..
-        } = event
-        {
-            let is_current = matches!(
+        let Some(output) = state.outputs.get_mut(*index) else {
+            return;
+        };
+        match event {
+            // `Mode` carries physical pixel dimensions; only the one flagged CURRENT is live.
+            wl_output::Event::Mode {
                 flags,
-                wayland_client::WEnum::Value(m) if m.contains(wl_output::Mode::Current)
-            );
-            if is_current && width > 0 && height > 0 {
-                state.current_modes.push((width as u32, height as u32));
+                width,
+                height,
+                ..
+            } => {
+                let is_current = matches!(
+                    flags,
+                    wayland_client::WEnum::Value(m) if m.contains(wl_output::Mode::Current)
+                );
+                if is_current && width > 0 && height > 0 {
+                    output.current_mode = Some((width as u32, height as u32));
+                }
             }
+            wl_output::Event::Scale { factor } if factor > 0 => output.scale = factor,
+            _ => {}
         }
     }
 }
 
-/// Every connected output's current-mode pixel size (physical pixels). Empty if the Wayland
-/// connection fails or no output reported a current mode. Used for the multi-monitor capture
-/// canvas envelope (`monitor_layout`); `wayland_output_size` picks the largest from this.
-pub fn wayland_output_sizes() -> Vec<(u32, u32)> {
+/// Bind every advertised output and collect its current mode and scale in one roundtrip.
+/// Empty if the Wayland connection fails.
+fn query_outputs() -> Vec<OutputInfo> {
     let Ok(conn) = Connection::connect_to_env() else {
         return Vec::new();
     };
@@ -73,19 +87,34 @@ pub fn wayland_output_sizes() -> Vec<(u32, u32)> {
 
     // Bind every advertised wl_output so each emits its Mode events on the next roundtrip.
     // Keep the proxies alive across the roundtrip so their events still route to us.
+    let mut state = Outputs::default();
     let mut _keep: Vec<WlOutput> = Vec::new();
     for global in globals.contents().clone_list() {
         if global.interface.as_str() == WlOutput::interface().name {
             let version = global.version.min(4);
-            _keep.push(globals.registry().bind(global.name, version, &qh, ()));
+            let index = state.outputs.len();
+            state.outputs.push(OutputInfo {
+                current_mode: None,
+                scale: 1,
+            });
+            _keep.push(globals.registry().bind(global.name, version, &qh, index));
         }
     }
 
-    let mut state = Outputs::default();
     if queue.roundtrip(&mut state).is_err() {
         return Vec::new();
     }
-    state.current_modes
+    state.outputs
+}
+
+/// Every connected output's current-mode pixel size (physical pixels). Empty if the Wayland
+/// connection fails or no output reported a current mode. Used for the multi-monitor capture
+/// canvas envelope (`monitor_layout`); `wayland_output_size` picks the largest from this.
+pub fn wayland_output_sizes() -> Vec<(u32, u32)> {
+    query_outputs()
+        .into_iter()
+        .filter_map(|o| o.current_mode)
+        .collect()
 }
 
 /// Largest connected output's current-mode pixel size, or `None` if none reported one.
@@ -94,3 +123,16 @@ pub fn wayland_output_size() -> Option<(u32, u32)> {
         .into_iter()
         .max_by_key(|&(w, h)| (w as u64) * (h as u64))
 }
+
+/// Integer scale of the output `wayland_output_size` picks, or `None` if none reported a
+/// current mode.
+pub fn wayland_output_scale() -> Option<i32> {
+    query_outputs()
+        .into_iter()
+        .filter_map(|o| {
+            o.current_mode
+                .map(|(w, h)| ((w as u64) * (h as u64), o.scale))
+        })
+        .max_by_key(|&(area, _)| area)
+        .map(|(_, scale)| scale)
+}
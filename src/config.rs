@@ -2,12 +2,22 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Current config schema version. Bump it and add a step to `Config::migrate` whenever
+/// a key is renamed or moved.
+pub const CONFIG_VERSION: u32 = 2;
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version of this file (missing = 1, written before versioning existed)
+    #[serde(default = "default_legacy_config_version")]
+    pub version: u32,
+
     /// Capture configuration (which apps to capture)
     #[serde(default)]
     pub capture: CaptureConfig,
@@ -28,6 +38,36 @@ pub struct Config {
     #[serde(default)]
     pub security: SecurityConfig,
 
+    /// Desktop notification verbosity
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+
+    /// Global hotkeys
+    #[serde(default)]
+    pub hotkeys: HotkeysConfig,
+
+    /// On-screen UI beyond the tray
+    #[serde(default)]
+    pub ui: UiConfig,
+
+    /// URLs notified of recording lifecycle events (`[[webhooks]]`)
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// `capture.target_apps` compiled for matching (rebuilt by `validate` and the
+    /// target-app setters)
+    #[serde(skip)]
+    target_matcher: TargetAppMatcher,
+
+    /// `capture.title_patterns` compiled per app (rebuilt by `validate`)
+    #[serde(skip)]
+    title_matchers: HashMap<String, TargetAppMatcher>,
+
+    /// Top-level keys this build doesn't know (e.g. written by a newer version), kept so
+    /// that saving doesn't drop them
+    #[serde(flatten)]
+    extra: toml::Table,
+
     /// Path to config file (not serialized)
     #[serde(skip)]
     config_path: Option<PathBuf>,
@@ -37,14 +77,23 @@ pub struct Config {
 pub struct CaptureConfig {
     /// List of app bundle IDs (macOS) or process names (Linux/Windows) to capture
     /// When empty, capture all apps (or use capture_all flag)
+    /// Entries may also be globs (`com.jetbrains.*`) or `re:`-prefixed regexes; see
+    /// `TargetAppMatcher`.
     #[serde(default)]
     pub target_apps: Vec<String>,
 
-    /// If true, capture input for all applications (ignore target_apps)
+    /// If true, capture input for all applications (ignore target_apps). Only covers apps
+    /// that were detected: see `when_frontmost_unknown`.
     #[serde(default)]
     pub capture_all: bool,
 
-    /// Polling interval for frontmost app detection (ms)
+    /// What to capture while the frontmost app can't be detected. Applies even with
+    /// `capture_all` on, so capture never silently follows an app nobody identified.
+    #[serde(default)]
+    pub when_frontmost_unknown: FrontmostUnknownPolicy,
+
+    /// Polling interval for frontmost app detection (ms). Clamped to
+    /// `MIN_POLL_INTERVAL_MS..=MAX_POLL_INTERVAL_MS`.
     #[serde(default = "default_poll_interval")]
     pub poll_interval_ms: u64,
 
@@ -58,9 +107,16 @@ pub struct CaptureConfig {
 
     /// Idle timeout in seconds before pausing capture (0 = disabled)
     /// When no keyboard/mouse activity is detected for this duration, recording pauses automatically.
-    #[serde(default = "default_idle_timeout_secs")]
+    /// Also accepted as `idle_pause_secs`.
+    #[serde(default = "default_idle_timeout_secs", alias = "idle_pause_secs")]
     pub idle_timeout_secs: u64,
 
+    /// End the whole recording session after this many seconds without any input
+    /// (0 = never), finalizing its last segment for upload; the next input starts a new
+    /// session. Unlike the idle pause, input in untracked apps counts as activity.
+    #[serde(default)]
+    pub idle_session_end_secs: u64,
+
     /// Whether to pause uploads during idle (in addition to recording)
     #[serde(default = "default_true")]
     pub pause_uploads_on_idle: bool,
@@ -82,6 +138,12 @@ pub struct CaptureConfig {
     #[serde(default = "default_true")]
     pub blank_video_on_untracked_app: bool,
 
+    /// How app-capture sources are arranged on the canvas when several are captured at
+    /// once (single-active mode off): `single` fits each to the whole canvas, `tile`
+    /// splits the canvas into a grid with one cell per app.
+    #[serde(default)]
+    pub layout: CaptureLayout,
+
     /// How long to wait for a newly switched capture source to become ready.
     #[serde(default = "default_capture_watchdog_timeout_ms")]
     pub capture_watchdog_timeout_ms: u64,
@@ -90,10 +152,203 @@ pub struct CaptureConfig {
     #[serde(default = "default_capture_watchdog_max_retries")]
     pub capture_watchdog_max_retries: u32,
 
+    /// How long a returned display must stay connected before its capture sources are
+    /// rebuilt (0 = immediately). A display that disappears again within the window
+    /// cancels the recovery, so a flapping display (sleep/wake, KVM switch) triggers one
+    /// rebuild once it settles instead of one per reconnect.
+    #[serde(default = "default_display_recovery_debounce_ms")]
+    pub display_recovery_debounce_ms: u64,
+
     /// xdg-desktop-portal ScreenCast restore tokens for supported Wayland display capture,
     /// keyed by reserved identifiers such as `__display__`.
     #[serde(default)]
     pub restore_tokens: HashMap<String, String>,
+
+    /// Video settings per app (bundle ID / process name), applied at the first segment
+    /// boundary after that app becomes frontmost. Apps without an entry record with the
+    /// defaults.
+    #[serde(default)]
+    pub app_profiles: HashMap<String, RecordingProfile>,
+
+    /// Window-title patterns per app (bundle ID / process name), in the same exact / glob /
+    /// `re:` syntax as `target_apps`. An app with an entry is only captured while the title
+    /// of its focused window matches one of them; apps without one are captured whatever
+    /// the window. Titles are read through the Accessibility API, so this is macOS only:
+    /// elsewhere the title is unknown and apps with an entry are not captured.
+    #[serde(default)]
+    pub title_patterns: HashMap<String, Vec<String>>,
+
+    /// What to do with a running recording once none of `target_apps` is running any
+    /// more. No effect with `capture_all` on.
+    #[serde(default)]
+    pub on_target_exit: TargetExitPolicy,
+}
+
+/// Per-app video settings (`capture.app_profiles`); unset fields keep the defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingProfile {
+    /// Frames per second
+    #[serde(default)]
+    pub fps: Option<u32>,
+    /// Maximum output height in pixels (0 = native resolution)
+    #[serde(default)]
+    pub max_height: Option<u32>,
+}
+
+impl RecordingProfile {
+    /// `(fps, max output height)` for this profile, taking unset fields from `defaults`
+    pub fn resolve(&self, defaults: (u32, u32)) -> (u32, u32) {
+        (
+            self.fps.unwrap_or(defaults.0),
+            self.max_height.unwrap_or(defaults.1),
+        )
+    }
+}
+
+/// What to do when a new recording's output file already exists (`recording.on_existing`),
+/// e.g. after a session ID collision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingOutputPolicy {
+    /// Refuse to start the recording
+    Error,
+    /// Replace the existing file
+    Overwrite,
+    /// Record the segment as the first free `<id>_1`, `<id>_2`, ... (video and input log)
+    #[default]
+    Suffix,
+}
+
+/// How a new recording's session ID is chosen (`recording.session_id_scheme`). The ID
+/// names every segment of the recording and groups them for upload. Every scheme gives
+/// each recording its own ID: none repeats an earlier one, even for the same machine,
+/// start second or configured `recording.session_id`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionIdScheme {
+    /// A random UUID per recording
+    #[default]
+    Uuid,
+    /// `<host>_<UTC start time>_<random>`, e.g. `studio-mac_20261017T093015Z_3f9a1c`, for
+    /// pipelines that correlate by machine and time
+    Timestamped,
+    /// `<session_id>_<UTC start time>_<random>`, with `recording.session_id` in place of
+    /// the host name, e.g. `study-42_20261017T093015Z_3f9a1c`, so every recording of a
+    /// study shares the prefix; recording refuses to start while it is unset
+    FromConfig,
+}
+
+/// What the startup sweep does with partial input flushes a crashed run left behind
+/// (`recording.orphaned_partials`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanedPartialsPolicy {
+    /// Combine each segment's partials into its `input_<segment>.msgpack`
+    #[default]
+    Recover,
+    /// Delete them
+    Delete,
+}
+
+/// Object store the presign endpoint signs upload URLs for (`upload.backend`). Decides
+/// how the agent PUTs to those URLs and whether large videos can use multipart upload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadBackend {
+    /// Amazon S3 pre-signed URLs
+    #[default]
+    S3,
+    /// Cloudflare R2, through its S3-compatible API
+    R2,
+    /// Google Cloud Storage V4 signed URLs
+    Gcs,
+    /// Azure Blob Storage SAS URLs
+    Azure,
+}
+
+/// A URL that receives a JSON POST for each of `events` (`[[webhooks]]`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Events to send; empty = all of them
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+}
+
+/// Recording lifecycle points a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A recording session started
+    SessionStarted,
+    /// A segment's video and input log finished uploading
+    SegmentUploaded,
+    /// The engine reported an error, or the upload endpoint rejected a segment
+    Error,
+}
+
+/// What happens to the recording when no target app is running any more
+/// (`capture.on_target_exit`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetExitPolicy {
+    /// Keep recording
+    #[default]
+    Continue,
+    /// Pause, and resume once a target app is running again
+    Pause,
+    /// Stop the recording
+    Stop,
+}
+
+/// Capture decision while the frontmost app can't be determined
+/// (`capture.when_frontmost_unknown`), e.g. on a Wayland session without focus tracking
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrontmostUnknownPolicy {
+    /// Capture regardless of which app it might be
+    CaptureAll,
+    /// Don't capture until the frontmost app is known again. Takes precedence over
+    /// `capture.capture_all`.
+    #[default]
+    CaptureNone,
+    /// Keep the decision made for the last app that was detected
+    LastKnown,
+}
+
+impl FrontmostUnknownPolicy {
+    /// Whether to capture while the frontmost app is unknown, given the decision made for
+    /// the last detected app (false if none was detected yet).
+    pub fn should_capture(self, last_known: bool) -> bool {
+        match self {
+            Self::CaptureAll => true,
+            Self::CaptureNone => false,
+            Self::LastKnown => last_known,
+        }
+    }
+}
+
+/// Scene arrangement for multi-source app capture (`capture.layout`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureLayout {
+    /// Every source scaled to fit the full canvas (stacked; the top one is visible)
+    #[default]
+    Single,
+    /// Sources tiled in a near-square grid, each fit inside its cell
+    Tile,
+}
+
+/// Clock behind input event timestamps (`recording.clock_source`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockSource {
+    /// OBS's video frame time, so a timestamp is the video offset the event lines up with
+    #[default]
+    ObsFrameTime,
+    /// The agent's monotonic clock, which keeps advancing while frame time stalls
+    Monotonic,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +368,74 @@ pub struct InputConfig {
     /// Whether to capture mouse scroll
     #[serde(default = "default_true")]
     pub capture_mouse_scroll: bool,
+
+    /// Whether to keep momentum-phase scrolls (the inertial scrolling after a trackpad
+    /// flick). macOS only; other platforms never mark scrolls as momentum.
+    #[serde(default = "default_true")]
+    pub capture_momentum_scroll: bool,
+
+    /// Whether to capture trackpad gestures (pinch, rotate, swipe). macOS only; ignored
+    /// on other platforms.
+    #[serde(default)]
+    pub capture_gestures: bool,
+
+    /// Whether to capture text committed by input methods (IME composition, dictation),
+    /// which key events alone don't reveal. macOS only; ignored on other platforms.
+    #[serde(default)]
+    pub capture_text_commits: bool,
+
+    /// Apps (bundle IDs / process names) whose input is replaced by a `Redacted` marker
+    /// while they are frontmost. Video is unaffected.
+    #[serde(default)]
+    pub redact_apps: Vec<String>,
+
+    /// Coalesce mouse moves closer together than this many microseconds into one event,
+    /// summing their deltas (0 = disabled).
+    #[serde(default)]
+    pub mouse_coalesce_us: u64,
+
+    /// Drop input beyond this many events per second (0 = unlimited, the default),
+    /// guarding against floods from a stuck key or a misbehaving device. Mouse moves are
+    /// shed first; key and button releases are never shed.
+    #[serde(default)]
+    pub max_events_per_sec: u32,
+
+    /// Append periodic event-buffer flushes to one length-prefixed partial file per
+    /// segment instead of writing a separate partial file for every flush.
+    #[serde(default)]
+    pub append_partial_flushes: bool,
+
+    /// Also record click and scroll positions as fractions (0..1) of the capture canvas,
+    /// so datasets don't depend on the recording resolution. Raw positions are kept.
+    #[serde(default)]
+    pub normalize_coordinates: bool,
+
+    /// Drop clicks and scrolls outside the screen area the video shows (e.g. on another
+    /// monitor). Keyboard input is always kept, as are clicks before the pointer's
+    /// position is known. The area follows display changes. macOS only; elsewhere
+    /// nothing is dropped.
+    #[serde(default)]
+    pub restrict_to_capture_region: bool,
+
+    /// Log the focused window's position and size at segment start and whenever it moves
+    /// or resizes (macOS; read through the Accessibility API).
+    #[serde(default)]
+    pub capture_window_geometry: bool,
+
+    /// Also log a `Chord` event (e.g. `["Meta", "KeyS"]`) when a key is pressed while
+    /// Control, Alt or Meta is held
+    #[serde(default)]
+    pub emit_chords: bool,
+
+    /// With `emit_chords`, log the chord in place of the completing key's press and
+    /// release. Modifier key events are always kept.
+    #[serde(default)]
+    pub chords_replace_keys: bool,
+
+    /// Drop the key presses generated by holding a key down (auto-repeat), keeping the
+    /// first press and the release. Repeats are otherwise logged with `is_repeat` set.
+    #[serde(default)]
+    pub drop_key_repeats: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,13 +443,61 @@ pub struct UploadConfig {
     /// Lambda endpoint for getting pre-signed URLs
     pub lambda_endpoint: Option<String>,
 
+    /// Object store behind the presign endpoint
+    #[serde(default)]
+    pub backend: UploadBackend,
+
     /// Whether to delete local files after successful upload
     #[serde(default = "default_true")]
     pub delete_after_upload: bool,
 
-    /// Maximum concurrent uploads
-    #[serde(default = "default_max_uploads")]
+    /// Maximum segments uploading at once (minimum 1). Also accepted as `max_concurrent`.
+    #[serde(default = "default_max_uploads", alias = "max_concurrent")]
     pub max_concurrent_uploads: usize,
+
+    /// Video files at or above this size (MiB) are uploaded with S3 multipart upload
+    /// instead of a single PUT, so a dropped connection only re-sends one part.
+    #[serde(default = "default_multipart_threshold_mb")]
+    pub multipart_threshold_mb: u64,
+
+    /// Part size (MiB) for multipart uploads. Clamped to S3's 5 MiB minimum.
+    #[serde(default = "default_multipart_part_size_mb")]
+    pub multipart_part_size_mb: u64,
+
+    /// On shutdown, wait up to this many seconds for queued and retrying uploads to
+    /// finish before exiting (0 = exit immediately). Anything left is retried next launch.
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+
+    /// Stop attempting uploads after this many consecutive failures (0 = never), until
+    /// `circuit_breaker_cooldown_secs` have passed and a single probe upload succeeds.
+    #[serde(default = "default_circuit_breaker_failures")]
+    pub circuit_breaker_failures: u32,
+
+    /// How long uploads stay paused once the circuit breaker opens
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64,
+
+    /// Check each segment before uploading it: the video must be non-empty and start with
+    /// a valid container header, and the input log must deserialize. Segments that fail
+    /// (e.g. from a crashed encoder) are moved to `invalid/` in the output directory.
+    #[serde(default)]
+    pub validate_before_upload: bool,
+
+    /// Encrypt video and input logs client-side (XChaCha20-Poly1305) before upload
+    #[serde(default)]
+    pub encryption_enabled: bool,
+
+    /// Base64-encoded 32-byte encryption key. Falls back to the
+    /// `CROWD_CAST_ENCRYPTION_KEY` environment variable when unset.
+    #[serde(default)]
+    pub encryption_key: Option<String>,
+
+    /// Extra user metadata stored with every uploaded video and input log (sent as
+    /// `x-amz-meta-<name>` headers on S3), next to the session ID, chunk ID and
+    /// timestamps the agent always attaches
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,9 +510,22 @@ pub struct RecordingConfig {
     #[serde(default = "default_autostart_on_launch")]
     pub autostart_on_launch: bool,
 
-    /// Session ID (auto-generated if not set)
+    /// Session ID (auto-generated if not set); the prefix of each recording's session ID
+    /// with `session_id_scheme = "from_config"`
     pub session_id: Option<String>,
 
+    /// How each recording's session ID is chosen. The timestamped schemes end in a random
+    /// suffix, so the ID is unique per recording rather than derived from the machine,
+    /// time or configured ID alone.
+    #[serde(default)]
+    pub session_id_scheme: SessionIdScheme,
+
+    /// After a crash, continue the interrupted session (same session ID, next segment
+    /// index) instead of starting a new one, as long as its last segment's video is still
+    /// on disk, so the session stays contiguous server-side
+    #[serde(default)]
+    pub resume_on_restart: bool,
+
     /// Whether to show notifications on recording start/stop
     #[serde(default = "default_true")]
     pub notify_on_start_stop: bool,
@@ -150,12 +534,189 @@ pub struct RecordingConfig {
     /// Recordings will be split into segments of this duration for progressive upload
     #[serde(default = "default_segment_duration_secs")]
     pub segment_duration_secs: u64,
+
+    /// Shortest segment a rotation may cut, in seconds (0 = no minimum). A rotation due
+    /// earlier into the segment is put off until the segment reaches this length, and
+    /// rotations due meanwhile fold into it. Stopping still ends the segment as it is.
+    #[serde(default)]
+    pub min_segment_secs: u64,
+
+    /// Keep uploaded segments locally as a rolling archive of at most this many bytes
+    /// (0 = disabled). When set, uploaded files are kept regardless of
+    /// `upload.delete_after_upload` and the oldest uploaded segments are pruned once the
+    /// output directory exceeds the budget. Segments still pending upload are never pruned.
+    #[serde(default)]
+    pub local_retention_bytes: u64,
+
+    /// Keep at most this many uploaded segments locally (0 = no cap). Works alongside
+    /// `local_retention_bytes`: whichever limit is stricter prunes first. Segments still
+    /// pending upload are never pruned or counted against it.
+    #[serde(default)]
+    pub local_retention_count: usize,
+
+    /// When recording stops, join the session's segments still waiting to upload into one
+    /// segment if their video adds up to less than this many bytes (0 = disabled). Videos
+    /// are remuxed without re-encoding, which needs `ffmpeg` on `PATH`; without it the
+    /// segments upload as they are.
+    #[serde(default)]
+    pub compact_below_bytes: u64,
+
+    /// Show the per-type event breakdown (keys/moves/clicks/scrolls) in the tray status
+    #[serde(default)]
+    pub show_event_breakdown: bool,
+
+    /// Record to a fragmented container (fMP4/fMOV) so a segment cut short by a crash is
+    /// still playable up to the last fragment. Costs slightly larger files and weaker
+    /// support in some older players.
+    #[serde(default)]
+    pub crash_resilient: bool,
+
+    /// Hold input capture for this long after recording starts (0 = disabled), until the
+    /// video clock has advanced past it, so the first events don't land on frames the
+    /// capture source hasn't rendered yet. Input arriving during the warmup is discarded.
+    #[serde(default)]
+    pub warmup_ms: u64,
+
+    /// After recording starts (and after `warmup_ms`), hold input capture for up to this
+    /// long until the rendered frames are no longer black (0 = disabled), so a recording
+    /// doesn't open on seconds of black while the capture source initializes. Only
+    /// applies when recording starts, not at segment rotation.
+    #[serde(default)]
+    pub content_warmup_ms: u64,
+
+    /// Clock input events are timed on: OBS's video frame clock (timestamps are video
+    /// offsets, but stand still while the video pipeline stalls) or the agent's monotonic
+    /// clock (always advancing; the video's start is recorded for alignment)
+    #[serde(default)]
+    pub clock_source: ClockSource,
+
+    /// Append a global counter to segment IDs (`<session>_seg0003_g00000042`) that keeps
+    /// increasing across sessions and restarts, so segments sort in recording order. The
+    /// counter is persisted in the agent's data directory.
+    #[serde(default)]
+    pub global_segment_counter: bool,
+
+    /// Predefined marker labels offered in the tray's "Add Marker" submenu. Any label can
+    /// also be added with `crowd-cast-agent marker <label>`.
+    #[serde(default)]
+    pub markers: Vec<String>,
+
+    /// What to do when a segment's output file already exists
+    #[serde(default)]
+    pub on_existing: ExistingOutputPolicy,
+
+    /// What to do at startup with partial input flushes left by a run that crashed
+    /// mid-segment
+    #[serde(default)]
+    pub orphaned_partials: OrphanedPartialsPolicy,
+
+    /// Encode at a fixed bitrate that follows upload throughput: lowered toward
+    /// `bitrate_floor_kbps` while segments are produced faster than they upload, raised
+    /// back toward `bitrate_ceiling_kbps` once uploads have headroom. Replaces the
+    /// encoder's quality (CRF) mode; changes apply at the next segment.
+    #[serde(default)]
+    pub adaptive_bitrate: bool,
+
+    /// Lowest video bitrate (Kbps) `adaptive_bitrate` goes down to
+    #[serde(default = "default_bitrate_floor_kbps")]
+    pub bitrate_floor_kbps: u32,
+
+    /// Highest video bitrate (Kbps) `adaptive_bitrate` uses, and the one it starts at
+    #[serde(default = "default_bitrate_ceiling_kbps")]
+    pub bitrate_ceiling_kbps: u32,
+
+    /// Audio capture (off by default: video only)
+    #[serde(default)]
+    pub audio: AudioConfig,
+
+    /// Record each segment as an HLS playlist plus short `.ts` pieces, uploaded as they
+    /// are written (off by default: one video file per segment)
+    #[serde(default)]
+    pub hls: HlsConfig,
+
+    /// Log the captured audio's peak level to the input log every few seconds as
+    /// `AudioLevel` events (needs `audio` capture on)
+    #[serde(default)]
+    pub log_audio_levels: bool,
+
+    /// Sample the agent's CPU and memory use at every health check and log a min/avg/max
+    /// summary at the end of each segment as a `ResourceUsage` event
+    #[serde(default)]
+    pub log_resource_usage: bool,
+
+    /// File names `crowd-cast-agent reupload` treats as segment videos. The first `*` is
+    /// the segment id, which pairs the video with its input log; `{ext}` stands for any
+    /// container extension OBS writes (mp4, mov, mkv, flv, ts) or the HLS playlist (m3u8).
+    #[serde(default = "default_video_glob")]
+    pub video_glob: String,
+
+    /// File names `crowd-cast-agent reupload` treats as segment input logs; the first `*`
+    /// is the segment id, as in `video_glob`
+    #[serde(default = "default_input_glob")]
+    pub input_glob: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioConfig {
+    /// Capture system (desktop) audio. On macOS this is the captured app's/display's
+    /// audio via ScreenCaptureKit.
+    #[serde(default)]
+    pub system: bool,
+
+    /// Microphone to capture: "default" for the OS default input, or a device name/id as
+    /// listed by OBS. Unset = no microphone.
+    #[serde(default)]
+    pub microphone: Option<String>,
+
+    /// Warn when the microphone has sat at one flat, non-silent level (a wrong device or a
+    /// stuck hum) for this many seconds while recording (0 = never warn). Silence and
+    /// system audio are not checked.
+    #[serde(default = "default_audio_flat_warning_secs")]
+    pub flat_warning_secs: u64,
+}
+
+fn default_audio_flat_warning_secs() -> u64 {
+    60
+}
+
+/// HLS recording (`recording.hls`). Segment rotation is unchanged: every segment gets its
+/// own `recording_<id>.m3u8`, cut into `recording_<id>.<n>.ts` pieces of `segment_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HlsConfig {
+    /// Have OBS write a playlist and pieces instead of a single container file
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Target length of each `.ts` piece in seconds. Pieces are cut at keyframes, so they
+    /// run at least this long.
+    #[serde(default = "default_hls_segment_secs")]
+    pub segment_secs: u32,
+}
+
+fn default_hls_segment_secs() -> u32 {
+    4
+}
+
+fn default_video_glob() -> String {
+    "recording_*.{ext}".to_string()
+}
+
+fn default_input_glob() -> String {
+    "input_*.msgpack".to_string()
 }
 
 fn default_segment_duration_secs() -> u64 {
     300 // 5 minutes
 }
 
+fn default_bitrate_floor_kbps() -> u32 {
+    500
+}
+
+fn default_bitrate_ceiling_kbps() -> u32 {
+    3000
+}
+
 fn default_idle_timeout_secs() -> u64 {
     120 // 2 minutes of inactivity before pausing capture
 }
@@ -176,21 +737,54 @@ fn default_capture_watchdog_timeout_ms() -> u64 {
     1500
 }
 
+fn default_display_recovery_debounce_ms() -> u64 {
+    2000
+}
+
 fn default_capture_watchdog_max_retries() -> u32 {
     1
 }
 
 // Default value functions
+fn default_legacy_config_version() -> u32 {
+    1
+}
+
 fn default_poll_interval() -> u64 {
     100 // 100ms for responsive frontmost app detection
 }
 
+/// Lower bound for `capture.poll_interval_ms`; anything faster busy-spins the engine loop
+pub const MIN_POLL_INTERVAL_MS: u64 = 50;
+/// Upper bound for `capture.poll_interval_ms`, keeping app/display-change detection responsive
+pub const MAX_POLL_INTERVAL_MS: u64 = 5_000;
+
 fn default_true() -> bool {
     true
 }
 
 fn default_max_uploads() -> usize {
-    2
+    3
+}
+
+fn default_multipart_threshold_mb() -> u64 {
+    100
+}
+
+fn default_multipart_part_size_mb() -> u64 {
+    16
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    30
+}
+
+fn default_circuit_breaker_failures() -> u32 {
+    5
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    300
 }
 
 fn default_recording_output_directory() -> PathBuf {
@@ -205,22 +799,47 @@ fn default_autostart_on_launch() -> bool {
     true
 }
 
+impl CaptureConfig {
+    /// The frontmost-app poll interval, clamped to the supported range
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(
+            self.poll_interval_ms
+                .clamp(MIN_POLL_INTERVAL_MS, MAX_POLL_INTERVAL_MS),
+        )
+    }
+
+    /// The `app_profiles` entry for `app`, or an empty profile (all defaults) when none
+    /// matches
+    pub fn app_profile(&self, app: Option<&str>) -> RecordingProfile {
+        app.and_then(|app| self.app_profiles.get(app))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
 impl Default for CaptureConfig {
     fn default() -> Self {
         Self {
             target_apps: Vec::new(),
             capture_all: false,
+            when_frontmost_unknown: FrontmostUnknownPolicy::default(),
             poll_interval_ms: default_poll_interval(),
             setup_completed: false,
             start_on_login: false,
             idle_timeout_secs: default_idle_timeout_secs(),
+            idle_session_end_secs: 0,
             pause_uploads_on_idle: true,
             single_active_app_capture: default_single_active_app_capture(),
             mac_multi_monitor_capture: default_mac_multi_monitor_capture(),
             blank_video_on_untracked_app: true,
+            layout: CaptureLayout::default(),
             capture_watchdog_timeout_ms: default_capture_watchdog_timeout_ms(),
             capture_watchdog_max_retries: default_capture_watchdog_max_retries(),
+            display_recovery_debounce_ms: default_display_recovery_debounce_ms(),
             restore_tokens: HashMap::new(),
+            app_profiles: HashMap::new(),
+            title_patterns: HashMap::new(),
+            on_target_exit: TargetExitPolicy::default(),
         }
     }
 }
@@ -232,6 +851,19 @@ impl Default for InputConfig {
             capture_mouse_move: true,
             capture_mouse_click: true,
             capture_mouse_scroll: true,
+            capture_momentum_scroll: true,
+            capture_gestures: false,
+            capture_text_commits: false,
+            redact_apps: Vec::new(),
+            mouse_coalesce_us: 0,
+            max_events_per_sec: 0,
+            append_partial_flushes: false,
+            normalize_coordinates: false,
+            restrict_to_capture_region: false,
+            capture_window_geometry: false,
+            emit_chords: false,
+            chords_replace_keys: false,
+            drop_key_repeats: false,
         }
     }
 }
@@ -240,8 +872,18 @@ impl Default for UploadConfig {
     fn default() -> Self {
         Self {
             lambda_endpoint: None,
+            backend: UploadBackend::default(),
             delete_after_upload: true,
             max_concurrent_uploads: default_max_uploads(),
+            multipart_threshold_mb: default_multipart_threshold_mb(),
+            multipart_part_size_mb: default_multipart_part_size_mb(),
+            drain_timeout_secs: default_drain_timeout_secs(),
+            circuit_breaker_failures: default_circuit_breaker_failures(),
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown_secs(),
+            validate_before_upload: false,
+            encryption_enabled: false,
+            encryption_key: None,
+            metadata: BTreeMap::new(),
         }
     }
 }
@@ -252,8 +894,51 @@ impl Default for RecordingConfig {
             output_directory: Some(default_recording_output_directory()),
             autostart_on_launch: default_autostart_on_launch(),
             session_id: None,
+            session_id_scheme: SessionIdScheme::default(),
+            resume_on_restart: false,
             notify_on_start_stop: true,
             segment_duration_secs: default_segment_duration_secs(),
+            min_segment_secs: 0,
+            local_retention_bytes: 0,
+            local_retention_count: 0,
+            compact_below_bytes: 0,
+            show_event_breakdown: false,
+            crash_resilient: false,
+            warmup_ms: 0,
+            content_warmup_ms: 0,
+            clock_source: ClockSource::default(),
+            global_segment_counter: false,
+            markers: Vec::new(),
+            on_existing: ExistingOutputPolicy::default(),
+            orphaned_partials: OrphanedPartialsPolicy::default(),
+            adaptive_bitrate: false,
+            bitrate_floor_kbps: default_bitrate_floor_kbps(),
+            bitrate_ceiling_kbps: default_bitrate_ceiling_kbps(),
+            audio: AudioConfig::default(),
+            hls: HlsConfig::default(),
+            log_audio_levels: false,
+            log_resource_usage: false,
+            video_glob: default_video_glob(),
+            input_glob: default_input_glob(),
+        }
+    }
+}
+
+impl Default for HlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            segment_secs: default_hls_segment_secs(),
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            system: false,
+            microphone: None,
+            flat_warning_secs: default_audio_flat_warning_secs(),
         }
     }
 }
@@ -274,6 +959,42 @@ pub struct SecurityConfig {
     pub enable_accessibility: bool,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Which notifications to show. Default: all.
+    #[serde(default)]
+    pub level: NotificationLevel,
+}
+
+/// Notification verbosity (`notifications.level`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    /// Every notification, including routine status (recording started, idle pause, ...)
+    #[default]
+    All,
+    /// Only ones that need attention: display changes, errors, and warnings
+    Important,
+    /// None (do not disturb)
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HotkeysConfig {
+    /// Chord that pauses a running recording (video and input) or resumes a paused one,
+    /// e.g. "ctrl+shift+p". Unset = no hotkey.
+    #[serde(default)]
+    pub toggle_capture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Show a small always-on-top red dot while input is being captured. Click-through
+    /// and excluded from screen capture, so it never appears in recordings. macOS only.
+    #[serde(default)]
+    pub recording_indicator: bool,
+}
+
 impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
@@ -286,16 +1007,156 @@ impl Default for SecurityConfig {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             capture: CaptureConfig::default(),
             input: InputConfig::default(),
             upload: UploadConfig::default(),
             recording: RecordingConfig::default(),
             security: SecurityConfig::default(),
+            notifications: NotificationsConfig::default(),
+            hotkeys: HotkeysConfig::default(),
+            ui: UiConfig::default(),
+            webhooks: Vec::new(),
+            target_matcher: TargetAppMatcher::default(),
+            title_matchers: HashMap::new(),
+            extra: toml::Table::new(),
             config_path: None,
         }
     }
 }
 
+/// Prefix marking a `capture.target_apps` entry as a regular expression
+pub const TARGET_APP_REGEX_PREFIX: &str = "re:";
+
+/// A non-exact `capture.target_apps` entry
+#[derive(Debug, Clone)]
+enum AppPattern {
+    Glob(String),
+    Regex(regex::Regex),
+}
+
+/// Compiled `capture.target_apps`. Each entry is one of:
+/// - an exact bundle ID / process name (`com.apple.Safari`), looked up in a set;
+/// - a glob, if it contains `*` (any run of characters) or `?` (one character),
+///   e.g. `com.apple.*` or `com.jetbrains.*`;
+/// - a regex prefixed with `re:`, e.g. `re:^com\.jetbrains\.(intellij|pycharm)`.
+///
+/// Like exact matching, patterns are case-insensitive on Windows and case-sensitive
+/// elsewhere. Entries that fail to compile are skipped by the matcher and reported by
+/// `Config::validate`; they stay in the config.
+#[derive(Debug, Clone, Default)]
+pub struct TargetAppMatcher {
+    /// The entries this was compiled from, to detect a stale matcher
+    source: Vec<String>,
+    exact: HashSet<String>,
+    patterns: Vec<AppPattern>,
+}
+
+impl TargetAppMatcher {
+    pub fn new(target_apps: &[String]) -> Self {
+        let mut matcher = Self {
+            source: target_apps.to_vec(),
+            ..Self::default()
+        };
+        for entry in target_apps {
+            match compile_app_pattern(entry) {
+                Ok(Some(pattern)) => matcher.patterns.push(pattern),
+                Ok(None) => {
+                    matcher.exact.insert(fold_app_case(entry));
+                }
+                Err(e) => warn!("Ignoring target app pattern '{}': {}", entry, e),
+            }
+        }
+        matcher
+    }
+
+    /// Whether `entry` is a glob or regex rather than a literal app id
+    pub fn is_pattern(entry: &str) -> bool {
+        entry.starts_with(TARGET_APP_REGEX_PREFIX) || entry.contains(['*', '?'])
+    }
+
+    /// Whether `app_id` is selected by any entry
+    pub fn matches(&self, app_id: &str) -> bool {
+        let app_id = fold_app_case(app_id);
+        if self.exact.contains(&app_id) {
+            return true;
+        }
+        self.patterns.iter().any(|pattern| match pattern {
+            AppPattern::Glob(glob) => glob_matches(glob, &app_id),
+            AppPattern::Regex(re) => re.is_match(&app_id),
+        })
+    }
+
+    fn is_compiled_from(&self, target_apps: &[String]) -> bool {
+        self.source == target_apps
+    }
+}
+
+/// Compile one entry: `None` for an exact id, an error for an invalid regex
+fn compile_app_pattern(entry: &str) -> Result<Option<AppPattern>> {
+    if let Some(re) = entry.strip_prefix(TARGET_APP_REGEX_PREFIX) {
+        let re = regex::RegexBuilder::new(re)
+            .case_insensitive(cfg!(target_os = "windows"))
+            .build()
+            .with_context(|| format!("invalid regex '{}'", re))?;
+        return Ok(Some(AppPattern::Regex(re)));
+    }
+    if TargetAppMatcher::is_pattern(entry) {
+        return Ok(Some(AppPattern::Glob(fold_app_case(entry))));
+    }
+    Ok(None)
+}
+
+/// Why an `upload.metadata` entry can't be sent as an object metadata header, if it can't:
+/// the name becomes part of the header name and the value must be a plain header value
+fn metadata_entry_problem(key: &str, value: &str) -> Option<&'static str> {
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Some("names may only use ASCII letters, digits, '-' and '_'");
+    }
+    if !value.chars().all(|c| c == ' ' || c.is_ascii_graphic()) {
+        return Some("values must be printable ASCII");
+    }
+    None
+}
+
+/// App ids compare case-insensitively on Windows (executable names) only
+fn fold_app_case(app_id: &str) -> String {
+    if cfg!(target_os = "windows") {
+        app_id.to_ascii_lowercase()
+    } else {
+        app_id.to_string()
+    }
+}
+
+/// `*` matches any run of characters (including none), `?` exactly one
+pub(crate) fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently absorbing up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == text[t]) {
+            g += 1;
+            t += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            backtrack = Some((g, t));
+            g += 1;
+        } else if let Some((star, absorbed)) = backtrack {
+            g = star + 1;
+            t = absorbed + 1;
+            backtrack = Some((star, t));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
 /// The agent's own app identifier: the value `get_frontmost_app()` reports when
 /// the agent itself is in the foreground. Computed once and cached.
 ///
@@ -329,30 +1190,245 @@ pub fn is_agent_self(bundle_id: &str) -> bool {
     !me.is_empty() && bundle_id.eq_ignore_ascii_case(me)
 }
 
+/// Why the config file couldn't be loaded or saved
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// No per-user config directory could be determined
+    #[error("Failed to determine config directory")]
+    NoConfigDir,
+    #[error("Failed to read config file: {path:?}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Not valid TOML, or doesn't match the config schema
+    #[error("Failed to parse config file: {path:?}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Config file is not a TOML table")]
+    NotATable,
+    /// The migrated document doesn't match the config schema
+    #[error("Invalid config after migration")]
+    Invalid(#[source] toml::de::Error),
+    #[error("Failed to serialize config")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Failed to write config file: {path:?}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
 impl Config {
     /// Load configuration from default location or create default
-    pub fn load() -> Result<Self> {
-        let config_path = Self::default_config_path()?;
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(Self::default_config_path()?)
+    }
 
+    fn load_from(config_path: PathBuf) -> Result<Self, ConfigError> {
         if config_path.exists() {
-            let contents = std::fs::read_to_string(&config_path)
-                .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
-
-            let mut config: Config = toml::from_str(&contents)
-                .with_context(|| format!("Failed to parse config file: {:?}", config_path))?;
-
+            let contents =
+                std::fs::read_to_string(&config_path).map_err(|source| ConfigError::Read {
+                    path: config_path.clone(),
+                    source,
+                })?;
+
+            let raw: toml::Value =
+                toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+                    path: config_path.clone(),
+                    source,
+                })?;
+            let file_version = config_version(&raw);
+
+            let mut config = Self::migrate(raw).map_err(|e| match e {
+                ConfigError::Invalid(source) => ConfigError::Parse {
+                    path: config_path.clone(),
+                    source,
+                },
+                other => other,
+            })?;
+            config.validate();
             config.config_path = Some(config_path);
+
+            if file_version < CONFIG_VERSION {
+                info!(
+                    "Migrated config from version {} to {}",
+                    file_version, CONFIG_VERSION
+                );
+                config.save_migrated(&contents, file_version);
+            } else if file_version > CONFIG_VERSION {
+                warn!(
+                    "Config version {} is newer than this build supports ({}); \
+                     unknown keys are kept as-is",
+                    file_version, CONFIG_VERSION
+                );
+            }
             Ok(config)
         } else {
             // Create default config
-            let config = Config::default();
+            let config = Config {
+                config_path: Some(config_path),
+                ..Config::default()
+            };
             config.save()?;
             Ok(config)
         }
     }
 
+    /// Write a config migrated from `old_version` back to its file, keeping the file as it
+    /// was (`old_contents`, comments and all) next to it as `<name>.v<old_version>.bak`. If
+    /// the backup can't be written the file is left alone; it is migrated again on the
+    /// next load.
+    fn save_migrated(&self, old_contents: &str, old_version: u32) {
+        let path = self.config_path();
+        let mut backup = path.clone().into_os_string();
+        backup.push(format!(".v{}.bak", old_version));
+        let backup = PathBuf::from(backup);
+        if let Err(e) = std::fs::write(&backup, old_contents) {
+            warn!(
+                "Failed to back up config to {:?}: {}; leaving {:?} as it is",
+                backup, e, path
+            );
+            return;
+        }
+        match self.save() {
+            Ok(()) => info!("Previous config kept at {:?}", backup),
+            Err(e) => warn!("Failed to write migrated config: {}", e),
+        }
+    }
+
+    /// Upgrade a parsed config file of any older schema version to the current one.
+    /// Each step rewrites the layout in place; keys no step knows about are left alone.
+    /// Files from a newer version keep their version number so a downgrade doesn't
+    /// re-run migrations on them.
+    pub fn migrate(mut raw: toml::Value) -> Result<Self, ConfigError> {
+        upgrade_layout(&mut raw)?;
+        raw.try_into().map_err(ConfigError::Invalid)
+    }
+
+    /// This config as a TOML template for `config export`. With `redact`, the keys that
+    /// belong to this machine ([`LOCAL_KEYS`], including the encryption key) are left out
+    /// so the template is safe to share.
+    pub fn to_template(&self, redact: bool) -> Result<String, ConfigError> {
+        let mut value = toml::Value::try_from(self)?;
+        if redact {
+            let table = value.as_table_mut().ok_or(ConfigError::NotATable)?;
+            remove_local_keys(table);
+        }
+        Ok(toml::to_string_pretty(&value)?)
+    }
+
+    /// This config with a template (`config import`) laid over it. Tables are merged key
+    /// by key, any other value in the template replaces the local one, and keys the
+    /// template doesn't mention are kept. [`LOCAL_KEYS`] always keep their local values.
+    pub fn merge_template(&self, mut template: toml::Value) -> Result<Self, ConfigError> {
+        upgrade_layout(&mut template)?;
+        let template = template.as_table_mut().ok_or(ConfigError::NotATable)?;
+        template.remove("version");
+        remove_local_keys(template);
+
+        let mut merged = toml::Value::try_from(self)?;
+        merge_tables(
+            merged.as_table_mut().ok_or(ConfigError::NotATable)?,
+            std::mem::take(template),
+        );
+        let mut config: Config = merged.try_into().map_err(ConfigError::Invalid)?;
+        config.validate();
+        config.config_path = self.config_path.clone();
+        Ok(config)
+    }
+
+    /// Clamp out-of-range values to something usable, logging a warning for each.
+    /// Returns the warnings so callers (and tests) can see what was adjusted.
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        let poll_interval_ms = self.capture.poll_interval().as_millis() as u64;
+        if poll_interval_ms != self.capture.poll_interval_ms {
+            warnings.push(format!(
+                "capture.poll_interval_ms = {} is outside {}..={}; using {}",
+                self.capture.poll_interval_ms,
+                MIN_POLL_INTERVAL_MS,
+                MAX_POLL_INTERVAL_MS,
+                poll_interval_ms
+            ));
+            self.capture.poll_interval_ms = poll_interval_ms;
+        }
+
+        // Invalid patterns stay in the config, so saving it doesn't erase what the user
+        // wrote; the matchers skip them.
+        for entry in &self.capture.target_apps {
+            if let Err(e) = compile_app_pattern(entry) {
+                warnings.push(format!(
+                    "capture.target_apps: ignoring '{}': {:#}",
+                    entry, e
+                ));
+            }
+        }
+        self.refresh_target_matcher();
+
+        for (app, patterns) in &self.capture.title_patterns {
+            for entry in patterns {
+                if let Err(e) = compile_app_pattern(entry) {
+                    warnings.push(format!(
+                        "capture.title_patterns.\"{}\": ignoring '{}': {:#}",
+                        app, entry, e
+                    ));
+                }
+            }
+        }
+        self.title_matchers = self
+            .capture
+            .title_patterns
+            .iter()
+            .map(|(app, patterns)| (app.clone(), TargetAppMatcher::new(patterns)))
+            .collect();
+
+        let globs = [
+            (
+                "video_glob",
+                &mut self.recording.video_glob,
+                default_video_glob(),
+            ),
+            (
+                "input_glob",
+                &mut self.recording.input_glob,
+                default_input_glob(),
+            ),
+        ];
+        for (key, glob, default) in globs {
+            if !glob.contains('*') {
+                warnings.push(format!(
+                    "recording.{} = \"{}\" has no `*` for the segment id; using \"{}\"",
+                    key, glob, default
+                ));
+                *glob = default;
+            }
+        }
+
+        self.upload
+            .metadata
+            .retain(|key, value| match metadata_entry_problem(key, value) {
+                Some(problem) => {
+                    warnings.push(format!("upload.metadata: dropping '{}': {}", key, problem));
+                    false
+                }
+                None => true,
+            });
+
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+        warnings
+    }
+
     /// Save configuration to file
-    pub fn save(&self) -> Result<()> {
+    pub fn save(&self) -> Result<(), ConfigError> {
         let config_path = self
             .config_path
             .clone()
@@ -360,14 +1436,18 @@ impl Config {
 
         // Ensure parent directory exists
         if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
+            std::fs::create_dir_all(parent).map_err(|source| ConfigError::Write {
+                path: parent.to_path_buf(),
+                source,
+            })?;
         }
 
-        let contents = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        let contents = toml::to_string_pretty(self)?;
 
-        std::fs::write(&config_path, contents)
-            .with_context(|| format!("Failed to write config file: {:?}", config_path))?;
+        std::fs::write(&config_path, contents).map_err(|source| ConfigError::Write {
+            path: config_path,
+            source,
+        })?;
 
         Ok(())
     }
@@ -380,13 +1460,21 @@ impl Config {
     }
 
     /// Get default config path
-    fn default_config_path() -> Result<PathBuf> {
+    fn default_config_path() -> Result<PathBuf, ConfigError> {
         let proj_dirs = directories::ProjectDirs::from("dev", "crowd-cast", "agent")
-            .context("Failed to determine config directory")?;
+            .ok_or(ConfigError::NoConfigDir)?;
 
         Ok(proj_dirs.config_dir().join("config.toml"))
     }
 
+    /// Where recordings are written: `recording.output_directory`, or the temp-dir default
+    pub fn output_directory(&self) -> PathBuf {
+        self.recording
+            .output_directory
+            .clone()
+            .unwrap_or_else(default_recording_output_directory)
+    }
+
     /// Get or generate session ID
     pub fn session_id(&self) -> String {
         self.recording
@@ -395,6 +1483,16 @@ impl Config {
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
     }
 
+    /// Session ID for a recording starting now, per `recording.session_id_scheme`
+    pub fn recording_session_id(&self) -> Result<String> {
+        recording_session_id(
+            self.recording.session_id_scheme,
+            self.recording.session_id.as_deref(),
+            &host_name(),
+            chrono::Utc::now(),
+        )
+    }
+
     /// Check if setup wizard needs to be run
     pub fn needs_setup(&self) -> bool {
         !self.capture.setup_completed
@@ -422,21 +1520,49 @@ impl Config {
         // On Windows, app identifiers are executable names whose case the user
         // can't reliably predict, so match case-insensitively. On macOS/Linux the
         // identifiers (bundle IDs / process names) are case-sensitive.
-        #[cfg(target_os = "windows")]
+        if self
+            .target_matcher
+            .is_compiled_from(&self.capture.target_apps)
         {
-            self.capture
-                .target_apps
-                .iter()
-                .any(|app| app.eq_ignore_ascii_case(bundle_id))
+            self.target_matcher.matches(bundle_id)
+        } else {
+            // target_apps was edited directly without a refresh; compile on the fly.
+            TargetAppMatcher::new(&self.capture.target_apps).matches(bundle_id)
+        }
+    }
+
+    /// Check if input should be captured for the given app while `window_title` (None when
+    /// unknown) is its focused window: the app must be captured and, if it has
+    /// `capture.title_patterns`, the title must match one of them
+    pub fn should_capture_window(&self, bundle_id: &str, window_title: Option<&str>) -> bool {
+        if !self.should_capture_app(bundle_id) {
+            return false;
         }
-        #[cfg(not(target_os = "windows"))]
+        let Some(patterns) = self.capture.title_patterns.get(bundle_id) else {
+            return true;
+        };
+        let Some(title) = window_title else {
+            return false;
+        };
+        match self.title_matchers.get(bundle_id) {
+            Some(matcher) if matcher.is_compiled_from(patterns) => matcher.matches(title),
+            // title_patterns was edited directly without a validate; compile on the fly.
+            _ => TargetAppMatcher::new(patterns).matches(title),
+        }
+    }
+
+    /// Recompile `capture.target_apps` after editing it directly
+    pub fn refresh_target_matcher(&mut self) {
+        if !self
+            .target_matcher
+            .is_compiled_from(&self.capture.target_apps)
         {
-            self.capture.target_apps.iter().any(|app| app == bundle_id)
+            self.target_matcher = TargetAppMatcher::new(&self.capture.target_apps);
         }
     }
 
     /// Mark setup as completed and save
-    pub fn complete_setup(&mut self) -> Result<()> {
+    pub fn complete_setup(&mut self) -> Result<(), ConfigError> {
         self.capture.setup_completed = true;
         self.save()
     }
@@ -446,19 +1572,226 @@ impl Config {
         if !self.capture.target_apps.contains(&bundle_id) {
             self.capture.target_apps.push(bundle_id);
         }
+        self.refresh_target_matcher();
     }
 
     /// Remove an app from the capture list
     pub fn remove_target_app(&mut self, bundle_id: &str) {
         self.capture.target_apps.retain(|app| app != bundle_id);
+        self.refresh_target_matcher();
     }
 
     /// Clear all target apps
     pub fn clear_target_apps(&mut self) {
         self.capture.target_apps.clear();
+        self.refresh_target_matcher();
     }
 }
 
+/// Session ID for a recording started at `now` on host `host` (see `SessionIdScheme`).
+/// The timestamped forms end in a random suffix, so recordings started within the same
+/// second (a restart after a panic, or the same configured ID on every run) never share
+/// segment names or upload keys.
+fn recording_session_id(
+    scheme: SessionIdScheme,
+    configured: Option<&str>,
+    host: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<String> {
+    let prefix = match scheme {
+        SessionIdScheme::Uuid => return Ok(uuid::Uuid::new_v4().to_string()),
+        SessionIdScheme::Timestamped => host.to_string(),
+        SessionIdScheme::FromConfig => match configured.map(id_safe) {
+            Some(id) if !id.is_empty() => id,
+            _ => anyhow::bail!(
+                "recording.session_id_scheme is \"from_config\" but recording.session_id is not set"
+            ),
+        },
+    };
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    Ok(format!(
+        "{}_{}_{}",
+        prefix,
+        now.format("%Y%m%dT%H%M%SZ"),
+        &suffix[..6]
+    ))
+}
+
+/// `text` trimmed, with every character other than ASCII letters, digits and '-' replaced
+/// by '-', so it is safe in file names and upload keys and can't be mistaken for the `_`
+/// that separates the parts of a segment ID
+fn id_safe(text: &str) -> String {
+    text.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// This machine's host name, reduced to characters safe in file names and upload keys
+/// (`unknown-host` if it can't be read)
+fn host_name() -> String {
+    #[cfg(unix)]
+    let name = {
+        let mut buf = [0u8; 256];
+        // SAFETY: gethostname writes at most `buf.len()` bytes into `buf`.
+        if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } == 0 {
+            let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+            String::from_utf8_lossy(&buf[..len]).into_owned()
+        } else {
+            String::new()
+        }
+    };
+    #[cfg(not(unix))]
+    let name = std::env::var("COMPUTERNAME").unwrap_or_default();
+
+    let name = id_safe(name.split('.').next().unwrap_or_default());
+    if name.is_empty() {
+        "unknown-host".to_string()
+    } else {
+        name
+    }
+}
+
+/// `crowd-cast-agent config export [--redact] [--out <path>]` prints the current config
+/// as a template (or writes it to a file); `config import <path>` merges a template into
+/// it. Returns the process exit code.
+pub fn run_config_command(args: &[String]) -> i32 {
+    const USAGE: &str = "Usage: crowd-cast-agent config export [--redact] [--out <path>]\n       \
+                         crowd-cast-agent config import <path>";
+
+    let result = match args.first().map(String::as_str) {
+        Some("export") => {
+            let mut redact = false;
+            let mut out = None;
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--redact" => redact = true,
+                    "--out" => match rest.next() {
+                        Some(path) => out = Some(PathBuf::from(path)),
+                        None => {
+                            eprintln!("{}", USAGE);
+                            return 2;
+                        }
+                    },
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return 2;
+                    }
+                }
+            }
+            export_config(redact, out.as_deref())
+        }
+        Some("import") if args.len() == 2 => import_config(Path::new(&args[1])),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            1
+        }
+    }
+}
+
+fn export_config(redact: bool, out: Option<&Path>) -> Result<()> {
+    let template = Config::load()?.to_template(redact)?;
+    match out {
+        Some(path) => {
+            std::fs::write(path, template)
+                .with_context(|| format!("Failed to write {:?}", path))?;
+            eprintln!("Config exported to {:?}", path);
+        }
+        None => print!("{}", template),
+    }
+    Ok(())
+}
+
+fn import_config(path: &Path) -> Result<()> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let template: toml::Value =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {:?}", path))?;
+    let config = Config::load()?.merge_template(template)?;
+    config.save()?;
+    println!(
+        "Imported {:?} into {:?}; restart the agent to apply it",
+        path,
+        config.config_path()
+    );
+    Ok(())
+}
+
+/// Keys that belong to one machine rather than to a shared template: secrets (the
+/// encryption key, portal restore tokens) and local state (session id, setup progress).
+/// `config export --redact` leaves them out and `config import` never overwrites them;
+/// the same goes for the top-level `webhooks`.
+const LOCAL_KEYS: &[(&str, &str)] = &[
+    ("upload", "encryption_key"),
+    ("capture", "restore_tokens"),
+    ("capture", "setup_completed"),
+    ("recording", "session_id"),
+];
+
+fn remove_local_keys(table: &mut toml::Table) {
+    for (section, key) in LOCAL_KEYS {
+        if let Some(section) = table.get_mut(*section).and_then(toml::Value::as_table_mut) {
+            section.remove(*key);
+        }
+    }
+    // Webhook URLs usually carry a token (e.g. Slack incoming webhooks)
+    table.remove("webhooks");
+}
+
+/// Lay `overlay` over `base`: tables present in both are merged recursively, every other
+/// overlay value replaces the base one.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Rewrite a parsed config file of any older schema version to the current layout in
+/// place and stamp it with the current version (newer files keep theirs).
+fn upgrade_layout(raw: &mut toml::Value) -> Result<(), ConfigError> {
+    let version = config_version(raw);
+    let table = raw.as_table_mut().ok_or(ConfigError::NotATable)?;
+
+    // v1 files (no `version` key) already have the v2 layout; v2 only added the stamp.
+    // Steps for later versions go here, oldest first.
+
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(version.max(CONFIG_VERSION) as i64),
+    );
+    Ok(())
+}
+
+/// Schema version recorded in a raw config file (1 when absent)
+fn config_version(raw: &toml::Value) -> u32 {
+    raw.get("version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or_else(default_legacy_config_version)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -487,4 +1820,417 @@ mod tests {
         // Self-exclusion is case-insensitive.
         assert!(!cfg.should_capture_app(&me.to_ascii_uppercase()));
     }
+
+    #[test]
+    fn unknown_frontmost_app_follows_policy() {
+        let policy = |toml: &str| {
+            toml::from_str::<CaptureConfig>(toml)
+                .unwrap()
+                .when_frontmost_unknown
+        };
+        // Unset means capture nothing, even with capture_all on.
+        let unset = policy("capture_all = true");
+        assert_eq!(unset, FrontmostUnknownPolicy::CaptureNone);
+        assert!(!unset.should_capture(true));
+
+        let all = policy(r#"when_frontmost_unknown = "capture_all""#);
+        assert!(all.should_capture(false));
+
+        // Last known keeps whatever was decided for the last detected app.
+        let last = policy(r#"when_frontmost_unknown = "last_known""#);
+        assert!(last.should_capture(true));
+        assert!(!last.should_capture(false));
+    }
+
+    #[test]
+    fn app_profile_selected_for_frontmost_app() {
+        let cfg: Config = toml::from_str(
+            r#"
+[capture.app_profiles."com.apple.Terminal"]
+fps = 15
+
+[capture.app_profiles."com.valvesoftware.steam"]
+fps = 60
+max_height = 720
+"#,
+        )
+        .unwrap();
+        let defaults = (30, 1080);
+
+        let terminal = cfg.capture.app_profile(Some("com.apple.Terminal"));
+        assert_eq!(terminal.resolve(defaults), (15, 1080));
+        let game = cfg.capture.app_profile(Some("com.valvesoftware.steam"));
+        assert_eq!(game.resolve(defaults), (60, 720));
+
+        // No matching profile (or no frontmost app): the defaults.
+        let other = cfg.capture.app_profile(Some("com.apple.Safari"));
+        assert_eq!(other, RecordingProfile::default());
+        assert_eq!(other.resolve(defaults), defaults);
+        assert_eq!(cfg.capture.app_profile(None).resolve(defaults), defaults);
+    }
+
+    const V1_CONFIG: &str = r#"
+# Hand-written settings
+[obs]
+host = "localhost"
+port = 4455
+
+[capture]
+target_apps = ["com.apple.Safari"]
+idle_timeout_secs = 120
+
+[upload]
+delete_after_upload = false
+"#;
+
+    #[test]
+    fn migrate_upgrades_v1_config() {
+        let raw: toml::Value = toml::from_str(V1_CONFIG).unwrap();
+        let config = Config::migrate(raw).unwrap();
+
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.capture.idle_timeout_secs, 120);
+        assert_eq!(config.capture.target_apps, vec!["com.apple.Safari"]);
+        assert!(!config.upload.delete_after_upload);
+
+        // Unknown sections survive a save round-trip.
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let reparsed: toml::Value = toml::from_str(&saved).unwrap();
+        assert_eq!(
+            reparsed["version"].as_integer(),
+            Some(CONFIG_VERSION as i64)
+        );
+        assert_eq!(reparsed["obs"]["port"].as_integer(), Some(4455));
+        assert_eq!(
+            reparsed["capture"]["idle_timeout_secs"].as_integer(),
+            Some(120)
+        );
+    }
+
+    #[test]
+    fn loading_a_v1_file_keeps_a_backup_of_it() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-v1-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, V1_CONFIG).unwrap();
+
+        let config = Config::load_from(path.clone()).unwrap();
+        assert_eq!(config.capture.idle_timeout_secs, 120);
+        let backup = dir.join("config.toml.v1.bak");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), V1_CONFIG);
+        let rewritten: toml::Value =
+            toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            rewritten["version"].as_integer(),
+            Some(CONFIG_VERSION as i64)
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_keeps_newer_version_and_unknown_keys() {
+        let raw: toml::Value = toml::from_str(
+            r#"
+version = 99
+future_flag = true
+
+[capture]
+idle_timeout_secs = 30
+"#,
+        )
+        .unwrap();
+        let config = Config::migrate(raw).unwrap();
+
+        assert_eq!(config.version, 99);
+        assert_eq!(config.capture.idle_timeout_secs, 30);
+        assert_eq!(
+            config.extra.get("future_flag"),
+            Some(&toml::Value::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn session_id_follows_scheme() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-10-17T09:30:15Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let id = |scheme, configured| recording_session_id(scheme, configured, "studio-mac", now);
+
+        let uuid = id(SessionIdScheme::Uuid, Some("ignored")).unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok(), "{}", uuid);
+        assert_ne!(uuid, id(SessionIdScheme::Uuid, None).unwrap());
+
+        // Same machine and start second, still distinct IDs.
+        let stamped = id(SessionIdScheme::Timestamped, None).unwrap();
+        assert!(
+            stamped.starts_with("studio-mac_20261017T093015Z_"),
+            "{}",
+            stamped
+        );
+        assert_eq!(stamped.len(), "studio-mac_20261017T093015Z_".len() + 6);
+        assert_ne!(stamped, id(SessionIdScheme::Timestamped, None).unwrap());
+
+        // The configured ID is a prefix, made safe for file names and segment IDs.
+        let configured = id(SessionIdScheme::FromConfig, Some(" study_42/a b ")).unwrap();
+        assert!(
+            configured.starts_with("study-42-a-b_20261017T093015Z_"),
+            "{}",
+            configured
+        );
+        assert_ne!(
+            configured,
+            id(SessionIdScheme::FromConfig, Some(" study_42/a b ")).unwrap()
+        );
+        assert!(id(SessionIdScheme::FromConfig, None).is_err());
+        assert!(id(SessionIdScheme::FromConfig, Some("  ")).is_err());
+
+        let host = host_name();
+        assert!(!host.is_empty());
+        assert!(host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+
+        let scheme = |toml: &str| {
+            toml::from_str::<RecordingConfig>(toml)
+                .unwrap()
+                .session_id_scheme
+        };
+        assert_eq!(scheme(""), SessionIdScheme::Uuid);
+        assert_eq!(
+            scheme("session_id_scheme = \"from_config\""),
+            SessionIdScheme::FromConfig
+        );
+    }
+
+    fn config_with_secrets() -> Config {
+        let mut config = Config::default();
+        config.upload.encryption_key = Some("c2VjcmV0".to_string());
+        config
+            .capture
+            .restore_tokens
+            .insert("__display__".to_string(), "token".to_string());
+        config.capture.setup_completed = true;
+        config.recording.session_id = Some("local-session".to_string());
+        config.capture.target_apps = vec!["com.apple.Safari".to_string()];
+        config
+    }
+
+    #[test]
+    fn redacted_export_leaves_out_local_keys() {
+        let config = config_with_secrets();
+
+        let redacted: toml::Value = toml::from_str(&config.to_template(true).unwrap()).unwrap();
+        assert!(redacted["upload"].get("encryption_key").is_none());
+        assert!(redacted["capture"].get("restore_tokens").is_none());
+        assert!(redacted["capture"].get("setup_completed").is_none());
+        assert!(redacted["recording"].get("session_id").is_none());
+        assert_eq!(
+            redacted["capture"]["target_apps"][0].as_str(),
+            Some("com.apple.Safari")
+        );
+        // Still a loadable config.
+        let reloaded = Config::migrate(redacted).unwrap();
+        assert!(reloaded.upload.encryption_key.is_none());
+
+        let full: toml::Value = toml::from_str(&config.to_template(false).unwrap()).unwrap();
+        assert_eq!(full["upload"]["encryption_key"].as_str(), Some("c2VjcmV0"));
+    }
+
+    #[test]
+    fn merged_template_keeps_local_secrets_and_unmentioned_keys() {
+        let mut local = config_with_secrets();
+        local.upload.delete_after_upload = false;
+        local.capture.idle_timeout_secs = 600;
+
+        let template: toml::Value = toml::from_str(
+            r#"
+version = 1
+
+[capture]
+target_apps = ["com.microsoft.VSCode"]
+idle_pause_secs = 90
+setup_completed = false
+
+[upload]
+encryption_key = "dGVtcGxhdGU="
+max_concurrent_uploads = 4
+
+[input]
+emit_chords = true
+"#,
+        )
+        .unwrap();
+        let merged = local.merge_template(template).unwrap();
+
+        // The template's settings win, including a v1 key name it still uses.
+        assert_eq!(merged.capture.target_apps, vec!["com.microsoft.VSCode"]);
+        assert_eq!(merged.capture.idle_timeout_secs, 90);
+        assert_eq!(merged.upload.max_concurrent_uploads, 4);
+        assert!(merged.input.emit_chords);
+        assert_eq!(merged.version, CONFIG_VERSION);
+        // Keys it doesn't mention keep their local values, not the defaults.
+        assert!(!merged.upload.delete_after_upload);
+        // Local secrets and state are never overwritten.
+        assert_eq!(merged.upload.encryption_key.as_deref(), Some("c2VjcmV0"));
+        assert_eq!(merged.capture.restore_tokens["__display__"], "token");
+        assert!(merged.capture.setup_completed);
+        assert_eq!(
+            merged.recording.session_id.as_deref(),
+            Some("local-session")
+        );
+        assert!(merged.should_capture_app("com.microsoft.VSCode"));
+    }
+
+    #[test]
+    fn load_reports_unreadable_configs_by_kind() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        std::fs::write(&path, "[capture\ntarget_apps = [").unwrap();
+        assert!(matches!(
+            Config::load_from(path.clone()),
+            Err(ConfigError::Parse { .. })
+        ));
+
+        // Valid TOML that doesn't fit the schema is a parse error for that file too.
+        std::fs::write(&path, "[capture]\nidle_timeout_secs = \"soon\"\n").unwrap();
+        match Config::load_from(path.clone()) {
+            Err(ConfigError::Parse { path: reported, .. }) => assert_eq!(reported, path),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+
+        assert!(matches!(
+            Config::migrate(toml::Value::Integer(2)),
+            Err(ConfigError::NotATable)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn target_app_globs_and_regexes() {
+        let apps = [
+            "com.apple.*",
+            "re:^com\\.jetbrains\\.(intellij|pycharm)",
+            "org.mozilla.firefox",
+            "code-?",
+        ]
+        .map(String::from);
+        let matcher = TargetAppMatcher::new(&apps);
+
+        assert!(matcher.matches("com.apple.Safari"));
+        assert!(matcher.matches("com.apple.dt.Xcode"));
+        assert!(!matcher.matches("com.applesauce"));
+        assert!(matcher.matches("com.jetbrains.intellij.ce"));
+        assert!(matcher.matches("com.jetbrains.pycharm"));
+        assert!(!matcher.matches("com.jetbrains.goland"));
+        assert!(matcher.matches("org.mozilla.firefox"));
+        assert!(!matcher.matches("org.mozilla.firefox.beta"));
+        assert!(matcher.matches("code-1"));
+        assert!(!matcher.matches("code-12"));
+
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(!glob_matches("a*b*c", "aXbYbZ"));
+    }
+
+    #[test]
+    fn title_patterns_require_app_and_window_title_match() {
+        let mut cfg: Config = toml::from_str(
+            r#"
+            [capture]
+            target_apps = ["com.google.Chrome", "com.apple.Terminal"]
+
+            [capture.title_patterns]
+            "com.google.Chrome" = ["*Jira*", "re:^PR #\\d+", "re:(broken"]
+            "#,
+        )
+        .unwrap();
+        let warnings = cfg.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("re:(broken"));
+
+        let chrome = "com.google.Chrome";
+        assert!(cfg.should_capture_window(chrome, Some("PROJ-12 - Jira - Google Chrome")));
+        assert!(cfg.should_capture_window(chrome, Some("PR #42 · crowd-cast")));
+        assert!(!cfg.should_capture_window(chrome, Some("Inbox - Gmail")));
+        // An unreadable title never satisfies the patterns.
+        assert!(!cfg.should_capture_window(chrome, None));
+
+        // Apps without patterns are captured whatever the window.
+        assert!(cfg.should_capture_window("com.apple.Terminal", None));
+        assert!(cfg.should_capture_window("com.apple.Terminal", Some("zsh")));
+
+        // A matching title doesn't capture an app that isn't a target.
+        cfg.capture
+            .title_patterns
+            .insert("com.apple.Safari".to_string(), vec!["*Jira*".to_string()]);
+        assert!(!cfg.should_capture_window("com.apple.Safari", Some("Jira")));
+
+        // Patterns edited directly (without validate) still apply.
+        cfg.capture
+            .title_patterns
+            .insert(chrome.to_string(), vec!["Docs".to_string()]);
+        assert!(cfg.should_capture_window(chrome, Some("Docs")));
+        assert!(!cfg.should_capture_window(chrome, Some("PROJ-12 - Jira")));
+    }
+
+    #[test]
+    fn validate_ignores_invalid_target_app_patterns_but_keeps_them() {
+        let mut cfg = Config::default();
+        cfg.capture.target_apps = vec!["re:com.(broken".to_string(), "com.apple.*".to_string()];
+
+        let warnings = cfg.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("re:com.(broken"));
+        assert!(cfg.should_capture_app("com.apple.Notes"));
+        assert!(!cfg.should_capture_app("com.(broken"));
+
+        // Saving writes back what the user wrote.
+        let saved: Config = toml::from_str(&toml::to_string_pretty(&cfg).unwrap()).unwrap();
+        assert_eq!(
+            saved.capture.target_apps,
+            vec!["re:com.(broken", "com.apple.*"]
+        );
+    }
+
+    #[test]
+    fn validate_drops_metadata_that_cant_be_a_header() {
+        let mut cfg = Config::default();
+        cfg.upload.metadata = BTreeMap::from([
+            ("project".to_string(), "onboarding study".to_string()),
+            ("team name".to_string(), "a".to_string()),
+            ("cohort".to_string(), "b\r\nx-amz-acl: x".to_string()),
+            ("owner".to_string(), "Zoë".to_string()),
+        ]);
+
+        let warnings = cfg.validate();
+        assert_eq!(
+            cfg.upload.metadata,
+            BTreeMap::from([("project".to_string(), "onboarding study".to_string())])
+        );
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.iter().any(|w| w.contains("'team name'")));
+    }
+
+    #[test]
+    fn validate_clamps_poll_interval() {
+        let mut cfg = Config::default();
+        assert!(cfg.validate().is_empty());
+
+        cfg.capture.poll_interval_ms = 0;
+        let warnings = cfg.validate();
+        assert_eq!(cfg.capture.poll_interval_ms, MIN_POLL_INTERVAL_MS);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("poll_interval_ms = 0"));
+
+        cfg.capture.poll_interval_ms = 60_000;
+        cfg.validate();
+        assert_eq!(cfg.capture.poll_interval_ms, MAX_POLL_INTERVAL_MS);
+
+        // The timer-facing accessor clamps even without validate().
+        cfg.capture.poll_interval_ms = 1;
+        assert_eq!(
+            cfg.capture.poll_interval(),
+            Duration::from_millis(MIN_POLL_INTERVAL_MS)
+        );
+    }
 }
@@ -0,0 +1,483 @@
+//! Local control socket
+//!
+//! A running agent listens on a Unix socket in its data directory so shell users and
+//! health checks can query it without the tray: `crowd-cast-agent status` connects,
+//! sends a one-line request, and reads back a one-line JSON `StatusSnapshot`. The same
+//! socket lists the apps that could be captured, runs a test upload and adds markers to
+//! the recording. Unix only; on Windows these commands report that they are unsupported.
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::sync::{CapturableApp, StatusSnapshot};
+
+/// Request line asking the agent for a `StatusSnapshot`
+#[cfg_attr(not(unix), allow(dead_code))]
+const STATUS_REQUEST: &str = "status";
+
+/// Request line asking the agent for the running apps it could capture
+#[cfg_attr(not(unix), allow(dead_code))]
+const APPS_REQUEST: &str = "apps";
+
+/// Request line asking the agent to run `Uploader::test_upload`
+#[cfg_attr(not(unix), allow(dead_code))]
+const TEST_UPLOAD_REQUEST: &str = "test-upload";
+
+/// Request line prefix asking the agent to add a marker; the rest of the line is the label
+#[cfg_attr(not(unix), allow(dead_code))]
+const MARKER_REQUEST: &str = "marker ";
+
+/// How long either side waits on the other before giving up
+#[cfg_attr(not(unix), allow(dead_code))]
+const CONTROL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Listing apps shells out on macOS, which can take a few seconds on a busy machine
+#[cfg_attr(not(unix), allow(dead_code))]
+const APPS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// A test upload makes two network requests (presign + PUT), each bounded at 30s
+#[cfg_attr(not(unix), allow(dead_code))]
+const TEST_UPLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(65);
+
+/// Control socket location, next to the other per-user agent state.
+#[cfg_attr(not(unix), allow(dead_code))]
+pub fn socket_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("dev", "crowd-cast", "agent")
+        .map(|p| p.data_dir().join("control.sock"))
+}
+
+/// Human-readable rendering of a status snapshot for `crowd-cast-agent status`.
+pub fn format_status(snapshot: &StatusSnapshot) -> String {
+    let recording = match (snapshot.recording, snapshot.paused) {
+        (true, true) => "paused",
+        (true, false) => "yes",
+        (false, _) => "no",
+    };
+    let mut text = format!(
+        "Status:          {}\nRecording:       {}\nSession:         {}\nPending uploads: {}{}",
+        snapshot.status,
+        recording,
+        snapshot.session_id.as_deref().unwrap_or("-"),
+        snapshot.pending_uploads,
+        if snapshot.uploads_circuit_open {
+            " (on hold after repeated failures)"
+        } else {
+            ""
+        }
+    );
+    if !snapshot.failed_apps.is_empty() {
+        text.push_str(&format!(
+            "\nNot capturing:   {}",
+            snapshot.failed_apps.join(", ")
+        ));
+    }
+    text
+}
+
+/// Human-readable rendering of the capturable app list for `crowd-cast-agent apps`: one
+/// line per app, `*` marking the ones the config already captures.
+pub fn format_apps(apps: &[CapturableApp]) -> String {
+    if apps.is_empty() {
+        return "No capturable apps running".to_string();
+    }
+    apps.iter()
+        .map(|app| {
+            let mut line = format!(
+                "{} {} ({}, pid {})",
+                if app.targeted { "*" } else { " " },
+                app.name,
+                app.bundle_id,
+                app.pid
+            );
+            if let Some(title) = &app.window_title {
+                line.push_str(&format!(" - {}", title));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Serve the control socket until the process exits. Refuses to take over the socket of
+/// another live agent; a stale socket file from a crashed run is replaced.
+#[cfg(unix)]
+pub async fn serve(
+    path: PathBuf,
+    cmd_tx: tokio::sync::mpsc::Sender<crate::sync::EngineCommand>,
+) -> Result<()> {
+    use anyhow::Context as _;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+    use tracing::{debug, info};
+
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            anyhow::bail!("another agent is already serving {:?}", path);
+        }
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove stale control socket {:?}", path))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Failed to bind control socket {:?}", path))?;
+    info!("Control socket listening on {:?}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let cmd_tx = cmd_tx.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut reader = BufReader::new(read);
+            let mut request = String::new();
+            let read_line = reader.read_line(&mut request);
+            if !matches!(
+                tokio::time::timeout(CONTROL_TIMEOUT, read_line).await,
+                Ok(Ok(_))
+            ) {
+                return;
+            }
+            let response = match request.trim() {
+                STATUS_REQUEST => {
+                    let (respond_to, mut rx) = tokio::sync::mpsc::unbounded_channel();
+                    let _ = cmd_tx
+                        .send(crate::sync::EngineCommand::QueryStatus { respond_to })
+                        .await;
+                    match tokio::time::timeout(CONTROL_TIMEOUT, rx.recv()).await {
+                        Ok(Some(snapshot)) => serde_json::to_string(&snapshot)
+                            .unwrap_or_else(|e| error_response(&e.to_string())),
+                        _ => error_response("engine did not respond"),
+                    }
+                }
+                APPS_REQUEST => {
+                    let (respond_to, mut rx) = tokio::sync::mpsc::unbounded_channel();
+                    let _ = cmd_tx
+                        .send(crate::sync::EngineCommand::ListCapturable { respond_to })
+                        .await;
+                    match tokio::time::timeout(APPS_TIMEOUT, rx.recv()).await {
+                        Ok(Some(apps)) => serde_json::to_string(&apps)
+                            .unwrap_or_else(|e| error_response(&e.to_string())),
+                        _ => error_response("engine did not respond"),
+                    }
+                }
+                TEST_UPLOAD_REQUEST => {
+                    let (respond_to, mut rx) = tokio::sync::mpsc::unbounded_channel();
+                    let _ = cmd_tx
+                        .send(crate::sync::EngineCommand::TestUpload { respond_to })
+                        .await;
+                    match tokio::time::timeout(TEST_UPLOAD_TIMEOUT, rx.recv()).await {
+                        Ok(Some(Ok(()))) => serde_json::json!({ "ok": true }).to_string(),
+                        Ok(Some(Err(e))) => error_response(&e),
+                        _ => error_response("engine did not respond"),
+                    }
+                }
+                other => match other.strip_prefix(MARKER_REQUEST) {
+                    Some(label) => add_marker(&cmd_tx, label.trim()).await,
+                    None => error_response(&format!("unknown request '{}'", other)),
+                },
+            };
+            if let Err(e) = write.write_all(format!("{}\n", response).as_bytes()).await {
+                debug!("Control socket client went away: {}", e);
+            }
+        });
+    }
+}
+
+/// Forward a marker to the engine, refusing it up front when nothing is recording so the
+/// caller learns the label went nowhere.
+#[cfg(unix)]
+async fn add_marker(
+    cmd_tx: &tokio::sync::mpsc::Sender<crate::sync::EngineCommand>,
+    label: &str,
+) -> String {
+    use crate::sync::EngineCommand;
+
+    if label.is_empty() {
+        return error_response("marker label is empty");
+    }
+    let (respond_to, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let _ = cmd_tx.send(EngineCommand::QueryStatus { respond_to }).await;
+    match tokio::time::timeout(CONTROL_TIMEOUT, rx.recv()).await {
+        Ok(Some(snapshot)) if snapshot.recording => {
+            let _ = cmd_tx
+                .send(EngineCommand::AddMarker {
+                    label: label.to_string(),
+                })
+                .await;
+            serde_json::json!({ "ok": true }).to_string()
+        }
+        Ok(Some(_)) => error_response("not recording"),
+        _ => error_response("engine did not respond"),
+    }
+}
+
+#[cfg(unix)]
+fn error_response(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Send one request line to the agent listening on `path` and return its JSON reply.
+/// An `{"error": ...}` reply becomes an `Err`.
+#[cfg(unix)]
+async fn request(
+    path: &std::path::Path,
+    line: &str,
+    timeout: std::time::Duration,
+) -> Result<serde_json::Value> {
+    use anyhow::Context as _;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = tokio::net::UnixStream::connect(path)
+        .await
+        .with_context(|| format!("No running agent (cannot connect to {:?})", path))?;
+    stream.write_all(format!("{}\n", line).as_bytes()).await?;
+    let mut reply = String::new();
+    tokio::time::timeout(timeout, BufReader::new(stream).read_line(&mut reply))
+        .await
+        .context("Timed out waiting for the agent to reply")??;
+
+    let value: serde_json::Value =
+        serde_json::from_str(reply.trim()).context("Malformed reply from the agent")?;
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!("{}", error);
+    }
+    Ok(value)
+}
+
+/// Ask the agent listening on `path` for its status.
+#[cfg(unix)]
+pub async fn query_status(path: &std::path::Path) -> Result<StatusSnapshot> {
+    use anyhow::Context as _;
+
+    let value = request(path, STATUS_REQUEST, CONTROL_TIMEOUT).await?;
+    serde_json::from_value(value).context("Malformed status response")
+}
+
+/// Ask the agent listening on `path` for the running apps it could capture.
+#[cfg(unix)]
+pub async fn list_apps(path: &std::path::Path) -> Result<Vec<CapturableApp>> {
+    use anyhow::Context as _;
+
+    let value = request(path, APPS_REQUEST, APPS_TIMEOUT).await?;
+    serde_json::from_value(value).context("Malformed app list response")
+}
+
+/// Ask the agent listening on `path` to run a test upload with its credentials.
+#[cfg(unix)]
+pub async fn test_upload(path: &std::path::Path) -> Result<()> {
+    request(path, TEST_UPLOAD_REQUEST, TEST_UPLOAD_TIMEOUT)
+        .await
+        .map(|_| ())
+}
+
+/// Ask the agent listening on `path` to add a marker to the current recording.
+#[cfg(unix)]
+pub async fn add_marker_request(path: &std::path::Path, label: &str) -> Result<()> {
+    request(
+        path,
+        &format!("{}{}", MARKER_REQUEST, label),
+        CONTROL_TIMEOUT,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// `crowd-cast-agent status [--json]`: print the running agent's status. Returns the
+/// process exit code (non-zero when no agent answers).
+pub fn run_status_command(json: bool) -> i32 {
+    #[cfg(unix)]
+    let result = with_running_agent(|path| async move { query_status(&path).await });
+    #[cfg(not(unix))]
+    let result: Result<StatusSnapshot> = unsupported();
+
+    match result {
+        Ok(snapshot) => {
+            if json {
+                match serde_json::to_string_pretty(&snapshot) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Failed to encode status: {}", e);
+                        return 1;
+                    }
+                }
+            } else {
+                println!("{}", format_status(&snapshot));
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            1
+        }
+    }
+}
+
+/// `crowd-cast-agent apps [--json]`: print the apps the running agent could capture.
+/// Returns the process exit code (non-zero when no agent answers).
+pub fn run_apps_command(json: bool) -> i32 {
+    #[cfg(unix)]
+    let result = with_running_agent(|path| async move { list_apps(&path).await });
+    #[cfg(not(unix))]
+    let result: Result<Vec<CapturableApp>> = unsupported();
+
+    match result {
+        Ok(apps) => {
+            if json {
+                match serde_json::to_string_pretty(&apps) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Failed to encode app list: {}", e);
+                        return 1;
+                    }
+                }
+            } else {
+                println!("{}", format_apps(&apps));
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            1
+        }
+    }
+}
+
+/// `crowd-cast-agent test-upload`: have the running agent upload a throwaway object
+/// and report whether its endpoint and credentials work.
+pub fn run_test_upload_command() -> i32 {
+    #[cfg(unix)]
+    let result = with_running_agent(|path| async move { test_upload(&path).await });
+    #[cfg(not(unix))]
+    let result: Result<()> = unsupported();
+
+    match result {
+        Ok(()) => {
+            println!("Test upload succeeded");
+            0
+        }
+        Err(e) => {
+            eprintln!("Test upload failed: {:#}", e);
+            1
+        }
+    }
+}
+
+/// `crowd-cast-agent marker <label>`: add a marker to the running agent's recording.
+pub fn run_marker_command(label: &str) -> i32 {
+    let label = label.trim();
+    if label.is_empty() || label.contains(['\n', '\r']) {
+        eprintln!("Usage: crowd-cast-agent marker <label> (a single non-empty line)");
+        return 2;
+    }
+
+    #[cfg(unix)]
+    let result = with_running_agent(|path| async move { add_marker_request(&path, label).await });
+    #[cfg(not(unix))]
+    let result: Result<()> = unsupported();
+
+    match result {
+        Ok(()) => {
+            println!("Marker added: {}", label);
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to add marker: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Run a control-socket client against the running agent's socket.
+#[cfg(unix)]
+fn with_running_agent<T, F>(client: impl FnOnce(PathBuf) -> F) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    let path = socket_path().ok_or_else(|| anyhow::anyhow!("No data directory available"))?;
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(client(path))
+}
+
+#[cfg(not(unix))]
+fn unsupported<T>() -> Result<T> {
+    anyhow::bail!("Control commands are not supported on this platform yet")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    fn snapshot() -> StatusSnapshot {
+        StatusSnapshot {
+            recording: true,
+            paused: false,
+            status: "Capturing".to_string(),
+            session_id: Some("session-123".to_string()),
+            pending_uploads: 4,
+            uploads_circuit_open: false,
+            failed_apps: vec!["com.microsoft.VSCode".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn status_client_parses_fake_server_reply() {
+        let path = std::env::temp_dir().join(format!("crowd-cast-ctl-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let reply = serde_json::to_string(&snapshot()).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut request = String::new();
+            BufReader::new(read).read_line(&mut request).await.unwrap();
+            write
+                .write_all(format!("{}\n", reply).as_bytes())
+                .await
+                .unwrap();
+            request
+        });
+
+        let status = query_status(&path).await.unwrap();
+        assert_eq!(server.await.unwrap().trim(), STATUS_REQUEST);
+        assert_eq!(status, snapshot());
+        let text = format_status(&status);
+        assert!(text.contains("Status:          Capturing"));
+        assert!(text.contains("Recording:       yes"));
+        assert!(text.contains("Session:         session-123"));
+        assert!(text.contains("Not capturing:   com.microsoft.VSCode"));
+        assert!(text.contains("Pending uploads: 4"));
+
+        // Nothing listening any more: the client fails instead of hanging.
+        let _ = std::fs::remove_file(&path);
+        assert!(query_status(&path).await.is_err());
+    }
+
+    #[test]
+    fn app_list_marks_targeted_apps() {
+        let apps = [
+            CapturableApp {
+                bundle_id: "com.apple.Safari".to_string(),
+                name: "Safari".to_string(),
+                pid: 42,
+                window_title: Some("Docs".to_string()),
+                targeted: true,
+            },
+            CapturableApp {
+                bundle_id: "com.apple.Terminal".to_string(),
+                name: "Terminal".to_string(),
+                pid: 7,
+                window_title: None,
+                targeted: false,
+            },
+        ];
+        assert_eq!(
+            format_apps(&apps),
+            "* Safari (com.apple.Safari, pid 42) - Docs\n  Terminal (com.apple.Terminal, pid 7)"
+        );
+        assert_eq!(format_apps(&[]), "No capturable apps running");
+    }
+}
@@ -1,6 +1,7 @@
 //! Input event data structures
 
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// Serialized app_id used when recording is active but the frontmost app is filtered out.
 pub const UNCAPTURED_APP_ID: &str = "UNCAPTURED";
@@ -10,11 +11,154 @@ pub const UNKNOWN_APP_ID: &str = "UNKNOWN";
 /// A single input event (keyboard or mouse)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputEvent {
-    /// Timestamp in microseconds since session start
+    /// Timestamp in microseconds since the segment's video started (see `Timebase`)
     pub timestamp_us: u64,
 
     /// The type of event
     pub event: EventType,
+
+    /// Wall-clock time the backend saw the event, in Unix epoch microseconds, for
+    /// correlating with other logs. Never rewritten onto the video clock; ordering and
+    /// alignment use `timestamp_us`. 0 in logs recorded before this field existed.
+    ///
+    /// NOTE: keylogs are msgpack POSITIONAL arrays — this is index 2 and must stay
+    /// before `modifiers`.
+    #[serde(default)]
+    pub absolute_us: u64,
+
+    /// Modifier keys and mouse buttons held when the event happened (`ModifierState`
+    /// bits). A press already includes its own bit; a release no longer does. 0 for
+    /// events not from the key/mouse backends (context, metadata, gestures) and in logs
+    /// recorded before this field existed.
+    ///
+    /// NOTE: positional index 3 in the msgpack wire format — must stay before `seq`.
+    #[serde(default)]
+    pub modifiers: u32,
+
+    /// Position in the input backend's event stream (`EventSequence`), assigned before
+    /// filtering, so events dropped anywhere downstream (filters, backpressure, a crash)
+    /// leave a gap. Events a filter derives from another (chords, redaction markers) share
+    /// its number. 0 for events not from a backend and in logs recorded before this
+    /// field existed.
+    ///
+    /// NOTE: positional index 4 in the msgpack wire format — must stay last.
+    #[serde(default)]
+    pub seq: u64,
+}
+
+impl InputEvent {
+    /// An event happening now: `absolute_us` is stamped from the wall clock.
+    pub fn now(timestamp_us: u64, event: EventType) -> Self {
+        Self {
+            timestamp_us,
+            event,
+            absolute_us: unix_epoch_us(),
+            modifiers: 0,
+            seq: 0,
+        }
+    }
+}
+
+/// Counter the input backends stamp onto each event as `InputEvent::seq`. Numbering
+/// starts at 1 (0 means "not from a backend"). Atomic so capture threads for separate
+/// devices and taps share one stream.
+#[derive(Debug, Default)]
+pub struct EventSequence(AtomicU64);
+
+impl EventSequence {
+    /// The next sequence number
+    pub fn next(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Running modifier-key / mouse-button mask that the input backends stamp onto each event
+/// as `InputEvent::modifiers`, so consumers don't have to replay presses and releases to
+/// know what was held. Atomic so capture threads for separate devices (e.g. evdev's
+/// keyboard and mouse) share one state.
+#[derive(Debug, Default)]
+pub struct ModifierState(AtomicU32);
+
+impl ModifierState {
+    pub const SHIFT_LEFT: u32 = 1 << 0;
+    pub const SHIFT_RIGHT: u32 = 1 << 1;
+    pub const CONTROL_LEFT: u32 = 1 << 2;
+    pub const CONTROL_RIGHT: u32 = 1 << 3;
+    /// Left Alt / Option
+    pub const ALT: u32 = 1 << 4;
+    /// Right Alt / Option
+    pub const ALT_GR: u32 = 1 << 5;
+    /// Left Command / Windows / Super
+    pub const META_LEFT: u32 = 1 << 6;
+    pub const META_RIGHT: u32 = 1 << 7;
+    pub const FUNCTION: u32 = 1 << 8;
+    pub const BUTTON_LEFT: u32 = 1 << 16;
+    pub const BUTTON_RIGHT: u32 = 1 << 17;
+    pub const BUTTON_MIDDLE: u32 = 1 << 18;
+    /// `MouseButton::Other(n)` sets bit `19 + n` (buttons past bit 31 aren't tracked)
+    const BUTTON_OTHER_SHIFT: u32 = 19;
+
+    /// Apply `event`'s press or release to the mask and return the mask to attach to it.
+    pub fn apply(&self, event: &EventType) -> u32 {
+        let (bit, pressed) = match event {
+            EventType::KeyPress(key) => (Self::key_bit(key.code), true),
+            EventType::KeyRelease(key) => (Self::key_bit(key.code), false),
+            EventType::MousePress(button) => (Self::button_bit(button.button), true),
+            EventType::MouseRelease(button) => (Self::button_bit(button.button), false),
+            _ => (0, false),
+        };
+        if bit == 0 {
+            self.0.load(Ordering::Relaxed)
+        } else if pressed {
+            self.0.fetch_or(bit, Ordering::Relaxed) | bit
+        } else {
+            self.0.fetch_and(!bit, Ordering::Relaxed) & !bit
+        }
+    }
+
+    /// Whether a `KeyEvent::code` is one of the tracked modifier keys
+    pub fn is_modifier_key(code: u32) -> bool {
+        Self::key_bit(code) != 0
+    }
+
+    /// Bit for a `KeyEvent::code` (codes are shared across backends), or 0
+    fn key_bit(code: u32) -> u32 {
+        match code {
+            30 => Self::SHIFT_LEFT,
+            31 => Self::SHIFT_RIGHT,
+            4 => Self::CONTROL_LEFT,
+            5 => Self::CONTROL_RIGHT,
+            0 => Self::ALT,
+            1 => Self::ALT_GR,
+            24 => Self::META_LEFT,
+            25 => Self::META_RIGHT,
+            104 => Self::FUNCTION,
+            _ => 0,
+        }
+    }
+
+    fn button_bit(button: MouseButton) -> u32 {
+        match button {
+            MouseButton::Left => Self::BUTTON_LEFT,
+            MouseButton::Right => Self::BUTTON_RIGHT,
+            MouseButton::Middle => Self::BUTTON_MIDDLE,
+            MouseButton::Other(n) => 1u32
+                .checked_shl(Self::BUTTON_OTHER_SHIFT + n as u32)
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// `time` as Unix epoch microseconds (0 for times before the epoch).
+pub fn epoch_us(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// The current wall-clock time as Unix epoch microseconds.
+pub fn unix_epoch_us() -> u64 {
+    epoch_us(std::time::SystemTime::now())
 }
 
 /// Type of input event
@@ -49,6 +193,69 @@ pub enum EventType {
     /// field). Carries no key content; marks where suppression began so post-processing
     /// sees a labeled gap rather than a silent hole.
     Redacted(RedactedEvent),
+
+    /// Trackpad gesture (pinch, rotate, swipe). macOS only; never emitted elsewhere.
+    Gesture(GestureEvent),
+
+    /// The main display's backing scale factor changed mid-recording (e.g. a
+    /// Retina/HiDPI scaling switch). The scale at segment start is in `Metadata`.
+    DisplayScaleChanged(DisplayScaleEvent),
+
+    /// Operator-supplied label (e.g. "start of task X") inserted at runtime via the
+    /// tray or the control socket, at the timeline position it was added.
+    Marker(MarkerEvent),
+
+    /// The frontmost app's focused window moved or was resized. Logged at segment start
+    /// and on every change while `input.capture_window_geometry` is on. macOS only.
+    WindowGeometry(WindowGeometryEvent),
+
+    /// Text an input method committed into the focused field (e.g. the kanji a Japanese
+    /// IME composed from the key events before it), while `input.capture_text_commits` is
+    /// on. macOS only.
+    TextCommit(TextCommitEvent),
+
+    /// A key pressed while Control, Alt or Meta was held (e.g. Cmd+S), logged right after
+    /// that key's press, or in place of it with `input.chords_replace_keys`. Only while
+    /// `input.emit_chords` is on.
+    Chord(ChordEvent),
+
+    /// Peak level of the captured audio since the previous sample, logged every health
+    /// check while `recording.log_audio_levels` is on and audio is captured.
+    AudioLevel(AudioLevelEvent),
+
+    /// The agent's CPU and memory use over the segment (OBS runs inside the agent, so
+    /// this includes capture and encoding), logged at the end of each segment while
+    /// `recording.log_resource_usage` is on.
+    ResourceUsage(ResourceUsageEvent),
+}
+
+impl EventType {
+    /// Fill in the normalized position of click and scroll events from their raw
+    /// position and the capture canvas `(width, height)`. Mouse moves carry only
+    /// deltas and other events no position, so they are left alone, as is everything
+    /// while the canvas size or the pointer position is unknown.
+    pub fn normalize_coordinates(&mut self, (width, height): (u32, u32)) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let normalize = |(x, y): (f64, f64)| (x / width as f64, y / height as f64);
+        match self {
+            EventType::MousePress(e) | EventType::MouseRelease(e) => {
+                e.normalized = e.position().map(normalize);
+            }
+            EventType::MouseScroll(e) => e.normalized = e.position().map(normalize),
+            _ => {}
+        }
+    }
+
+    /// Where the pointer was for a click or scroll, when the backend could tell
+    pub fn pointer_position(&self) -> Option<(f64, f64)> {
+        match self {
+            EventType::MousePress(e) | EventType::MouseRelease(e) => e.position(),
+            EventType::MouseScroll(e) => e.position(),
+            _ => None,
+        }
+    }
 }
 
 /// Frontmost application context at a point in time
@@ -66,6 +273,11 @@ pub struct KeyEvent {
 
     /// Key name (e.g., "KeyA", "Enter", "ShiftLeft")
     pub name: String,
+
+    /// A press generated by the key being held down (auto-repeat) rather than a new
+    /// keystroke. Always false on releases and in older recordings.
+    #[serde(default)]
+    pub is_repeat: bool,
 }
 
 /// Mouse button event data
@@ -74,11 +286,25 @@ pub struct MouseButtonEvent {
     /// Button identifier
     pub button: MouseButton,
 
-    /// X coordinate at time of click
-    pub x: f64,
+    /// X coordinate at time of click; `None` when the backend can't tell where the
+    /// pointer is (evdev, or rdev before the pointer first moves). Older recordings
+    /// wrote 0 for an unknown position.
+    pub x: Option<f64>,
+
+    /// Y coordinate at time of click; `None` like `x`
+    pub y: Option<f64>,
+
+    /// `(x, y)` as fractions of the capture canvas, when `input.normalize_coordinates`
+    /// is enabled; `None` otherwise and in older recordings.
+    #[serde(default)]
+    pub normalized: Option<(f64, f64)>,
+}
 
-    /// Y coordinate at time of click
-    pub y: f64,
+impl MouseButtonEvent {
+    /// `(x, y)`, when the backend knew where the pointer was
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
 }
 
 /// Mouse button identifier
@@ -109,11 +335,121 @@ pub struct MouseScrollEvent {
     /// Vertical scroll delta
     pub delta_y: i64,
 
-    /// X coordinate at time of scroll
-    pub x: f64,
+    /// X coordinate at time of scroll; `None` when the backend can't tell where the
+    /// pointer is (evdev, or rdev before the pointer first moves). Older recordings
+    /// wrote 0 for an unknown position.
+    pub x: Option<f64>,
+
+    /// Y coordinate at time of scroll; `None` like `x`
+    pub y: Option<f64>,
+
+    /// `(x, y)` as fractions of the capture canvas, when `input.normalize_coordinates`
+    /// is enabled; `None` otherwise and in older recordings.
+    #[serde(default)]
+    pub normalized: Option<(f64, f64)>,
+
+    /// The deltas are pixels from a continuous device (trackpad, Magic Mouse) rather than
+    /// wheel lines. macOS only; false elsewhere and in older recordings.
+    #[serde(default)]
+    pub precise: bool,
+
+    /// Part of the inertial scrolling that continues after the fingers lift, not direct
+    /// user input. macOS only; false elsewhere and in older recordings.
+    #[serde(default)]
+    pub momentum: bool,
+}
+
+impl MouseScrollEvent {
+    /// `(x, y)`, when the backend knew where the pointer was
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
+}
+
+/// Trackpad gesture event data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GestureEvent {
+    /// Which gesture produced the event
+    pub kind: GestureKind,
+
+    /// Per-event change as reported by the OS: magnification delta for `Magnify`,
+    /// degrees for `Rotate`, signed swipe progress (sign = direction) for `Swipe`
+    pub magnitude: f64,
+}
+
+/// Display scale factor change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayScaleEvent {
+    /// New backing scale factor (pixels per point; 1.0 = unscaled)
+    pub scale: f64,
+}
+
+/// Runtime annotation of the recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkerEvent {
+    /// Free-form label
+    pub label: String,
+}
+
+/// Text committed by an input method (IME composition, dictation, accented characters)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextCommitEvent {
+    /// The committed text
+    pub text: String,
+}
+
+/// A modifier + key combination
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChordEvent {
+    /// Held modifiers (`Control`, `Alt`, `Shift`, `Meta`, `Fn`, in that order, sides
+    /// merged) followed by the completing key's name, e.g. `["Meta", "Shift", "KeyS"]`
+    pub keys: Vec<String>,
+}
+
+/// Periodic audio level sample
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AudioLevelEvent {
+    /// Peak level in dBFS (0 = full scale), floored at -96 for silence
+    pub db: f32,
+}
+
+/// Summary of the agent process's resource use over one segment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsageEvent {
+    /// Number of samples summarized (one per health check)
+    pub samples: u32,
+
+    /// CPU use in percent of one core (above 100 when several cores are busy)
+    pub cpu_percent_min: f32,
+    pub cpu_percent_avg: f32,
+    pub cpu_percent_max: f32,
+
+    /// Resident memory in bytes
+    pub memory_bytes_min: u64,
+    pub memory_bytes_avg: u64,
+    pub memory_bytes_max: u64,
+}
+
+/// Frame of the focused window, in POINTS in the global virtual-desktop space (same
+/// coordinate space as `MonitorInfo` bounds)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometryEvent {
+    /// Global top-left X of the window
+    pub x: i32,
+    /// Global top-left Y of the window
+    pub y: i32,
+    /// Window width
+    pub width: i32,
+    /// Window height
+    pub height: i32,
+}
 
-    /// Y coordinate at time of scroll
-    pub y: f64,
+/// Trackpad gesture kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GestureKind {
+    Magnify,
+    Rotate,
+    Swipe,
 }
 
 /// A connected display's identity + geometry, for reconstructing the multi-monitor spatial
@@ -211,6 +547,22 @@ pub struct MetadataEvent {
     /// NOTE: positional index 13 in the msgpack wire format — must stay after `platform`.
     #[serde(default)]
     pub capture_mode: String,
+
+    /// Backing scale factor of the main display (pixels per point, e.g. 2.0 on Retina), or
+    /// 0.0 when unknown or for recordings made before this field existed. Later changes
+    /// within the segment are logged as `DisplayScaleChanged`.
+    ///
+    /// NOTE: positional index 14 in the msgpack wire format — must stay after `capture_mode`.
+    #[serde(default)]
+    pub display_scale: f64,
+
+    /// OBS id of the video encoder recording the segment (e.g. "jim_hevc_nvenc",
+    /// "obs_x264"), as libobs reports it for the segment's output. Empty when unknown or
+    /// for recordings made before this field existed.
+    ///
+    /// NOTE: positional index 15 in the msgpack wire format — must stay after `display_scale`.
+    #[serde(default)]
+    pub video_encoder: String,
 }
 
 /// Marker emitted when secure-input gating begins withholding key events.
@@ -353,7 +705,11 @@ impl From<rdev::Key> for KeyEvent {
             rdev::Key::Unknown(code) => (code as u32 + 1000, format!("Unknown({})", code)),
         };
 
-        Self { code, name }
+        Self {
+            code,
+            name,
+            is_repeat: false,
+        }
     }
 }
 
@@ -515,12 +871,14 @@ impl From<evdev::Key> for KeyEvent {
                 return Self {
                     code: key.0 as u32 + 1000,
                     name: format!("Unknown({})", key.0),
+                    is_repeat: false,
                 };
             }
         };
         Self {
             code,
             name: name.to_string(),
+            is_repeat: false,
         }
     }
 }
@@ -554,13 +912,205 @@ mod tests {
     use super::*;
 
     #[test]
-    fn context_changed_msgpack_roundtrip() {
+    fn absolute_time_rides_alongside_video_time() {
+        let before = unix_epoch_us();
+        let event = InputEvent::now(
+            1_500_000,
+            EventType::KeyPress(KeyEvent {
+                code: 30,
+                name: "KeyA".to_string(),
+                is_repeat: false,
+            }),
+        );
+        let after = unix_epoch_us();
+        assert!((before..=after).contains(&event.absolute_us));
+        // Plausibly "now", not 0 or a monotonic-clock reading: after 2020-01-01.
+        assert!(event.absolute_us > 1_577_836_800_000_000);
+
+        let bytes = rmp_serde::to_vec(&event).unwrap();
+        let decoded: InputEvent = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.timestamp_us, 1_500_000);
+        assert_eq!(decoded.absolute_us, event.absolute_us);
+        // Positional wire format: absolute_us is index 2.
+        let (timestamp_us, _, absolute_us, _, _): (u64, EventType, u64, u32, u64) =
+            rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!((timestamp_us, absolute_us), (1_500_000, event.absolute_us));
+
+        // Keylogs from before the field existed still decode, with absolute time unknown.
+        let old_bytes = rmp_serde::to_vec(&(7u64, event.event.clone())).unwrap();
+        let old: InputEvent = rmp_serde::from_slice(&old_bytes).unwrap();
+        assert_eq!(old.timestamp_us, 7);
+        assert_eq!(old.absolute_us, 0);
+    }
+
+    fn key(code: u32, name: &str) -> KeyEvent {
+        KeyEvent {
+            code,
+            name: name.to_string(),
+            is_repeat: false,
+        }
+    }
+
+    #[test]
+    fn key_event_carries_held_shift() {
+        let state = ModifierState::default();
+        let shift = key(30, "ShiftLeft");
+        let a = key(64, "KeyA");
+
+        // The press already carries its own bit; the key typed while it is down sees it.
+        assert_eq!(
+            state.apply(&EventType::KeyPress(shift.clone())),
+            ModifierState::SHIFT_LEFT
+        );
+        assert_eq!(
+            state.apply(&EventType::KeyPress(a.clone())),
+            ModifierState::SHIFT_LEFT
+        );
+        let click = MouseButtonEvent {
+            button: MouseButton::Left,
+            x: None,
+            y: None,
+            normalized: None,
+        };
+        assert_eq!(
+            state.apply(&EventType::MousePress(click.clone())),
+            ModifierState::SHIFT_LEFT | ModifierState::BUTTON_LEFT
+        );
+        assert_eq!(
+            state.apply(&EventType::MouseRelease(click)),
+            ModifierState::SHIFT_LEFT
+        );
+
+        // The release no longer does, nor does anything after it.
+        assert_eq!(state.apply(&EventType::KeyRelease(shift)), 0);
+        assert_eq!(state.apply(&EventType::KeyPress(a)), 0);
+
         let event = InputEvent {
-            timestamp_us: 42,
-            event: EventType::ContextChanged(ContextEvent {
-                app_id: UNCAPTURED_APP_ID.to_string(),
+            modifiers: ModifierState::SHIFT_LEFT,
+            ..InputEvent::now(3, EventType::KeyPress(key(64, "KeyA")))
+        };
+        let bytes = rmp_serde::to_vec(&event).unwrap();
+        let (_, _, _, modifiers, _): (u64, EventType, u64, u32, u64) =
+            rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(modifiers, ModifierState::SHIFT_LEFT);
+    }
+
+    #[test]
+    fn key_repeat_flag_round_trips() {
+        let repeat = KeyEvent {
+            is_repeat: true,
+            ..key(64, "KeyA")
+        };
+        let bytes = rmp_serde::to_vec(&EventType::KeyPress(repeat)).unwrap();
+        let decoded: EventType = rmp_serde::from_slice(&bytes).unwrap();
+        let EventType::KeyPress(decoded) = decoded else {
+            unreachable!()
+        };
+        assert!(decoded.is_repeat);
+        assert_eq!((decoded.code, decoded.name.as_str()), (64, "KeyA"));
+
+        let json = serde_json::to_value(key(64, "KeyA")).unwrap();
+        assert_eq!(json["is_repeat"], false);
+
+        // Keylogs from before the flag existed decode every press as a distinct one.
+        let old_bytes = rmp_serde::to_vec(&(64u32, "KeyA")).unwrap();
+        let old: KeyEvent = rmp_serde::from_slice(&old_bytes).unwrap();
+        assert!(!old.is_repeat);
+        assert_eq!(old.code, 64);
+    }
+
+    #[test]
+    fn click_normalized_to_canvas_fraction() {
+        let mut event = EventType::MousePress(MouseButtonEvent {
+            button: MouseButton::Left,
+            x: Some(960.0),
+            y: Some(540.0),
+            normalized: None,
+        });
+        event.normalize_coordinates((1920, 1080));
+        let EventType::MousePress(click) = &event else {
+            unreachable!()
+        };
+        assert_eq!(click.normalized, Some((0.5, 0.5)));
+        // The raw position is kept.
+        assert_eq!((click.x, click.y), (Some(960.0), Some(540.0)));
+
+        // Unknown position: nothing to normalize, rather than a made-up (0, 0).
+        let mut unplaced = EventType::MouseRelease(MouseButtonEvent {
+            button: MouseButton::Left,
+            x: None,
+            y: None,
+            normalized: None,
+        });
+        unplaced.normalize_coordinates((1920, 1080));
+        assert_eq!(unplaced.pointer_position(), None);
+        assert!(matches!(
+            unplaced,
+            EventType::MouseRelease(MouseButtonEvent {
+                normalized: None,
+                ..
+            })
+        ));
+
+        // Unknown canvas: nothing to normalize against.
+        let mut unknown = EventType::MouseScroll(MouseScrollEvent {
+            delta_x: 0,
+            delta_y: -1,
+            x: Some(960.0),
+            y: Some(540.0),
+            normalized: None,
+            precise: false,
+            momentum: false,
+        });
+        unknown.normalize_coordinates((0, 0));
+        assert!(matches!(
+            unknown,
+            EventType::MouseScroll(MouseScrollEvent {
+                normalized: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn scroll_flags_roundtrip_and_default_for_older_logs() {
+        let event = InputEvent::now(
+            7,
+            EventType::MouseScroll(MouseScrollEvent {
+                delta_x: 1,
+                delta_y: -24,
+                x: None,
+                y: None,
+                normalized: None,
+                precise: true,
+                momentum: true,
             }),
+        );
+        let decoded: InputEvent =
+            rmp_serde::from_slice(&rmp_serde::to_vec(&event).unwrap()).unwrap();
+        let EventType::MouseScroll(scroll) = decoded.event else {
+            panic!("expected MouseScroll, got {:?}", decoded.event)
         };
+        assert_eq!((scroll.delta_x, scroll.delta_y), (1, -24));
+        assert!(scroll.precise && scroll.momentum);
+
+        // A scroll written before the flags existed: [delta_x, delta_y, x, y, normalized]
+        let old =
+            rmp_serde::to_vec(&(0i64, -3i64, 540.0f64, 320.0f64, None::<(f64, f64)>)).unwrap();
+        let scroll: MouseScrollEvent = rmp_serde::from_slice(&old).unwrap();
+        assert_eq!(scroll.delta_y, -3);
+        assert_eq!(scroll.position(), Some((540.0, 320.0)));
+        assert!(!scroll.precise && !scroll.momentum);
+    }
+
+    #[test]
+    fn context_changed_msgpack_roundtrip() {
+        let event = InputEvent::now(
+            42,
+            EventType::ContextChanged(ContextEvent {
+                app_id: UNCAPTURED_APP_ID.to_string(),
+            }),
+        );
 
         let bytes = rmp_serde::to_vec(&event).unwrap();
         let decoded: InputEvent = rmp_serde::from_slice(&bytes).unwrap();
@@ -595,9 +1145,9 @@ mod tests {
             px_height: 1912,
             is_main: false,
         };
-        let event = InputEvent {
-            timestamp_us: 7,
-            event: EventType::Metadata(MetadataEvent {
+        let event = InputEvent::now(
+            7,
+            EventType::Metadata(MetadataEvent {
                 display_width: 1728,
                 display_height: 1080,
                 display_aspect: 1.6,
@@ -612,8 +1162,10 @@ mod tests {
                 displays: vec![dell.clone(), builtin],
                 platform: "macos".to_string(),
                 capture_mode: "single_active_app".to_string(),
+                display_scale: 2.0,
+                video_encoder: "com.apple.videotoolbox.videoencoder.ave.hevc".to_string(),
             }),
-        };
+        );
         let bytes = rmp_serde::to_vec(&event).unwrap();
         let decoded: InputEvent = rmp_serde::from_slice(&bytes).unwrap();
         match decoded.event {
@@ -650,6 +1202,8 @@ mod tests {
             displays: Vec::new(),
             platform: "linux".to_string(),
             capture_mode: "display".to_string(),
+            display_scale: 1.0,
+            video_encoder: "obs_x264".to_string(),
         };
 
         // Typed roundtrip: the new fields survive encode/decode.
@@ -658,8 +1212,9 @@ mod tests {
         assert_eq!(decoded.platform, "linux");
         assert_eq!(decoded.capture_mode, "display");
 
-        // Positional contract: decode the same bytes as a bare 14-tuple and assert
-        // platform sits at index 12 and capture_mode at index 13.
+        // Positional contract: decode the same bytes as a bare 16-tuple and assert
+        // platform sits at index 12, capture_mode at 13, display_scale at 14 and
+        // video_encoder at 15.
         type MetadataTuple = (
             u32,
             u32,
@@ -675,6 +1230,8 @@ mod tests {
             Vec<MonitorInfo>,
             String,
             String,
+            f64,
+            String,
         );
         let tuple: MetadataTuple = rmp_serde::from_slice(&bytes).unwrap();
         assert_eq!(tuple.12, "linux", "platform must be positional index 12");
@@ -682,6 +1239,11 @@ mod tests {
             tuple.13, "display",
             "capture_mode must be positional index 13"
         );
+        assert_eq!(tuple.14, 1.0, "display_scale must be positional index 14");
+        assert_eq!(
+            tuple.15, "obs_x264",
+            "video_encoder must be positional index 15"
+        );
 
         // Backward compat: a pre-fix 12-element array (no platform/capture_mode)
         // still decodes, with both new fields defaulting to "".
@@ -704,6 +1266,68 @@ mod tests {
         assert_eq!(old.display_width, 1920);
         assert_eq!(old.platform, "");
         assert_eq!(old.capture_mode, "");
+        assert_eq!(old.display_scale, 0.0);
+        assert_eq!(old.video_encoder, "");
+    }
+
+    #[test]
+    fn gesture_msgpack_roundtrip() {
+        for (kind, magnitude) in [
+            (GestureKind::Magnify, 0.125),
+            (GestureKind::Rotate, -12.5),
+            (GestureKind::Swipe, 1.0),
+        ] {
+            let event = InputEvent::now(99, EventType::Gesture(GestureEvent { kind, magnitude }));
+            let bytes = rmp_serde::to_vec(&event).unwrap();
+            let decoded: InputEvent = rmp_serde::from_slice(&bytes).unwrap();
+            match decoded.event {
+                EventType::Gesture(g) => {
+                    assert_eq!(g.kind, kind);
+                    assert_eq!(g.magnitude, magnitude);
+                }
+                other => panic!("unexpected event after roundtrip: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn gesture_json_shape() {
+        let event = EventType::Gesture(GestureEvent {
+            kind: GestureKind::Rotate,
+            magnitude: 3.0,
+        });
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "Gesture",
+                "data": { "kind": "Rotate", "magnitude": 3.0 }
+            })
+        );
+    }
+
+    #[test]
+    fn text_commit_roundtrip_and_json_shape() {
+        let event = InputEvent::now(
+            42,
+            EventType::TextCommit(TextCommitEvent {
+                text: "日本語".to_string(),
+            }),
+        );
+        let bytes = rmp_serde::to_vec(&event).unwrap();
+        let decoded: InputEvent = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.timestamp_us, 42);
+        match decoded.event {
+            EventType::TextCommit(commit) => assert_eq!(commit.text, "日本語"),
+            other => panic!("unexpected event after roundtrip: {:?}", other),
+        }
+
+        assert_eq!(
+            serde_json::to_value(&event.event).unwrap(),
+            serde_json::json!({
+                "type": "TextCommit",
+                "data": { "text": "日本語" }
+            })
+        );
     }
 }
 
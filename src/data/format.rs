@@ -2,8 +2,9 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
-use super::InputEvent;
+use super::{EventType, InputEvent};
 
 /// A chunk of input events associated with a video chunk
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,24 +113,223 @@ pub struct CompletedChunk {
 
     /// End timestamp (microseconds)
     pub end_time_us: u64,
+
+    /// Clock the `timestamp_us` of `events` (and `start_time_us`/`end_time_us`) are on
+    #[serde(default)]
+    pub timebase: Timebase,
+
+    /// OBS video-clock time (nanoseconds) that `timestamp_us == 0` corresponds to, when
+    /// `timebase` is `VideoRelativeUs`. Already advanced past any paused spans, so
+    /// `timestamp_us == (obs_frame_time_ns - video_start_ns) / 1000` holds for every
+    /// event and an event at `timestamp_us` lines up with the video at that offset.
+    /// With `MonotonicUs`, the OBS video-clock time the video started at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_start_ns: Option<u64>,
+
+    /// With `MonotonicUs`, the video offset (microseconds past `video_start_ns` on the
+    /// OBS video clock) that `timestamp_us == 0` corresponds to. Both clocks are read
+    /// together when the segment starts, so an event lines up with the video at
+    /// `timestamp_us + video_offset_us`, less the length of any frame clock stall before
+    /// it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_offset_us: Option<u64>,
+
+    /// Sanity figures sent along with the upload
+    #[serde(default)]
+    pub stats: SegmentStats,
 }
 
+/// Figures that let the upload endpoint reject obviously broken segments, such as a
+/// minute of video with no input or input without any video
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentStats {
+    /// Number of input events in the segment
+    pub event_count: u64,
+    /// `end_time_us - start_time_us`
+    pub duration_us: u64,
+    /// Size of the segment's video (with an HLS playlist's pieces); 0 without one
+    pub video_bytes: u64,
+}
+
+impl SegmentStats {
+    /// Stats for `events` spanning `start_time_us..end_time_us`, next to `video_bytes`
+    /// of video
+    pub fn new(
+        events: &[InputEvent],
+        start_time_us: u64,
+        end_time_us: u64,
+        video_bytes: u64,
+    ) -> Self {
+        Self {
+            event_count: events.len() as u64,
+            duration_us: end_time_us.saturating_sub(start_time_us),
+            video_bytes,
+        }
+    }
+}
+
+/// Timebase of a chunk's input event timestamps — the keylog/video sync contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Timebase {
+    /// Microseconds since the segment's video started, on OBS's video frame clock with
+    /// paused spans removed: the video's own timeline. Every recorded segment uses this.
+    #[default]
+    VideoRelativeUs,
+
+    /// Raw input-backend microseconds; no recording clock was available to rebase onto.
+    BackendUs,
+
+    /// Microseconds since the segment's video started, on the agent's monotonic clock
+    /// with paused spans removed (`recording.clock_source = "monotonic"`). Keeps advancing
+    /// while the video frame clock stalls, so it can run ahead of the video by the length
+    /// of a stall.
+    MonotonicUs,
+}
+
+impl Timebase {
+    /// Timebase and video start for events rebased against `recording_start_ns`
+    /// (`None` when no recording clock was running).
+    pub fn for_recording_start(recording_start_ns: Option<u64>) -> (Self, Option<u64>) {
+        match recording_start_ns {
+            Some(start_ns) => (Self::VideoRelativeUs, Some(start_ns)),
+            None => (Self::BackendUs, None),
+        }
+    }
+}
+
+/// Schema version of the input logs this build writes (`InputLog::schema_version`). Bump
+/// it whenever an `InputEvent` change would break older readers.
+pub const INPUT_LOG_SCHEMA_VERSION: u32 = 1;
+
+/// On-disk input log (`input_<segment>.msgpack` and partial flushes): the events behind a
+/// small versioned header. Files written before the header existed are a bare
+/// `Vec<InputEvent>`; [`decode_input_log`] reads both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputLog {
+    /// Format of `events` (see `INPUT_LOG_SCHEMA_VERSION`)
+    pub schema_version: u32,
+    /// When the file was written (Unix epoch microseconds)
+    pub created_at: u64,
+    /// The logged events
+    pub events: Vec<InputEvent>,
+}
+
+/// Serialize `events` as a current-version input log
+pub fn encode_input_log(events: &[InputEvent]) -> Result<Vec<u8>> {
+    #[derive(Serialize)]
+    struct InputLogRef<'a> {
+        schema_version: u32,
+        created_at: u64,
+        events: &'a [InputEvent],
+    }
+
+    Ok(rmp_serde::to_vec(&InputLogRef {
+        schema_version: INPUT_LOG_SCHEMA_VERSION,
+        created_at: super::unix_epoch_us(),
+        events,
+    })?)
+}
+
+/// Read an input log's events: the versioned envelope, or a legacy bare event list. Logs
+/// from a newer schema than this build knows are refused rather than misread.
+pub fn decode_input_log(bytes: &[u8]) -> Result<Vec<InputEvent>> {
+    match rmp_serde::from_slice::<InputLog>(bytes) {
+        Ok(log) if log.schema_version > INPUT_LOG_SCHEMA_VERSION => anyhow::bail!(
+            "input log schema version {} is newer than this build supports ({})",
+            log.schema_version,
+            INPUT_LOG_SCHEMA_VERSION
+        ),
+        Ok(log) => Ok(log.events),
+        Err(_) => Ok(rmp_serde::from_slice::<Vec<InputEvent>>(bytes)?),
+    }
+}
+
+/// Most events an `InputEventBuffer` holds before it starts shedding mouse moves. The
+/// engine flushes at 10k, so this is only reached when flushes stall (full disk, slow FS).
+pub const EVENT_BUFFER_CAP: usize = 100_000;
+
 /// Buffer for collecting input events during capture
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct InputEventBuffer {
-    /// The buffered events (public for partial flush access)
-    pub events: Vec<InputEvent>,
+    /// The buffered events
+    events: Vec<InputEvent>,
+    /// Length past which the oldest mouse moves are dropped
+    cap: usize,
+    /// Length past which the next shed runs: the cap, or a quarter cap beyond the length
+    /// a shed couldn't get under it, so a buffer of unsheddable events isn't rescanned on
+    /// every push
+    shed_at: usize,
+    /// Mouse moves currently buffered, i.e. how many events a shed could drop
+    mouse_moves: usize,
+    /// Mouse moves dropped over the cap since the buffer was created
+    dropped: u64,
+}
+
+impl Default for InputEventBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InputEventBuffer {
-    /// Create a new empty buffer
+    /// Create a new empty buffer capped at `EVENT_BUFFER_CAP`
     pub fn new() -> Self {
-        Self { events: Vec::new() }
+        Self::with_cap(EVENT_BUFFER_CAP)
     }
 
-    /// Add an event to the buffer
-    pub fn push(&mut self, event: InputEvent) {
+    /// Create a new empty buffer that sheds mouse moves past `cap` events
+    pub fn with_cap(cap: usize) -> Self {
+        Self {
+            events: Vec::new(),
+            cap: cap.max(1),
+            shed_at: cap.max(1),
+            mouse_moves: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Add an event to the buffer. Once it holds more than the cap, the oldest mouse
+    /// moves are dropped down to three quarters of the cap; keys, clicks and the rest are
+    /// always kept, so the cap is soft. When too few moves are left to get back under the
+    /// cap, the next shed waits for another quarter cap of events. Returns how many events
+    /// were dropped.
+    pub fn push(&mut self, event: InputEvent) -> usize {
+        if matches!(event.event, EventType::MouseMove(_)) {
+            self.mouse_moves += 1;
+        }
         self.events.push(event);
+        if self.events.len() <= self.shed_at {
+            return 0;
+        }
+
+        let mut excess = (self.events.len() - self.cap * 3 / 4).min(self.mouse_moves);
+        let before = self.events.len();
+        if excess > 0 {
+            self.events.retain(|e| {
+                if excess > 0 && matches!(e.event, EventType::MouseMove(_)) {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        let dropped = before - self.events.len();
+        self.mouse_moves -= dropped;
+        self.shed_at = if self.events.len() <= self.cap {
+            self.cap
+        } else {
+            self.events.len() + self.cap / 4
+        };
+        if dropped > 0 {
+            self.dropped += dropped as u64;
+            warn!(
+                "Input event buffer over its cap of {}: dropped {} oldest mouse moves ({} total)",
+                self.cap, dropped, self.dropped
+            );
+        }
+        dropped
     }
 
     /// Get the number of events in the buffer
@@ -144,11 +344,176 @@ impl InputEventBuffer {
 
     /// Clear the buffer
     pub fn clear(&mut self) {
-        self.events.clear();
+        self.drain();
     }
 
     /// Drain all events from the buffer
     pub fn drain(&mut self) -> Vec<InputEvent> {
+        self.shed_at = self.cap;
+        self.mouse_moves = 0;
         std::mem::take(&mut self.events)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{KeyEvent, MouseMoveEvent};
+
+    fn events() -> Vec<InputEvent> {
+        vec![
+            InputEvent::now(
+                10,
+                EventType::KeyPress(KeyEvent {
+                    code: 30,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                }),
+            ),
+            InputEvent::now(
+                20,
+                EventType::KeyRelease(KeyEvent {
+                    code: 30,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                }),
+            ),
+        ]
+    }
+
+    fn timestamps(events: &[InputEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.timestamp_us).collect()
+    }
+
+    #[test]
+    fn input_log_reads_envelope_and_legacy_bare_list() {
+        let bytes = encode_input_log(&events()).unwrap();
+        let log: InputLog = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(log.schema_version, INPUT_LOG_SCHEMA_VERSION);
+        assert!(log.created_at > 0);
+        assert_eq!(timestamps(&decode_input_log(&bytes).unwrap()), [10, 20]);
+
+        // Files written before the envelope are a bare event list.
+        let legacy = rmp_serde::to_vec(&events()).unwrap();
+        assert_eq!(timestamps(&decode_input_log(&legacy).unwrap()), [10, 20]);
+        let empty = rmp_serde::to_vec(&Vec::<InputEvent>::new()).unwrap();
+        assert!(decode_input_log(&empty).unwrap().is_empty());
+        assert!(decode_input_log(&encode_input_log(&[]).unwrap())
+            .unwrap()
+            .is_empty());
+
+        // A newer schema is refused, not misread.
+        let newer = rmp_serde::to_vec(&InputLog {
+            schema_version: INPUT_LOG_SCHEMA_VERSION + 1,
+            created_at: 0,
+            events: events(),
+        })
+        .unwrap();
+        let err = decode_input_log(&newer).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+        assert!(decode_input_log(b"not msgpack").is_err());
+    }
+
+    #[test]
+    fn event_buffer_sheds_oldest_mouse_moves_past_its_cap() {
+        let mut buffer = InputEventBuffer::with_cap(100);
+        for i in 0..10_000u64 {
+            let event = if i % 500 == 0 {
+                EventType::KeyPress(KeyEvent {
+                    code: 30,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                })
+            } else {
+                EventType::MouseMove(MouseMoveEvent {
+                    delta_x: 1.0,
+                    delta_y: 0.0,
+                })
+            };
+            buffer.push(InputEvent::now(i, event));
+            assert!(buffer.len() <= 100);
+        }
+
+        let keys: Vec<u64> = buffer
+            .events
+            .iter()
+            .filter(|e| matches!(e.event, EventType::KeyPress(_)))
+            .map(|e| e.timestamp_us)
+            .collect();
+        assert_eq!(keys, (0..10_000).step_by(500).collect::<Vec<_>>());
+        assert_eq!(buffer.dropped, 10_000 - buffer.len() as u64);
+        assert_eq!(buffer.mouse_moves, buffer.len() - keys.len());
+        // The newest mouse move survives; it is the oldest ones that go.
+        assert_eq!(buffer.events.last().unwrap().timestamp_us, 9_999);
+
+        // Past the cap with nothing left to shed, keys are kept anyway.
+        let mut keys_only = InputEventBuffer::with_cap(10);
+        for i in 0..20 {
+            assert_eq!(keys_only.push(events().remove(0)), 0, "push {}", i);
+        }
+        assert_eq!(keys_only.len(), 20);
+        // With nothing to shed, the next attempt waits for another quarter cap of pushes.
+        assert_eq!(keys_only.shed_at, 20 + 10 / 4);
+        let mv = || {
+            InputEvent::now(
+                0,
+                EventType::MouseMove(MouseMoveEvent {
+                    delta_x: 1.0,
+                    delta_y: 0.0,
+                }),
+            )
+        };
+        assert_eq!(keys_only.push(mv()), 0);
+        assert_eq!(keys_only.push(mv()), 0);
+        assert_eq!(keys_only.push(mv()), 3);
+        assert_eq!(keys_only.len(), 20);
+        keys_only.clear();
+        assert_eq!((keys_only.shed_at, keys_only.mouse_moves), (10, 0));
+    }
+
+    #[test]
+    fn segment_stats_match_the_chunk() {
+        let events = events();
+        let start_time_us = events.first().unwrap().timestamp_us;
+        let end_time_us = events.last().unwrap().timestamp_us;
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0000".to_string(),
+            video_path: None,
+            stats: SegmentStats::new(&events, start_time_us, end_time_us, 4096),
+            events,
+            start_time_us,
+            end_time_us,
+            timebase: Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+        };
+        assert_eq!(
+            chunk.stats,
+            SegmentStats {
+                event_count: 2,
+                duration_us: 10,
+                video_bytes: 4096,
+            }
+        );
+        assert_eq!(chunk.stats.event_count, chunk.events.len() as u64);
+        assert_eq!(
+            chunk.stats.duration_us,
+            chunk.end_time_us - chunk.start_time_us
+        );
+
+        // Video without input: no events, no span.
+        assert_eq!(
+            SegmentStats::new(&[], 0, 0, 4096),
+            SegmentStats {
+                event_count: 0,
+                duration_us: 0,
+                video_bytes: 4096,
+            }
+        );
+
+        let decoded: CompletedChunk =
+            rmp_serde::from_slice(&rmp_serde::to_vec_named(&chunk).unwrap()).unwrap();
+        assert_eq!(decoded.stats, chunk.stats);
+    }
+}
@@ -0,0 +1,195 @@
+//! `crowd-cast-agent diagnostics`: collect what a capture bug report needs into one zip,
+//! so users don't have to find each file themselves.
+//!
+//! The bundle holds the libobs logs (`obs/`), the most recent agent daily logs, the
+//! `crash.log` breadcrumbs (`log_critical_operation` markers, panics, signals) and the
+//! config with secrets and machine-local state left out (`config.toml`).
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+
+/// Agent daily logs to include, newest first
+const AGENT_LOGS: usize = 3;
+
+/// libobs log files to include, newest first
+const OBS_LOGS: usize = 5;
+
+/// `crowd-cast-agent diagnostics [--out <path>]` writes the bundle (by default into the
+/// current directory) and prints where it went. Returns the process exit code.
+pub fn run_diagnostics_command(args: &[String]) -> i32 {
+    const USAGE: &str = "Usage: crowd-cast-agent diagnostics [--out <path>]";
+
+    let out = match args {
+        [] => PathBuf::from(format!(
+            "crowd-cast-diagnostics-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        )),
+        [flag, path] if flag == "--out" => PathBuf::from(path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let result = crate::logging::get_log_dir().and_then(|log_dir| {
+        let config = match Config::load().and_then(|c| c.to_template(true)) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                eprintln!("Leaving the config out: {:#}", e);
+                None
+            }
+        };
+        write_bundle(
+            &out,
+            &log_dir,
+            crate::capture::obs_log_dir().as_deref(),
+            config.as_deref(),
+        )
+    });
+
+    match result {
+        Ok(entries) => {
+            println!(
+                "Wrote {} file(s) to {:?}; attach it to your bug report",
+                entries.len(),
+                out
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            1
+        }
+    }
+}
+
+/// Write the bundle to `out` from the agent log directory, the libobs log directory and
+/// an already-redacted config. Missing sources are skipped. Returns the entry names.
+fn write_bundle(
+    out: &Path,
+    log_dir: &Path,
+    obs_log_dir: Option<&Path>,
+    redacted_config: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut files: Vec<(String, PathBuf)> = newest_files(log_dir, AGENT_LOGS, |name| {
+        name.starts_with("crowd-cast.log")
+    })
+    .into_iter()
+    .map(|path| (entry_name("", &path), path))
+    .collect();
+
+    let crash_log = log_dir.join("crash.log");
+    if crash_log.is_file() {
+        files.push(("crash.log".to_string(), crash_log));
+    }
+
+    if let Some(obs_log_dir) = obs_log_dir {
+        for path in newest_files(obs_log_dir, OBS_LOGS, |name| name.ends_with(".txt")) {
+            files.push((entry_name("obs/", &path), path));
+        }
+    }
+
+    let file = std::fs::File::create(out).with_context(|| format!("Failed to create {:?}", out))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+    let mut entries = Vec::new();
+
+    for (name, path) in files {
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&bytes)?;
+        entries.push(name);
+    }
+
+    if let Some(config) = redacted_config {
+        zip.start_file("config.toml", options)?;
+        zip.write_all(config.as_bytes())?;
+        entries.push("config.toml".to_string());
+    }
+
+    zip.finish()?;
+    Ok(entries)
+}
+
+fn entry_name(prefix: &str, path: &Path) -> String {
+    format!(
+        "{}{}",
+        prefix,
+        path.file_name().unwrap_or_default().to_string_lossy()
+    )
+}
+
+/// Up to `limit` files in `dir` whose name passes `keep`, most recently modified first
+fn newest_files(dir: &Path, limit: usize, keep: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(&keep))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            Some((metadata.modified().ok()?, entry.path()))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_collects_logs_crash_breadcrumbs_and_config() {
+        let root = std::env::temp_dir().join(format!("crowd-cast-diag-{}", std::process::id()));
+        let log_dir = root.join("logs");
+        let obs_dir = root.join("obs-logs");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        std::fs::create_dir_all(&obs_dir).unwrap();
+
+        std::fs::write(log_dir.join("crowd-cast.log.2026-10-16"), "agent log").unwrap();
+        std::fs::write(log_dir.join("crash.log"), "CRITICAL_OP: start_recording").unwrap();
+        std::fs::write(log_dir.join("perf-sampler.csv"), "not a log").unwrap();
+        std::fs::write(obs_dir.join("2026-10-16 09-00-00.txt"), "libobs log").unwrap();
+
+        let out = root.join("bundle.zip");
+        let entries = write_bundle(&out, &log_dir, Some(&obs_dir), Some("[capture]\n")).unwrap();
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "config.toml",
+                "crash.log",
+                "crowd-cast.log.2026-10-16",
+                "obs/2026-10-16 09-00-00.txt",
+            ]
+        );
+        assert_eq!(entries.len(), 4);
+
+        let mut crash = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("crash.log").unwrap(), &mut crash).unwrap();
+        assert!(crash.contains("start_recording"));
+
+        // Without an OBS log dir or a loadable config, the rest still bundles.
+        let entries = write_bundle(&out, &log_dir, None, None).unwrap();
+        assert_eq!(entries, ["crowd-cast.log.2026-10-16", "crash.log"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -27,7 +27,14 @@ pub trait InputBackend: Send + Sync {
 /// Linux uses evdev for both X11 and Wayland: raw pre-acceleration deltas, reaches the
 /// same input layer raw-input consumers read, and works regardless of display server.
 /// rdev is not linked on Linux (see Cargo.toml). macOS/Windows use rdev.
-pub fn create_input_backend(secure: Arc<SecureInputState>) -> Result<Box<dyn InputBackend>> {
+///
+/// `capture_gestures` adds trackpad gesture capture and `capture_text_commits` IME text
+/// commit capture on macOS; other platforms ignore both.
+pub fn create_input_backend(
+    secure: Arc<SecureInputState>,
+    capture_gestures: bool,
+    capture_text_commits: bool,
+) -> Result<Box<dyn InputBackend>> {
     #[cfg(target_os = "linux")]
     {
         // No fallback by design: crowd-cast exists to record input, so a backend that can't
@@ -35,6 +42,7 @@ pub fn create_input_backend(secure: Arc<SecureInputState>) -> Result<Box<dyn Inp
         // silently dropping every keystroke. Startup gates on 'input' group membership (see
         // installer::requirements), so evdev should succeed by the time we get here; if it
         // still fails, fail closed and loud rather than degrade to recording no input.
+        let _ = (capture_gestures, capture_text_commits);
         let backend = super::evdev_backend::EvdevBackend::new(secure).context(
             "evdev input backend init failed -- ensure the user is in the 'input' group",
         )?;
@@ -48,6 +56,10 @@ pub fn create_input_backend(secure: Arc<SecureInputState>) -> Result<Box<dyn Inp
         // (e.g. macOS Secure Event Input), so the shared gate is inert here.
         let _ = secure;
         tracing::info!("Using rdev backend for input capture");
-        Ok(Box::new(super::rdev_backend::RdevBackend::new()))
+        Ok(Box::new(
+            super::rdev_backend::RdevBackend::new()
+                .with_gestures(capture_gestures)
+                .with_text_commits(capture_text_commits),
+        ))
     }
 }
@@ -3,8 +3,8 @@
 
 #[cfg(target_os = "linux")]
 use crate::data::{
-    EventType, InputEvent, KeyEvent, MouseButton, MouseButtonEvent, MouseMoveEvent,
-    MouseScrollEvent,
+    epoch_us, EventSequence, EventType, InputEvent, KeyEvent, ModifierState, MouseButton,
+    MouseButtonEvent, MouseMoveEvent, MouseScrollEvent,
 };
 #[cfg(target_os = "linux")]
 use crate::input::secure::SecureInputState;
@@ -142,6 +142,8 @@ fn spawn_capture_thread(
     tx: mpsc::UnboundedSender<InputEvent>,
     capturing: Arc<AtomicBool>,
     secure: Arc<SecureInputState>,
+    modifiers: Arc<ModifierState>,
+    seq: Arc<EventSequence>,
     start_time: Instant,
     active: ActiveDevices,
 ) {
@@ -170,10 +172,16 @@ fn spawn_capture_thread(
                             secure.should_suppress_keys(),
                             &mut out,
                         );
+                        for release in coalescer.withheld_releases.drain(..) {
+                            modifiers.apply(&release);
+                        }
                         for event in out.drain(..) {
                             if let Err(e) = tx.send(InputEvent {
                                 timestamp_us,
+                                modifiers: modifiers.apply(&event),
                                 event,
+                                absolute_us: epoch_us(ev.timestamp()),
+                                seq: seq.next(),
                             }) {
                                 debug!("Failed to send input event: {}", e);
                             }
@@ -211,6 +219,8 @@ fn spawn_hotplug_watcher(
     tx: mpsc::UnboundedSender<InputEvent>,
     capturing: Arc<AtomicBool>,
     secure: Arc<SecureInputState>,
+    modifiers: Arc<ModifierState>,
+    seq: Arc<EventSequence>,
     start_time: Instant,
     active: ActiveDevices,
 ) {
@@ -258,6 +268,8 @@ fn spawn_hotplug_watcher(
                         tx.clone(),
                         capturing.clone(),
                         secure.clone(),
+                        modifiers.clone(),
+                        seq.clone(),
                         start_time,
                         active.clone(),
                     );
@@ -280,6 +292,9 @@ struct EventCoalescer {
     dy: f64,
     scroll_x: i64,
     scroll_y: i64,
+    /// Key releases withheld by `suppress_keys`. Never logged, but the held-modifier
+    /// state must still see them, or a modifier let go under the gate stays held.
+    withheld_releases: Vec<EventType>,
 }
 
 #[cfg(target_os = "linux")]
@@ -298,10 +313,12 @@ impl EventCoalescer {
                 // Buttons are never gated by secure-input (matches macOS, where clicks aren't
                 // withheld for a focused password field).
                 if let Some(button) = MouseButton::from_evdev_key(key) {
+                    // Only relative motion reaches evdev, so the pointer position is unknown.
                     let be = MouseButtonEvent {
                         button,
-                        x: 0.0,
-                        y: 0.0,
+                        x: None,
+                        y: None,
+                        normalized: None,
                     };
                     match value {
                         1 => out.push(EventType::MousePress(be)),
@@ -310,12 +327,21 @@ impl EventCoalescer {
                     }
                 } else if suppress_keys {
                     // Withhold keystrokes while a secure context is active.
+                    if value == 0 {
+                        self.withheld_releases
+                            .push(EventType::KeyRelease(KeyEvent::from(key)));
+                    }
                 } else {
-                    let ke = KeyEvent::from(key);
+                    let mut ke = KeyEvent::from(key);
                     match value {
                         1 => out.push(EventType::KeyPress(ke)),
                         0 => out.push(EventType::KeyRelease(ke)),
-                        _ => {} // key repeat (value == 2)
+                        // Auto-repeat while the key is held
+                        2 => {
+                            ke.is_repeat = true;
+                            out.push(EventType::KeyPress(ke));
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -341,8 +367,11 @@ impl EventCoalescer {
                     out.push(EventType::MouseScroll(MouseScrollEvent {
                         delta_x: self.scroll_x,
                         delta_y: self.scroll_y,
-                        x: 0.0,
-                        y: 0.0,
+                        x: None,
+                        y: None,
+                        normalized: None,
+                        precise: false,
+                        momentum: false,
                     }));
                     self.scroll_x = 0;
                     self.scroll_y = 0;
@@ -367,6 +396,12 @@ impl InputBackend for EvdevBackend {
         // Tracks which device paths currently have a live capture thread; shared with the
         // hotplug watcher so it never double-adopts and so disconnected devices can return.
         let active: ActiveDevices = Arc::new(Mutex::new(HashSet::new()));
+        // Held modifiers/buttons, shared across devices: Shift on the keyboard shows up
+        // on a click from the mouse.
+        let modifiers = Arc::new(ModifierState::default());
+        // One sequence across devices, so a gap means a dropped event rather than a
+        // switch of device.
+        let seq = Arc::new(EventSequence::default());
 
         // Spawn a capture thread per device enumerated at startup. Register each path before
         // spawning the watcher so its first tick treats them as already-owned.
@@ -382,6 +417,8 @@ impl InputBackend for EvdevBackend {
                 tx.clone(),
                 self.capturing.clone(),
                 self.secure.clone(),
+                modifiers.clone(),
+                seq.clone(),
                 start_time,
                 active.clone(),
             );
@@ -392,6 +429,8 @@ impl InputBackend for EvdevBackend {
             tx,
             self.capturing.clone(),
             self.secure.clone(),
+            modifiers,
+            seq,
             start_time,
             active,
         );
@@ -486,6 +525,32 @@ mod coalescer_tests {
         }
     }
 
+    #[test]
+    fn held_key_repeats_are_flagged_presses() {
+        let mut c = EventCoalescer::default();
+        let mut out = Vec::new();
+        for value in [1, 2, 2, 0] {
+            c.feed(InputEventKind::Key(Key::KEY_A), value, false, &mut out);
+        }
+        let kinds: Vec<_> = out
+            .iter()
+            .map(|event| match event {
+                EventType::KeyPress(k) => ("press", k.is_repeat),
+                EventType::KeyRelease(k) => ("release", k.is_repeat),
+                other => panic!("expected key events, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("press", false),
+                ("press", true),
+                ("press", true),
+                ("release", false)
+            ]
+        );
+    }
+
     #[test]
     fn secure_gate_withholds_keys_not_buttons() {
         let mut c = EventCoalescer::default();
@@ -495,6 +560,26 @@ mod coalescer_tests {
         c.feed(InputEventKind::Key(Key::BTN_LEFT), 1, true, &mut out);
         assert_eq!(out.len(), 1, "pointer buttons are never gated");
         assert!(matches!(out[0], EventType::MousePress(_)));
+
+        // Shift pressed before the gate and let go under it: the release isn't logged,
+        // but it still clears the held-modifier bit.
+        let modifiers = ModifierState::default();
+        out.clear();
+        c.feed(InputEventKind::Key(Key::KEY_LEFTSHIFT), 1, false, &mut out);
+        assert_eq!(modifiers.apply(&out[0]), ModifierState::SHIFT_LEFT);
+        out.clear();
+        c.feed(InputEventKind::Key(Key::KEY_LEFTSHIFT), 0, true, &mut out);
+        assert!(out.is_empty());
+        for release in c.withheld_releases.drain(..) {
+            modifiers.apply(&release);
+        }
+        let click = EventType::MousePress(MouseButtonEvent {
+            button: MouseButton::Left,
+            x: None,
+            y: None,
+            normalized: None,
+        });
+        assert_eq!(modifiers.apply(&click), ModifierState::BUTTON_LEFT);
     }
 
     #[test]
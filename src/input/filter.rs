@@ -0,0 +1,1175 @@
+//! Composable input event filters.
+//!
+//! Every input event passes through a [`FilterChain`] in the sync engine before it is
+//! buffered. Each [`EventFilter`] votes Keep/Drop/Redact; the first non-Keep vote wins.
+//! A redaction replaces the event with a single [`RedactedEvent`] marker per run, so
+//! post-processing sees a labeled gap rather than a silent hole (same convention as the
+//! secure-input gate).
+//!
+//! Non-input events (context changes, metadata, redaction markers) are never filtered.
+//! A filter may also derive a follow-up event from one the chain kept (chords), which is
+//! logged right after it.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use tracing::{trace, warn};
+
+use crate::config::InputConfig;
+use crate::data::{
+    ChordEvent, EventType, InputEvent, ModifierState, MouseMoveEvent, RedactedEvent,
+};
+
+/// A filter's verdict on one event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterDecision {
+    /// Pass the event on to the next filter.
+    Keep,
+    /// Discard the event silently.
+    Drop,
+    /// Withhold the event's content, leaving a `Redacted` marker with this reason.
+    Redact(String),
+}
+
+/// Engine state a filter may consult, sampled per event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilterContext<'a> {
+    /// Frontmost app (bundle id / process name), if known.
+    pub frontmost_app: Option<&'a str>,
+    /// Whether a secure context (e.g. focused password field) is active.
+    pub secure_input: bool,
+    /// Screen area the video shows, if known.
+    pub capture_region: Option<CaptureRegion>,
+}
+
+/// A screen rectangle in the coordinate space of mouse event positions (global points on
+/// macOS).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl CaptureRegion {
+    /// Whether `(x, y)` lies inside the region; the right and bottom edges are outside.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// One stage of the filter chain.
+pub trait EventFilter: Send + Sync {
+    /// Short name for logging.
+    fn name(&self) -> &'static str;
+
+    /// Decide what happens to `event`.
+    fn keep(&self, event: &InputEvent, ctx: &FilterContext) -> FilterDecision;
+
+    /// Rewrite an event this filter kept. Identity by default; used by filters
+    /// that fold dropped events into the next kept one.
+    fn transform(&self, event: InputEvent) -> InputEvent {
+        event
+    }
+
+    /// An event to log right after `event`, which the whole chain kept and transformed.
+    /// None by default; used by filters that derive events from the input.
+    fn follow_up(&self, _event: &InputEvent) -> Option<InputEvent> {
+        None
+    }
+}
+
+/// Whether the event is user input (as opposed to a context/metadata/marker event).
+fn is_user_input(event: &EventType) -> bool {
+    !matches!(
+        event,
+        EventType::ContextChanged(_)
+            | EventType::Metadata(_)
+            | EventType::Redacted(_)
+            | EventType::DisplayScaleChanged(_)
+            | EventType::Marker(_)
+            | EventType::WindowGeometry(_)
+            | EventType::AudioLevel(_)
+            | EventType::ResourceUsage(_)
+    )
+}
+
+/// Whether the event carries typed content: a key event or an IME text commit
+fn is_key_event(event: &EventType) -> bool {
+    matches!(
+        event,
+        EventType::KeyPress(_)
+            | EventType::KeyRelease(_)
+            | EventType::TextCommit(_)
+            | EventType::Chord(_)
+    )
+}
+
+/// Ordered list of filters applied to every input event.
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<Box<dyn EventFilter>>,
+    /// Reason of the redaction run in progress, so a run of redacted events
+    /// yields one marker instead of one per event.
+    redacting: Mutex<Option<String>>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a filter; filters run in insertion order.
+    pub fn with(mut self, filter: impl EventFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// The built-in chain for `config`: event-type toggles, key auto-repeat, the
+    /// capture-region check, secure-input redaction, blocklisted-app redaction,
+    /// mouse-move coalescing, the event-rate limit, then chord detection.
+    pub fn from_config(config: &InputConfig) -> Self {
+        let mut chain = Self::new().with(EventTypeFilter::from_config(config));
+        if config.drop_key_repeats {
+            chain = chain.with(KeyRepeatFilter);
+        }
+        if config.restrict_to_capture_region {
+            chain = chain.with(CaptureRegionFilter);
+        }
+        chain = chain.with(SecureInputFilter);
+        if !config.redact_apps.is_empty() {
+            chain = chain.with(BlocklistAppFilter::new(config.redact_apps.clone()));
+        }
+        if config.mouse_coalesce_us > 0 {
+            chain = chain.with(MouseCoalesceFilter::new(config.mouse_coalesce_us));
+        }
+        if config.max_events_per_sec > 0 {
+            chain = chain.with(RateLimitFilter::new(config.max_events_per_sec));
+        }
+        if config.emit_chords {
+            chain = chain.with(ChordFilter::new(config.chords_replace_keys));
+        }
+        chain
+    }
+
+    /// Run `event` through the chain. Returns the event to buffer (possibly a
+    /// `Redacted` marker) or `None` if it was dropped.
+    pub fn apply(&self, event: InputEvent, ctx: &FilterContext) -> Option<InputEvent> {
+        self.run(event, ctx).map(|(event, _)| event)
+    }
+
+    /// Like [`FilterChain::apply`], followed by any events the filters derive from the
+    /// kept one, in the order they are to be buffered.
+    pub fn apply_all(&self, event: InputEvent, ctx: &FilterContext) -> Vec<InputEvent> {
+        let Some((event, kept_by)) = self.run(event, ctx) else {
+            return Vec::new();
+        };
+        let follow_ups: Vec<InputEvent> = kept_by
+            .iter()
+            .filter_map(|filter| filter.follow_up(&event))
+            .collect();
+        std::iter::once(event).chain(follow_ups).collect()
+    }
+
+    /// The event to buffer, with the filters that kept it (none for a redaction marker
+    /// or a non-input event)
+    fn run(
+        &self,
+        event: InputEvent,
+        ctx: &FilterContext,
+    ) -> Option<(InputEvent, Vec<&dyn EventFilter>)> {
+        if !is_user_input(&event.event) {
+            return Some((event, Vec::new()));
+        }
+
+        let mut kept_by: Vec<&dyn EventFilter> = Vec::with_capacity(self.filters.len());
+        for filter in &self.filters {
+            match filter.keep(&event, ctx) {
+                FilterDecision::Keep => kept_by.push(filter.as_ref()),
+                FilterDecision::Drop => {
+                    trace!("input filter '{}' dropped event", filter.name());
+                    return None;
+                }
+                FilterDecision::Redact(reason) => {
+                    trace!(
+                        "input filter '{}' redacted event ({})",
+                        filter.name(),
+                        reason
+                    );
+                    let mut redacting = self.redacting.lock().unwrap_or_else(|p| p.into_inner());
+                    if redacting.as_deref() == Some(reason.as_str()) {
+                        return None;
+                    }
+                    *redacting = Some(reason.clone());
+                    let marker = InputEvent {
+                        event: EventType::Redacted(RedactedEvent { reason }),
+                        ..event
+                    };
+                    return Some((marker, Vec::new()));
+                }
+            }
+        }
+
+        *self.redacting.lock().unwrap_or_else(|p| p.into_inner()) = None;
+        let event = kept_by
+            .iter()
+            .fold(event, |event, filter| filter.transform(event));
+        Some((event, kept_by))
+    }
+}
+
+/// Drops event categories disabled in `[input]` (`capture_keyboard`, `capture_mouse_*`,
+/// `capture_momentum_scroll`, `capture_gestures`, `capture_text_commits`).
+pub struct EventTypeFilter {
+    pub keyboard: bool,
+    pub mouse_move: bool,
+    pub mouse_click: bool,
+    pub mouse_scroll: bool,
+    pub momentum_scroll: bool,
+    pub gestures: bool,
+    pub text_commits: bool,
+}
+
+impl EventTypeFilter {
+    pub fn from_config(config: &InputConfig) -> Self {
+        Self {
+            keyboard: config.capture_keyboard,
+            mouse_move: config.capture_mouse_move,
+            mouse_click: config.capture_mouse_click,
+            mouse_scroll: config.capture_mouse_scroll,
+            momentum_scroll: config.capture_momentum_scroll,
+            gestures: config.capture_gestures,
+            text_commits: config.capture_text_commits,
+        }
+    }
+}
+
+impl EventFilter for EventTypeFilter {
+    fn name(&self) -> &'static str {
+        "event-type"
+    }
+
+    fn keep(&self, event: &InputEvent, _ctx: &FilterContext) -> FilterDecision {
+        let enabled = match &event.event {
+            EventType::KeyPress(_) | EventType::KeyRelease(_) => self.keyboard,
+            EventType::MouseMove(_) => self.mouse_move,
+            EventType::MousePress(_) | EventType::MouseRelease(_) => self.mouse_click,
+            EventType::MouseScroll(scroll) => {
+                self.mouse_scroll && (self.momentum_scroll || !scroll.momentum)
+            }
+            EventType::Gesture(_) => self.gestures,
+            EventType::TextCommit(_) => self.text_commits,
+            _ => true,
+        };
+        if enabled {
+            FilterDecision::Keep
+        } else {
+            FilterDecision::Drop
+        }
+    }
+}
+
+/// Drops key presses generated by holding a key down (`input.drop_key_repeats`), so a
+/// held key logs one press and one release.
+pub struct KeyRepeatFilter;
+
+impl EventFilter for KeyRepeatFilter {
+    fn name(&self) -> &'static str {
+        "key-repeat"
+    }
+
+    fn keep(&self, event: &InputEvent, _ctx: &FilterContext) -> FilterDecision {
+        match &event.event {
+            EventType::KeyPress(key) if key.is_repeat => FilterDecision::Drop,
+            _ => FilterDecision::Keep,
+        }
+    }
+}
+
+/// Drops clicks and scrolls positioned outside the capture region
+/// (`input.restrict_to_capture_region`). Keyboard input, mouse moves (which carry only
+/// deltas) and gestures are kept, as is everything while the region is unknown and any
+/// click or scroll whose position the backend couldn't tell.
+pub struct CaptureRegionFilter;
+
+impl EventFilter for CaptureRegionFilter {
+    fn name(&self) -> &'static str {
+        "capture-region"
+    }
+
+    fn keep(&self, event: &InputEvent, ctx: &FilterContext) -> FilterDecision {
+        let Some(region) = ctx.capture_region else {
+            return FilterDecision::Keep;
+        };
+        let Some((x, y)) = event.event.pointer_position() else {
+            return FilterDecision::Keep;
+        };
+        if region.contains(x, y) {
+            FilterDecision::Keep
+        } else {
+            FilterDecision::Drop
+        }
+    }
+}
+
+/// Redacts key events and IME text commits while a secure context is active. On Linux the evdev backend
+/// already withholds these at the source; this is the engine-level backstop for any
+/// backend that doesn't.
+pub struct SecureInputFilter;
+
+impl EventFilter for SecureInputFilter {
+    fn name(&self) -> &'static str {
+        "secure-input"
+    }
+
+    fn keep(&self, event: &InputEvent, ctx: &FilterContext) -> FilterDecision {
+        if ctx.secure_input && is_key_event(&event.event) {
+            FilterDecision::Redact("secure-field".to_string())
+        } else {
+            FilterDecision::Keep
+        }
+    }
+}
+
+/// Redacts all user input while a blocklisted app (`input.redact_apps`) is frontmost.
+/// Matching follows `Config::should_capture_app`: case-insensitive on Windows,
+/// exact elsewhere.
+pub struct BlocklistAppFilter {
+    apps: Vec<String>,
+}
+
+impl BlocklistAppFilter {
+    pub fn new(apps: Vec<String>) -> Self {
+        Self { apps }
+    }
+
+    fn is_blocked(&self, app: &str) -> bool {
+        self.apps.iter().any(|blocked| {
+            if cfg!(target_os = "windows") {
+                blocked.eq_ignore_ascii_case(app)
+            } else {
+                blocked == app
+            }
+        })
+    }
+}
+
+impl EventFilter for BlocklistAppFilter {
+    fn name(&self) -> &'static str {
+        "blocklist-app"
+    }
+
+    fn keep(&self, _event: &InputEvent, ctx: &FilterContext) -> FilterDecision {
+        match ctx.frontmost_app {
+            Some(app) if self.is_blocked(app) => {
+                FilterDecision::Redact("blocklisted-app".to_string())
+            }
+            _ => FilterDecision::Keep,
+        }
+    }
+}
+
+/// Thins high-rate mouse motion: moves closer than `min_interval_us` to the last
+/// kept move are dropped and their deltas folded into the next kept move, so the
+/// total motion is preserved.
+pub struct MouseCoalesceFilter {
+    min_interval_us: u64,
+    state: Mutex<CoalesceState>,
+}
+
+#[derive(Default)]
+struct CoalesceState {
+    last_kept_us: Option<u64>,
+    pending_dx: f64,
+    pending_dy: f64,
+}
+
+impl MouseCoalesceFilter {
+    pub fn new(min_interval_us: u64) -> Self {
+        Self {
+            min_interval_us,
+            state: Mutex::new(CoalesceState::default()),
+        }
+    }
+}
+
+impl EventFilter for MouseCoalesceFilter {
+    fn name(&self) -> &'static str {
+        "mouse-coalesce"
+    }
+
+    fn keep(&self, event: &InputEvent, _ctx: &FilterContext) -> FilterDecision {
+        let EventType::MouseMove(mv) = &event.event else {
+            return FilterDecision::Keep;
+        };
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        match state.last_kept_us {
+            Some(last) if event.timestamp_us.saturating_sub(last) < self.min_interval_us => {
+                state.pending_dx += mv.delta_x;
+                state.pending_dy += mv.delta_y;
+                FilterDecision::Drop
+            }
+            _ => FilterDecision::Keep,
+        }
+    }
+
+    fn transform(&self, event: InputEvent) -> InputEvent {
+        let EventType::MouseMove(mv) = &event.event else {
+            return event;
+        };
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        state.last_kept_us = Some(event.timestamp_us);
+        let merged = MouseMoveEvent {
+            delta_x: mv.delta_x + std::mem::take(&mut state.pending_dx),
+            delta_y: mv.delta_y + std::mem::take(&mut state.pending_dy),
+        };
+        InputEvent {
+            event: EventType::MouseMove(merged),
+            ..event
+        }
+    }
+}
+
+/// How often (in event time) the rate limiter logs the drops since its last warning
+const RATE_LIMIT_WARN_INTERVAL_US: u64 = 10_000_000;
+
+/// Caps input at `max_per_sec` events per one-second window of event time. Mouse moves
+/// may only use half of each window's budget, so a motion flood can't crowd out the
+/// clicks and key presses that follow it. Excess events are dropped and counted. Key and
+/// button releases are never dropped (they still use up budget): losing one would leave
+/// the key or button held down in the log.
+pub struct RateLimitFilter {
+    max_per_sec: u64,
+    state: Mutex<RateLimitState>,
+}
+
+#[derive(Default)]
+struct RateLimitState {
+    window_start_us: Option<u64>,
+    in_window: u64,
+    dropped: u64,
+    unreported: u64,
+    last_warn_us: Option<u64>,
+}
+
+impl RateLimitFilter {
+    pub fn new(max_per_sec: u32) -> Self {
+        Self {
+            max_per_sec: u64::from(max_per_sec),
+            state: Mutex::new(RateLimitState::default()),
+        }
+    }
+}
+
+impl EventFilter for RateLimitFilter {
+    fn name(&self) -> &'static str {
+        "rate-limit"
+    }
+
+    fn keep(&self, event: &InputEvent, _ctx: &FilterContext) -> FilterDecision {
+        let now = event.timestamp_us;
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        // Timestamps restart at 0 with every segment; treat going backwards as a new window.
+        let expired = state
+            .window_start_us
+            .is_none_or(|start| now < start || now - start >= 1_000_000);
+        if expired {
+            state.window_start_us = Some(now);
+            state.in_window = 0;
+        }
+
+        let budget = match event.event {
+            EventType::KeyRelease(_) | EventType::MouseRelease(_) => u64::MAX,
+            EventType::MouseMove(_) => self.max_per_sec / 2,
+            _ => self.max_per_sec,
+        };
+        if state.in_window < budget {
+            state.in_window += 1;
+            return FilterDecision::Keep;
+        }
+
+        state.dropped += 1;
+        state.unreported += 1;
+        let warn_due = state
+            .last_warn_us
+            .is_none_or(|last| now < last || now - last >= RATE_LIMIT_WARN_INTERVAL_US);
+        if warn_due {
+            warn!(
+                "Input event flood: dropped {} event(s) over the {}/s limit ({} total)",
+                state.unreported, self.max_per_sec, state.dropped
+            );
+            state.unreported = 0;
+            state.last_warn_us = Some(now);
+        }
+        FilterDecision::Drop
+    }
+}
+
+/// Modifiers that turn a key press into a chord. Shift alone only changes what is typed,
+/// AltGr types characters on many layouts, and Fn remaps keys on laptop keyboards.
+const CHORD_TRIGGER_MODIFIERS: u32 = ModifierState::CONTROL_LEFT
+    | ModifierState::CONTROL_RIGHT
+    | ModifierState::ALT
+    | ModifierState::META_LEFT
+    | ModifierState::META_RIGHT;
+
+/// Modifier names in chord order, with the `ModifierState` bits each covers
+const CHORD_MODIFIERS: [(&str, u32); 5] = [
+    (
+        "Control",
+        ModifierState::CONTROL_LEFT | ModifierState::CONTROL_RIGHT,
+    ),
+    ("Alt", ModifierState::ALT | ModifierState::ALT_GR),
+    (
+        "Shift",
+        ModifierState::SHIFT_LEFT | ModifierState::SHIFT_RIGHT,
+    ),
+    ("Meta", ModifierState::META_LEFT | ModifierState::META_RIGHT),
+    ("Fn", ModifierState::FUNCTION),
+];
+
+/// Derives `Chord` events from key presses made while Control, Alt or Meta is held
+/// (`input.emit_chords`). By default the chord follows the raw press; with
+/// `replace_keys` it takes the press's place and the key's release is dropped.
+pub struct ChordFilter {
+    replace_keys: bool,
+    /// Codes of keys whose press became a chord and whose release is still to come
+    replaced: Mutex<HashSet<u32>>,
+}
+
+impl ChordFilter {
+    pub fn new(replace_keys: bool) -> Self {
+        Self {
+            replace_keys,
+            replaced: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The chord `event` completes: a non-modifier key press with a trigger modifier held.
+    /// An auto-repeat of the key completes nothing new.
+    fn chord(event: &InputEvent) -> Option<InputEvent> {
+        let EventType::KeyPress(key) = &event.event else {
+            return None;
+        };
+        if key.is_repeat
+            || ModifierState::is_modifier_key(key.code)
+            || event.modifiers & CHORD_TRIGGER_MODIFIERS == 0
+        {
+            return None;
+        }
+        let keys = CHORD_MODIFIERS
+            .iter()
+            .filter(|(_, bits)| event.modifiers & bits != 0)
+            .map(|(name, _)| name.to_string())
+            .chain(std::iter::once(key.name.clone()))
+            .collect();
+        Some(InputEvent {
+            event: EventType::Chord(ChordEvent { keys }),
+            ..event.clone()
+        })
+    }
+}
+
+impl EventFilter for ChordFilter {
+    fn name(&self) -> &'static str {
+        "chord"
+    }
+
+    fn keep(&self, event: &InputEvent, _ctx: &FilterContext) -> FilterDecision {
+        if !self.replace_keys {
+            return FilterDecision::Keep;
+        }
+        let mut replaced = self.replaced.lock().unwrap_or_else(|p| p.into_inner());
+        match &event.event {
+            // Repeats and the release of a key the chord replaced belong to the chord.
+            EventType::KeyPress(key) if key.is_repeat && replaced.contains(&key.code) => {
+                FilterDecision::Drop
+            }
+            EventType::KeyRelease(key) if replaced.remove(&key.code) => FilterDecision::Drop,
+            _ => FilterDecision::Keep,
+        }
+    }
+
+    fn transform(&self, event: InputEvent) -> InputEvent {
+        if !self.replace_keys {
+            return event;
+        }
+        match (Self::chord(&event), &event.event) {
+            (Some(chord), EventType::KeyPress(key)) => {
+                self.replaced
+                    .lock()
+                    .unwrap_or_else(|p| p.into_inner())
+                    .insert(key.code);
+                chord
+            }
+            _ => event,
+        }
+    }
+
+    fn follow_up(&self, event: &InputEvent) -> Option<InputEvent> {
+        if self.replace_keys {
+            None
+        } else {
+            Self::chord(event)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        EventSequence, KeyEvent, MouseButton, MouseButtonEvent, MouseScrollEvent, TextCommitEvent,
+    };
+
+    fn key(ts: u64) -> InputEvent {
+        InputEvent::now(
+            ts,
+            EventType::KeyPress(KeyEvent {
+                code: 30,
+                name: "KeyA".to_string(),
+                is_repeat: false,
+            }),
+        )
+    }
+
+    fn text_commit(ts: u64) -> InputEvent {
+        InputEvent::now(
+            ts,
+            EventType::TextCommit(TextCommitEvent {
+                text: "こんにちは".to_string(),
+            }),
+        )
+    }
+
+    fn click(ts: u64) -> InputEvent {
+        InputEvent::now(
+            ts,
+            EventType::MousePress(MouseButtonEvent {
+                button: MouseButton::Left,
+                x: None,
+                y: None,
+                normalized: None,
+            }),
+        )
+    }
+
+    fn mouse_move(ts: u64, dx: f64, dy: f64) -> InputEvent {
+        InputEvent::now(
+            ts,
+            EventType::MouseMove(MouseMoveEvent {
+                delta_x: dx,
+                delta_y: dy,
+            }),
+        )
+    }
+
+    fn redaction_reason(event: &InputEvent) -> Option<&str> {
+        match &event.event {
+            EventType::Redacted(r) => Some(r.reason.as_str()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn event_type_filter_drops_disabled_categories() {
+        let filter = EventTypeFilter {
+            keyboard: false,
+            mouse_move: true,
+            mouse_click: true,
+            mouse_scroll: true,
+            momentum_scroll: true,
+            gestures: true,
+            text_commits: true,
+        };
+        let ctx = FilterContext::default();
+        assert_eq!(filter.keep(&key(1), &ctx), FilterDecision::Drop);
+        assert_eq!(filter.keep(&text_commit(4), &ctx), FilterDecision::Keep);
+        // Text commits are off by default.
+        assert_eq!(
+            EventTypeFilter::from_config(&InputConfig::default()).keep(&text_commit(5), &ctx),
+            FilterDecision::Drop
+        );
+        assert_eq!(filter.keep(&click(2), &ctx), FilterDecision::Keep);
+        assert_eq!(
+            filter.keep(&mouse_move(3, 1.0, 1.0), &ctx),
+            FilterDecision::Keep
+        );
+    }
+
+    #[test]
+    fn sequence_numbers_leave_gaps_only_where_events_were_dropped() {
+        let stream = || {
+            let seq = EventSequence::default();
+            (0..6u64)
+                .map(|i| {
+                    let event = if i % 2 == 0 {
+                        key(i)
+                    } else {
+                        mouse_move(i, 1.0, 0.0)
+                    };
+                    InputEvent {
+                        seq: seq.next(),
+                        ..event
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let kept_seqs = |chain: FilterChain| -> Vec<u64> {
+            let ctx = FilterContext::default();
+            stream()
+                .into_iter()
+                .filter_map(|event| chain.apply(event, &ctx))
+                .map(|event| event.seq)
+                .collect()
+        };
+
+        assert_eq!(kept_seqs(FilterChain::new()), [1, 2, 3, 4, 5, 6]);
+
+        let no_moves = EventTypeFilter {
+            mouse_move: false,
+            ..EventTypeFilter::from_config(&InputConfig::default())
+        };
+        assert_eq!(kept_seqs(FilterChain::new().with(no_moves)), [1, 3, 5]);
+
+        // The number survives serialization (positional index 4).
+        let event = stream().remove(2);
+        let decoded: InputEvent =
+            rmp_serde::from_slice(&rmp_serde::to_vec(&event).unwrap()).unwrap();
+        assert_eq!(decoded.seq, 3);
+    }
+
+    #[test]
+    fn event_type_filter_drops_momentum_scrolls_only_when_disabled() {
+        let scroll = |momentum| {
+            InputEvent::now(
+                1,
+                EventType::MouseScroll(MouseScrollEvent {
+                    delta_x: 0,
+                    delta_y: -12,
+                    x: None,
+                    y: None,
+                    normalized: None,
+                    precise: true,
+                    momentum,
+                }),
+            )
+        };
+        let mut filter = EventTypeFilter::from_config(&InputConfig::default());
+        let ctx = FilterContext::default();
+        assert_eq!(filter.keep(&scroll(true), &ctx), FilterDecision::Keep);
+
+        filter.momentum_scroll = false;
+        assert_eq!(filter.keep(&scroll(true), &ctx), FilterDecision::Drop);
+        // The fingers-down part of the same scroll is still recorded.
+        assert_eq!(filter.keep(&scroll(false), &ctx), FilterDecision::Keep);
+    }
+
+    #[test]
+    fn secure_input_filter_redacts_keys_only() {
+        let ctx = FilterContext {
+            secure_input: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            SecureInputFilter.keep(&key(1), &ctx),
+            FilterDecision::Redact("secure-field".to_string())
+        );
+        assert_eq!(
+            SecureInputFilter.keep(&text_commit(4), &ctx),
+            FilterDecision::Redact("secure-field".to_string())
+        );
+        assert_eq!(
+            SecureInputFilter.keep(&click(2), &ctx),
+            FilterDecision::Keep
+        );
+        assert_eq!(
+            SecureInputFilter.keep(&key(3), &FilterContext::default()),
+            FilterDecision::Keep
+        );
+    }
+
+    #[test]
+    fn blocklist_filter_redacts_only_blocked_app() {
+        let filter = BlocklistAppFilter::new(vec!["com.1password.1password".to_string()]);
+        let blocked = FilterContext {
+            frontmost_app: Some("com.1password.1password"),
+            ..Default::default()
+        };
+        let other = FilterContext {
+            frontmost_app: Some("com.apple.Safari"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            filter.keep(&click(1), &blocked),
+            FilterDecision::Redact(_)
+        ));
+        assert_eq!(filter.keep(&click(1), &other), FilterDecision::Keep);
+        assert_eq!(
+            filter.keep(&click(1), &FilterContext::default()),
+            FilterDecision::Keep
+        );
+    }
+
+    #[test]
+    fn capture_region_filter_drops_clicks_outside_region() {
+        let click_at = |ts, x, y| {
+            InputEvent::now(
+                ts,
+                EventType::MousePress(MouseButtonEvent {
+                    button: MouseButton::Left,
+                    x: Some(x),
+                    y: Some(y),
+                    normalized: None,
+                }),
+            )
+        };
+        let config = InputConfig {
+            restrict_to_capture_region: true,
+            ..InputConfig::default()
+        };
+        let chain = FilterChain::from_config(&config);
+        // A second monitor to the right of the captured 1440x900 one.
+        let ctx = FilterContext {
+            capture_region: Some(CaptureRegion {
+                x: 0.0,
+                y: 0.0,
+                width: 1440.0,
+                height: 900.0,
+            }),
+            ..Default::default()
+        };
+
+        assert!(chain.apply(click_at(1, 720.0, 450.0), &ctx).is_some());
+        assert!(chain.apply(click_at(2, 0.0, 899.5), &ctx).is_some());
+        assert!(chain.apply(click_at(3, 2000.0, 450.0), &ctx).is_none());
+        assert!(chain.apply(click_at(4, 1440.0, 10.0), &ctx).is_none());
+        assert!(chain.apply(click_at(5, -5.0, 10.0), &ctx).is_none());
+        // Keys carry no position and always pass.
+        assert!(chain.apply(key(6), &ctx).is_some());
+        // Without a known region nothing is dropped.
+        assert!(chain
+            .apply(click_at(7, 2000.0, 450.0), &FilterContext::default())
+            .is_some());
+        // Nor is a click whose position the backend couldn't tell.
+        assert!(chain.apply(click(8), &ctx).is_some());
+    }
+
+    #[test]
+    fn mouse_coalesce_preserves_total_delta() {
+        let chain = FilterChain::new().with(MouseCoalesceFilter::new(1_000));
+        let ctx = FilterContext::default();
+
+        let first = chain.apply(mouse_move(0, 1.0, 2.0), &ctx).unwrap();
+        assert!(chain.apply(mouse_move(300, 3.0, 4.0), &ctx).is_none());
+        assert!(chain.apply(mouse_move(600, 5.0, 6.0), &ctx).is_none());
+        let merged = chain.apply(mouse_move(1_200, 7.0, 8.0), &ctx).unwrap();
+
+        let EventType::MouseMove(first) = first.event else {
+            panic!("expected a move");
+        };
+        assert_eq!((first.delta_x, first.delta_y), (1.0, 2.0));
+        let EventType::MouseMove(merged) = merged.event else {
+            panic!("expected a move");
+        };
+        assert_eq!((merged.delta_x, merged.delta_y), (15.0, 18.0));
+    }
+
+    #[test]
+    fn rate_limit_bounds_floods_and_keeps_clicks_and_releases() {
+        let limiter = RateLimitFilter::new(1_000);
+        let ctx = FilterContext::default();
+
+        // Two seconds of a 50k/s motion flood (one move every 20us), with a click
+        // every 100ms mixed in.
+        let mut kept_moves = 0u64;
+        let mut kept_clicks = 0u64;
+        for i in 0..100_000u64 {
+            let ts = i * 20;
+            if limiter.keep(&mouse_move(ts, 1.0, 0.0), &ctx) == FilterDecision::Keep {
+                kept_moves += 1;
+            }
+            if ts % 100_000 == 0 && limiter.keep(&click(ts), &ctx) == FilterDecision::Keep {
+                kept_clicks += 1;
+            }
+        }
+
+        // Moves are held to half the budget per second; every click still gets through.
+        assert!(kept_moves > 0 && kept_moves <= 2 * 500);
+        assert_eq!(kept_clicks, 20);
+
+        // A stuck key floods presses past the whole budget, but its release still lands.
+        let press = |ts| key_with(ts, true, 30, "KeyA", 0);
+        let kept_presses = (0..5_000u64)
+            .filter(|&i| limiter.keep(&press(3_000_000 + i * 100), &ctx) == FilterDecision::Keep)
+            .count();
+        assert_eq!(kept_presses, 1_000);
+        assert_eq!(
+            limiter.keep(&key_with(3_600_000, false, 30, "KeyA", 0), &ctx),
+            FilterDecision::Keep
+        );
+        let release = InputEvent::now(
+            3_600_001,
+            EventType::MouseRelease(MouseButtonEvent {
+                button: MouseButton::Left,
+                x: None,
+                y: None,
+                normalized: None,
+            }),
+        );
+        assert_eq!(limiter.keep(&release, &ctx), FilterDecision::Keep);
+
+        // A new segment restarts timestamps at 0, which opens a fresh window.
+        assert_eq!(
+            limiter.keep(&mouse_move(0, 1.0, 0.0), &ctx),
+            FilterDecision::Keep
+        );
+    }
+
+    #[test]
+    fn chain_collapses_redaction_runs_and_passes_markers() {
+        let chain = FilterChain::new().with(SecureInputFilter);
+        let secure = FilterContext {
+            secure_input: true,
+            ..Default::default()
+        };
+
+        let marker = chain.apply(key(1), &secure).unwrap();
+        assert_eq!(redaction_reason(&marker), Some("secure-field"));
+        assert!(chain.apply(key(2), &secure).is_none());
+        // A kept event ends the run; the next secure span gets a fresh marker.
+        assert!(chain.apply(click(3), &secure).is_some());
+        assert!(chain
+            .apply(key(4), &secure)
+            .is_some_and(|e| redaction_reason(&e).is_some()));
+        // Markers themselves are never filtered.
+        assert!(chain.apply(marker, &secure).is_some());
+    }
+
+    #[test]
+    fn chain_first_non_keep_decision_wins() {
+        let config = InputConfig {
+            capture_keyboard: false,
+            ..InputConfig::default()
+        };
+        let secure = FilterContext {
+            secure_input: true,
+            ..Default::default()
+        };
+
+        // Type toggle runs before secure redaction: a disabled key is dropped, not redacted.
+        let chain = FilterChain::from_config(&config);
+        assert!(chain.apply(key(1), &secure).is_none());
+
+        // Reversed order: redaction wins.
+        let chain = FilterChain::new()
+            .with(SecureInputFilter)
+            .with(EventTypeFilter::from_config(&config));
+        let out = chain.apply(key(1), &secure).unwrap();
+        assert_eq!(redaction_reason(&out), Some("secure-field"));
+    }
+
+    #[test]
+    fn held_key_repeats_collapse_to_one_press() {
+        let held = |ts: u64, press: bool, is_repeat: bool| {
+            let key = KeyEvent {
+                code: 30,
+                name: "KeyA".to_string(),
+                is_repeat,
+            };
+            InputEvent::now(
+                ts,
+                if press {
+                    EventType::KeyPress(key)
+                } else {
+                    EventType::KeyRelease(key)
+                },
+            )
+        };
+        let sequence = || {
+            vec![
+                held(1, true, false),
+                held(2, true, true),
+                held(3, true, true),
+                held(4, true, true),
+                held(5, false, false),
+            ]
+        };
+        let ctx = FilterContext::default();
+        let kept = |config: &InputConfig| -> Vec<u64> {
+            let chain = FilterChain::from_config(config);
+            sequence()
+                .into_iter()
+                .filter_map(|event| chain.apply(event, &ctx))
+                .map(|event| event.timestamp_us)
+                .collect()
+        };
+
+        // Repeats are logged by default, flagged.
+        assert_eq!(kept(&InputConfig::default()), vec![1, 2, 3, 4, 5]);
+
+        let config = InputConfig {
+            drop_key_repeats: true,
+            ..InputConfig::default()
+        };
+        assert_eq!(kept(&config), vec![1, 5]);
+    }
+
+    /// A key press or release (code, name) with the `ModifierState` bits held after it
+    fn key_with(ts: u64, press: bool, code: u32, name: &str, modifiers: u32) -> InputEvent {
+        let key = KeyEvent {
+            code,
+            name: name.to_string(),
+            is_repeat: false,
+        };
+        InputEvent {
+            modifiers,
+            ..InputEvent::now(
+                ts,
+                if press {
+                    EventType::KeyPress(key)
+                } else {
+                    EventType::KeyRelease(key)
+                },
+            )
+        }
+    }
+
+    fn chord_keys(event: &InputEvent) -> Option<Vec<&str>> {
+        match &event.event {
+            EventType::Chord(chord) => Some(chord.keys.iter().map(String::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn chord_follows_the_completing_key_press() {
+        let chain = FilterChain::new().with(ChordFilter::new(false));
+        let ctx = FilterContext::default();
+        let meta = ModifierState::META_LEFT;
+
+        // Cmd down, S down (chord), S up, Cmd up
+        assert_eq!(
+            chain
+                .apply_all(key_with(1, true, 24, "MetaLeft", meta), &ctx)
+                .len(),
+            1
+        );
+        let out = chain.apply_all(key_with(2, true, 50, "KeyS", meta), &ctx);
+        assert_eq!(out.len(), 2);
+        assert!(matches!(out[0].event, EventType::KeyPress(_)));
+        assert_eq!(chord_keys(&out[1]), Some(vec!["Meta", "KeyS"]));
+        assert_eq!(out[1].timestamp_us, 2);
+        assert_eq!(out[1].modifiers, meta);
+        assert_eq!(
+            chain
+                .apply_all(key_with(3, false, 50, "KeyS", meta), &ctx)
+                .len(),
+            1
+        );
+
+        // Shift alone only changes what is typed, and modifiers themselves never chord.
+        let shift = ModifierState::SHIFT_LEFT;
+        assert_eq!(
+            chain
+                .apply_all(key_with(4, true, 50, "KeyS", shift), &ctx)
+                .len(),
+            1
+        );
+        assert_eq!(
+            chain
+                .apply_all(key_with(5, true, 30, "ShiftLeft", meta | shift), &ctx)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn multi_modifier_chord_lists_modifiers_in_fixed_order() {
+        let chain = FilterChain::new().with(ChordFilter::new(false));
+        let ctx = FilterContext::default();
+        let held = ModifierState::SHIFT_RIGHT
+            | ModifierState::META_RIGHT
+            | ModifierState::CONTROL_LEFT
+            | ModifierState::ALT;
+        let out = chain.apply_all(key_with(1, true, 51, "KeyT", held), &ctx);
+        assert_eq!(
+            chord_keys(&out[1]),
+            Some(vec!["Control", "Alt", "Shift", "Meta", "KeyT"])
+        );
+    }
+
+    #[test]
+    fn held_chord_key_repeats_make_no_further_chords() {
+        let control = ModifierState::CONTROL_LEFT;
+        let repeat = |ts| {
+            let mut event = key_with(ts, true, 50, "KeyS", control);
+            if let EventType::KeyPress(key) = &mut event.event {
+                key.is_repeat = true;
+            }
+            event
+        };
+        let ctx = FilterContext::default();
+
+        // Alongside the key events: one chord, then the repeats as plain presses.
+        let chain = FilterChain::new().with(ChordFilter::new(false));
+        assert_eq!(
+            chain
+                .apply_all(key_with(1, true, 50, "KeyS", control), &ctx)
+                .len(),
+            2
+        );
+        assert_eq!(chain.apply_all(repeat(2), &ctx).len(), 1);
+        assert_eq!(chain.apply_all(repeat(3), &ctx).len(), 1);
+
+        // Replacing: the chord stands for the whole hold, repeats included.
+        let chain = FilterChain::new().with(ChordFilter::new(true));
+        let logged: Vec<InputEvent> = [
+            key_with(1, true, 50, "KeyS", control),
+            repeat(2),
+            repeat(3),
+            key_with(4, false, 50, "KeyS", control),
+        ]
+        .into_iter()
+        .flat_map(|event| chain.apply_all(event, &ctx))
+        .collect();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(chord_keys(&logged[0]), Some(vec!["Control", "KeyS"]));
+    }
+
+    #[test]
+    fn replacing_chords_drops_the_completing_key_events() {
+        let chain = FilterChain::new().with(ChordFilter::new(true));
+        let ctx = FilterContext::default();
+        let control = ModifierState::CONTROL_LEFT;
+
+        let logged: Vec<InputEvent> = [
+            key_with(1, true, 4, "ControlLeft", control),
+            key_with(2, true, 50, "KeyS", control),
+            // Ctrl released before S: S's release is still the chord's.
+            key_with(3, false, 4, "ControlLeft", 0),
+            key_with(4, false, 50, "KeyS", 0),
+            key_with(5, true, 50, "KeyS", 0),
+            key_with(6, false, 50, "KeyS", 0),
+        ]
+        .into_iter()
+        .flat_map(|event| chain.apply_all(event, &ctx))
+        .collect();
+
+        let timeline: Vec<(u64, Option<Vec<&str>>)> = logged
+            .iter()
+            .map(|e| (e.timestamp_us, chord_keys(e)))
+            .collect();
+        assert_eq!(
+            timeline,
+            vec![
+                (1, None),
+                (2, Some(vec!["Control", "KeyS"])),
+                (3, None),
+                (5, None),
+                (6, None),
+            ]
+        );
+    }
+}
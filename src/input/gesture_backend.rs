@@ -0,0 +1,311 @@
+//! Trackpad gesture and scroll capture (macOS only)
+//!
+//! rdev only surfaces keyboard/mouse/wheel events, so pinch, rotate and swipe gestures
+//! are read from a second, listen-only CGEventTap. Gestures arrive as the private
+//! `kCGSEventGesture` CGEvent type, with the HID gesture kind and its value in private
+//! integer/double fields (the same fields AppKit reads to build NSEvent magnify/rotate/
+//! swipe events). Events share the rdev backend's channel and start time, so they land
+//! on the same timeline as every other input event.
+//!
+//! rdev's wheel events carry only the deltas, so the same tap also takes over scrolling:
+//! it reads where the pointer is, whether the deltas are pixels from a continuous device
+//! and whether the event is inertial (momentum-phase) scrolling. Once the tap is running, the rdev callback
+//! drops its own wheel events (see `spawn`).
+
+use crate::data::{
+    EventSequence, EventType, GestureEvent, GestureKind, InputEvent, ModifierState,
+    MouseScrollEvent,
+};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+type CFMachPortRef = *mut c_void;
+type CGEventRef = *mut c_void;
+type CGEventTapCallBack = extern "C" fn(
+    proxy: *mut c_void,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef;
+
+/// `kCGSessionEventTap`
+const SESSION_EVENT_TAP: u32 = 1;
+/// `kCGHeadInsertEventTap`
+const HEAD_INSERT_EVENT_TAP: u32 = 0;
+/// `kCGEventTapOptionListenOnly`
+const TAP_OPTION_LISTEN_ONLY: u32 = 1;
+/// `kCGEventTapDisabledByTimeout` / `kCGEventTapDisabledByUserInput`
+const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+const TAP_DISABLED_BY_USER_INPUT: u32 = 0xFFFF_FFFF;
+
+/// Private `kCGSEventGesture` event type (NSEventTypeGesture)
+const GESTURE_EVENT_TYPE: u32 = 29;
+/// Private CGEvent fields carrying the gesture's HID type and values
+const FIELD_GESTURE_HID_TYPE: u32 = 110;
+const FIELD_GESTURE_ZOOM_VALUE: u32 = 113;
+const FIELD_GESTURE_ROTATION_VALUE: u32 = 114;
+const FIELD_GESTURE_SWIPE_VALUE: u32 = 115;
+
+/// `kCGEventScrollWheel`
+const SCROLL_WHEEL_EVENT_TYPE: u32 = 22;
+/// `kCGScrollWheelEventIsContinuous`, `kCGScrollWheelEventPointDeltaAxis1/2` and
+/// `kCGScrollWheelEventMomentumPhase` (nonzero while inertial scrolling runs)
+const FIELD_SCROLL_IS_CONTINUOUS: u32 = 88;
+const FIELD_SCROLL_POINT_DELTA_Y: u32 = 96;
+const FIELD_SCROLL_POINT_DELTA_X: u32 = 97;
+const FIELD_SCROLL_MOMENTUM_PHASE: u32 = 123;
+
+/// IOHIDEventType values for the gestures we record
+const HID_TYPE_ROTATION: i64 = 5;
+const HID_TYPE_ZOOM: i64 = 8;
+const HID_TYPE_NAVIGATION_SWIPE: i64 = 16;
+const HID_TYPE_DOCK_SWIPE: i64 = 23;
+
+/// `CGPoint`
+#[repr(C)]
+struct CGPoint {
+    x: f64,
+    y: f64,
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn CGEventTapCreate(
+        tap: u32,
+        place: u32,
+        options: u32,
+        events_of_interest: u64,
+        callback: CGEventTapCallBack,
+        user_info: *mut c_void,
+    ) -> CFMachPortRef;
+    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventGetIntegerValueField(event: CGEventRef, field: u32) -> i64;
+    fn CGEventGetDoubleValueField(event: CGEventRef, field: u32) -> f64;
+    fn CGEventGetLocation(event: CGEventRef) -> CGPoint;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFRunLoopCommonModes: *const c_void;
+    fn CFMachPortCreateRunLoopSource(
+        allocator: *const c_void,
+        port: CFMachPortRef,
+        order: isize,
+    ) -> *mut c_void;
+    fn CFRunLoopGetCurrent() -> *mut c_void;
+    fn CFRunLoopAddSource(run_loop: *mut c_void, source: *mut c_void, mode: *const c_void);
+    fn CFRunLoopRun();
+}
+
+/// State handed to the tap callback through `user_info`
+struct TapState {
+    tx: mpsc::UnboundedSender<InputEvent>,
+    capturing: Arc<AtomicBool>,
+    start_time: Instant,
+    modifiers: Arc<ModifierState>,
+    seq: Arc<EventSequence>,
+    tap: CFMachPortRef,
+}
+
+/// Map an IOHIDEventType to the gesture it represents, if we record it
+fn gesture_kind(hid_type: i64) -> Option<GestureKind> {
+    match hid_type {
+        HID_TYPE_ZOOM => Some(GestureKind::Magnify),
+        HID_TYPE_ROTATION => Some(GestureKind::Rotate),
+        HID_TYPE_NAVIGATION_SWIPE | HID_TYPE_DOCK_SWIPE => Some(GestureKind::Swipe),
+        _ => None,
+    }
+}
+
+/// Scroll event for a `kCGEventScrollWheel`'s location (global display points, as rdev
+/// reports pointer moves) and fields (point deltas, as rdev reports them), or None for
+/// the zero-delta events that only mark a scroll or momentum phase boundary
+fn scroll_event(
+    (x, y): (f64, f64),
+    delta_x: i64,
+    delta_y: i64,
+    is_continuous: i64,
+    momentum_phase: i64,
+) -> Option<MouseScrollEvent> {
+    if delta_x == 0 && delta_y == 0 {
+        return None;
+    }
+    Some(MouseScrollEvent {
+        delta_x,
+        delta_y,
+        x: Some(x),
+        y: Some(y),
+        normalized: None,
+        precise: is_continuous != 0,
+        momentum: momentum_phase != 0,
+    })
+}
+
+extern "C" fn tap_callback(
+    _proxy: *mut c_void,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef {
+    // SAFETY: user_info is the leaked `TapState` installed in `spawn`, valid for the
+    // lifetime of the process.
+    let state = unsafe { &*(user_info as *const TapState) };
+
+    if kind == TAP_DISABLED_BY_TIMEOUT || kind == TAP_DISABLED_BY_USER_INPUT {
+        debug!("Gesture event tap was disabled by the system; re-enabling");
+        unsafe { CGEventTapEnable(state.tap, true) };
+        return event;
+    }
+    if !state.capturing.load(Ordering::SeqCst) {
+        return event;
+    }
+    if kind == SCROLL_WHEEL_EVENT_TYPE {
+        let scroll = unsafe {
+            let location = CGEventGetLocation(event);
+            scroll_event(
+                (location.x, location.y),
+                CGEventGetIntegerValueField(event, FIELD_SCROLL_POINT_DELTA_X),
+                CGEventGetIntegerValueField(event, FIELD_SCROLL_POINT_DELTA_Y),
+                CGEventGetIntegerValueField(event, FIELD_SCROLL_IS_CONTINUOUS),
+                CGEventGetIntegerValueField(event, FIELD_SCROLL_MOMENTUM_PHASE),
+            )
+        };
+        if let Some(scroll) = scroll {
+            let event_type = EventType::MouseScroll(scroll);
+            let input_event = InputEvent {
+                modifiers: state.modifiers.apply(&event_type),
+                seq: state.seq.next(),
+                ..InputEvent::now(state.start_time.elapsed().as_micros() as u64, event_type)
+            };
+            if let Err(e) = state.tx.send(input_event) {
+                debug!("Failed to send scroll event: {}", e);
+            }
+        }
+        return event;
+    }
+    if kind != GESTURE_EVENT_TYPE {
+        return event;
+    }
+
+    let hid_type = unsafe { CGEventGetIntegerValueField(event, FIELD_GESTURE_HID_TYPE) };
+    let Some(kind) = gesture_kind(hid_type) else {
+        return event;
+    };
+    let field = match kind {
+        GestureKind::Magnify => FIELD_GESTURE_ZOOM_VALUE,
+        GestureKind::Rotate => FIELD_GESTURE_ROTATION_VALUE,
+        GestureKind::Swipe => FIELD_GESTURE_SWIPE_VALUE,
+    };
+    let magnitude = unsafe { CGEventGetDoubleValueField(event, field) };
+    // Gesture begin/end phases carry no change; skip them rather than log zeros.
+    if magnitude == 0.0 {
+        return event;
+    }
+
+    let input_event = InputEvent {
+        seq: state.seq.next(),
+        ..InputEvent::now(
+            state.start_time.elapsed().as_micros() as u64,
+            EventType::Gesture(GestureEvent { kind, magnitude }),
+        )
+    };
+    if let Err(e) = state.tx.send(input_event) {
+        debug!("Failed to send gesture event: {}", e);
+    }
+    event
+}
+
+/// Start the scroll (and, with `gestures`, gesture) tap on its own run-loop thread.
+/// Events are dropped while `capturing` is false, mirroring the rdev callback, and scroll
+/// events share `modifiers` with it (and `seq` with every other backend event). Returns a flag that turns true once the tap is live;
+/// until then (or for good, if the tap can't be created) rdev keeps reporting scrolls.
+pub fn spawn(
+    tx: mpsc::UnboundedSender<InputEvent>,
+    capturing: Arc<AtomicBool>,
+    start_time: Instant,
+    modifiers: Arc<ModifierState>,
+    seq: Arc<EventSequence>,
+    gestures: bool,
+) -> Arc<AtomicBool> {
+    let scroll_tapped = Arc::new(AtomicBool::new(false));
+    let tapped = scroll_tapped.clone();
+    thread::spawn(move || {
+        let state = Box::into_raw(Box::new(TapState {
+            tx,
+            capturing,
+            start_time,
+            modifiers,
+            seq,
+            tap: std::ptr::null_mut(),
+        }));
+
+        let mut events_of_interest = 1u64 << SCROLL_WHEEL_EVENT_TYPE;
+        if gestures {
+            events_of_interest |= 1u64 << GESTURE_EVENT_TYPE;
+        }
+
+        unsafe {
+            let tap = CGEventTapCreate(
+                SESSION_EVENT_TAP,
+                HEAD_INSERT_EVENT_TAP,
+                TAP_OPTION_LISTEN_ONLY,
+                events_of_interest,
+                tap_callback,
+                state as *mut c_void,
+            );
+            if tap.is_null() {
+                warn!("Failed to create gesture/scroll event tap (missing Input Monitoring permission?)");
+                drop(Box::from_raw(state));
+                return;
+            }
+            (*state).tap = tap;
+
+            let source = CFMachPortCreateRunLoopSource(std::ptr::null(), tap, 0);
+            CFRunLoopAddSource(CFRunLoopGetCurrent(), source, kCFRunLoopCommonModes);
+            tapped.store(true, Ordering::SeqCst);
+            CGEventTapEnable(tap, true);
+            if gestures {
+                info!("Trackpad gesture capture started");
+            }
+            CFRunLoopRun();
+        }
+    });
+    scroll_tapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gesture_kind_maps_recorded_hid_types() {
+        assert_eq!(gesture_kind(HID_TYPE_ZOOM), Some(GestureKind::Magnify));
+        assert_eq!(gesture_kind(HID_TYPE_ROTATION), Some(GestureKind::Rotate));
+        assert_eq!(
+            gesture_kind(HID_TYPE_NAVIGATION_SWIPE),
+            Some(GestureKind::Swipe)
+        );
+        assert_eq!(gesture_kind(HID_TYPE_DOCK_SWIPE), Some(GestureKind::Swipe));
+        // Scroll and other HID types are already covered by rdev (or not recorded).
+        assert_eq!(gesture_kind(6), None);
+    }
+
+    #[test]
+    fn scroll_event_reads_precision_and_momentum() {
+        let wheel = scroll_event((640.0, 400.0), 0, -3, 0, 0).unwrap();
+        assert_eq!((wheel.delta_x, wheel.delta_y), (0, -3));
+        assert_eq!(wheel.position(), Some((640.0, 400.0)));
+        assert!(!wheel.precise && !wheel.momentum);
+
+        let inertial = scroll_event((640.0, 400.0), 2, 14, 1, 2).unwrap();
+        assert!(inertial.precise && inertial.momentum);
+
+        // Phase-boundary events carry no movement.
+        assert!(scroll_event((640.0, 400.0), 0, 0, 1, 3).is_none());
+    }
+}
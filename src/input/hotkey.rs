@@ -0,0 +1,224 @@
+//! Global hotkeys recognized in the captured input stream.
+//!
+//! The input backends already see every keystroke system-wide, so a hotkey is matched
+//! against the events they deliver rather than through a second OS-level hook. A matching
+//! key press (and its release) is consumed before the filter chain, so the chord's key never
+//! lands in the recording; the modifier presses leading up to it are ordinary input and
+//! stay.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+
+use crate::data::{EventType, InputEvent, ModifierState};
+
+/// Modifier groups a hotkey can require. Left and right keys count the same.
+const MODIFIER_GROUPS: [(&[&str], u32); 4] = [
+    (
+        &["ctrl", "control"],
+        ModifierState::CONTROL_LEFT | ModifierState::CONTROL_RIGHT,
+    ),
+    (
+        &["shift"],
+        ModifierState::SHIFT_LEFT | ModifierState::SHIFT_RIGHT,
+    ),
+    (
+        &["alt", "option"],
+        ModifierState::ALT | ModifierState::ALT_GR,
+    ),
+    (
+        &["cmd", "command", "meta", "super", "win"],
+        ModifierState::META_LEFT | ModifierState::META_RIGHT,
+    ),
+];
+
+/// A key chord such as `ctrl+shift+p`, in the form events carry it: modifier groups
+/// (`ModifierState` bits) and the backend key name of the final key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    /// Union of the required modifier groups' bits
+    modifiers: u32,
+    /// `KeyEvent::name` of the non-modifier key, e.g. "KeyP" or "F13"
+    key: String,
+}
+
+impl Hotkey {
+    /// Whether `name` (a `KeyEvent::name`) is this chord's key
+    fn is_key(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case(&self.key)
+    }
+
+    /// Whether `event` is this chord's key going down with exactly its modifiers held
+    fn matches_press(&self, event: &InputEvent) -> bool {
+        let EventType::KeyPress(key) = &event.event else {
+            return false;
+        };
+        self.is_key(&key.name)
+            && MODIFIER_GROUPS.iter().all(|(_, bits)| {
+                let held = event.modifiers & bits != 0;
+                let required = self.modifiers & bits != 0;
+                held == required
+            })
+    }
+}
+
+/// Backend key name for one (non-modifier) token of a hotkey string
+fn key_name(token: &str) -> String {
+    if let Some(n) = token
+        .strip_prefix(['f', 'F'])
+        .filter(|n| n.parse::<u8>().is_ok())
+    {
+        return format!("F{}", n);
+    }
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => format!("Key{}", c.to_ascii_uppercase()),
+        (Some(c), None) if c.is_ascii_digit() => format!("Num{}", c),
+        // Anything else is a backend key name ("Space", "PageUp", ...), matched ignoring case
+        _ => token.to_string(),
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = anyhow::Error;
+
+    /// Parse `+`-separated modifiers followed by one key, case-insensitively:
+    /// `ctrl+shift+p`, `cmd+alt+9`, `F13`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = 0;
+        let mut key = None;
+        for token in s.split('+').map(str::trim) {
+            if token.is_empty() {
+                bail!("empty key in hotkey '{}'", s);
+            }
+            if key.is_some() {
+                bail!("hotkey '{}' must end with its one non-modifier key", s);
+            }
+            let lower = token.to_ascii_lowercase();
+            match MODIFIER_GROUPS
+                .iter()
+                .find(|(names, _)| names.contains(&lower.as_str()))
+            {
+                Some((_, bits)) => modifiers |= bits,
+                None => key = Some(key_name(token)),
+            }
+        }
+        let Some(key) = key else {
+            bail!("hotkey '{}' has no key besides modifiers", s);
+        };
+        Ok(Self { modifiers, key })
+    }
+}
+
+/// Consumes a hotkey's events from the input stream.
+#[derive(Debug)]
+pub struct HotkeyMatcher {
+    hotkey: Hotkey,
+    /// The chord fired and its key is still down, so its release is consumed too
+    key_held: bool,
+}
+
+impl HotkeyMatcher {
+    pub fn new(hotkey: Hotkey) -> Self {
+        Self {
+            hotkey,
+            key_held: false,
+        }
+    }
+
+    /// Check `event` against the hotkey. Returns `Some(true)` when the chord was just
+    /// pressed, `Some(false)` for its key's release or auto-repeat (consume quietly), and
+    /// `None` for every other event.
+    pub fn check(&mut self, event: &InputEvent) -> Option<bool> {
+        match &event.event {
+            EventType::KeyPress(key) if self.key_held && self.hotkey.is_key(&key.name) => {
+                Some(false)
+            }
+            EventType::KeyPress(_) if self.hotkey.matches_press(event) => {
+                self.key_held = true;
+                Some(true)
+            }
+            EventType::KeyRelease(key) if self.key_held && self.hotkey.is_key(&key.name) => {
+                self.key_held = false;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::KeyEvent;
+
+    fn key(press: bool, name: &str, modifiers: u32) -> InputEvent {
+        let key = KeyEvent {
+            code: 0,
+            name: name.to_string(),
+            is_repeat: false,
+        };
+        InputEvent {
+            modifiers,
+            ..InputEvent::now(
+                0,
+                if press {
+                    EventType::KeyPress(key)
+                } else {
+                    EventType::KeyRelease(key)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn parses_hotkey_strings() {
+        let hotkey: Hotkey = "Ctrl+Shift+P".parse().unwrap();
+        assert_eq!(
+            hotkey,
+            Hotkey {
+                modifiers: ModifierState::CONTROL_LEFT
+                    | ModifierState::CONTROL_RIGHT
+                    | ModifierState::SHIFT_LEFT
+                    | ModifierState::SHIFT_RIGHT,
+                key: "KeyP".to_string(),
+            }
+        );
+        assert_eq!("cmd + option + 9".parse::<Hotkey>().unwrap().key, "Num9");
+        assert_eq!(
+            "f13".parse::<Hotkey>().unwrap(),
+            Hotkey {
+                modifiers: 0,
+                key: "F13".to_string(),
+            }
+        );
+        assert!("alt+space".parse::<Hotkey>().unwrap().is_key("Space"));
+
+        assert!("ctrl+shift".parse::<Hotkey>().is_err());
+        assert!("ctrl++p".parse::<Hotkey>().is_err());
+        assert!("p+q".parse::<Hotkey>().is_err());
+    }
+
+    #[test]
+    fn matcher_consumes_the_chord_and_its_release() {
+        let ctrl_shift = ModifierState::CONTROL_LEFT | ModifierState::SHIFT_RIGHT;
+        let mut matcher = HotkeyMatcher::new("ctrl+shift+p".parse().unwrap());
+
+        // Wrong modifiers (missing one, or an extra one held) don't fire.
+        assert_eq!(
+            matcher.check(&key(true, "KeyP", ModifierState::CONTROL_LEFT)),
+            None
+        );
+        assert_eq!(
+            matcher.check(&key(true, "KeyP", ctrl_shift | ModifierState::ALT)),
+            None
+        );
+        assert_eq!(matcher.check(&key(false, "KeyP", ctrl_shift)), None);
+
+        assert_eq!(matcher.check(&key(true, "KeyP", ctrl_shift)), Some(true));
+        // Auto-repeat and the release are swallowed, even with the modifiers let go first.
+        assert_eq!(matcher.check(&key(true, "KeyP", ctrl_shift)), Some(false));
+        assert_eq!(matcher.check(&key(false, "KeyP", 0)), Some(false));
+        assert_eq!(matcher.check(&key(false, "KeyP", 0)), None);
+    }
+}
@@ -1,9 +1,15 @@
 //! Input capture backends
 
 mod backend;
+pub(crate) mod filter;
+#[cfg(target_os = "macos")]
+pub(crate) mod gesture_backend;
+pub(crate) mod hotkey;
 #[cfg(not(target_os = "linux"))]
 pub(crate) mod rdev_backend;
 pub(crate) mod secure;
+#[cfg(target_os = "macos")]
+pub(crate) mod text_commit_backend;
 
 #[cfg(target_os = "linux")]
 pub(crate) mod evdev_backend;
@@ -2,11 +2,12 @@
 //! Works on Windows, macOS, and Linux (X11)
 
 use crate::data::{
-    EventType, InputEvent, KeyEvent, MouseButton, MouseButtonEvent, MouseMoveEvent,
-    MouseScrollEvent,
+    epoch_us, EventSequence, EventType, InputEvent, KeyEvent, ModifierState, MouseButton,
+    MouseButtonEvent, MouseMoveEvent, MouseScrollEvent,
 };
 use crate::input::InputBackend;
 use anyhow::Result;
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -19,6 +20,10 @@ pub struct RdevBackend {
     capturing: Arc<AtomicBool>,
     /// The instant when the backend was started, used for timestamp calculation
     start_time: Option<Instant>,
+    /// Also capture trackpad gestures (macOS only; see `gesture_backend`)
+    capture_gestures: bool,
+    /// Also capture IME text commits (macOS only; see `text_commit_backend`)
+    capture_text_commits: bool,
 }
 
 impl RdevBackend {
@@ -27,8 +32,22 @@ impl RdevBackend {
         Self {
             capturing: Arc::new(AtomicBool::new(false)),
             start_time: None,
+            capture_gestures: false,
+            capture_text_commits: false,
         }
     }
+
+    /// Enable trackpad gesture capture. No-op outside macOS.
+    pub fn with_gestures(mut self, enabled: bool) -> Self {
+        self.capture_gestures = enabled;
+        self
+    }
+
+    /// Enable IME text-commit capture. No-op outside macOS.
+    pub fn with_text_commits(mut self, enabled: bool) -> Self {
+        self.capture_text_commits = enabled;
+        self
+    }
 }
 
 impl Default for RdevBackend {
@@ -48,6 +67,43 @@ impl InputBackend for RdevBackend {
         let start_time = Instant::now();
         self.start_time = Some(start_time);
 
+        let modifiers = Arc::new(ModifierState::default());
+        // Shared with the macOS taps, so their events number into the same stream.
+        let seq = Arc::new(EventSequence::default());
+
+        // macOS: a second tap reports scrolls with precision/momentum (and gestures when
+        // enabled); rdev's own wheel events are dropped once it is running.
+        #[cfg(target_os = "macos")]
+        let scroll_tapped = super::gesture_backend::spawn(
+            tx.clone(),
+            capturing.clone(),
+            start_time,
+            modifiers.clone(),
+            seq.clone(),
+            self.capture_gestures,
+        );
+        #[cfg(not(target_os = "macos"))]
+        let scroll_tapped = {
+            if self.capture_gestures {
+                debug!("Trackpad gesture capture is only supported on macOS; ignoring");
+            }
+            Arc::new(AtomicBool::new(false))
+        };
+
+        #[cfg(target_os = "macos")]
+        if self.capture_text_commits {
+            super::text_commit_backend::spawn(
+                tx.clone(),
+                capturing.clone(),
+                start_time,
+                seq.clone(),
+            );
+        }
+        #[cfg(not(target_os = "macos"))]
+        if self.capture_text_commits {
+            debug!("IME text commit capture is only supported on macOS; ignoring");
+        }
+
         let handle = thread::spawn(move || {
             // CRITICAL (macOS): Tell rdev we're NOT on the main thread so it dispatches
             // TSM (Text Services Manager) API calls to the main thread via GCD.
@@ -59,57 +115,87 @@ impl InputBackend for RdevBackend {
 
             info!("rdev input capture started");
 
+            // rdev reports auto-repeat as further presses; a press of a key that is
+            // already down is one.
+            let mut held_keys = HashSet::new();
+            // Button and wheel events carry no position; they happen where the pointer
+            // last moved to. Unknown until the first move.
+            let mut pointer: Option<(f64, f64)> = None;
             let callback = move |event: rdev::Event| {
-                if !capturing.load(Ordering::SeqCst) {
-                    return;
-                }
+                // Held keys, the pointer and modifiers are tracked while not capturing
+                // too, so a key released meanwhile doesn't make its next press a repeat.
+                let capturing = capturing.load(Ordering::SeqCst);
 
                 let timestamp_us = start_time.elapsed().as_micros() as u64;
 
                 let event_type = match event.event_type {
                     rdev::EventType::KeyPress(key) => {
-                        Some(EventType::KeyPress(KeyEvent::from(key)))
+                        let mut key = KeyEvent::from(key);
+                        key.is_repeat = !held_keys.insert(key.code);
+                        Some(EventType::KeyPress(key))
                     }
                     rdev::EventType::KeyRelease(key) => {
-                        Some(EventType::KeyRelease(KeyEvent::from(key)))
+                        let key = KeyEvent::from(key);
+                        held_keys.remove(&key.code);
+                        Some(EventType::KeyRelease(key))
                     }
                     rdev::EventType::ButtonPress(button) => {
-                        // Get current mouse position from the event
                         Some(EventType::MousePress(MouseButtonEvent {
                             button: MouseButton::from(button),
-                            x: 0.0, // rdev doesn't provide position with button events
-                            y: 0.0,
+                            x: pointer.map(|(x, _)| x),
+                            y: pointer.map(|(_, y)| y),
+                            normalized: None,
                         }))
                     }
                     rdev::EventType::ButtonRelease(button) => {
                         Some(EventType::MouseRelease(MouseButtonEvent {
                             button: MouseButton::from(button),
-                            x: 0.0,
-                            y: 0.0,
+                            x: pointer.map(|(x, _)| x),
+                            y: pointer.map(|(_, y)| y),
+                            normalized: None,
                         }))
                     }
                     rdev::EventType::MouseMove {
-                        delta_x, delta_y, ..
-                    } => Some(EventType::MouseMove(MouseMoveEvent { delta_x, delta_y })),
+                        x,
+                        y,
+                        delta_x,
+                        delta_y,
+                        ..
+                    } => {
+                        pointer = Some((x, y));
+                        Some(EventType::MouseMove(MouseMoveEvent { delta_x, delta_y }))
+                    }
+                    rdev::EventType::Wheel { .. } if scroll_tapped.load(Ordering::SeqCst) => None,
                     rdev::EventType::Wheel { delta_x, delta_y } => {
                         Some(EventType::MouseScroll(MouseScrollEvent {
                             delta_x,
                             delta_y,
-                            x: 0.0,
-                            y: 0.0,
+                            x: pointer.map(|(x, _)| x),
+                            y: pointer.map(|(_, y)| y),
+                            normalized: None,
+                            precise: false,
+                            momentum: false,
                         }))
                     }
                 };
 
-                if let Some(event_type) = event_type {
-                    let input_event = InputEvent {
-                        timestamp_us,
-                        event: event_type,
-                    };
+                let Some(event_type) = event_type else {
+                    return;
+                };
+                let modifiers = modifiers.apply(&event_type);
+                if !capturing {
+                    return;
+                }
+                let input_event = InputEvent {
+                    timestamp_us,
+                    modifiers,
+                    event: event_type,
+                    absolute_us: epoch_us(event.time),
+                    seq: seq.next(),
+                };
 
-                    if let Err(e) = tx.send(input_event) {
-                        debug!("Failed to send input event: {}", e);
-                    }
+                if let Err(e) = tx.send(input_event) {
+                    debug!("Failed to send input event: {}", e);
                 }
             };
 
@@ -66,12 +66,12 @@ pub async fn run(
                 debug!("secure-input: password field focused; suppressing key capture");
                 // Label the gap for post-processing. timestamp_us=0 is re-stamped to
                 // recording time by the sync engine before buffering.
-                let _ = marker_tx.send(InputEvent {
-                    timestamp_us: 0,
-                    event: EventType::Redacted(RedactedEvent {
+                let _ = marker_tx.send(InputEvent::now(
+                    0,
+                    EventType::Redacted(RedactedEvent {
                         reason: "secure-field".to_string(),
                     }),
-                });
+                ));
             }
             Transition::Left => {
                 debug!("secure-input: password field blurred; resuming key capture")
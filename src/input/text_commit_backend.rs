@@ -0,0 +1,351 @@
+//! IME text-commit capture (macOS only)
+//!
+//! Key events show which keys were pressed, not what an input method made of them: a
+//! Japanese or Chinese IME turns a run of romaji/pinyin keystrokes into text inside the
+//! target app, and that text never passes through an event tap. Accessibility does see it.
+//! Text views post `AXValueChanged` with an edit description (`AXTextStateChangeType` =
+//! edit, plus an `AXTextEditType` and the inserted `AXTextChangeValue` per change), the
+//! same information VoiceOver echoes typed text from. An `AXObserver` on the frontmost app
+//! turns those edits into `TextCommit` events on the shared input channel.
+//!
+//! Plain typing is reported as one edit per keystroke, which the key events already
+//! cover, so single ASCII-character insertions are skipped. Nothing is read from secure
+//! text fields or while Secure Event Input is on; the engine's secure-input filter also
+//! redacts commits like key events.
+
+use crate::data::{EventSequence, EventType, InputEvent, TextCommitEvent};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+type AXObserverRef = *mut c_void;
+type AXUIElementRef = *const c_void;
+type CFStringRef = *const c_void;
+type CFDictionaryRef = *const c_void;
+type AXObserverCallbackWithInfo = extern "C" fn(
+    observer: AXObserverRef,
+    element: AXUIElementRef,
+    notification: CFStringRef,
+    info: CFDictionaryRef,
+    refcon: *mut c_void,
+);
+
+/// How often the frontmost app is checked, so the observer follows focus
+const FRONTMOST_POLL: Duration = Duration::from_millis(250);
+
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+/// `kCFNumberSInt64Type`
+const K_CF_NUMBER_SINT64_TYPE: i32 = 4;
+/// `kCFRunLoopRunFinished`: the run loop has no sources to wait on
+const RUN_LOOP_RUN_FINISHED: i32 = 1;
+
+/// `kAXTextStateChangeTypeEdit`
+const TEXT_STATE_CHANGE_EDIT: i64 = 1;
+/// `kAXTextEditTypeInsert`, `kAXTextEditTypeTyping` and `kAXTextEditTypeDictation`: the
+/// edits that add text. Deletes, cuts, pastes and attribute changes are not commits.
+const TEXT_EDIT_INSERT: i64 = 2;
+const TEXT_EDIT_TYPING: i64 = 3;
+const TEXT_EDIT_DICTATION: i64 = 4;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXObserverCreateWithInfoCallback(
+        pid: i32,
+        callback: AXObserverCallbackWithInfo,
+        observer: *mut AXObserverRef,
+    ) -> i32;
+    fn AXObserverAddNotification(
+        observer: AXObserverRef,
+        element: AXUIElementRef,
+        notification: CFStringRef,
+        refcon: *mut c_void,
+    ) -> i32;
+    fn AXObserverGetRunLoopSource(observer: AXObserverRef) -> *mut c_void;
+    fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+    fn AXUIElementCopyAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: *mut *const c_void,
+    ) -> i32;
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    // `Boolean` (unsigned char)
+    fn IsSecureEventInputEnabled() -> u8;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFRunLoopDefaultMode: *const c_void;
+    fn CFRunLoopGetCurrent() -> *mut c_void;
+    fn CFRunLoopAddSource(run_loop: *mut c_void, source: *mut c_void, mode: *const c_void);
+    fn CFRunLoopRemoveSource(run_loop: *mut c_void, source: *mut c_void, mode: *const c_void);
+    fn CFRunLoopRunInMode(mode: *const c_void, seconds: f64, return_after_source: u8) -> i32;
+    fn CFStringCreateWithCString(
+        alloc: *const c_void,
+        c_str: *const i8,
+        encoding: u32,
+    ) -> CFStringRef;
+    fn CFStringGetLength(string: CFStringRef) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(length: isize, encoding: u32) -> isize;
+    fn CFStringGetCString(string: CFStringRef, buffer: *mut i8, size: isize, encoding: u32) -> u8;
+    fn CFDictionaryGetValue(dict: CFDictionaryRef, key: *const c_void) -> *const c_void;
+    fn CFArrayGetCount(array: *const c_void) -> isize;
+    fn CFArrayGetValueAtIndex(array: *const c_void, idx: isize) -> *const c_void;
+    fn CFNumberGetValue(number: *const c_void, the_type: i32, value: *mut c_void) -> u8;
+    fn CFRelease(cf: *const c_void);
+}
+
+/// State handed to the observer callback through `refcon`
+struct ObserverState {
+    tx: mpsc::UnboundedSender<InputEvent>,
+    capturing: Arc<AtomicBool>,
+    start_time: Instant,
+    seq: Arc<EventSequence>,
+}
+
+/// Observer registered on one app
+struct Observed {
+    pid: u32,
+    observer: AXObserverRef,
+    app: AXUIElementRef,
+}
+
+/// The text an edit contributes as a commit, if any: insertions (IME commits, dictation,
+/// composed characters), except the single ASCII characters plain typing produces
+fn commit_text(edit_type: i64, text: &str) -> Option<&str> {
+    if !matches!(
+        edit_type,
+        TEXT_EDIT_INSERT | TEXT_EDIT_TYPING | TEXT_EDIT_DICTATION
+    ) {
+        return None;
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => None,
+        (Some(c), None) if c.is_ascii() => None,
+        _ => Some(text),
+    }
+}
+
+/// A CFString for a NUL-terminated name. The caller releases it.
+unsafe fn cfstring(name: &[u8]) -> CFStringRef {
+    CFStringCreateWithCString(
+        std::ptr::null(),
+        name.as_ptr() as *const i8,
+        K_CF_STRING_ENCODING_UTF8,
+    )
+}
+
+unsafe fn cfstring_to_string(string: CFStringRef) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    let size =
+        CFStringGetMaximumSizeForEncoding(CFStringGetLength(string), K_CF_STRING_ENCODING_UTF8) + 1;
+    let mut buffer = vec![0u8; usize::try_from(size).ok()?];
+    if CFStringGetCString(
+        string,
+        buffer.as_mut_ptr() as *mut i8,
+        size,
+        K_CF_STRING_ENCODING_UTF8,
+    ) == 0
+    {
+        return None;
+    }
+    let len = buffer.iter().position(|&b| b == 0)?;
+    buffer.truncate(len);
+    String::from_utf8(buffer).ok()
+}
+
+/// Value for NUL-terminated `key` in a CF dictionary (borrowed, not retained)
+unsafe fn dictionary_value(dict: CFDictionaryRef, key: &[u8]) -> *const c_void {
+    let key = cfstring(key);
+    if key.is_null() {
+        return std::ptr::null();
+    }
+    let value = CFDictionaryGetValue(dict, key);
+    CFRelease(key);
+    value
+}
+
+unsafe fn dictionary_i64(dict: CFDictionaryRef, key: &[u8]) -> Option<i64> {
+    let number = dictionary_value(dict, key);
+    let mut value = 0i64;
+    (!number.is_null()
+        && CFNumberGetValue(
+            number,
+            K_CF_NUMBER_SINT64_TYPE,
+            &mut value as *mut i64 as *mut c_void,
+        ) != 0)
+        .then_some(value)
+}
+
+/// Whether `element` is a password field, whose edits are never read
+unsafe fn is_secure_field(element: AXUIElementRef) -> bool {
+    let attribute = cfstring(b"AXRole\0");
+    if attribute.is_null() {
+        return false;
+    }
+    let mut role: *const c_void = std::ptr::null();
+    let err = AXUIElementCopyAttributeValue(element, attribute, &mut role);
+    CFRelease(attribute);
+    if err != 0 || role.is_null() {
+        return false;
+    }
+    let secure = cfstring_to_string(role).as_deref() == Some("AXSecureTextField");
+    CFRelease(role);
+    secure
+}
+
+extern "C" fn observer_callback(
+    _observer: AXObserverRef,
+    element: AXUIElementRef,
+    _notification: CFStringRef,
+    info: CFDictionaryRef,
+    refcon: *mut c_void,
+) {
+    // SAFETY: refcon is the leaked `ObserverState` installed in `spawn`, valid for the
+    // lifetime of the process.
+    let state = unsafe { &*(refcon as *const ObserverState) };
+    if info.is_null() || !state.capturing.load(Ordering::SeqCst) {
+        return;
+    }
+
+    unsafe {
+        if IsSecureEventInputEnabled() != 0 || is_secure_field(element) {
+            return;
+        }
+        if dictionary_i64(info, b"AXTextStateChangeType\0") != Some(TEXT_STATE_CHANGE_EDIT) {
+            return;
+        }
+        let changes = dictionary_value(info, b"AXTextChangeValues\0");
+        if changes.is_null() {
+            return;
+        }
+        for i in 0..CFArrayGetCount(changes) {
+            let change = CFArrayGetValueAtIndex(changes, i);
+            let Some(edit_type) = dictionary_i64(change, b"AXTextEditType\0") else {
+                continue;
+            };
+            let Some(text) = cfstring_to_string(dictionary_value(change, b"AXTextChangeValue\0"))
+            else {
+                continue;
+            };
+            let Some(text) = commit_text(edit_type, &text) else {
+                continue;
+            };
+            let event = InputEvent {
+                seq: state.seq.next(),
+                ..InputEvent::now(
+                    state.start_time.elapsed().as_micros() as u64,
+                    EventType::TextCommit(TextCommitEvent {
+                        text: text.to_string(),
+                    }),
+                )
+            };
+            if let Err(e) = state.tx.send(event) {
+                debug!("Failed to send text commit event: {}", e);
+            }
+        }
+    }
+}
+
+/// Register the observer on app `pid` and add it to this thread's run loop
+unsafe fn observe(pid: u32, state: *mut c_void) -> Option<Observed> {
+    let mut observer: AXObserverRef = std::ptr::null_mut();
+    if AXObserverCreateWithInfoCallback(pid as i32, observer_callback, &mut observer) != 0
+        || observer.is_null()
+    {
+        return None;
+    }
+    let app = AXUIElementCreateApplication(pid as i32);
+    let notification = cfstring(b"AXValueChanged\0");
+    let err = AXObserverAddNotification(observer, app, notification, state);
+    CFRelease(notification);
+    if err != 0 {
+        debug!("App {} doesn't report text edits (AX error {})", pid, err);
+        CFRelease(app);
+        CFRelease(observer);
+        return None;
+    }
+    CFRunLoopAddSource(
+        CFRunLoopGetCurrent(),
+        AXObserverGetRunLoopSource(observer),
+        kCFRunLoopDefaultMode,
+    );
+    Some(Observed { pid, observer, app })
+}
+
+unsafe fn stop_observing(observed: Observed) {
+    CFRunLoopRemoveSource(
+        CFRunLoopGetCurrent(),
+        AXObserverGetRunLoopSource(observed.observer),
+        kCFRunLoopDefaultMode,
+    );
+    CFRelease(observed.app);
+    CFRelease(observed.observer);
+}
+
+/// Start text-commit capture on its own run-loop thread, following the frontmost app.
+/// Events are dropped while `capturing` is false, mirroring the rdev callback.
+pub fn spawn(
+    tx: mpsc::UnboundedSender<InputEvent>,
+    capturing: Arc<AtomicBool>,
+    start_time: Instant,
+    seq: Arc<EventSequence>,
+) {
+    thread::spawn(move || {
+        let state = Box::into_raw(Box::new(ObserverState {
+            tx,
+            capturing,
+            start_time,
+            seq,
+        })) as *mut c_void;
+        info!("IME text commit capture started");
+
+        let mut observed: Option<Observed> = None;
+        loop {
+            let pid = crate::capture::get_frontmost_app().map(|app| app.pid);
+            if pid != observed.as_ref().map(|o| o.pid) {
+                if let Some(old) = observed.take() {
+                    unsafe { stop_observing(old) };
+                }
+                observed = pid.and_then(|pid| unsafe { observe(pid, state) });
+            }
+            let result = unsafe {
+                CFRunLoopRunInMode(kCFRunLoopDefaultMode, FRONTMOST_POLL.as_secs_f64(), 0)
+            };
+            // Nothing to wait on (no observable frontmost app): don't spin.
+            if result == RUN_LOOP_RUN_FINISHED {
+                thread::sleep(FRONTMOST_POLL);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_text_keeps_insertions_beyond_plain_typing() {
+        assert_eq!(commit_text(TEXT_EDIT_TYPING, "日本語"), Some("日本語"));
+        assert_eq!(commit_text(TEXT_EDIT_INSERT, "é"), Some("é"));
+        assert_eq!(
+            commit_text(TEXT_EDIT_DICTATION, "hello there"),
+            Some("hello there")
+        );
+        // A single typed ASCII character is already in the key events.
+        assert_eq!(commit_text(TEXT_EDIT_TYPING, "a"), None);
+        assert_eq!(commit_text(TEXT_EDIT_TYPING, ""), None);
+        // Deletes (1), pastes (6) and other edits aren't commits.
+        assert_eq!(commit_text(1, "日本"), None);
+        assert_eq!(commit_text(6, "pasted text"), None);
+    }
+}
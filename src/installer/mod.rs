@@ -23,5 +23,8 @@ pub mod gnome_focus;
 
 pub use autostart::*;
 pub use permissions::*;
-pub use wizard::{needs_setup, run_wizard, run_wizard_async, WizardResult};
+pub use wizard::{
+    needs_setup, permission_issues, run_wizard, run_wizard_async, run_wizard_with_progress,
+    Permission, SetupIssue, StepState, WizardProgress, WizardResult,
+};
 pub use wizard_gui::{run_wizard_gui, WizardResult as GuiWizardResult};
@@ -422,6 +422,21 @@ pub fn all_permissions_granted() -> bool {
         && status.input_group.is_granted()
 }
 
+/// Whether the permission input capture depends on at runtime is still granted. Only
+/// macOS's Accessibility can be revoked while the agent runs ('input' group membership
+/// is fixed for the login session), so this is always true elsewhere.
+pub fn input_permission_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        check_accessibility_macos().is_granted()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
 /// Get a human-readable description of missing permissions
 pub fn describe_missing_permissions() -> Vec<String> {
     let status = check_permissions();
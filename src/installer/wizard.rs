@@ -7,11 +7,152 @@
 
 use anyhow::{Context, Result};
 use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 
 use crate::capture::{list_capturable_apps, AppInfo};
 use crate::config::Config;
-use crate::installer::permissions::{check_permissions, request_permissions, PermissionState};
+use crate::installer::permissions::{
+    check_permissions, request_permissions, PermissionState, PermissionStatus,
+};
+
+/// A permission the wizard checks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Accessibility (macOS keyboard/mouse capture)
+    Accessibility,
+    /// Screen Recording (macOS window capture)
+    ScreenRecording,
+    /// `input` group membership (Linux Wayland input capture)
+    InputGroup,
+}
+
+/// A specific problem setup ran into, for callers that need to react to it rather than
+/// read the console output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupIssue {
+    /// A required permission is still missing after asking for it
+    PermissionDenied(Permission),
+    /// Setup finished without capture-all and without any app to capture, so no input
+    /// will be recorded
+    NoAppsSelected,
+    /// Start-on-login was chosen but could not be registered
+    AutostartFailed(String),
+}
+
+impl SetupIssue {
+    /// Process exit code for this issue when setup can't prompt (1 and 2 are taken by
+    /// generic failures and usage errors)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SetupIssue::PermissionDenied(Permission::Accessibility) => 3,
+            SetupIssue::PermissionDenied(Permission::ScreenRecording) => 4,
+            SetupIssue::PermissionDenied(Permission::InputGroup) => 5,
+            SetupIssue::NoAppsSelected => 6,
+            SetupIssue::AutostartFailed(_) => 7,
+        }
+    }
+}
+
+impl std::fmt::Display for SetupIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetupIssue::PermissionDenied(Permission::Accessibility) => {
+                write!(f, "Accessibility permission not granted (no input capture)")
+            }
+            SetupIssue::PermissionDenied(Permission::ScreenRecording) => {
+                write!(f, "Screen Recording permission not granted (no video)")
+            }
+            SetupIssue::PermissionDenied(Permission::InputGroup) => {
+                write!(f, "not in the 'input' group (no Wayland input capture)")
+            }
+            SetupIssue::NoAppsSelected => {
+                write!(f, "no applications selected, so no input will be captured")
+            }
+            SetupIssue::AutostartFailed(e) => write!(f, "start on login not enabled: {}", e),
+        }
+    }
+}
+
+/// Issues for every permission in `perms` that is required here and not granted
+pub fn permission_issues(perms: &PermissionStatus) -> Vec<SetupIssue> {
+    [
+        (perms.accessibility, Permission::Accessibility),
+        (perms.screen_recording, Permission::ScreenRecording),
+        (perms.input_group, Permission::InputGroup),
+    ]
+    .into_iter()
+    .filter(|(state, _)| !state.is_granted())
+    .map(|(_, permission)| SetupIssue::PermissionDenied(permission))
+    .collect()
+}
+
+/// Wizard steps in order, as labeled in [`WizardProgress`]
+const STEPS: [&str; 4] = [
+    "Checking permissions",
+    "Selecting applications",
+    "Configuring autostart",
+    "Saving configuration",
+];
+
+/// Where a wizard step stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepState {
+    Started,
+    Completed,
+    /// The step failed; setup either stopped there or carried on without it (see
+    /// [`WizardResult::issues`])
+    Failed,
+}
+
+/// Sent at each step transition so a UI can show real progress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WizardProgress {
+    /// 1-based step number
+    pub step: usize,
+    pub total: usize,
+    pub label: &'static str,
+    pub state: StepState,
+}
+
+/// Sends [`WizardProgress`] updates when a caller asked for them
+struct ProgressReporter {
+    tx: Option<mpsc::UnboundedSender<WizardProgress>>,
+}
+
+impl ProgressReporter {
+    fn report(&self, step: usize, state: StepState) {
+        if let Some(tx) = &self.tx {
+            // A receiver that went away has just stopped listening
+            let _ = tx.send(WizardProgress {
+                step,
+                total: STEPS.len(),
+                label: STEPS[step - 1],
+                state,
+            });
+        }
+    }
+}
+
+/// How often a prompt waiting on stdin checks whether setup was cancelled
+const PROMPT_CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// A prompt gave up because setup was cancelled
+#[derive(Debug)]
+struct WizardCancelled;
+
+impl std::fmt::Display for WizardCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "setup wizard cancelled")
+    }
+}
+
+impl std::error::Error for WizardCancelled {}
 
 /// Result of running the setup wizard
 #[derive(Debug)]
@@ -24,18 +165,122 @@ pub struct WizardResult {
     pub capture_all: bool,
     /// Whether autostart was enabled
     pub autostart_enabled: bool,
+    /// Problems found along the way; setup can succeed with some (e.g. a permission the
+    /// user chose to continue without)
+    pub issues: Vec<SetupIssue>,
+    /// Setup was cancelled before it changed anything
+    pub cancelled: bool,
+}
+
+impl WizardResult {
+    fn cancelled() -> Self {
+        Self {
+            success: false,
+            selected_apps: vec![],
+            capture_all: false,
+            autostart_enabled: false,
+            issues: vec![],
+            cancelled: true,
+        }
+    }
 }
 
 /// Run the setup wizard
 pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
+    run_wizard_with_progress(config, None, &CancellationToken::new())
+}
+
+/// Everything the wizard touches outside itself: the console prompts, the permission
+/// checks, the app list, autostart and the config file. [`SystemEnv`] outside tests.
+trait WizardEnv {
+    fn check_permissions(&self) -> PermissionStatus;
+    fn request_permissions(&self) -> Result<PermissionStatus>;
+    fn list_apps(&self) -> Vec<AppInfo>;
+    /// Next line the user typed, failing with `WizardCancelled` once `cancel` fires
+    fn read_line(&mut self, cancel: &CancellationToken) -> Result<String>;
+    fn enable_autostart(&self) -> Result<()>;
+    fn disable_autostart(&self) -> Result<()>;
+    /// Mark setup completed in `config` and write it to its file
+    fn save(&self, config: &mut Config) -> Result<()>;
+}
+
+/// The real console and system
+struct SystemEnv;
+
+impl WizardEnv for SystemEnv {
+    fn check_permissions(&self) -> PermissionStatus {
+        check_permissions()
+    }
+
+    fn request_permissions(&self) -> Result<PermissionStatus> {
+        request_permissions()
+    }
+
+    fn list_apps(&self) -> Vec<AppInfo> {
+        list_capturable_apps()
+    }
+
+    fn read_line(&mut self, cancel: &CancellationToken) -> Result<String> {
+        read_line(cancel)
+    }
+
+    fn enable_autostart(&self) -> Result<()> {
+        crate::installer::autostart::enable_autostart(
+            &crate::installer::autostart::AutostartConfig::default(),
+        )
+    }
+
+    fn disable_autostart(&self) -> Result<()> {
+        crate::installer::autostart::disable_autostart()
+    }
+
+    fn save(&self, config: &mut Config) -> Result<()> {
+        Ok(config.complete_setup()?)
+    }
+}
+
+/// Run the setup wizard, sending a [`WizardProgress`] to `progress_tx` at each step
+/// transition. The console output is the same either way. Cancelling `cancel` stops the
+/// wizard between steps or while a prompt waits for input; nothing more is applied
+/// (autostart, config) and the result is marked cancelled.
+pub fn run_wizard_with_progress(
+    config: &mut Config,
+    progress_tx: Option<mpsc::UnboundedSender<WizardProgress>>,
+    cancel: &CancellationToken,
+) -> Result<WizardResult> {
+    run_wizard_in(&mut SystemEnv, config, progress_tx, cancel)
+}
+
+/// [`run_wizard_with_progress`] against `env`
+fn run_wizard_in(
+    env: &mut dyn WizardEnv,
+    config: &mut Config,
+    progress_tx: Option<mpsc::UnboundedSender<WizardProgress>>,
+    cancel: &CancellationToken,
+) -> Result<WizardResult> {
+    let progress = ProgressReporter { tx: progress_tx };
+    match run_steps(env, config, &progress, cancel) {
+        Err(e) if e.is::<WizardCancelled>() => Ok(WizardResult::cancelled()),
+        result => result,
+    }
+}
+
+/// The wizard itself; a prompt cancelled while waiting fails with `WizardCancelled`
+fn run_steps(
+    env: &mut dyn WizardEnv,
+    config: &mut Config,
+    progress: &ProgressReporter,
+    cancel: &CancellationToken,
+) -> Result<WizardResult> {
     println!("\n=================================================");
     println!("  crowd-cast Setup Wizard");
     println!("=================================================\n");
 
     // Step 1: Check and request permissions
     println!("Step 1: Checking permissions...\n");
+    progress.report(1, StepState::Started);
 
-    let perms = check_permissions();
+    let perms = env.check_permissions();
     let mut all_granted = true;
 
     if !perms.accessibility.is_granted() {
@@ -57,9 +302,11 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
         all_granted = false;
     }
 
+    let mut issues = Vec::new();
     if !all_granted {
         println!("\nRequesting missing permissions...");
-        let new_perms = request_permissions()?;
+        let new_perms = env.request_permissions()?;
+        issues = permission_issues(&new_perms);
 
         // Check again
         if !new_perms.accessibility.is_granted() {
@@ -69,12 +316,15 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
             );
             println!("Then restart crowd-cast.\n");
 
-            if !prompt_continue("Continue anyway?")? {
+            if !prompt_continue(env, "Continue anyway?", cancel)? {
+                progress.report(1, StepState::Failed);
                 return Ok(WizardResult {
                     success: false,
                     selected_apps: vec![],
                     capture_all: false,
                     autostart_enabled: false,
+                    issues,
+                    cancelled: false,
                 });
             }
         }
@@ -84,12 +334,15 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
             println!("Please grant permission in System Settings > Privacy & Security > Screen Recording");
             println!("Then restart crowd-cast.\n");
 
-            if !prompt_continue("Continue anyway?")? {
+            if !prompt_continue(env, "Continue anyway?", cancel)? {
+                progress.report(1, StepState::Failed);
                 return Ok(WizardResult {
                     success: false,
                     selected_apps: vec![],
                     capture_all: false,
                     autostart_enabled: false,
+                    issues,
+                    cancelled: false,
                 });
             }
         }
@@ -98,36 +351,49 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
     println!();
 
     // Step 2: Application selection
+    progress.report(1, StepState::Completed);
+    if cancel.is_cancelled() {
+        return Ok(WizardResult::cancelled());
+    }
     println!("Step 2: Select applications to capture\n");
+    progress.report(2, StepState::Started);
     println!("Input will only be captured when one of the selected");
     println!("applications is in the foreground.\n");
 
-    let capture_all = prompt_yes_no("Capture input for ALL applications?")?;
+    let capture_all = prompt_yes_no(env, "Capture input for ALL applications?", cancel)?;
 
     let selected_apps = if capture_all {
         println!("\nAll applications will be captured.\n");
         vec![]
     } else {
         println!("\nLoading running applications...\n");
-        let apps = list_capturable_apps();
+        let apps = env.list_apps();
 
         if apps.is_empty() {
             println!("[Warning] No capturable applications found.");
             println!("You can add applications manually in the config file later.\n");
             vec![]
         } else {
-            select_applications(&apps)?
+            select_applications(env, &apps, cancel)?
         }
     };
 
     // Step 3: Autostart
+    progress.report(2, StepState::Completed);
+    if cancel.is_cancelled() {
+        return Ok(WizardResult::cancelled());
+    }
     println!("Step 3: Autostart configuration\n");
+    progress.report(3, StepState::Started);
+    let mut autostart_state = StepState::Completed;
 
-    let autostart_enabled = prompt_yes_no("Start crowd-cast automatically on login?")?;
+    let autostart_enabled = prompt_yes_no(env, "Start crowd-cast automatically on login?", cancel)?;
+    if cancel.is_cancelled() {
+        return Ok(WizardResult::cancelled());
+    }
 
     if autostart_enabled {
-        let autostart_config = crate::installer::autostart::AutostartConfig::default();
-        match crate::installer::autostart::enable_autostart(&autostart_config) {
+        match env.enable_autostart() {
             Ok(_) => {
                 // wlroots compositors (sway/Hyprland/...) don't run XDG autostart entries, so
                 // print the exact line the user must paste rather than claiming it's enabled.
@@ -144,22 +410,33 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
                 #[cfg(not(target_os = "linux"))]
                 println!("\n[OK] Autostart enabled.\n");
             }
-            Err(e) => println!("\n[Warning] Failed to enable autostart: {}\n", e),
+            Err(e) => {
+                println!("\n[Warning] Failed to enable autostart: {}\n", e);
+                issues.push(SetupIssue::AutostartFailed(e.to_string()));
+                autostart_state = StepState::Failed;
+            }
         }
     } else {
-        match crate::installer::autostart::disable_autostart() {
+        match env.disable_autostart() {
             Ok(_) => println!("\n[OK] Autostart disabled.\n"),
             Err(e) => println!("\n[Warning] Failed to disable autostart: {}\n", e),
         }
     }
 
+    progress.report(3, autostart_state);
+
     // Save configuration
     println!("Saving configuration...\n");
+    progress.report(4, StepState::Started);
 
     config.capture.capture_all = capture_all;
     config.capture.target_apps = selected_apps.clone();
     config.capture.start_on_login = autostart_enabled;
-    config.complete_setup()?;
+    if let Err(e) = env.save(config) {
+        progress.report(4, StepState::Failed);
+        return Err(e);
+    }
+    progress.report(4, StepState::Completed);
 
     println!("=================================================");
     println!("  Setup Complete!");
@@ -169,6 +446,7 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
         println!("Input capture: ALL applications");
     } else if selected_apps.is_empty() {
         println!("Input capture: No applications selected (disabled)");
+        issues.push(SetupIssue::NoAppsSelected);
     } else {
         println!("Input capture: {} application(s)", selected_apps.len());
         for app in &selected_apps {
@@ -179,16 +457,30 @@ pub fn run_wizard(config: &mut Config) -> Result<WizardResult> {
     println!("\nConfiguration saved to: {:?}", config.config_path());
     println!();
 
+    if !issues.is_empty() {
+        println!("Still to sort out:");
+        for issue in &issues {
+            println!("  - {}", issue);
+        }
+        println!();
+    }
+
     Ok(WizardResult {
         success: true,
         selected_apps,
         capture_all,
         autostart_enabled,
+        issues,
+        cancelled: false,
     })
 }
 
 /// Interactive application selection
-fn select_applications(apps: &[AppInfo]) -> Result<Vec<String>> {
+fn select_applications(
+    env: &mut dyn WizardEnv,
+    apps: &[AppInfo],
+    cancel: &CancellationToken,
+) -> Result<Vec<String>> {
     let mut selected = Vec::new();
 
     println!("Available applications:\n");
@@ -203,8 +495,7 @@ fn select_applications(apps: &[AppInfo]) -> Result<Vec<String>> {
     print!("\nSelection: ");
     io::stdout().flush()?;
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    let input = env.read_line(cancel)?;
     let input = input.trim().to_lowercase();
 
     if input == "all" {
@@ -239,13 +530,13 @@ fn select_applications(apps: &[AppInfo]) -> Result<Vec<String>> {
     // Confirm selection
     if selected.is_empty() {
         println!("No applications selected.");
-        if prompt_yes_no("Would you like to select again?")? {
-            return select_applications(apps);
+        if prompt_yes_no(env, "Would you like to select again?", cancel)? {
+            return select_applications(env, apps, cancel);
         }
     } else {
         println!("Selected {} application(s).", selected.len());
-        if !prompt_yes_no("Confirm selection?")? {
-            return select_applications(apps);
+        if !prompt_yes_no(env, "Confirm selection?", cancel)? {
+            return select_applications(env, apps, cancel);
         }
     }
 
@@ -253,20 +544,66 @@ fn select_applications(apps: &[AppInfo]) -> Result<Vec<String>> {
 }
 
 /// Prompt for yes/no input
-fn prompt_yes_no(prompt: &str) -> Result<bool> {
+fn prompt_yes_no(
+    env: &mut dyn WizardEnv,
+    prompt: &str,
+    cancel: &CancellationToken,
+) -> Result<bool> {
     print!("{} [y/N]: ", prompt);
     io::stdout().flush()?;
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    let input = env.read_line(cancel)?;
     let input = input.trim().to_lowercase();
 
     Ok(input == "y" || input == "yes")
 }
 
 /// Prompt to continue
-fn prompt_continue(prompt: &str) -> Result<bool> {
-    prompt_yes_no(prompt)
+fn prompt_continue(
+    env: &mut dyn WizardEnv,
+    prompt: &str,
+    cancel: &CancellationToken,
+) -> Result<bool> {
+    prompt_yes_no(env, prompt, cancel)
+}
+
+/// Lines typed on stdin, read on a thread of their own. A prompt blocked in `read_line`
+/// couldn't be interrupted, and the runtime waits for its blocking task at exit, so a
+/// cancelled wizard would hang the process until the user pressed Enter. The reader
+/// thread isn't waited for. It stops after EOF or a read error.
+fn stdin_lines() -> &'static Mutex<Receiver<io::Result<String>>> {
+    static LINES: OnceLock<Mutex<Receiver<io::Result<String>>>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = io::stdin().read_line(&mut line);
+            let done = !matches!(read, Ok(n) if n > 0);
+            if tx.send(read.map(|_| line)).is_err() || done {
+                break;
+            }
+        });
+        Mutex::new(rx)
+    })
+}
+
+/// Next line typed on stdin, or `WizardCancelled` once `cancel` fires. At EOF every
+/// prompt reads an empty line, as `read_line` would.
+fn read_line(cancel: &CancellationToken) -> Result<String> {
+    if cancel.is_cancelled() {
+        return Err(WizardCancelled.into());
+    }
+    let lines = stdin_lines().lock().unwrap_or_else(PoisonError::into_inner);
+    loop {
+        match lines.recv_timeout(PROMPT_CANCEL_POLL) {
+            Ok(line) => return Ok(line?),
+            Err(RecvTimeoutError::Timeout) if cancel.is_cancelled() => {
+                return Err(WizardCancelled.into());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(String::new()),
+        }
+    }
 }
 
 /// Check if setup wizard should be run
@@ -274,13 +611,42 @@ pub fn needs_setup(config: &Config) -> bool {
     config.needs_setup()
 }
 
-/// Run setup wizard asynchronously (for use with tokio)
-pub async fn run_wizard_async(config: &mut Config) -> Result<WizardResult> {
+/// Wait for the blocking wizard task, or stop waiting as soon as `cancel` fires (`None`).
+/// The abandoned task sees the same token, gives up on its prompt within
+/// `PROMPT_CANCEL_POLL` and applies nothing.
+async fn wait_unless_cancelled<T>(
+    task: JoinHandle<Result<T>>,
+    cancel: &CancellationToken,
+) -> Result<Option<T>> {
+    tokio::select! {
+        result = task => Ok(Some(result.context("Wizard task panicked")??)),
+        _ = cancel.cancelled() => Ok(None),
+    }
+}
+
+/// Run setup wizard asynchronously (for use with tokio), optionally streaming its
+/// [`WizardProgress`] to `progress_tx`. Cancelling `cancel` returns a cancelled result
+/// right away, even while the wizard is waiting on a prompt.
+pub async fn run_wizard_async(
+    config: &mut Config,
+    progress_tx: Option<mpsc::UnboundedSender<WizardProgress>>,
+    cancel: CancellationToken,
+) -> Result<WizardResult> {
     // Run the blocking wizard in a spawn_blocking task
     let mut config_clone = config.clone();
-    let result = tokio::task::spawn_blocking(move || run_wizard(&mut config_clone))
-        .await
-        .context("Wizard task panicked")??;
+    let task_cancel = cancel.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        run_wizard_with_progress(&mut config_clone, progress_tx, &task_cancel)
+    });
+    let Some(result) = wait_unless_cancelled(task, &cancel).await? else {
+        info!("Setup wizard cancelled");
+        return Ok(WizardResult::cancelled());
+    };
+    if result.cancelled {
+        // Cancelled just as the task finished a step: it stopped there on its own
+        info!("Setup wizard cancelled");
+        return Ok(result);
+    }
 
     // Update the original config if successful
     if result.success {
@@ -303,4 +669,199 @@ mod tests {
         let config = Config::default();
         assert!(needs_setup(&config));
     }
+
+    /// Scripted answers in place of the console and the system
+    struct StubEnv {
+        perms: PermissionStatus,
+        apps: Vec<AppInfo>,
+        answers: std::collections::VecDeque<&'static str>,
+        saved: bool,
+    }
+
+    impl WizardEnv for StubEnv {
+        fn check_permissions(&self) -> PermissionStatus {
+            self.perms.clone()
+        }
+
+        fn request_permissions(&self) -> Result<PermissionStatus> {
+            Ok(self.perms.clone())
+        }
+
+        fn list_apps(&self) -> Vec<AppInfo> {
+            self.apps.clone()
+        }
+
+        fn read_line(&mut self, cancel: &CancellationToken) -> Result<String> {
+            if cancel.is_cancelled() {
+                return Err(WizardCancelled.into());
+            }
+            Ok(self
+                .answers
+                .pop_front()
+                .expect("unexpected prompt")
+                .to_string())
+        }
+
+        fn enable_autostart(&self) -> Result<()> {
+            anyhow::bail!("no login items here")
+        }
+
+        fn disable_autostart(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn save(&self, config: &mut Config) -> Result<()> {
+            config.capture.setup_completed = true;
+            Ok(())
+        }
+    }
+
+    fn stub_env(answers: &[&'static str]) -> StubEnv {
+        StubEnv {
+            perms: PermissionStatus {
+                accessibility: PermissionState::Granted,
+                screen_recording: PermissionState::Granted,
+                input_group: PermissionState::NotApplicable,
+            },
+            apps: vec![AppInfo {
+                bundle_id: "com.example.editor".to_string(),
+                name: "Editor".to_string(),
+                pid: 42,
+            }],
+            answers: answers.iter().copied().collect(),
+            saved: false,
+        }
+    }
+
+    fn run_stubbed(env: &mut StubEnv) -> (WizardResult, Vec<(usize, StepState)>) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut config = Config::default();
+        let result = run_wizard_in(env, &mut config, Some(tx), &CancellationToken::new()).unwrap();
+        env.saved = config.capture.setup_completed;
+        let mut updates = Vec::new();
+        while let Ok(update) = rx.try_recv() {
+            assert_eq!(update.total, STEPS.len());
+            assert_eq!(update.label, STEPS[update.step - 1]);
+            updates.push((update.step, update.state));
+        }
+        (result, updates)
+    }
+
+    #[test]
+    fn progress_reports_each_step_transition_in_order() {
+        use StepState::{Completed, Failed, Started};
+
+        // Pick the one app, confirm it, no autostart.
+        let mut env = stub_env(&["n", "1", "y", "n"]);
+        let (result, updates) = run_stubbed(&mut env);
+        assert!(result.success);
+        assert_eq!(result.selected_apps, vec!["com.example.editor".to_string()]);
+        assert!(env.saved);
+        assert_eq!(
+            updates,
+            vec![
+                (1, Started),
+                (1, Completed),
+                (2, Started),
+                (2, Completed),
+                (3, Started),
+                (3, Completed),
+                (4, Started),
+                (4, Completed),
+            ]
+        );
+
+        // Autostart that can't be registered fails its step, and setup carries on.
+        let mut env = stub_env(&["y", "y"]);
+        let (result, updates) = run_stubbed(&mut env);
+        assert!(result.success && result.capture_all);
+        assert!(matches!(
+            result.issues.as_slice(),
+            [SetupIssue::AutostartFailed(_)]
+        ));
+        assert_eq!(updates[4..6], [(3, Started), (3, Failed)]);
+        assert_eq!(updates.last(), Some(&(4, Completed)));
+
+        // Declining to continue without a permission stops at the first step.
+        let mut env = stub_env(&["n"]);
+        env.perms.accessibility = PermissionState::Denied;
+        let (result, updates) = run_stubbed(&mut env);
+        assert!(!result.success && !env.saved);
+        assert_eq!(updates, vec![(1, Started), (1, Failed)]);
+
+        // Nobody listening (the CLI), or a listener that went away: reporting is a no-op.
+        ProgressReporter { tx: None }.report(1, StepState::Started);
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(rx);
+        ProgressReporter { tx: Some(tx) }.report(2, StepState::Failed);
+    }
+
+    #[tokio::test]
+    async fn cancelling_mid_wait_returns_promptly() {
+        // Stands in for a wizard blocked on a prompt until `release` is dropped
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let task = tokio::task::spawn_blocking(move || {
+            let _ = blocked.recv_timeout(std::time::Duration::from_secs(10));
+            Ok(())
+        });
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let outcome = wait_unless_cancelled(task, &cancel).await.unwrap();
+        assert!(outcome.is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        drop(release);
+
+        // Not cancelled: the task's own result comes through.
+        let task = tokio::task::spawn_blocking(|| Ok(7));
+        let outcome = wait_unless_cancelled(task, &CancellationToken::new()).await;
+        assert_eq!(outcome.unwrap(), Some(7));
+        assert!(WizardResult::cancelled().cancelled);
+
+        // A prompt gives up once setup is cancelled instead of waiting on stdin.
+        let err = read_line(&cancel).unwrap_err();
+        assert!(err.is::<WizardCancelled>());
+    }
+
+    #[test]
+    fn permission_issues_name_each_missing_permission() {
+        let perms = PermissionStatus {
+            accessibility: PermissionState::Denied,
+            screen_recording: PermissionState::Unknown,
+            input_group: PermissionState::NotApplicable,
+        };
+        let issues = permission_issues(&perms);
+        assert_eq!(
+            issues,
+            vec![
+                SetupIssue::PermissionDenied(Permission::Accessibility),
+                SetupIssue::PermissionDenied(Permission::ScreenRecording),
+            ]
+        );
+        assert_eq!(issues[0].exit_code(), 3);
+        assert_eq!(
+            issues[1].to_string(),
+            "Screen Recording permission not granted (no video)"
+        );
+
+        let linux = PermissionStatus {
+            accessibility: PermissionState::NotApplicable,
+            screen_recording: PermissionState::NotApplicable,
+            input_group: PermissionState::Denied,
+        };
+        assert_eq!(
+            permission_issues(&linux),
+            vec![SetupIssue::PermissionDenied(Permission::InputGroup)]
+        );
+        assert!(permission_issues(&PermissionStatus {
+            input_group: PermissionState::Granted,
+            ..linux
+        })
+        .is_empty());
+    }
 }
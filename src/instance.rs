@@ -0,0 +1,133 @@
+//! Single-instance lock
+//!
+//! Two agents running at once would both capture and both write segments into the same
+//! output directory, fighting over OBS and corrupting each other's uploads. `main` takes an
+//! exclusive lock on a file in the data directory before it brings up capture; a second
+//! agent finds the lock held and exits. The lock is an OS file lock (`flock` on Unix,
+//! `LockFileEx` on Windows), so it dies with its process: an agent that crashed leaves the
+//! file behind but never a held lock. The file records the owner's PID for diagnostics.
+
+use anyhow::{Context as _, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the lock before concluding another agent holds it. Covers the
+/// relaunch paths that spawn the replacement process before the old one has exited.
+pub const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lock file location, next to the control socket and other per-user agent state.
+pub fn lock_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("dev", "crowd-cast", "agent")
+        .map(|p| p.data_dir().join("agent.lock"))
+}
+
+/// Another live agent holds the single-instance lock
+#[derive(Debug)]
+pub struct AlreadyRunning {
+    /// PID recorded by the running agent, when readable (Windows locks block reads)
+    pub pid: Option<u32>,
+}
+
+impl std::fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(
+                f,
+                "another crowd-cast agent is already running (pid {})",
+                pid
+            ),
+            None => write!(f, "another crowd-cast agent is already running"),
+        }
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+/// Held single-instance lock. Released when dropped or when the process exits.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Take the lock at `path`, retrying for up to `wait`. Fails with [`AlreadyRunning`]
+    /// when another process still holds it; a file left by a crashed agent is reused.
+    pub fn acquire(path: &Path, wait: Duration) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open lock file {:?}", path))?;
+
+        let deadline = Instant::now() + wait;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(AlreadyRunning {
+                        pid: read_pid(&mut file),
+                    }
+                    .into());
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Failed to lock {:?}", path));
+                }
+            }
+        }
+
+        // Ours now; replace whatever PID a previous (possibly crashed) agent left
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquisition_fails_while_lock_is_held() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-lock-{}", std::process::id()));
+        let path = dir.join("agent.lock");
+        // A stale file from a crashed agent doesn't block acquisition.
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "999999").unwrap();
+
+        let held = InstanceLock::acquire(&path, Duration::ZERO).unwrap();
+        let err = InstanceLock::acquire(&path, Duration::from_millis(250)).unwrap_err();
+        let running = err
+            .downcast_ref::<AlreadyRunning>()
+            .expect("held lock reports AlreadyRunning");
+        // Windows' mandatory locks keep the PID unreadable from other handles.
+        if cfg!(unix) {
+            assert_eq!(running.pid, Some(std::process::id()));
+        }
+
+        // Released on drop, e.g. when the owning agent exits.
+        drop(held);
+        assert!(InstanceLock::acquire(&path, Duration::ZERO).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
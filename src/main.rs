@@ -12,10 +12,13 @@
 mod auth;
 mod capture;
 mod config;
+mod control;
 mod crash;
 mod data;
+mod diagnostics;
 mod input;
 mod installer;
+mod instance;
 mod logging;
 #[cfg(target_os = "linux")]
 mod resume_linux;
@@ -188,6 +191,29 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // `status` / `apps` / `test-upload` / `marker` talk to an already-running agent over
+    // its control socket and exit; they never start capture themselves.
+    match args.get(1).map(String::as_str) {
+        Some("status") => std::process::exit(control::run_status_command(
+            args.iter().any(|a| a == "--json"),
+        )),
+        Some("apps") => std::process::exit(control::run_apps_command(
+            args.iter().any(|a| a == "--json"),
+        )),
+        Some("test-upload") => std::process::exit(control::run_test_upload_command()),
+        Some("marker") => std::process::exit(control::run_marker_command(&args[2..].join(" "))),
+        // Uploads the files itself, so it runs only while no agent does.
+        Some("reupload") => std::process::exit(upload::run_reupload_command(
+            &args[2..],
+            init_auth_manager(),
+        )),
+        // Rewrites the config file; a running agent keeps its settings until restarted.
+        Some("config") => std::process::exit(config::run_config_command(&args[2..])),
+        // Only reads files, so it is safe alongside a running agent.
+        Some("diagnostics") => std::process::exit(diagnostics::run_diagnostics_command(&args[2..])),
+        _ => {}
+    }
+
     // Headless host-requirements diagnostic (Linux): print the same checks the
     // setup wizard gates on, then exit. Useful for support and CI.
     #[cfg(target_os = "linux")]
@@ -327,6 +353,7 @@ fn main() -> Result<()> {
     }
 
     let force_setup = args.iter().any(|a| a == "--setup" || a == "-s");
+    let headless = args.iter().any(|a| a == "--headless");
     let missing_permissions = !installer::all_permissions_granted();
 
     // True only on the run re-exec'd by a just-completed setup wizard (the marker is
@@ -350,6 +377,7 @@ fn main() -> Result<()> {
     // Load configuration
     let mut config = Config::load()?;
     info!("Configuration loaded from {:?}", config.config_path());
+    ui::notifications::set_notification_level(config.notifications.level);
 
     // On Linux, also re-show the wizard whenever a Required host component is missing
     // (e.g. the ScreenCast portal backend), or the saved config requires a capture mode
@@ -387,6 +415,19 @@ fn main() -> Result<()> {
         || config_incompatible
         || autostart_unsatisfied
     {
+        if headless {
+            error!(
+                "Setup is required but --headless cannot show the setup wizard. Run \
+                 `crowd-cast-agent --setup` once with a display, or complete the config at {:?}",
+                config.config_path()
+            );
+            // A missing permission gets its own exit code, so scripted installs can tell
+            // which grant is still outstanding.
+            let code = installer::permission_issues(&installer::check_permissions())
+                .first()
+                .map_or(1, installer::SetupIssue::exit_code);
+            std::process::exit(code);
+        }
         if autostart_unsatisfied {
             info!(
                 "start_on_login is set but the autostart line is not in the compositor config \
@@ -436,6 +477,26 @@ fn main() -> Result<()> {
         std::process::exit(0);
     }
 
+    // One agent per user: a second would fight the first over OBS and the output directory.
+    // Taken after the wizard, whose re-exec may briefly overlap this process on Windows.
+    let _instance_lock = match instance::lock_path()
+        .map(|path| instance::InstanceLock::acquire(&path, instance::LOCK_WAIT))
+    {
+        Some(Ok(lock)) => Some(lock),
+        Some(Err(e)) if e.is::<instance::AlreadyRunning>() => {
+            error!("{}; exiting", e);
+            ui::notifications::show_already_running_notification();
+            // Exit cleanly: a nonzero exit would have launchd's KeepAlive relaunch us
+            // straight back into the same refusal.
+            std::process::exit(0);
+        }
+        Some(Err(e)) => {
+            warn!("Could not take the single-instance lock: {:#}", e);
+            None
+        }
+        None => None,
+    };
+
     reconcile_start_on_login(&mut config);
 
     // Check permissions
@@ -450,7 +511,7 @@ fn main() -> Result<()> {
     // Bootstrap OBS binaries if needed
     info!("Bootstrapping OBS binaries...");
     let mut capture_ctx =
-        match runtime.block_on(capture::CaptureContext::new(get_output_directory(&config))) {
+        match runtime.block_on(capture::CaptureContext::new(config.output_directory())) {
             Ok(ctx) => ctx,
             Err(e) => {
                 error!("Failed to bootstrap OBS binaries: {}", e);
@@ -468,9 +529,23 @@ fn main() -> Result<()> {
     // Prime the capture mode + target list before initialize so the canvas can choose the
     // multi-monitor per-app envelope vs the display-capture canvas (setup_capture re-sets these).
     capture_ctx.set_single_active_app_capture(config.capture.single_active_app_capture);
+    capture_ctx.set_capture_layout(config.capture.layout);
     capture_ctx.set_mac_multi_monitor_capture(config.capture.mac_multi_monitor_capture);
     let target_apps = config.capture.target_apps.clone();
     capture_ctx.set_target_apps(&target_apps);
+    let mut recording_config = capture::RecordingConfig::default();
+    if config.recording.crash_resilient {
+        recording_config = recording_config.crash_resilient();
+    }
+    if config.recording.hls.enabled {
+        recording_config = recording_config.hls(config.recording.hls.segment_secs);
+    }
+    capture_ctx.set_recording_config(recording_config);
+    capture_ctx.set_existing_output_policy(config.recording.on_existing);
+    capture_ctx.set_audio_capture(
+        config.recording.audio.system,
+        config.recording.audio.microphone.clone(),
+    );
 
     // Initialize libobs + capture sources. On macOS this is retried with backoff:
     // the agent is commonly (re)launched right after a wake-time crash, while the
@@ -489,11 +564,23 @@ fn main() -> Result<()> {
     let mut startup_attempt = 0usize;
     loop {
         let step_err = match capture_ctx.initialize() {
-            Err(e) => Some(("initialize libobs", e)),
+            // Missing encoders don't come back with a retry.
+            Err(e @ capture::CaptureError::NoVideoEncoder { .. }) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+            Err(e) => Some(("initialize libobs", anyhow::Error::from(e))),
             Ok(()) => {
                 match capture_ctx.setup_capture(&target_apps, &config.capture.restore_tokens) {
                     Err(e) => Some(("setup capture", e)),
-                    Ok(_) => None,
+                    Ok(outcome) => {
+                        if !outcome.failed.is_empty() {
+                            ui::notifications::show_capture_sources_failed_notification(
+                                &outcome.failed_apps(),
+                            );
+                        }
+                        None
+                    }
                 }
             }
         };
@@ -570,18 +657,19 @@ fn main() -> Result<()> {
     let (cmd_tx, cmd_rx, status_tx, _status_rx) = create_engine_channels();
 
     // Initialize optional Google OAuth auth manager
-    let auth_manager = option_env!("CROWD_CAST_GOOGLE_CLIENT_ID").map(|client_id| {
-        let client_secret = option_env!("CROWD_CAST_GOOGLE_CLIENT_SECRET").unwrap_or("");
-        let mgr = auth::AuthManager::new(client_id, client_secret);
-        if mgr.is_authenticated() {
-            info!("Authenticated as {}", mgr.email().unwrap_or("unknown"));
-        }
-        std::sync::Arc::new(tokio::sync::Mutex::new(mgr))
-    });
+    let auth_manager = init_auth_manager();
+
+    // Headless there is no tray to press Start, so recording always starts on launch. Only
+    // the engine's copy is changed; the saved config keeps the user's preference.
+    let mut engine_config = config.clone();
+    if headless {
+        info!("Running headless: no system tray; recording starts on launch");
+        engine_config.recording.autostart_on_launch = true;
+    }
 
     // Create sync engine
     let engine = SyncEngine::new(
-        config.clone(),
+        engine_config,
         capture_ctx,
         cmd_rx,
         status_tx.clone(),
@@ -605,10 +693,21 @@ fn main() -> Result<()> {
 
     // One-time post-setup sign-in prompt, before the tray starts. The dialog blocks
     // only this (main) thread; the engine just spawned keeps recording behind it.
-    if post_setup_run {
+    if post_setup_run && !headless {
         prompt_post_setup_signin(&auth_manager, &runtime);
     }
 
+    // Serve the control socket for `crowd-cast-agent status` (Unix only).
+    #[cfg(unix)]
+    if let Some(path) = control::socket_path() {
+        let control_tx = cmd_tx.clone();
+        runtime.spawn(async move {
+            if let Err(e) = control::serve(path, control_tx).await {
+                warn!("Control socket unavailable: {:#}", e);
+            }
+        });
+    }
+
     // Linux: listen for resume-from-suspend via logind and restart the recording fresh, so a
     // recording that straddled a sleep doesn't drift (keylog↔video re-zero). Primary signal;
     // the engine's wall-clock-gap check is the fallback. macOS uses its restart-on-unlock path;
@@ -685,57 +784,51 @@ fn main() -> Result<()> {
         }
     }
 
-    // Run tray on main thread
-    #[cfg(not(no_tray))]
-    {
-        let tray_cmd_tx = cmd_tx.clone();
-        let tray_status_rx = status_tx.subscribe();
-
-        info!("Starting system tray on main thread");
-
-        match ui::TrayApp::new(
-            tray_cmd_tx,
-            tray_status_rx,
-            auth_manager.clone(),
-            Some(runtime.clone()),
-        ) {
-            Ok(tray) => {
-                if let Err(e) = tray.run() {
-                    error!("Tray error: {}", e);
+    // Run tray on main thread, or just wait for Ctrl+C
+    if main_loop(headless) == MainLoop::Tray {
+        #[cfg(not(no_tray))]
+        {
+            let tray_cmd_tx = cmd_tx.clone();
+            let tray_status_rx = status_tx.subscribe();
+
+            info!("Starting system tray on main thread");
+
+            match ui::TrayApp::new(
+                tray_cmd_tx,
+                tray_status_rx,
+                auth_manager.clone(),
+                Some(runtime.clone()),
+            ) {
+                Ok(tray) => {
+                    if let Err(e) = tray.run() {
+                        error!("Tray error: {}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to create tray: {}", e);
                 }
             }
-            Err(e) => {
-                error!("Failed to create tray: {}", e);
-            }
-        }
-
-        info!("Tray exited, shutting down...");
-    }
 
-    #[cfg(no_tray)]
-    {
-        info!("System tray not available on this platform");
-        info!("Press Ctrl+C to exit...");
-
-        // Wait for engine to finish (Ctrl+C handler will send shutdown)
-        engine_handle.join().ok();
-    }
+            info!("Tray exited, shutting down...");
+        }
 
-    // Send shutdown command to engine (in case tray exited without Ctrl+C), then wait for
-    // the engine thread. On no_tray builds (Windows) the engine was already joined above
-    // (after the Ctrl+C handler sent Shutdown), so this is skipped to avoid a double join.
-    #[cfg(not(no_tray))]
-    {
+        // Send shutdown command to engine (in case tray exited without Ctrl+C)
         runtime.block_on(async {
             let _ = cmd_tx.send(EngineCommand::Shutdown).await;
         });
-
-        // Wait for engine thread to finish. The no_tray path above already joined
-        // engine_handle while waiting for Ctrl+C, so this (tray-owned) path is the
-        // only other join — no double join.
-        let _ = engine_handle.join();
+    } else {
+        if !headless {
+            info!("System tray not available on this platform");
+        }
+        info!("Press Ctrl+C to exit...");
+        #[cfg(target_os = "macos")]
+        run_main_run_loop_until_finished(&engine_handle);
     }
 
+    // Wait for the engine thread to finish: the tray path above sent Shutdown, otherwise the
+    // Ctrl+C handler does.
+    let _ = engine_handle.join();
+
     // Determine exit code: intentional exits (Quit menu, Sparkle update, Ctrl+C)
     // exit with 0 so KeepAlive/Crashed does NOT restart. All other exits
     // (SIGTERM from macOS sleep/hibernate, NSApp termination) exit with 1
@@ -754,12 +847,48 @@ fn main() -> Result<()> {
     }
 }
 
-fn get_output_directory(config: &Config) -> std::path::PathBuf {
-    config
-        .recording
-        .output_directory
-        .clone()
-        .unwrap_or_else(|| std::env::temp_dir().join("crowd-cast-recordings"))
+/// What the main thread runs while the engine records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MainLoop {
+    /// The system tray's event loop; quitting it shuts the engine down.
+    Tray,
+    /// Nothing (on macOS, the main run loop): block on the engine thread until Ctrl+C
+    /// shuts it down.
+    WaitForCtrlC,
+}
+
+/// macOS delivers notification callbacks, display reconfiguration events and work
+/// dispatched to the main queue through the main thread's run loop, which the tray
+/// normally runs. Without it, turn the run loop until the engine thread exits.
+#[cfg(target_os = "macos")]
+fn run_main_run_loop_until_finished<T>(engine: &std::thread::JoinHandle<T>) {
+    use std::os::raw::c_void;
+
+    /// `kCFRunLoopRunFinished`: the run loop has no sources to wait on
+    const RUN_LOOP_RUN_FINISHED: i32 = 1;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopDefaultMode: *const c_void;
+        fn CFRunLoopRunInMode(mode: *const c_void, seconds: f64, return_after_source: u8) -> i32;
+    }
+
+    while !engine.is_finished() {
+        // Return now and then to notice that the engine has stopped.
+        let result = unsafe { CFRunLoopRunInMode(kCFRunLoopDefaultMode, 0.25, 0) };
+        if result == RUN_LOOP_RUN_FINISHED {
+            std::thread::sleep(std::time::Duration::from_millis(250));
+        }
+    }
+}
+
+/// `--headless` never builds the tray, even where one is available.
+fn main_loop(headless: bool) -> MainLoop {
+    if headless || cfg!(no_tray) {
+        MainLoop::WaitForCtrlC
+    } else {
+        MainLoop::Tray
+    }
 }
 
 fn reconcile_start_on_login(config: &mut Config) {
@@ -878,15 +1007,56 @@ fn show_post_setup_signin_dialog() -> bool {
     false
 }
 
+/// Optional Google OAuth auth manager, when this build has a client id compiled in
+fn init_auth_manager() -> Option<Arc<tokio::sync::Mutex<auth::AuthManager>>> {
+    option_env!("CROWD_CAST_GOOGLE_CLIENT_ID").map(|client_id| {
+        let client_secret = option_env!("CROWD_CAST_GOOGLE_CLIENT_SECRET").unwrap_or("");
+        let mgr = auth::AuthManager::new(client_id, client_secret);
+        if mgr.is_authenticated() {
+            info!("Authenticated as {}", mgr.email().unwrap_or("unknown"));
+        }
+        Arc::new(tokio::sync::Mutex::new(mgr))
+    })
+}
+
 fn print_help() {
     println!("crowd-cast Agent - Paired screencast and input capture");
     println!();
     println!("USAGE:");
     println!("    crowd-cast-agent [OPTIONS]");
+    println!("    crowd-cast-agent status [--json]");
+    println!("    crowd-cast-agent apps [--json]");
+    println!("    crowd-cast-agent test-upload");
+    println!("    crowd-cast-agent marker <LABEL>");
+    println!("    crowd-cast-agent reupload [--dir <PATH>] [--delete]");
+    println!("    crowd-cast-agent config export [--redact] [--out <PATH>]");
+    println!("    crowd-cast-agent config import <PATH>");
+    println!("    crowd-cast-agent diagnostics [--out <PATH>]");
+    println!();
+    println!("COMMANDS:");
+    println!("    status        Print the running agent's status and exit (non-zero if none)");
+    println!("    apps          List the running apps the agent could capture; * marks the");
+    println!("                  ones it already captures");
+    println!("    test-upload   Have the running agent upload a tiny test file to check the");
+    println!("                  upload endpoint and credentials");
+    println!("    marker        Add a labeled marker at the current point of the running");
+    println!("                  agent's recording (e.g. \"start of task 3\")");
+    println!("    reupload      Upload the complete segments (video + input log) left in a");
+    println!("                  directory (default: the output directory); --delete removes");
+    println!("                  each one once uploaded. Run while the agent is stopped");
+    println!("    config        export prints the config as a template (--out writes it to a");
+    println!("                  file; --redact leaves out secrets and machine-local state);");
+    println!("                  import merges a template into the local config");
+    println!("    diagnostics   Bundle the OBS and agent logs, crash breadcrumbs and the");
+    println!("                  redacted config into a zip to attach to a bug report");
     println!();
     println!("OPTIONS:");
     println!("    -h, --help    Print this help message");
     println!("    -s, --setup   Run the setup wizard");
+    println!("        --headless");
+    println!("                  Run without the system tray and start recording on launch;");
+    println!("                  stop with Ctrl+C (servers, CI). Exits 3-5 if setup is needed");
+    println!("                  because a permission is missing");
     #[cfg(target_os = "linux")]
     {
         println!("        --check-requirements");
@@ -911,3 +1081,19 @@ fn print_help() {
     println!();
     println!("For more information, visit: https://github.com/p-doom/crowd-cast");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_never_builds_the_tray() {
+        assert_eq!(main_loop(true), MainLoop::WaitForCtrlC);
+        let expected = if cfg!(no_tray) {
+            MainLoop::WaitForCtrlC
+        } else {
+            MainLoop::Tray
+        };
+        assert_eq!(main_loop(false), expected);
+    }
+}
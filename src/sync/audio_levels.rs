@@ -0,0 +1,138 @@
+//! Flat audio detection (`recording.audio.flat_warning_secs`)
+//!
+//! A microphone bound to the wrong device or stuck on a constant hum records a level that
+//! never moves, without any error from OBS. While a microphone is captured, the engine
+//! samples its meter's peak at every health check and feeds it to [`FlatAudioDetector`],
+//! which reports once when the level hasn't moved for the configured time and again only
+//! after the audio has come back to life in between. Digital silence is not flat: a
+//! quiet room or a deliberately muted microphone is not a fault. System audio isn't
+//! checked at all, since it is silent whenever nothing plays.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Levels are clamped to this floor; a reading at it is digital silence (-inf dBFS)
+pub(super) const AUDIO_LEVEL_FLOOR_DB: f32 = -96.0;
+
+/// Samples within this many dB of each other count as the same level
+const FLAT_TOLERANCE_DB: f32 = 1.0;
+
+/// Clamp a meter reading to `[AUDIO_LEVEL_FLOOR_DB, 0]`; NaN reads as silence
+pub(super) fn clamp_level_db(db: f32) -> f32 {
+    if db.is_nan() {
+        AUDIO_LEVEL_FLOOR_DB
+    } else {
+        db.clamp(AUDIO_LEVEL_FLOOR_DB, 0.0)
+    }
+}
+
+/// Watches sampled peak levels for a run that stays flat for longer than `threshold`
+#[derive(Debug)]
+pub(super) struct FlatAudioDetector {
+    threshold: Duration,
+    /// Start of the current flat run, with its lowest and highest level
+    run: Option<(Instant, f32, f32)>,
+    /// The current run has already been reported
+    reported: bool,
+}
+
+impl FlatAudioDetector {
+    pub(super) fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            run: None,
+            reported: false,
+        }
+    }
+
+    /// Feed one peak level sampled at `now`. Returns true exactly once per flat run, when
+    /// it first outlasts the threshold. Silence ends a run without starting one. A zero
+    /// threshold never reports.
+    pub(super) fn observe(&mut self, db: f32, now: Instant) -> bool {
+        let db = clamp_level_db(db);
+        if db <= AUDIO_LEVEL_FLOOR_DB {
+            self.reset();
+            return false;
+        }
+        match &mut self.run {
+            Some((_, low, high)) if db.max(*high) - db.min(*low) <= FLAT_TOLERANCE_DB => {
+                *low = low.min(db);
+                *high = high.max(db);
+            }
+            _ => {
+                self.run = Some((now, db, db));
+                self.reported = false;
+            }
+        }
+        let Some((started, _, _)) = self.run else {
+            return false;
+        };
+        if self.reported || self.threshold.is_zero() || now.duration_since(started) < self.threshold
+        {
+            return false;
+        }
+        self.reported = true;
+        true
+    }
+
+    /// Whether the current run has been reported and not broken since
+    pub(super) fn is_flat(&self) -> bool {
+        self.reported
+    }
+
+    /// Forget the current run (recording stopped or paused)
+    pub(super) fn reset(&mut self) {
+        self.run = None;
+        self.reported = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_levels_are_reported_once_per_run() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut detector = FlatAudioDetector::new(Duration::from_secs(30));
+
+        // Silence (including -inf from an idle meter) is never flat, however long.
+        for secs in [0, 10, 20, 30, 600] {
+            assert!(!detector.observe(f32::NEG_INFINITY, at(secs)));
+        }
+        assert!(!detector.observe(-120.0, at(610)));
+        assert!(!detector.observe(AUDIO_LEVEL_FLOOR_DB, at(620)));
+        assert!(!detector.is_flat());
+
+        // A constant hum for 30s reports once, jitter included.
+        assert!(!detector.observe(-40.0, at(1000)));
+        assert!(!detector.observe(-40.6, at(1010)));
+        assert!(!detector.observe(-39.8, at(1020)));
+        assert!(detector.observe(-40.2, at(1030)));
+        assert!(detector.is_flat());
+        assert!(!detector.observe(-40.1, at(1040)));
+
+        // Speech breaks the run, and so does silence.
+        assert!(!detector.observe(-12.0, at(1050)));
+        assert!(!detector.is_flat());
+        assert!(!detector.observe(-40.0, at(1060)));
+        assert!(!detector.observe(f32::NEG_INFINITY, at(1080)));
+        assert!(!detector.observe(-40.0, at(1090)));
+        assert!(!detector.observe(-40.0, at(1110)));
+        assert!(detector.observe(-40.0, at(1120)));
+
+        // Drift wider than the tolerance is not flat, even if each step is small.
+        detector.reset();
+        for (i, db) in [-30.0, -30.8, -31.6, -32.4, -33.2].into_iter().enumerate() {
+            assert!(!detector.observe(db, at(100 + 10 * i as u64)));
+        }
+
+        // A zero threshold disables the warning.
+        let mut disabled = FlatAudioDetector::new(Duration::ZERO);
+        for secs in [0, 600, 6000] {
+            assert!(!disabled.observe(-40.0, at(secs)));
+        }
+    }
+}
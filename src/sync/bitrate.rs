@@ -0,0 +1,185 @@
+//! Adaptive recording bitrate
+//!
+//! A segment that takes longer to upload than it took to record never lets the local
+//! queue drain. With `recording.adaptive_bitrate` on, the upload task records how fast
+//! segments actually go out ([`UploadThroughput`]) and at every segment boundary the
+//! engine compares that against how fast the last segment was produced. The
+//! [`BitrateGovernor`] lowers the encoder bitrate when production has outpaced uploads for
+//! a few segments in a row and raises it again once there is clear headroom, always within
+//! `[bitrate_floor_kbps, bitrate_ceiling_kbps]`. The new bitrate applies to the next
+//! segment's encoder.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Completed uploads the throughput estimate is taken over
+const THROUGHPUT_WINDOW: usize = 5;
+
+/// Consecutive over-budget segments before the bitrate is lowered
+const OVER_BUDGET_SEGMENTS: u32 = 2;
+/// Consecutive segments with headroom before the bitrate is raised
+const HEADROOM_SEGMENTS: u32 = 3;
+/// Production at or below this fraction of upload throughput counts as headroom
+const HEADROOM_RATIO: f64 = 0.5;
+/// When lowering, aim production at this fraction of upload throughput
+const TARGET_UTILIZATION: f64 = 0.8;
+/// Factor the bitrate grows by per raise
+const RAISE_STEP: f64 = 1.25;
+
+/// Recent upload throughput, fed by the upload task
+#[derive(Debug, Default)]
+pub(super) struct UploadThroughput {
+    samples: VecDeque<(u64, Duration)>,
+}
+
+impl UploadThroughput {
+    /// Record one successful upload of `bytes` that took `elapsed`
+    pub(super) fn record(&mut self, bytes: u64, elapsed: Duration) {
+        if bytes == 0 || elapsed.is_zero() {
+            return;
+        }
+        if self.samples.len() == THROUGHPUT_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((bytes, elapsed));
+    }
+
+    /// Bytes per second over the recent uploads, or None before the first one. Per
+    /// upload, so with concurrent uploads this understates the total and errs on the side
+    /// of a lower bitrate.
+    pub(super) fn bytes_per_sec(&self) -> Option<f64> {
+        let bytes: u64 = self.samples.iter().map(|(b, _)| b).sum();
+        let secs: f64 = self.samples.iter().map(|(_, d)| d.as_secs_f64()).sum();
+        (secs > 0.0).then(|| bytes as f64 / secs)
+    }
+}
+
+/// Picks the encoder bitrate for the next segment from production vs upload throughput
+#[derive(Debug)]
+pub(super) struct BitrateGovernor {
+    floor_kbps: u32,
+    ceiling_kbps: u32,
+    current_kbps: u32,
+    over_budget: u32,
+    headroom: u32,
+}
+
+impl BitrateGovernor {
+    /// Starts at the ceiling. A floor above the ceiling is treated as the ceiling.
+    pub(super) fn new(floor_kbps: u32, ceiling_kbps: u32) -> Self {
+        let ceiling_kbps = ceiling_kbps.max(1);
+        Self {
+            floor_kbps: floor_kbps.clamp(1, ceiling_kbps),
+            ceiling_kbps,
+            current_kbps: ceiling_kbps,
+            over_budget: 0,
+            headroom: 0,
+        }
+    }
+
+    pub(super) fn current_kbps(&self) -> u32 {
+        self.current_kbps
+    }
+
+    /// Feed one finished segment's production rate and the current upload throughput
+    /// (both bytes/sec). Returns the new bitrate when it should change.
+    pub(super) fn decide(&mut self, produced_bps: f64, upload_bps: f64) -> Option<u32> {
+        if produced_bps <= 0.0 || upload_bps <= 0.0 {
+            return None;
+        }
+
+        if produced_bps > upload_bps {
+            self.headroom = 0;
+            self.over_budget += 1;
+            if self.over_budget < OVER_BUDGET_SEGMENTS {
+                return None;
+            }
+            self.over_budget = 0;
+            let scaled = self.current_kbps as f64 * upload_bps * TARGET_UTILIZATION / produced_bps;
+            self.set((scaled as u32).max(self.floor_kbps))
+        } else if produced_bps <= upload_bps * HEADROOM_RATIO {
+            self.over_budget = 0;
+            self.headroom += 1;
+            if self.headroom < HEADROOM_SEGMENTS {
+                return None;
+            }
+            self.headroom = 0;
+            let raised = (self.current_kbps as f64 * RAISE_STEP).ceil() as u32;
+            self.set(raised.min(self.ceiling_kbps))
+        } else {
+            self.over_budget = 0;
+            self.headroom = 0;
+            None
+        }
+    }
+
+    fn set(&mut self, kbps: u32) -> Option<u32> {
+        (kbps != self.current_kbps).then(|| {
+            self.current_kbps = kbps;
+            kbps
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1_000_000;
+
+    #[test]
+    fn throughput_averages_the_recent_uploads() {
+        let mut throughput = UploadThroughput::default();
+        assert_eq!(throughput.bytes_per_sec(), None);
+
+        throughput.record(10 * MB, Duration::from_secs(10));
+        throughput.record(30 * MB, Duration::from_secs(10));
+        assert_eq!(throughput.bytes_per_sec(), Some(2_000_000.0));
+
+        // Older samples fall out of the window.
+        for _ in 0..THROUGHPUT_WINDOW {
+            throughput.record(5 * MB, Duration::from_secs(10));
+        }
+        assert_eq!(throughput.bytes_per_sec(), Some(500_000.0));
+    }
+
+    #[test]
+    fn governor_lowers_on_sustained_overrun_and_raises_with_headroom() {
+        let mut governor = BitrateGovernor::new(500, 4000);
+        assert_eq!(governor.current_kbps(), 4000);
+
+        // One slow upload isn't a trend.
+        assert_eq!(governor.decide(500_000.0, 250_000.0), None);
+        // A second over-budget segment lowers the bitrate so production lands at 80% of
+        // throughput.
+        assert_eq!(governor.decide(500_000.0, 250_000.0), Some(1600));
+
+        // A segment within budget resets the streak.
+        assert_eq!(governor.decide(200_000.0, 100_000.0), None);
+        assert_eq!(governor.decide(90_000.0, 100_000.0), None);
+        assert_eq!(governor.decide(200_000.0, 100_000.0), None);
+
+        // Collapsed throughput bottoms out at the floor.
+        assert_eq!(governor.decide(200_000.0, 1_000.0), Some(500));
+        assert_eq!(governor.decide(200_000.0, 1_000.0), None);
+        assert_eq!(governor.decide(200_000.0, 1_000.0), None);
+
+        // Headroom has to hold for several segments before raising, in steps.
+        assert_eq!(governor.decide(100_000.0, 1_000_000.0), None);
+        assert_eq!(governor.decide(100_000.0, 1_000_000.0), None);
+        assert_eq!(governor.decide(100_000.0, 1_000_000.0), Some(625));
+        for _ in 0..30 {
+            governor.decide(100_000.0, 1_000_000.0);
+        }
+        assert_eq!(governor.current_kbps(), 4000);
+    }
+
+    #[test]
+    fn governor_floor_never_exceeds_ceiling() {
+        let mut governor = BitrateGovernor::new(8000, 2000);
+        assert_eq!(governor.current_kbps(), 2000);
+        assert_eq!(governor.decide(1_000_000.0, 1_000.0), None);
+        assert_eq!(governor.decide(1_000_000.0, 1_000.0), None);
+        assert_eq!(governor.current_kbps(), 2000);
+    }
+}
@@ -0,0 +1,161 @@
+//! Clock behind input event timestamps (`recording.clock_source`)
+//!
+//! By default events are timed on OBS's video frame clock, so a timestamp is the video
+//! offset the event lines up with. That clock only advances while the video pipeline
+//! renders; when it stalls (a context reinit, a wedged encoder) every event arriving
+//! meanwhile gets the same timestamp. The monotonic source times events on the agent's
+//! own steady clock instead. It reads the OBS frame clock once as each segment starts and
+//! records how far into the video its own zero falls, so the two timelines can still be
+//! aligned afterwards.
+
+use anyhow::Result;
+use tokio::time::Instant;
+
+use crate::config::ClockSource;
+use crate::data::Timebase;
+
+/// Reads the configured clock and hands out event timestamps for the current segment
+#[derive(Debug)]
+pub(super) struct EventClock {
+    source: ClockSource,
+    /// Zero point of the monotonic clock's nanosecond readings
+    origin: Instant,
+    /// OBS frame time the current segment's video started at
+    video_start_ns: Option<u64>,
+    /// Video offset (microseconds) the monotonic start of the current segment fell at
+    video_offset_us: Option<u64>,
+    /// Last event timestamp handed out on the monotonic clock in this segment
+    last_stamp_us: Option<u64>,
+}
+
+impl EventClock {
+    pub(super) fn new(source: ClockSource) -> Self {
+        Self {
+            source,
+            origin: Instant::now(),
+            video_start_ns: None,
+            video_offset_us: None,
+            last_stamp_us: None,
+        }
+    }
+
+    /// Start timing a segment whose video started at OBS frame time `video_start_ns`.
+    /// Returns the segment's start on the configured clock. On the monotonic clock,
+    /// `frame_time` is read at the same moment to anchor that start on the video.
+    pub(super) fn start_segment(
+        &mut self,
+        video_start_ns: u64,
+        frame_time: impl FnOnce() -> Result<u64>,
+    ) -> u64 {
+        self.video_start_ns = Some(video_start_ns);
+        self.video_offset_us = None;
+        self.last_stamp_us = None;
+        match self.source {
+            ClockSource::ObsFrameTime => video_start_ns,
+            ClockSource::Monotonic => {
+                let start_ns = self.monotonic_ns();
+                self.video_offset_us = frame_time()
+                    .ok()
+                    .map(|frame_ns| frame_ns.saturating_sub(video_start_ns) / 1000);
+                start_ns
+            }
+        }
+    }
+
+    /// The current time (nanoseconds) on the configured clock; `frame_time` reads OBS's
+    /// video frame clock and is only called in `ObsFrameTime` mode.
+    pub(super) fn now_ns(&self, frame_time: impl FnOnce() -> Result<u64>) -> Option<u64> {
+        match self.source {
+            ClockSource::ObsFrameTime => frame_time().ok(),
+            ClockSource::Monotonic => Some(self.monotonic_ns()),
+        }
+    }
+
+    /// Timestamp (microseconds since `start_ns`) for an input event read at `now_ns`. On
+    /// the monotonic clock each timestamp is later than the one before, even for events
+    /// read within the same microsecond.
+    pub(super) fn stamp_us(&mut self, start_ns: u64, now_ns: u64) -> u64 {
+        let elapsed_us = now_ns.saturating_sub(start_ns) / 1000;
+        if self.source != ClockSource::Monotonic {
+            return elapsed_us;
+        }
+        let stamp = match self.last_stamp_us {
+            Some(last) if elapsed_us <= last => last + 1,
+            _ => elapsed_us,
+        };
+        self.last_stamp_us = Some(stamp);
+        stamp
+    }
+
+    /// Timebase, video start and (monotonic clock only) video offset of the clock's zero
+    /// to record with a segment whose events were timed against `recording_start_ns`
+    /// (`None` when no recording clock was running)
+    pub(super) fn timebase(
+        &self,
+        recording_start_ns: Option<u64>,
+    ) -> (Timebase, Option<u64>, Option<u64>) {
+        match (self.source, recording_start_ns) {
+            (ClockSource::Monotonic, Some(_)) => (
+                Timebase::MonotonicUs,
+                self.video_start_ns,
+                self.video_offset_us,
+            ),
+            _ => {
+                let (timebase, video_start_ns) = Timebase::for_recording_start(recording_start_ns);
+                (timebase, video_start_ns, None)
+            }
+        }
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.origin.elapsed().as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monotonic_timestamps_advance_while_frame_time_stalls() {
+        let video_start_ns = 5_000_000_000;
+        let stalled = || Ok(video_start_ns + 2_000_000);
+
+        // On frame time, every event during the stall shares one timestamp.
+        let mut obs = EventClock::new(ClockSource::ObsFrameTime);
+        let start_ns = obs.start_segment(video_start_ns, stalled);
+        let stamps: Vec<u64> = (0..3)
+            .map(|_| {
+                let now_ns = obs.now_ns(stalled).unwrap();
+                obs.stamp_us(start_ns, now_ns)
+            })
+            .collect();
+        assert_eq!(stamps, vec![2000, 2000, 2000]);
+        assert_eq!(
+            obs.timebase(Some(start_ns)),
+            (Timebase::VideoRelativeUs, Some(video_start_ns), None)
+        );
+
+        // On the monotonic clock they keep increasing, and the frame time read when the
+        // segment started anchors them on the video: 2ms in.
+        let mut monotonic = EventClock::new(ClockSource::Monotonic);
+        let start_ns = monotonic.start_segment(video_start_ns, stalled);
+        let mut previous = None;
+        for _ in 0..1000 {
+            let now_ns = monotonic.now_ns(stalled).unwrap();
+            let stamp = monotonic.stamp_us(start_ns, now_ns);
+            assert!(previous.is_none_or(|previous| stamp > previous));
+            previous = Some(stamp);
+        }
+        assert_eq!(
+            monotonic.timebase(Some(start_ns)),
+            (Timebase::MonotonicUs, Some(video_start_ns), Some(2000))
+        );
+        assert_eq!(monotonic.timebase(None), (Timebase::BackendUs, None, None));
+
+        // A new segment restarts its timestamps at its own start.
+        let start_ns = monotonic.start_segment(video_start_ns + 60_000_000_000, stalled);
+        let now_ns = monotonic.now_ns(stalled).unwrap();
+        assert!(monotonic.stamp_us(start_ns, now_ns) < previous.unwrap());
+    }
+}
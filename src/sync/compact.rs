@@ -0,0 +1,227 @@
+//! Compaction of a session's small segments at stop (`recording.compact_below_bytes`)
+//!
+//! A short session, or a tiny `segment_duration_secs`, leaves many small segments that
+//! each cost an upload round trip and a stored object. When recording stops, the
+//! session's segments still held back from upload can be joined into one: the videos are
+//! remuxed end to end with ffmpeg's concat demuxer (stream copy, no re-encode) and the
+//! input logs are appended with each segment's events moved onto the joined timeline.
+//!
+//! ffmpeg and ffprobe are looked up on `PATH`, then in the usual package manager
+//! directories: an app launched from the macOS Finder or Dock gets a `PATH` without them.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::data::{CompletedChunk, InputEvent};
+
+/// Program the videos are remuxed with
+const FFMPEG: &str = "ffmpeg";
+
+/// Program the videos' durations are read with
+const FFPROBE: &str = "ffprobe";
+
+/// Where Homebrew (Apple silicon, then Intel) and MacPorts install programs, searched
+/// after `PATH`
+#[cfg(target_os = "macos")]
+const EXTRA_PROGRAM_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/opt/local/bin"];
+#[cfg(not(target_os = "macos"))]
+const EXTRA_PROGRAM_DIRS: &[&str] = &[];
+
+/// The chunks' events on one timeline, in order. A chunk's events move forward by the
+/// combined duration of the videos before it (`video_durations_us`, one per chunk), which
+/// is where its video begins in the joined file: the gaps between segments (the rotation
+/// itself, a pause) have no frames there. Chunks without a recording clock (`BackendUs`)
+/// are already on one clock and keep their timestamps.
+pub(super) fn concat_segment_events(
+    chunks: &[&CompletedChunk],
+    video_durations_us: &[u64],
+) -> Vec<InputEvent> {
+    let offsets_us = video_durations_us
+        .iter()
+        .scan(0u64, |elapsed_us, duration_us| {
+            let offset_us = *elapsed_us;
+            *elapsed_us += duration_us;
+            Some(offset_us)
+        });
+    let mut events: Vec<InputEvent> = chunks
+        .iter()
+        .zip(offsets_us)
+        .flat_map(|(chunk, offset_us)| {
+            let offset_us = if chunk.video_start_ns.is_some() {
+                offset_us
+            } else {
+                0
+            };
+            chunk.events.iter().map(move |event| InputEvent {
+                timestamp_us: event.timestamp_us + offset_us,
+                ..event.clone()
+            })
+        })
+        .collect();
+    // Stable, so events sharing a timestamp keep their segment and recording order.
+    events.sort_by_key(|e| e.timestamp_us);
+    events
+}
+
+/// Remux `videos` end to end into `output` without re-encoding. They have to share a
+/// container and codec settings, which segments of one session do.
+pub(super) fn remux_concat(videos: &[PathBuf], output: &Path) -> Result<()> {
+    let list_path = output.with_extension("concat.txt");
+    let mut list = std::fs::File::create(&list_path)
+        .with_context(|| format!("Failed to create {:?}", list_path))?;
+    for video in videos {
+        let video = std::path::absolute(video)?;
+        writeln!(list, "file '{}'", concat_list_escape(&video))?;
+    }
+    drop(list);
+
+    let result = Command::new(find_program(FFMPEG))
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        .args(["-c", "copy"])
+        .arg(output)
+        .stdin(Stdio::null())
+        .output();
+    let _ = std::fs::remove_file(&list_path);
+
+    let output_status = result.with_context(|| format!("Failed to run {}", FFMPEG))?;
+    if !output_status.status.success() {
+        let _ = std::fs::remove_file(output);
+        bail!(
+            "{} exited with {}: {}",
+            FFMPEG,
+            output_status.status,
+            String::from_utf8_lossy(&output_status.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Duration of `video`'s container in microseconds, as ffprobe reads it
+pub(super) fn video_duration_us(video: &Path) -> Result<u64> {
+    let output = Command::new(find_program(FFPROBE))
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", FFPROBE))?;
+    if !output.status.success() {
+        bail!(
+            "{} exited with {}: {}",
+            FFPROBE,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let secs: f64 = text
+        .trim()
+        .parse()
+        .with_context(|| format!("{} reported no duration for {:?}", FFPROBE, video))?;
+    Ok((secs * 1_000_000.0).round() as u64)
+}
+
+/// Full path of `program` in the first `PATH` or `EXTRA_PROGRAM_DIRS` directory that has
+/// it; the bare name (which then fails to run) when none does
+fn find_program(program: &str) -> PathBuf {
+    let path_dirs = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    path_dirs
+        .into_iter()
+        .chain(EXTRA_PROGRAM_DIRS.iter().map(PathBuf::from))
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| PathBuf::from(program))
+}
+
+/// `path` quoted for a concat list `file '...'` line: a `'` closes the quote, is escaped,
+/// and reopens it
+fn concat_list_escape(path: &Path) -> String {
+    path.to_string_lossy().replace('\'', r"'\''")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{EventType, KeyEvent, SegmentStats, Timebase};
+
+    fn chunk(video_start_ns: Option<u64>, keys: &[(u64, u32)]) -> CompletedChunk {
+        let events: Vec<InputEvent> = keys
+            .iter()
+            .map(|&(timestamp_us, code)| {
+                InputEvent::now(
+                    timestamp_us,
+                    EventType::KeyPress(KeyEvent {
+                        code,
+                        name: "KeyA".to_string(),
+                        is_repeat: false,
+                    }),
+                )
+            })
+            .collect();
+        CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0000".to_string(),
+            video_path: None,
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: Timebase::VideoRelativeUs,
+            video_start_ns,
+            stats: SegmentStats::new(&events, 0, 0, 0),
+            events,
+        }
+    }
+
+    fn timeline(events: &[InputEvent]) -> Vec<(u64, u32)> {
+        events
+            .iter()
+            .map(|e| match &e.event {
+                EventType::KeyPress(key) => (e.timestamp_us, key.code),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn concatenated_events_follow_the_joined_video_timeline() {
+        // Three segments of 2s, 2s and 1s of video. The rotations left gaps between them
+        // (300ms, then 1.2s across a pause) that the joined video doesn't have.
+        let first = chunk(Some(5_000_000_000), &[(0, 1), (1_500_000, 2)]);
+        let second = chunk(Some(7_300_000_000), &[(0, 3), (1_999_000, 4)]);
+        let third = chunk(Some(10_500_000_000), &[(0, 5), (500_000, 6)]);
+        let durations_us = [2_000_000, 2_000_000, 1_000_000];
+
+        let merged = concat_segment_events(&[&first, &second, &third], &durations_us);
+        assert_eq!(
+            timeline(&merged),
+            vec![
+                (0, 1),
+                (1_500_000, 2),
+                (2_000_000, 3),
+                (3_999_000, 4),
+                (4_000_000, 5),
+                (4_500_000, 6),
+            ]
+        );
+
+        // Without a recording clock the timestamps are left alone and merely ordered.
+        let early = chunk(None, &[(300, 7), (900, 8)]);
+        let late = chunk(None, &[(600, 9)]);
+        let merged = concat_segment_events(&[&early, &late], &[1_000, 1_000]);
+        assert_eq!(timeline(&merged), vec![(300, 7), (600, 9), (900, 8)]);
+    }
+
+    #[test]
+    fn concat_list_quotes_survive_apostrophes() {
+        assert_eq!(
+            concat_list_escape(Path::new("/Users/o'neil/seg.mp4")),
+            r"/Users/o'\''neil/seg.mp4"
+        );
+    }
+}
@@ -11,9 +11,9 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -22,26 +22,45 @@ use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use crate::capture::{
-    get_display_uuid, get_frontmost_app, get_main_display_resolution, CaptureContext,
-    DisplayChangeEvent, DisplayMonitor, RecordingSession,
+    get_display_uuid, get_focused_window_geometry, get_focused_window_title, get_frontmost_app,
+    get_main_display_resolution, get_main_display_scale, list_capturable_apps, list_running_apps,
+    AppInfo, CaptureContext, DisplayChangeEvent, DisplayMonitor, FrameLumaProbe, RecordingSession,
+    SetupOutcome, BLACK_PEAK_LUMA, SHUTDOWN_FLUSH_TIMEOUT,
 };
-use crate::config::Config;
+use crate::config::{Config, OrphanedPartialsPolicy, TargetExitPolicy};
 use crate::data::{
-    CompletedChunk, ContextEvent, EventType, InputEvent, InputEventBuffer, MetadataEvent,
-    UNCAPTURED_APP_ID, UNKNOWN_APP_ID,
+    decode_input_log, encode_input_log, AudioLevelEvent, CompletedChunk, ContextEvent,
+    DisplayScaleEvent, EventType, InputEvent, InputEventBuffer, MarkerEvent, MetadataEvent,
+    SegmentStats, Timebase, WindowGeometryEvent, UNCAPTURED_APP_ID, UNKNOWN_APP_ID,
 };
+use crate::input::filter::{CaptureRegion, FilterChain, FilterContext};
+use crate::input::hotkey::{Hotkey, HotkeyMatcher};
 use crate::input::{create_input_backend, InputBackend};
-use crate::installer::permissions::describe_missing_permissions;
+use crate::installer::permissions::{describe_missing_permissions, input_permission_granted};
 use crate::ui::notifications::{
-    is_authorized as notifications_authorized, show_idle_paused_notification,
+    is_authorized as notifications_authorized, show_audio_flat_notification,
+    show_capture_sources_failed_notification, show_idle_paused_notification,
     show_idle_resumed_notification, show_low_disk_notification,
-    show_permissions_missing_notification, show_recording_paused_notification,
-    show_recording_resumed_notification, show_recording_started_notification,
-    show_recording_stopped_notification, NotificationAction,
+    show_permission_revoked_notification, show_permissions_missing_notification,
+    show_recording_paused_notification, show_recording_resumed_notification,
+    show_recording_started_notification, show_recording_stopped_notification,
+    show_target_app_exited_notification, NotificationAction,
+};
+use crate::upload::{
+    is_playlist, playlist_pieces, remove_upload_leftovers, video_bytes, ProgressSink, UploadError,
+    Uploader,
 };
-use crate::upload::Uploader;
 
-use super::{EngineCommand, EngineStatus};
+use super::audio_levels::{clamp_level_db, FlatAudioDetector};
+use super::bitrate::{BitrateGovernor, UploadThroughput};
+use super::clock::EventClock;
+use super::compact::{concat_segment_events, remux_concat, video_duration_us};
+use super::input_gate::InputGate;
+use super::resources::ResourceSampler;
+use super::webhooks::{WebhookPayload, Webhooks};
+use super::{
+    CapturableApp, EngineCommand, EngineStatus, EventTypeCounts, RecordingHealth, StatusSnapshot,
+};
 
 /// Warn when free space on the recording volume drops below this. crowd-cast's
 /// own files stay small (uploads delete them), so this mostly catches the disk
@@ -50,10 +69,29 @@ const LOW_DISK_THRESHOLD_BYTES: u64 = 2 * 1024 * 1024 * 1024; // 2 GiB
 /// How often to check free space (it's a syscall, so don't run it every poll).
 const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
+/// How often to check whether a target app is still running (`capture.on_target_exit`)
+const TARGET_APP_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Retry-queue depth at which uploads count as stalled (network down or endpoint failing).
+const UPLOAD_STALL_QUEUE_THRESHOLD: usize = 10;
+/// Segments arriving with no successful upload for this long also count as a stall.
+const UPLOAD_STALL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often to recompute the consolidated recording health. Frame progress is measured
+/// between checks, so this must comfortably exceed a frame interval.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
 /// How often to re-check the captured source resolution for changes. Resolution
 /// changes are rare (app switch / window resize), so this need not run every poll.
 const SOURCE_RES_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
+/// How often to re-check the main display's scale factor. Scaling switches are rare,
+/// and the Wayland query opens a fresh compositor connection, so keep this coarse.
+const DISPLAY_SCALE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often to re-check, while recording, that input capture is still permitted
+const INPUT_PERMISSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Wall-clock gap between consecutive poll ticks above which we treat the process as having been
 /// frozen by a system suspend (Windows/Linux) — far longer than any real poll interval or hitch,
 /// so only a genuine sleep/resume trips it. On trip, an in-progress recording is restarted fresh.
@@ -466,16 +504,25 @@ where
 {
     const OBS_CALL_TIMEOUT: Duration = Duration::from_secs(5);
 
+    obs_call_with_watchdog_timeout(f, description, OBS_CALL_TIMEOUT)
+}
+
+/// `obs_call_with_watchdog` for calls that legitimately wait longer, such as a shutdown
+/// that lets the muxer finish the file first.
+fn obs_call_with_watchdog_timeout<F, T>(f: F, description: &str, timeout: Duration) -> T
+where
+    F: FnOnce() -> T,
+{
     let completed = Arc::new(AtomicBool::new(false));
     let completed_clone = completed.clone();
     let desc = description.to_string();
 
     std::thread::spawn(move || {
-        std::thread::sleep(OBS_CALL_TIMEOUT);
+        std::thread::sleep(timeout);
         if !completed_clone.load(AtomicOrdering::SeqCst) {
             error!(
                 "OBS call '{}' hung for {:?} — restarting process",
-                desc, OBS_CALL_TIMEOUT
+                desc, timeout
             );
             restart_process();
         }
@@ -602,7 +649,7 @@ fn append_pending_upload(entry: PendingUploadEntry) {
     write_pending_uploads(&entries);
 }
 
-fn remove_pending_upload(chunk_id: &str) {
+pub(crate) fn remove_pending_upload(chunk_id: &str) {
     let mut entries = read_pending_uploads();
     let before = entries.len();
     entries.retain(|e| e.chunk_id != chunk_id);
@@ -611,6 +658,316 @@ fn remove_pending_upload(chunk_id: &str) {
     }
 }
 
+// --- Pre-upload validation (`upload.validate_before_upload`) ---
+
+/// Subdirectory of the output directory holding segments that failed validation
+const INVALID_SEGMENTS_DIR: &str = "invalid";
+
+/// Whether `header` (the first bytes of a video file) starts like a container OBS writes:
+/// an ISO-BMFF box (MP4/MOV), Matroska's EBML magic, FLV, MPEG-TS sync bytes, or an HLS
+/// playlist.
+fn is_video_container_header(header: &[u8]) -> bool {
+    const ISO_BOX_TYPES: [&[u8]; 6] = [b"ftyp", b"moov", b"mdat", b"free", b"skip", b"wide"];
+    (header.len() >= 8 && ISO_BOX_TYPES.contains(&&header[4..8]))
+        || header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3])
+        || header.starts_with(b"FLV")
+        || (header.len() > 188 && header[0] == 0x47 && header[188] == 0x47)
+        || header.starts_with(b"#EXTM3U")
+}
+
+/// A chunk's video files: the video itself, preceded for an HLS playlist by the pieces it
+/// lists (which have to be read before the playlist is moved or deleted)
+fn chunk_video_files(chunk: &CompletedChunk) -> Vec<PathBuf> {
+    let Some(ref video_path) = chunk.video_path else {
+        return Vec::new();
+    };
+    let mut files = playlist_pieces(video_path);
+    files.push(video_path.clone());
+    files
+}
+
+/// Remove what an unfinished upload of a chunk's video left on disk, once the chunk is
+/// deleted or given up on
+fn remove_chunk_upload_leftovers(chunk: &CompletedChunk) {
+    if let Some(video_path) = chunk.video_path.as_deref() {
+        remove_upload_leftovers(video_path);
+    }
+}
+
+/// Combined size of a segment's video and input files (missing files count as 0)
+fn segment_file_bytes(segment: &CompletedSegment) -> u64 {
+    chunk_video_files(&segment.chunk)
+        .iter()
+        .chain(std::iter::once(&segment.input_path))
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Check a segment's files before upload: a non-empty video with a container header, and
+/// an input log that deserializes. Returns why the segment looks corrupt, if it does.
+fn validate_segment(segment: &CompletedSegment) -> Result<(), String> {
+    use std::io::Read as _;
+
+    if let Some(ref video_path) = segment.chunk.video_path {
+        let mut header = Vec::with_capacity(189);
+        std::fs::File::open(video_path)
+            .and_then(|file| file.take(189).read_to_end(&mut header))
+            .map_err(|e| format!("can't read video {:?}: {}", video_path, e))?;
+        if header.is_empty() {
+            return Err(format!("video {:?} is empty", video_path));
+        }
+        if !is_video_container_header(&header) {
+            return Err(format!(
+                "video {:?} has no valid container header",
+                video_path
+            ));
+        }
+    }
+
+    let bytes = std::fs::read(&segment.input_path)
+        .map_err(|e| format!("can't read input log {:?}: {}", segment.input_path, e))?;
+    decode_input_log(&bytes).map_err(|e| {
+        format!(
+            "input log {:?} doesn't deserialize: {:#}",
+            segment.input_path, e
+        )
+    })?;
+    Ok(())
+}
+
+/// Move a segment's files into `invalid/` under `output_dir`, out of the upload queue and
+/// local retention, but kept for inspection.
+fn quarantine_segment(output_dir: &Path, segment: &CompletedSegment) {
+    let dir = output_dir.join(INVALID_SEGMENTS_DIR);
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        warn!("Failed to create quarantine directory {:?}: {}", dir, e);
+        return;
+    }
+    let video_files = chunk_video_files(&segment.chunk);
+    let files = video_files
+        .iter()
+        .chain(std::iter::once(&segment.input_path));
+    for path in files {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        match std::fs::rename(path, dir.join(name)) {
+            Ok(()) => debug!("Quarantined {:?}", path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to quarantine {:?}: {}", path, e),
+        }
+    }
+    remove_chunk_upload_leftovers(&segment.chunk);
+}
+
+// --- Local retention (rolling archive of uploaded segments) ---
+
+/// Uploaded segment files kept for local retention, oldest first.
+fn retained_uploads_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("dev", "crowd-cast", "agent")
+        .map(|p| p.data_dir().join("retained_uploads.json"))
+}
+
+pub(crate) fn read_retained_uploads() -> Vec<PathBuf> {
+    retained_uploads_path()
+        .and_then(|p| std::fs::read_to_string(&p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn write_retained_uploads(entries: &[PathBuf]) {
+    let Some(path) = retained_uploads_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(entries) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                warn!("Failed to write retained uploads manifest: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize retained uploads: {}", e),
+    }
+}
+
+/// Caps on the rolling archive of uploaded segments (`recording.local_retention_bytes` and
+/// `recording.local_retention_count`; 0 disables a cap)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct LocalRetention {
+    bytes: u64,
+    segments: usize,
+}
+
+impl LocalRetention {
+    /// Whether uploaded files are kept (and pruned) rather than deleted right away
+    fn is_enabled(&self) -> bool {
+        self.bytes > 0 || self.segments > 0
+    }
+}
+
+/// Segment a retained file belongs to: `recording_<id>.<ext>` and `input_<id>.msgpack`
+/// share `<id>`. Any other name is its own segment.
+fn retained_segment_id(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let id = name
+        .strip_prefix("recording_")
+        .or_else(|| name.strip_prefix("input_"))
+        .unwrap_or(&name);
+    id.split('.').next().unwrap_or(id).to_string()
+}
+
+/// Delete the oldest `retained` files until everything in `dir` fits in `limits.bytes` and
+/// at most `limits.segments` uploaded segments remain; whichever cap is stricter decides.
+/// Only files in `retained` (already uploaded) are candidates, and anything in `pending`
+/// is skipped even if listed, so a segment awaiting upload is never lost. Entries whose
+/// file is already gone are dropped from `retained`. Returns the deleted paths.
+fn prune_local_retention(
+    dir: &Path,
+    limits: LocalRetention,
+    retained: &mut Vec<PathBuf>,
+    pending: &HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    retained.retain(|path| path.exists());
+
+    let mut total: u64 = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|meta| meta.is_file())
+                .map(|meta| meta.len())
+                .sum()
+        })
+        .unwrap_or(0);
+
+    // Files left per retained segment; a segment stops counting once all of them are gone.
+    let mut segment_files: std::collections::HashMap<String, usize> =
+        std::collections::HashMap::new();
+    for path in retained.iter() {
+        *segment_files.entry(retained_segment_id(path)).or_default() += 1;
+    }
+    let mut segments = segment_files.len();
+
+    let mut pruned = Vec::new();
+    let mut kept = Vec::with_capacity(retained.len());
+    for path in retained.drain(..) {
+        let over_bytes = limits.bytes > 0 && total > limits.bytes;
+        let over_count = limits.segments > 0 && segments > limits.segments;
+        if !(over_bytes || over_count) || pending.contains(&path) {
+            kept.push(path);
+            continue;
+        }
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                total = total.saturating_sub(size);
+                if let Some(left) = segment_files.get_mut(&retained_segment_id(&path)) {
+                    *left -= 1;
+                    if *left == 0 {
+                        segments -= 1;
+                    }
+                }
+                pruned.push(path);
+            }
+            Err(e) => {
+                warn!("Failed to prune retained file {:?}: {}", path, e);
+                kept.push(path);
+            }
+        }
+    }
+    *retained = kept;
+    pruned
+}
+
+// --- Global segment counter (monotonic across sessions and restarts) ---
+
+fn segment_counter_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("dev", "crowd-cast", "agent")
+        .map(|p| p.data_dir().join("segment_counter"))
+}
+
+/// Hand out the next value of the counter persisted at `path` (starting at 0). The file
+/// holds the next value to hand out; it is replaced by an fsynced temp file and rename,
+/// so a crash leaves either the old or the new count and no value is ever handed out twice.
+fn next_segment_counter(path: &Path) -> Result<u64> {
+    let value = match std::fs::read_to_string(path) {
+        Ok(contents) => contents.trim().parse::<u64>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    {
+        use std::io::Write;
+        let mut file = std::fs::File::create(&tmp)?;
+        write!(file, "{}", value + 1)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(value)
+}
+
+// --- Interrupted-session resume (`recording.resume_on_restart`) ---
+
+/// The recording session in progress, kept on disk while recording so the next run can
+/// tell a crash cut it short (a clean stop removes it)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ActiveSessionMarker {
+    session_id: String,
+    /// Index of the segment being recorded
+    segment_index: u32,
+    /// Video the segment is being recorded to
+    output_path: PathBuf,
+}
+
+fn active_session_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("dev", "crowd-cast", "agent")
+        .map(|p| p.data_dir().join("active_session.json"))
+}
+
+fn read_active_session(path: &Path) -> Option<ActiveSessionMarker> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn write_active_session(path: &Path, marker: &ActiveSessionMarker) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(marker) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(path, json) {
+                warn!("Failed to write active session marker: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize active session marker: {}", e),
+    }
+}
+
+/// Session ID and segment index an interrupted session continues at: the segment after
+/// the one the crash cut short. `None` when that segment's video is gone, so there is
+/// nothing on disk left to continue from.
+fn resume_point(marker: &ActiveSessionMarker) -> Option<(String, u32)> {
+    if !marker.output_path.is_file() {
+        return None;
+    }
+    Some((marker.session_id.clone(), marker.segment_index + 1))
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum StatusKind {
     Idle,
@@ -618,7 +975,9 @@ enum StatusKind {
     RecordingBlocked,
     WaitingForOBS,
     Uploading,
+    UploadProgress,
     Error,
+    Health,
 }
 
 impl StatusKind {
@@ -629,10 +988,203 @@ impl StatusKind {
             EngineStatus::Paused => Self::Idle,
             EngineStatus::RecordingBlocked => Self::RecordingBlocked,
             EngineStatus::WaitingForOBS => Self::WaitingForOBS,
-            EngineStatus::Uploading { .. } => Self::Uploading,
+            EngineStatus::Uploading { .. } | EngineStatus::Draining { .. } => Self::Uploading,
+            EngineStatus::UploadProgress { .. } => Self::UploadProgress,
             EngineStatus::Error(_) => Self::Error,
+            EngineStatus::Health(_) => Self::Health,
+        }
+    }
+}
+
+/// Debounce for `DisplayChangeEvent::OriginalReturned`. A returned display is only
+/// recovered once it has stayed connected for `delay`; each further return restarts the
+/// wait, and the display disappearing again (or capture moving to another display)
+/// cancels it, so a flapping display yields a single source rebuild after it settles.
+struct DisplayRecoveryDebounce {
+    delay: Duration,
+    pending: Option<(Instant, DisplayChangeEvent)>,
+}
+
+impl DisplayRecoveryDebounce {
+    fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Feed a display change. Returns the event to act on now, if any.
+    fn on_event(&mut self, event: DisplayChangeEvent, now: Instant) -> Option<DisplayChangeEvent> {
+        if self.delay.is_zero() {
+            return Some(event);
+        }
+        match event {
+            DisplayChangeEvent::OriginalReturned { .. } => {
+                if self.pending.is_some() {
+                    debug!("Display returned again; restarting the recovery debounce");
+                }
+                self.pending = Some((now + self.delay, event));
+                None
+            }
+            other => {
+                if self.pending.take().is_some() {
+                    info!("Display changed again before settling; cancelled pending recovery");
+                }
+                Some(other)
+            }
+        }
+    }
+
+    /// The pending recovery, once the display has been stable for the full delay.
+    fn poll(&mut self, now: Instant) -> Option<DisplayChangeEvent> {
+        match &self.pending {
+            Some((due, _)) if now >= *due => self.pending.take().map(|(_, event)| event),
+            _ => None,
+        }
+    }
+}
+
+/// Put a segment's events (partial flushes, the live buffer and transition input, each
+/// in its own batch) into timeline order. The sort is stable, so events sharing a
+/// timestamp — e.g. a marker added on the same frame as a keystroke — keep the order
+/// they were recorded in.
+fn sort_segment_events(events: &mut [InputEvent]) {
+    events.sort_by_key(|e| e.timestamp_us);
+}
+
+/// Last known display scale factor, so each scale transition is logged exactly once.
+#[derive(Debug, Default)]
+struct DisplayScaleTracker {
+    current: Option<f64>,
+}
+
+impl DisplayScaleTracker {
+    /// Record a scale that was already logged (in a metadata event) without reporting it.
+    fn reset(&mut self, scale: Option<f64>) {
+        self.current = scale;
+    }
+
+    /// Record the latest observed scale. Returns it when it differs from the last one.
+    fn observe(&mut self, scale: f64) -> Option<f64> {
+        if self
+            .current
+            .is_some_and(|current| (current - scale).abs() < 1e-3)
+        {
+            return None;
+        }
+        self.current = Some(scale);
+        Some(scale)
+    }
+}
+
+/// Last logged frame of the focused window, so only actual moves and resizes are logged.
+/// Frames are read off the engine loop (see `WindowGeometryLookup`), one lookup at a time.
+#[derive(Debug, Default)]
+struct WindowGeometryTracker {
+    current: Option<WindowGeometryEvent>,
+    /// Bumped by `reset`, so a lookup requested before it is dropped when it lands
+    generation: u64,
+    /// A lookup is running; further ones are skipped until it's back, so an app that
+    /// stops answering can't pile them up
+    in_flight: bool,
+}
+
+impl WindowGeometryTracker {
+    /// Forget the last frame, so the next observation is reported (segment start, or the
+    /// focus leaving the captured apps).
+    fn reset(&mut self) {
+        self.current = None;
+        self.generation += 1;
+    }
+
+    /// Claim the next lookup, returning the generation to tag it with; None while one is
+    /// still running
+    fn start_lookup(&mut self) -> Option<u64> {
+        if self.in_flight {
+            return None;
+        }
+        self.in_flight = true;
+        Some(self.generation)
+    }
+
+    /// Take a finished lookup. Returns its frame when it's current and differs from the
+    /// last one.
+    fn finish_lookup(
+        &mut self,
+        generation: u64,
+        frame: Option<WindowGeometryEvent>,
+    ) -> Option<WindowGeometryEvent> {
+        self.in_flight = false;
+        if generation != self.generation {
+            return None;
+        }
+        self.observe(frame?)
+    }
+
+    /// Record the latest observed frame. Returns it when it differs from the last one.
+    fn observe(&mut self, frame: WindowGeometryEvent) -> Option<WindowGeometryEvent> {
+        if self.current == Some(frame) {
+            return None;
+        }
+        self.current = Some(frame);
+        Some(frame)
+    }
+}
+
+/// A focused-window frame read on the blocking pool: the Accessibility call can wait on
+/// an unresponsive app, which the engine loop shouldn't.
+#[derive(Debug)]
+struct WindowGeometryLookup {
+    /// `WindowGeometryTracker` generation it was requested under
+    generation: u64,
+    /// Recording time to log the frame at
+    timestamp_us: u64,
+    frame: Option<WindowGeometryEvent>,
+}
+
+/// How long a frontmost window title read for `capture.title_patterns` is reused. Capture
+/// state is re-evaluated on every input event; the title only needs polling speed.
+const WINDOW_TITLE_REFRESH: Duration = Duration::from_millis(200);
+
+/// Last focused-window title read per frontmost process, re-read after
+/// `WINDOW_TITLE_REFRESH` or when the process changes. Reads go through the Accessibility
+/// API, which can wait on an unresponsive app, so they run off the engine loop: `get`
+/// answers from the cache and requests a read, `store` takes the result.
+#[derive(Debug, Default)]
+struct WindowTitleCache {
+    last: Option<(u32, Instant, Option<String>)>,
+    /// A read was requested and hasn't been stored yet
+    in_flight: bool,
+}
+
+impl WindowTitleCache {
+    /// Title last read for `pid` (None until one is back), calling `request` to start a
+    /// read when it's stale and none is running
+    fn get(&mut self, pid: u32, now: Instant, request: impl FnOnce(u32)) -> Option<&str> {
+        let fresh = matches!(
+            &self.last,
+            Some((last_pid, read_at, _))
+                if *last_pid == pid && now.duration_since(*read_at) < WINDOW_TITLE_REFRESH
+        );
+        if !fresh && !self.in_flight {
+            self.in_flight = true;
+            request(pid);
+        }
+        match &self.last {
+            Some((last_pid, _, title)) if *last_pid == pid => title.as_deref(),
+            _ => None,
         }
     }
+
+    /// Store a finished read of `pid`'s title
+    fn store(&mut self, pid: u32, now: Instant, title: Option<String>) {
+        self.in_flight = false;
+        self.last = Some((pid, now, title));
+    }
 }
 
 /// A completed segment ready for upload
@@ -648,89 +1200,575 @@ struct CompletedSegment {
 enum UploadMessage {
     StartSession(String),
     Segment(CompletedSegment),
+    /// Shutdown drain: retry everything queued now and report the remaining count on the
+    /// sender after each change. The sender is dropped once the queue is empty (or
+    /// uploads are paused, in which case nothing will drain).
+    Drain(mpsc::UnboundedSender<usize>),
 }
 
-/// Result sent back from a spawned upload task
-struct UploadResult {
-    chunk_id: String,
-    segment: CompletedSegment,
-    attempts: u32,
-    first_failed_at: Instant,
-    result: Result<()>,
+/// Periodic re-check of the input-capture permission while recording. Revoking
+/// Accessibility on macOS doesn't make the input backend fail, it just goes quiet, so
+/// without this a recording would carry on as video with no input.
+struct InputPermissionWatch {
+    /// Permission query; `input_permission_granted` outside tests
+    probe: fn() -> bool,
+    last_check: Instant,
 }
 
-#[derive(Debug)]
-struct RetryItem {
-    segment: CompletedSegment,
-    attempts: u32,
-    first_failed_at: Instant,
-    next_attempt_at: Instant,
+impl InputPermissionWatch {
+    fn new(probe: fn() -> bool, now: Instant) -> Self {
+        Self {
+            probe,
+            last_check: now,
+        }
+    }
+
+    /// Returns true when a due check finds the permission revoked.
+    fn revoked(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last_check) < INPUT_PERMISSION_CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = now;
+        !(self.probe)()
+    }
 }
 
-#[derive(Debug)]
-struct RetryEntry {
-    next_attempt_at: Instant,
-    sequence: u64,
-    item: RetryItem,
+/// Debounce for the upload-stalled notification. A stall episode starts when the retry
+/// queue grows past `UPLOAD_STALL_QUEUE_THRESHOLD`, or when segments are still waiting
+/// but nothing has uploaded for `UPLOAD_STALL_TIMEOUT`. It fires once per episode; the
+/// next successful upload ends the episode.
+struct UploadStallDetector {
+    last_success: Instant,
+    notified: bool,
 }
 
-impl Ord for RetryEntry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Reverse for min-heap behavior.
-        other
-            .next_attempt_at
-            .cmp(&self.next_attempt_at)
-            .then_with(|| other.sequence.cmp(&self.sequence))
+impl UploadStallDetector {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_success: now,
+            notified: false,
+        }
     }
-}
 
-impl PartialOrd for RetryEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn record_success(&mut self, now: Instant) {
+        self.last_success = now;
+        self.notified = false;
     }
-}
 
-impl PartialEq for RetryEntry {
-    fn eq(&self, other: &Self) -> bool {
-        self.next_attempt_at == other.next_attempt_at && self.sequence == other.sequence
+    /// Returns true exactly once per stall episode, when the notification should fire.
+    fn check(&mut self, pending: usize, now: Instant) -> bool {
+        if self.notified || pending == 0 {
+            return false;
+        }
+        let stalled = pending >= UPLOAD_STALL_QUEUE_THRESHOLD
+            || now.duration_since(self.last_success) >= UPLOAD_STALL_TIMEOUT;
+        if stalled {
+            self.notified = true;
+        }
+        stalled
     }
 }
 
-impl Eq for RetryEntry {}
-
-#[derive(Debug, Clone)]
-struct PendingAppSwitch {
-    target_app: Option<String>,
-    scheduled_at: Instant,
+/// While the circuit is half-open, how long other due uploads wait for the probe's verdict
+const UPLOAD_BREAKER_PROBE_WAIT: Duration = Duration::from_secs(5);
+
+/// Circuit breaker for the upload endpoint, so an outage isn't hammered by every queued
+/// retry. After `threshold` consecutive failures the circuit opens and nothing is attempted
+/// for `cooldown`; it then half-opens and lets a single probe through. A successful upload
+/// closes it; a failed probe re-opens it for another cooldown. `threshold` 0 disables it.
+/// Whether it is open is mirrored to `open` for the status snapshot.
+struct UploadCircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    state: BreakerState,
+    open: Arc<AtomicBool>,
 }
 
-#[derive(Debug, Clone)]
-struct PendingCaptureWatchdog {
-    expected_app: String,
-    deadline: Instant,
-    attempt: u32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakerState {
+    Closed,
+    Open {
+        until: Instant,
+    },
+    /// Cooldown over; the probe upload is in flight
+    HalfOpen,
 }
 
-#[derive(Debug)]
-struct PendingInputTransition {
-    target_app: String,
-    events: Vec<InputEvent>,
-}
+impl UploadCircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration, open: Arc<AtomicBool>) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            consecutive_failures: 0,
+            state: BreakerState::Closed,
+            open,
+        }
+    }
 
-const CAPTURING_STATUS_INTERVAL: Duration = Duration::from_secs(1);
-const MAX_TRANSITION_INPUT_EVENTS: usize = 512;
+    /// Whether an upload may start now. Past the cooldown, the first caller becomes the
+    /// half-open probe.
+    fn allow(&mut self, now: Instant) -> bool {
+        match self.state {
+            BreakerState::Closed => true,
+            BreakerState::Open { until } if now >= until => {
+                info!("Upload circuit half-open: probing the endpoint with one upload");
+                self.state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen => false,
+        }
+    }
 
-/// The synchronization engine coordinates recording and input capture
-pub struct SyncEngine {
-    /// Configuration
-    config: Config,
-    /// Capture context for libobs operations
-    capture_ctx: CaptureContext,
+    /// When an upload refused by `allow` should be tried again
+    fn retry_at(&self, now: Instant) -> Instant {
+        match self.state {
+            BreakerState::Open { until } => until.max(now),
+            _ => now + UPLOAD_BREAKER_PROBE_WAIT,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        if self.state != BreakerState::Closed {
+            info!("Upload circuit closed: the endpoint is accepting uploads again");
+        }
+        self.set_state(BreakerState::Closed);
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let trip = match self.state {
+            BreakerState::Closed => {
+                self.threshold > 0 && self.consecutive_failures >= self.threshold
+            }
+            BreakerState::HalfOpen => true,
+            BreakerState::Open { .. } => false,
+        };
+        if trip {
+            warn!(
+                "Upload circuit open after {} consecutive failure(s); pausing uploads for {}s",
+                self.consecutive_failures,
+                self.cooldown.as_secs()
+            );
+            self.set_state(BreakerState::Open {
+                until: now + self.cooldown,
+            });
+        }
+    }
+
+    /// An upload `allow` let through never ran (uploads were paused while it waited). If
+    /// it was the half-open probe, the circuit goes back to open with its cooldown over, so
+    /// the next upload becomes the probe instead of the circuit waiting on it forever.
+    fn probe_cancelled(&mut self, now: Instant) {
+        if self.state == BreakerState::HalfOpen {
+            self.set_state(BreakerState::Open { until: now });
+        }
+    }
+
+    fn set_state(&mut self, state: BreakerState) {
+        self.state = state;
+        self.open
+            .store(state != BreakerState::Closed, AtomicOrdering::SeqCst);
+    }
+}
+
+/// Combine the recording health sub-checks. `source_dims` are the active source's
+/// dimensions; `prev_frame_ns`/`frame_ns` are the video frame clock at the previous and
+/// current check (no previous sample yet counts as advancing, so a fresh recording is not
+/// flagged before it could have been measured).
+fn compute_recording_health(
+    source_dims: Option<(u32, u32)>,
+    prev_frame_ns: Option<u64>,
+    frame_ns: Option<u64>,
+    low_disk: bool,
+    uploads_stalled: bool,
+    audio_flat: bool,
+) -> RecordingHealth {
+    let frames_advancing = match (prev_frame_ns, frame_ns) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(prev), Some(now)) => now > prev,
+    };
+    RecordingHealth {
+        sources_active: source_dims.is_some_and(|(w, h)| w > 0 && h > 0),
+        frames_advancing,
+        disk_ok: !low_disk,
+        uploads_keeping_up: !uploads_stalled,
+        audio_ok: !audio_flat,
+    }
+}
+
+/// When the idle auto-pause should fire: `idle_timeout` after the last recorded action,
+/// while a recording is running and not already paused. None when idle pausing is
+/// disabled (zero timeout) or there is nothing to pause.
+fn idle_pause_deadline(
+    last_recorded_action: Instant,
+    idle_timeout: Duration,
+    recording: bool,
+    paused: bool,
+) -> Option<Instant> {
+    if idle_timeout.is_zero() || paused || !recording {
+        return None;
+    }
+    Some(last_recorded_action + idle_timeout)
+}
+
+/// When the session should end for inactivity: `idle_session_end` after the last input,
+/// while a recording is running. An idle pause still counts as idle; a pause the user
+/// asked for (or `capture.on_target_exit` made) does not, so `held_paused` disarms it.
+/// None when disabled (zero) or there is no session to end.
+fn idle_session_end_deadline(
+    last_input: Instant,
+    idle_session_end: Duration,
+    recording: bool,
+    held_paused: bool,
+) -> Option<Instant> {
+    if idle_session_end.is_zero() || held_paused || !recording {
+        return None;
+    }
+    Some(last_input + idle_session_end)
+}
+
+/// Ends the session after `capture.idle_session_end_secs` without input, and has the
+/// next input start a new one
+#[derive(Debug)]
+struct IdleSessionEnd {
+    /// No-input time after which the session ends (Duration::ZERO means disabled)
+    window: Duration,
+    /// The session was ended for inactivity; the next input starts a new one
+    ended: bool,
+}
+
+impl IdleSessionEnd {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            ended: false,
+        }
+    }
+
+    /// See `idle_session_end_deadline`
+    fn deadline(&self, last_input: Instant, recording: bool, held_paused: bool) -> Option<Instant> {
+        idle_session_end_deadline(last_input, self.window, recording, held_paused)
+    }
+
+    /// The session was just ended for inactivity
+    fn end(&mut self) {
+        self.ended = true;
+    }
+
+    /// Input arrived: whether it should start a new session. Only the first input after
+    /// an idle end does.
+    fn take_restart(&mut self) -> bool {
+        std::mem::take(&mut self.ended)
+    }
+}
+
+/// Ask the upload task to finish everything queued and wait up to `timeout` for it to
+/// report an empty queue. `on_progress` sees each remaining-count update. Returns the
+/// number of segments still pending when the wait ended (0 = fully drained).
+async fn drain_upload_queue(
+    upload_tx: &mpsc::UnboundedSender<UploadMessage>,
+    timeout: Duration,
+    mut on_progress: impl FnMut(usize),
+) -> usize {
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    if upload_tx.send(UploadMessage::Drain(progress_tx)).is_err() {
+        return 0;
+    }
+    let deadline = Instant::now() + timeout;
+    let mut remaining = 0;
+    while let Ok(Some(pending)) = tokio::time::timeout_at(deadline, progress_rx.recv()).await {
+        remaining = pending;
+        on_progress(pending);
+    }
+    remaining
+}
+
+/// Take the input events still queued when a segment is cut and split them: those the
+/// backend read at or before `cut_us` (its own clock, `InputBackend::current_timestamp`)
+/// belong to the segment being closed, the rest to the next one. Order is kept on both
+/// sides.
+fn take_queued_input_at_cut(
+    input_rx: &mut mpsc::UnboundedReceiver<InputEvent>,
+    cut_us: u64,
+) -> (Vec<InputEvent>, Vec<InputEvent>) {
+    let mut queued = Vec::new();
+    while let Ok(event) = input_rx.try_recv() {
+        queued.push(event);
+    }
+    queued
+        .into_iter()
+        .partition(|event| event.timestamp_us <= cut_us)
+}
+
+/// End a segment's recording with `stop`, cutting its input where the stop began: `cut_us`
+/// is the input backend's clock at that moment (None before the backend started, when all
+/// queued input is the closing segment's). Returns what `stop` returned, then the queued
+/// input before and after the cut, including what arrived while `stop` ran.
+fn stop_at_cut<T>(
+    input_rx: &mut mpsc::UnboundedReceiver<InputEvent>,
+    cut_us: Option<u64>,
+    stop: impl FnOnce() -> T,
+) -> (T, Vec<InputEvent>, Vec<InputEvent>) {
+    let stopped = stop();
+    let (before_cut, after_cut) = take_queued_input_at_cut(input_rx, cut_us.unwrap_or(u64::MAX));
+    (stopped, before_cut, after_cut)
+}
+
+/// How long to hold off a rotation requested `elapsed_us` into the segment so that it
+/// doesn't cut a segment shorter than `min_segment` (`recording.min_segment_secs`). None
+/// to rotate now, including when the segment's length is unknown.
+fn rotation_deferral(elapsed_us: Option<u64>, min_segment: Duration) -> Option<Duration> {
+    let elapsed = Duration::from_micros(elapsed_us?);
+    (elapsed < min_segment).then(|| min_segment - elapsed)
+}
+
+/// Entries for `EngineCommand::ListCapturable`: one per app (the first process of apps
+/// running several), sorted by name, marked with whether `is_target` already captures it
+/// and with its focused window's title from `window_title`
+fn capturable_app_list(
+    apps: Vec<AppInfo>,
+    is_target: impl Fn(&str) -> bool,
+    window_title: impl Fn(u32) -> Option<String>,
+) -> Vec<CapturableApp> {
+    let mut seen = HashSet::new();
+    let mut list: Vec<CapturableApp> = apps
+        .into_iter()
+        .filter(|app| seen.insert(app.bundle_id.clone()))
+        .map(|app| CapturableApp {
+            targeted: is_target(&app.bundle_id),
+            window_title: window_title(app.pid),
+            bundle_id: app.bundle_id,
+            name: app.name,
+            pid: app.pid,
+        })
+        .collect();
+    list.sort_by_cached_key(|app| app.name.to_lowercase());
+    list
+}
+
+/// What `capture.on_target_exit` asks of the recording after a check for running target apps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetExitAction {
+    Pause,
+    Stop,
+    /// A target app is back after a `Pause`
+    Resume,
+}
+
+/// Decide what to do given whether any target app is `running` and whether the recording
+/// is already paused because none was (`paused_for_exit`)
+fn target_exit_action(
+    policy: TargetExitPolicy,
+    running: bool,
+    paused_for_exit: bool,
+) -> Option<TargetExitAction> {
+    match (running, paused_for_exit, policy) {
+        (true, true, _) => Some(TargetExitAction::Resume),
+        (false, false, TargetExitPolicy::Pause) => Some(TargetExitAction::Pause),
+        (false, false, TargetExitPolicy::Stop) => Some(TargetExitAction::Stop),
+        _ => None,
+    }
+}
+
+/// Result sent back from a spawned upload task
+struct UploadResult {
+    chunk_id: String,
+    segment: CompletedSegment,
+    attempts: u32,
+    first_failed_at: Instant,
+    /// Bytes sent and time taken, for the throughput estimate
+    uploaded_bytes: u64,
+    upload_time: Duration,
+    /// `None` when uploads were paused while it waited for a permit, so it never started
+    result: Option<Result<(), UploadError>>,
+}
+
+#[derive(Debug)]
+struct RetryItem {
+    segment: CompletedSegment,
+    attempts: u32,
+    first_failed_at: Instant,
+    next_attempt_at: Instant,
+}
+
+#[derive(Debug)]
+struct RetryEntry {
+    next_attempt_at: Instant,
+    sequence: u64,
+    item: RetryItem,
+}
+
+impl Ord for RetryEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reverse for min-heap behavior.
+        other
+            .next_attempt_at
+            .cmp(&self.next_attempt_at)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for RetryEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RetryEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.next_attempt_at == other.next_attempt_at && self.sequence == other.sequence
+    }
+}
+
+impl Eq for RetryEntry {}
+
+#[derive(Debug, Clone)]
+struct PendingAppSwitch {
+    target_app: Option<String>,
+    scheduled_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct PendingCaptureWatchdog {
+    expected_app: String,
+    deadline: Instant,
+    attempt: u32,
+}
+
+#[derive(Debug)]
+struct PendingInputTransition {
+    target_app: String,
+    events: Vec<InputEvent>,
+}
+
+const CAPTURING_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_TRANSITION_INPUT_EVENTS: usize = 512;
+/// How often the recording warmup re-reads the video clock or the latest frame
+const WARMUP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Extra time the warmup waits for a stalled video clock before enabling capture anyway
+const WARMUP_GRACE: Duration = Duration::from_secs(5);
+
+/// A started recording whose input is held while it warms up: until the video clock is
+/// `recording.warmup_ms` past its start, then, with `recording.content_warmup_ms`, until
+/// a rendered frame is brighter than black (capture sources can render black for a while
+/// after they're created, and input recorded against those frames would line up with
+/// nothing). The engine loop polls it, so commands and input keep flowing meanwhile.
+#[derive(Debug)]
+struct PendingWarmup {
+    phase: WarmupPhase,
+    /// How far into the recording the video clock phase waits for
+    warmup: Duration,
+    /// Longest the content phase waits for a non-black frame (zero = no content phase)
+    content_timeout: Duration,
+    /// When the current phase gives up and input is enabled anyway, so a pipeline that
+    /// never renders can't hold it forever
+    deadline: Instant,
+    /// When the engine loop polls next
+    next_poll: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WarmupPhase {
+    /// Waiting for the video clock to reach `target_ns`
+    VideoClock { target_ns: u64 },
+    /// Waiting for a rendered frame brighter than black
+    Content,
+}
+
+impl PendingWarmup {
+    /// Warm up a recording whose video clock started at `start_ns`, beginning at `now`.
+    /// None when neither phase is enabled.
+    fn new(
+        start_ns: u64,
+        warmup: Duration,
+        content_timeout: Duration,
+        now: Instant,
+    ) -> Option<Self> {
+        let (phase, deadline) = if !warmup.is_zero() {
+            let target_ns = start_ns.saturating_add(warmup.as_nanos() as u64);
+            (
+                WarmupPhase::VideoClock { target_ns },
+                now + warmup + WARMUP_GRACE,
+            )
+        } else if !content_timeout.is_zero() {
+            (WarmupPhase::Content, now + content_timeout)
+        } else {
+            return None;
+        };
+        Some(Self {
+            phase,
+            warmup,
+            content_timeout,
+            deadline,
+            next_poll: now,
+        })
+    }
+
+    /// Check the current phase at `now`, reading the video clock (`frame_time`) or the
+    /// latest frame's brightest luma (`peak_luma`) as it needs. Returns whether the warmup
+    /// is over; otherwise the engine polls again at `next_poll`.
+    fn poll(
+        &mut self,
+        now: Instant,
+        frame_time: impl FnOnce() -> Result<u64>,
+        peak_luma: impl FnOnce() -> Option<u8>,
+    ) -> bool {
+        match self.phase {
+            WarmupPhase::VideoClock { target_ns } => {
+                if !frame_time().is_ok_and(|ns| ns >= target_ns) {
+                    if now < self.deadline {
+                        self.next_poll = now + WARMUP_POLL_INTERVAL;
+                        return false;
+                    }
+                    warn!(
+                        "Video clock did not advance {}ms into the recording; enabling input capture anyway",
+                        self.warmup.as_millis()
+                    );
+                }
+                if self.content_timeout.is_zero() {
+                    return true;
+                }
+                self.phase = WarmupPhase::Content;
+                self.deadline = now + self.content_timeout;
+                self.next_poll = now;
+                false
+            }
+            WarmupPhase::Content => {
+                if peak_luma().is_some_and(|peak| peak > BLACK_PEAK_LUMA) {
+                    return true;
+                }
+                if now >= self.deadline {
+                    warn!(
+                        "Capture still black {}ms into the recording; enabling input capture anyway",
+                        self.content_timeout.as_millis()
+                    );
+                    return true;
+                }
+                self.next_poll = now + WARMUP_POLL_INTERVAL;
+                false
+            }
+        }
+    }
+}
+
+/// The synchronization engine coordinates recording and input capture
+pub struct SyncEngine {
+    /// Configuration
+    config: Config,
+    /// Capture context for libobs operations
+    capture_ctx: CaptureContext,
     /// Input backend
     input_backend: Box<dyn InputBackend>,
     /// Shared secure-input gate consulted by the input backend; flips on while a
     /// password field is focused (Linux). See src/input/secure/.
     secure_state: Arc<crate::input::secure::SecureInputState>,
+    /// Filters applied to every input event before buffering
+    filter_chain: FilterChain,
+    /// `hotkeys.toggle_capture`, consumed from the input stream before filtering
+    capture_hotkey: Option<HotkeyMatcher>,
     /// Command receiver
     cmd_rx: mpsc::Receiver<EngineCommand>,
     /// Status broadcaster
@@ -743,17 +1781,31 @@ pub struct SyncEngine {
     is_paused: bool,
     /// Last known frontmost app
     last_frontmost_app: Option<String>,
+    /// Capture decision for the last app that was detected, for
+    /// `capture.when_frontmost_unknown = "last_known"`
+    last_known_should_capture: bool,
     /// Current recording session
     current_session: Option<RecordingSession>,
-    /// OBS timestamp at recording start (nanoseconds). Shifted forward on resume from a
-    /// pause so input-event timestamps stay aligned with the video PTS (see `pause_start_ns`).
+    /// Recording start on `event_clock` (nanoseconds; the OBS frame time by default).
+    /// Shifted forward on resume from a pause so input-event timestamps stay aligned with
+    /// the video PTS (see `pause_start_ns`).
     recording_start_ns: Option<u64>,
-    /// OBS frame-time clock captured at the moment recording was paused (nanoseconds), or None
-    /// when not paused. OBS's frame-time clock keeps advancing while a recording is paused, but
+    /// Clock input events are timed on (`recording.clock_source`)
+    event_clock: EventClock,
+    /// `event_clock` time captured at the moment recording was paused (nanoseconds), or None
+    /// when not paused. The clock keeps advancing while a recording is paused, but
     /// the file accrues no frames, so on resume we add (now - pause_start_ns) to
     /// `recording_start_ns` — keeping `current_recording_elapsed_us()` matched to the seamless
     /// video timeline rather than drifting ahead by the pause duration.
     pause_start_ns: Option<u64>,
+    /// Which input events count toward the running recording: those the backends saw
+    /// after it started, or after its warmup ended. Earlier ones that queued up meanwhile
+    /// are discarded.
+    input_gate: InputGate,
+    /// Warmup of the running recording, while its input is still held
+    pending_warmup: Option<PendingWarmup>,
+    /// Watches rendered frames during the warmup's content phase
+    warmup_probe: Option<FrameLumaProbe>,
     /// Output directory for chunks
     output_dir: PathBuf,
     /// Display monitor for detecting display hotplug events (macOS)
@@ -762,6 +1814,12 @@ pub struct SyncEngine {
     main_session_id: Option<String>,
     /// Current segment index (0-based)
     segment_index: u32,
+    /// Global counter value of the current segment, when `recording.global_segment_counter`
+    /// is on (see `next_segment_counter`)
+    segment_counter: Option<u64>,
+    /// Session and segment index the next `start_recording` continues at instead of
+    /// starting a new session (`recording.resume_on_restart`, after a crash)
+    resume_session: Option<(String, u32)>,
     /// Channel for completed segments to upload
     upload_tx: mpsc::UnboundedSender<UploadMessage>,
     /// Buffer for completed segments — held for 10 minutes before uploading
@@ -773,8 +1831,18 @@ pub struct SyncEngine {
     segment_duration_secs: u64,
     /// Whether to delete files after upload
     delete_after_upload: bool,
+    /// Rolling local archive caps (cached from config)
+    local_retention: LocalRetention,
+    /// How long shutdown waits for pending uploads (zero = don't wait; cached from config)
+    drain_timeout: Duration,
     /// Shared flag to pause/resume uploads from the tray
     uploads_paused: Arc<AtomicBool>,
+    /// Set by the upload task while an upload stall episode is in progress
+    uploads_stalled: Arc<AtomicBool>,
+    /// Set by the upload task while its circuit breaker is holding uploads
+    uploads_circuit_open: Arc<AtomicBool>,
+    /// Live HLS piece shipping (`recording.hls`), while it runs
+    hls_shipper: Option<tokio::task::JoinHandle<()>>,
     /// Upload receiver (taken once when run() starts)
     upload_rx: Option<mpsc::UnboundedReceiver<UploadMessage>>,
     /// Notification action receiver (taken once when run() starts)
@@ -788,8 +1856,17 @@ pub struct SyncEngine {
     last_resume_restart_at: Option<Instant>,
     /// Whether we're currently auto-paused due to idle (vs user-initiated pause)
     idle_paused: bool,
+    /// Whether the recording is being paused or stopped because no target app is running
+    /// (`capture.on_target_exit`)
+    target_app_gone: bool,
+    /// Last check for a running target app
+    last_target_app_check: Instant,
     /// Idle timeout duration (cached from config, Duration::ZERO means disabled)
     idle_timeout: Duration,
+    /// Session end after `capture.idle_session_end_secs` without input
+    idle_session: IdleSessionEnd,
+    /// Last input event of any kind, from any app
+    last_input_time: Instant,
     /// Whether to pause uploads during idle
     pause_uploads_on_idle: bool,
     /// Last broadcast status kind (used to dedupe noisy status broadcasts)
@@ -809,6 +1886,8 @@ pub struct SyncEngine {
     /// Last time the macOS canvas convergence check ran (throttled to ~1s).
     #[cfg(target_os = "macos")]
     last_canvas_convergence_check: Option<std::time::Instant>,
+    /// Holds a returned display's recovery until it has stayed connected
+    display_recovery: DisplayRecoveryDebounce,
     /// Segment rotation timer — fires every `segment_duration_secs` to split
     /// the recording into manageable chunks. Stored as a struct field so that
     /// every code path that starts/stops recording (including display recovery)
@@ -820,6 +1899,8 @@ pub struct SyncEngine {
     last_emitted_context: Option<String>,
     /// Number of buffered non-context input events for O(1) status updates
     buffered_non_context_event_count: usize,
+    /// Per-type input tally for the current segment (reported in `EngineStatus::Capturing`)
+    segment_event_counts: EventTypeCounts,
     /// Whether any capture source has ever been ready during this session
     any_source_ever_ready: bool,
     /// PER-APP: when each app's active capture source was first seen not-ready, keyed by the
@@ -871,15 +1952,52 @@ pub struct SyncEngine {
     /// UUID of the display captured at the last metadata emit (macOS multi-monitor), so a
     /// follow-focus switch to a same-resolution display still re-emits the layout timeline.
     last_logged_active_display: Option<String>,
+    /// Bounds of the captured display as of the last metadata emit, for
+    /// `input.restrict_to_capture_region`
+    capture_region: Option<CaptureRegion>,
     /// Last time the captured source resolution was checked for changes
     last_source_res_check: Instant,
+    /// Display scale factor last logged, for `log_display_scale_changes`
+    display_scale: DisplayScaleTracker,
+    /// Last time the display scale factor was checked for changes
+    last_display_scale_check: Instant,
+    /// Focused window frame last logged, for `log_window_geometry_changes`
+    window_geometry: WindowGeometryTracker,
+    /// Focused-window frames read off the loop come back through here
+    window_geometry_tx: mpsc::UnboundedSender<WindowGeometryLookup>,
+    window_geometry_rx: Option<mpsc::UnboundedReceiver<WindowGeometryLookup>>,
+    /// Focused-window titles (`(pid, title)`) read off the loop come back through here
+    window_title_tx: mpsc::UnboundedSender<(u32, Option<String>)>,
+    window_title_rx: Option<mpsc::UnboundedReceiver<(u32, Option<String>)>>,
+    /// Focused window title, for apps with `capture.title_patterns`
+    window_title: WindowTitleCache,
+    /// Re-checks input-capture permission while recording, for `check_input_permission`
+    input_permission: InputPermissionWatch,
+    /// Last recording health sent to the tray (None = not recording or not yet checked)
+    last_health: Option<RecordingHealth>,
+    /// Last time the recording health was computed
+    last_health_check: Instant,
+    /// Video frame time at the last health check, to tell whether frames are advancing
+    last_health_frame_ns: Option<u64>,
+    /// `(fps, max output height)` for apps without a `capture.app_profiles` entry
+    default_video_settings: (u32, u32),
+    /// Recent upload throughput, recorded by the upload task
+    upload_throughput: Arc<std::sync::Mutex<UploadThroughput>>,
+    /// `[[webhooks]]` notified of session starts, segment uploads and errors
+    webhooks: Webhooks,
+    /// Picks each segment's video bitrate when `recording.adaptive_bitrate` is on
+    bitrate_governor: Option<BitrateGovernor>,
+    /// Flat-level check of the captured audio, fed at every health check
+    flat_audio: FlatAudioDetector,
+    /// CPU/memory sampling for `recording.log_resource_usage`, fed at every health check
+    resource_sampler: Option<ResourceSampler>,
 }
 
 impl SyncEngine {
     /// Create a new sync engine
     pub fn new(
         config: Config,
-        capture_ctx: CaptureContext,
+        mut capture_ctx: CaptureContext,
         cmd_rx: mpsc::Receiver<EngineCommand>,
         status_tx: broadcast::Sender<EngineStatus>,
         notification_rx: mpsc::UnboundedReceiver<NotificationAction>,
@@ -892,9 +2010,52 @@ impl SyncEngine {
             .unwrap_or_else(|| std::env::temp_dir().join("crowd-cast-recordings"));
 
         let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        let (window_geometry_tx, window_geometry_rx) = mpsc::unbounded_channel();
+        let (window_title_tx, window_title_rx) = mpsc::unbounded_channel();
         let uploader = Uploader::new(&config, auth);
         let segment_duration_secs = config.recording.segment_duration_secs;
         let delete_after_upload = config.upload.delete_after_upload;
+        let local_retention = LocalRetention {
+            bytes: config.recording.local_retention_bytes,
+            segments: config.recording.local_retention_count,
+        };
+        let drain_timeout = Duration::from_secs(config.upload.drain_timeout_secs);
+        let filter_chain = FilterChain::from_config(&config.input);
+        let capture_hotkey =
+            config.hotkeys.toggle_capture.as_deref().and_then(|chord| {
+                match chord.parse::<Hotkey>() {
+                    Ok(hotkey) => {
+                        info!("Capture toggle hotkey: {}", chord);
+                        Some(HotkeyMatcher::new(hotkey))
+                    }
+                    Err(e) => {
+                        warn!("Ignoring hotkeys.toggle_capture: {:#}", e);
+                        None
+                    }
+                }
+            });
+        let default_video_settings = capture_ctx.video_settings();
+        let event_clock = EventClock::new(config.recording.clock_source);
+        let flat_audio = FlatAudioDetector::new(Duration::from_secs(
+            config.recording.audio.flat_warning_secs,
+        ));
+        let resource_sampler = config
+            .recording
+            .log_resource_usage
+            .then(ResourceSampler::new);
+        let bitrate_governor = config.recording.adaptive_bitrate.then(|| {
+            BitrateGovernor::new(
+                config.recording.bitrate_floor_kbps,
+                config.recording.bitrate_ceiling_kbps,
+            )
+        });
+        if let Some(governor) = &bitrate_governor {
+            info!(
+                "Adaptive bitrate on, starting at {} Kbps",
+                governor.current_kbps()
+            );
+            capture_ctx.set_video_bitrate(governor.current_kbps());
+        }
 
         // Activity-gated capture settings
         let idle_timeout_secs = config.capture.idle_timeout_secs;
@@ -903,6 +2064,7 @@ impl SyncEngine {
         } else {
             Duration::ZERO // Disabled
         };
+        let idle_session_end = Duration::from_secs(config.capture.idle_session_end_secs);
         let pause_uploads_on_idle = config.capture.pause_uploads_on_idle;
         #[cfg(target_os = "linux")]
         let single_active_app_capture =
@@ -933,29 +2095,51 @@ unintended app video."
         #[cfg(not(target_os = "linux"))]
         let display_resolution = get_main_display_resolution().unwrap_or((1920, 1080));
 
+        let capture_gestures = config.input.capture_gestures;
+        let capture_text_commits = config.input.capture_text_commits;
+        let webhooks = Webhooks::new(&config.webhooks);
+
         Ok(Self {
             config,
             capture_ctx,
             secure_state: secure_state.clone(),
-            input_backend: create_input_backend(secure_state)?,
+            filter_chain,
+            capture_hotkey,
+            input_backend: create_input_backend(
+                secure_state,
+                capture_gestures,
+                capture_text_commits,
+            )?,
             cmd_rx,
             status_tx,
             event_buffer: InputEventBuffer::new(),
             capture_enabled: false,
             is_paused: false,
             last_frontmost_app: None,
+            last_known_should_capture: false,
             current_session: None,
             recording_start_ns: None,
+            event_clock,
+            input_gate: InputGate::default(),
+            pending_warmup: None,
+            warmup_probe: None,
             pause_start_ns: None,
             output_dir,
             display_monitor: DisplayMonitor::new(),
             main_session_id: None,
             segment_index: 0,
+            segment_counter: None,
+            resume_session: None,
             upload_tx,
             uploader,
             segment_duration_secs,
             delete_after_upload,
+            local_retention,
+            drain_timeout,
             uploads_paused: Arc::new(AtomicBool::new(read_uploads_paused())),
+            uploads_stalled: Arc::new(AtomicBool::new(false)),
+            uploads_circuit_open: Arc::new(AtomicBool::new(false)),
+            hls_shipper: None,
             upload_buffer: std::collections::VecDeque::new(),
             upload_rx: Some(upload_rx),
             notification_rx: Some(notification_rx),
@@ -963,7 +2147,11 @@ unintended app video."
             #[cfg(not(target_os = "macos"))]
             last_resume_restart_at: None,
             idle_paused: false,
+            target_app_gone: false,
+            last_target_app_check: Instant::now(),
             idle_timeout,
+            idle_session: IdleSessionEnd::new(idle_session_end),
+            last_input_time: Instant::now(),
             pause_uploads_on_idle,
             last_status_kind: None,
             last_capturing_status_at: None,
@@ -974,10 +2162,14 @@ unintended app video."
             pending_capture_watchdog: None,
             #[cfg(target_os = "macos")]
             last_canvas_convergence_check: None,
+            display_recovery: DisplayRecoveryDebounce::new(Duration::from_millis(
+                config.capture.display_recovery_debounce_ms,
+            )),
             segment_timer: None,
             pending_input_transition: None,
             last_emitted_context: None,
             buffered_non_context_event_count: 0,
+            segment_event_counts: EventTypeCounts::default(),
             any_source_ever_ready: false,
             #[cfg(all(target_os = "macos", not(no_tray)))]
             capture_dead_since: std::collections::HashMap::new(),
@@ -997,7 +2189,26 @@ unintended app video."
             last_disk_check: Instant::now(),
             last_logged_source_dims: None,
             last_logged_active_display: None,
+            capture_region: None,
             last_source_res_check: Instant::now(),
+            display_scale: DisplayScaleTracker::default(),
+            last_display_scale_check: Instant::now(),
+            window_geometry: WindowGeometryTracker::default(),
+            window_geometry_tx,
+            window_geometry_rx: Some(window_geometry_rx),
+            window_title_tx,
+            window_title_rx: Some(window_title_rx),
+            window_title: WindowTitleCache::default(),
+            input_permission: InputPermissionWatch::new(input_permission_granted, Instant::now()),
+            last_health: None,
+            last_health_check: Instant::now(),
+            last_health_frame_ns: None,
+            default_video_settings,
+            upload_throughput: Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+            webhooks,
+            bitrate_governor,
+            flat_audio,
+            resource_sampler,
         })
     }
 
@@ -1014,8 +2225,19 @@ unintended app video."
         // only place the complete text is recoverable.
         if let EngineStatus::Error(msg) = &status {
             error!("engine error status: {}", msg);
+            self.webhooks.send(WebhookPayload::error(
+                msg,
+                self.main_session_id.as_deref(),
+                None,
+            ));
         }
         let status_kind = StatusKind::from_status(&status);
+        // Health and upload progress refine the current status rather than replacing it,
+        // so they bypass the dedupe bookkeeping (both are already sent sparingly).
+        if matches!(status_kind, StatusKind::Health | StatusKind::UploadProgress) {
+            let _ = self.status_tx.send(status);
+            return;
+        }
         let now = Instant::now();
 
         let should_send = if force {
@@ -1078,6 +2300,130 @@ unintended app video."
         }
     }
 
+    /// Join the session's segments still held in the upload buffer into one segment when
+    /// their video adds up to less than `recording.compact_below_bytes`. Leaves them as
+    /// they are if any of them can't be joined (no video, HLS) or the remux fails.
+    fn compact_session_segments(&mut self, session_id: &str) {
+        let threshold = self.config.recording.compact_below_bytes;
+        if threshold == 0 {
+            return;
+        }
+
+        let held: Vec<&CompletedSegment> = self
+            .upload_buffer
+            .iter()
+            .map(|(_, segment)| segment)
+            .filter(|segment| segment.chunk.session_id == session_id)
+            .collect();
+        if held.len() < 2 {
+            return;
+        }
+        let total_bytes: u64 = held.iter().map(|s| s.chunk.stats.video_bytes).sum();
+        if total_bytes >= threshold {
+            debug!(
+                "Not compacting session {}: {} segment(s) hold {} bytes of video",
+                session_id,
+                held.len(),
+                total_bytes
+            );
+            return;
+        }
+        let Some(videos) = held
+            .iter()
+            .map(|s| s.chunk.video_path.clone().filter(|path| !is_playlist(path)))
+            .collect::<Option<Vec<PathBuf>>>()
+        else {
+            debug!(
+                "Not compacting session {}: a segment has no single video file",
+                session_id
+            );
+            return;
+        };
+
+        let first = &held[0].chunk;
+        let chunk_id = format!("{}_compact", first.chunk_id);
+        let extension = videos[0]
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("mp4");
+        let video_path = self
+            .output_dir
+            .join(format!("recording_{}.{}", chunk_id, extension));
+        let input_path = self.output_dir.join(format!("input_{}.msgpack", chunk_id));
+        let (timebase, video_start_ns, video_offset_us) =
+            (first.timebase, first.video_start_ns, first.video_offset_us);
+        let chunks: Vec<&CompletedChunk> = held.iter().map(|s| &s.chunk).collect();
+        let count = held.len();
+
+        let result = tokio::task::block_in_place(|| {
+            let durations_us = videos
+                .iter()
+                .map(|video| video_duration_us(video))
+                .collect::<Result<Vec<u64>>>()?;
+            let events = concat_segment_events(&chunks, &durations_us);
+            remux_concat(&videos, &video_path)?;
+            std::fs::write(&input_path, encode_input_log(&events)?)?;
+            Ok::<_, anyhow::Error>(events)
+        });
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                warn!(
+                    "Leaving session {}'s {} segments uncompacted: {:#}",
+                    session_id, count, e
+                );
+                let _ = std::fs::remove_file(&video_path);
+                return;
+            }
+        };
+        let (compacted, kept) = std::mem::take(&mut self.upload_buffer)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, segment)| segment.chunk.session_id == session_id);
+        self.upload_buffer = kept.into_iter().collect();
+        for (_, segment) in compacted {
+            // With `upload.delete_after_upload = false` the originals stay, like any
+            // other recording the user keeps.
+            if self.delete_after_upload {
+                for path in chunk_video_files(&segment.chunk)
+                    .into_iter()
+                    .chain(std::iter::once(segment.input_path))
+                {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        warn!("Failed to delete compacted file {:?}: {}", path, e);
+                    }
+                }
+            }
+            remove_chunk_upload_leftovers(&segment.chunk);
+            remove_pending_upload(&segment.chunk.chunk_id);
+        }
+
+        let start_time_us = events.first().map(|e| e.timestamp_us).unwrap_or(0);
+        let end_time_us = events.last().map(|e| e.timestamp_us).unwrap_or(0);
+        let stats = SegmentStats::new(
+            &events,
+            start_time_us,
+            end_time_us,
+            video_bytes(&video_path),
+        );
+        info!(
+            "Compacted {} segments of session {} ({} bytes of video) into {}",
+            count, session_id, total_bytes, chunk_id
+        );
+        let chunk = CompletedChunk {
+            chunk_id: chunk_id.clone(),
+            session_id: session_id.to_string(),
+            events,
+            video_path: Some(video_path),
+            start_time_us,
+            end_time_us,
+            timebase,
+            video_start_ns,
+            video_offset_us,
+            stats,
+        };
+        self.buffer_segment_for_upload(CompletedSegment { chunk, input_path }, chunk_id);
+    }
+
     /// Graduate buffered segments older than 10 minutes to the upload task.
     fn graduate_upload_buffer(&mut self) {
         const UPLOAD_BUFFER_DELAY: Duration = Duration::from_secs(600);
@@ -1111,6 +2457,30 @@ unintended app video."
         }
     }
 
+    /// Shutdown drain: give queued and retrying uploads up to `drain_timeout` to finish,
+    /// reporting progress through the engine status. Call after `flush_upload_buffer`.
+    async fn drain_uploads(&mut self) {
+        if self.drain_timeout.is_zero() || !self.uploader.is_configured() {
+            return;
+        }
+        let upload_tx = self.upload_tx.clone();
+        let remaining = drain_upload_queue(&upload_tx, self.drain_timeout, |pending| {
+            if pending > 0 {
+                info!("Draining uploads: {} segment(s) remaining", pending);
+                self.send_status_force(EngineStatus::Draining { pending });
+            }
+        })
+        .await;
+        if remaining > 0 {
+            warn!(
+                "Exiting with {} segment(s) still pending upload; they will be retried on next launch",
+                remaining
+            );
+        } else {
+            info!("Upload queue drained");
+        }
+    }
+
     /// Panic: delete all buffered segments from disk and clear the manifest.
     fn purge_upload_buffer(&mut self) {
         let count = self.upload_buffer.len();
@@ -1118,8 +2488,8 @@ unintended app video."
             info!("Panic: deleting {} buffered segment(s)", count);
         }
         while let Some((_, segment)) = self.upload_buffer.pop_front() {
-            if let Some(ref video_path) = segment.chunk.video_path {
-                if let Err(e) = std::fs::remove_file(video_path) {
+            for video_path in chunk_video_files(&segment.chunk) {
+                if let Err(e) = std::fs::remove_file(&video_path) {
                     warn!("Failed to delete video {:?}: {}", video_path, e);
                 } else {
                     debug!("Deleted video: {:?}", video_path);
@@ -1130,18 +2500,93 @@ unintended app video."
             } else {
                 debug!("Deleted input: {:?}", segment.input_path);
             }
+            remove_chunk_upload_leftovers(&segment.chunk);
         }
         write_pending_uploads(&[]);
     }
 
-    fn active_video_target(&self) -> Option<&str> {
-        self.capture_ctx.active_capture_app()
+    /// Start shipping HLS pieces (`recording.hls`) as OBS finishes them rather than when
+    /// the segment rotates. A pass is skipped while uploads are paused or the upload
+    /// circuit is open; it just leaves more for the next pass or the segment upload.
+    fn spawn_hls_shipper(&mut self) {
+        self.stop_hls_shipper();
+        let mut shipper =
+            crate::upload::HlsShipper::new(self.uploader.clone(), self.output_dir.clone());
+        let interval = Duration::from_secs(self.config.recording.hls.segment_secs.max(1) as u64);
+        let paused = self.uploads_paused.clone();
+        let circuit_open = self.uploads_circuit_open.clone();
+        self.hls_shipper = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if !paused.load(AtomicOrdering::SeqCst)
+                    && !circuit_open.load(AtomicOrdering::SeqCst)
+                {
+                    shipper.run_once().await;
+                }
+            }
+        }));
     }
 
-    fn current_recording_elapsed_us(&self) -> Option<u64> {
-        let start_ns = self.recording_start_ns?;
-        let current_ns = self.capture_ctx.get_video_frame_time().ok()?;
-        Some(current_ns.saturating_sub(start_ns) / 1000)
+    /// Stop HLS piece shipping, abandoning any pass in flight. Returns whether it was running.
+    fn stop_hls_shipper(&mut self) -> bool {
+        match self.hls_shipper.take() {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tell the user about requested apps `setup_capture` couldn't create a source for;
+    /// the rest record as usual.
+    fn report_setup_outcome(&self, outcome: &SetupOutcome) {
+        if outcome.failed.is_empty() {
+            return;
+        }
+        for (app, reason) in &outcome.failed {
+            warn!("Not capturing '{}': {}", app, reason);
+        }
+        show_capture_sources_failed_notification(&outcome.failed_apps());
+    }
+
+    /// Snapshot of the engine state for `EngineCommand::QueryStatus`.
+    fn status_snapshot(&self) -> StatusSnapshot {
+        StatusSnapshot {
+            recording: self.current_session.is_some(),
+            paused: self.is_paused,
+            status: self
+                .last_status_kind
+                .map(|kind| format!("{:?}", kind))
+                .unwrap_or_else(|| "Idle".to_string()),
+            session_id: self.main_session_id.clone(),
+            // The pending file covers held-back segments as well as queued and retrying ones.
+            pending_uploads: read_pending_uploads().len(),
+            uploads_circuit_open: self.uploads_circuit_open.load(AtomicOrdering::SeqCst),
+            failed_apps: self.capture_ctx.failed_capture_apps().to_vec(),
+        }
+    }
+
+    fn active_video_target(&self) -> Option<&str> {
+        self.capture_ctx.active_capture_app()
+    }
+
+    /// The current time on the input backend's clock, which stamps raw input events
+    /// (microseconds; 0 before the backend starts)
+    fn input_clock_now_us(&self) -> u64 {
+        self.input_backend.current_timestamp().unwrap_or(0)
+    }
+
+    /// The current time on `event_clock` (nanoseconds)
+    fn clock_now_ns(&self) -> Option<u64> {
+        self.event_clock
+            .now_ns(|| self.capture_ctx.get_video_frame_time())
+    }
+
+    fn current_recording_elapsed_us(&self) -> Option<u64> {
+        let start_ns = self.recording_start_ns?;
+        let current_ns = self.clock_now_ns()?;
+        Some(current_ns.saturating_sub(start_ns) / 1000)
     }
 
     fn clear_pending_input_transition(&mut self) {
@@ -1249,10 +2694,14 @@ unintended app video."
         let buffered_count = pending.events.len();
 
         for event in pending.events {
+            // Held during a warmup like any other input
+            if !self.input_gate.admits(&event) {
+                continue;
+            }
             let delta_us = last_raw_timestamp.saturating_sub(event.timestamp_us);
             self.buffer_input_event(InputEvent {
                 timestamp_us: flush_elapsed_us.saturating_sub(delta_us),
-                event: event.event,
+                ..event
             });
         }
 
@@ -1483,14 +2932,13 @@ unintended app video."
         (should_capture, desired_target)
     }
 
-    fn adjust_input_event_timestamp(&self, event: InputEvent) -> InputEvent {
-        if let Some(elapsed_us) = self.current_recording_elapsed_us() {
-            InputEvent {
-                timestamp_us: elapsed_us,
-                ..event
-            }
-        } else {
-            event
+    fn adjust_input_event_timestamp(&mut self, event: InputEvent) -> InputEvent {
+        let (Some(start_ns), Some(now_ns)) = (self.recording_start_ns, self.clock_now_ns()) else {
+            return event;
+        };
+        InputEvent {
+            timestamp_us: self.event_clock.stamp_us(start_ns, now_ns),
+            ..event
         }
     }
 
@@ -1522,10 +2970,13 @@ unintended app video."
         let mut remapped = Vec::with_capacity(pending.events.len());
 
         for event in pending.events {
+            if !self.input_gate.admits(&event) {
+                continue;
+            }
             let delta_us = last_raw_timestamp.saturating_sub(event.timestamp_us);
             remapped.push(InputEvent {
                 timestamp_us: flush_elapsed_us.saturating_sub(delta_us),
-                event: event.event,
+                ..event
             });
         }
 
@@ -1558,9 +3009,33 @@ unintended app video."
     fn frontmost_capture_state(&mut self) -> (Option<String>, bool) {
         let frontmost = get_frontmost_app();
         let bundle_id = frontmost.as_ref().map(|a| a.bundle_id.clone());
-        let should_capture = match bundle_id.as_deref() {
-            Some(id) => self.config.should_capture_app(id),
-            None => self.config.capture.capture_all,
+        let should_capture = match frontmost.as_ref() {
+            Some(app) => {
+                // The title is only read for apps that filter on it
+                let title = if self
+                    .config
+                    .capture
+                    .title_patterns
+                    .contains_key(&app.bundle_id)
+                {
+                    let tx = self.window_title_tx.clone();
+                    self.window_title.get(app.pid, Instant::now(), |pid| {
+                        tokio::task::spawn_blocking(move || {
+                            let _ = tx.send((pid, get_focused_window_title(pid)));
+                        });
+                    })
+                } else {
+                    None
+                };
+                self.last_known_should_capture =
+                    self.config.should_capture_window(&app.bundle_id, title);
+                self.last_known_should_capture
+            }
+            None => self
+                .config
+                .capture
+                .when_frontmost_unknown
+                .should_capture(self.last_known_should_capture),
         };
 
         if bundle_id != self.last_frontmost_app {
@@ -1890,8 +3365,10 @@ unintended app video."
     }
 
     fn buffer_input_event(&mut self, event: InputEvent) {
-        self.event_buffer.push(event);
-        self.buffered_non_context_event_count += 1;
+        self.segment_event_counts.record(&event.event);
+        let dropped = self.event_buffer.push(event);
+        self.buffered_non_context_event_count =
+            (self.buffered_non_context_event_count + 1).saturating_sub(dropped);
     }
 
     fn clear_event_buffer(&mut self) {
@@ -1916,8 +3393,7 @@ unintended app video."
     fn current_capture_timestamp_us(&self) -> u64 {
         match self.recording_start_ns {
             Some(start_ns) => {
-                self.capture_ctx
-                    .get_video_frame_time()
+                self.clock_now_ns()
                     .unwrap_or(start_ns)
                     .saturating_sub(start_ns)
                     / 1000
@@ -1927,12 +3403,12 @@ unintended app video."
     }
 
     fn push_context_event(&mut self, app_id: String, timestamp_us: u64) {
-        self.event_buffer.push(InputEvent {
+        self.event_buffer.push(InputEvent::now(
             timestamp_us,
-            event: EventType::ContextChanged(ContextEvent {
+            EventType::ContextChanged(ContextEvent {
                 app_id: app_id.clone(),
             }),
-        });
+        ));
         self.last_emitted_context = Some(app_id);
     }
 
@@ -1981,10 +3457,13 @@ unintended app video."
         // against) rather than the resolved active_display's uuid, so the re-emit change check
         // can never disagree with what was logged (e.g. a cached display no longer in the list).
         self.last_logged_active_display = self.capture_ctx.active_display_uuid();
+        self.refresh_capture_region();
+        let display_scale = get_main_display_scale();
+        self.display_scale.reset(display_scale);
         let utc_now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
-        self.event_buffer.push(InputEvent {
+        self.event_buffer.push(InputEvent::now(
             timestamp_us,
-            event: EventType::Metadata(MetadataEvent {
+            EventType::Metadata(MetadataEvent {
                 display_width: dw,
                 display_height: dh,
                 display_aspect: aspect_ratio(dw, dh),
@@ -1999,8 +3478,10 @@ unintended app video."
                 displays,
                 platform: std::env::consts::OS.to_string(),
                 capture_mode: self.capture_ctx.capture_mode().to_string(),
+                display_scale: display_scale.unwrap_or(0.0),
+                video_encoder: self.capture_ctx.video_encoder().unwrap_or_default(),
             }),
-        });
+        ));
     }
 
     /// Emit a fresh metadata event when the captured source's native resolution
@@ -2014,6 +3495,8 @@ unintended app video."
             return;
         }
         self.last_source_res_check = Instant::now();
+        // Displays can be rearranged without the captured one changing size.
+        self.refresh_capture_region();
 
         let Some((w, h)) = self.capture_ctx.active_source_dimensions().ok().flatten() else {
             return;
@@ -2031,6 +3514,131 @@ unintended app video."
         }
     }
 
+    /// Re-read where the captured display sits, for `input.restrict_to_capture_region`
+    fn refresh_capture_region(&mut self) {
+        if self.config.input.restrict_to_capture_region {
+            self.capture_region = self.capture_ctx.capture_region();
+        }
+    }
+
+    /// Log a `DisplayScaleChanged` event when the main display's backing scale factor
+    /// changes mid-segment (e.g. the user switches a Retina/HiDPI scaling mode).
+    fn log_display_scale_changes(&mut self) {
+        if self.current_session.is_none() || self.is_paused {
+            return;
+        }
+        if self.last_display_scale_check.elapsed() < DISPLAY_SCALE_CHECK_INTERVAL {
+            return;
+        }
+        self.last_display_scale_check = Instant::now();
+
+        let Some(scale) = get_main_display_scale() else {
+            return;
+        };
+        if let Some(scale) = self.display_scale.observe(scale) {
+            info!("Display scale factor changed to {}", scale);
+            self.event_buffer.push(InputEvent::now(
+                self.current_capture_timestamp_us(),
+                EventType::DisplayScaleChanged(DisplayScaleEvent { scale }),
+            ));
+        }
+    }
+
+    /// Log a `WindowGeometry` event when the focused window of process `pid` moves or is
+    /// resized. Only windows of captured apps are logged; once focus leaves them the last
+    /// frame is forgotten, so coming back logs the window's frame again. The frame is read
+    /// on the blocking pool and logged by `record_window_geometry` when it's back.
+    fn log_window_geometry_changes(
+        &mut self,
+        pid: Option<u32>,
+        should_capture: bool,
+        timestamp_us: u64,
+    ) {
+        if !self.config.input.capture_window_geometry || self.current_session.is_none() {
+            return;
+        }
+        if !should_capture {
+            self.window_geometry.reset();
+            return;
+        }
+        let Some(pid) = pid else {
+            return;
+        };
+        let Some(generation) = self.window_geometry.start_lookup() else {
+            return;
+        };
+        let tx = self.window_geometry_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(WindowGeometryLookup {
+                generation,
+                timestamp_us,
+                frame: get_focused_window_geometry(pid),
+            });
+        });
+    }
+
+    /// Log the frame a `log_window_geometry_changes` lookup read, if it's still current
+    /// and the window moved or was resized
+    fn record_window_geometry(&mut self, lookup: WindowGeometryLookup) {
+        let frame = self
+            .window_geometry
+            .finish_lookup(lookup.generation, lookup.frame);
+        let Some(frame) = frame.filter(|_| self.current_session.is_some()) else {
+            return;
+        };
+        debug!(
+            "Focused window geometry: {}x{} at ({}, {})",
+            frame.width, frame.height, frame.x, frame.y
+        );
+        self.event_buffer.push(InputEvent::now(
+            lookup.timestamp_us,
+            EventType::WindowGeometry(frame),
+        ));
+    }
+
+    /// Stop recording when input-capture permission is revoked mid-run (macOS
+    /// Accessibility): the input backend goes silent rather than failing, so every
+    /// further segment would be video without input. The persisted recording state is
+    /// kept, so recording resumes on the next launch once the permission is back.
+    async fn check_input_permission(&mut self) {
+        if self.current_session.is_none() || !self.input_permission.revoked(Instant::now()) {
+            return;
+        }
+        let message = "Recording stopped: Accessibility permission was revoked, so keyboard \
+            and mouse input can no longer be captured. Grant it again in System Settings to \
+            resume recording.";
+        error!("{}", message);
+        if let Err(e) = self.stop_recording().await {
+            error!("Failed to stop recording: {}", e);
+        }
+        self.reset_segment_timer();
+        self.send_status_force(EngineStatus::Error(message.to_string()));
+        if notifications_authorized() {
+            show_permission_revoked_notification(message);
+        }
+    }
+
+    /// Insert an operator marker at the current timeline position. Markers bypass the
+    /// input filters and capture gating: they annotate the recording, not the user's input.
+    fn add_marker(&mut self, label: &str) {
+        let label = label.trim();
+        if label.is_empty() {
+            return;
+        }
+        if self.current_session.is_none() {
+            warn!("Ignoring marker '{}': not recording", label);
+            return;
+        }
+        let timestamp_us = self.current_capture_timestamp_us();
+        info!("Marker '{}' at {}us", label, timestamp_us);
+        self.event_buffer.push(InputEvent::now(
+            timestamp_us,
+            EventType::Marker(MarkerEvent {
+                label: label.to_string(),
+            }),
+        ));
+    }
+
     fn emit_context_snapshot(&mut self, should_capture: bool, timestamp_us: u64) {
         let app_id = self.current_context_app_id(should_capture).to_string();
         self.push_context_event(app_id, timestamp_us);
@@ -2052,28 +3660,48 @@ unintended app video."
 
     /// Spawn background task for uploading completed segments.
     ///
-    /// Uploads run concurrently (up to MAX_CONCURRENT_UPLOADS) so that one
-    /// slow or failing upload does not block the entire pipeline.
+    /// Uploads run concurrently (up to `max_concurrent_uploads`, at least one) so
+    /// that one slow or failing upload does not block the entire pipeline.
+    #[allow(clippy::too_many_arguments)]
     fn spawn_upload_task(
         mut upload_rx: mpsc::UnboundedReceiver<UploadMessage>,
         uploader: Uploader,
         delete_after_upload: bool,
+        max_concurrent_uploads: usize,
+        local_retention: LocalRetention,
+        output_dir: PathBuf,
         uploads_paused: Arc<AtomicBool>,
+        uploads_stalled: Arc<AtomicBool>,
+        mut breaker: UploadCircuitBreaker,
+        validate_before_upload: bool,
+        upload_throughput: Arc<std::sync::Mutex<UploadThroughput>>,
+        status_tx: broadcast::Sender<EngineStatus>,
+        webhooks: Webhooks,
     ) {
         const BASE_RETRY_BACKOFF: Duration = Duration::from_secs(30);
         const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(2 * 60 * 60);
         const MAX_RETRY_WINDOW: Duration = Duration::from_secs(2 * 60 * 60);
         const UPLOAD_PAUSE_NOTIFY_THRESHOLD: usize = 50;
-        const MAX_CONCURRENT_UPLOADS: usize = 3;
 
         tokio::spawn(async move {
             let mut retry_queue: BinaryHeap<RetryEntry> = BinaryHeap::new();
             let mut sequence: u64 = 0;
             let mut active_session_id: Option<String> = None;
             let mut upload_pause_notified = false;
+            // One rejection toast per run of rejections; a successful upload re-arms it.
+            let mut upload_rejected_notified = false;
+            let mut stall_detector = UploadStallDetector::new(Instant::now());
+            // Uploads spawned but not yet reported back, and the shutdown drain (if any)
+            // waiting for those plus the retry queue to reach zero.
+            let mut in_flight: usize = 0;
+            let mut drain: Option<(mpsc::UnboundedSender<usize>, Option<usize>)> = None;
+            // With a local retention cap, uploaded files are kept and pruned oldest-first
+            // instead of being deleted right after upload.
+            let retain_locally = local_retention.is_enabled();
+            let delete_after_upload = delete_after_upload && !retain_locally;
 
             // Semaphore limits concurrent uploads
-            let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_UPLOADS));
+            let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrent_uploads.max(1)));
 
             // Channel for receiving upload results from spawned tasks
             let (result_tx, mut result_rx) = mpsc::unbounded_channel::<UploadResult>();
@@ -2099,6 +3727,8 @@ unintended app video."
 
             /// Spawn a concurrent upload task. Acquires a semaphore permit,
             /// performs the upload, cleans up files, and sends the result back.
+            /// If uploads were paused while it waited for the permit, it sends the
+            /// segment back untouched instead.
             fn spawn_upload(
                 uploader: Uploader,
                 segment: CompletedSegment,
@@ -2106,19 +3736,46 @@ unintended app video."
                 attempts: u32,
                 first_failed_at: Option<Instant>,
                 delete_after_upload: bool,
+                uploads_paused: Arc<AtomicBool>,
                 semaphore: Arc<tokio::sync::Semaphore>,
                 result_tx: mpsc::UnboundedSender<UploadResult>,
+                status_tx: broadcast::Sender<EngineStatus>,
             ) {
                 tokio::spawn(async move {
-                    // Acquire a permit — blocks if MAX_CONCURRENT_UPLOADS are in flight
+                    // Acquire a permit — blocks if max_concurrent_uploads are in flight
                     let _permit = semaphore.acquire().await.expect("semaphore closed");
+                    if uploads_paused.load(AtomicOrdering::SeqCst) {
+                        let _ = result_tx.send(UploadResult {
+                            chunk_id,
+                            segment,
+                            attempts,
+                            first_failed_at: first_failed_at.unwrap_or_else(Instant::now),
+                            uploaded_bytes: 0,
+                            upload_time: Duration::ZERO,
+                            result: None,
+                        });
+                        return;
+                    }
 
+                    let uploaded_bytes = segment_file_bytes(&segment);
+                    let started = Instant::now();
+                    let progress_chunk_id = chunk_id.clone();
+                    let progress_tx = status_tx.clone();
+                    let progress: ProgressSink = Arc::new(move |sent, total| {
+                        let _ = progress_tx.send(EngineStatus::UploadProgress {
+                            chunk_id: progress_chunk_id.clone(),
+                            sent,
+                            total,
+                        });
+                    });
                     let result = async {
-                        uploader.upload(&segment.chunk).await?;
+                        uploader
+                            .upload_with_progress(&segment.chunk, Some(progress))
+                            .await?;
 
                         if delete_after_upload {
-                            if let Some(ref video_path) = segment.chunk.video_path {
-                                if let Err(e) = tokio::fs::remove_file(video_path).await {
+                            for video_path in chunk_video_files(&segment.chunk) {
+                                if let Err(e) = tokio::fs::remove_file(&video_path).await {
                                     warn!("Failed to delete video file {:?}: {}", video_path, e);
                                 } else {
                                     debug!("Deleted video file: {:?}", video_path);
@@ -2134,21 +3791,39 @@ unintended app video."
                             }
                         }
 
-                        Ok::<(), anyhow::Error>(())
+                        Ok::<(), UploadError>(())
                     }
                     .await;
+                    // The attempt is over, whether or not it got through.
+                    let _ = status_tx.send(EngineStatus::UploadProgress {
+                        chunk_id: chunk_id.clone(),
+                        sent: 0,
+                        total: 0,
+                    });
 
                     let _ = result_tx.send(UploadResult {
                         chunk_id,
                         segment,
                         attempts,
                         first_failed_at: first_failed_at.unwrap_or_else(Instant::now),
-                        result,
+                        uploaded_bytes,
+                        upload_time: started.elapsed(),
+                        result: Some(result),
                     });
                 });
             }
 
             loop {
+                if let Some((progress_tx, last_reported)) = drain.as_mut() {
+                    let remaining = in_flight + retry_queue.len();
+                    if *last_reported != Some(remaining) {
+                        *last_reported = Some(remaining);
+                        if progress_tx.send(remaining).is_err() || remaining == 0 {
+                            drain = None;
+                        }
+                    }
+                }
+
                 let next_retry_at = retry_queue.peek().map(|entry| entry.next_attempt_at);
 
                 tokio::select! {
@@ -2158,6 +3833,28 @@ unintended app video."
                             UploadMessage::StartSession(session_id) => {
                                 active_session_id = Some(session_id);
                             }
+                            UploadMessage::Drain(progress_tx) => {
+                                if uploads_paused.load(AtomicOrdering::SeqCst) {
+                                    info!(
+                                        "Uploads paused; leaving {} segment(s) queued for the next launch",
+                                        in_flight + retry_queue.len()
+                                    );
+                                    let _ = progress_tx.send(in_flight + retry_queue.len());
+                                    continue;
+                                }
+                                // Don't sit out retry backoff during shutdown: everything
+                                // queued is due now.
+                                let now = Instant::now();
+                                retry_queue = retry_queue
+                                    .into_iter()
+                                    .map(|mut entry| {
+                                        entry.next_attempt_at = now;
+                                        entry.item.next_attempt_at = now;
+                                        entry
+                                    })
+                                    .collect();
+                                drain = Some((progress_tx, None));
+                            }
                             UploadMessage::Segment(segment) => {
                                 let chunk_id = segment.chunk.chunk_id.clone();
                                 let segment_session_id = segment.chunk.session_id.clone();
@@ -2165,6 +3862,18 @@ unintended app video."
                                     active_session_id = Some(segment_session_id);
                                 }
 
+                                if validate_before_upload {
+                                    if let Err(reason) = validate_segment(&segment) {
+                                        error!(
+                                            "Segment {} failed validation ({}); moved to {}/ instead of uploading",
+                                            chunk_id, reason, INVALID_SEGMENTS_DIR
+                                        );
+                                        quarantine_segment(&output_dir, &segment);
+                                        remove_pending_upload(&chunk_id);
+                                        continue;
+                                    }
+                                }
+
                                 // If uploads are paused, queue the segment for later
                                 if uploads_paused.load(AtomicOrdering::SeqCst) {
                                     info!("Uploads paused, queuing segment {} for later", chunk_id);
@@ -2189,7 +3898,33 @@ unintended app video."
                                 }
 
                                 upload_pause_notified = false;
+                                let pending = retry_queue.len();
+                                if stall_detector.check(pending, Instant::now()) {
+                                    warn!("Uploads stalled: {} segments waiting to upload", pending);
+                                    crate::ui::notifications::show_upload_stalled_notification(pending);
+                                    uploads_stalled.store(true, AtomicOrdering::SeqCst);
+                                }
+
+                                // Circuit open: queue it behind the cooldown/probe instead
+                                let now = Instant::now();
+                                if drain.is_none() && !breaker.allow(now) {
+                                    debug!("Upload circuit open, queuing segment {}", chunk_id);
+                                    let retry_at = breaker.retry_at(now);
+                                    sequence = sequence.wrapping_add(1);
+                                    retry_queue.push(RetryEntry {
+                                        next_attempt_at: retry_at,
+                                        sequence,
+                                        item: RetryItem {
+                                            segment,
+                                            attempts: 0,
+                                            first_failed_at: now,
+                                            next_attempt_at: retry_at,
+                                        },
+                                    });
+                                    continue;
+                                }
                                 info!("Background upload starting for segment {}", chunk_id);
+                                in_flight += 1;
                                 spawn_upload(
                                     uploader.clone(),
                                     segment,
@@ -2197,8 +3932,10 @@ unintended app video."
                                     0,
                                     None,
                                     delete_after_upload,
+                                    uploads_paused.clone(),
                                     semaphore.clone(),
                                     result_tx.clone(),
+                                    status_tx.clone(),
                                 );
                             }
                         }
@@ -2206,11 +3943,82 @@ unintended app video."
 
                     // Branch 2: Results from completed upload tasks
                     Some(upload_result) = result_rx.recv() => {
-                        let UploadResult { chunk_id, segment, attempts, first_failed_at, result } = upload_result;
+                        let UploadResult { chunk_id, segment, attempts, first_failed_at, uploaded_bytes, upload_time, result } = upload_result;
+                        in_flight = in_flight.saturating_sub(1);
+                        let Some(result) = result else {
+                            breaker.probe_cancelled(Instant::now());
+                            // Paused before it started: hold it with the rest of the
+                            // paused queue, files untouched, until uploads resume.
+                            debug!("Uploads paused, holding segment {} for later", chunk_id);
+                            let now = Instant::now();
+                            sequence = sequence.wrapping_add(1);
+                            retry_queue.push(RetryEntry {
+                                next_attempt_at: now,
+                                sequence,
+                                item: RetryItem {
+                                    segment,
+                                    attempts,
+                                    first_failed_at,
+                                    next_attempt_at: now,
+                                },
+                            });
+                            continue;
+                        };
                         match result {
                             Ok(()) => {
                                 info!("Successfully uploaded segment {}", chunk_id);
+                                webhooks.send(WebhookPayload::segment_uploaded(&segment.chunk));
+                                if let Ok(mut throughput) = upload_throughput.lock() {
+                                    throughput.record(uploaded_bytes, upload_time);
+                                }
+                                breaker.record_success();
+                                upload_rejected_notified = false;
+                                remove_pending_upload(&chunk_id);
+                                stall_detector.record_success(Instant::now());
+                                uploads_stalled.store(false, AtomicOrdering::SeqCst);
+                                if retain_locally {
+                                    let mut retained = read_retained_uploads();
+                                    retained.extend(chunk_video_files(&segment.chunk));
+                                    retained.push(segment.input_path.clone());
+                                    let pending: HashSet<PathBuf> = read_pending_uploads()
+                                        .into_iter()
+                                        .flat_map(|e| e.video_path.into_iter().chain(std::iter::once(e.input_path)))
+                                        .collect();
+                                    let pruned = prune_local_retention(
+                                        &output_dir,
+                                        local_retention,
+                                        &mut retained,
+                                        &pending,
+                                    );
+                                    if !pruned.is_empty() {
+                                        info!("Pruned {} retained file(s) to stay within the local retention limits", pruned.len());
+                                    }
+                                    write_retained_uploads(&retained);
+                                }
+                            }
+                            Err(e) if !e.is_retryable() => {
+                                // Would be rejected again (e.g. 403 for a bad signature), so
+                                // don't let it hold a retry slot for hours; the files stay on disk.
+                                error!(
+                                    "Upload of segment {} permanently rejected: {:#} (attempt {}); not retrying",
+                                    chunk_id, e, attempts + 1
+                                );
+                                breaker.record_failure(Instant::now());
+                                remove_chunk_upload_leftovers(&segment.chunk);
                                 remove_pending_upload(&chunk_id);
+                                webhooks.send(WebhookPayload::error(
+                                    &format!("Upload permanently rejected: {:#}", e),
+                                    Some(&segment.chunk.session_id),
+                                    Some(&chunk_id),
+                                ));
+                                if !upload_rejected_notified {
+                                    upload_rejected_notified = true;
+                                    let reason = match &e {
+                                        UploadError::Http { status, .. } => format!("HTTP {}", status),
+                                        other => other.to_string(),
+                                    };
+                                    crate::ui::notifications::show_upload_rejected_notification(&reason);
+                                }
                             }
                             Err(e) => {
                                 let attempt = attempts + 1;
@@ -2218,6 +4026,7 @@ unintended app video."
                                     "Failed to upload segment {}: {:#} (attempt {}, retry queue: {})",
                                     chunk_id, e, attempt, retry_queue.len()
                                 );
+                                breaker.record_failure(Instant::now());
                                 let mut delay = backoff_for_attempt(attempt);
                                 delay = delay.mul_f64(jitter_multiplier(&chunk_id, attempt));
                                 if delay > MAX_RETRY_BACKOFF {
@@ -2236,6 +4045,12 @@ unintended app video."
                                     sequence,
                                     item: retry_item,
                                 });
+                                let pending = retry_queue.len();
+                                if stall_detector.check(pending, now) {
+                                    warn!("Uploads stalled: {} segments waiting to upload", pending);
+                                    crate::ui::notifications::show_upload_stalled_notification(pending);
+                                    uploads_stalled.store(true, AtomicOrdering::SeqCst);
+                                }
                             }
                         }
                     }
@@ -2261,21 +4076,43 @@ unintended app video."
                             let item = entry.item;
                             let chunk_id = item.segment.chunk.chunk_id.clone();
 
-                            if now.duration_since(item.first_failed_at) >= MAX_RETRY_WINDOW {
+                            // Only segments that actually failed run out of retries; one
+                            // held through a long upload pause has not been tried yet.
+                            if item.attempts > 0
+                                && now.duration_since(item.first_failed_at) >= MAX_RETRY_WINDOW
+                            {
                                 warn!(
                                     "Giving up on segment {} after {} attempts (retry window exceeded)",
                                     chunk_id, item.attempts
                                 );
+                                remove_chunk_upload_leftovers(&item.segment.chunk);
                                 remove_pending_upload(&chunk_id);
                                 continue;
                             }
 
+                            // Circuit open: push it back behind the cooldown/probe. Stop
+                            // popping, since everything else due would be refused too.
+                            if drain.is_none() && !breaker.allow(now) {
+                                let retry_at = breaker.retry_at(now);
+                                sequence = sequence.wrapping_add(1);
+                                retry_queue.push(RetryEntry {
+                                    next_attempt_at: retry_at,
+                                    sequence,
+                                    item: RetryItem {
+                                        next_attempt_at: retry_at,
+                                        ..item
+                                    },
+                                });
+                                break;
+                            }
+
                             info!(
                                 "Retrying upload for segment {} (attempt {})",
                                 chunk_id,
                                 item.attempts + 1
                             );
 
+                            in_flight += 1;
                             spawn_upload(
                                 uploader.clone(),
                                 item.segment,
@@ -2283,8 +4120,10 @@ unintended app video."
                                 item.attempts,
                                 Some(item.first_failed_at),
                                 delete_after_upload,
+                                uploads_paused.clone(),
                                 semaphore.clone(),
                                 result_tx.clone(),
+                                status_tx.clone(),
                             );
                         }
                     }
@@ -2304,7 +4143,20 @@ unintended app video."
                 upload_rx,
                 self.uploader.clone(),
                 self.delete_after_upload,
+                self.config.upload.max_concurrent_uploads,
+                self.local_retention,
+                self.capture_ctx.output_directory().clone(),
                 self.uploads_paused.clone(),
+                self.uploads_stalled.clone(),
+                UploadCircuitBreaker::new(
+                    self.config.upload.circuit_breaker_failures,
+                    Duration::from_secs(self.config.upload.circuit_breaker_cooldown_secs),
+                    self.uploads_circuit_open.clone(),
+                ),
+                self.config.upload.validate_before_upload,
+                self.upload_throughput.clone(),
+                self.status_tx.clone(),
+                self.webhooks.clone(),
             );
         }
 
@@ -2333,8 +4185,18 @@ unintended app video."
             }
         }
 
+        if self.config.recording.hls.enabled && self.uploader.is_configured() {
+            info!(
+                "HLS piece shipping enabled (every {}s)",
+                self.config.recording.hls.segment_secs.max(1)
+            );
+            self.spawn_hls_shipper();
+        }
+
         // Take notification receiver for the main loop
         let mut notification_rx = self.notification_rx.take();
+        let mut window_geometry_rx = self.window_geometry_rx.take();
+        let mut window_title_rx = self.window_title_rx.take();
 
         // Ensure output directory exists
         std::fs::create_dir_all(&self.output_dir)?;
@@ -2362,12 +4224,33 @@ unintended app video."
         crate::capture::focus::ensure_started();
 
         // Main polling interval
-        let poll_interval = Duration::from_millis(self.config.capture.poll_interval_ms);
+        let poll_interval = self.config.capture.poll_interval();
         let mut poll_timer = tokio::time::interval(poll_interval);
 
         // Broadcast initial status
         self.send_status_force(EngineStatus::Idle);
 
+        // A session marker left behind means the previous run never stopped cleanly.
+        if let Some(marker) = active_session_path().and_then(|p| read_active_session(&p)) {
+            if !self.config.recording.resume_on_restart {
+                debug!(
+                    "Previous session {} was interrupted; starting a new one",
+                    marker.session_id
+                );
+            } else if let Some((session_id, segment_index)) = resume_point(&marker) {
+                info!(
+                    "Resuming interrupted session {} at segment {}",
+                    session_id, segment_index
+                );
+                self.resume_session = Some((session_id, segment_index));
+            } else {
+                warn!(
+                    "Not resuming interrupted session {}: {:?} is missing",
+                    marker.session_id, marker.output_path
+                );
+            }
+        }
+
         // Restore recording state from previous session, or fall back to
         // autostart_on_launch for fresh installs (no persisted state).
         let desired_state = read_recording_state().unwrap_or_else(|| {
@@ -2421,7 +4304,7 @@ unintended app video."
 
                     let events: Vec<InputEvent> =
                         match std::fs::read(&entry.input_path).and_then(|bytes| {
-                            rmp_serde::from_slice(&bytes).map_err(|e| {
+                            decode_input_log(&bytes).map_err(|e| {
                                 std::io::Error::new(std::io::ErrorKind::InvalidData, e)
                             })
                         }) {
@@ -2438,14 +4321,27 @@ unintended app video."
 
                     let start_time_us = events.first().map(|e| e.timestamp_us).unwrap_or(0);
                     let end_time_us = events.last().map(|e| e.timestamp_us).unwrap_or(0);
+                    let video_path = entry.video_path.clone().filter(|_| video_exists);
+                    let stats = SegmentStats::new(
+                        &events,
+                        start_time_us,
+                        end_time_us,
+                        video_path.as_deref().map_or(0, video_bytes),
+                    );
 
                     let chunk = CompletedChunk {
                         chunk_id: entry.chunk_id.clone(),
                         session_id: entry.session_id.clone(),
-                        video_path: entry.video_path.clone().filter(|_| video_exists),
+                        video_path,
                         events,
                         start_time_us,
                         end_time_us,
+                        // Events were rebased when recorded; the clock origin died with the
+                        // previous run.
+                        timebase: Timebase::default(),
+                        video_start_ns: None,
+                        video_offset_us: None,
+                        stats,
                     };
 
                     let segment = CompletedSegment {
@@ -2477,7 +4373,7 @@ unintended app video."
                             e.input_path.exists()
                                 && std::fs::read(&e.input_path)
                                     .and_then(|b| {
-                                        rmp_serde::from_slice::<Vec<InputEvent>>(&b).map_err(|e| {
+                                        decode_input_log(&b).map_err(|e| {
                                             std::io::Error::new(std::io::ErrorKind::InvalidData, e)
                                         })
                                     })
@@ -2490,6 +4386,21 @@ unintended app video."
             }
         }
 
+        // Partial input flushes left by a crash mid-segment are never collected by a
+        // rotation; assemble or drop them (`recording.orphaned_partials`).
+        let (recovered, deleted) = sweep_orphaned_partials(
+            &self.output_dir,
+            std::time::SystemTime::now() - ORPHANED_PARTIAL_MIN_AGE,
+            self.main_session_id.as_deref(),
+            self.config.recording.orphaned_partials,
+        );
+        if deleted > 0 {
+            info!(
+                "Swept {} orphaned partial flush file(s), recovering {} segment input log(s)",
+                deleted, recovered
+            );
+        }
+
         // Wall-clock anchor for resume-from-suspend detection (Windows/Linux). Initialized after
         // all startup work (OBS install/bootstrap can take minutes on first run) so the first
         // poll tick doesn't read that as a freeze. macOS uses its restart-on-unlock path instead.
@@ -2514,6 +4425,8 @@ unintended app video."
                             }
                         }
                         EngineCommand::StopRecording => {
+                            // Also cancels a restart pending after an idle session end.
+                            self.idle_session.take_restart();
                             self.stop_recording().await?;
                             write_recording_state(PersistedRecordingState::Stopped);
                             self.reset_segment_timer();
@@ -2575,6 +4488,7 @@ unintended app video."
                             {
                                 self.config.capture.target_apps = target_apps;
                                 self.config.capture.capture_all = capture_all;
+                                self.config.refresh_target_matcher();
                                 self.capture_ctx.set_single_active_app_capture(
                                     self.config.capture.single_active_app_capture,
                                 );
@@ -2596,7 +4510,8 @@ unintended app video."
                                 };
 
                                 match reload {
-                                    Ok(()) => {
+                                    Ok(outcome) => {
+                                        self.report_setup_outcome(&outcome);
                                         // A freshly (re)created source must warm up again before
                                         // the capture-loss watchdog can declare it dead (Wayland
                                         // reports 0x0 until the portal negotiates).
@@ -2636,17 +4551,23 @@ unintended app video."
                         }
                         EngineCommand::Panic => {
                             warn!("PANIC: deleting recent recordings");
+                            // Nothing of the deleted recording may go up after this.
+                            let shipping = self.stop_hls_shipper();
                             if self.current_session.is_some() {
                                 let session = obs_call_with_watchdog(
                                     || tokio::task::block_in_place(|| self.capture_ctx.stop_recording()),
                                     "panic: stop_recording",
                                 )?;
                                 if let Some(session) = session {
+                                    for piece in playlist_pieces(&session.output_path) {
+                                        if let Err(e) = std::fs::remove_file(&piece) {
+                                            warn!("Failed to delete video piece {:?}: {}", piece, e);
+                                        }
+                                    }
                                     if let Err(e) = std::fs::remove_file(&session.output_path) {
                                         warn!("Failed to delete video {:?}: {}", session.output_path, e);
                                     }
-                                    let seg_id = self.current_segment_id();
-                                    let prefix = format!("input_{}", seg_id);
+                                    let prefix = format!("input_{}", session.session_id);
                                     if let Ok(entries) = std::fs::read_dir(&self.output_dir) {
                                         for entry in entries.flatten() {
                                             let name = entry.file_name();
@@ -2658,10 +4579,15 @@ unintended app video."
                                 }
                                 self.current_session = None;
                                 self.recording_start_ns = None;
+                                self.input_gate.close();
+                                self.end_warmup();
                                 self.segment_timer = None;
                                 self.clear_event_buffer();
                             }
                             self.purge_upload_buffer();
+                            if shipping {
+                                self.spawn_hls_shipper();
+                            }
                             write_recording_state(PersistedRecordingState::Recording);
                             if let Err(e) = self.start_recording().await {
                                 error!("Failed to restart recording after panic: {}", e);
@@ -2671,6 +4597,40 @@ unintended app video."
                             info!("User requested switch to display {}", display_id);
                             self.switch_to_display(display_id);
                         }
+                        EngineCommand::AddMarker { label } => {
+                            self.add_marker(&label);
+                        }
+                        EngineCommand::ListDisplays { respond_to } => {
+                            let _ = respond_to.send(self.display_monitor.list_displays());
+                        }
+                        EngineCommand::ListCapturable { respond_to } => {
+                            // Listing apps shells out on macOS; keep the command loop responsive.
+                            let config = self.config.clone();
+                            tokio::task::spawn_blocking(move || {
+                                let _ = respond_to.send(capturable_app_list(
+                                    list_capturable_apps(),
+                                    |bundle_id| config.should_capture_app(bundle_id),
+                                    get_focused_window_title,
+                                ));
+                            });
+                        }
+                        EngineCommand::QueryStatus { respond_to } => {
+                            let _ = respond_to.send(self.status_snapshot());
+                        }
+                        EngineCommand::TestUpload { respond_to } => {
+                            // Network round trips; keep the command loop responsive.
+                            let uploader = self.uploader.clone();
+                            tokio::spawn(async move {
+                                let result = uploader
+                                    .test_upload()
+                                    .await
+                                    .map_err(|e| format!("{:#}", e));
+                                if let Err(ref e) = result {
+                                    warn!("Test upload failed: {}", e);
+                                }
+                                let _ = respond_to.send(result);
+                            });
+                        }
                         EngineCommand::RestartProcess => {
                             // Bound the restart RATE, never the total. A wedged macOS status-item
                             // host (ControlCenter) can drive a restart storm through this command
@@ -2727,8 +4687,21 @@ unintended app video."
                         EngineCommand::Shutdown => {
                             info!("Shutdown command received");
                             self.input_backend.stop();
+                            // Finished segments go up through the drain below.
+                            self.stop_hls_shipper();
                             self.stop_recording().await?;
+                            // Release libobs now rather than whenever the engine is
+                            // dropped, so a failure is reported while the log still runs.
+                            let shutdown = obs_call_with_watchdog_timeout(
+                                || tokio::task::block_in_place(|| self.capture_ctx.shutdown()),
+                                "shutdown: CaptureContext::shutdown",
+                                SHUTDOWN_FLUSH_TIMEOUT + Duration::from_secs(5),
+                            );
+                            if let Err(e) = shutdown {
+                                error!("Capture shutdown failed: {:#}", e);
+                            }
                             self.flush_upload_buffer();
+                            self.drain_uploads().await;
                             break;
                         }
                     }
@@ -2745,16 +4718,43 @@ unintended app video."
                         NotificationAction::Dismissed => {
                             debug!("User acknowledged display change notification");
                         }
+                        NotificationAction::OpenFolder => {
+                            crate::ui::open_folder(self.capture_ctx.output_directory());
+                        }
+                        NotificationAction::OpenAccessibilitySettings => {
+                            #[cfg(not(target_os = "linux"))]
+                            if let Err(e) =
+                                crate::installer::permissions::open_accessibility_settings()
+                            {
+                                warn!("Failed to open Accessibility settings: {}", e);
+                            }
+                        }
                     }
                 }
 
                 // Handle input events
                 Some(event) = input_rx.recv() => {
-                    let was_paused = self.is_paused;
-                    self.handle_input_event(event).await;
-                    if was_paused && !self.is_paused {
-                        self.reset_segment_timer();
+                    self.dispatch_input_event(event).await;
+                }
+
+                // Focused-window frames read off the loop
+                Some(lookup) = async {
+                    match window_geometry_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    self.record_window_geometry(lookup);
+                }
+
+                // Focused-window titles read off the loop, for `capture.title_patterns`
+                Some((pid, title)) = async {
+                    match window_title_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
                     }
+                } => {
+                    self.window_title.store(pid, Instant::now(), title);
                 }
 
                 // Poll frontmost app and check for display changes
@@ -2798,7 +4798,11 @@ unintended app video."
                     self.graduate_upload_buffer();
                     self.check_capture_health();
                     self.check_low_disk_space();
+                    self.check_recording_health();
+                    self.check_target_app_running().await;
                     self.log_source_resolution_changes();
+                    self.log_display_scale_changes();
+                    self.check_input_permission().await;
                     #[cfg(target_os = "linux")]
                     self.check_capture_alive().await;
                 }
@@ -2822,7 +4826,7 @@ unintended app video."
                 } => {
                     if self.current_session.is_some() && !self.is_paused {
                         info!("Segment duration reached, rotating to new segment...");
-                        if let Err(e) = self.rotate_segment().await {
+                        if let Err(e) = self.rotate_segment(&mut input_rx).await {
                             error!("Failed to rotate segment: {}", e);
                         }
                     } else if self.is_paused {
@@ -2832,6 +4836,16 @@ unintended app video."
                 }
 
 
+                // Recording warmup: open input once the video is up
+                _ = async {
+                    match &self.pending_warmup {
+                        Some(pending) => tokio::time::sleep_until(pending.next_poll).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    self.poll_warmup();
+                }
+
                 // Verify that a newly switched active app source has started producing frames
                 _ = async {
                     match &self.pending_capture_watchdog {
@@ -2848,11 +4862,14 @@ unintended app video."
                     // - Idle timeout is enabled (non-zero)
                     // - Not already paused (either manually or idle-paused)
                     // - Recording is active
-                    if self.idle_timeout.is_zero() || self.is_paused || self.current_session.is_none() {
-                        std::future::pending::<()>().await
-                    } else {
-                        let deadline = self.last_recorded_action_time + self.idle_timeout;
-                        tokio::time::sleep_until(deadline).await
+                    match idle_pause_deadline(
+                        self.last_recorded_action_time,
+                        self.idle_timeout,
+                        self.current_session.is_some(),
+                        self.is_paused,
+                    ) {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending::<()>().await,
                     }
                 } => {
                     let was_paused = self.is_paused;
@@ -2861,6 +4878,28 @@ unintended app video."
                         self.reset_segment_timer();
                     }
                 }
+
+                // End the session after `capture.idle_session_end_secs` without input
+                _ = async {
+                    match self.idle_session.deadline(
+                        self.last_input_time,
+                        self.current_session.is_some(),
+                        self.is_paused && !self.idle_paused,
+                    ) {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending::<()>().await,
+                    }
+                } => {
+                    info!(
+                        "No input for {:?}; ending the session until the next input",
+                        self.idle_session.window
+                    );
+                    if let Err(e) = self.stop_recording().await {
+                        error!("Failed to end the idle session: {}", e);
+                    }
+                    self.idle_session.end();
+                    self.reset_segment_timer();
+                }
             }
         }
 
@@ -2868,16 +4907,120 @@ unintended app video."
         Ok(())
     }
 
+    /// With `recording.adaptive_bitrate`, set the next segment's video bitrate from how
+    /// fast the finished segment was produced vs how fast segments are uploading
+    fn adapt_bitrate(&mut self, video_path: Option<&Path>, elapsed_us: Option<u64>) {
+        let Some(governor) = self.bitrate_governor.as_mut() else {
+            return;
+        };
+        let Some(elapsed_us) = elapsed_us.filter(|&us| us > 0) else {
+            return;
+        };
+        let Some(bytes) = video_path
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map(|meta| meta.len())
+        else {
+            return;
+        };
+        // No completed upload yet: nothing to compare against
+        let Some(upload_bps) = self
+            .upload_throughput
+            .lock()
+            .ok()
+            .and_then(|throughput| throughput.bytes_per_sec())
+        else {
+            return;
+        };
+
+        let produced_bps = bytes as f64 / (elapsed_us as f64 / 1_000_000.0);
+        if let Some(kbps) = governor.decide(produced_bps, upload_bps) {
+            info!(
+                "Adaptive bitrate: {} Kbps from the next segment (producing {:.0} KB/s, uploading {:.0} KB/s)",
+                kbps,
+                produced_bps / 1000.0,
+                upload_bps / 1000.0
+            );
+            self.capture_ctx.set_video_bitrate(kbps);
+        }
+    }
+
+    /// Route one event from the input channel: the capture hotkey toggles pause, anything
+    /// else goes through `handle_input_event`.
+    async fn dispatch_input_event(&mut self, event: InputEvent) {
+        self.last_input_time = Instant::now();
+        if self.idle_session.take_restart() {
+            info!("Input after an idle session end; starting a new session");
+            if let Err(e) = self.start_recording().await {
+                error!("Failed to start a new session after idle: {}", e);
+            }
+            self.reset_segment_timer();
+        }
+        let was_paused = self.is_paused;
+        match self
+            .capture_hotkey
+            .as_mut()
+            .and_then(|hotkey| hotkey.check(&event))
+        {
+            Some(true) => self.toggle_capture_pause(),
+            // The chord's key repeating or coming back up
+            Some(false) => {}
+            None => self.handle_input_event(event).await,
+        }
+        if was_paused && !self.is_paused {
+            self.reset_segment_timer();
+        }
+    }
+
     /// Rotate to a new recording segment
     ///
     /// This stops the current recording, queues it for upload, and starts
     /// a new recording segment. Used for progressive upload to minimize
     /// storage overhead.
-    async fn rotate_segment(&mut self) -> Result<()> {
+    async fn rotate_segment(
+        &mut self,
+        input_rx: &mut mpsc::UnboundedReceiver<InputEvent>,
+    ) -> Result<()> {
         if self.current_session.is_none() {
             debug!("No recording in progress, skipping segment rotation");
             return Ok(());
         }
+
+        let min_segment = Duration::from_secs(self.config.recording.min_segment_secs);
+        if let Some(delay) = rotation_deferral(self.current_recording_elapsed_us(), min_segment) {
+            // Re-arm the timer for when the segment is long enough; any rotation due
+            // before then is the same one.
+            info!(
+                "Segment {} is shorter than {:?}; deferring rotation by {:?}",
+                self.segment_index, min_segment, delay
+            );
+            let period = Duration::from_secs(self.segment_duration_secs.max(1));
+            self.segment_timer = Some(tokio::time::interval_at(Instant::now() + delay, period));
+            return Ok(());
+        }
+
+        // Input after the cut is held and replayed once the next segment is capturing.
+        // Tradeoff: input during the cut itself has no video frames of its own, so it is
+        // timed at the start of the next segment, up to the rotation's length late.
+        let mut after_cut = Vec::new();
+        let result = self.rotate_segment_at_cut(input_rx, &mut after_cut).await;
+        for event in after_cut {
+            self.dispatch_input_event(event).await;
+        }
+        result
+    }
+
+    /// Close the current segment and start the next. Input still queued when the recording
+    /// stops would otherwise be handled after the new segment starts and land on its
+    /// timeline: what the backend read before the stop goes into the closing segment, and
+    /// the rest is left in `after_cut`.
+    async fn rotate_segment_at_cut(
+        &mut self,
+        input_rx: &mut mpsc::UnboundedReceiver<InputEvent>,
+        after_cut: &mut Vec<InputEvent>,
+    ) -> Result<()> {
+        if self.current_session.is_none() {
+            return Ok(());
+        }
         if self.is_paused {
             debug!("Skipping segment rotation while paused");
             return Ok(());
@@ -2893,34 +5036,52 @@ unintended app video."
             self.segment_index, main_session_id
         );
 
-        // Disable input capture during rotation to prevent events without corresponding video
-        self.capture_enabled = false;
-
-        // Flush current events and get video path
         let video_path = self.current_session.as_ref().map(|s| s.output_path.clone());
-        let segment_id = self.current_segment_id();
+        let segment_id = self.recording_segment_id();
 
-        // Collect all events: partial flush files + remaining buffer
-        let events = self.collect_segment_events(&segment_id).await?;
-        let start_time_us = events.first().map(|e| e.timestamp_us).unwrap_or(0);
+        // Stop the current recording
+        let segment_elapsed_us = self.current_recording_elapsed_us();
+        let cut_us = self.input_backend.current_timestamp();
+        let (stopped, before_cut, queued_after_cut) = stop_at_cut(input_rx, cut_us, || {
+            obs_call_with_watchdog(
+                || tokio::task::block_in_place(|| self.capture_ctx.stop_recording()),
+                "rotate_segment: stop_recording",
+            )
+        });
+        *after_cut = queued_after_cut;
+        for event in before_cut {
+            self.dispatch_input_event(event).await;
+        }
+        let _session = stopped?;
+        self.adapt_bitrate(video_path.as_deref(), segment_elapsed_us);
+
+        // Disable input capture during rotation to prevent events without corresponding video
+        self.capture_enabled = false;
+
+        self.log_resource_usage();
+        // Collect all events: partial flush files + remaining buffer
+        let events = self.collect_segment_events(&segment_id).await?;
+        let start_time_us = events.first().map(|e| e.timestamp_us).unwrap_or(0);
         let end_time_us = events.last().map(|e| e.timestamp_us).unwrap_or(0);
 
         // Save combined input events to disk
         let input_path = self
             .output_dir
             .join(format!("input_{}.msgpack", segment_id));
-        let bytes = rmp_serde::to_vec(&events)?;
+        let bytes = encode_input_log(&events)?;
         tokio::fs::write(&input_path, bytes).await?;
 
         info!("Saved {} events to {:?}", events.len(), input_path);
 
-        // Stop the current recording
-        let _session = obs_call_with_watchdog(
-            || tokio::task::block_in_place(|| self.capture_ctx.stop_recording()),
-            "rotate_segment: stop_recording",
-        )?;
-
         // Create completed segment for upload
+        let (timebase, video_start_ns, video_offset_us) =
+            self.event_clock.timebase(self.recording_start_ns);
+        let stats = SegmentStats::new(
+            &events,
+            start_time_us,
+            end_time_us,
+            video_path.as_deref().map_or(0, video_bytes),
+        );
         let chunk = CompletedChunk {
             chunk_id: segment_id.clone(),
             session_id: main_session_id.clone(),
@@ -2928,6 +5089,10 @@ unintended app video."
             video_path: video_path.clone(),
             start_time_us,
             end_time_us,
+            timebase,
+            video_start_ns,
+            video_offset_us,
+            stats,
         };
 
         // Buffer for delayed upload (10-minute hold for panic button)
@@ -2940,6 +5105,7 @@ unintended app video."
         self.recording_start_ns = None;
 
         let (frontmost_app, should_capture) = self.frontmost_capture_state();
+        self.apply_app_profile(frontmost_app.as_deref());
         let desired_target = self.prepare_active_capture_target(
             frontmost_app.as_deref(),
             should_capture,
@@ -2948,6 +5114,7 @@ unintended app video."
 
         // Increment segment index
         self.segment_index += 1;
+        self.advance_segment_counter();
 
         // Start new recording segment
         let new_segment_id = self.current_segment_id();
@@ -2956,13 +5123,15 @@ unintended app video."
             Err(e) => {
                 // Failed to start new segment - leave capture disabled and in non-recording state
                 error!("Failed to start new segment after rotation: {}", e);
+                self.input_gate.close();
+                self.end_warmup();
                 self.main_session_id = None;
                 self.segment_index = 0;
                 self.send_status_force(EngineStatus::Error(format!(
                     "Segment rotation failed: {}",
                     e
                 )));
-                return Err(e);
+                return Err(e.into());
             }
         };
 
@@ -2971,18 +5140,28 @@ unintended app video."
             self.segment_index, session.session_id, session.output_path
         );
 
-        self.recording_start_ns = Some(session.start_time_ns);
+        let frame_time = || self.capture_ctx.get_video_frame_time();
+        let start_ns = self
+            .event_clock
+            .start_segment(session.start_time_ns, frame_time);
+        self.recording_start_ns = Some(start_ns);
         self.pause_start_ns = None;
         self.current_session = Some(session);
+        self.persist_active_session();
 
         self.emit_metadata_event(0);
         self.emit_context_snapshot(should_capture, 0);
+        self.window_geometry.reset();
+        self.log_window_geometry_changes(get_frontmost_app().map(|app| app.pid), should_capture, 0);
         if let Some(app) = desired_target.as_deref() {
             self.schedule_capture_watchdog(app, 0);
         }
         self.update_capture_enabled(should_capture, desired_target.as_deref());
         if self.capture_enabled {
-            self.send_status_force(EngineStatus::Capturing { event_count: 0 });
+            self.send_status_force(EngineStatus::Capturing {
+                event_count: 0,
+                counts: self.segment_event_counts,
+            });
         } else {
             self.send_status_force(EngineStatus::RecordingBlocked);
         }
@@ -2990,30 +5169,117 @@ unintended app video."
         Ok(())
     }
 
-    /// Get the current segment ID (main_session_id + segment_index)
+    /// Switch the video to `frontmost_app`'s `capture.app_profiles` settings, or back to the
+    /// defaults. Only called between segments: resetting the video needs the recording
+    /// output stopped and recreates the capture sources.
+    fn apply_app_profile(&mut self, frontmost_app: Option<&str>) {
+        let settings = self
+            .config
+            .capture
+            .app_profile(frontmost_app)
+            .resolve(self.default_video_settings);
+        let previous = self.capture_ctx.video_settings();
+        if settings == previous {
+            return;
+        }
+
+        info!(
+            "Switching video to {} fps, max height {} for {:?}",
+            settings.0, settings.1, frontmost_app
+        );
+        self.capture_ctx.set_video_settings(settings);
+        let reset = obs_call_with_watchdog(
+            || tokio::task::block_in_place(|| self.capture_ctx.reset_video_and_recreate_sources()),
+            "apply_app_profile: reset_video_and_recreate_sources",
+        );
+        if let Err(e) = reset {
+            error!("Failed to apply recording profile: {}", e);
+            // Retry at the next boundary rather than believing the switch happened
+            self.capture_ctx.set_video_settings(previous);
+        }
+    }
+
+    /// Get the current segment ID (main_session_id + segment_index, plus the global
+    /// counter when enabled)
     fn current_segment_id(&self) -> String {
-        match &self.main_session_id {
+        let id = match &self.main_session_id {
             Some(id) => format!("{}_seg{:04}", id, self.segment_index),
             None => format!("unknown_seg{:04}", self.segment_index),
+        };
+        match self.segment_counter {
+            Some(counter) => format!("{}_g{:08}", id, counter),
+            None => id,
+        }
+    }
+
+    /// ID of the segment being recorded, as the capture context recorded it
+    /// (`recording.on_existing = "suffix"` may have renamed it), so the input log and partial
+    /// flushes share the video's ID. Falls back to `current_segment_id` between segments.
+    fn recording_segment_id(&self) -> String {
+        match &self.current_session {
+            Some(session) => session.session_id.clone(),
+            None => self.current_segment_id(),
+        }
+    }
+
+    /// Draw the global counter value for a segment that is about to start
+    fn advance_segment_counter(&mut self) {
+        self.segment_counter = None;
+        if !self.config.recording.global_segment_counter {
+            return;
+        }
+        let Some(path) = segment_counter_path() else {
+            return;
+        };
+        match next_segment_counter(&path) {
+            Ok(counter) => self.segment_counter = Some(counter),
+            Err(e) => warn!(
+                "Failed to advance global segment counter {:?}: {}; naming segment without it",
+                path, e
+            ),
         }
     }
 
+    /// Record the segment now being recorded in the active session marker, so a crash
+    /// leaves behind where the session stood
+    fn persist_active_session(&self) {
+        let (Some(path), Some(session_id), Some(session)) = (
+            active_session_path(),
+            self.main_session_id.as_ref(),
+            self.current_session.as_ref(),
+        ) else {
+            return;
+        };
+        let marker = ActiveSessionMarker {
+            session_id: session_id.clone(),
+            segment_index: self.segment_index,
+            output_path: session.output_path.clone(),
+        };
+        write_active_session(&path, &marker);
+    }
+
     /// Collect all events for a segment, including partial flush files and buffer
     ///
     /// This reads any partial flush files for the segment, combines them with
     /// the remaining buffer events, and cleans up the partial files.
     async fn collect_segment_events(&mut self, segment_id: &str) -> Result<Vec<InputEvent>> {
         let mut all_events = Vec::new();
+        // The segment is being finalized; the next one starts a fresh tally.
+        self.segment_event_counts = EventTypeCounts::default();
 
-        // Find and read all partial flush files for this segment
+        // Find and read all partial flush files for this segment. Both layouts are
+        // picked up, so toggling `input.append_partial_flushes` mid-segment loses nothing.
         let partial_prefix = format!("input_{}_partial_", segment_id);
+        let append_name = partial_append_file_name(segment_id);
         let mut partial_files = Vec::new();
 
         if let Ok(mut entries) = tokio::fs::read_dir(&self.output_dir).await {
             while let Ok(Some(entry)) = entries.next_entry().await {
                 let file_name = entry.file_name();
                 let file_name_str = file_name.to_string_lossy();
-                if file_name_str.starts_with(&partial_prefix) && file_name_str.ends_with(".msgpack")
+                if (file_name_str.starts_with(&partial_prefix)
+                    && file_name_str.ends_with(".msgpack"))
+                    || file_name_str == append_name
                 {
                     partial_files.push(entry.path());
                 }
@@ -3024,26 +5290,7 @@ unintended app video."
         partial_files.sort();
 
         // Read and combine events from partial files
-        for partial_path in &partial_files {
-            match tokio::fs::read(partial_path).await {
-                Ok(bytes) => match rmp_serde::from_slice::<Vec<InputEvent>>(&bytes) {
-                    Ok(events) => {
-                        debug!(
-                            "Loaded {} events from partial file {:?}",
-                            events.len(),
-                            partial_path
-                        );
-                        all_events.extend(events);
-                    }
-                    Err(e) => {
-                        warn!("Failed to parse partial file {:?}: {}", partial_path, e);
-                    }
-                },
-                Err(e) => {
-                    warn!("Failed to read partial file {:?}: {}", partial_path, e);
-                }
-            }
-        }
+        all_events.extend(read_partial_files(&partial_files, &append_name).await);
 
         // Add remaining events from buffer
         let buffer_events = self.drain_event_buffer();
@@ -3064,8 +5311,7 @@ unintended app video."
             }
         }
 
-        // Sort all events by timestamp to ensure proper order
-        all_events.sort_by_key(|e| e.timestamp_us);
+        sort_segment_events(&mut all_events);
 
         Ok(all_events)
     }
@@ -3176,25 +5422,34 @@ unintended app video."
 
         info!("Starting recording...");
 
+        // The main session ID persists across all segments; fails before any capture
+        // setup when `recording.session_id_scheme` can't produce one. A session a crash
+        // interrupted continues under its own ID instead.
+        let (main_session_id, first_segment_index) = match self.resume_session.take() {
+            Some(resumed) => resumed,
+            None => (self.config.recording_session_id()?, 0),
+        };
+
         // Ensure capture sources are set up
         if !self.capture_ctx.is_capture_setup() {
-            self.capture_ctx.setup_capture(
+            let outcome = self.capture_ctx.setup_capture(
                 &self.config.capture.target_apps,
                 &self.config.capture.restore_tokens,
             )?;
+            self.report_setup_outcome(&outcome);
         }
 
         let (frontmost_app, should_capture) = self.frontmost_capture_state();
+        self.apply_app_profile(frontmost_app.as_deref());
         let desired_target = self.prepare_active_capture_target(
             frontmost_app.as_deref(),
             should_capture,
             "Failed to initialize active capture source before recording start",
         )?;
 
-        // Generate a main session ID (persists across all segments)
-        let main_session_id = uuid::Uuid::new_v4().to_string();
         self.main_session_id = Some(main_session_id.clone());
-        self.segment_index = 0;
+        self.segment_index = first_segment_index;
+        self.advance_segment_counter();
         let _ = self
             .upload_tx
             .send(UploadMessage::StartSession(main_session_id.clone()));
@@ -3211,7 +5466,7 @@ unintended app video."
         let segment_id = self.current_segment_id();
 
         // Start libobs recording with HEVC hardware encoding
-        let session = self.capture_ctx.start_recording(segment_id)?;
+        let session = self.capture_ctx.start_recording(segment_id.clone())?;
 
         let segment_info = if self.config.recording.segment_duration_secs > 0 {
             format!(
@@ -3228,23 +5483,62 @@ unintended app video."
         );
 
         // Store the OBS timestamp for event synchronization
-        self.recording_start_ns = Some(session.start_time_ns);
+        let session_start_ns = session.start_time_ns;
+        let frame_time = || self.capture_ctx.get_video_frame_time();
+        let start_ns = self.event_clock.start_segment(session_start_ns, frame_time);
+        self.recording_start_ns = Some(start_ns);
+        self.input_gate.open(self.input_clock_now_us());
         self.pause_start_ns = None;
         self.current_session = Some(session);
+        self.persist_active_session();
+        self.webhooks.send(WebhookPayload::session_started(
+            &main_session_id,
+            &segment_id,
+        ));
         self.clear_event_buffer();
         self.clear_pending_input_transition();
         self.is_paused = false; // Ensure not paused when starting
         self.idle_paused = false; // Ensure not idle-paused when starting
+        self.idle_session.take_restart();
+        self.target_app_gone = false;
         self.last_recorded_action_time = Instant::now(); // Reset recorded-action timer
+        self.last_input_time = Instant::now();
 
         self.emit_metadata_event(0);
         self.emit_context_snapshot(should_capture, 0);
+        self.window_geometry.reset();
+        self.log_window_geometry_changes(get_frontmost_app().map(|app| app.pid), should_capture, 0);
         if let Some(app) = desired_target.as_deref() {
             self.schedule_capture_watchdog(app, 0);
         }
+
+        // Hold input while the recording warms up; the engine loop polls the warmup
+        // (`poll_warmup`) and opens the gate once it's over.
+        let warmup = Duration::from_millis(self.config.recording.warmup_ms);
+        let mut content_warmup = Duration::from_millis(self.config.recording.content_warmup_ms);
+        self.end_warmup();
+        if !content_warmup.is_zero() {
+            match self.capture_ctx.context().map(FrameLumaProbe::attach) {
+                Some(Ok(probe)) => self.warmup_probe = Some(probe),
+                Some(Err(e)) => {
+                    warn!("Failed to watch rendered frames for content: {}", e);
+                    content_warmup = Duration::ZERO;
+                }
+                None => content_warmup = Duration::ZERO,
+            }
+        }
+        self.pending_warmup =
+            PendingWarmup::new(session_start_ns, warmup, content_warmup, Instant::now());
+        if self.pending_warmup.is_some() {
+            self.input_gate.close();
+        }
+
         self.update_capture_enabled(should_capture, desired_target.as_deref());
         if self.capture_enabled {
-            self.send_status_force(EngineStatus::Capturing { event_count: 0 });
+            self.send_status_force(EngineStatus::Capturing {
+                event_count: 0,
+                counts: self.segment_event_counts,
+            });
         } else {
             self.send_status_force(EngineStatus::RecordingBlocked);
         }
@@ -3258,6 +5552,31 @@ unintended app video."
         Ok(())
     }
 
+    /// Check the running recording's warmup; once it's over (or has given up), input
+    /// counts from here on.
+    fn poll_warmup(&mut self) {
+        let Some(warmup) = self.pending_warmup.as_mut() else {
+            return;
+        };
+        let capture_ctx = &self.capture_ctx;
+        let probe = self.warmup_probe.as_ref();
+        let done = warmup.poll(
+            Instant::now(),
+            || capture_ctx.get_video_frame_time(),
+            || probe.and_then(FrameLumaProbe::peak_luma),
+        );
+        if done {
+            self.end_warmup();
+            self.input_gate.open(self.input_clock_now_us());
+        }
+    }
+
+    /// Drop the warmup state, detaching the frame probe
+    fn end_warmup(&mut self) {
+        self.pending_warmup = None;
+        self.warmup_probe = None;
+    }
+
     /// Stop recording
     async fn stop_recording(&mut self) -> Result<()> {
         if self.current_session.is_none() {
@@ -3269,8 +5588,9 @@ unintended app video."
 
         // Save any buffered events with final video path
         let video_path = self.current_session.as_ref().map(|s| s.output_path.clone());
-        let segment_id = self.current_segment_id();
+        let segment_id = self.recording_segment_id();
 
+        self.log_resource_usage();
         // Collect all events: partial flush files + remaining buffer
         let events = self.collect_segment_events(&segment_id).await?;
 
@@ -3282,7 +5602,7 @@ unintended app video."
             let input_path = self
                 .output_dir
                 .join(format!("input_{}.msgpack", segment_id));
-            let bytes = rmp_serde::to_vec(&events)?;
+            let bytes = encode_input_log(&events)?;
             tokio::fs::write(&input_path, bytes).await?;
 
             info!("Saved {} events to {:?}", events.len(), input_path);
@@ -3302,17 +5622,30 @@ unintended app video."
             // Queue final segment for upload
             if self.uploader.is_configured() {
                 let main_session_id = self.main_session_id.clone().unwrap_or_default();
+                let (timebase, video_start_ns, video_offset_us) =
+                    self.event_clock.timebase(self.recording_start_ns);
+                let stats = SegmentStats::new(
+                    &events,
+                    start_time_us,
+                    end_time_us,
+                    video_path.as_deref().map_or(0, video_bytes),
+                );
                 let chunk = CompletedChunk {
                     chunk_id: segment_id.clone(),
-                    session_id: main_session_id,
+                    session_id: main_session_id.clone(),
                     events,
                     video_path,
                     start_time_us,
                     end_time_us,
+                    timebase,
+                    video_start_ns,
+                    video_offset_us,
+                    stats,
                 };
 
                 let segment = CompletedSegment { chunk, input_path };
                 self.buffer_segment_for_upload(segment, segment_id);
+                self.compact_session_segments(&main_session_id);
             }
         } else {
             // Just stop recording without upload
@@ -3328,10 +5661,16 @@ unintended app video."
             }
         }
 
+        if let Some(path) = active_session_path() {
+            let _ = std::fs::remove_file(path);
+        }
         self.current_session = None;
         self.recording_start_ns = None;
+        self.input_gate.close();
+        self.end_warmup();
         self.main_session_id = None;
         self.segment_index = 0;
+        self.segment_counter = None;
         self.is_paused = false;
         self.idle_paused = false;
         self.pending_app_switch = None;
@@ -3353,10 +5692,15 @@ unintended app video."
         let capture_lost = self.capture_lost;
         #[cfg(not(target_os = "linux"))]
         let capture_lost = false;
-        if self.config.recording.notify_on_start_stop && notifications_authorized() && !capture_lost
+        // A stop because the target app quit gets its own toast from `check_target_app_running`.
+        if self.config.recording.notify_on_start_stop
+            && notifications_authorized()
+            && !capture_lost
+            && !self.target_app_gone
         {
             show_recording_stopped_notification();
         }
+        self.target_app_gone = false;
 
         Ok(())
     }
@@ -3383,10 +5727,10 @@ unintended app video."
             // the intent to pause should stick even if OBS is degraded.
         }
 
-        // Capture the frame-time clock at pause. On resume we add the elapsed pause to
-        // recording_start_ns, because OBS's clock advances while paused but the recording
+        // Capture the event clock at pause. On resume we add the elapsed pause to
+        // recording_start_ns, because the clock advances while paused but the recording
         // file does not — without this, post-resume event timestamps drift ahead of the video.
-        self.pause_start_ns = self.capture_ctx.get_video_frame_time().ok();
+        self.pause_start_ns = self.clock_now_ns();
 
         self.is_paused = true;
         self.capture_enabled = false;
@@ -3396,9 +5740,11 @@ unintended app video."
         // When the pause is idle-initiated, `handle_idle_timeout` shows the more specific
         // "Recording paused (idle)" toast itself, so skip the generic one to avoid a double.
         // (`idle_paused` is set true before `pause_recording()` runs; it's false for a user pause.)
+        // Likewise for a pause because the target app quit (`target_app_gone`).
         if self.config.recording.notify_on_start_stop
             && notifications_authorized()
             && !self.idle_paused
+            && !self.target_app_gone
         {
             show_recording_paused_notification();
         }
@@ -3406,6 +5752,24 @@ unintended app video."
         info!("Recording paused");
     }
 
+    /// `hotkeys.toggle_capture` was pressed: pause the running recording, or resume it if
+    /// it is paused (an idle pause included).
+    fn toggle_capture_pause(&mut self) {
+        if self.current_session.is_none() {
+            debug!("Capture hotkey pressed while not recording");
+            return;
+        }
+        if self.is_paused {
+            info!("Capture hotkey: resuming");
+            self.idle_paused = false;
+            self.target_app_gone = false;
+            self.resume_recording();
+        } else {
+            info!("Capture hotkey: pausing");
+            self.pause_recording();
+        }
+    }
+
     /// Resume recording (both video capture and keylog)
     fn resume_recording(&mut self) {
         if self.current_session.is_none() {
@@ -3446,13 +5810,13 @@ unintended app video."
 
         self.is_paused = false;
 
-        // Pause-drift correction: OBS's frame-time clock kept advancing while paused, but the
+        // Pause-drift correction: the event clock kept advancing while paused, but the
         // recording file accrued no frames, so the video timeline is seamless across the pause.
         // Shift recording_start_ns forward by the pause duration so current_recording_elapsed_us()
         // (which times every input event) stays matched to the video PTS instead of running ahead.
         // Must happen before the emit_context_snapshot below, which timestamps the first event.
         if let Some(pause_start) = self.pause_start_ns.take() {
-            if let Ok(now) = self.capture_ctx.get_video_frame_time() {
+            if let Some(now) = self.clock_now_ns() {
                 if let Some(start) = self.recording_start_ns.as_mut() {
                     let pause_ns = now.saturating_sub(pause_start);
                     *start = start.saturating_add(pause_ns);
@@ -3473,6 +5837,7 @@ unintended app video."
         if self.capture_enabled {
             self.send_status_force(EngineStatus::Capturing {
                 event_count: self.buffered_input_event_count(),
+                counts: self.segment_event_counts,
             });
         } else {
             self.send_status_force(EngineStatus::RecordingBlocked);
@@ -3484,6 +5849,7 @@ unintended app video."
         if self.config.recording.notify_on_start_stop
             && notifications_authorized()
             && !self.idle_paused
+            && !self.target_app_gone
         {
             show_recording_resumed_notification();
         }
@@ -3549,7 +5915,7 @@ unintended app video."
         let Some(start_ns) = self.recording_start_ns else {
             return;
         };
-        let current_ns = self.capture_ctx.get_video_frame_time().unwrap_or(start_ns);
+        let current_ns = self.clock_now_ns().unwrap_or(start_ns);
         let elapsed = Duration::from_nanos(current_ns.saturating_sub(start_ns));
         if elapsed < CAPTURE_HEALTH_TIMEOUT {
             return;
@@ -3678,8 +6044,18 @@ unintended app video."
     /// initially report 0x0 dimensions — the watchdog retries indefinitely until
     /// SCK delivers frames.
     async fn check_display_changes(&mut self) {
-        // Check if display configuration changed (macOS only, no-op on other platforms)
-        let Some(event) = self.display_monitor.check_for_changes() else {
+        // Check if display configuration changed (macOS only, no-op on other platforms). A
+        // returned display is held back until it stops flapping (see DisplayRecoveryDebounce).
+        let now = Instant::now();
+        let event = match self.display_monitor.check_for_changes() {
+            Some(event) => self.display_recovery.on_event(event, now),
+            None => self.display_recovery.poll(now),
+        };
+        let Some(event) = event else {
+            // A returned display is still settling; leave the canvas alone until it has.
+            if self.display_recovery.is_pending() {
+                return;
+            }
             // No change event — but verify the canvas still matches the live display set.
             // Change detection compares against its own last snapshot; if that snapshot and
             // the canvas were built from mode reads taken at different instants during
@@ -3823,6 +6199,7 @@ unintended app video."
                 info!("All displays disconnected, waiting for reconnection...");
             }
         }
+        self.refresh_capture_region();
     }
 
     /// macOS: restart the process for a fresh capture context after a *settled* display change.
@@ -3902,6 +6279,74 @@ unintended app video."
         }
     }
 
+    /// Apply `capture.on_target_exit`: pause or stop the recording once none of
+    /// `capture.target_apps` is running, and resume a pause once one is back.
+    async fn check_target_app_running(&mut self) {
+        let policy = self.config.capture.on_target_exit;
+        if policy == TargetExitPolicy::Continue
+            || self.config.capture.capture_all
+            || self.config.capture.target_apps.is_empty()
+            || self.current_session.is_none()
+        {
+            return;
+        }
+        // Leave user and idle pauses alone.
+        if self.is_paused && !self.target_app_gone {
+            return;
+        }
+        if self.last_target_app_check.elapsed() < TARGET_APP_CHECK_INTERVAL {
+            return;
+        }
+        self.last_target_app_check = Instant::now();
+
+        let apps = list_running_apps();
+        // An empty list means the apps can't be listed (e.g. no focus provider on
+        // Wayland), not that every app has quit.
+        if apps.is_empty() {
+            return;
+        }
+        let running = apps
+            .iter()
+            .any(|app| self.config.should_capture_app(&app.bundle_id));
+        let Some(action) = target_exit_action(policy, running, self.target_app_gone) else {
+            return;
+        };
+
+        let notify = self.config.recording.notify_on_start_stop && notifications_authorized();
+        match action {
+            TargetExitAction::Pause => {
+                info!("No target app is running; pausing the recording until one is back");
+                self.target_app_gone = true;
+                self.pause_recording();
+                if notify {
+                    show_target_app_exited_notification(false);
+                }
+            }
+            TargetExitAction::Stop => {
+                info!("No target app is running; stopping the recording");
+                self.target_app_gone = true;
+                if let Err(e) = self.stop_recording().await {
+                    error!("Failed to stop recording after the target app quit: {}", e);
+                }
+                if notify {
+                    show_target_app_exited_notification(true);
+                }
+            }
+            TargetExitAction::Resume => {
+                info!("A target app is running again; resuming the recording");
+                self.resume_recording();
+                if self.is_paused {
+                    return;
+                }
+                self.target_app_gone = false;
+                self.last_recorded_action_time = Instant::now();
+                if notify {
+                    show_recording_resumed_notification();
+                }
+            }
+        }
+    }
+
     /// Resume recording after idle-pause when user activity is detected
     ///
     /// Called when any user input is detected while in idle-paused state.
@@ -3967,6 +6412,98 @@ unintended app video."
         }
     }
 
+    /// Recompute the consolidated recording health and send it to the tray when it
+    /// changes. Only meaningful while an unpaused recording is running; otherwise the
+    /// frame-progress baseline is reset so the next recording starts fresh.
+    fn check_recording_health(&mut self) {
+        if self.current_session.is_none() || self.is_paused {
+            self.last_health = None;
+            self.last_health_frame_ns = None;
+            self.flat_audio.reset();
+            return;
+        }
+        if self.last_health_check.elapsed() < HEALTH_CHECK_INTERVAL {
+            return;
+        }
+        self.last_health_check = Instant::now();
+
+        let audio_flat = self.sample_audio_level();
+        if let Some(sampler) = self.resource_sampler.as_mut() {
+            sampler.sample();
+        }
+        let frame_ns = self.capture_ctx.get_video_frame_time().ok();
+        let health = compute_recording_health(
+            self.capture_ctx.active_source_dimensions().ok().flatten(),
+            self.last_health_frame_ns,
+            frame_ns,
+            self.low_disk_warned,
+            self.uploads_stalled.load(AtomicOrdering::SeqCst),
+            audio_flat,
+        );
+        self.last_health_frame_ns = frame_ns;
+
+        if self.last_health == Some(health) {
+            return;
+        }
+        if health.is_healthy() {
+            info!("Recording health OK");
+        } else {
+            warn!(
+                "Recording health degraded: {}",
+                health.problems().join(", ")
+            );
+        }
+        self.last_health = Some(health);
+        self.send_status(EngineStatus::Health(health));
+    }
+
+    /// Take the captured audio's peak level since the previous health check, log it with
+    /// `recording.log_audio_levels` and feed the microphone's to the flat-audio check
+    /// (system audio is legitimately flat whenever nothing plays). Returns whether the
+    /// microphone is currently flat; false when it isn't metered.
+    fn sample_audio_level(&mut self) -> bool {
+        let peaks = self.capture_ctx.audio_peaks();
+        if let (Some(db), true) = (peaks.overall, self.config.recording.log_audio_levels) {
+            let timestamp_us = self.current_capture_timestamp_us();
+            self.event_buffer.push(InputEvent::now(
+                timestamp_us,
+                EventType::AudioLevel(AudioLevelEvent {
+                    db: clamp_level_db(db),
+                }),
+            ));
+        }
+        let Some(db) = peaks.microphone.map(clamp_level_db) else {
+            return false;
+        };
+        if self.flat_audio.observe(db, Instant::now()) {
+            let secs = self.config.recording.audio.flat_warning_secs;
+            warn!(
+                "Microphone level has stayed at {:.0} dBFS for {}s; check the microphone and device",
+                db, secs
+            );
+            if notifications_authorized() {
+                show_audio_flat_notification(secs);
+            }
+        }
+        self.flat_audio.is_flat()
+    }
+
+    /// Log the resource usage summary of the segment that is ending, if any was sampled
+    fn log_resource_usage(&mut self) {
+        let Some(summary) = self
+            .resource_sampler
+            .as_mut()
+            .and_then(ResourceSampler::take_summary)
+        else {
+            return;
+        };
+        let timestamp_us = self.current_capture_timestamp_us();
+        self.event_buffer.push(InputEvent::now(
+            timestamp_us,
+            EventType::ResourceUsage(summary),
+        ));
+    }
+
     /// Poll the frontmost application and update capture state
     async fn poll_frontmost_app(&mut self) {
         // Ignore the agent's own app being frontmost (our Settings/wizard window
@@ -3976,7 +6513,9 @@ unintended app video."
         // reporting the previous app (filter_self in frontmost.rs), which also
         // protects the frontmost_capture_state callers that bypass this poll
         // guard (segment rotation, recording start/resume, app switches).
-        if get_frontmost_app()
+        let frontmost = get_frontmost_app();
+        if frontmost
+            .as_ref()
             .map(|a| crate::config::is_agent_self(&a.bundle_id))
             .unwrap_or(false)
         {
@@ -4014,6 +6553,11 @@ unintended app video."
         let is_recording = self.current_session.is_some();
         if is_recording {
             self.maybe_emit_context_transition(should_capture);
+            self.log_window_geometry_changes(
+                frontmost.map(|a| a.pid),
+                should_capture,
+                self.current_capture_timestamp_us(),
+            );
         }
         self.update_capture_enabled(should_capture, desired_target.as_deref());
 
@@ -4022,6 +6566,7 @@ unintended app video."
             if self.capture_enabled {
                 self.send_status(EngineStatus::Capturing {
                     event_count: self.buffered_input_event_count(),
+                    counts: self.segment_event_counts,
                 });
             } else if !self.is_paused {
                 self.send_status(EngineStatus::RecordingBlocked);
@@ -4032,6 +6577,11 @@ unintended app video."
 
     /// Handle an input event
     async fn handle_input_event(&mut self, event: InputEvent) {
+        // Input that queued up while start_recording was starting or warming up
+        if self.input_gate.predates(&event) {
+            return;
+        }
+
         let mut transition_target = None;
 
         // Auto-resume from idle only when frontmost app is capturable
@@ -4074,17 +6624,46 @@ unintended app video."
             self.last_recorded_action_time = Instant::now();
         }
 
-        // Only buffer events if capture is enabled
-        if !self.capture_enabled {
-            if let Some(target_app) = transition_target.as_deref() {
-                self.buffer_transition_input_event(target_app, event);
-            }
+        // Input that won't be recorded (capture off and not held for a pending app switch)
+        // stays out of the filter chain, so its coalescing and chord state can't carry
+        // into later output.
+        if !self.capture_enabled && transition_target.is_none() {
             return;
         }
 
-        let adjusted_event = self.adjust_input_event_timestamp(event);
+        // Event-type toggles, the capture region, redaction, coalescing and chords (see
+        // input::filter). A dropped event yields nothing; a chord follows the key press that
+        // completed it.
+        let ctx = FilterContext {
+            frontmost_app: self.last_frontmost_app.as_deref(),
+            secure_input: self.secure_state.should_suppress_keys(),
+            capture_region: self.capture_region,
+        };
+        let events = self.filter_chain.apply_all(event, &ctx);
+        for mut event in events {
+            if self.config.input.normalize_coordinates {
+                event
+                    .event
+                    .normalize_coordinates(self.capture_ctx.canvas_dimensions());
+            }
+
+            // Only buffer events if capture is enabled
+            if !self.capture_enabled {
+                if let Some(target_app) = transition_target.as_deref() {
+                    self.buffer_transition_input_event(target_app, event);
+                }
+                continue;
+            }
+
+            // Only a started recording takes input
+            if !self.input_gate.admits(&event) {
+                continue;
+            }
+
+            let adjusted_event = self.adjust_input_event_timestamp(event);
 
-        self.buffer_input_event(adjusted_event);
+            self.buffer_input_event(adjusted_event);
+        }
 
         // Check if buffer should be flushed (e.g., every N events or time interval)
         if self.event_buffer.len() >= 10000 {
@@ -4097,14 +6676,29 @@ unintended app video."
     /// Flush the event buffer to disk (for periodic flushing during long segments)
     ///
     /// This drains the buffer to bound memory usage. Events are saved to numbered
-    /// partial files that will be combined with the main segment file at rotation.
+    /// partial files (or appended to the segment's single partial file when
+    /// `input.append_partial_flushes` is set) that will be combined with the main
+    /// segment file at rotation.
     async fn flush_event_buffer(&mut self) -> Result<()> {
         if self.event_buffer.is_empty() {
             return Ok(());
         }
 
+        if self.config.input.append_partial_flushes {
+            let segment_id = self.recording_segment_id();
+            let flush_path = self.output_dir.join(partial_append_file_name(&segment_id));
+            let events = self.drain_event_buffer();
+            append_partial_batch(&flush_path, &events).await?;
+            debug!(
+                "Partial flush: appended {} events to {:?} (buffer cleared)",
+                events.len(),
+                flush_path
+            );
+            return Ok(());
+        }
+
         // Generate a unique partial file name using timestamp to allow multiple flushes
-        let segment_id = self.current_segment_id();
+        let segment_id = self.recording_segment_id();
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_millis())
@@ -4117,7 +6711,7 @@ unintended app video."
         // Drain the buffer to bound memory usage
         let events = self.drain_event_buffer();
         let event_count = events.len();
-        let bytes = rmp_serde::to_vec(&events)?;
+        let bytes = encode_input_log(&events)?;
         tokio::fs::write(&flush_path, bytes).await?;
 
         debug!(
@@ -4129,6 +6723,235 @@ unintended app video."
     }
 }
 
+/// Name of a segment's append-mode partial flush file (`input.append_partial_flushes`).
+fn partial_append_file_name(segment_id: &str) -> String {
+    format!("input_{}_partial.batches", segment_id)
+}
+
+/// Append one flush to an append-mode partial file as a length-prefixed batch: a u32
+/// little-endian byte count followed by the msgpack-encoded events.
+async fn append_partial_batch(path: &Path, events: &[InputEvent]) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let bytes = rmp_serde::to_vec(events)?;
+    let mut record = Vec::with_capacity(4 + bytes.len());
+    record.extend_from_slice(&u32::try_from(bytes.len())?.to_le_bytes());
+    record.extend_from_slice(&bytes);
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&record).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// Decode every batch of an append-mode partial file, in write order. A truncated final
+/// batch (the agent died mid-append) is dropped; the batches before it are kept.
+fn decode_partial_batches(bytes: &[u8]) -> Result<Vec<InputEvent>> {
+    let mut events = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let Some((len, body)) = rest.split_first_chunk::<4>() else {
+            warn!("Dropping truncated batch header in partial file");
+            break;
+        };
+        let len = u32::from_le_bytes(*len) as usize;
+        let Some(batch) = body.get(..len) else {
+            warn!("Dropping truncated batch in partial file");
+            break;
+        };
+        events.extend(rmp_serde::from_slice::<Vec<InputEvent>>(batch)?);
+        rest = &body[len..];
+    }
+    Ok(events)
+}
+
+/// Most partial flush files read and parsed at once while a segment is finalized.
+const PARTIAL_READ_CONCURRENCY: usize = 4;
+
+/// Read and parse a segment's partial flush files on blocking threads, at most
+/// `PARTIAL_READ_CONCURRENCY` at a time, so many large partials don't stretch the
+/// rotation pause. Events come back concatenated in `paths` order; files that can't be
+/// read or parsed are logged and skipped.
+async fn read_partial_files(paths: &[PathBuf], append_name: &str) -> Vec<InputEvent> {
+    let mut per_file: Vec<Vec<InputEvent>> =
+        std::iter::repeat_with(Vec::new).take(paths.len()).collect();
+    let mut store = |joined: Result<(usize, Vec<InputEvent>), tokio::task::JoinError>| match joined
+    {
+        Ok((index, events)) => per_file[index] = events,
+        Err(e) => warn!("Partial file read task failed: {}", e),
+    };
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, path) in paths.iter().enumerate() {
+        if tasks.len() >= PARTIAL_READ_CONCURRENCY {
+            if let Some(joined) = tasks.join_next().await {
+                store(joined);
+            }
+        }
+        let path = path.clone();
+        let is_append_file = path.file_name() == Some(std::ffi::OsStr::new(append_name));
+        tasks.spawn_blocking(move || (index, read_partial_file(&path, is_append_file)));
+    }
+    while let Some(joined) = tasks.join_next().await {
+        store(joined);
+    }
+
+    per_file.into_iter().flatten().collect()
+}
+
+/// Events from one partial flush file in either layout, or none if it can't be read.
+fn read_partial_file(path: &Path, is_append_file: bool) -> Vec<InputEvent> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read partial file {:?}: {}", path, e);
+            return Vec::new();
+        }
+    };
+    let parsed = if is_append_file {
+        decode_partial_batches(&bytes)
+    } else {
+        decode_input_log(&bytes)
+    };
+    match parsed {
+        Ok(events) => {
+            debug!(
+                "Loaded {} events from partial file {:?}",
+                events.len(),
+                path
+            );
+            events
+        }
+        Err(e) => {
+            warn!("Failed to parse partial file {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Partial flushes younger than this may belong to a segment that is still being recorded
+/// (or whose rotation is running), so the startup sweep leaves them alone.
+const ORPHANED_PARTIAL_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Segment id of a partial flush file in either layout (`input_<id>_partial_<ms>.msgpack`
+/// or `input_<id>_partial.batches`), and whether it is the append-mode one.
+fn partial_flush_segment_id(file_name: &str) -> Option<(&str, bool)> {
+    let rest = file_name.strip_prefix("input_")?;
+    if let Some(segment_id) = rest.strip_suffix("_partial.batches") {
+        return Some((segment_id, true));
+    }
+    let (segment_id, timestamp) = rest.rsplit_once("_partial_")?;
+    let timestamp = timestamp.strip_suffix(".msgpack")?;
+    timestamp
+        .parse::<u128>()
+        .is_ok()
+        .then_some((segment_id, false))
+}
+
+/// Startup sweep for partial flushes no rotation will ever collect (the agent died
+/// mid-segment). A segment's partials are only touched once every one of them is older
+/// than `cutoff` and the segment isn't part of `active_session`. `Recover` assembles them,
+/// in flush order, into the segment's `input_<id>.msgpack` (unless one already exists) so
+/// `reupload` can pick the segment up; `Delete` just removes them. Returns how many
+/// segments were recovered and how many partial files were removed.
+fn sweep_orphaned_partials(
+    dir: &Path,
+    cutoff: std::time::SystemTime,
+    active_session: Option<&str>,
+    policy: OrphanedPartialsPolicy,
+) -> (usize, usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    let active_prefix = active_session.map(|id| format!("{}_seg", id));
+    let mut segments: std::collections::BTreeMap<String, Vec<(PathBuf, bool)>> =
+        std::collections::BTreeMap::new();
+    let mut recent: HashSet<String> = HashSet::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some((segment_id, is_append)) = partial_flush_segment_id(&file_name.to_string_lossy())
+            .map(|(id, is_append)| (id.to_string(), is_append))
+        else {
+            continue;
+        };
+        if active_prefix
+            .as_ref()
+            .is_some_and(|prefix| segment_id.starts_with(prefix.as_str()))
+        {
+            continue;
+        }
+        let modified = entry.metadata().and_then(|m| m.modified());
+        if !modified.is_ok_and(|modified| modified < cutoff) {
+            recent.insert(segment_id.clone());
+        }
+        segments
+            .entry(segment_id)
+            .or_default()
+            .push((entry.path(), is_append));
+    }
+
+    let mut recovered = 0;
+    let mut deleted = 0;
+    for (segment_id, mut files) in segments {
+        if recent.contains(&segment_id) {
+            continue;
+        }
+        // Timestamped names sort in flush order
+        files.sort();
+
+        let input_path = dir.join(format!("input_{}.msgpack", segment_id));
+        if policy == OrphanedPartialsPolicy::Recover && !input_path.exists() {
+            let mut events: Vec<InputEvent> = Vec::new();
+            for (path, is_append) in &files {
+                let parsed = std::fs::read(path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|bytes| {
+                        if *is_append {
+                            decode_partial_batches(&bytes)
+                        } else {
+                            decode_input_log(&bytes)
+                        }
+                    });
+                match parsed {
+                    Ok(batch) => events.extend(batch),
+                    Err(e) => warn!("Failed to read orphaned partial file {:?}: {}", path, e),
+                }
+            }
+            if !events.is_empty() {
+                let written = encode_input_log(&events)
+                    .and_then(|bytes| Ok(std::fs::write(&input_path, bytes)?));
+                if let Err(e) = written {
+                    // Keep the partials; the next startup tries again.
+                    warn!(
+                        "Failed to recover orphaned partials for segment {}: {}",
+                        segment_id, e
+                    );
+                    continue;
+                }
+                info!(
+                    "Recovered {} events for orphaned segment {} into {:?}",
+                    events.len(),
+                    segment_id,
+                    input_path
+                );
+                recovered += 1;
+            }
+        }
+
+        for (path, _) in files {
+            match std::fs::remove_file(&path) {
+                Ok(()) => deleted += 1,
+                Err(e) => warn!("Failed to delete orphaned partial file {:?}: {}", path, e),
+            }
+        }
+    }
+    (recovered, deleted)
+}
+
 /// Create command and status channels for the engine
 pub fn create_engine_channels() -> (
     mpsc::Sender<EngineCommand>,
@@ -4226,26 +7049,237 @@ mod tests {
                 events: vec![],
                 start_time_us: 0,
                 end_time_us: 1000,
+                timebase: Timebase::default(),
+                video_start_ns: None,
+                video_offset_us: None,
+                stats: SegmentStats::default(),
             },
             input_path,
         }
     }
 
-    #[test]
-    fn purge_upload_buffer_deletes_files() {
-        let dir = test_dir("purge");
-        let seg1 = make_test_segment(&dir, "seg1");
-        let seg2 = make_test_segment(&dir, "seg2");
+    #[tokio::test]
+    async fn append_mode_flushes_share_one_partial_file() {
+        let dir = test_dir("append-partial");
+        let path = dir.join(partial_append_file_name("seg"));
+        let _ = std::fs::remove_file(&path);
 
-        let video1 = seg1.chunk.video_path.clone().unwrap();
-        let input1 = seg1.input_path.clone();
-        let video2 = seg2.chunk.video_path.clone().unwrap();
-        let input2 = seg2.input_path.clone();
+        const FLUSHES: u64 = 5;
+        for flush in 0..FLUSHES {
+            let events: Vec<InputEvent> = (0..3)
+                .map(|i| {
+                    InputEvent::now(
+                        flush * 10 + i,
+                        EventType::ContextChanged(ContextEvent {
+                            app_id: format!("app-{}", flush),
+                        }),
+                    )
+                })
+                .collect();
+            append_partial_batch(&path, &events).await.unwrap();
+        }
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let events = decode_partial_batches(&bytes).unwrap();
+        assert_eq!(events.len(), 3 * FLUSHES as usize);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp_us < w[1].timestamp_us));
+
+        // A crash mid-append leaves a partial record; the complete batches survive.
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(b"cut");
+        assert_eq!(decode_partial_batches(&bytes).unwrap().len(), events.len());
 
-        assert!(video1.exists());
-        assert!(input1.exists());
-        assert!(video2.exists());
-        assert!(input2.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn parallel_partial_reads_match_sequential_order() {
+        let dir = test_dir("parallel-partials");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Flushes interleave in time, so the final order depends on the sort, not on
+        // which read finishes first.
+        let event = |timestamp_us: u64, file: usize| {
+            InputEvent::now(
+                timestamp_us,
+                EventType::ContextChanged(ContextEvent {
+                    app_id: format!("file-{}", file),
+                }),
+            )
+        };
+        let mut paths = Vec::new();
+        for file in 0..(PARTIAL_READ_CONCURRENCY * 2 + 1) {
+            let events: Vec<InputEvent> = (0..50)
+                .map(|i| event(i * 100 + file as u64, file))
+                .collect();
+            let path = dir.join(format!("input_seg_partial_{}.msgpack", 1000 + file));
+            std::fs::write(&path, encode_input_log(&events).unwrap()).unwrap();
+            paths.push(path);
+        }
+        let appended = dir.join(partial_append_file_name("seg"));
+        append_partial_batch(&appended, &[event(42, 99)])
+            .await
+            .unwrap();
+        paths.push(appended);
+        let corrupt = dir.join("input_seg_partial_9999.msgpack");
+        std::fs::write(&corrupt, b"not msgpack").unwrap();
+        paths.push(corrupt);
+        paths.sort();
+
+        let append_name = partial_append_file_name("seg");
+        let mut sequential: Vec<InputEvent> = paths
+            .iter()
+            .flat_map(|path| {
+                read_partial_file(path, path.file_name().unwrap() == append_name.as_str())
+            })
+            .collect();
+        let mut parallel = read_partial_files(&paths, &append_name).await;
+        sort_segment_events(&mut sequential);
+        sort_segment_events(&mut parallel);
+
+        let key = |e: &InputEvent| match &e.event {
+            EventType::ContextChanged(c) => (e.timestamp_us, c.app_id.clone()),
+            _ => unreachable!(),
+        };
+        assert_eq!(parallel.len(), (PARTIAL_READ_CONCURRENCY * 2 + 1) * 50 + 1);
+        assert_eq!(
+            parallel.iter().map(key).collect::<Vec<_>>(),
+            sequential.iter().map(key).collect::<Vec<_>>()
+        );
+        assert!(parallel
+            .windows(2)
+            .all(|w| w[0].timestamp_us <= w[1].timestamp_us));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn startup_sweep_handles_only_old_orphaned_partials() {
+        let dir = test_dir("orphaned-partials");
+        let two_hours_ago = std::time::SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        let cutoff = std::time::SystemTime::now() - ORPHANED_PARTIAL_MIN_AGE;
+        let events = |n: u64| -> Vec<InputEvent> {
+            (0..n)
+                .map(|i| {
+                    InputEvent::now(
+                        i,
+                        EventType::ContextChanged(ContextEvent {
+                            app_id: "app".to_string(),
+                        }),
+                    )
+                })
+                .collect()
+        };
+        let write = |name: &str, n: u64, old: bool| {
+            let path = dir.join(name);
+            std::fs::write(&path, rmp_serde::to_vec(&events(n)).unwrap()).unwrap();
+            if old {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(two_hours_ago)
+                    .unwrap();
+            }
+            path
+        };
+
+        let old_a = write("input_crashed_seg0000_partial_1000.msgpack", 2, true);
+        let old_b = write("input_crashed_seg0000_partial_2000.msgpack", 3, true);
+        let old_appended = dir.join(partial_append_file_name("crashed_seg0001"));
+        append_partial_batch(&old_appended, &events(4))
+            .await
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old_appended)
+            .unwrap()
+            .set_modified(two_hours_ago)
+            .unwrap();
+        let recent = write("input_other_seg0003_partial_5000.msgpack", 1, false);
+        let active = write("input_live_seg0002_partial_3000.msgpack", 1, true);
+
+        // Delete: the old orphans go; recent partials and the live session's stay.
+        let (recovered, deleted) =
+            sweep_orphaned_partials(&dir, cutoff, Some("live"), OrphanedPartialsPolicy::Delete);
+        assert_eq!((recovered, deleted), (0, 3));
+        assert!(!old_a.exists() && !old_b.exists() && !old_appended.exists());
+        assert!(recent.exists() && active.exists());
+        assert!(!dir.join("input_crashed_seg0000.msgpack").exists());
+
+        // Recover: both layouts are assembled into the segment's input log.
+        write("input_crashed_seg0000_partial_1000.msgpack", 2, true);
+        write("input_crashed_seg0000_partial_2000.msgpack", 3, true);
+        let (recovered, deleted) =
+            sweep_orphaned_partials(&dir, cutoff, None, OrphanedPartialsPolicy::Recover);
+        assert_eq!((recovered, deleted), (2, 3));
+        let assembled =
+            decode_input_log(&std::fs::read(dir.join("input_crashed_seg0000.msgpack")).unwrap())
+                .unwrap();
+        assert_eq!(assembled.len(), 5);
+        assert!(dir.join("input_live_seg0002.msgpack").exists());
+        assert!(recent.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn chunk_timebase_comes_from_recording_session_and_roundtrips() {
+        let session = RecordingSession {
+            session_id: "session".to_string(),
+            output_path: PathBuf::from("session_seg0000.mp4"),
+            start_time_ns: 5_000_000_000,
+        };
+        let (timebase, video_start_ns) = Timebase::for_recording_start(Some(session.start_time_ns));
+        let mut segment = make_test_segment(&test_dir("timebase"), "timebase_seg0000");
+        segment.chunk.timebase = timebase;
+        segment.chunk.video_start_ns = video_start_ns;
+        assert_eq!(segment.chunk.timebase, Timebase::VideoRelativeUs);
+        assert_eq!(segment.chunk.video_start_ns, Some(5_000_000_000));
+
+        let json = serde_json::to_value(&segment.chunk).unwrap();
+        assert_eq!(json["timebase"], "video_relative_us");
+        assert_eq!(json["video_start_ns"], 5_000_000_000u64);
+        let decoded: CompletedChunk = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.timebase, Timebase::VideoRelativeUs);
+        assert_eq!(decoded.video_start_ns, Some(5_000_000_000));
+        let decoded: CompletedChunk =
+            rmp_serde::from_slice(&rmp_serde::to_vec_named(&segment.chunk).unwrap()).unwrap();
+        assert_eq!(decoded.video_start_ns, Some(5_000_000_000));
+
+        // No recording clock: raw backend time, no video origin.
+        assert_eq!(
+            Timebase::for_recording_start(None),
+            (Timebase::BackendUs, None)
+        );
+        // Chunks serialized before these fields existed read as video-relative.
+        let legacy: CompletedChunk = serde_json::from_str(
+            r#"{"session_id":"s","chunk_id":"c","events":[],"start_time_us":0,"end_time_us":0}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.timebase, Timebase::VideoRelativeUs);
+        assert_eq!(legacy.video_start_ns, None);
+    }
+
+    #[test]
+    fn purge_upload_buffer_deletes_files() {
+        let dir = test_dir("purge");
+        let seg1 = make_test_segment(&dir, "seg1");
+        let seg2 = make_test_segment(&dir, "seg2");
+
+        let video1 = seg1.chunk.video_path.clone().unwrap();
+        let input1 = seg1.input_path.clone();
+        let video2 = seg2.chunk.video_path.clone().unwrap();
+        let input2 = seg2.input_path.clone();
+
+        assert!(video1.exists());
+        assert!(input1.exists());
+        assert!(video2.exists());
+        assert!(input2.exists());
 
         let mut buffer: std::collections::VecDeque<(Instant, CompletedSegment)> =
             std::collections::VecDeque::new();
@@ -4326,4 +7360,1159 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn recording_health_from_synthetic_inputs() {
+        let healthy = compute_recording_health(
+            Some((1920, 1080)),
+            Some(1_000),
+            Some(2_000),
+            false,
+            false,
+            false,
+        );
+        assert!(healthy.is_healthy());
+        assert!(healthy.problems().is_empty());
+
+        // First check of a recording has no baseline yet; frames count as advancing.
+        assert!(
+            compute_recording_health(Some((1920, 1080)), None, Some(5), false, false, false)
+                .frames_advancing
+        );
+
+        let degraded =
+            compute_recording_health(Some((0, 0)), Some(2_000), Some(2_000), true, true, true);
+        assert_eq!(
+            degraded,
+            RecordingHealth {
+                sources_active: false,
+                frames_advancing: false,
+                disk_ok: false,
+                uploads_keeping_up: false,
+                audio_ok: false,
+            }
+        );
+        assert_eq!(
+            degraded.problems(),
+            vec![
+                "no active capture source",
+                "video frames not advancing",
+                "low disk space",
+                "uploads falling behind",
+                "microphone level stuck",
+            ]
+        );
+
+        // No source and no frame clock (OBS not producing video).
+        let no_video = compute_recording_health(None, Some(2_000), None, false, false, false);
+        assert!(!no_video.sources_active && !no_video.frames_advancing);
+        assert!(no_video.disk_ok && no_video.uploads_keeping_up && no_video.audio_ok);
+    }
+
+    #[test]
+    fn upload_stall_notifies_once_per_episode() {
+        let start = Instant::now();
+        let mut detector = UploadStallDetector::new(start);
+
+        // Healthy: a short queue shortly after start is not a stall.
+        assert!(!detector.check(2, start + Duration::from_secs(60)));
+        // Queue depth crossing the threshold fires once...
+        assert!(detector.check(
+            UPLOAD_STALL_QUEUE_THRESHOLD,
+            start + Duration::from_secs(120)
+        ));
+        // ...and stays quiet while the episode continues.
+        assert!(!detector.check(
+            UPLOAD_STALL_QUEUE_THRESHOLD + 5,
+            start + Duration::from_secs(180)
+        ));
+        assert!(!detector.check(1, start + UPLOAD_STALL_TIMEOUT * 2));
+
+        // A successful upload ends the episode; a long drought with segments waiting
+        // starts a new one even below the queue threshold.
+        let success = start + Duration::from_secs(600);
+        detector.record_success(success);
+        assert!(!detector.check(1, success + Duration::from_secs(60)));
+        assert!(!detector.check(0, success + UPLOAD_STALL_TIMEOUT));
+        assert!(detector.check(1, success + UPLOAD_STALL_TIMEOUT));
+        assert!(!detector.check(1, success + UPLOAD_STALL_TIMEOUT * 2));
+    }
+
+    #[test]
+    fn revoked_input_permission_detected_on_next_check() {
+        static GRANTED: AtomicBool = AtomicBool::new(true);
+        let start = Instant::now();
+        let mut watch = InputPermissionWatch::new(|| GRANTED.load(AtomicOrdering::SeqCst), start);
+
+        // Granted: checks pass.
+        assert!(!watch.revoked(start + INPUT_PERMISSION_CHECK_INTERVAL));
+
+        // Revoked between checks: not probed again until the interval elapses...
+        GRANTED.store(false, AtomicOrdering::SeqCst);
+        let checked = start + INPUT_PERMISSION_CHECK_INTERVAL;
+        assert!(!watch.revoked(checked + Duration::from_secs(1)));
+        // ...then reported.
+        assert!(watch.revoked(checked + INPUT_PERMISSION_CHECK_INTERVAL));
+    }
+
+    #[test]
+    fn upload_circuit_opens_after_failures_and_recovers() {
+        let start = Instant::now();
+        let cooldown = Duration::from_secs(300);
+        let open = Arc::new(AtomicBool::new(false));
+        let mut breaker = UploadCircuitBreaker::new(3, cooldown, open.clone());
+
+        // Failures below the threshold keep uploads flowing.
+        for _ in 0..2 {
+            assert!(breaker.allow(start));
+            breaker.record_failure(start);
+        }
+        assert!(breaker.allow(start));
+        assert!(!open.load(AtomicOrdering::SeqCst));
+
+        // The third consecutive failure opens the circuit for the cooldown.
+        breaker.record_failure(start);
+        assert!(open.load(AtomicOrdering::SeqCst));
+        assert!(!breaker.allow(start + Duration::from_secs(60)));
+        assert_eq!(breaker.retry_at(start), start + cooldown);
+
+        // After the cooldown exactly one probe goes out; a failed probe re-opens it.
+        let half_open = start + cooldown;
+        assert!(breaker.allow(half_open));
+        assert!(!breaker.allow(half_open));
+        breaker.record_failure(half_open);
+        assert!(!breaker.allow(half_open + Duration::from_secs(1)));
+
+        // A successful probe closes it again.
+        let recovered = half_open + cooldown;
+        assert!(breaker.allow(recovered));
+        breaker.record_success();
+        assert!(!open.load(AtomicOrdering::SeqCst));
+        assert!(breaker.allow(recovered));
+        assert!(breaker.allow(recovered));
+
+        // A probe that never ran (uploads paused meanwhile) hands the probe to the next upload.
+        breaker.record_failure(recovered);
+        breaker.record_failure(recovered);
+        breaker.record_failure(recovered);
+        let reprobe = recovered + cooldown;
+        assert!(breaker.allow(reprobe));
+        assert!(!breaker.allow(reprobe));
+        breaker.probe_cancelled(reprobe);
+        assert!(open.load(AtomicOrdering::SeqCst));
+        assert!(breaker.allow(reprobe));
+        assert!(!breaker.allow(reprobe));
+        // Outside half-open it changes nothing.
+        breaker.record_success();
+        breaker.probe_cancelled(reprobe);
+        assert!(breaker.allow(reprobe));
+        assert!(!open.load(AtomicOrdering::SeqCst));
+
+        // Threshold 0 never opens.
+        let mut disabled = UploadCircuitBreaker::new(0, cooldown, open.clone());
+        for _ in 0..20 {
+            disabled.record_failure(start);
+        }
+        assert!(disabled.allow(start));
+        assert!(!open.load(AtomicOrdering::SeqCst));
+    }
+
+    #[test]
+    fn display_scale_change_logged_once_per_transition() {
+        let mut tracker = DisplayScaleTracker::default();
+        // The segment-start scale is already in the metadata event.
+        tracker.reset(Some(2.0));
+
+        let polled = [2.0, 2.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0];
+        let changes: Vec<f64> = polled.iter().filter_map(|&s| tracker.observe(s)).collect();
+        assert_eq!(changes, vec![1.0, 1.5, 2.0]);
+
+        // Unknown at segment start: the first successful query is reported.
+        tracker.reset(None);
+        assert_eq!(tracker.observe(1.0), Some(1.0));
+        assert_eq!(tracker.observe(1.0), None);
+    }
+
+    #[test]
+    fn window_geometry_logged_only_on_change() {
+        let frame = |x, y, width, height| WindowGeometryEvent {
+            x,
+            y,
+            width,
+            height,
+        };
+        let mut tracker = WindowGeometryTracker::default();
+
+        // The first poll of a segment is its initial geometry.
+        assert_eq!(
+            tracker.observe(frame(0, 25, 1280, 800)),
+            Some(frame(0, 25, 1280, 800))
+        );
+
+        // Unchanged polls log nothing; a move and a resize each log once.
+        let polled = [
+            frame(0, 25, 1280, 800),
+            frame(0, 25, 1280, 800),
+            frame(100, 25, 1280, 800),
+            frame(100, 25, 1280, 800),
+            frame(100, 25, 1440, 900),
+            frame(100, 25, 1440, 900),
+        ];
+        let changes: Vec<_> = polled.iter().filter_map(|&f| tracker.observe(f)).collect();
+        assert_eq!(
+            changes,
+            vec![frame(100, 25, 1280, 800), frame(100, 25, 1440, 900)]
+        );
+
+        // After a reset (new segment, focus back from another app) the same frame is
+        // reported again.
+        tracker.reset();
+        assert_eq!(
+            tracker.observe(frame(100, 25, 1440, 900)),
+            Some(frame(100, 25, 1440, 900))
+        );
+
+        // Lookups run one at a time, and one requested before a reset is dropped.
+        let stale = tracker.start_lookup().unwrap();
+        assert_eq!(tracker.start_lookup(), None);
+        tracker.reset();
+        assert_eq!(
+            tracker.finish_lookup(stale, Some(frame(0, 0, 800, 600))),
+            None
+        );
+        let current = tracker.start_lookup().unwrap();
+        assert_eq!(
+            tracker.finish_lookup(current, Some(frame(0, 0, 800, 600))),
+            Some(frame(0, 0, 800, 600))
+        );
+        let current = tracker.start_lookup().unwrap();
+        assert_eq!(tracker.finish_lookup(current, None), None);
+        assert!(tracker.start_lookup().is_some());
+    }
+
+    #[test]
+    fn window_title_read_off_loop_once_per_refresh() {
+        let start = Instant::now();
+        let mut cache = WindowTitleCache::default();
+        let mut requested = Vec::new();
+
+        // Nothing is known until the first read is back, and only one read runs at a time.
+        assert_eq!(cache.get(7, start, |pid| requested.push(pid)), None);
+        assert_eq!(cache.get(7, start, |pid| requested.push(pid)), None);
+        assert_eq!(requested, vec![7]);
+        cache.store(7, start, Some("Inbox".to_string()));
+
+        // Fresh titles are answered from the cache.
+        let soon = start + WINDOW_TITLE_REFRESH / 2;
+        assert_eq!(cache.get(7, soon, |pid| requested.push(pid)), Some("Inbox"));
+        assert_eq!(requested, vec![7]);
+
+        // A stale title is still used while its refresh runs.
+        let later = start + WINDOW_TITLE_REFRESH;
+        assert_eq!(
+            cache.get(7, later, |pid| requested.push(pid)),
+            Some("Inbox")
+        );
+        assert_eq!(requested, vec![7, 7]);
+        cache.store(7, later, Some("Drafts".to_string()));
+
+        // Another process never gets the last one's title.
+        assert_eq!(cache.get(9, later, |pid| requested.push(pid)), None);
+        assert_eq!(requested, vec![7, 7, 9]);
+    }
+
+    #[test]
+    fn flapping_display_recovers_once_after_stabilizing() {
+        let returned = || DisplayChangeEvent::OriginalReturned {
+            display_id: 1,
+            uuid: "uuid-1".to_string(),
+            display_name: "Built-in".to_string(),
+        };
+        let mut debounce = DisplayRecoveryDebounce::new(Duration::from_millis(2000));
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+
+        // Display drops and returns three times within the window (e.g. a KVM switch
+        // bouncing); each disconnect cancels, each return restarts the wait.
+        let mut recoveries = 0;
+        for (ms, event) in [
+            (0, returned()),
+            (500, DisplayChangeEvent::AllDisconnected),
+            (800, returned()),
+            (1500, DisplayChangeEvent::AllDisconnected),
+            (1900, returned()),
+        ] {
+            if matches!(
+                debounce.on_event(event, at(ms)),
+                Some(DisplayChangeEvent::OriginalReturned { .. })
+            ) {
+                recoveries += 1;
+            }
+            assert!(debounce.poll(at(ms + 100)).is_none());
+        }
+        assert_eq!(recoveries, 0);
+        assert!(debounce.is_pending());
+
+        // Still settling just before the deadline of the last return, due right at it.
+        assert!(debounce.poll(at(3899)).is_none());
+        assert!(matches!(
+            debounce.poll(at(3900)),
+            Some(DisplayChangeEvent::OriginalReturned { display_id: 1, .. })
+        ));
+        assert!(debounce.poll(at(10_000)).is_none());
+        assert!(!debounce.is_pending());
+
+        // A zero delay recovers immediately, as before.
+        let mut immediate = DisplayRecoveryDebounce::new(Duration::ZERO);
+        assert!(immediate.on_event(returned(), t0).is_some());
+        assert!(!immediate.is_pending());
+    }
+
+    #[test]
+    fn marker_sorts_into_timeline_among_input_events() {
+        use crate::data::KeyEvent;
+
+        let key = |timestamp_us, code| {
+            InputEvent::now(
+                timestamp_us,
+                EventType::KeyPress(KeyEvent {
+                    code,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                }),
+            )
+        };
+        let marker = |timestamp_us| {
+            InputEvent::now(
+                timestamp_us,
+                EventType::Marker(MarkerEvent {
+                    label: "start of task 3".to_string(),
+                }),
+            )
+        };
+        let is_marker = |e: &InputEvent| matches!(e.event, EventType::Marker(_));
+
+        // A partial flush already on disk, then the live buffer: the marker was added
+        // after a later keystroke was buffered, and on the same frame as another.
+        let mut events = vec![key(100, 1), key(500, 2)];
+        events.extend([key(400, 3), marker(300), key(300, 4), marker(400)]);
+        sort_segment_events(&mut events);
+
+        let timeline: Vec<(u64, bool)> = events
+            .iter()
+            .map(|e| (e.timestamp_us, is_marker(e)))
+            .collect();
+        assert_eq!(
+            timeline,
+            vec![
+                (100, false),
+                (300, true),
+                (300, false),
+                (400, false),
+                (400, true),
+                (500, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn idle_pause_fires_after_timeout_and_rearms_on_activity() {
+        let idle = Duration::from_secs(120);
+        let start = Instant::now();
+
+        // Recording with no input: the pause is due exactly one timeout after the last action.
+        let deadline = idle_pause_deadline(start, idle, true, false).unwrap();
+        assert_eq!(deadline, start + idle);
+        assert!(start + Duration::from_secs(119) < deadline);
+
+        // Once idle-paused, the timer is disarmed until capture resumes.
+        assert_eq!(idle_pause_deadline(start, idle, true, true), None);
+
+        // Input resumes capture and resets the last action; the next pause is a full timeout later.
+        let resumed_at = start + Duration::from_secs(600);
+        assert_eq!(
+            idle_pause_deadline(resumed_at, idle, true, false),
+            Some(resumed_at + idle)
+        );
+
+        // Disabled, or nothing recording: never pause.
+        assert_eq!(
+            idle_pause_deadline(start, Duration::ZERO, true, false),
+            None
+        );
+        assert_eq!(idle_pause_deadline(start, idle, false, false), None);
+    }
+
+    #[tokio::test]
+    async fn shutdown_drain_waits_for_queued_uploads() {
+        use crate::upload::test_support::{mock_uploader, spawn_mock_endpoint};
+
+        let dir = test_dir("drain");
+        let (base, log) = spawn_mock_endpoint(Duration::from_millis(200)).await;
+        let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        SyncEngine::spawn_upload_task(
+            upload_rx,
+            mock_uploader(&base),
+            false,
+            2,
+            LocalRetention::default(),
+            dir.clone(),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            UploadCircuitBreaker::new(0, Duration::ZERO, Arc::new(AtomicBool::new(false))),
+            false,
+            Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+            broadcast::channel(16).0,
+            Webhooks::default(),
+        );
+        for name in ["drain_seg0000", "drain_seg0001"] {
+            let segment = make_test_segment(&dir, name);
+            upload_tx.send(UploadMessage::Segment(segment)).unwrap();
+        }
+
+        let mut progress = Vec::new();
+        let remaining =
+            drain_upload_queue(&upload_tx, Duration::from_secs(10), |n| progress.push(n)).await;
+
+        assert_eq!(remaining, 0);
+        assert_eq!(progress.first(), Some(&2));
+        assert_eq!(progress.last(), Some(&0));
+        // Both segments fully uploaded (video + keylog) before the drain returned.
+        let puts = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, path, _)| method == "PUT" && path.starts_with("/put/"))
+            .count();
+        assert_eq!(puts, 4);
+    }
+
+    #[tokio::test]
+    async fn invalid_segment_is_quarantined_not_uploaded() {
+        use crate::upload::test_support::{mock_uploader, spawn_mock_endpoint};
+
+        let dir = test_dir("validate");
+        let (base, log) = spawn_mock_endpoint(Duration::ZERO).await;
+        let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        SyncEngine::spawn_upload_task(
+            upload_rx,
+            mock_uploader(&base),
+            false,
+            1,
+            LocalRetention::default(),
+            dir.clone(),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            UploadCircuitBreaker::new(0, Duration::ZERO, Arc::new(AtomicBool::new(false))),
+            true,
+            Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+            broadcast::channel(16).0,
+            Webhooks::default(),
+        );
+        let input = rmp_serde::to_vec(&Vec::<InputEvent>::new()).unwrap();
+        let mut mp4 = vec![0, 0, 0, 0x18];
+        mp4.extend_from_slice(b"ftypmp42");
+        for (name, video) in [
+            ("validate_seg0000", &b""[..]),
+            ("validate_seg0001", &mp4[..]),
+        ] {
+            let segment = make_test_segment(&dir, name);
+            std::fs::write(segment.chunk.video_path.as_ref().unwrap(), video).unwrap();
+            std::fs::write(&segment.input_path, &input).unwrap();
+            upload_tx.send(UploadMessage::Segment(segment)).unwrap();
+        }
+
+        let remaining = drain_upload_queue(&upload_tx, Duration::from_secs(10), |_| {}).await;
+        assert_eq!(remaining, 0);
+
+        // The zero-byte video's segment never reached the endpoint; the valid one did.
+        let log = log.lock().unwrap();
+        assert!(!log
+            .iter()
+            .any(|(_, path, _)| path.contains("validate_seg0000")));
+        assert!(log
+            .iter()
+            .any(|(method, path, _)| method == "PUT" && path.contains("validate_seg0001")));
+        let invalid = dir.join(INVALID_SEGMENTS_DIR);
+        assert!(invalid.join("validate_seg0000.mp4").exists());
+        assert!(invalid.join("validate_seg0000.msgpack").exists());
+        assert!(!dir.join("validate_seg0000.mp4").exists());
+        assert!(dir.join("validate_seg0001.mp4").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn permanent_upload_failure_is_not_requeued() {
+        use crate::upload::test_support::{mock_uploader, spawn_failing_endpoint};
+
+        for (status, requeued) in [("403 Forbidden", false), ("503 Service Unavailable", true)] {
+            let dir = test_dir(&format!("rejected-{}", &status[..3]));
+            let base = spawn_failing_endpoint(status).await;
+            let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+            SyncEngine::spawn_upload_task(
+                upload_rx,
+                mock_uploader(&base),
+                false,
+                1,
+                LocalRetention::default(),
+                dir.clone(),
+                Arc::new(AtomicBool::new(false)),
+                Arc::new(AtomicBool::new(false)),
+                UploadCircuitBreaker::new(0, Duration::ZERO, Arc::new(AtomicBool::new(false))),
+                false,
+                Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+                broadcast::channel(16).0,
+                Webhooks::default(),
+            );
+            let segment = make_test_segment(&dir, "rejected_seg0000");
+            upload_tx.send(UploadMessage::Segment(segment)).unwrap();
+
+            // The drain ends as soon as nothing is pending; a re-queued segment sits out
+            // its retry backoff well past the timeout.
+            let remaining = drain_upload_queue(&upload_tx, Duration::from_secs(2), |_| {}).await;
+            assert_eq!(remaining, usize::from(requeued), "{}", status);
+            // Either way the recording itself is left on disk.
+            assert!(dir.join("rejected_seg0000.mp4").exists());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn uploads_run_in_parallel_up_to_max_concurrent() {
+        use crate::upload::test_support::{mock_uploader, spawn_counting_endpoint};
+
+        let dir = test_dir("concurrent");
+        let (base, peak) = spawn_counting_endpoint(Duration::from_millis(200)).await;
+        let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        SyncEngine::spawn_upload_task(
+            upload_rx,
+            mock_uploader(&base),
+            false,
+            2,
+            LocalRetention::default(),
+            dir.clone(),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            UploadCircuitBreaker::new(0, Duration::ZERO, Arc::new(AtomicBool::new(false))),
+            false,
+            Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+            broadcast::channel(16).0,
+            Webhooks::default(),
+        );
+        for name in ["par_seg0000", "par_seg0001", "par_seg0002", "par_seg0003"] {
+            let segment = make_test_segment(&dir, name);
+            upload_tx.send(UploadMessage::Segment(segment)).unwrap();
+        }
+
+        let remaining = drain_upload_queue(&upload_tx, Duration::from_secs(30), |_| {}).await;
+
+        assert_eq!(remaining, 0);
+        // Each segment's requests go one after another, so the requests the endpoint
+        // handles at once are the segments uploading at once: exactly the limit.
+        assert_eq!(peak.load(AtomicOrdering::SeqCst), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn paused_uploads_accumulate_and_drain_on_resume() {
+        use crate::upload::test_support::{mock_uploader, spawn_mock_endpoint};
+
+        let dir = test_dir("paused-uploads");
+        let (base, log) = spawn_mock_endpoint(Duration::ZERO).await;
+        let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        let paused = Arc::new(AtomicBool::new(true));
+        SyncEngine::spawn_upload_task(
+            upload_rx,
+            mock_uploader(&base),
+            true,
+            2,
+            LocalRetention::default(),
+            dir.clone(),
+            paused.clone(),
+            Arc::new(AtomicBool::new(false)),
+            UploadCircuitBreaker::new(0, Duration::ZERO, Arc::new(AtomicBool::new(false))),
+            false,
+            Arc::new(std::sync::Mutex::new(UploadThroughput::default())),
+            broadcast::channel(16).0,
+            Webhooks::default(),
+        );
+        let names = ["paused_seg0000", "paused_seg0001", "paused_seg0002"];
+        for name in names {
+            let segment = make_test_segment(&dir, name);
+            upload_tx.send(UploadMessage::Segment(segment)).unwrap();
+        }
+
+        // Paused: everything is held, nothing is sent, and despite delete_after_upload
+        // every file stays on disk.
+        let remaining = drain_upload_queue(&upload_tx, Duration::from_secs(2), |_| {}).await;
+        assert_eq!(remaining, names.len());
+        assert!(log.lock().unwrap().is_empty());
+        for name in names {
+            assert!(dir.join(format!("{}.mp4", name)).exists());
+            assert!(dir.join(format!("{}.msgpack", name)).exists());
+        }
+
+        // Resumed: the held segments upload and are then deleted.
+        paused.store(false, AtomicOrdering::SeqCst);
+        let remaining = drain_upload_queue(&upload_tx, Duration::from_secs(10), |_| {}).await;
+        assert_eq!(remaining, 0);
+        let puts = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, path, _)| method == "PUT" && path.starts_with("/put/"))
+            .count();
+        assert_eq!(puts, 2 * names.len());
+        for name in names {
+            assert!(!dir.join(format!("{}.mp4", name)).exists());
+            assert!(!dir.join(format!("{}.msgpack", name)).exists());
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn idle_session_ends_after_window_and_restarts_on_input() {
+        let window = Duration::from_secs(30 * 60);
+        let start = Instant::now();
+
+        // Recording with no input: the session ends one window after the last input, and an
+        // idle pause along the way doesn't hold it off.
+        let deadline = idle_session_end_deadline(start, window, true, false).unwrap();
+        assert_eq!(deadline, start + window);
+
+        // A user pause does.
+        assert_eq!(idle_session_end_deadline(start, window, true, true), None);
+
+        // Once ended there is no session to end until input starts a new one, which is
+        // then given a full window from that input.
+        assert_eq!(idle_session_end_deadline(start, window, false, false), None);
+        let input_at = deadline + Duration::from_secs(3600);
+        assert_eq!(
+            idle_session_end_deadline(input_at, window, true, false),
+            Some(input_at + window)
+        );
+
+        // Disabled: never ends.
+        assert_eq!(
+            idle_session_end_deadline(start, Duration::ZERO, true, false),
+            None
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_session_ends_and_next_input_starts_a_new_one() {
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut idle = IdleSessionEnd::new(window);
+        let mut last_input = start;
+        let mut recording = true;
+
+        // Input at 30s, then nothing until 200s.
+        let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for at in [30, 200] {
+                tokio::time::sleep_until(start + Duration::from_secs(at)).await;
+                let _ = input_tx.send(());
+            }
+            std::future::pending::<()>().await
+        });
+
+        // The engine loop's input and idle-session-end arms
+        let mut log = Vec::new();
+        while log.len() < 4 {
+            tokio::select! {
+                Some(()) = input_rx.recv() => {
+                    last_input = Instant::now();
+                    if idle.take_restart() {
+                        recording = true;
+                        log.push(("started", last_input - start));
+                    }
+                    log.push(("input", last_input - start));
+                }
+                _ = async {
+                    match idle.deadline(last_input, recording, false) {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending::<()>().await,
+                    }
+                } => {
+                    recording = false;
+                    idle.end();
+                    log.push(("ended", Instant::now() - start));
+                }
+            }
+        }
+
+        let secs = Duration::from_secs;
+        assert_eq!(
+            log,
+            vec![
+                ("input", secs(30)),
+                ("ended", secs(90)),
+                ("started", secs(200)),
+                ("input", secs(200)),
+            ]
+        );
+        // The new session gets a full window from the input that started it.
+        assert_eq!(
+            idle.deadline(last_input, recording, false),
+            Some(start + secs(260))
+        );
+    }
+
+    #[test]
+    fn target_exit_policy_acts_when_target_apps_come_and_go() {
+        use TargetExitAction::{Pause, Resume, Stop};
+
+        // (target running, already paused for exit) as the target app quits, stays away
+        // and relaunches; the pause flag follows what the engine would do with each action.
+        for (policy, expected) in [
+            (TargetExitPolicy::Continue, [None, None, None, None]),
+            (
+                TargetExitPolicy::Pause,
+                [None, Some(Pause), None, Some(Resume)],
+            ),
+            (TargetExitPolicy::Stop, [None, Some(Stop), None, None]),
+        ] {
+            let mut paused_for_exit = false;
+            let mut stopped = false;
+            let actions: Vec<_> = [true, false, false, true]
+                .into_iter()
+                .map(|running| {
+                    // Nothing is checked once the recording has stopped.
+                    if stopped {
+                        return None;
+                    }
+                    let action = target_exit_action(policy, running, paused_for_exit);
+                    match action {
+                        Some(Pause) => paused_for_exit = true,
+                        Some(Resume) => paused_for_exit = false,
+                        Some(Stop) => stopped = true,
+                        None => {}
+                    }
+                    action
+                })
+                .collect();
+            assert_eq!(actions, expected, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn input_queued_at_segment_cut_is_split_not_lost() {
+        use crate::data::{KeyEvent, MouseMoveEvent};
+
+        // The input backend's clock when the recording stopped
+        let cut_us = 90_000_000;
+        let key = |timestamp_us: u64| {
+            InputEvent::now(
+                timestamp_us,
+                EventType::KeyPress(KeyEvent {
+                    code: 30,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                }),
+            )
+        };
+        let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+        // Typing queued up while the rotation began, with a mouse move landing exactly on
+        // the cut. The wall clock stepped back meanwhile, which must not matter.
+        for i in 0..4u64 {
+            input_tx
+                .send(InputEvent {
+                    absolute_us: 1_700_000_000_000_000 - i * 1_000_000,
+                    ..key(cut_us - 4_000 + i * 1_000)
+                })
+                .unwrap();
+        }
+        input_tx
+            .send(InputEvent::now(
+                cut_us,
+                EventType::MouseMove(MouseMoveEvent {
+                    delta_x: 1.0,
+                    delta_y: 0.0,
+                }),
+            ))
+            .unwrap();
+
+        // More arrives while the recording stops: input the backend read just before the
+        // cut but delivered late, and input from after it.
+        let (stopped, before, after) = stop_at_cut(&mut input_rx, Some(cut_us), || {
+            for timestamp_us in [cut_us - 500, cut_us + 1_000, cut_us + 2_000] {
+                input_tx.send(key(timestamp_us)).unwrap();
+            }
+            "stopped"
+        });
+
+        // Every queued event lands on exactly one side, in order; the one at the cut
+        // closes out the old segment.
+        assert_eq!(stopped, "stopped");
+        let stamps =
+            |events: &[InputEvent]| events.iter().map(|e| e.timestamp_us).collect::<Vec<_>>();
+        assert_eq!(
+            stamps(&before),
+            [4_000, 3_000, 2_000, 1_000, 0, 500].map(|ago| cut_us - ago)
+        );
+        assert!(matches!(before[4].event, EventType::MouseMove(_)));
+        assert_eq!(stamps(&after), [cut_us + 1_000, cut_us + 2_000]);
+        assert!(input_rx.try_recv().is_err());
+
+        // Before the backend has a clock, everything queued belongs to the closing segment.
+        input_tx.send(key(5)).unwrap();
+        let ((), before, after) = stop_at_cut(&mut input_rx, None, || {});
+        assert_eq!(before.len(), 1);
+        assert!(after.is_empty());
+    }
+
+    #[test]
+    fn capturable_apps_are_listed_once_by_name_with_their_target_state() {
+        let app = |bundle_id: &str, name: &str, pid: u32| AppInfo {
+            bundle_id: bundle_id.to_string(),
+            name: name.to_string(),
+            pid,
+        };
+        let apps = vec![
+            app("com.microsoft.VSCode", "Code", 300),
+            app("firefox", "firefox", 120),
+            app("com.microsoft.VSCode", "Code", 301),
+            app("com.apple.Terminal", "Terminal", 200),
+        ];
+
+        let list = capturable_app_list(
+            apps,
+            |bundle_id| bundle_id == "com.apple.Terminal",
+            |pid| (pid == 200).then(|| "~/src — zsh".to_string()),
+        );
+
+        assert_eq!(
+            list,
+            vec![
+                CapturableApp {
+                    bundle_id: "com.microsoft.VSCode".to_string(),
+                    name: "Code".to_string(),
+                    pid: 300,
+                    window_title: None,
+                    targeted: false,
+                },
+                CapturableApp {
+                    bundle_id: "firefox".to_string(),
+                    name: "firefox".to_string(),
+                    pid: 120,
+                    window_title: None,
+                    targeted: false,
+                },
+                CapturableApp {
+                    bundle_id: "com.apple.Terminal".to_string(),
+                    name: "Terminal".to_string(),
+                    pid: 200,
+                    window_title: Some("~/src — zsh".to_string()),
+                    targeted: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rotation_within_the_minimum_segment_length_is_deferred() {
+        let min = Duration::from_secs(2);
+
+        // Requested 300ms after the last rotation: held off until the segment is 2s long.
+        assert_eq!(
+            rotation_deferral(Some(300_000), min),
+            Some(Duration::from_millis(1_700))
+        );
+        assert_eq!(rotation_deferral(Some(0), min), Some(min));
+        // Long enough, no minimum, or no clock to tell: rotate right away.
+        assert_eq!(rotation_deferral(Some(2_000_000), min), None);
+        assert_eq!(rotation_deferral(Some(300_000_000), min), None);
+        assert_eq!(rotation_deferral(Some(300_000), Duration::ZERO), None);
+        assert_eq!(rotation_deferral(None, min), None);
+    }
+
+    #[test]
+    fn warmup_waits_for_video_clock_to_advance() {
+        const START_NS: u64 = 5_000_000_000;
+        let stall = Duration::from_millis(150);
+        let warmup = Duration::from_millis(50);
+        let started = Instant::now();
+        // Nothing renders for `stall`; after that the video clock runs in real time.
+        let frame_time = |at: Instant| {
+            let rendering = (at - started).saturating_sub(stall);
+            Ok(START_NS + rendering.as_nanos() as u64)
+        };
+
+        let mut pending = PendingWarmup::new(START_NS, warmup, Duration::ZERO, started).unwrap();
+        let mut at = pending.next_poll;
+        while !pending.poll(at, || frame_time(at), || None) {
+            assert_eq!(pending.next_poll, at + WARMUP_POLL_INTERVAL);
+            at = pending.next_poll;
+        }
+        assert!(
+            at - started >= stall + warmup,
+            "capture enabled after {:?}, before the clock advanced {:?}",
+            at - started,
+            warmup
+        );
+
+        // A clock that never advances is given up on after the grace period.
+        let mut stalled = PendingWarmup::new(START_NS, warmup, Duration::ZERO, started).unwrap();
+        assert!(!stalled.poll(started, || Ok(START_NS), || None));
+        let deadline = started + warmup + WARMUP_GRACE;
+        assert!(stalled.poll(deadline, || Ok(START_NS), || None));
+    }
+
+    #[test]
+    fn content_warmup_waits_for_a_non_black_frame() {
+        const START_NS: u64 = 5_000_000_000;
+        let black = Duration::from_millis(150);
+        let started = Instant::now();
+        // No frame at first, then black frames, then the capture shows content.
+        let peak_luma = |at: Instant| match at - started {
+            elapsed if elapsed < Duration::from_millis(40) => None,
+            elapsed if elapsed < black => Some(16),
+            _ => Some(235),
+        };
+        let poll_until_done = |pending: &mut PendingWarmup| {
+            let mut at = pending.next_poll;
+            while !pending.poll(
+                at,
+                || Ok(START_NS + (at - started).as_nanos() as u64),
+                || peak_luma(at),
+            ) {
+                at = pending.next_poll;
+            }
+            at
+        };
+
+        let mut pending =
+            PendingWarmup::new(START_NS, Duration::ZERO, Duration::from_secs(5), started).unwrap();
+        let done = poll_until_done(&mut pending);
+        assert!(
+            done - started >= black,
+            "capture marked healthy after {:?}, while frames were still black",
+            done - started
+        );
+
+        // After a video clock warmup, the content phase still waits for the frames.
+        let mut pending = PendingWarmup::new(
+            START_NS,
+            Duration::from_millis(20),
+            Duration::from_secs(5),
+            started,
+        )
+        .unwrap();
+        assert!(poll_until_done(&mut pending) - started >= black);
+
+        // A capture that stays black gives up at the timeout.
+        let timeout = Duration::from_millis(60);
+        let mut stuck = PendingWarmup::new(START_NS, Duration::ZERO, timeout, started).unwrap();
+        assert!(!stuck.poll(started, || Ok(START_NS), || Some(16)));
+        assert!(stuck.poll(started + timeout, || Ok(START_NS), || Some(16)));
+
+        assert!(PendingWarmup::new(START_NS, Duration::ZERO, Duration::ZERO, started).is_none());
+    }
+
+    #[test]
+    fn segment_counter_increments_and_persists_across_restarts() {
+        let dir = test_dir("segment-counter");
+        let path = dir.join("segment_counter");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(next_segment_counter(&path).unwrap(), 0);
+        assert_eq!(next_segment_counter(&path).unwrap(), 1);
+        // Nothing is held in memory, so each call reads what a restarted agent would.
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2");
+        assert_eq!(next_segment_counter(&path).unwrap(), 2);
+        assert!(!path.with_extension("tmp").exists());
+
+        // A corrupt count is an error rather than a silent restart from 0.
+        std::fs::write(&path, "garbage").unwrap();
+        assert!(next_segment_counter(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interrupted_session_resumes_from_its_marker() {
+        let dir = test_dir("active-session");
+        let path = dir.join("active_session.json");
+        let video = dir.join("recording_sess_seg0003.mp4");
+        std::fs::write(&video, b"partial video").unwrap();
+
+        let marker = ActiveSessionMarker {
+            session_id: "sess".to_string(),
+            segment_index: 3,
+            output_path: video.clone(),
+        };
+        write_active_session(&path, &marker);
+
+        // What the next run reads back continues the session after the cut-short segment.
+        let restored = read_active_session(&path).unwrap();
+        assert_eq!(restored, marker);
+        assert_eq!(resume_point(&restored), Some(("sess".to_string(), 4)));
+
+        // Without the interrupted segment's video there's nothing to continue.
+        std::fs::remove_file(&video).unwrap();
+        assert_eq!(resume_point(&restored), None);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(read_active_session(&path), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn local_retention_prunes_oldest_and_spares_pending() {
+        let dir = test_dir("retention");
+        let write = |name: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            path
+        };
+        // Oldest first. `pending` is listed as retained but is still awaiting upload.
+        let pending_file = write("pending.mp4");
+        let old1 = write("old1.mp4");
+        let old2 = write("old2.mp4");
+        let old3 = write("old3.mp4");
+        let recording = write("recording.mp4");
+
+        let mut retained = vec![
+            pending_file.clone(),
+            old1.clone(),
+            old2.clone(),
+            old3.clone(),
+            dir.join("already-gone.mp4"),
+        ];
+        let pending: HashSet<PathBuf> = [pending_file.clone()].into_iter().collect();
+        let budget = LocalRetention {
+            bytes: 300,
+            segments: 0,
+        };
+
+        // 500 bytes on disk, 300 budget: the two oldest uploaded files go; the pending
+        // file is skipped even though it is oldest, and the non-retained file is untouched.
+        let pruned = prune_local_retention(&dir, budget, &mut retained, &pending);
+
+        assert_eq!(pruned, vec![old1.clone(), old2.clone()]);
+        assert!(!old1.exists() && !old2.exists());
+        assert!(old3.exists());
+        assert!(pending_file.exists());
+        assert!(recording.exists());
+        assert_eq!(retained, vec![pending_file.clone(), old3.clone()]);
+
+        // Within budget: nothing more to prune.
+        assert!(prune_local_retention(&dir, budget, &mut retained, &pending).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn local_retention_count_keeps_newest_segments() {
+        let dir = test_dir("retention-count");
+        let mut retained = Vec::new();
+        for i in 0..5 {
+            for name in [
+                format!("recording_sess_seg{:04}.mp4", i),
+                format!("input_sess_seg{:04}.msgpack", i),
+            ] {
+                let path = dir.join(name);
+                std::fs::write(&path, [0u8; 10]).unwrap();
+                retained.push(path);
+            }
+        }
+        let limits = LocalRetention {
+            bytes: 0,
+            segments: 3,
+        };
+
+        // Five uploaded segments, cap of three: both files of the two oldest go.
+        let pruned = prune_local_retention(&dir, limits, &mut retained, &HashSet::new());
+        assert_eq!(pruned.len(), 4);
+        assert!(!dir.join("recording_sess_seg0001.mp4").exists());
+        assert!(!dir.join("input_sess_seg0001.msgpack").exists());
+        assert!(dir.join("recording_sess_seg0002.mp4").exists());
+        assert_eq!(retained.len(), 6);
+
+        // The byte budget still applies when it is the stricter cap.
+        let limits = LocalRetention {
+            bytes: 40,
+            segments: 3,
+        };
+        let pruned = prune_local_retention(&dir, limits, &mut retained, &HashSet::new());
+        assert_eq!(
+            pruned,
+            vec![
+                dir.join("recording_sess_seg0002.mp4"),
+                dir.join("input_sess_seg0002.msgpack")
+            ]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn event_type_counts_tally_mixed_events() {
+        use crate::data::{
+            GestureEvent, GestureKind, KeyEvent, MouseButton, MouseButtonEvent, MouseMoveEvent,
+            MouseScrollEvent,
+        };
+
+        let key = KeyEvent {
+            code: 30,
+            name: "KeyA".to_string(),
+            is_repeat: false,
+        };
+        let button = MouseButtonEvent {
+            button: MouseButton::Left,
+            x: None,
+            y: None,
+            normalized: None,
+        };
+        let events = [
+            EventType::KeyPress(key.clone()),
+            EventType::KeyRelease(key),
+            EventType::MouseMove(MouseMoveEvent {
+                delta_x: 1.0,
+                delta_y: 0.0,
+            }),
+            EventType::MouseMove(MouseMoveEvent {
+                delta_x: 0.0,
+                delta_y: 1.0,
+            }),
+            EventType::MouseMove(MouseMoveEvent {
+                delta_x: 2.0,
+                delta_y: 2.0,
+            }),
+            EventType::MousePress(button.clone()),
+            EventType::MouseRelease(button),
+            EventType::MouseScroll(MouseScrollEvent {
+                delta_x: 0,
+                delta_y: -1,
+                x: None,
+                y: None,
+                normalized: None,
+                precise: false,
+                momentum: false,
+            }),
+            EventType::Gesture(GestureEvent {
+                kind: GestureKind::Magnify,
+                magnitude: 0.1,
+            }),
+            EventType::ContextChanged(ContextEvent {
+                app_id: "com.example.app".to_string(),
+            }),
+        ];
+
+        let mut counts = EventTypeCounts::default();
+        for event in &events {
+            counts.record(event);
+        }
+
+        assert_eq!(
+            counts,
+            EventTypeCounts {
+                keys: 2,
+                mouse_moves: 3,
+                mouse_clicks: 2,
+                scrolls: 1,
+                gestures: 1,
+            }
+        );
+    }
 }
@@ -0,0 +1,173 @@
+//! Ordering of input against recording start
+//!
+//! Input backends read events on their own threads and queue them for the engine, so by
+//! the time the engine sets a recording's start, events read before it may still be
+//! waiting in the channel. Stamping those on arrival at the engine would place them
+//! inside the recording. The gate instead decides by when the backend saw each event, on
+//! the backend's own monotonic clock (`InputEvent::timestamp_us` as the backend stamps
+//! it, before the engine rebases it onto the video): once the recording has started, only
+//! events seen at or after its cutoff are committed, and everything older is discarded.
+//! The decision depends only on the event, not on when the engine got to it, and a
+//! wall-clock step (NTP) can't move it.
+
+use crate::data::InputEvent;
+
+/// Whether input may be committed to the current recording's input log
+#[derive(Debug, Default)]
+pub(super) struct InputGate {
+    /// Backend clock time (`InputBackend::current_timestamp`) from which input counts;
+    /// None while no recording takes input
+    cutoff_us: Option<u64>,
+}
+
+impl InputGate {
+    /// Commit events the backends saw at or after `cutoff_us` (backend clock) from now
+    /// on, replacing any earlier cutoff
+    pub(super) fn open(&mut self, cutoff_us: u64) {
+        self.cutoff_us = Some(cutoff_us);
+    }
+
+    /// Stop committing input until the next `open`
+    pub(super) fn close(&mut self) {
+        self.cutoff_us = None;
+    }
+
+    /// Input cutoff of the running recording
+    #[cfg(test)]
+    pub(super) fn cutoff_us(&self) -> Option<u64> {
+        self.cutoff_us
+    }
+
+    /// Whether `event` (not yet rebased) was seen before the running recording's cutoff
+    pub(super) fn predates(&self, event: &InputEvent) -> bool {
+        self.cutoff_us
+            .is_some_and(|cutoff| event.timestamp_us < cutoff)
+    }
+
+    /// Whether `event` (not yet rebased) may be committed to the running recording
+    pub(super) fn admits(&self, event: &InputEvent) -> bool {
+        self.cutoff_us
+            .is_some_and(|cutoff| event.timestamp_us >= cutoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{EventType, KeyEvent};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::time::Instant;
+
+    /// Microseconds since `backend_start`, as a backend stamps its events
+    fn backend_now(backend_start: Instant) -> u64 {
+        backend_start.elapsed().as_micros() as u64
+    }
+
+    #[test]
+    fn concurrent_pushes_around_start_never_commit_earlier_input() {
+        const PRODUCERS: u32 = 4;
+        const EVENTS_AFTER_START: u64 = 500;
+
+        let (tx, rx) = mpsc::channel::<InputEvent>();
+        let backend_start = Instant::now();
+        let started = Arc::new(AtomicBool::new(false));
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let tx = tx.clone();
+                let started = started.clone();
+                std::thread::spawn(move || {
+                    let mut seq = 0;
+                    let mut after_start = 0;
+                    while after_start < EVENTS_AFTER_START {
+                        if started.load(Ordering::SeqCst) {
+                            after_start += 1;
+                        }
+                        seq += 1;
+                        let mut event = InputEvent::now(
+                            backend_now(backend_start),
+                            EventType::KeyPress(KeyEvent {
+                                code: producer,
+                                name: "KeyA".to_string(),
+                                is_repeat: false,
+                            }),
+                        );
+                        event.seq = seq;
+                        tx.send(event).unwrap();
+                        std::thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        let mut gate = InputGate::default();
+        let mut received = Vec::new();
+        let mut committed = Vec::new();
+        for event in rx {
+            if received.len() == 200 {
+                // Recording starts while the producers are mid-stream.
+                gate.open(backend_now(backend_start));
+                started.store(true, Ordering::SeqCst);
+            }
+            if gate.admits(&event) {
+                committed.push(event.clone());
+            }
+            received.push(event);
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let cutoff = gate.cutoff_us().unwrap();
+        assert!(received.iter().any(|e| gate.predates(e)));
+        assert!(committed.len() >= (PRODUCERS as u64 * EVENTS_AFTER_START) as usize);
+        // Nothing seen before the start is committed, however late it was handled...
+        assert!(committed.iter().all(|e| e.timestamp_us >= cutoff));
+        // ...and nothing seen after it is lost.
+        let expected: Vec<_> = received.iter().filter(|e| !gate.predates(e)).collect();
+        assert_eq!(committed.len(), expected.len());
+        // Each backend's events are committed as one contiguous run in its own order.
+        for producer in 0..PRODUCERS {
+            let seqs: Vec<u64> = committed
+                .iter()
+                .filter(|e| matches!(&e.event, EventType::KeyPress(k) if k.code == producer))
+                .map(|e| e.seq)
+                .collect();
+            assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1), "{}", producer);
+        }
+
+        gate.close();
+        assert!(!gate.admits(&committed[0]));
+        assert!(!gate.predates(&received[0]));
+    }
+
+    #[test]
+    fn reopening_replaces_the_cutoff() {
+        let key = |timestamp_us| {
+            let mut event = InputEvent::now(
+                timestamp_us,
+                EventType::KeyPress(KeyEvent {
+                    code: 30,
+                    name: "KeyA".to_string(),
+                    is_repeat: false,
+                }),
+            );
+            // A wall clock stepped back an hour doesn't matter; only the backend clock does.
+            event.absolute_us = event.absolute_us.saturating_sub(3_600_000_000);
+            event
+        };
+        let mut gate = InputGate::default();
+        gate.open(5_000);
+        assert!(gate.predates(&key(4_999)));
+        assert!(gate.admits(&key(5_000)));
+
+        // The end of a warmup moves it forward...
+        gate.open(8_000);
+        assert!(!gate.admits(&key(6_000)));
+        // ...and an earlier cutoff (a restarted backend clock) takes effect as well.
+        gate.open(2_000);
+        assert_eq!(gate.cutoff_us(), Some(2_000));
+        assert!(gate.admits(&key(2_000)));
+    }
+}
@@ -1,8 +1,21 @@
 //! Synchronization engine - coordinates input capture with recording state
 
+mod audio_levels;
+mod bitrate;
+mod clock;
+mod compact;
 mod engine;
+mod input_gate;
+mod resources;
+mod webhooks;
 
 pub use engine::{create_engine_channels, SyncEngine};
+pub(crate) use engine::{read_retained_uploads, remove_pending_upload};
+
+use crate::capture::DisplayInfo;
+use crate::data::EventType;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 /// Commands that can be sent to the sync engine
 #[derive(Debug, Clone)]
@@ -26,8 +39,29 @@ pub enum EngineCommand {
     ResumeUploads,
     /// Panic: delete current + buffered recordings
     Panic,
-    /// User requested switch to a specific display (from notification action)
+    /// User requested switch to a specific display (from notification action or tray picker)
     SwitchToDisplay { display_id: u32 },
+    /// Insert a `Marker` event with this label at the current recording timeline position
+    /// (from the tray's marker menu or the control socket). Ignored when not recording.
+    AddMarker { label: String },
+    /// Enumerate connected displays for the tray's display picker
+    ListDisplays {
+        respond_to: mpsc::UnboundedSender<Vec<DisplayInfo>>,
+    },
+    /// Enumerate the apps that can be captured right now, for a settings UI's app picker.
+    /// Answered off the engine loop; takes about as long as listing running apps.
+    ListCapturable {
+        respond_to: mpsc::UnboundedSender<Vec<CapturableApp>>,
+    },
+    /// One-shot status snapshot for the control socket (`crowd-cast-agent status`)
+    QueryStatus {
+        respond_to: mpsc::UnboundedSender<StatusSnapshot>,
+    },
+    /// Upload a tiny throwaway object to check the endpoint and credentials; replies with
+    /// the error text on failure
+    TestUpload {
+        respond_to: mpsc::UnboundedSender<Result<(), String>>,
+    },
     /// Restart the process (exec) for fresh capture sources after unlock
     RestartProcess,
     /// System resumed from a suspend (Windows/Linux): restart the recording fresh so the keylog
@@ -39,6 +73,44 @@ pub enum EngineCommand {
     Shutdown,
 }
 
+/// Point-in-time engine state reported over the control socket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    /// A recording session is running (possibly paused)
+    pub recording: bool,
+    /// The recording is paused (manually or by the idle timeout)
+    pub paused: bool,
+    /// Last status the engine reported (e.g. "Capturing", "Idle")
+    pub status: String,
+    /// Current recording session id
+    pub session_id: Option<String>,
+    /// Segments waiting to upload (held back, queued, or retrying)
+    pub pending_uploads: usize,
+    /// Uploads are on hold after repeated failures (see `upload.circuit_breaker_failures`)
+    #[serde(default)]
+    pub uploads_circuit_open: bool,
+    /// Requested apps that aren't being recorded because their capture source couldn't
+    /// be created
+    #[serde(default)]
+    pub failed_apps: Vec<String>,
+}
+
+/// A running app that can be picked as a capture target (`EngineCommand::ListCapturable`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturableApp {
+    /// Bundle identifier (macOS) or process name (Linux/Windows), as `capture.target_apps`
+    /// names it
+    pub bundle_id: String,
+    /// Display name
+    pub name: String,
+    /// Process ID (the first one, for apps running several processes)
+    pub pid: u32,
+    /// Title of the app's focused window (macOS only)
+    pub window_title: Option<String>,
+    /// The current config already captures this app
+    pub targeted: bool,
+}
+
 /// Status updates from the sync engine
 #[derive(Debug, Clone)]
 pub enum EngineStatus {
@@ -48,6 +120,8 @@ pub enum EngineStatus {
     Capturing {
         /// Number of events captured in current chunk
         event_count: usize,
+        /// Per-type breakdown of input captured in the current segment
+        counts: EventTypeCounts,
     },
     /// Recording is paused (both video and keylog)
     Paused,
@@ -60,6 +134,109 @@ pub enum EngineStatus {
         /// Chunk ID being uploaded
         chunk_id: String,
     },
+    /// Bytes of a chunk's video sent so far, throttled per upload. Like `Health`, it
+    /// runs alongside the states above rather than replacing them. `total == 0` marks
+    /// the end of an upload attempt, successful or not.
+    UploadProgress {
+        /// Chunk ID being uploaded
+        chunk_id: String,
+        /// Bytes sent
+        sent: u64,
+        /// Size of the video being uploaded
+        total: u64,
+    },
+    /// Shutting down and waiting for the remaining uploads to finish
+    Draining {
+        /// Segments still queued, retrying, or in flight
+        pending: usize,
+    },
     /// An error occurred
     Error(String),
+    /// Periodic recording health check while recording. Orthogonal to the states above:
+    /// it refines `Capturing`/`RecordingBlocked` rather than replacing them.
+    Health(RecordingHealth),
+}
+
+/// Consolidated health of a running recording, built from independent sub-checks so
+/// a single indicator can say whether anything is wrong and what.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingHealth {
+    /// The active capture source reports non-zero dimensions
+    pub sources_active: bool,
+    /// The video frame clock moved since the previous check
+    pub frames_advancing: bool,
+    /// Free space on the recording volume is above the low-disk threshold
+    pub disk_ok: bool,
+    /// Uploads are draining (no upload stall in progress)
+    pub uploads_keeping_up: bool,
+    /// The microphone has not sat at one flat level past `recording.audio.flat_warning_secs`
+    /// (always true when no microphone is captured)
+    pub audio_ok: bool,
+}
+
+impl RecordingHealth {
+    /// True when every sub-check passes.
+    pub fn is_healthy(&self) -> bool {
+        self.sources_active
+            && self.frames_advancing
+            && self.disk_ok
+            && self.uploads_keeping_up
+            && self.audio_ok
+    }
+
+    /// Short descriptions of the failing sub-checks, in display order.
+    pub fn problems(&self) -> Vec<&'static str> {
+        [
+            (self.sources_active, "no active capture source"),
+            (self.frames_advancing, "video frames not advancing"),
+            (self.disk_ok, "low disk space"),
+            (self.uploads_keeping_up, "uploads falling behind"),
+            (self.audio_ok, "microphone level stuck"),
+        ]
+        .into_iter()
+        .filter(|(ok, _)| !ok)
+        .map(|(_, problem)| problem)
+        .collect()
+    }
+}
+
+/// Per-type tally of input events captured in the current segment. Lets monitoring spot
+/// e.g. a dead keyboard hook (moves and clicks climbing while keys stay at zero).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventTypeCounts {
+    /// Key presses and releases, and IME text commits
+    pub keys: usize,
+    /// Mouse moves
+    pub mouse_moves: usize,
+    /// Mouse button presses and releases
+    pub mouse_clicks: usize,
+    /// Scroll events
+    pub scrolls: usize,
+    /// Trackpad gestures (macOS)
+    pub gestures: usize,
+}
+
+impl EventTypeCounts {
+    /// Count one event. Context, metadata and redaction markers are not input and are ignored,
+    /// and chords are already counted through their key events.
+    pub fn record(&mut self, event: &EventType) {
+        match event {
+            EventType::KeyPress(_) | EventType::KeyRelease(_) | EventType::TextCommit(_) => {
+                self.keys += 1
+            }
+            EventType::MouseMove(_) => self.mouse_moves += 1,
+            EventType::MousePress(_) | EventType::MouseRelease(_) => self.mouse_clicks += 1,
+            EventType::MouseScroll(_) => self.scrolls += 1,
+            EventType::Gesture(_) => self.gestures += 1,
+            EventType::ContextChanged(_)
+            | EventType::Metadata(_)
+            | EventType::Redacted(_)
+            | EventType::DisplayScaleChanged(_)
+            | EventType::Marker(_)
+            | EventType::WindowGeometry(_)
+            | EventType::Chord(_)
+            | EventType::AudioLevel(_)
+            | EventType::ResourceUsage(_) => {}
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Resource usage sampling (`recording.log_resource_usage`)
+//!
+//! To show what recording costs a contributor's machine, the engine samples the agent
+//! process's CPU and resident memory at every health check and logs a min/avg/max
+//! summary at the end of each segment. OBS runs inside the agent, so capture and
+//! encoding are included. GPU encoder load is not sampled: neither sysinfo nor OBS
+//! reports it.
+
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+use crate::data::ResourceUsageEvent;
+
+/// Running min/avg/max of the samples taken since the last summary
+#[derive(Debug, Default)]
+pub(super) struct UsageAccumulator {
+    samples: u32,
+    cpu_min: f32,
+    cpu_max: f32,
+    cpu_sum: f64,
+    memory_min: u64,
+    memory_max: u64,
+    memory_sum: u128,
+}
+
+impl UsageAccumulator {
+    pub(super) fn observe(&mut self, cpu_percent: f32, memory_bytes: u64) {
+        if self.samples == 0 {
+            self.cpu_min = cpu_percent;
+            self.cpu_max = cpu_percent;
+            self.memory_min = memory_bytes;
+            self.memory_max = memory_bytes;
+        } else {
+            self.cpu_min = self.cpu_min.min(cpu_percent);
+            self.cpu_max = self.cpu_max.max(cpu_percent);
+            self.memory_min = self.memory_min.min(memory_bytes);
+            self.memory_max = self.memory_max.max(memory_bytes);
+        }
+        self.samples += 1;
+        self.cpu_sum += f64::from(cpu_percent);
+        self.memory_sum += u128::from(memory_bytes);
+    }
+
+    /// Summary of everything observed so far, then start over. `None` without samples.
+    pub(super) fn take(&mut self) -> Option<ResourceUsageEvent> {
+        if self.samples == 0 {
+            return None;
+        }
+        let summary = ResourceUsageEvent {
+            samples: self.samples,
+            cpu_percent_min: self.cpu_min,
+            cpu_percent_avg: (self.cpu_sum / f64::from(self.samples)) as f32,
+            cpu_percent_max: self.cpu_max,
+            memory_bytes_min: self.memory_min,
+            memory_bytes_avg: (self.memory_sum / u128::from(self.samples)) as u64,
+            memory_bytes_max: self.memory_max,
+        };
+        *self = Self::default();
+        Some(summary)
+    }
+}
+
+/// Samples the agent process through `sysinfo`
+pub(super) struct ResourceSampler {
+    system: System,
+    pid: Option<Pid>,
+    usage: UsageAccumulator,
+}
+
+impl ResourceSampler {
+    pub(super) fn new() -> Self {
+        let mut sampler = Self {
+            system: System::new(),
+            pid: sysinfo::get_current_pid().ok(),
+            usage: UsageAccumulator::default(),
+        };
+        // CPU use is measured between two refreshes; this one sets the baseline.
+        sampler.refresh();
+        sampler
+    }
+
+    /// Take one sample into the current segment's summary
+    pub(super) fn sample(&mut self) {
+        if !self.refresh() {
+            return;
+        }
+        let Some(process) = self.pid.and_then(|pid| self.system.process(pid)) else {
+            return;
+        };
+        self.usage.observe(process.cpu_usage(), process.memory());
+    }
+
+    /// Summary of the samples since the previous call (the segment just ended)
+    pub(super) fn take_summary(&mut self) -> Option<ResourceUsageEvent> {
+        self.usage.take()
+    }
+
+    fn refresh(&mut self) -> bool {
+        let Some(pid) = self.pid else {
+            return false;
+        };
+        self.system
+            .refresh_process_specifics(pid, ProcessRefreshKind::new().with_cpu().with_memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulator_summarizes_and_resets() {
+        let mut usage = UsageAccumulator::default();
+        assert_eq!(usage.take(), None);
+
+        for (cpu, memory) in [(40.0, 300), (10.0, 500), (130.0, 400)] {
+            usage.observe(cpu, memory);
+        }
+        assert_eq!(
+            usage.take(),
+            Some(ResourceUsageEvent {
+                samples: 3,
+                cpu_percent_min: 10.0,
+                cpu_percent_avg: 60.0,
+                cpu_percent_max: 130.0,
+                memory_bytes_min: 300,
+                memory_bytes_avg: 400,
+                memory_bytes_max: 500,
+            })
+        );
+
+        // The next segment starts from scratch: a single sample is its own min, avg and max.
+        assert_eq!(usage.take(), None);
+        usage.observe(5.0, u64::MAX);
+        let summary = usage.take().unwrap();
+        assert_eq!(summary.samples, 1);
+        assert_eq!(summary.cpu_percent_min, 5.0);
+        assert_eq!(summary.cpu_percent_avg, 5.0);
+        assert_eq!(summary.memory_bytes_avg, u64::MAX);
+    }
+}
@@ -0,0 +1,65 @@
+/*
+ * macOS recording indicator: a small red dot in the top-right corner of the main
+ * display, shown while input is being captured (ui.recording_indicator).
+ *
+ * The window is borderless, always on top, on every Space, and click-through. Its
+ * sharingType is NSWindowSharingNone, which keeps it out of ScreenCaptureKit and
+ * CGWindowList captures, so the recording never contains the indicator itself.
+ *
+ * Must be called on the main thread (the tray event loop).
+ */
+
+#import <Cocoa/Cocoa.h>
+#include <stdbool.h>
+
+static const CGFloat kIndicatorDiameter = 12.0;
+/* Inset from the right edge and from the bottom of the menu bar */
+static const CGFloat kIndicatorMargin = 8.0;
+
+static NSWindow *g_indicator = nil;
+
+static NSRect indicator_frame(void) {
+    NSScreen *screen = [NSScreen mainScreen];
+    NSRect visible = screen ? screen.visibleFrame : NSMakeRect(0, 0, 800, 600);
+    return NSMakeRect(NSMaxX(visible) - kIndicatorDiameter - kIndicatorMargin,
+                      NSMaxY(visible) - kIndicatorDiameter - kIndicatorMargin,
+                      kIndicatorDiameter, kIndicatorDiameter);
+}
+
+static NSWindow *create_indicator(void) {
+    NSWindow *window = [[NSWindow alloc] initWithContentRect:indicator_frame()
+                                                   styleMask:NSWindowStyleMaskBorderless
+                                                     backing:NSBackingStoreBuffered
+                                                       defer:NO];
+    window.opaque = NO;
+    window.backgroundColor = [NSColor clearColor];
+    window.hasShadow = NO;
+    window.ignoresMouseEvents = YES;
+    window.level = NSStatusWindowLevel;
+    window.sharingType = NSWindowSharingNone;
+    window.releasedWhenClosed = NO;
+    window.collectionBehavior = NSWindowCollectionBehaviorCanJoinAllSpaces |
+                                NSWindowCollectionBehaviorStationary |
+                                NSWindowCollectionBehaviorIgnoresCycle |
+                                NSWindowCollectionBehaviorFullScreenAuxiliary;
+
+    NSView *dot = [[NSView alloc] initWithFrame:NSMakeRect(0, 0, kIndicatorDiameter, kIndicatorDiameter)];
+    dot.wantsLayer = YES;
+    dot.layer.backgroundColor = [NSColor systemRedColor].CGColor;
+    dot.layer.cornerRadius = kIndicatorDiameter / 2.0;
+    window.contentView = dot;
+    return window;
+}
+
+void recording_indicator_set_visible(bool visible) {
+    if (visible) {
+        if (g_indicator == nil) {
+            g_indicator = create_indicator();
+        }
+        // Follow display changes since the last show
+        [g_indicator setFrame:indicator_frame() display:NO];
+        [g_indicator orderFrontRegardless];
+    } else if (g_indicator != nil) {
+        [g_indicator orderOut:nil];
+    }
+}
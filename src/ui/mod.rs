@@ -11,6 +11,7 @@ pub mod notifications;
 #[cfg(target_os = "linux")]
 pub mod notify_linux;
 mod platform_tray;
+mod recording_indicator;
 mod tray;
 pub mod tray_ffi;
 #[cfg(target_os = "linux")]
@@ -4,6 +4,7 @@
 //! Since display switching is automatic, notifications are purely informational.
 
 use std::ffi::CString;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::OnceLock;
 use tokio::sync::mpsc;
 // Not every macro is used on every platform (the macOS arms use them all; Linux routes through
@@ -11,16 +12,65 @@ use tokio::sync::mpsc;
 #[allow(unused_imports)]
 use tracing::{debug, error, info, warn};
 
+use crate::config::NotificationLevel;
+
 /// Actions that can be triggered from notifications
 #[derive(Debug, Clone)]
 pub enum NotificationAction {
     /// User dismissed or tapped the notification
     Dismissed,
+    /// User clicked "Open Folder" on the upload-stalled notification
+    OpenFolder,
+    /// User clicked "Open Settings" on the permission-revoked notification
+    OpenAccessibilitySettings,
 }
 
 /// Channel sender for notification actions (set once during init)
 static ACTION_SENDER: OnceLock<mpsc::UnboundedSender<NotificationAction>> = OnceLock::new();
 
+/// `notifications.level`, stored as its discriminant (set at startup)
+static LEVEL: AtomicU8 = AtomicU8::new(NotificationLevel::All as u8);
+
+/// How much a notification needs the user's attention, for `notifications.level`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Priority {
+    /// Display changes, errors, and warnings: shown unless the level is `None`
+    Important,
+    /// Routine status (recording started, idle pause, ...): shown only at `All`
+    Routine,
+}
+
+/// Apply the configured `notifications.level` to every `show_*` helper below.
+pub fn set_notification_level(level: NotificationLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+fn notification_level() -> NotificationLevel {
+    match LEVEL.load(Ordering::Relaxed) {
+        l if l == NotificationLevel::None as u8 => NotificationLevel::None,
+        l if l == NotificationLevel::Important as u8 => NotificationLevel::Important,
+        _ => NotificationLevel::All,
+    }
+}
+
+fn level_allows(level: NotificationLevel, priority: Priority) -> bool {
+    match level {
+        NotificationLevel::All => true,
+        NotificationLevel::Important => priority == Priority::Important,
+        NotificationLevel::None => false,
+    }
+}
+
+/// Whether a notification of `priority` passes the configured level. Suppressed ones are
+/// dropped silently; callers never need to know.
+fn allowed(priority: Priority) -> bool {
+    let allowed = level_allows(notification_level(), priority);
+    if !allowed {
+        debug!("Notification suppressed by notifications.level");
+    }
+    allowed
+}
+
 // FFI declarations for the Objective-C implementation
 #[cfg(target_os = "macos")]
 mod ffi {
@@ -43,6 +93,7 @@ mod ffi {
         pub fn notifications_show_recording_paused();
         pub fn notifications_show_recording_resumed();
         pub fn notifications_show_permissions_missing(message: *const c_char);
+        pub fn notifications_show_permission_revoked(message: *const c_char);
         pub fn notifications_show_obs_download_started();
         pub fn notifications_show_setup_configuring();
         pub fn notifications_show_sources_refreshed();
@@ -54,6 +105,12 @@ mod ffi {
             build: *const c_char,
         );
         pub fn notifications_show_upload_queue_warning();
+        pub fn notifications_show_upload_stalled(pending: u32);
+        pub fn notifications_show_audio_flat(secs: u32);
+        pub fn notifications_show_capture_sources_failed(apps: *const c_char);
+        pub fn notifications_show_target_app_exited(stopped: i32);
+        pub fn notifications_show_already_running();
+        pub fn notifications_show_upload_rejected(reason: *const c_char);
         pub fn notifications_is_authorized() -> i32;
     }
 }
@@ -74,6 +131,8 @@ extern "C" fn notification_action_callback(action_id: *const std::ffi::c_char, d
 
     let action = match action_str {
         "dismiss" | "default" => NotificationAction::Dismissed,
+        "open_folder" => NotificationAction::OpenFolder,
+        "open_accessibility" => NotificationAction::OpenAccessibilitySettings,
         _ => {
             warn!("Unknown notification action: {}", action_str);
             NotificationAction::Dismissed
@@ -184,6 +243,9 @@ pub fn init_notifications(
 /// The `to_display_id` is passed back in the callback when user clicks "Switch".
 #[cfg(target_os = "macos")]
 pub fn show_display_change_notification(from_display: &str, to_display: &str, to_display_id: u32) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     let from_c = match CString::new(from_display) {
         Ok(s) => s,
         Err(e) => {
@@ -212,6 +274,9 @@ pub fn show_display_change_notification(from_display: &str, to_display: &str, to
 /// Show display change notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_display_change_notification(from_display: &str, to_display: &str, _to_display_id: u32) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     emit(
         "Display Changed",
         &format!(
@@ -224,6 +289,9 @@ pub fn show_display_change_notification(from_display: &str, to_display: &str, _t
 /// Show notification when capture resumes on original display
 #[cfg(target_os = "macos")]
 pub fn show_capture_resumed_notification(display_name: &str) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     let name_c = match CString::new(display_name) {
         Ok(s) => s,
         Err(e) => {
@@ -242,6 +310,9 @@ pub fn show_capture_resumed_notification(display_name: &str) {
 /// Show capture resumed notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_capture_resumed_notification(display_name: &str) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit(
         "Capture Resumed",
         &format!("Recording restarted on {display_name}"),
@@ -251,6 +322,9 @@ pub fn show_capture_resumed_notification(display_name: &str) {
 /// Show notification when recording starts
 #[cfg(target_os = "macos")]
 pub fn show_recording_started_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_recording_started();
     }
@@ -261,12 +335,18 @@ pub fn show_recording_started_notification() {
 /// Show recording started notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_recording_started_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording started", "");
 }
 
 /// Show notification when recording stops
 #[cfg(target_os = "macos")]
 pub fn show_recording_stopped_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_recording_stopped();
     }
@@ -277,12 +357,18 @@ pub fn show_recording_stopped_notification() {
 /// Show recording stopped notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_recording_stopped_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording stopped", "");
 }
 
 /// Show notification when recording is paused
 #[cfg(target_os = "macos")]
 pub fn show_recording_paused_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_recording_paused();
     }
@@ -293,12 +379,18 @@ pub fn show_recording_paused_notification() {
 /// Show recording paused notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_recording_paused_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording paused", "");
 }
 
 /// Show notification when recording is resumed
 #[cfg(target_os = "macos")]
 pub fn show_recording_resumed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_recording_resumed();
     }
@@ -309,12 +401,18 @@ pub fn show_recording_resumed_notification() {
 /// Show recording resumed notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_recording_resumed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording resumed", "");
 }
 
 /// Show notification when recording is blocked by missing permissions
 #[cfg(target_os = "macos")]
 pub fn show_permissions_missing_notification(message: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     let msg_c = match CString::new(message) {
         Ok(s) => s,
         Err(e) => {
@@ -333,6 +431,9 @@ pub fn show_permissions_missing_notification(message: &str) {
 /// Show permissions missing notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_permissions_missing_notification(message: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     let body = if message.is_empty() {
         "Recording not started. Required permissions are missing."
     } else {
@@ -341,9 +442,44 @@ pub fn show_permissions_missing_notification(message: &str) {
     emit("Permissions Required", body);
 }
 
+/// Show notification when recording was stopped because input-capture permission was
+/// revoked mid-run. Carries an "Open Settings" action that reports
+/// `NotificationAction::OpenAccessibilitySettings`.
+#[cfg(target_os = "macos")]
+pub fn show_permission_revoked_notification(message: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    let msg_c = match CString::new(message) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Invalid permission revoked message string: {}", e);
+            return;
+        }
+    };
+
+    unsafe {
+        ffi::notifications_show_permission_revoked(msg_c.as_ptr());
+    }
+
+    debug!("Showed permission revoked notification");
+}
+
+/// Show permission revoked notification (non-macOS, text only).
+#[cfg(not(target_os = "macos"))]
+pub fn show_permission_revoked_notification(message: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit("Recording stopped", message);
+}
+
 /// Show notification when OBS download starts
 #[cfg(target_os = "macos")]
 pub fn show_obs_download_started_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_obs_download_started();
     }
@@ -354,6 +490,9 @@ pub fn show_obs_download_started_notification() {
 /// Show OBS download started notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_obs_download_started_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit(
         "Downloading OBS",
         "Preparing capture components. This may take a minute.",
@@ -363,6 +502,9 @@ pub fn show_obs_download_started_notification() {
 /// Show notification warning that many segments are queued because uploads are paused
 #[cfg(target_os = "macos")]
 pub fn show_upload_queue_warning_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_upload_queue_warning();
     }
@@ -373,15 +515,88 @@ pub fn show_upload_queue_warning_notification() {
 /// Show upload queue warning notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_upload_queue_warning_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
     emit(
         "Uploads paused",
         "Many segments are waiting to upload — resume uploads from the tray menu.",
     );
 }
 
+/// Show notification that uploads are not getting through (network down or endpoint
+/// failing) while segments keep accumulating on disk. Carries an "Open Folder" action
+/// that reports `NotificationAction::OpenFolder`.
+#[cfg(target_os = "macos")]
+pub fn show_upload_stalled_notification(pending: usize) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    unsafe {
+        ffi::notifications_show_upload_stalled(pending.min(u32::MAX as usize) as u32);
+    }
+
+    debug!("Showed upload stalled notification ({} pending)", pending);
+}
+
+/// Show upload stalled notification (non-macOS). The freedesktop/toast paths carry no
+/// action buttons, so this is text only.
+#[cfg(not(target_os = "macos"))]
+pub fn show_upload_stalled_notification(pending: usize) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit(
+        "Uploads stalled",
+        &format!(
+            "{pending} segments are waiting to upload. Check your network connection; \
+             recordings are kept on disk until they upload."
+        ),
+    );
+}
+
+/// The upload server refused a segment outright (e.g. HTTP 403), so it won't be retried.
+#[cfg(target_os = "macos")]
+pub fn show_upload_rejected_notification(reason: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    let reason_c = match CString::new(reason) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Invalid upload rejection reason: {}", e);
+            return;
+        }
+    };
+
+    unsafe {
+        ffi::notifications_show_upload_rejected(reason_c.as_ptr());
+    }
+
+    debug!("Showed upload rejected notification ({})", reason);
+}
+
+/// The upload server refused a segment outright (non-macOS).
+#[cfg(not(target_os = "macos"))]
+pub fn show_upload_rejected_notification(reason: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit(
+        "Upload rejected",
+        &format!(
+            "The upload server refused a recording ({reason}) and it won't be retried; \
+             it is kept on disk."
+        ),
+    );
+}
+
 /// Show notification when post-wizard setup starts
 #[cfg(target_os = "macos")]
 pub fn show_setup_configuring_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_setup_configuring();
     }
@@ -392,6 +607,9 @@ pub fn show_setup_configuring_notification() {
 /// Show setup configuring notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_setup_configuring_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit(
         "Setting up Crowd-Cast",
         "Configuring components in the background. OBS installation will start shortly.",
@@ -401,6 +619,9 @@ pub fn show_setup_configuring_notification() {
 /// Show notification when capture sources are refreshed
 #[cfg(target_os = "macos")]
 pub fn show_sources_refreshed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_sources_refreshed();
     }
@@ -411,12 +632,18 @@ pub fn show_sources_refreshed_notification() {
 /// Show sources refreshed notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_sources_refreshed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Sources refreshed", "Capture sources updated.");
 }
 
 /// Show notification when recording is paused due to user inactivity
 #[cfg(target_os = "macos")]
 pub fn show_idle_paused_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_idle_paused();
     }
@@ -427,12 +654,18 @@ pub fn show_idle_paused_notification() {
 /// Show idle paused notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_idle_paused_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording paused (idle)", "");
 }
 
 /// Show notification when recording resumes after user activity detected
 #[cfg(target_os = "macos")]
 pub fn show_idle_resumed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_idle_resumed();
     }
@@ -443,9 +676,48 @@ pub fn show_idle_resumed_notification() {
 /// Show idle resumed notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_idle_resumed_notification() {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     emit("Recording resumed", "");
 }
 
+/// No target app is running any more, so the recording was paused (until one is back)
+/// or stopped (`capture.on_target_exit`).
+#[cfg(target_os = "macos")]
+pub fn show_target_app_exited_notification(stopped: bool) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
+    unsafe {
+        ffi::notifications_show_target_app_exited(i32::from(stopped));
+    }
+
+    debug!(
+        "Showed target app exited notification (stopped: {})",
+        stopped
+    );
+}
+
+/// No target app is running any more (non-macOS).
+#[cfg(not(target_os = "macos"))]
+pub fn show_target_app_exited_notification(stopped: bool) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
+    if stopped {
+        emit(
+            "Recording stopped",
+            "None of your selected apps is running.",
+        );
+    } else {
+        emit(
+            "Recording paused",
+            "None of your selected apps is running. Recording resumes when one is reopened.",
+        );
+    }
+}
+
 /// Low disk space warning. No macOS toast yet (the engine logs it regardless);
 /// add an FFI toast here if/when macOS distribution needs one.
 #[cfg(target_os = "macos")]
@@ -454,12 +726,105 @@ pub fn show_low_disk_notification(_free_mb: u64) {}
 /// Low disk space warning (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_low_disk_notification(free_mb: u64) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     emit(
         "Low disk space",
         &format!("{free_mb} MB free. Recording may stop soon."),
     );
 }
 
+/// The microphone level hasn't moved for `secs` while recording
+#[cfg(target_os = "macos")]
+pub fn show_audio_flat_notification(secs: u64) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    unsafe {
+        ffi::notifications_show_audio_flat(secs.min(u32::MAX as u64) as u32);
+    }
+
+    debug!("Showed audio flat notification ({}s)", secs);
+}
+
+/// The microphone level hasn't moved for `secs` while recording (non-macOS).
+#[cfg(not(target_os = "macos"))]
+pub fn show_audio_flat_notification(secs: u64) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit(
+        "Microphone level stuck",
+        &format!(
+            "The microphone level hasn't changed for {secs}s. Check the microphone and audio device."
+        ),
+    );
+}
+
+/// Capture sources for some of the selected apps couldn't be created, so those apps
+/// aren't being recorded
+#[cfg(target_os = "macos")]
+pub fn show_capture_sources_failed_notification(apps: &[String]) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    let apps_c = match CString::new(apps.join(", ")) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Invalid capture sources failed app list: {}", e);
+            return;
+        }
+    };
+
+    unsafe {
+        ffi::notifications_show_capture_sources_failed(apps_c.as_ptr());
+    }
+
+    debug!("Showed capture sources failed notification ({:?})", apps);
+}
+
+/// Capture sources for some of the selected apps couldn't be created (non-macOS).
+#[cfg(not(target_os = "macos"))]
+pub fn show_capture_sources_failed_notification(apps: &[String]) {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit(
+        "Some apps aren't being recorded",
+        &format!(
+            "Couldn't capture {}. The other apps still record.",
+            apps.join(", ")
+        ),
+    );
+}
+
+/// Another agent already holds the single-instance lock. Waits briefly for the toast to
+/// be handed to the notification center, since the process exits right after.
+#[cfg(target_os = "macos")]
+pub fn show_already_running_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    unsafe {
+        ffi::notifications_show_already_running();
+    }
+
+    debug!("Showed already running notification");
+}
+
+/// Another agent already holds the single-instance lock (non-macOS).
+#[cfg(not(target_os = "macos"))]
+pub fn show_already_running_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
+    emit(
+        "crowd-cast is already running",
+        "This copy exited so the two don't record over each other.",
+    );
+}
+
 /// Feedback toast for a manual "Check for Updates" (macOS uses Sparkle's own UI).
 #[cfg(target_os = "macos")]
 pub fn show_update_check_notification(_message: &str) {}
@@ -469,12 +834,18 @@ pub fn show_update_check_notification(_message: &str) {}
 /// drives the silent check and we report progress/result via this toast.
 #[cfg(not(target_os = "macos"))]
 pub fn show_update_check_notification(message: &str) {
+    if !allowed(Priority::Important) {
+        return;
+    }
     emit(message, "");
 }
 
 /// Show notification when an update is being installed
 #[cfg(target_os = "macos")]
 pub fn show_update_installing_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
     unsafe {
         ffi::notifications_show_update_installing();
     }
@@ -485,6 +856,9 @@ pub fn show_update_installing_notification() {
 /// Show update installing notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_update_installing_notification() {
+    if !allowed(Priority::Important) {
+        return;
+    }
     emit(
         "Update Available",
         "A new version of CrowdCast is being installed. The app will restart shortly.",
@@ -494,6 +868,9 @@ pub fn show_update_installing_notification() {
 /// Show notification after a background update completed
 #[cfg(target_os = "macos")]
 pub fn show_update_completed_notification(version: &str, build: &str) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     let version_c = CString::new(version).unwrap_or_default();
     let build_c = CString::new(build).unwrap_or_default();
     unsafe {
@@ -508,6 +885,9 @@ pub fn show_update_completed_notification(version: &str, build: &str) {
 /// Show update completed notification (non-macOS).
 #[cfg(not(target_os = "macos"))]
 pub fn show_update_completed_notification(version: &str, build: &str) {
+    if !allowed(Priority::Routine) {
+        return;
+    }
     // Omit "(build ...)" when the caller has no platform build string, rather than rendering
     // an ugly empty build suffix.
     let body = match (version.is_empty(), build.is_empty()) {
@@ -550,3 +930,42 @@ pub fn is_authorized() -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_level_filters_by_priority() {
+        assert!(level_allows(NotificationLevel::All, Priority::Routine));
+        assert!(level_allows(NotificationLevel::All, Priority::Important));
+        assert!(!level_allows(
+            NotificationLevel::Important,
+            Priority::Routine
+        ));
+        assert!(level_allows(
+            NotificationLevel::Important,
+            Priority::Important
+        ));
+        assert!(!level_allows(NotificationLevel::None, Priority::Routine));
+        assert!(!level_allows(NotificationLevel::None, Priority::Important));
+
+        // Do not disturb: every helper returns without reaching the platform backend.
+        set_notification_level(NotificationLevel::None);
+        assert_eq!(notification_level(), NotificationLevel::None);
+        assert!(!allowed(Priority::Important));
+        show_display_change_notification("Built-in", "External", 2);
+        show_permissions_missing_notification("");
+        show_upload_stalled_notification(3);
+        show_low_disk_notification(100);
+        show_audio_flat_notification(60);
+        show_recording_started_notification();
+        show_idle_paused_notification();
+        show_update_completed_notification("1.0.0", "");
+
+        set_notification_level(NotificationLevel::Important);
+        assert_eq!(notification_level(), NotificationLevel::Important);
+        set_notification_level(NotificationLevel::All);
+        assert_eq!(notification_level(), NotificationLevel::All);
+    }
+}
@@ -16,6 +16,12 @@ static BOOL g_initialized = NO;
 
 // Category identifiers
 static NSString* const CATEGORY_DISPLAY_CHANGE = @"DISPLAY_CHANGE";
+static NSString* const CATEGORY_UPLOAD_STALLED = @"UPLOAD_STALLED";
+static NSString* const CATEGORY_PERMISSION_REVOKED = @"PERMISSION_REVOKED";
+
+// Action identifiers (passed through to the Rust callback verbatim)
+static NSString* const ACTION_OPEN_FOLDER = @"open_folder";
+static NSString* const ACTION_OPEN_ACCESSIBILITY = @"open_accessibility";
 
 // Notification delegate to handle user responses
 @interface CrowdCastNotificationDelegate : NSObject <UNUserNotificationCenterDelegate>
@@ -52,6 +58,9 @@ didReceiveNotificationResponse:(UNNotificationResponse *)response
         } else if ([actionIdentifier isEqualToString:UNNotificationDismissActionIdentifier]) {
             // User dismissed the notification
             g_action_callback("dismiss", displayId);
+        } else {
+            // Custom action button (e.g. "open_folder")
+            g_action_callback([actionIdentifier UTF8String], displayId);
         }
     }
     
@@ -85,8 +94,30 @@ int notifications_init(NotificationActionCallback callback) {
             intentIdentifiers:@[]
             options:UNNotificationCategoryOptionNone];
         
-        // Register the category
-        [center setNotificationCategories:[NSSet setWithObject:displayChangeCategory]];
+        // Create category for upload stalled notifications ("Open Folder" button)
+        UNNotificationAction *openFolderAction = [UNNotificationAction
+            actionWithIdentifier:ACTION_OPEN_FOLDER
+            title:@"Open Folder"
+            options:UNNotificationActionOptionForeground];
+        UNNotificationCategory *uploadStalledCategory = [UNNotificationCategory
+            categoryWithIdentifier:CATEGORY_UPLOAD_STALLED
+            actions:@[openFolderAction]
+            intentIdentifiers:@[]
+            options:UNNotificationCategoryOptionNone];
+
+        // Create category for permission revoked notifications ("Open Settings" button)
+        UNNotificationAction *openAccessibilityAction = [UNNotificationAction
+            actionWithIdentifier:ACTION_OPEN_ACCESSIBILITY
+            title:@"Open Settings"
+            options:UNNotificationActionOptionForeground];
+        UNNotificationCategory *permissionRevokedCategory = [UNNotificationCategory
+            categoryWithIdentifier:CATEGORY_PERMISSION_REVOKED
+            actions:@[openAccessibilityAction]
+            intentIdentifiers:@[]
+            options:UNNotificationCategoryOptionNone];
+
+        // Register the categories
+        [center setNotificationCategories:[NSSet setWithObjects:displayChangeCategory, uploadStalledCategory, permissionRevokedCategory, nil]];
         
         // Request authorization
         [center requestAuthorizationWithOptions:(UNAuthorizationOptionAlert | UNAuthorizationOptionSound)
@@ -543,6 +574,221 @@ void notifications_show_upload_queue_warning(void) {
     }
 }
 
+// Show a notification when uploads are stalled while segments accumulate
+// pending: Number of segments waiting to upload
+void notifications_show_upload_stalled(uint32_t pending) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"Uploads Stalled";
+        content.body = [NSString stringWithFormat:
+            @"%u segments are waiting to upload. Check your network connection; recordings are kept on disk until they upload.",
+            pending];
+        content.categoryIdentifier = CATEGORY_UPLOAD_STALLED;
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
+// Show a notification when recording stopped because a permission was revoked mid-run
+void notifications_show_permission_revoked(const char* message) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"Recording Stopped";
+        if (message) {
+            content.body = [NSString stringWithUTF8String:message];
+        } else {
+            content.body = @"Recording stopped. A required permission was revoked.";
+        }
+        content.categoryIdentifier = CATEGORY_PERMISSION_REVOKED;
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
+// Show a warning when the microphone level has not moved for a while
+// secs: How long the level has been flat
+void notifications_show_audio_flat(uint32_t secs) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"Microphone Level Stuck";
+        content.body = [NSString stringWithFormat:
+            @"The microphone level hasn't changed for %us. Check the microphone and audio device.",
+            secs];
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
+// Show a warning when some selected apps couldn't get a capture source
+// apps: Comma-separated app names that aren't being recorded
+void notifications_show_capture_sources_failed(const char* apps) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"Some Apps Aren't Being Recorded";
+        content.body = [NSString stringWithFormat:
+            @"Couldn't capture %@. The other apps still record.",
+            apps ? [NSString stringWithUTF8String:apps] : @"some apps"];
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
+// Show a notification when the upload server refuses a recording for good
+void notifications_show_upload_rejected(const char* reason) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"Upload Rejected";
+        content.body = [NSString stringWithFormat:
+            @"The upload server refused a recording (%@) and it won't be retried; it is kept on disk.",
+            reason ? [NSString stringWithUTF8String:reason] : @"no reason given"];
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
+// Show a notification when another agent already holds the single-instance lock
+void notifications_show_already_running(void) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        content.title = @"crowd-cast Is Already Running";
+        content.body = @"This copy exited so the two don't record over each other.";
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        // The process exits right after this; wait for the request to be handed off.
+        dispatch_semaphore_t semaphore = dispatch_semaphore_create(0);
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+            dispatch_semaphore_signal(semaphore);
+        }];
+        dispatch_semaphore_wait(semaphore, dispatch_time(DISPATCH_TIME_NOW, 2 * NSEC_PER_SEC));
+    }
+}
+
+// Show a notification when the last target app exits (stopped: 1 if recording stopped, 0 if paused)
+void notifications_show_target_app_exited(int stopped) {
+    if (!g_initialized) {
+        NSLog(@"[CrowdCast] Notifications not initialized");
+        return;
+    }
+
+    @autoreleasepool {
+        UNMutableNotificationContent *content = [[UNMutableNotificationContent alloc] init];
+        if (stopped) {
+            content.title = @"Recording Stopped";
+            content.body = @"None of your selected apps is running.";
+        } else {
+            content.title = @"Recording Paused";
+            content.body = @"None of your selected apps is running. Recording resumes when one is reopened.";
+        }
+
+        NSString *identifier = [[NSUUID UUID] UUIDString];
+        UNNotificationRequest *request = [UNNotificationRequest
+            requestWithIdentifier:identifier
+            content:content
+            trigger:nil];
+
+        UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
+        [center addNotificationRequest:request withCompletionHandler:^(NSError * _Nullable error) {
+            if (error) {
+                NSLog(@"[CrowdCast] Failed to show notification: %@", error);
+            }
+        }];
+    }
+}
+
 // Check if notifications are authorized
 // Returns: 1 if authorized, 0 if not, -1 on error
 int notifications_is_authorized(void) {
@@ -7,6 +7,11 @@
 use anyhow::Result;
 use std::path::PathBuf;
 
+use crate::capture::DisplayInfo;
+
+/// Base tooltip shown on the tray icon.
+pub const TRAY_TOOLTIP: &str = "crowd-cast Agent";
+
 /// Actions that can be triggered by the user via the tray menu.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TrayAction {
@@ -17,7 +22,13 @@ pub enum TrayAction {
     SignIn,
     Settings,
     CheckForUpdates,
+    /// Open the recording output directory in the platform file manager.
+    OpenRecordingsFolder,
     ReportBug,
+    /// Switch capture to the display with this id (from the "Select Display" submenu).
+    SelectDisplay(u32),
+    /// Add a marker with this label to the recording (from the "Add Marker" submenu).
+    AddMarker(String),
     Quit,
 }
 
@@ -45,6 +56,9 @@ pub struct TrayDisplayState {
     pub icon_state: TrayIconState,
     /// Top-of-menu status line (e.g. "Status: Capturing (42 events)").
     pub status_text: String,
+    /// Extra tooltip line under the app name (e.g. "Recording issues: low disk space"),
+    /// empty for none.
+    pub tooltip_detail: String,
     /// Account display line (e.g. "Signed in as user@example.com"), empty when signed out.
     pub account_text: String,
     /// Label for the sign-in / sign-out menu item.
@@ -59,6 +73,22 @@ pub struct TrayDisplayState {
     pub uploads_text: String,
     /// Whether "Check for Updates" should be enabled.
     pub can_check_updates: bool,
+    /// Entries for the "Select Display" submenu; empty hides it (only macOS switches
+    /// displays by id, so other platforms leave this empty).
+    pub displays: Vec<DisplayInfo>,
+    /// Labels for the "Add Marker" submenu (`recording.markers`); empty hides it.
+    pub markers: Vec<String>,
+    /// Whether the "Add Marker" entries are clickable (only while recording).
+    pub can_add_marker: bool,
+}
+
+/// Full tooltip for the tray icon: the app name, plus the detail line when present.
+pub fn tooltip_text(detail: &str) -> String {
+    if detail.is_empty() {
+        TRAY_TOOLTIP.to_string()
+    } else {
+        format!("{}\n{}", TRAY_TOOLTIP, detail)
+    }
 }
 
 /// Result of polling the platform tray for events.
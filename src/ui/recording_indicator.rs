@@ -0,0 +1,135 @@
+//! On-screen recording indicator (`ui.recording_indicator`)
+//!
+//! A small always-on-top dot shown while the engine reports `Capturing`, so contributors
+//! can tell at a glance that input is being recorded. The macOS overlay lives in
+//! `indicator_darwin.m`: click-through, on every Space, and excluded from screen capture
+//! so OBS never records it. Other platforms have no overlay yet; the setting is ignored
+//! there.
+
+use tracing::{debug, info};
+
+use crate::sync::EngineStatus;
+
+#[cfg(target_os = "macos")]
+#[link(name = "indicator_darwin", kind = "static")]
+extern "C" {
+    fn recording_indicator_set_visible(visible: bool);
+}
+
+/// Whether the indicator should be visible after `status`, given whether it is now.
+/// Health checks and upload progress leave it as it is; any other status hides it.
+fn visible_after(status: &EngineStatus, visible: bool) -> bool {
+    match status {
+        EngineStatus::Capturing { .. } => true,
+        EngineStatus::Health(_)
+        | EngineStatus::Uploading { .. }
+        | EngineStatus::UploadProgress { .. } => visible,
+        EngineStatus::Idle
+        | EngineStatus::Paused
+        | EngineStatus::RecordingBlocked
+        | EngineStatus::WaitingForOBS
+        | EngineStatus::Draining { .. }
+        | EngineStatus::Error(_) => false,
+    }
+}
+
+/// Shows and hides the overlay as engine statuses arrive. Must be driven from the tray's
+/// (main) thread.
+pub struct RecordingIndicator {
+    enabled: bool,
+    visible: bool,
+}
+
+impl RecordingIndicator {
+    pub fn new(enabled: bool) -> Self {
+        if enabled && cfg!(not(target_os = "macos")) {
+            info!("ui.recording_indicator is only supported on macOS; ignoring it");
+        }
+        Self {
+            enabled: enabled && cfg!(target_os = "macos"),
+            visible: false,
+        }
+    }
+
+    /// Apply a new engine status. Returns the new visibility when it changed.
+    pub fn update(&mut self, status: &EngineStatus) -> Option<bool> {
+        if !self.enabled {
+            return None;
+        }
+        let visible = visible_after(status, self.visible);
+        if visible == self.visible {
+            return None;
+        }
+        self.visible = visible;
+        debug!(
+            "Recording indicator {}",
+            if visible { "shown" } else { "hidden" }
+        );
+        set_visible(visible);
+        Some(visible)
+    }
+}
+
+#[cfg(all(target_os = "macos", not(test)))]
+fn set_visible(visible: bool) {
+    unsafe { recording_indicator_set_visible(visible) }
+}
+
+#[cfg(any(not(target_os = "macos"), test))]
+fn set_visible(_visible: bool) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{EventTypeCounts, RecordingHealth};
+
+    fn capturing() -> EngineStatus {
+        EngineStatus::Capturing {
+            event_count: 0,
+            counts: EventTypeCounts::default(),
+        }
+    }
+
+    #[test]
+    fn indicator_follows_capture_state() {
+        let mut indicator = RecordingIndicator {
+            enabled: true,
+            visible: false,
+        };
+        assert_eq!(indicator.update(&EngineStatus::Idle), None);
+        assert_eq!(indicator.update(&capturing()), Some(true));
+        // Repeated capture updates don't re-show it.
+        assert_eq!(indicator.update(&capturing()), None);
+
+        // Health and upload progress don't change what's being captured.
+        let health = RecordingHealth {
+            sources_active: true,
+            frames_advancing: true,
+            disk_ok: true,
+            uploads_keeping_up: true,
+            audio_ok: true,
+        };
+        assert_eq!(indicator.update(&EngineStatus::Health(health)), None);
+        assert_eq!(
+            indicator.update(&EngineStatus::Uploading {
+                chunk_id: "seg0001".to_string()
+            }),
+            None
+        );
+        assert!(indicator.visible);
+
+        assert_eq!(indicator.update(&EngineStatus::Paused), Some(false));
+        assert_eq!(indicator.update(&capturing()), Some(true));
+        // Input stops while the capture source is blocked.
+        assert_eq!(
+            indicator.update(&EngineStatus::RecordingBlocked),
+            Some(false)
+        );
+        assert_eq!(indicator.update(&capturing()), Some(true));
+        assert_eq!(indicator.update(&EngineStatus::Idle), Some(false));
+
+        // Disabled: never shown.
+        let mut disabled = RecordingIndicator::new(false);
+        assert_eq!(disabled.update(&capturing()), None);
+    }
+}
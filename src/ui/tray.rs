@@ -7,6 +7,7 @@
 use anyhow::Result;
 use image::imageops::FilterType;
 use image::RgbaImage;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{broadcast, mpsc};
@@ -15,8 +16,10 @@ use tracing::{debug, error, info, warn};
 use super::platform_tray::{
     PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths, TrayIconState,
 };
+use super::recording_indicator::RecordingIndicator;
 use super::UpdaterController;
-use crate::sync::{EngineCommand, EngineStatus};
+use crate::capture::DisplayInfo;
+use crate::sync::{EngineCommand, EngineStatus, EventTypeCounts, RecordingHealth};
 
 // ---------------------------------------------------------------------------
 // Globals shared with main.rs
@@ -59,6 +62,7 @@ fn status_blocks_immediate_update(status: &EngineStatus) -> bool {
         EngineStatus::Capturing { .. }
             | EngineStatus::RecordingBlocked
             | EngineStatus::Uploading { .. }
+            | EngineStatus::Draining { .. }
     )
 }
 
@@ -154,6 +158,43 @@ fn open_url(url: &str) {
     warn!("No URL opener available on this platform; visit {}", url);
 }
 
+/// The command that shows `path` in the platform file manager, or `None` where there is
+/// none.
+fn open_folder_command(path: &std::path::Path) -> Option<std::process::Command> {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "linux") {
+        "xdg-open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        return None;
+    };
+    let mut command = std::process::Command::new(program);
+    command.arg(path);
+    Some(command)
+}
+
+/// Open a local folder in the platform file manager. Failures are logged, never fatal.
+pub fn open_folder(path: &std::path::Path) {
+    let Some(mut command) = open_folder_command(path) else {
+        warn!("No file manager available on this platform; see {:?}", path);
+        return;
+    };
+    if let Err(e) = command.spawn() {
+        warn!("Failed to open folder {:?}: {}", path, e);
+    }
+}
+
+/// Open the recording output directory, creating it first if no recording has yet.
+fn open_recordings_folder(dir: &std::path::Path) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        warn!("Failed to create recordings folder {:?}: {}", dir, e);
+        return;
+    }
+    open_folder(dir);
+}
+
 // ---------------------------------------------------------------------------
 // Auth display helper
 // ---------------------------------------------------------------------------
@@ -191,6 +232,10 @@ pub struct TrayApp {
     updater: UpdaterController,
     last_updater_can_check: Option<bool>,
     last_status: Option<EngineStatus>,
+    /// Latest `EngineStatus::Health` for the running recording (cleared when idle)
+    last_health: Option<RecordingHealth>,
+    /// Latest `EngineStatus::UploadProgress` (sent, total) of each running upload, by chunk id
+    upload_progress: BTreeMap<String, (u64, u64)>,
     pending_prepare_for_update: bool,
     last_update_check: std::time::Instant,
     uploads_paused: bool,
@@ -200,6 +245,19 @@ pub struct TrayApp {
     account_display_text: String,
     sign_action_display_text: String,
     auth_configured: bool,
+    /// Show the per-type event breakdown in the status line (`recording.show_event_breakdown`)
+    show_event_breakdown: bool,
+    /// Labels for the "Add Marker" submenu (`recording.markers`)
+    markers: Vec<String>,
+    /// Connected displays for the "Select Display" submenu, refreshed from the engine
+    displays: Vec<DisplayInfo>,
+    displays_tx: mpsc::UnboundedSender<Vec<DisplayInfo>>,
+    displays_rx: mpsc::UnboundedReceiver<Vec<DisplayInfo>>,
+    last_displays_request: Option<std::time::Instant>,
+    /// On-screen dot shown while capturing (`ui.recording_indicator`)
+    recording_indicator: RecordingIndicator,
+    /// Recording output directory, for "Open Recordings Folder"
+    output_dir: PathBuf,
 }
 
 impl TrayApp {
@@ -225,6 +283,23 @@ impl TrayApp {
             .map(|s| s.trim() == "true")
             .unwrap_or(false);
 
+        let config = crate::config::Config::load().ok();
+        let output_dir = config
+            .as_ref()
+            .map(crate::config::Config::output_directory)
+            .unwrap_or_else(|| crate::config::Config::default().output_directory());
+        let (show_event_breakdown, markers, recording_indicator) = config
+            .map(|c| {
+                (
+                    c.recording.show_event_breakdown,
+                    c.recording.markers,
+                    c.ui.recording_indicator,
+                )
+            })
+            .unwrap_or_default();
+
+        let (displays_tx, displays_rx) = mpsc::unbounded_channel();
+
         info!("System tray created");
 
         Ok(Self {
@@ -234,6 +309,8 @@ impl TrayApp {
             updater,
             last_updater_can_check: None,
             last_status: None,
+            last_health: None,
+            upload_progress: BTreeMap::new(),
             pending_prepare_for_update: false,
             last_update_check: std::time::Instant::now(),
             uploads_paused,
@@ -242,6 +319,14 @@ impl TrayApp {
             account_display_text,
             sign_action_display_text,
             auth_configured,
+            show_event_breakdown,
+            markers,
+            displays: Vec::new(),
+            displays_tx,
+            displays_rx,
+            last_displays_request: None,
+            recording_indicator: RecordingIndicator::new(recording_indicator),
+            output_dir,
         })
     }
 
@@ -251,8 +336,11 @@ impl TrayApp {
             Some(EngineStatus::Idle) => {
                 ("Status: Idle".to_string(), TrayIconState::Idle, true, false)
             }
-            Some(EngineStatus::Capturing { event_count }) => (
-                format!("Status: Capturing ({} events)", event_count),
+            Some(EngineStatus::Capturing {
+                event_count,
+                counts,
+            }) => (
+                capturing_status_text(*event_count, counts, self.show_event_breakdown),
                 TrayIconState::Recording,
                 false,
                 true,
@@ -281,18 +369,50 @@ impl TrayApp {
                 true,
                 false,
             ),
+            Some(EngineStatus::Draining { pending }) => (
+                format!("Status: Finishing uploads ({} left)...", pending),
+                TrayIconState::Idle,
+                false,
+                false,
+            ),
             Some(EngineStatus::Error(msg)) => (
                 format!("Status: Error - {}", truncate_str(msg, 30)),
                 TrayIconState::Idle,
                 true,
                 false,
             ),
-            None => ("Status: Idle".to_string(), TrayIconState::Idle, true, false),
+            // Health and upload progress never become `last_status` (see `update_status`).
+            Some(EngineStatus::Health(_) | EngineStatus::UploadProgress { .. }) | None => {
+                ("Status: Idle".to_string(), TrayIconState::Idle, true, false)
+            }
         };
 
+        // Health refines a running recording: any failing sub-check turns the icon to
+        // the blocked color and is spelled out in the tooltip.
+        let recording = matches!(
+            self.last_status,
+            Some(EngineStatus::Capturing { .. } | EngineStatus::RecordingBlocked)
+        );
+        let health = self.last_health.filter(|_| recording);
+        let icon_state = match health {
+            Some(h) if !h.is_healthy() => TrayIconState::Blocked,
+            _ => icon_state,
+        };
+        let tooltip_detail = health
+            .map(|h| health_detail_text(&h))
+            .into_iter()
+            .chain(
+                self.upload_progress
+                    .iter()
+                    .map(|(chunk_id, (sent, total))| upload_progress_text(chunk_id, *sent, *total)),
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+
         TrayDisplayState {
             icon_state,
             status_text,
+            tooltip_detail,
             account_text: self.account_display_text.clone(),
             sign_action_text: self.sign_action_display_text.clone(),
             auth_action_enabled: self.auth_configured,
@@ -304,6 +424,35 @@ impl TrayApp {
                 "Pause Uploads".to_string()
             },
             can_check_updates: self.updater.can_check_for_updates(),
+            displays: self.displays.clone(),
+            markers: self.markers.clone(),
+            // A recording is running (possibly paused) exactly when it can be stopped.
+            can_add_marker: can_stop,
+        }
+    }
+
+    /// Periodically ask the engine for the connected displays, and redraw the menu
+    /// when the answer changes (hotplug, or a switch moved the current display).
+    fn refresh_displays(&mut self) {
+        const DISPLAYS_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+        if self
+            .last_displays_request
+            .is_none_or(|t| t.elapsed() >= DISPLAYS_REFRESH_INTERVAL)
+        {
+            let respond_to = self.displays_tx.clone();
+            let _ = self
+                .cmd_tx
+                .try_send(EngineCommand::ListDisplays { respond_to });
+            self.last_displays_request = Some(std::time::Instant::now());
+        }
+
+        let mut latest = None;
+        while let Ok(displays) = self.displays_rx.try_recv() {
+            latest = Some(displays);
+        }
+        if let Some(displays) = latest.filter(|d| *d != self.displays) {
+            self.displays = displays;
+            self.refresh_display();
         }
     }
 
@@ -411,13 +560,36 @@ impl TrayApp {
                         }
                         self.last_update_check = std::time::Instant::now();
                     }
+                    TrayAction::OpenRecordingsFolder => {
+                        info!("Recordings folder {:?} opened via tray", self.output_dir);
+                        open_recordings_folder(&self.output_dir);
+                    }
                     TrayAction::ReportBug => {
                         info!("Bug report requested via tray");
                         open_url(&bug_report_url());
                     }
+                    TrayAction::SelectDisplay(display_id) => {
+                        info!("Display {} selected via tray", display_id);
+                        if let Err(e) = self
+                            .cmd_tx
+                            .try_send(EngineCommand::SwitchToDisplay { display_id })
+                        {
+                            error!("Failed to send switch display command: {}", e);
+                        }
+                        // Re-query soon so the checkmark follows the switch.
+                        self.last_displays_request = None;
+                    }
+                    TrayAction::AddMarker(label) => {
+                        info!("Marker '{}' added via tray", label);
+                        if let Err(e) = self.cmd_tx.try_send(EngineCommand::AddMarker { label }) {
+                            error!("Failed to send add marker command: {}", e);
+                        }
+                    }
                 },
             }
 
+            self.refresh_displays();
+
             // Check if sign-in completed on the background thread
             if SIGN_IN_COMPLETED.swap(false, Ordering::SeqCst) {
                 self.update_auth_display();
@@ -507,6 +679,36 @@ impl TrayApp {
 
     /// Process a new engine status: update internal state and refresh the display.
     fn update_status(&mut self, status: &EngineStatus) {
+        self.recording_indicator.update(status);
+        if let EngineStatus::Health(health) = status {
+            self.last_health = Some(*health);
+            self.refresh_display();
+            debug!("Tray recording health updated: {:?}", health);
+            return;
+        }
+        if let EngineStatus::UploadProgress {
+            chunk_id,
+            sent,
+            total,
+        } = status
+        {
+            // Each upload reports on its own; a finished one (or the end marker) only
+            // removes its own line.
+            if sent < total {
+                self.upload_progress
+                    .insert(chunk_id.clone(), (*sent, *total));
+            } else {
+                self.upload_progress.remove(chunk_id);
+            }
+            self.refresh_display();
+            return;
+        }
+        // The engine forgets health whenever a recording stops or pauses and resends it
+        // once one runs again, so drop it here too rather than show the previous
+        // recording's health until then.
+        if matches!(status, EngineStatus::Idle | EngineStatus::Paused) {
+            self.last_health = None;
+        }
         self.last_status = Some(status.clone());
 
         self.updater
@@ -518,13 +720,20 @@ impl TrayApp {
             "Tray status updated: {}",
             match status {
                 EngineStatus::Idle => "Idle".to_string(),
-                EngineStatus::Capturing { event_count } =>
+                EngineStatus::Capturing { event_count, .. } =>
                     format!("Capturing ({} events)", event_count),
                 EngineStatus::Paused => "Paused".to_string(),
                 EngineStatus::RecordingBlocked => "RecordingBlocked".to_string(),
                 EngineStatus::WaitingForOBS => "WaitingForOBS".to_string(),
                 EngineStatus::Uploading { chunk_id } => format!("Uploading {}", chunk_id),
+                EngineStatus::Draining { pending } => format!("Draining ({} pending)", pending),
                 EngineStatus::Error(msg) => format!("Error: {}", msg),
+                EngineStatus::Health(health) => format!("Health {:?}", health),
+                EngineStatus::UploadProgress {
+                    chunk_id,
+                    sent,
+                    total,
+                } => upload_progress_text(chunk_id, *sent, *total),
             }
         );
     }
@@ -655,10 +864,11 @@ fn create_platform_tray(icon_paths: &TrayIconPaths) -> Result<Box<dyn PlatformTr
 #[cfg(test)]
 mod tests {
     use super::{
-        build_bug_report_url, next_prepare_for_update_action, status_blocks_immediate_update,
-        status_needs_prepare_for_update, PrepareForUpdateAction,
+        build_bug_report_url, capturing_status_text, next_prepare_for_update_action,
+        open_folder_command, status_blocks_immediate_update, status_needs_prepare_for_update,
+        upload_progress_text, PrepareForUpdateAction,
     };
-    use crate::sync::EngineStatus;
+    use crate::sync::{EngineStatus, EventTypeCounts};
 
     #[test]
     fn bug_report_url_encodes_title_and_body() {
@@ -697,10 +907,34 @@ mod tests {
         assert!(title.contains("macos"));
     }
 
+    #[test]
+    fn open_folder_uses_the_platform_file_manager() {
+        let path = std::path::Path::new("/tmp/crowd-cast-recordings");
+        let command = open_folder_command(path);
+        let expected = if cfg!(target_os = "macos") {
+            Some("open")
+        } else if cfg!(target_os = "linux") {
+            Some("xdg-open")
+        } else if cfg!(target_os = "windows") {
+            Some("explorer")
+        } else {
+            None
+        };
+        assert_eq!(
+            command.as_ref().and_then(|c| c.get_program().to_str()),
+            expected
+        );
+        if let Some(command) = command {
+            // The path is a single argument, never split or shell-parsed.
+            assert_eq!(command.get_args().collect::<Vec<_>>(), [path.as_os_str()]);
+        }
+    }
+
     #[test]
     fn update_blocking_statuses_match_policy() {
         assert!(status_blocks_immediate_update(&EngineStatus::Capturing {
-            event_count: 1
+            event_count: 1,
+            counts: Default::default(),
         }));
         assert!(status_blocks_immediate_update(
             &EngineStatus::RecordingBlocked
@@ -721,7 +955,8 @@ mod tests {
     #[test]
     fn prepare_for_update_only_targets_active_recording_states() {
         assert!(status_needs_prepare_for_update(&EngineStatus::Capturing {
-            event_count: 1
+            event_count: 1,
+            counts: Default::default(),
         }));
         assert!(status_needs_prepare_for_update(&EngineStatus::Paused));
         assert!(status_needs_prepare_for_update(
@@ -740,7 +975,13 @@ mod tests {
     #[test]
     fn prepare_for_update_action_is_one_shot_and_status_driven() {
         assert_eq!(
-            next_prepare_for_update_action(true, Some(&EngineStatus::Capturing { event_count: 1 })),
+            next_prepare_for_update_action(
+                true,
+                Some(&EngineStatus::Capturing {
+                    event_count: 1,
+                    counts: Default::default(),
+                })
+            ),
             PrepareForUpdateAction::SendCommand
         );
         assert_eq!(
@@ -769,12 +1010,85 @@ mod tests {
             PrepareForUpdateAction::Wait
         );
     }
+
+    #[test]
+    fn upload_progress_text_shows_percentage() {
+        let mib = 1024 * 1024;
+        assert_eq!(
+            upload_progress_text("s_seg0002", 30 * mib, 120 * mib),
+            "Uploading s_seg0002: 25% of 120.0 MB"
+        );
+        assert_eq!(
+            upload_progress_text("s_seg0002", 0, 0),
+            "Uploading s_seg0002: 100% of 0.0 MB"
+        );
+    }
+
+    #[test]
+    fn capturing_status_shows_breakdown_only_when_enabled() {
+        let counts = EventTypeCounts {
+            keys: 4,
+            mouse_moves: 10,
+            mouse_clicks: 2,
+            scrolls: 1,
+            gestures: 3,
+        };
+        assert_eq!(
+            capturing_status_text(20, &counts, false),
+            "Status: Capturing (20 events)"
+        );
+        assert_eq!(
+            capturing_status_text(20, &counts, true),
+            "Status: Capturing (20 events: 4 keys, 10 moves, 2 clicks, 1 scrolls, 3 gestures)"
+        );
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Icon generation (cross-platform)
 // ---------------------------------------------------------------------------
 
+/// Status line while capturing, optionally with the per-type breakdown.
+fn capturing_status_text(event_count: usize, counts: &EventTypeCounts, breakdown: bool) -> String {
+    if breakdown {
+        format!(
+            "Status: Capturing ({} events: {} keys, {} moves, {} clicks, {} scrolls, {} gestures)",
+            event_count,
+            counts.keys,
+            counts.mouse_moves,
+            counts.mouse_clicks,
+            counts.scrolls,
+            counts.gestures
+        )
+    } else {
+        format!("Status: Capturing ({} events)", event_count)
+    }
+}
+
+/// Tooltip line describing a recording's health.
+fn health_detail_text(health: &RecordingHealth) -> String {
+    if health.is_healthy() {
+        "Recording healthy".to_string()
+    } else {
+        format!("Recording issues: {}", health.problems().join(", "))
+    }
+}
+
+/// Tooltip line for an upload in progress
+fn upload_progress_text(chunk_id: &str, sent: u64, total: u64) -> String {
+    let percent = if total == 0 {
+        100
+    } else {
+        sent.saturating_mul(100) / total
+    };
+    format!(
+        "Uploading {}: {}% of {:.1} MB",
+        chunk_id,
+        percent.min(100),
+        total as f64 / (1024.0 * 1024.0)
+    )
+}
+
 /// Truncate a string to a maximum length, adding ellipsis if needed
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
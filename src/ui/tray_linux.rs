@@ -30,6 +30,7 @@ use tracing::{info, warn};
 
 use super::platform_tray::{
     PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths, TrayIconState,
+    TRAY_TOOLTIP,
 };
 
 /// Set by the SIGINT handler (via `request_tray_exit`) to break the tray loop, the Linux
@@ -52,6 +53,7 @@ pub fn request_tray_exit() {
 struct TrayModel {
     icon_state: TrayIconState,
     status_text: String,
+    tooltip_detail: String,
     account_text: String,
     sign_action_text: String,
     auth_enabled: bool,
@@ -59,6 +61,8 @@ struct TrayModel {
     can_stop: bool,
     uploads_text: String,
     can_check_updates: bool,
+    markers: Vec<String>,
+    can_add_marker: bool,
 
     icon_idle: ksni::Icon,
     icon_recording: ksni::Icon,
@@ -88,7 +92,7 @@ impl ksni::Tray for TrayModel {
     }
 
     fn title(&self) -> String {
-        "crowd-cast Agent".into()
+        TRAY_TOOLTIP.into()
     }
 
     // Always "Active" so hosts that hide Passive items still show us.
@@ -102,14 +106,18 @@ impl ksni::Tray for TrayModel {
 
     fn tool_tip(&self) -> ksni::ToolTip {
         ksni::ToolTip {
-            title: "crowd-cast Agent".into(),
-            description: self.status_text.clone(),
+            title: TRAY_TOOLTIP.into(),
+            description: if self.tooltip_detail.is_empty() {
+                self.status_text.clone()
+            } else {
+                format!("{}\n{}", self.status_text, self.tooltip_detail)
+            },
             ..Default::default()
         }
     }
 
     fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
-        use ksni::menu::{MenuItem, StandardItem};
+        use ksni::menu::{MenuItem, StandardItem, SubMenu};
 
         let mut items: Vec<MenuItem<Self>> = Vec::new();
 
@@ -170,6 +178,33 @@ impl ksni::Tray for TrayModel {
             }
             .into(),
         );
+        if !self.markers.is_empty() {
+            let submenu = self
+                .markers
+                .iter()
+                .map(|label| {
+                    let action = TrayAction::AddMarker(label.clone());
+                    StandardItem {
+                        label: label.clone(),
+                        enabled: self.can_add_marker,
+                        activate: Box::new(move |m: &mut Self| {
+                            let _ = m.tx.send(action.clone());
+                        }),
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect();
+            items.push(
+                SubMenu {
+                    label: "Add Marker".into(),
+                    enabled: self.can_add_marker,
+                    submenu,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
 
         items.push(MenuItem::Separator);
 
@@ -206,6 +241,17 @@ impl ksni::Tray for TrayModel {
             }
             .into(),
         );
+        items.push(
+            StandardItem {
+                label: "Open Recordings Folder".into(),
+                enabled: true,
+                activate: Box::new(|m: &mut Self| {
+                    let _ = m.tx.send(TrayAction::OpenRecordingsFolder);
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
         items.push(
             StandardItem {
                 label: "Check for Updates".into(),
@@ -339,6 +385,7 @@ impl PlatformTray for LinuxTray {
         let model = TrayModel {
             icon_state: TrayIconState::Idle,
             status_text: "Status: Idle".to_string(),
+            tooltip_detail: String::new(),
             account_text: String::new(),
             sign_action_text: "Sign in with Google".to_string(),
             auth_enabled: true,
@@ -346,6 +393,8 @@ impl PlatformTray for LinuxTray {
             can_stop: false,
             uploads_text: "Pause Uploads".to_string(),
             can_check_updates: false,
+            markers: Vec::new(),
+            can_add_marker: false,
             icon_idle: self.icon_idle.clone(),
             icon_recording: self.icon_recording.clone(),
             icon_blocked: self.icon_blocked.clone(),
@@ -402,6 +451,7 @@ impl PlatformTray for LinuxTray {
 
         let icon_state = state.icon_state;
         let status_text = state.status_text.clone();
+        let tooltip_detail = state.tooltip_detail.clone();
         let account_text = state.account_text.clone();
         let sign_action_text = state.sign_action_text.clone();
         let auth_enabled = state.auth_action_enabled;
@@ -409,11 +459,14 @@ impl PlatformTray for LinuxTray {
         let can_stop = state.can_stop;
         let uploads_text = state.uploads_text.clone();
         let can_check_updates = state.can_check_updates;
+        let markers = state.markers.clone();
+        let can_add_marker = state.can_add_marker;
 
         // Runs on the ksni service thread; ksni re-renders icon + menu afterwards.
         handle.update(move |m: &mut TrayModel| {
             m.icon_state = icon_state;
             m.status_text = status_text;
+            m.tooltip_detail = tooltip_detail;
             m.account_text = account_text;
             m.sign_action_text = sign_action_text;
             m.auth_enabled = auth_enabled;
@@ -421,6 +474,8 @@ impl PlatformTray for LinuxTray {
             m.can_stop = can_stop;
             m.uploads_text = uploads_text;
             m.can_check_updates = can_check_updates;
+            m.markers = markers;
+            m.can_add_marker = can_add_marker;
         });
     }
 
@@ -6,13 +6,15 @@
 
 use anyhow::Result;
 use std::ffi::CString;
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, Ordering};
 use tracing::info;
 
 use super::platform_tray::{
-    PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths, TrayIconState,
+    tooltip_text, PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths,
+    TrayIconState, TRAY_TOOLTIP,
 };
 use super::tray_ffi::{self, Tray, TrayMenuItem};
+use crate::capture::DisplayInfo;
 
 // ---------------------------------------------------------------------------
 // Atomic flags set by C callbacks, read by poll()
@@ -23,10 +25,15 @@ static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
 static PANIC_REQUESTED: AtomicBool = AtomicBool::new(false);
 static CHECK_FOR_UPDATES_REQUESTED: AtomicBool = AtomicBool::new(false);
 static REPORT_BUG_REQUESTED: AtomicBool = AtomicBool::new(false);
+static OPEN_RECORDINGS_REQUESTED: AtomicBool = AtomicBool::new(false);
 static SETTINGS_REQUESTED: AtomicBool = AtomicBool::new(false);
 static TOGGLE_UPLOADS_REQUESTED: AtomicBool = AtomicBool::new(false);
 static SIGN_IN_REQUESTED: AtomicBool = AtomicBool::new(false);
 static MACOS_QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// The "Select Display" submenu item that was clicked; resolved to a display id in poll()
+static SELECTED_DISPLAY_ITEM: AtomicPtr<TrayMenuItem> = AtomicPtr::new(std::ptr::null_mut());
+/// The "Add Marker" submenu item that was clicked; resolved to a label in poll()
+static SELECTED_MARKER_ITEM: AtomicPtr<TrayMenuItem> = AtomicPtr::new(std::ptr::null_mut());
 
 // Last status-item health verdict seen by poll(), so transitions are logged
 // exactly once. -1 = nothing observed yet (distinct from the C layer's
@@ -69,6 +76,10 @@ unsafe extern "C" fn on_report_bug(_item: *mut TrayMenuItem) {
     REPORT_BUG_REQUESTED.store(true, Ordering::SeqCst);
 }
 
+unsafe extern "C" fn on_open_recordings(_item: *mut TrayMenuItem) {
+    OPEN_RECORDINGS_REQUESTED.store(true, Ordering::SeqCst);
+}
+
 unsafe extern "C" fn on_toggle_uploads(_item: *mut TrayMenuItem) {
     TOGGLE_UPLOADS_REQUESTED.store(true, Ordering::SeqCst);
 }
@@ -81,6 +92,14 @@ unsafe extern "C" fn on_settings(_item: *mut TrayMenuItem) {
     SETTINGS_REQUESTED.store(true, Ordering::SeqCst);
 }
 
+unsafe extern "C" fn on_select_display(item: *mut TrayMenuItem) {
+    SELECTED_DISPLAY_ITEM.store(item, Ordering::SeqCst);
+}
+
+unsafe extern "C" fn on_add_marker(item: *mut TrayMenuItem) {
+    SELECTED_MARKER_ITEM.store(item, Ordering::SeqCst);
+}
+
 unsafe extern "C" fn on_quit(_item: *mut TrayMenuItem) {
     MACOS_QUIT_REQUESTED.store(true, Ordering::SeqCst);
     unsafe {
@@ -130,11 +149,14 @@ const MENU_STOP: usize = 4;
 const MENU_UPLOADS: usize = 7;
 const MENU_SIGN_ACTION: usize = 8;
 // 9 = settings (text never changes)
-const MENU_UPDATES: usize = 10;
-// 11 = report bug (text never changes)
-// 12 = separator
-// 13 = quit
-// 14 = NULL terminator
+// 10 = open recordings folder (text never changes)
+const MENU_UPDATES: usize = 11;
+// 12 = report bug (text never changes)
+const MENU_SELECT_DISPLAY: usize = 13;
+const MENU_ADD_MARKER: usize = 14;
+// 15 = separator
+// 16 = quit
+// 17 = NULL terminator
 
 // ---------------------------------------------------------------------------
 // MacOSTray
@@ -144,15 +166,23 @@ pub struct MacOSTray {
     tray: Tray,
     // Owned data that must live as long as the tray
     icons: TrayIconCStrings,
-    _tooltip: CString,
+    tooltip: CString,
     menu_items: Vec<TrayMenuItem>,
     menu_strings: Vec<CString>,
+    /// "Select Display" submenu (NULL-terminated) and the display id behind each entry
+    display_items: Vec<TrayMenuItem>,
+    display_strings: Vec<CString>,
+    display_ids: Vec<u32>,
+    /// "Add Marker" submenu (NULL-terminated) and the label behind each entry
+    marker_items: Vec<TrayMenuItem>,
+    marker_strings: Vec<CString>,
+    marker_labels: Vec<String>,
 }
 
 impl MacOSTray {
     pub fn new(icon_paths: &TrayIconPaths) -> Result<Self> {
         let icons = TrayIconCStrings::new(icon_paths)?;
-        let tooltip = CString::new("crowd-cast Agent")?;
+        let tooltip = CString::new(TRAY_TOOLTIP)?;
 
         // Initial menu strings (overwritten by the first update() call)
         let menu_strings = vec![
@@ -166,10 +196,13 @@ impl MacOSTray {
             CString::new("Pause Uploads")?,          // 7
             CString::new("Sign in with Google")?,    // 8
             CString::new("Settings")?,               // 9
-            CString::new("Check for Updates")?,      // 10
-            CString::new("Report Bug…")?,            // 11
-            CString::new("-")?,                      // 12: separator
-            CString::new("Quit")?,                   // 13
+            CString::new("Open Recordings Folder")?, // 10
+            CString::new("Check for Updates")?,      // 11
+            CString::new("Report Bug…")?,            // 12
+            CString::new("Select Display")?,         // 13
+            CString::new("Add Marker")?,             // 14
+            CString::new("-")?,                      // 15: separator
+            CString::new("Quit")?,                   // 16
         ];
 
         let mut menu_items = vec![
@@ -253,39 +286,63 @@ impl MacOSTray {
                 cb: Some(on_settings),
                 submenu: std::ptr::null_mut(),
             },
-            // 10: Check for Updates
+            // 10: Open Recordings Folder
             TrayMenuItem {
                 text: menu_strings[10].as_ptr(),
+                disabled: 0,
+                checked: 0,
+                cb: Some(on_open_recordings),
+                submenu: std::ptr::null_mut(),
+            },
+            // 11: Check for Updates
+            TrayMenuItem {
+                text: menu_strings[11].as_ptr(),
                 disabled: 1,
                 checked: 0,
                 cb: Some(on_check_for_updates),
                 submenu: std::ptr::null_mut(),
             },
-            // 11: Report Bug
+            // 12: Report Bug
             TrayMenuItem {
-                text: menu_strings[11].as_ptr(),
+                text: menu_strings[12].as_ptr(),
                 disabled: 0,
                 checked: 0,
                 cb: Some(on_report_bug),
                 submenu: std::ptr::null_mut(),
             },
-            // 12: Separator
+            // 13: Select Display (submenu filled in by update(); disabled until then)
             TrayMenuItem {
-                text: menu_strings[12].as_ptr(),
+                text: menu_strings[13].as_ptr(),
+                disabled: 1,
+                checked: 0,
+                cb: None,
+                submenu: std::ptr::null_mut(),
+            },
+            // 14: Add Marker (submenu filled in by update(); disabled until then)
+            TrayMenuItem {
+                text: menu_strings[14].as_ptr(),
+                disabled: 1,
+                checked: 0,
+                cb: None,
+                submenu: std::ptr::null_mut(),
+            },
+            // 15: Separator
+            TrayMenuItem {
+                text: menu_strings[15].as_ptr(),
                 disabled: 0,
                 checked: 0,
                 cb: None,
                 submenu: std::ptr::null_mut(),
             },
-            // 13: Quit
+            // 16: Quit
             TrayMenuItem {
-                text: menu_strings[13].as_ptr(),
+                text: menu_strings[16].as_ptr(),
                 disabled: 0,
                 checked: 0,
                 cb: Some(on_quit),
                 submenu: std::ptr::null_mut(),
             },
-            // 14: NULL terminator
+            // 17: NULL terminator
             TrayMenuItem {
                 text: std::ptr::null(),
                 disabled: 0,
@@ -305,11 +362,84 @@ impl MacOSTray {
         Ok(Self {
             tray,
             icons,
-            _tooltip: tooltip,
+            tooltip,
             menu_items,
             menu_strings,
+            display_items: Vec::new(),
+            display_strings: Vec::new(),
+            display_ids: Vec::new(),
+            marker_items: Vec::new(),
+            marker_strings: Vec::new(),
+            marker_labels: Vec::new(),
         })
     }
+
+    /// Rebuild the "Add Marker" submenu; entries are clickable only while recording.
+    fn update_marker_menu(&mut self, labels: &[String], enabled: bool) {
+        self.marker_labels = labels.to_vec();
+        self.marker_strings = labels
+            .iter()
+            .map(|label| CString::new(label.as_str()).unwrap_or_default())
+            .collect();
+        self.marker_items = self
+            .marker_strings
+            .iter()
+            .map(|text| TrayMenuItem {
+                text: text.as_ptr(),
+                disabled: (!enabled) as i32,
+                checked: 0,
+                cb: Some(on_add_marker),
+                submenu: std::ptr::null_mut(),
+            })
+            .collect();
+        self.marker_items.push(TrayMenuItem::default());
+
+        let item = &mut self.menu_items[MENU_ADD_MARKER];
+        if labels.is_empty() || !enabled {
+            item.disabled = 1;
+            item.submenu = std::ptr::null_mut();
+        } else {
+            item.disabled = 0;
+            item.submenu = self.marker_items.as_mut_ptr();
+        }
+    }
+
+    /// Rebuild the "Select Display" submenu, checking the display capture follows.
+    fn update_display_menu(&mut self, displays: &[DisplayInfo]) {
+        self.display_strings = displays
+            .iter()
+            .map(|d| {
+                let label = if d.resolution == (0, 0) {
+                    d.name.clone()
+                } else {
+                    format!("{} ({}x{})", d.name, d.resolution.0, d.resolution.1)
+                };
+                CString::new(label).unwrap_or_default()
+            })
+            .collect();
+        self.display_ids = displays.iter().map(|d| d.id).collect();
+        self.display_items = displays
+            .iter()
+            .zip(&self.display_strings)
+            .map(|(d, text)| TrayMenuItem {
+                text: text.as_ptr(),
+                disabled: 0,
+                checked: d.is_current as i32,
+                cb: Some(on_select_display),
+                submenu: std::ptr::null_mut(),
+            })
+            .collect();
+        self.display_items.push(TrayMenuItem::default());
+
+        let item = &mut self.menu_items[MENU_SELECT_DISPLAY];
+        if displays.is_empty() {
+            item.disabled = 1;
+            item.submenu = std::ptr::null_mut();
+        } else {
+            item.disabled = 0;
+            item.submenu = self.display_items.as_mut_ptr();
+        }
+    }
 }
 
 impl PlatformTray for MacOSTray {
@@ -320,10 +450,13 @@ impl PlatformTray for MacOSTray {
         PANIC_REQUESTED.store(false, Ordering::SeqCst);
         CHECK_FOR_UPDATES_REQUESTED.store(false, Ordering::SeqCst);
         REPORT_BUG_REQUESTED.store(false, Ordering::SeqCst);
+        OPEN_RECORDINGS_REQUESTED.store(false, Ordering::SeqCst);
         SETTINGS_REQUESTED.store(false, Ordering::SeqCst);
         TOGGLE_UPLOADS_REQUESTED.store(false, Ordering::SeqCst);
         SIGN_IN_REQUESTED.store(false, Ordering::SeqCst);
         MACOS_QUIT_REQUESTED.store(false, Ordering::SeqCst);
+        SELECTED_DISPLAY_ITEM.store(std::ptr::null_mut(), Ordering::SeqCst);
+        SELECTED_MARKER_ITEM.store(std::ptr::null_mut(), Ordering::SeqCst);
 
         let result = unsafe { tray_ffi::tray_init(&mut self.tray) };
         if result != 0 {
@@ -399,6 +532,31 @@ impl PlatformTray for MacOSTray {
         if REPORT_BUG_REQUESTED.swap(false, Ordering::SeqCst) {
             return PlatformTrayPoll::Action(TrayAction::ReportBug);
         }
+        if OPEN_RECORDINGS_REQUESTED.swap(false, Ordering::SeqCst) {
+            return PlatformTrayPoll::Action(TrayAction::OpenRecordingsFolder);
+        }
+        let selected = SELECTED_DISPLAY_ITEM.swap(std::ptr::null_mut(), Ordering::SeqCst);
+        if !selected.is_null() {
+            if let Some(&display_id) = self
+                .display_items
+                .iter()
+                .position(|item| std::ptr::eq(item, selected))
+                .and_then(|i| self.display_ids.get(i))
+            {
+                return PlatformTrayPoll::Action(TrayAction::SelectDisplay(display_id));
+            }
+        }
+        let selected = SELECTED_MARKER_ITEM.swap(std::ptr::null_mut(), Ordering::SeqCst);
+        if !selected.is_null() {
+            if let Some(label) = self
+                .marker_items
+                .iter()
+                .position(|item| std::ptr::eq(item, selected))
+                .and_then(|i| self.marker_labels.get(i))
+            {
+                return PlatformTrayPoll::Action(TrayAction::AddMarker(label.clone()));
+            }
+        }
 
         PlatformTrayPoll::None
     }
@@ -436,8 +594,18 @@ impl PlatformTray for MacOSTray {
         // Check for Updates enabled state
         self.menu_items[MENU_UPDATES].disabled = if state.can_check_updates { 0 } else { 1 };
 
-        // Icon
+        // Select Display submenu
+        self.update_display_menu(&state.displays);
+
+        // Add Marker submenu
+        self.update_marker_menu(&state.markers, state.can_add_marker);
+
+        // Icon + tooltip
         self.tray.icon_filepath = self.icons.path_for(state.icon_state);
+        if let Ok(text) = CString::new(tooltip_text(&state.tooltip_detail)) {
+            self.tooltip = text;
+            self.tray.tooltip = self.tooltip.as_ptr();
+        }
 
         // Apply
         self.tray.menu = self.menu_items.as_mut_ptr();
@@ -9,11 +9,12 @@
 //! and pump the thread's message queue in `poll()`.
 
 use anyhow::{Context, Result};
-use muda::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use muda::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
 
 use super::platform_tray::{
-    PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths, TrayIconState,
+    tooltip_text, PlatformTray, PlatformTrayPoll, TrayAction, TrayDisplayState, TrayIconPaths,
+    TrayIconState, TRAY_TOOLTIP,
 };
 
 // Stable menu-item ids so menu clicks map deterministically to actions.
@@ -23,9 +24,12 @@ const ID_PANIC: &str = "cc.panic";
 const ID_UPLOADS: &str = "cc.uploads";
 const ID_SIGN: &str = "cc.sign";
 const ID_SETTINGS: &str = "cc.settings";
+const ID_RECORDINGS: &str = "cc.recordings";
 const ID_UPDATES: &str = "cc.updates";
 const ID_REPORT_BUG: &str = "cc.reportbug";
 const ID_QUIT: &str = "cc.quit";
+/// "Add Marker" entries are `cc.marker.<index into marker_labels>`.
+const ID_MARKER_PREFIX: &str = "cc.marker.";
 
 pub struct WindowsTray {
     idle_icon_path: std::path::PathBuf,
@@ -35,7 +39,7 @@ pub struct WindowsTray {
     pending_menu: Option<Menu>,
     tray: Option<TrayIcon>,
     // Item handles we mutate on update(). Items we never change (panic, settings,
-    // report bug, quit, separators) are owned by the menu and don't need handles here.
+    // recordings folder, report bug, quit, separators) are owned by the menu and don't need handles here.
     status_item: MenuItem,
     account_item: MenuItem,
     start_item: MenuItem,
@@ -43,7 +47,12 @@ pub struct WindowsTray {
     uploads_item: MenuItem,
     sign_item: MenuItem,
     updates_item: MenuItem,
+    /// "Add Marker" submenu, rebuilt when the configured labels change
+    markers_menu: Submenu,
+    marker_items: Vec<MenuItem>,
+    marker_labels: Vec<String>,
     last_icon_state: Option<TrayIconState>,
+    last_tooltip: Option<String>,
 }
 
 impl WindowsTray {
@@ -61,8 +70,11 @@ impl WindowsTray {
         let uploads_item = MenuItem::with_id(ID_UPLOADS, "Pause Uploads", true, None);
         let sign_item = MenuItem::with_id(ID_SIGN, "Sign in with Google", true, None);
         let settings_item = MenuItem::with_id(ID_SETTINGS, "Settings", true, None);
+        let recordings_item =
+            MenuItem::with_id(ID_RECORDINGS, "Open Recordings Folder", true, None);
         let updates_item = MenuItem::with_id(ID_UPDATES, "Check for Updates", false, None);
         let report_bug_item = MenuItem::with_id(ID_REPORT_BUG, "Report Bug…", true, None);
+        let markers_menu = Submenu::new("Add Marker", false);
         let quit_item = MenuItem::with_id(ID_QUIT, "Quit", true, None);
 
         let sep1 = PredefinedMenuItem::separator();
@@ -76,10 +88,12 @@ impl WindowsTray {
             &start_item,
             &stop_item,
             &panic_item,
+            &markers_menu,
             &sep2,
             &uploads_item,
             &sign_item,
             &settings_item,
+            &recordings_item,
             &updates_item,
             &report_bug_item,
             &sep3,
@@ -100,7 +114,11 @@ impl WindowsTray {
             uploads_item,
             sign_item,
             updates_item,
+            markers_menu,
+            marker_items: Vec::new(),
+            marker_labels: Vec::new(),
             last_icon_state: None,
+            last_tooltip: None,
         })
     }
 
@@ -112,6 +130,24 @@ impl WindowsTray {
         }
     }
 
+    /// Replace the "Add Marker" entries when the labels change.
+    fn update_markers_menu(&mut self, labels: &[String]) {
+        if self.marker_labels == labels {
+            return;
+        }
+        for item in self.marker_items.drain(..) {
+            let _ = self.markers_menu.remove(&item);
+        }
+        for (i, label) in labels.iter().enumerate() {
+            let item = MenuItem::with_id(format!("{}{}", ID_MARKER_PREFIX, i), label, true, None);
+            if let Err(e) = self.markers_menu.append(&item) {
+                tracing::warn!("Failed to add marker menu item: {}", e);
+            }
+            self.marker_items.push(item);
+        }
+        self.marker_labels = labels.to_vec();
+    }
+
     fn load_icon(&self, state: TrayIconState) -> Result<Icon> {
         let path = self.icon_path(state);
         Icon::from_path(path, None)
@@ -130,7 +166,7 @@ impl PlatformTray for WindowsTray {
 
         let tray = TrayIconBuilder::new()
             .with_menu(Box::new(menu))
-            .with_tooltip("crowd-cast Agent")
+            .with_tooltip(TRAY_TOOLTIP)
             .with_icon(icon)
             .build()
             .map_err(|e| anyhow::anyhow!("Failed to create Windows tray icon: {}", e))?;
@@ -165,10 +201,16 @@ impl PlatformTray for WindowsTray {
                 ID_UPLOADS => PlatformTrayPoll::Action(TrayAction::ToggleUploads),
                 ID_SIGN => PlatformTrayPoll::Action(TrayAction::SignIn),
                 ID_SETTINGS => PlatformTrayPoll::Action(TrayAction::Settings),
+                ID_RECORDINGS => PlatformTrayPoll::Action(TrayAction::OpenRecordingsFolder),
                 ID_UPDATES => PlatformTrayPoll::Action(TrayAction::CheckForUpdates),
                 ID_REPORT_BUG => PlatformTrayPoll::Action(TrayAction::ReportBug),
                 ID_QUIT => PlatformTrayPoll::Action(TrayAction::Quit),
-                _ => PlatformTrayPoll::None,
+                id => id
+                    .strip_prefix(ID_MARKER_PREFIX)
+                    .and_then(|i| i.parse::<usize>().ok())
+                    .and_then(|i| self.marker_labels.get(i))
+                    .map(|label| PlatformTrayPoll::Action(TrayAction::AddMarker(label.clone())))
+                    .unwrap_or(PlatformTrayPoll::None),
             };
         }
 
@@ -184,6 +226,9 @@ impl PlatformTray for WindowsTray {
         self.sign_item.set_text(&state.sign_action_text);
         self.sign_item.set_enabled(state.auth_action_enabled);
         self.updates_item.set_enabled(state.can_check_updates);
+        self.update_markers_menu(&state.markers);
+        self.markers_menu
+            .set_enabled(state.can_add_marker && !state.markers.is_empty());
 
         if self.last_icon_state != Some(state.icon_state) {
             if let Some(tray) = self.tray.as_ref() {
@@ -197,6 +242,15 @@ impl PlatformTray for WindowsTray {
                 }
             }
         }
+
+        let tooltip = tooltip_text(&state.tooltip_detail);
+        if self.last_tooltip.as_deref() != Some(tooltip.as_str()) {
+            if let Some(tray) = self.tray.as_ref() {
+                if tray.set_tooltip(Some(&tooltip)).is_ok() {
+                    self.last_tooltip = Some(tooltip);
+                }
+            }
+        }
     }
 
     fn prepare_for_restart(&mut self) {
@@ -0,0 +1,124 @@
+//! Object-store specifics of uploading to a signed URL (`upload.backend`)
+//!
+//! The presign endpoint does the signing for every store, so the agent never holds
+//! storage credentials. What differs on this side is the PUT itself and whether the
+//! endpoint's S3 multipart actions are available: S3, R2 and GCS V4 signed URLs take the
+//! same plain PUT (only S3 and R2 have the multipart actions), and Azure SAS URLs
+//! additionally require the blob type on every write. Each store also has its own header
+//! prefix for user metadata.
+
+use std::sync::Arc;
+
+use reqwest::{Client, RequestBuilder};
+
+use crate::config::UploadBackend;
+
+/// How to write an object to a URL the presign endpoint signed for one store
+pub(super) trait StorageBackend: Send + Sync {
+    /// Start a PUT of an object body to a signed `url`
+    fn signed_put(&self, client: &Client, url: &str, content_type: &str) -> RequestBuilder;
+
+    /// Header a piece of user metadata named `key` travels in
+    fn metadata_header(&self, key: &str) -> String;
+
+    /// Whether large videos can go through the presign endpoint's multipart actions
+    fn supports_multipart(&self) -> bool;
+}
+
+/// A plain PUT with the content type: Amazon S3, a store speaking its API (R2), or a
+/// Google Cloud Storage V4 signed URL. GCS requires the Content-Type the URL was signed
+/// with, which is why the presign response's content type is passed through.
+struct PlainPut {
+    /// Prefix of user metadata headers (`x-amz-meta-`, `x-goog-meta-`)
+    metadata_prefix: &'static str,
+    multipart: bool,
+}
+
+impl StorageBackend for PlainPut {
+    fn signed_put(&self, client: &Client, url: &str, content_type: &str) -> RequestBuilder {
+        client.put(url).header("Content-Type", content_type)
+    }
+
+    fn metadata_header(&self, key: &str) -> String {
+        format!("{}{}", self.metadata_prefix, key)
+    }
+
+    fn supports_multipart(&self) -> bool {
+        self.multipart
+    }
+}
+
+/// Azure Blob Storage SAS URLs. Put Blob rejects writes that don't name the blob type;
+/// a single Put Blob takes up to 5000 MiB, far above any segment.
+struct AzureSas;
+
+impl StorageBackend for AzureSas {
+    fn signed_put(&self, client: &Client, url: &str, content_type: &str) -> RequestBuilder {
+        client
+            .put(url)
+            .header("Content-Type", content_type)
+            .header("x-ms-blob-type", "BlockBlob")
+    }
+
+    fn metadata_header(&self, key: &str) -> String {
+        // Azure metadata names must be C# identifiers
+        format!("x-ms-meta-{}", key.replace('-', "_"))
+    }
+
+    fn supports_multipart(&self) -> bool {
+        false
+    }
+}
+
+pub(super) fn storage_backend(backend: UploadBackend) -> Arc<dyn StorageBackend> {
+    match backend {
+        UploadBackend::S3 | UploadBackend::R2 => Arc::new(PlainPut {
+            metadata_prefix: "x-amz-meta-",
+            multipart: true,
+        }),
+        UploadBackend::Gcs => Arc::new(PlainPut {
+            metadata_prefix: "x-goog-meta-",
+            multipart: false,
+        }),
+        UploadBackend::Azure => Arc::new(AzureSas),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_put_carries_each_stores_headers() {
+        let client = Client::new();
+        let url = "https://storage.example/bucket/recordings/seg0000.mp4?sig=abc";
+        for (backend, multipart, blob_type) in [
+            (UploadBackend::S3, true, None),
+            (UploadBackend::R2, true, None),
+            (UploadBackend::Gcs, false, None),
+            (UploadBackend::Azure, false, Some("BlockBlob")),
+        ] {
+            let store = storage_backend(backend);
+            let request = store.signed_put(&client, url, "video/mp4").build().unwrap();
+            assert_eq!(request.method(), reqwest::Method::PUT);
+            // The signature lives in the query string and must reach the store untouched.
+            assert_eq!(request.url().as_str(), url);
+            assert_eq!(request.headers()["Content-Type"], "video/mp4");
+            assert_eq!(
+                request
+                    .headers()
+                    .get("x-ms-blob-type")
+                    .map(|v| v.to_str().unwrap()),
+                blob_type,
+                "{:?}",
+                backend
+            );
+            assert_eq!(store.supports_multipart(), multipart, "{:?}", backend);
+        }
+
+        let meta = |backend| storage_backend(backend).metadata_header("agent-version");
+        assert_eq!(meta(UploadBackend::R2), "x-amz-meta-agent-version");
+        assert_eq!(meta(UploadBackend::Gcs), "x-goog-meta-agent-version");
+        assert_eq!(meta(UploadBackend::Azure), "x-ms-meta-agent_version");
+    }
+}
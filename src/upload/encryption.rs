@@ -0,0 +1,267 @@
+//! Optional client-side encryption of chunk bytes before upload
+//!
+//! When `upload.encryption_enabled` is set, video and input-log bytes are sealed with
+//! XChaCha20-Poly1305 so the storage backend only ever sees ciphertext. Each sealed
+//! object is self-describing:
+//! - input logs (`CCE1`): the 4-byte magic, the random 24-byte nonce, then the
+//!   ciphertext with its 16-byte tag;
+//! - videos (`CCS1`), which can be gigabytes and are sealed without reading them into
+//!   memory: the magic, a random 19-byte nonce prefix, then the STREAM construction
+//!   (big-endian 32-bit counter) over 64 KiB plaintext pieces, each followed by its tag.
+
+use std::io::{Read, Write};
+
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::EncryptorBE32;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::XChaCha20Poly1305;
+#[cfg(test)]
+use chacha20poly1305::XNonce;
+
+use crate::config::UploadConfig;
+
+/// Environment variable consulted when `upload.encryption_key` is not set
+pub const ENCRYPTION_KEY_ENV: &str = "CROWD_CAST_ENCRYPTION_KEY";
+
+/// Header identifying a sealed chunk (format version 1)
+const MAGIC: &[u8; 4] = b"CCE1";
+/// XChaCha20 nonce length in bytes
+const NONCE_LEN: usize = 24;
+
+/// Header identifying a stream-sealed video (format version 1)
+const STREAM_MAGIC: &[u8; 4] = b"CCS1";
+/// Nonce prefix length of the STREAM construction: the XChaCha20 nonce minus the 4-byte
+/// counter and 1-byte last-piece flag
+const STREAM_NONCE_LEN: usize = NONCE_LEN - 5;
+/// Plaintext bytes sealed per STREAM piece
+const STREAM_CHUNK: usize = 64 * 1024;
+/// Poly1305 tag length in bytes
+const TAG_LEN: usize = 16;
+
+/// Seals and opens chunk bytes with a 256-bit key
+#[derive(Clone)]
+pub struct ChunkCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl ChunkCipher {
+    /// Build from a base64-encoded 32-byte key
+    pub fn from_base64_key(key: &str) -> Result<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(key.trim())
+            .context("Encryption key is not valid base64")?;
+        if bytes.len() != 32 {
+            anyhow::bail!(
+                "Encryption key must decode to 32 bytes (got {})",
+                bytes.len()
+            );
+        }
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new_from_slice(&bytes).context("Invalid encryption key")?,
+        })
+    }
+
+    /// The cipher for `config`: None when encryption is off, an error when it is on but
+    /// no usable key is configured (in `upload.encryption_key` or `CROWD_CAST_ENCRYPTION_KEY`).
+    pub fn from_config(config: &UploadConfig) -> Result<Option<Self>> {
+        if !config.encryption_enabled {
+            return Ok(None);
+        }
+        let key = config
+            .encryption_key
+            .clone()
+            .filter(|k| !k.trim().is_empty())
+            .or_else(|| std::env::var(ENCRYPTION_KEY_ENV).ok())
+            .filter(|k| !k.trim().is_empty())
+            .with_context(|| {
+                format!(
+                    "Upload encryption is enabled but no key is configured \
+                     (set upload.encryption_key or {})",
+                    ENCRYPTION_KEY_ENV
+                )
+            })?;
+        Self::from_base64_key(&key).map(Some)
+    }
+
+    /// Seal `plaintext` under a fresh random nonce
+    pub fn encrypt_chunk(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt chunk"))?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Seal everything `reader` yields into `writer`, 64 KiB at a time
+    pub fn encrypt_stream(&self, mut reader: impl Read, mut writer: impl Write) -> Result<()> {
+        let mut nonce = [0u8; STREAM_NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        writer.write_all(STREAM_MAGIC)?;
+        writer.write_all(&nonce)?;
+        let mut encryptor = EncryptorBE32::from_aead(self.cipher.clone(), nonce.as_slice().into());
+
+        // The last piece is sealed differently, so read one piece ahead to know which is.
+        let mut piece = vec![0u8; STREAM_CHUNK];
+        let mut next = vec![0u8; STREAM_CHUNK];
+        let mut len = read_full(&mut reader, &mut piece)?;
+        loop {
+            let next_len = if len == STREAM_CHUNK {
+                read_full(&mut reader, &mut next)?
+            } else {
+                0
+            };
+            if next_len == 0 {
+                let sealed = encryptor
+                    .encrypt_last(&piece[..len])
+                    .map_err(|_| anyhow::anyhow!("Failed to encrypt stream"))?;
+                writer.write_all(&sealed)?;
+                return Ok(());
+            }
+            let sealed = encryptor
+                .encrypt_next(&piece[..len])
+                .map_err(|_| anyhow::anyhow!("Failed to encrypt stream"))?;
+            writer.write_all(&sealed)?;
+            std::mem::swap(&mut piece, &mut next);
+            len = next_len;
+        }
+    }
+
+    /// Open bytes produced by `encrypt_stream`
+    #[cfg(test)]
+    pub fn decrypt_stream(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        use chacha20poly1305::aead::stream::DecryptorBE32;
+
+        let body = sealed
+            .strip_prefix(STREAM_MAGIC.as_slice())
+            .context("Not an encrypted stream (bad header)")?;
+        if body.len() < STREAM_NONCE_LEN {
+            anyhow::bail!("Encrypted stream is truncated");
+        }
+        let (nonce, ciphertext) = body.split_at(STREAM_NONCE_LEN);
+        let mut decryptor = DecryptorBE32::from_aead(self.cipher.clone(), nonce.into());
+        let mut plaintext = Vec::new();
+        let mut pieces = ciphertext.chunks(STREAM_CHUNK + TAG_LEN).peekable();
+        let failed = || anyhow::anyhow!("Failed to decrypt stream (wrong key or corrupted data)");
+        while let Some(piece) = pieces.next() {
+            if pieces.peek().is_some() {
+                plaintext.extend(decryptor.decrypt_next(piece).map_err(|_| failed())?);
+            } else {
+                plaintext.extend(decryptor.decrypt_last(piece).map_err(|_| failed())?);
+                return Ok(plaintext);
+            }
+        }
+        anyhow::bail!("Encrypted stream is truncated")
+    }
+
+    /// Open bytes produced by `encrypt_chunk`
+    #[cfg(test)]
+    pub fn decrypt_chunk(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let body = sealed
+            .strip_prefix(MAGIC.as_slice())
+            .context("Not an encrypted chunk (bad header)")?;
+        if body.len() < NONCE_LEN {
+            anyhow::bail!("Encrypted chunk is truncated");
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt chunk (wrong key or corrupted data)"))
+    }
+}
+
+/// Fill `buf` from `reader` as far as it goes; short only at end of input
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> String {
+        base64::engine::general_purpose::STANDARD.encode([7u8; 32])
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let cipher = ChunkCipher::from_base64_key(&test_key()).unwrap();
+        let plaintext = b"fake video bytes".repeat(100);
+
+        let sealed = cipher.encrypt_chunk(&plaintext).unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_ne!(&sealed[MAGIC.len() + NONCE_LEN..], plaintext.as_slice());
+        assert_eq!(cipher.decrypt_chunk(&sealed).unwrap(), plaintext);
+
+        // Fresh nonce per chunk, and tampering is detected.
+        assert_ne!(cipher.encrypt_chunk(&plaintext).unwrap(), sealed);
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt_chunk(&tampered).is_err());
+
+        let other = ChunkCipher::from_base64_key(
+            &base64::engine::general_purpose::STANDARD.encode([8u8; 32]),
+        )
+        .unwrap();
+        assert!(other.decrypt_chunk(&sealed).is_err());
+    }
+
+    #[test]
+    fn stream_round_trip_across_piece_boundaries() {
+        let cipher = ChunkCipher::from_base64_key(&test_key()).unwrap();
+        // Empty, short, exactly one piece, and several pieces with a partial last one
+        for len in [0, 10, STREAM_CHUNK, 2 * STREAM_CHUNK + 7] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut sealed = Vec::new();
+            cipher
+                .encrypt_stream(plaintext.as_slice(), &mut sealed)
+                .unwrap();
+            assert!(sealed.starts_with(STREAM_MAGIC));
+            let pieces = len.div_ceil(STREAM_CHUNK).max(1);
+            assert_eq!(
+                sealed.len(),
+                STREAM_MAGIC.len() + STREAM_NONCE_LEN + len + pieces * TAG_LEN
+            );
+            assert_eq!(cipher.decrypt_stream(&sealed).unwrap(), plaintext);
+
+            // Dropping the last piece is detected, not mistaken for a shorter video.
+            if pieces > 1 {
+                let cut = sealed.len() - (len % STREAM_CHUNK) - TAG_LEN;
+                assert!(cipher.decrypt_stream(&sealed[..cut]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn from_config_requires_key_when_enabled() {
+        let mut config = UploadConfig::default();
+        assert!(ChunkCipher::from_config(&config).unwrap().is_none());
+
+        config.encryption_enabled = true;
+        if std::env::var(ENCRYPTION_KEY_ENV).is_err() {
+            let err = ChunkCipher::from_config(&config).err().unwrap();
+            assert!(err.to_string().contains("no key is configured"));
+        }
+
+        config.encryption_key = Some(test_key());
+        assert!(ChunkCipher::from_config(&config).unwrap().is_some());
+
+        config.encryption_key = Some("dG9vIHNob3J0".to_string());
+        assert!(ChunkCipher::from_config(&config).is_err());
+    }
+}
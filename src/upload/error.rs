@@ -0,0 +1,113 @@
+//! Typed upload failures
+//!
+//! The uploader builds its errors with anyhow internally; its public methods hand back an
+//! [`UploadError`] so the engine's retry queue can tell failures worth retrying (timeouts,
+//! dropped connections, throttling, 5xx) from ones that fail the same way on every attempt
+//! (a rejected credential, no compiled-in endpoint, an unusable encryption key).
+
+use reqwest::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UploadError {
+    /// No presign endpoint was compiled into this build
+    #[error("Lambda endpoint not configured at compile time")]
+    NotConfigured,
+    /// Encryption is enabled but the key is unusable
+    #[error("{0}")]
+    Encryption(String),
+    /// The presign endpoint or S3 answered with a non-2xx status
+    #[error("{error:#}")]
+    Http {
+        status: StatusCode,
+        error: anyhow::Error,
+    },
+    /// The request got no usable answer: connection refused or reset, timeout
+    #[error("{0:#}")]
+    Network(anyhow::Error),
+    /// Preparing the upload failed locally: reading, encrypting or serializing the chunk
+    #[error("{0:#}")]
+    Local(anyhow::Error),
+}
+
+impl UploadError {
+    /// Whether another attempt could succeed. A 401 is retried because the next attempt
+    /// refreshes the auth token; any other 4xx (e.g. 403 for a revoked credential) would
+    /// be rejected again. Local failures stay retryable since a full disk can clear up.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::NotConfigured | Self::Encryption(_) => false,
+            Self::Http { status, .. } => {
+                status.is_server_error()
+                    || matches!(
+                        *status,
+                        StatusCode::UNAUTHORIZED
+                            | StatusCode::REQUEST_TIMEOUT
+                            | StatusCode::TOO_MANY_REQUESTS
+                    )
+            }
+            Self::Network(_) | Self::Local(_) => true,
+        }
+    }
+}
+
+/// Non-2xx response, raised inside the uploader and lifted into [`UploadError::Http`]
+/// (context and all) at the public boundary.
+#[derive(Debug, thiserror::Error)]
+#[error("{what} returned HTTP {status}")]
+pub(super) struct HttpStatus {
+    pub what: String,
+    pub status: StatusCode,
+}
+
+impl From<anyhow::Error> for UploadError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(status) = error.downcast_ref::<HttpStatus>().map(|e| e.status) {
+            Self::Http { status, error }
+        } else if error.chain().any(|cause| cause.is::<reqwest::Error>()) {
+            Self::Network(error)
+        } else {
+            Self::Local(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retryable_by_status() {
+        let http = |status| {
+            UploadError::from(anyhow::Error::from(HttpStatus {
+                what: "Video upload".to_string(),
+                status,
+            }))
+        };
+        assert!(http(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(http(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(http(StatusCode::UNAUTHORIZED).is_retryable());
+        assert!(!http(StatusCode::FORBIDDEN).is_retryable());
+        assert!(!http(StatusCode::NOT_FOUND).is_retryable());
+
+        // Context added on the way out is kept in the message.
+        let wrapped = UploadError::from(
+            anyhow::Error::from(HttpStatus {
+                what: "Part upload".to_string(),
+                status: StatusCode::FORBIDDEN,
+            })
+            .context("Part 3/3 failed after 3 attempts"),
+        );
+        assert!(
+            matches!(wrapped, UploadError::Http { status, .. } if status == StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            wrapped.to_string(),
+            "Part 3/3 failed after 3 attempts: Part upload returned HTTP 403 Forbidden"
+        );
+
+        let local = UploadError::from(anyhow::anyhow!("Failed to read video file"));
+        assert!(matches!(local, UploadError::Local(_)));
+        assert!(local.is_retryable());
+        assert!(!UploadError::NotConfigured.is_retryable());
+    }
+}
@@ -0,0 +1,247 @@
+//! Incremental upload of HLS recordings (`recording.hls`)
+//!
+//! With HLS on, each segment is recorded as a playlist (`recording_<id>.m3u8`) and
+//! `recording_<id>.<n>.ts` pieces of a few seconds. ffmpeg only lists a piece once it has
+//! finished writing it, so the playlist is the record of what is safe to upload. While a
+//! segment records, the shipper uploads each newly listed piece and then the playlist
+//! (overwriting the previous copy), so the recording can be consumed as it grows. When
+//! the segment ends the playlist gains `#EXT-X-ENDLIST` and the shipper leaves it alone:
+//! the segment's regular upload sends the remaining pieces, the final playlist and the
+//! input log. Only playlists written since the shipper started are shipped, so one left
+//! unfinished by an earlier run goes up with its segment's upload instead.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::{debug, warn};
+
+use super::Uploader;
+
+/// Extension of an HLS recording's playlist
+const PLAYLIST_EXTENSION: &str = "m3u8";
+
+/// Content type of a playlist, when the presign endpoint doesn't name one
+pub(super) const PLAYLIST_CONTENT_TYPE: &str = "application/vnd.apple.mpegurl";
+
+/// Content type of an MPEG-TS piece, when the presign endpoint doesn't name one
+pub(super) const PIECE_CONTENT_TYPE: &str = "video/mp2t";
+
+/// Tag ffmpeg appends once the recording has stopped
+const END_LIST_TAG: &str = "#EXT-X-ENDLIST";
+
+/// Whether `path` is an HLS playlist rather than a single video file
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == PLAYLIST_EXTENSION)
+}
+
+/// Whether a playlist's text marks the recording as finished
+pub(super) fn is_finished(playlist: &str) -> bool {
+    playlist.lines().any(|line| line.trim() == END_LIST_TAG)
+}
+
+/// Piece file names a playlist lists, in order: every line that is neither blank nor a
+/// tag. Only the file name is kept, so an entry can't point outside the playlist's
+/// directory.
+fn listed_pieces(playlist: &str) -> Vec<&str> {
+    playlist
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| Path::new(line).file_name()?.to_str())
+        .collect()
+}
+
+/// The piece files `playlist` lists, next to it. Empty when it isn't a playlist or can't
+/// be read.
+pub fn playlist_pieces(playlist: &Path) -> Vec<PathBuf> {
+    if !is_playlist(playlist) {
+        return Vec::new();
+    }
+    let Ok(text) = std::fs::read_to_string(playlist) else {
+        return Vec::new();
+    };
+    let dir = playlist.parent().unwrap_or(Path::new(""));
+    listed_pieces(&text)
+        .into_iter()
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+/// Bytes of video a segment recorded at `video_path`: the file, plus the pieces it lists
+/// when it is a playlist. Missing files count as 0.
+pub fn video_bytes(video_path: &Path) -> u64 {
+    playlist_pieces(video_path)
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(std::iter::once(video_path))
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Ships the pieces and playlists of HLS segments still being recorded
+pub struct HlsShipper {
+    uploader: Uploader,
+    output_dir: PathBuf,
+    /// Playlists last written before this are left to their segment's upload
+    started: SystemTime,
+    /// Playlist size at its last successful upload
+    shipped: HashMap<PathBuf, u64>,
+}
+
+impl HlsShipper {
+    pub fn new(uploader: Uploader, output_dir: PathBuf) -> Self {
+        Self {
+            uploader,
+            output_dir,
+            started: SystemTime::now(),
+            shipped: HashMap::new(),
+        }
+    }
+
+    /// One shipping pass over the playlists in the output directory that are still
+    /// growing. Best-effort: a failed upload is logged and retried on the next pass.
+    pub async fn run_once(&mut self) {
+        let entries = match std::fs::read_dir(&self.output_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Output dir not readable, skipping HLS shipping: {}", e);
+                return;
+            }
+        };
+
+        let mut live = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_playlist(&path) {
+                continue;
+            }
+            let modified = entry.metadata().and_then(|meta| meta.modified());
+            if modified.map_or(true, |modified| modified < self.started) {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            // Finished playlists belong to the segment upload queue.
+            if is_finished(&text) {
+                continue;
+            }
+            let size = text.len() as u64;
+            if self.shipped.get(&path) != Some(&size) {
+                live.push((path, size));
+            }
+        }
+
+        for (playlist, size) in live {
+            match self.uploader.upload_hls_playlist(&playlist).await {
+                Ok(pieces) => {
+                    if pieces > 0 {
+                        debug!("Shipped {} HLS piece(s) of {:?}", pieces, playlist);
+                    }
+                    self.shipped.insert(playlist, size);
+                }
+                Err(e) => warn!("HLS shipping failed for {:?}: {}", playlist, e),
+            }
+        }
+
+        self.shipped.retain(|playlist, _| playlist.exists());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_lists_pieces_next_to_it() {
+        let playlist = "#EXTM3U\n\
+                        #EXT-X-VERSION:3\n\
+                        #EXT-X-TARGETDURATION:4\n\
+                        #EXT-X-MEDIA-SEQUENCE:0\n\
+                        #EXT-X-PLAYLIST-TYPE:EVENT\n\
+                        #EXTINF:4.000000,\n\
+                        recording_s_seg0001.00000.ts\n\
+                        #EXTINF:4.000000,\n\
+                        recording_s_seg0001.00001.ts\n\
+                        \n\
+                        #EXTINF:4.000000,\n\
+                        ../../etc/recording_s_seg0001.00002.ts\n";
+        assert_eq!(
+            listed_pieces(playlist),
+            vec![
+                "recording_s_seg0001.00000.ts",
+                "recording_s_seg0001.00001.ts",
+                "recording_s_seg0001.00002.ts",
+            ]
+        );
+
+        let dir = std::env::temp_dir().join(format!("cc_hls_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("recording_s_seg0001.m3u8");
+        std::fs::write(&path, playlist).unwrap();
+        assert!(is_playlist(&path));
+        assert_eq!(
+            playlist_pieces(&path),
+            vec![
+                dir.join("recording_s_seg0001.00000.ts"),
+                dir.join("recording_s_seg0001.00001.ts"),
+                dir.join("recording_s_seg0001.00002.ts"),
+            ]
+        );
+        assert!(!is_playlist(&dir.join("recording_s_seg0001.mp4")));
+        assert!(playlist_pieces(&dir.join("recording_s_seg0001.mp4")).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn shipper_skips_finished_and_stale_playlists() {
+        use crate::upload::test_support::{mock_uploader, spawn_mock_endpoint};
+
+        let dir = std::env::temp_dir().join(format!("cc_hls_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |id: &str, end: &str| {
+            std::fs::write(dir.join(format!("recording_{id}.00000.ts")), b"ts").unwrap();
+            let playlist = dir.join(format!("recording_{id}.m3u8"));
+            std::fs::write(
+                &playlist,
+                format!("#EXTM3U\n#EXTINF:4.0,\nrecording_{id}.00000.ts\n{end}"),
+            )
+            .unwrap();
+            playlist
+        };
+        // Left unfinished by an earlier run
+        let stale = write("s_seg0000", "");
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - std::time::Duration::from_secs(3600))
+            .unwrap();
+
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let mut shipper = HlsShipper::new(mock_uploader(&base), dir.clone());
+        write("s_seg0001", "#EXT-X-ENDLIST\n");
+        write("s_seg0002", "");
+        shipper.run_once().await;
+
+        let mut puts: Vec<String> = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, _, _)| method == "PUT")
+            .map(|(_, path, _)| path.clone())
+            .collect();
+        puts.sort();
+        assert_eq!(
+            puts,
+            vec![
+                "/put/recordings/recording_s_seg0002.00000.ts",
+                "/put/recordings/recording_s_seg0002.m3u8",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,7 +1,20 @@
 //! S3 upload via pre-signed URLs
 
+mod backend;
+mod encryption;
+mod error;
+mod hls;
 mod log_shipper;
 mod presigned;
+mod progress;
+mod reupload;
 
+pub use encryption::ChunkCipher;
+pub use error::UploadError;
+pub use hls::{is_playlist, playlist_pieces, video_bytes, HlsShipper};
 pub use log_shipper::LogShipper;
 pub use presigned::*;
+pub use progress::ProgressSink;
+pub use reupload::run_reupload_command;
+#[cfg(test)]
+pub(crate) use presigned::test_support;
@@ -1,20 +1,39 @@
 //! Pre-signed URL upload implementation
 //!
 //! Supports streaming uploads to minimize RAM usage for large video files.
+//! Video files above `upload.multipart_threshold_mb` go through S3 multipart
+//! upload (create / upload-parts / complete, each step presigned by the Lambda)
+//! so a dropped connection only re-sends one part. Multipart progress is kept
+//! in a `<video>.multipart.json` sidecar so uploads resume across restarts.
+//! With `upload.encryption_enabled`, video and input bytes are sealed client-side
+//! (see `encryption`) and uploaded under a `.enc` suffix. An HLS segment's video is its
+//! playlist; the pieces it lists are uploaded first (see `hls`).
 
 use anyhow::{Context, Result};
 use reqwest::{Body, Client};
 use serde::{Deserialize, Serialize};
 use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio_util::io::ReaderStream;
 use tracing::{debug, error, info, warn};
 
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::auth::AuthManager;
 use crate::config::Config;
-use crate::data::CompletedChunk;
+use crate::data::{CompletedChunk, SegmentStats};
+use crate::upload::ChunkCipher;
+
+use super::backend::{storage_backend, StorageBackend};
+use super::error::HttpStatus;
+use super::hls::{
+    is_finished, is_playlist, playlist_pieces, PIECE_CONTENT_TYPE, PLAYLIST_CONTENT_TYPE,
+};
+use super::progress::{track, ProgressSink, UploadProgress};
+use super::UploadError;
 
 /// Request to Lambda endpoint for pre-signed URLs
 #[derive(Debug, Serialize)]
@@ -24,6 +43,17 @@ struct PresignRequest {
     version: String,
     #[serde(rename = "userId")]
     user_id: String,
+    /// A segment's `SegmentStats`, sent with the presign request for its input log
+    #[serde(rename = "eventCount", skip_serializing_if = "Option::is_none")]
+    event_count: Option<u64>,
+    #[serde(rename = "durationUs", skip_serializing_if = "Option::is_none")]
+    duration_us: Option<u64>,
+    #[serde(rename = "videoBytes", skip_serializing_if = "Option::is_none")]
+    video_bytes: Option<u64>,
+    /// User metadata the object is PUT with (see `object_metadata`), so the endpoint can
+    /// sign the headers carrying it
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
 }
 
 /// Response from Lambda endpoint with pre-signed URLs
@@ -34,6 +64,247 @@ struct PresignResponse {
     key: String,
     #[serde(rename = "contentType")]
     content_type: String,
+    /// Metadata keys the URL was signed with. Only these are sent as metadata headers: a
+    /// signed PUT carrying a header outside its signature is rejected by S3 and GCS.
+    #[serde(rename = "signedMetadata", default)]
+    signed_metadata: Vec<String>,
+}
+
+/// S3 caps a multipart upload at 10,000 parts.
+const MULTIPART_MAX_PARTS: u64 = 10_000;
+/// S3 rejects non-final parts smaller than 5 MiB.
+const MULTIPART_MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// Attempts per part before the whole upload is failed back to the engine's retry queue.
+const MULTIPART_PART_MAX_ATTEMPTS: u32 = 3;
+/// Base delay between part attempts (multiplied by the attempt number).
+const MULTIPART_PART_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Multipart request to the Lambda endpoint. `action` selects the S3 call the
+/// Lambda performs or presigns: `createMultipartUpload`, `presignPart`,
+/// `completeMultipartUpload` or `abortMultipartUpload`.
+#[derive(Debug, Serialize)]
+struct MultipartRequest<'a> {
+    action: &'a str,
+    #[serde(rename = "fileName")]
+    file_name: &'a str,
+    version: &'a str,
+    #[serde(rename = "userId")]
+    user_id: &'a str,
+    #[serde(rename = "uploadId", skip_serializing_if = "Option::is_none")]
+    upload_id: Option<&'a str>,
+    #[serde(rename = "partNumber", skip_serializing_if = "Option::is_none")]
+    part_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parts: Option<&'a [CompletedPart]>,
+    /// User metadata for the object, on `createMultipartUpload`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<&'a BTreeMap<String, String>>,
+}
+
+/// Response to `createMultipartUpload`
+#[derive(Debug, Deserialize)]
+struct CreateMultipartResponse {
+    #[serde(rename = "uploadId")]
+    upload_id: String,
+    key: String,
+}
+
+/// Response to `presignPart`
+#[derive(Debug, Deserialize)]
+struct PresignPartResponse {
+    #[serde(rename = "uploadUrl")]
+    upload_url: String,
+}
+
+/// A part S3 has acknowledged, echoed back verbatim on `completeMultipartUpload`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CompletedPart {
+    #[serde(rename = "PartNumber")]
+    part_number: u32,
+    #[serde(rename = "ETag")]
+    etag: String,
+}
+
+/// Multipart uploads older than this are not resumed: S3 lifecycle rules may
+/// already have aborted the upload id, so start over instead.
+const MULTIPART_STATE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Throwaway object written by `Uploader::test_upload` (overwritten on every check)
+const TEST_UPLOAD_FILE_NAME: &str = "tests/upload_check.txt";
+
+/// Content type of an uploaded input log, unless the presign endpoint names one
+const KEYLOG_CONTENT_TYPE: &str = "application/x-msgpack";
+
+/// Content type of a segment video, from its container, unless the presign endpoint
+/// names one
+fn video_content_type(video_path: &Path) -> &'static str {
+    let extension = video_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("mov") => "video/quicktime",
+        Some("mkv") => "video/x-matroska",
+        Some("flv") => "video/x-flv",
+        Some("ts") => PIECE_CONTENT_TYPE,
+        _ if is_playlist(video_path) => PLAYLIST_CONTENT_TYPE,
+        _ => "video/mp4",
+    }
+}
+
+/// User metadata stored with a segment's video and input log (`x-amz-meta-*` on S3), so
+/// the bucket describes itself: the session and chunk, the span the input covers, where
+/// monotonic timestamps fall in the video, then `upload.metadata`. The built-in keys take
+/// precedence over configured ones.
+fn object_metadata(
+    chunk: &CompletedChunk,
+    extra: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut metadata: BTreeMap<String, String> = extra
+        .iter()
+        .map(|(key, value)| (key.to_ascii_lowercase(), value.clone()))
+        .collect();
+    metadata.insert("session-id".to_string(), chunk.session_id.clone());
+    metadata.insert("chunk-id".to_string(), chunk.chunk_id.clone());
+    metadata.insert("start-time-us".to_string(), chunk.start_time_us.to_string());
+    metadata.insert("end-time-us".to_string(), chunk.end_time_us.to_string());
+    if let Some(offset_us) = chunk.video_offset_us {
+        metadata.insert("video-offset-us".to_string(), offset_us.to_string());
+    }
+    metadata
+}
+
+/// In-progress multipart upload persisted next to the video file
+/// (`<video>.multipart.json`) so an interrupted upload resumes after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MultipartState {
+    file_name: String,
+    upload_id: String,
+    key: String,
+    file_size: u64,
+    part_size: u64,
+    created_at_epoch_s: u64,
+    parts: Vec<CompletedPart>,
+}
+
+impl MultipartState {
+    /// Whether this state describes an upload of `file_name` with the same layout
+    /// that is still young enough to resume.
+    fn matches(&self, file_name: &str, file_size: u64, part_size: u64, now_epoch_s: u64) -> bool {
+        self.file_name == file_name
+            && self.file_size == file_size
+            && self.part_size == part_size
+            && now_epoch_s.saturating_sub(self.created_at_epoch_s) < MULTIPART_STATE_MAX_AGE_SECS
+    }
+}
+
+fn multipart_state_path(video_path: &std::path::Path) -> std::path::PathBuf {
+    let mut name = video_path.as_os_str().to_owned();
+    name.push(".multipart.json");
+    std::path::PathBuf::from(name)
+}
+
+fn read_multipart_state(path: &std::path::Path) -> Option<MultipartState> {
+    let contents = std::fs::read(path).ok()?;
+    match serde_json::from_slice(&contents) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("Ignoring unreadable multipart state {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn write_multipart_state(path: &std::path::Path, state: &MultipartState) {
+    let result = serde_json::to_vec(state)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| std::fs::write(path, bytes).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        warn!("Failed to persist multipart state to {:?}: {}", path, e);
+    }
+}
+
+fn remove_multipart_state(path: &std::path::Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove multipart state {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Delete what an unfinished upload of `video_path` left next to it: the multipart state
+/// sidecar and, with encryption, the sealed `.enc` copy and its sidecar. For segments that
+/// are deleted or given up on; a finished upload cleans up after itself.
+pub fn remove_upload_leftovers(video_path: &std::path::Path) {
+    let mut sealed = video_path.as_os_str().to_owned();
+    sealed.push(".enc");
+    let sealed = std::path::PathBuf::from(sealed);
+    remove_multipart_state(&multipart_state_path(video_path));
+    remove_multipart_state(&multipart_state_path(&sealed));
+    if let Err(e) = std::fs::remove_file(&sealed) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove encrypted copy {:?}: {}", sealed, e);
+        }
+    }
+}
+
+fn now_epoch_s() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Sealed copy of `video_path` for upload (`<video>.enc`), streamed through the cipher so
+/// the video is never held in memory. Reused when an earlier attempt already wrote it, so
+/// a resumed multipart upload keeps sending the same bytes.
+async fn encrypted_video_path(
+    cipher: &ChunkCipher,
+    video_path: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    let mut name = video_path.as_os_str().to_owned();
+    name.push(".enc");
+    let sealed_path = std::path::PathBuf::from(name);
+    if tokio::fs::try_exists(&sealed_path).await.unwrap_or(false) {
+        return Ok(sealed_path);
+    }
+
+    let cipher = cipher.clone();
+    let source = video_path.to_path_buf();
+    let dest = sealed_path.clone();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let plaintext = std::fs::File::open(&source)
+            .with_context(|| format!("Failed to read video file for encryption: {:?}", source))?;
+        // Write-then-rename so an interrupted write never looks like a finished copy.
+        let tmp = dest.with_extension("enc.tmp");
+        let mut sealed = std::io::BufWriter::new(
+            std::fs::File::create(&tmp)
+                .with_context(|| format!("Failed to write encrypted video: {:?}", tmp))?,
+        );
+        cipher
+            .encrypt_stream(std::io::BufReader::new(plaintext), &mut sealed)
+            .with_context(|| format!("Failed to encrypt video: {:?}", source))?;
+        sealed
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .with_context(|| format!("Failed to write encrypted video: {:?}", tmp))?;
+        std::fs::rename(&tmp, &dest)
+            .with_context(|| format!("Failed to finalize encrypted video: {:?}", dest))?;
+        Ok(())
+    })
+    .await
+    .context("Video encryption task failed")??;
+
+    Ok(sealed_path)
+}
+
+/// Part size for a file: the configured size, grown as needed to stay under
+/// S3's part-count cap.
+fn effective_part_size(file_size: u64, configured: u64) -> u64 {
+    configured
+        .max(file_size.div_ceil(MULTIPART_MAX_PARTS))
+        .max(1)
 }
 
 /// Uploader for completed chunks
@@ -43,18 +314,59 @@ struct PresignResponse {
 pub struct Uploader {
     client: Client,
     auth: Option<Arc<Mutex<AuthManager>>>,
+    /// Presign endpoint (compile-time `CROWD_CAST_API_GATEWAY_URL`)
+    endpoint: Option<String>,
+    /// Video files at or above this many bytes use multipart upload
+    multipart_threshold: u64,
+    /// Configured multipart part size in bytes
+    multipart_part_size: u64,
+    /// Client-side encryption, when enabled
+    encryption: Option<ChunkCipher>,
+    /// Why uploads are disabled despite an endpoint (encryption on but unusable key)
+    encryption_error: Option<String>,
+    /// Object store the presigned URLs point at
+    backend: Arc<dyn StorageBackend>,
+    /// HLS pieces uploaded ahead of their segment, skipped when the segment goes up
+    hls_pieces_shipped: Arc<std::sync::Mutex<HashSet<PathBuf>>>,
+    /// Held while HLS pieces and playlists go up, so live shipping never interleaves with
+    /// the upload of a finished HLS segment
+    hls_lock: Arc<Mutex<()>>,
+    /// Extra user metadata for segment objects (`upload.metadata`)
+    metadata: BTreeMap<String, String>,
 }
 
 impl Uploader {
     /// Create a new uploader
     pub fn new(config: &Config, auth: Option<Arc<Mutex<AuthManager>>>) -> Self {
-        let _ = config;
+        let (encryption, encryption_error) = match ChunkCipher::from_config(&config.upload) {
+            Ok(cipher) => (cipher, None),
+            Err(e) => {
+                error!("{:#}; uploads are disabled", e);
+                (None, Some(format!("{:#}", e)))
+            }
+        };
         Self {
             client: Client::builder()
                 .connect_timeout(std::time::Duration::from_secs(30))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
             auth,
+            endpoint: Self::compile_time_endpoint().map(str::to_string),
+            multipart_threshold: config
+                .upload
+                .multipart_threshold_mb
+                .saturating_mul(1024 * 1024),
+            multipart_part_size: config
+                .upload
+                .multipart_part_size_mb
+                .saturating_mul(1024 * 1024)
+                .max(MULTIPART_MIN_PART_SIZE),
+            encryption,
+            encryption_error,
+            backend: storage_backend(config.upload.backend),
+            hls_pieces_shipped: Arc::default(),
+            hls_lock: Arc::default(),
+            metadata: config.upload.metadata.clone(),
         }
     }
 
@@ -96,6 +408,7 @@ impl Uploader {
         uuid::Uuid::new_v4().to_string()
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn request_presigned_url(
         &self,
         endpoint: &str,
@@ -103,11 +416,17 @@ impl Uploader {
         version: &str,
         user_id: &str,
         auth_token: Option<&str>,
+        stats: Option<&SegmentStats>,
+        metadata: Option<&BTreeMap<String, String>>,
     ) -> Result<PresignResponse> {
         let presign_request = PresignRequest {
             file_name: file_name.to_string(),
             version: version.to_string(),
             user_id: user_id.to_string(),
+            event_count: stats.map(|stats| stats.event_count),
+            duration_us: stats.map(|stats| stats.duration_us),
+            video_bytes: stats.map(|stats| stats.video_bytes),
+            metadata: metadata.cloned().unwrap_or_default(),
         };
 
         let mut req = self
@@ -120,10 +439,19 @@ impl Uploader {
             req = req.header("Authorization", format!("Bearer {}", token));
         }
 
-        let presign_response: PresignResponse = req
+        let response = req
             .send()
             .await
-            .context("Failed to request pre-signed URL")?
+            .context("Failed to request pre-signed URL")?;
+        // Checked before parsing so a rejection (e.g. 403) surfaces as its status.
+        if !response.status().is_success() {
+            return Err(HttpStatus {
+                what: "Pre-signed URL request".to_string(),
+                status: response.status(),
+            }
+            .into());
+        }
+        let presign_response: PresignResponse = response
             .json()
             .await
             .context("Failed to parse pre-signed URL response")?;
@@ -136,15 +464,51 @@ impl Uploader {
     /// This method streams video files directly from disk to the network,
     /// avoiding the need to load the entire file into RAM. This is critical
     /// for segments that can be several hundred MB.
-    pub async fn upload(&self, chunk: &CompletedChunk) -> Result<()> {
-        let endpoint = Self::compile_time_endpoint()
-            .context("Lambda endpoint not configured at compile time")?;
+    pub async fn upload(&self, chunk: &CompletedChunk) -> Result<(), UploadError> {
+        self.upload_with_progress(chunk, None).await
+    }
+
+    /// Like [`Uploader::upload`], reporting `(sent, total)` bytes of the video to `progress`
+    /// as it streams out (throttled; the completing report is always delivered).
+    pub async fn upload_with_progress(
+        &self,
+        chunk: &CompletedChunk,
+        progress: Option<ProgressSink>,
+    ) -> Result<(), UploadError> {
+        let endpoint = self.checked_endpoint()?;
+        Ok(self.upload_chunk(endpoint, chunk, progress).await?)
+    }
 
+    /// The presign endpoint, or why nothing can be uploaded
+    fn checked_endpoint(&self) -> Result<&str, UploadError> {
+        let endpoint = self.endpoint.as_deref().ok_or(UploadError::NotConfigured)?;
+        if let Some(ref e) = self.encryption_error {
+            return Err(UploadError::Encryption(e.clone()));
+        }
+        Ok(endpoint)
+    }
+
+    async fn upload_chunk(
+        &self,
+        endpoint: &str,
+        chunk: &CompletedChunk,
+        progress: Option<ProgressSink>,
+    ) -> Result<()> {
         info!(
-            "Uploading chunk {} for session {}",
-            chunk.chunk_id, chunk.session_id
+            "Uploading chunk {} for session {} ({} events over {:.1}s, {:.2} MB of video)",
+            chunk.chunk_id,
+            chunk.session_id,
+            chunk.stats.event_count,
+            chunk.stats.duration_us as f64 / 1_000_000.0,
+            chunk.stats.video_bytes as f64 / (1024.0 * 1024.0)
         );
 
+        // With encryption on, the video is uploaded from a sealed `<video>.enc` copy.
+        let video_path = match (&chunk.video_path, &self.encryption) {
+            (Some(path), Some(cipher)) => Some(encrypted_video_path(cipher, path).await?),
+            (path, _) => path.clone(),
+        };
+
         // The backend keys the upload prefix off this version (`uploads/<version>/...`). Explicit
         // binary choice, NOT a fallback: a test build (CROWD_CAST_UPLOAD_TEST set) uploads to a
         // segregated, deletable `uploads/TEST_VERSION/` prefix; every other build uploads to the
@@ -158,30 +522,67 @@ impl Uploader {
         let user_id = Self::compute_user_id();
         let auth_token = self.get_auth_token().await;
         let auth_token_ref = auth_token.as_deref();
+        let metadata = object_metadata(chunk, &self.metadata);
 
-        // 1. Get pre-signed URL for video (if path is available)
+        // An HLS segment's pieces go up before its playlist, so the uploaded playlist never
+        // lists a piece that isn't there. The live shipper waits until this upload is done.
+        let _hls_guard = match chunk.video_path.as_deref() {
+            Some(path) if is_playlist(path) => Some(self.hls_lock.lock().await),
+            _ => None,
+        };
+        let hls_pieces = chunk
+            .video_path
+            .as_deref()
+            .map(playlist_pieces)
+            .unwrap_or_default();
+        self.upload_hls_pieces(endpoint, &hls_pieces).await?;
+
+        // 1. Get pre-signed URL for video (if path is available). Large files skip the
+        //    single-PUT URL and go through multipart in step 3.
         let mut video_presign: Option<PresignResponse> = None;
         let mut video_file_name: Option<String> = None;
+        let mut video_size: u64 = 0;
 
-        if let Some(ref video_path) = chunk.video_path {
+        if let Some(ref video_path) = video_path {
             let video_file = video_path
                 .file_name()
                 .and_then(|name| name.to_str())
                 .context("Failed to get video filename")?;
             let file_name = format!("recordings/{}", video_file);
-            let presign_response = self
-                .request_presigned_url(endpoint, &file_name, version, &user_id, auth_token_ref)
-                .await?;
-            debug!(
-                "Got pre-signed URL for video chunk {} (key: {})",
-                chunk.chunk_id, presign_response.key
-            );
-            video_presign = Some(presign_response);
+
+            // Get file size for Content-Length header and the multipart decision
+            let file_meta = tokio::fs::metadata(video_path)
+                .await
+                .with_context(|| format!("Failed to get video file metadata: {:?}", video_path))?;
+            video_size = file_meta.len();
+
+            if video_size < self.multipart_threshold || !self.backend.supports_multipart() {
+                let presign_response = self
+                    .request_presigned_url(
+                        endpoint,
+                        &file_name,
+                        version,
+                        &user_id,
+                        auth_token_ref,
+                        None,
+                        Some(&metadata),
+                    )
+                    .await?;
+                debug!(
+                    "Got pre-signed URL for video chunk {} (key: {})",
+                    chunk.chunk_id, presign_response.key
+                );
+                video_presign = Some(presign_response);
+            }
             video_file_name = Some(file_name);
         }
 
         // 2. Get pre-signed URL for keylogs
-        let keylog_file_name = format!("keylogs/input_{}.msgpack", chunk.chunk_id);
+        let keylog_file_name = if self.encryption.is_some() {
+            format!("keylogs/input_{}.msgpack.enc", chunk.chunk_id)
+        } else {
+            format!("keylogs/input_{}.msgpack", chunk.chunk_id)
+        };
         let keylog_presign = self
             .request_presigned_url(
                 endpoint,
@@ -189,6 +590,8 @@ impl Uploader {
                 version,
                 &user_id,
                 auth_token_ref,
+                Some(&chunk.stats),
+                Some(&metadata),
             )
             .await?;
         debug!(
@@ -197,52 +600,74 @@ impl Uploader {
         );
 
         // 3. Upload video file using streaming (if path is available)
-        if let Some(ref video_path) = chunk.video_path {
-            let presign = video_presign
-                .as_ref()
-                .context("Missing video pre-signed URL")?;
+        if let Some(ref video_path) = video_path {
+            let file_size = video_size;
+            let progress = progress.map(|sink| Arc::new(UploadProgress::new(file_size, sink)));
 
-            // Get file size for Content-Length header
-            let metadata = tokio::fs::metadata(video_path)
-                .await
-                .with_context(|| format!("Failed to get video file metadata: {:?}", video_path))?;
-            let file_size = metadata.len();
+            if let Some(presign) = video_presign.as_ref() {
+                // Open file and create streaming body
+                let file = File::open(video_path)
+                    .await
+                    .with_context(|| format!("Failed to open video file: {:?}", video_path))?;
 
-            // Open file and create streaming body
-            let file = File::open(video_path)
-                .await
-                .with_context(|| format!("Failed to open video file: {:?}", video_path))?;
+                // Use ReaderStream to stream the file without loading it all into RAM
+                let stream = ReaderStream::new(file);
+                let body = match progress {
+                    Some(progress) => Body::wrap_stream(track(stream, progress, 0)),
+                    None => Body::wrap_stream(stream),
+                };
 
-            // Use ReaderStream to stream the file without loading it all into RAM
-            let stream = ReaderStream::new(file);
-            let body = Body::wrap_stream(stream);
+                let content_type = if !presign.content_type.is_empty() {
+                    presign.content_type.as_str()
+                } else {
+                    chunk
+                        .video_path
+                        .as_deref()
+                        .map_or("video/mp4", video_content_type)
+                };
 
-            let content_type = if presign.content_type.is_empty() {
-                "video/mp4"
-            } else {
-                presign.content_type.as_str()
-            };
+                let response = self
+                    .object_put(presign, content_type, &metadata)
+                    .header("Content-Length", file_size)
+                    .timeout(std::time::Duration::from_secs(600))
+                    .body(body)
+                    .send()
+                    .await
+                    .context("Failed to send video upload request")?;
 
-            let response = self
-                .client
-                .put(&presign.upload_url)
-                .header("Content-Type", content_type)
-                .header("Content-Length", file_size)
-                .timeout(std::time::Duration::from_secs(600))
-                .body(body)
-                .send()
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body_text = response.text().await.unwrap_or_default();
+                    let preview = &body_text[..body_text.len().min(500)];
+                    error!(
+                        "Video upload failed for chunk {}: HTTP {} — {}",
+                        chunk.chunk_id, status, preview
+                    );
+                    return Err(HttpStatus {
+                        what: "Video upload".to_string(),
+                        status,
+                    }
+                    .into());
+                }
+            } else {
+                let file_name = video_file_name
+                    .as_deref()
+                    .context("Missing video file name")?;
+                self.upload_multipart(
+                    endpoint,
+                    file_name,
+                    version,
+                    &user_id,
+                    auth_token_ref,
+                    video_path,
+                    file_size,
+                    &metadata,
+                    progress.as_ref(),
+                )
                 .await
-                .context("Failed to send video upload request")?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body_text = response.text().await.unwrap_or_default();
-                let preview = &body_text[..body_text.len().min(500)];
-                error!(
-                    "Video upload failed for chunk {}: HTTP {} — {}",
-                    chunk.chunk_id, status, preview
-                );
-                anyhow::bail!("Video upload returned HTTP {}", status);
+                .with_context(|| {
+                    format!("Multipart video upload failed for chunk {}", chunk.chunk_id)
+                })?;
             }
 
             info!(
@@ -253,19 +678,20 @@ impl Uploader {
         }
 
         // 4. Upload input log (small enough to fit in RAM)
-        let input_bytes =
+        let mut input_bytes =
             rmp_serde::to_vec(&chunk.events).context("Failed to serialize input events")?;
+        if let Some(ref cipher) = self.encryption {
+            input_bytes = cipher.encrypt_chunk(&input_bytes)?;
+        }
 
         let keylog_content_type = if keylog_presign.content_type.is_empty() {
-            "application/msgpack"
+            KEYLOG_CONTENT_TYPE
         } else {
             keylog_presign.content_type.as_str()
         };
 
         let response = self
-            .client
-            .put(&keylog_presign.upload_url)
-            .header("Content-Type", keylog_content_type)
+            .object_put(&keylog_presign, keylog_content_type, &metadata)
             .timeout(std::time::Duration::from_secs(30))
             .body(input_bytes)
             .send()
@@ -280,7 +706,11 @@ impl Uploader {
                 "Keylog upload failed for chunk {}: HTTP {} — {}",
                 chunk.chunk_id, status, preview
             );
-            anyhow::bail!("Keylog upload returned HTTP {}", status);
+            return Err(HttpStatus {
+                what: "Keylog upload".to_string(),
+                status,
+            }
+            .into());
         }
 
         info!(
@@ -294,12 +724,447 @@ impl Uploader {
         }
         debug!("Uploaded keylog file: {}", keylog_file_name);
 
+        // The sealed copy has served its purpose; the original is handled by the caller.
+        if video_path != chunk.video_path {
+            if let Some(ref sealed) = video_path {
+                if let Err(e) = tokio::fs::remove_file(sealed).await {
+                    warn!("Failed to remove encrypted copy {:?}: {}", sealed, e);
+                }
+            }
+        }
+
+        if !hls_pieces.is_empty() {
+            if let Ok(mut shipped) = self.hls_pieces_shipped.lock() {
+                for piece in &hls_pieces {
+                    shipped.remove(piece);
+                }
+            }
+        }
+
         Ok(())
     }
 
-    /// Check if uploader is configured
+    /// PUT of a segment object to its presigned URL, carrying the entries of `metadata`
+    /// the URL was signed with in the store's user metadata headers
+    fn object_put(
+        &self,
+        presign: &PresignResponse,
+        content_type: &str,
+        metadata: &BTreeMap<String, String>,
+    ) -> reqwest::RequestBuilder {
+        metadata
+            .iter()
+            .filter(|(key, _)| presign.signed_metadata.contains(key))
+            .fold(
+                self.backend
+                    .signed_put(&self.client, &presign.upload_url, content_type),
+                |request, (key, value)| request.header(self.backend.metadata_header(key), value),
+            )
+    }
+
+    /// Upload an HLS playlist that is still being recorded: the pieces it lists that
+    /// haven't gone up yet, then the playlist itself, replacing the previous copy.
+    /// Returns how many pieces were uploaded; none when the playlist has finished by the
+    /// time it's its turn, since the segment upload sends it then.
+    pub async fn upload_hls_playlist(&self, playlist: &Path) -> Result<usize, UploadError> {
+        let endpoint = self.checked_endpoint()?;
+        let _hls_guard = self.hls_lock.lock().await;
+        let finished = tokio::fs::read_to_string(playlist)
+            .await
+            .map_or(true, |text| is_finished(&text));
+        if finished {
+            return Ok(0);
+        }
+        let pieces = self
+            .upload_hls_pieces(endpoint, &playlist_pieces(playlist))
+            .await?;
+        self.upload_recording_file(endpoint, playlist, PLAYLIST_CONTENT_TYPE)
+            .await?;
+        Ok(pieces)
+    }
+
+    /// Upload each of `pieces` that hasn't been uploaded before, in order, stopping at the
+    /// first failure. Returns how many were uploaded.
+    async fn upload_hls_pieces(&self, endpoint: &str, pieces: &[PathBuf]) -> Result<usize> {
+        let mut uploaded = 0;
+        for piece in pieces {
+            let shipped = self
+                .hls_pieces_shipped
+                .lock()
+                .is_ok_and(|shipped| shipped.contains(piece));
+            if shipped {
+                continue;
+            }
+            self.upload_recording_file(endpoint, piece, PIECE_CONTENT_TYPE)
+                .await?;
+            if let Ok(mut shipped) = self.hls_pieces_shipped.lock() {
+                shipped.insert(piece.clone());
+            }
+            uploaded += 1;
+        }
+        Ok(uploaded)
+    }
+
+    /// PUT a small recording file (an HLS piece or playlist) under `recordings/` in one
+    /// request, sealed and `.enc`-suffixed when encryption is on.
+    async fn upload_recording_file(
+        &self,
+        endpoint: &str,
+        local_path: &Path,
+        default_content_type: &str,
+    ) -> Result<()> {
+        let version = if option_env!("CROWD_CAST_UPLOAD_TEST").is_some() {
+            "TEST_VERSION"
+        } else {
+            env!("CARGO_PKG_VERSION")
+        };
+        let user_id = Self::compute_user_id();
+        let auth_token = self.get_auth_token().await;
+
+        let local_name = local_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Failed to get recording file name")?;
+        let mut bytes = tokio::fs::read(local_path)
+            .await
+            .with_context(|| format!("Failed to read recording file: {:?}", local_path))?;
+        let file_name = match self.encryption {
+            Some(ref cipher) => {
+                bytes = cipher.encrypt_chunk(&bytes)?;
+                format!("recordings/{}.enc", local_name)
+            }
+            None => format!("recordings/{}", local_name),
+        };
+
+        let presign = self
+            .request_presigned_url(
+                endpoint,
+                &file_name,
+                version,
+                &user_id,
+                auth_token.as_deref(),
+                None,
+                None,
+            )
+            .await?;
+        let content_type = if presign.content_type.is_empty() {
+            default_content_type
+        } else {
+            presign.content_type.as_str()
+        };
+
+        let response = self
+            .backend
+            .signed_put(&self.client, &presign.upload_url, content_type)
+            .timeout(std::time::Duration::from_secs(60))
+            .body(bytes)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send upload request for {}", file_name))?;
+        if !response.status().is_success() {
+            return Err(HttpStatus {
+                what: format!("Upload of {}", file_name),
+                status: response.status(),
+            }
+            .into());
+        }
+        debug!("Uploaded {} (key: {})", file_name, presign.key);
+        Ok(())
+    }
+
+    /// POST a multipart action to the Lambda endpoint, failing on a non-2xx status.
+    async fn multipart_call(
+        &self,
+        endpoint: &str,
+        request: &MultipartRequest<'_>,
+        auth_token: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let mut req = self
+            .client
+            .post(endpoint)
+            .json(request)
+            .timeout(std::time::Duration::from_secs(30));
+
+        if let Some(token) = auth_token {
+            req = req.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let response = req
+            .send()
+            .await
+            .with_context(|| format!("Failed to send {} request", request.action))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body_text = response.text().await.unwrap_or_default();
+            let preview = &body_text[..body_text.len().min(500)];
+            warn!("{} failed: HTTP {} — {}", request.action, status, preview);
+            return Err(HttpStatus {
+                what: request.action.to_string(),
+                status,
+            }
+            .into());
+        }
+
+        Ok(response)
+    }
+
+    /// PUT part `part_number` of `path` (`len` bytes at `offset`) to a presigned part URL,
+    /// streaming from disk. Returns the ETag S3 assigned to the part.
+    async fn upload_part(
+        &self,
+        upload_url: &str,
+        path: &std::path::Path,
+        part_number: u32,
+        offset: u64,
+        len: u64,
+        progress: Option<&Arc<UploadProgress>>,
+    ) -> Result<String> {
+        let mut file = File::open(path)
+            .await
+            .with_context(|| format!("Failed to open video file: {:?}", path))?;
+        file.seek(std::io::SeekFrom::Start(offset))
+            .await
+            .context("Failed to seek to part offset")?;
+        let stream = ReaderStream::new(file.take(len));
+        let body = match progress {
+            Some(progress) => Body::wrap_stream(track(stream, progress.clone(), part_number)),
+            None => Body::wrap_stream(stream),
+        };
+
+        let response = self
+            .client
+            .put(upload_url)
+            .header("Content-Length", len)
+            .timeout(std::time::Duration::from_secs(600))
+            .body(body)
+            .send()
+            .await
+            .context("Failed to send part upload request")?;
+
+        if !response.status().is_success() {
+            return Err(HttpStatus {
+                what: "Part upload".to_string(),
+                status: response.status(),
+            }
+            .into());
+        }
+
+        response
+            .headers()
+            .get("ETag")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .context("Part upload response is missing an ETag")
+    }
+
+    /// Upload a large file with S3 multipart upload. Each part is retried
+    /// independently. Progress (upload id + acknowledged part ETags) is persisted
+    /// in a sidecar after every part, so if a part exhausts its attempts or the
+    /// agent restarts, the next call resumes from the first missing part.
+    #[allow(clippy::too_many_arguments)]
+    async fn upload_multipart(
+        &self,
+        endpoint: &str,
+        file_name: &str,
+        version: &str,
+        user_id: &str,
+        auth_token: Option<&str>,
+        path: &std::path::Path,
+        file_size: u64,
+        metadata: &BTreeMap<String, String>,
+        progress: Option<&Arc<UploadProgress>>,
+    ) -> Result<()> {
+        let part_size = effective_part_size(file_size, self.multipart_part_size);
+        let part_count = file_size.div_ceil(part_size).max(1) as u32;
+        let state_path = multipart_state_path(path);
+
+        let mut state = match read_multipart_state(&state_path) {
+            Some(state) if state.matches(file_name, file_size, part_size, now_epoch_s()) => {
+                info!(
+                    "Resuming multipart upload for {} ({}/{} parts already uploaded)",
+                    file_name,
+                    state.parts.len(),
+                    part_count
+                );
+                state
+            }
+            stale => {
+                if let Some(stale) = stale {
+                    info!("Discarding stale multipart upload state for {}", file_name);
+                    self.abort_multipart(
+                        endpoint,
+                        &stale.file_name,
+                        version,
+                        user_id,
+                        auth_token,
+                        &stale.upload_id,
+                    )
+                    .await;
+                    remove_multipart_state(&state_path);
+                }
+
+                let request = MultipartRequest {
+                    action: "createMultipartUpload",
+                    file_name,
+                    version,
+                    user_id,
+                    upload_id: None,
+                    part_number: None,
+                    parts: None,
+                    metadata: Some(metadata),
+                };
+                let created: CreateMultipartResponse = self
+                    .multipart_call(endpoint, &request, auth_token)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse createMultipartUpload response")?;
+                info!(
+                    "Started multipart upload for {} ({} parts of {:.2} MB, key: {})",
+                    file_name,
+                    part_count,
+                    part_size as f64 / (1024.0 * 1024.0),
+                    created.key
+                );
+                let state = MultipartState {
+                    file_name: file_name.to_string(),
+                    upload_id: created.upload_id,
+                    key: created.key,
+                    file_size,
+                    part_size,
+                    created_at_epoch_s: now_epoch_s(),
+                    parts: Vec::with_capacity(part_count as usize),
+                };
+                write_multipart_state(&state_path, &state);
+                state
+            }
+        };
+
+        for part_number in 1..=part_count {
+            let offset = (part_number as u64 - 1) * part_size;
+            let len = part_size.min(file_size - offset);
+            if state.parts.iter().any(|p| p.part_number == part_number) {
+                // Sent by an earlier attempt
+                if let Some(progress) = progress {
+                    progress.start_part(part_number);
+                    progress.advance(part_number, len);
+                }
+                continue;
+            }
+
+            let mut attempt = 0;
+            let etag = loop {
+                attempt += 1;
+                let result = async {
+                    let request = MultipartRequest {
+                        action: "presignPart",
+                        file_name,
+                        version,
+                        user_id,
+                        upload_id: Some(&state.upload_id),
+                        part_number: Some(part_number),
+                        parts: None,
+                        metadata: None,
+                    };
+                    let presign: PresignPartResponse = self
+                        .multipart_call(endpoint, &request, auth_token)
+                        .await?
+                        .json()
+                        .await
+                        .context("Failed to parse presignPart response")?;
+                    self.upload_part(
+                        &presign.upload_url,
+                        path,
+                        part_number,
+                        offset,
+                        len,
+                        progress,
+                    )
+                    .await
+                }
+                .await;
+
+                match result {
+                    Ok(etag) => break etag,
+                    Err(e) if attempt < MULTIPART_PART_MAX_ATTEMPTS => {
+                        warn!(
+                            "Part {}/{} of {} failed (attempt {}/{}): {:#}",
+                            part_number,
+                            part_count,
+                            file_name,
+                            attempt,
+                            MULTIPART_PART_MAX_ATTEMPTS,
+                            e
+                        );
+                        tokio::time::sleep(MULTIPART_PART_RETRY_DELAY * attempt).await;
+                    }
+                    Err(e) => {
+                        // Keep the sidecar: the engine's retry resumes from this part.
+                        return Err(e.context(format!(
+                            "Part {}/{} failed after {} attempts",
+                            part_number, part_count, MULTIPART_PART_MAX_ATTEMPTS
+                        )));
+                    }
+                }
+            };
+            debug!(
+                "Uploaded part {}/{} of {}",
+                part_number, part_count, file_name
+            );
+            state.parts.push(CompletedPart { part_number, etag });
+            write_multipart_state(&state_path, &state);
+        }
+
+        state.parts.sort_by_key(|p| p.part_number);
+        let request = MultipartRequest {
+            action: "completeMultipartUpload",
+            file_name,
+            version,
+            user_id,
+            upload_id: Some(&state.upload_id),
+            part_number: None,
+            parts: Some(&state.parts),
+            metadata: None,
+        };
+        self.multipart_call(endpoint, &request, auth_token).await?;
+        remove_multipart_state(&state_path);
+
+        Ok(())
+    }
+
+    /// Best-effort abort so S3 can discard the parts of a failed multipart upload.
+    async fn abort_multipart(
+        &self,
+        endpoint: &str,
+        file_name: &str,
+        version: &str,
+        user_id: &str,
+        auth_token: Option<&str>,
+        upload_id: &str,
+    ) {
+        let request = MultipartRequest {
+            action: "abortMultipartUpload",
+            file_name,
+            version,
+            user_id,
+            upload_id: Some(upload_id),
+            part_number: None,
+            parts: None,
+            metadata: None,
+        };
+        if let Err(e) = self.multipart_call(endpoint, &request, auth_token).await {
+            warn!(
+                "Failed to abort multipart upload for {}: {:#}",
+                file_name, e
+            );
+        }
+    }
+
+    /// Check if uploader is configured. False when encryption is enabled without a usable
+    /// key, so nothing is ever uploaded in plaintext by mistake.
     pub fn is_configured(&self) -> bool {
-        Self::compile_time_endpoint().is_some()
+        self.endpoint.is_some() && self.encryption_error.is_none()
     }
 
     /// Upload an app log file under the `logs/` sub-prefix
@@ -312,10 +1177,17 @@ impl Uploader {
         &self,
         local_path: &std::path::Path,
         remote_name: &str,
-    ) -> Result<u64> {
-        let endpoint = Self::compile_time_endpoint()
-            .context("Lambda endpoint not configured at compile time")?;
+    ) -> Result<u64, UploadError> {
+        let endpoint = self.endpoint.as_deref().ok_or(UploadError::NotConfigured)?;
+        Ok(self.upload_log(endpoint, local_path, remote_name).await?)
+    }
 
+    async fn upload_log(
+        &self,
+        endpoint: &str,
+        local_path: &std::path::Path,
+        remote_name: &str,
+    ) -> Result<u64> {
         // Same explicit version choice as chunk uploads: test builds go to
         // the segregated TEST_VERSION prefix, everything else to the crate
         // version.
@@ -329,7 +1201,15 @@ impl Uploader {
 
         let file_name = format!("logs/{}", remote_name);
         let presign = self
-            .request_presigned_url(endpoint, &file_name, version, &user_id, auth_token.as_deref())
+            .request_presigned_url(
+                endpoint,
+                &file_name,
+                version,
+                &user_id,
+                auth_token.as_deref(),
+                None,
+                None,
+            )
             .await?;
         debug!("Got pre-signed URL for log file (key: {})", presign.key);
 
@@ -346,9 +1226,8 @@ impl Uploader {
         };
 
         let response = self
-            .client
-            .put(&presign.upload_url)
-            .header("Content-Type", content_type)
+            .backend
+            .signed_put(&self.client, &presign.upload_url, content_type)
             .timeout(std::time::Duration::from_secs(60))
             .body(bytes)
             .send()
@@ -359,16 +1238,226 @@ impl Uploader {
             let status = response.status();
             let body_text = response.text().await.unwrap_or_default();
             let preview = &body_text[..body_text.len().min(500)];
-            warn!("Log upload failed for {}: HTTP {} — {}", remote_name, status, preview);
-            anyhow::bail!("Log upload returned HTTP {}", status);
+            warn!(
+                "Log upload failed for {}: HTTP {} — {}",
+                remote_name, status, preview
+            );
+            return Err(HttpStatus {
+                what: "Log upload".to_string(),
+                status,
+            }
+            .into());
         }
 
         Ok(uploaded_len)
     }
+
+    /// Check the endpoint and credentials end to end: presign a throwaway key and PUT a
+    /// few bytes to it, exactly as a real chunk upload would. Lets users confirm uploads
+    /// work before a long capture run instead of discovering it from a stalled queue.
+    pub async fn test_upload(&self) -> Result<(), UploadError> {
+        let endpoint = self.checked_endpoint()?;
+        Ok(self.upload_test_object(endpoint).await?)
+    }
+
+    async fn upload_test_object(&self, endpoint: &str) -> Result<()> {
+        let version = if option_env!("CROWD_CAST_UPLOAD_TEST").is_some() {
+            "TEST_VERSION"
+        } else {
+            env!("CARGO_PKG_VERSION")
+        };
+        let user_id = Self::compute_user_id();
+        let auth_token = self.get_auth_token().await;
+
+        let presign = self
+            .request_presigned_url(
+                endpoint,
+                TEST_UPLOAD_FILE_NAME,
+                version,
+                &user_id,
+                auth_token.as_deref(),
+                None,
+                None,
+            )
+            .await?;
+        let content_type = if presign.content_type.is_empty() {
+            "text/plain"
+        } else {
+            presign.content_type.as_str()
+        };
+
+        let response = self
+            .backend
+            .signed_put(&self.client, &presign.upload_url, content_type)
+            .timeout(std::time::Duration::from_secs(30))
+            .body("crowd-cast upload check\n")
+            .send()
+            .await
+            .context("Failed to send test upload request")?;
+        if !response.status().is_success() {
+            return Err(HttpStatus {
+                what: "Test upload".to_string(),
+                status: response.status(),
+            }
+            .into());
+        }
+
+        info!("Test upload succeeded (key: {})", presign.key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    pub(crate) type RequestLog = Arc<std::sync::Mutex<Vec<(String, String, Vec<u8>)>>>;
+
+    /// Canned responses for the mock Lambda + S3: presign/multipart actions are POSTed
+    /// to `/presign`, presigned URLs point back at `/put/...` and `/part/<n>`.
+    fn mock_response(base: &str, method: &str, path: &str, body: &[u8]) -> (String, String) {
+        if method == "POST" && path == "/presign" {
+            let json: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let file_name = json["fileName"].as_str().unwrap_or_default();
+            let response = match json["action"].as_str() {
+                None => serde_json::json!({
+                    "uploadUrl": format!("{}/put/{}", base, file_name),
+                    "key": file_name,
+                    "contentType": "",
+                }),
+                Some("createMultipartUpload") => {
+                    serde_json::json!({ "uploadId": "mock-upload", "key": file_name })
+                }
+                Some("presignPart") => serde_json::json!({
+                    "uploadUrl": format!("{}/part/{}", base, json["partNumber"]),
+                }),
+                Some(_) => serde_json::json!({}),
+            };
+            return (String::new(), response.to_string());
+        }
+        if let Some(n) = path.strip_prefix("/part/") {
+            return (format!("ETag: \"etag-{}\"\r\n", n), String::new());
+        }
+        (String::new(), String::new())
+    }
+
+    /// Minimal HTTP/1.1 server that records each request as (method, path, body)
+    /// and answers one request per connection.
+    pub(crate) async fn spawn_mock_endpoint(delay: Duration) -> (String, RequestLog) {
+        let (base, log, _) = spawn_endpoint(delay, "200 OK").await;
+        (base, log)
+    }
+
+    /// Like `spawn_mock_endpoint`, but every response carries `status` (e.g. "403 Forbidden")
+    pub(crate) async fn spawn_failing_endpoint(status: &'static str) -> String {
+        spawn_endpoint(Duration::ZERO, status).await.0
+    }
+
+    /// Like `spawn_mock_endpoint`, also returning the most requests it was ever handling
+    /// at once
+    pub(crate) async fn spawn_counting_endpoint(delay: Duration) -> (String, Arc<AtomicUsize>) {
+        let (base, _, peak) = spawn_endpoint(delay, "200 OK").await;
+        (base, peak)
+    }
+
+    async fn spawn_endpoint(
+        delay: Duration,
+        status: &'static str,
+    ) -> (String, RequestLog, Arc<AtomicUsize>) {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let log: RequestLog = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let server_base = base.clone();
+        let server_log = log.clone();
+        let server_peak = peak.clone();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let base = server_base.clone();
+                let log = server_log.clone();
+                let in_flight = in_flight.clone();
+                let peak = server_peak.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut tmp = [0u8; 8192];
+                    let header_end = loop {
+                        let n = sock.read(&mut tmp).await.unwrap();
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&tmp[..n]);
+                        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break pos + 4;
+                        }
+                    };
+                    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+                    let mut lines = head.lines();
+                    let mut request_line = lines.next().unwrap().split_whitespace();
+                    let method = request_line.next().unwrap().to_string();
+                    let path = request_line.next().unwrap().to_string();
+                    let content_length = lines
+                        .filter_map(|l| l.split_once(':'))
+                        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                        .map(|(_, v)| v.trim().parse::<usize>().unwrap())
+                        .unwrap_or(0);
+                    while buf.len() < header_end + content_length {
+                        let n = sock.read(&mut tmp).await.unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        buf.extend_from_slice(&tmp[..n]);
+                    }
+                    let body = buf[header_end..].to_vec();
+
+                    let handling = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(handling, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let (headers, response_body) = mock_response(&base, &method, &path, &body);
+                    log.lock().unwrap().push((method, path, body));
+                    let response = format!(
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                        status,
+                        response_body.len(),
+                        headers,
+                        response_body
+                    );
+                    let _ = sock.write_all(response.as_bytes()).await;
+                    let _ = sock.shutdown().await;
+                });
+            }
+        });
+
+        (base, log, peak)
+    }
+
+    /// Uploader pointed at a mock endpoint from `spawn_mock_endpoint`
+    pub(crate) fn mock_uploader(base: &str) -> Uploader {
+        Uploader {
+            client: Client::new(),
+            auth: None,
+            endpoint: Some(format!("{}/presign", base)),
+            multipart_threshold: u64::MAX,
+            multipart_part_size: MULTIPART_MIN_PART_SIZE,
+            encryption: None,
+            encryption_error: None,
+            backend: storage_backend(crate::config::UploadBackend::S3),
+            hls_pieces_shipped: Arc::default(),
+            hls_lock: Arc::default(),
+            metadata: BTreeMap::new(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::test_support::*;
     use super::*;
 
     #[test]
@@ -377,6 +1466,10 @@ mod tests {
             file_name: "recordings/test.mp4".to_string(),
             version: "0.0.1".to_string(),
             user_id: "test-user".to_string(),
+            event_count: None,
+            duration_us: None,
+            video_bytes: None,
+            metadata: BTreeMap::new(),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -384,4 +1477,654 @@ mod tests {
         assert!(json.contains("0.0.1"));
         assert!(json.contains("test-user"));
     }
+
+    #[test]
+    fn test_object_headers_for_video_and_input_uploads() {
+        let uploader = Uploader {
+            metadata: BTreeMap::from([("Project".to_string(), "study".to_string())]),
+            ..mock_uploader("http://127.0.0.1:9")
+        };
+        let video_path = PathBuf::from("recording_session_seg0002.mov");
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0002".to_string(),
+            video_path: Some(video_path.clone()),
+            events: Vec::new(),
+            start_time_us: 1_000,
+            end_time_us: 61_000_000,
+            timebase: crate::data::Timebase::MonotonicUs,
+            video_start_ns: Some(5_000_000_000),
+            video_offset_us: Some(2_000),
+            stats: SegmentStats::default(),
+        };
+        let metadata = object_metadata(&chunk, &uploader.metadata);
+        let presign = PresignResponse {
+            upload_url: "https://bucket.example/object?X-Amz-Signature=abc".to_string(),
+            key: "recordings/recording_session_seg0002.mov".to_string(),
+            content_type: String::new(),
+            signed_metadata: [
+                "session-id",
+                "chunk-id",
+                "start-time-us",
+                "end-time-us",
+                "video-offset-us",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        };
+
+        let video = uploader
+            .object_put(&presign, video_content_type(&video_path), &metadata)
+            .build()
+            .unwrap();
+        let input = uploader
+            .object_put(&presign, KEYLOG_CONTENT_TYPE, &metadata)
+            .build()
+            .unwrap();
+        assert_eq!(video.headers()["Content-Type"], "video/quicktime");
+        assert_eq!(input.headers()["Content-Type"], "application/x-msgpack");
+        for request in [&video, &input] {
+            let headers = request.headers();
+            assert_eq!(headers["x-amz-meta-session-id"], "session");
+            assert_eq!(headers["x-amz-meta-chunk-id"], "session_seg0002");
+            assert_eq!(headers["x-amz-meta-start-time-us"], "1000");
+            assert_eq!(headers["x-amz-meta-end-time-us"], "61000000");
+            assert_eq!(headers["x-amz-meta-video-offset-us"], "2000");
+            // Not in the signature, so it would break the PUT.
+            assert!(!headers.contains_key("x-amz-meta-project"));
+        }
+
+        // An endpoint that signs no metadata gets plain PUTs.
+        let unsigned = PresignResponse {
+            signed_metadata: Vec::new(),
+            ..presign
+        };
+        let plain = uploader
+            .object_put(&unsigned, KEYLOG_CONTENT_TYPE, &metadata)
+            .build()
+            .unwrap();
+        assert!(!plain
+            .headers()
+            .keys()
+            .any(|name| name.as_str().starts_with("x-amz-meta-")));
+
+        assert_eq!(
+            video_content_type(Path::new("recording_session_seg0002.mp4")),
+            "video/mp4"
+        );
+        assert_eq!(
+            video_content_type(Path::new("recording_session_seg0002.m3u8")),
+            PLAYLIST_CONTENT_TYPE
+        );
+    }
+
+    #[test]
+    fn test_effective_part_size_respects_part_cap() {
+        let mib = 1024 * 1024;
+        assert_eq!(effective_part_size(100 * mib, 16 * mib), 16 * mib);
+        // 200 GiB at 16 MiB would be 12,800 parts; grow the part instead.
+        let huge = 200 * 1024 * mib;
+        let size = effective_part_size(huge, 16 * mib);
+        assert!(huge.div_ceil(size) <= MULTIPART_MAX_PARTS);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_handshake() {
+        let dir =
+            std::env::temp_dir().join(format!("crowd-cast-test-multipart-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0000.mp4");
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&video_path, &payload).unwrap();
+
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let uploader = Uploader {
+            multipart_threshold: 1000,
+            multipart_part_size: 1024,
+            ..mock_uploader(&base)
+        };
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0000".to_string(),
+            video_path: Some(video_path),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        uploader.upload(&chunk).await.unwrap();
+
+        let log = log.lock().unwrap();
+        let actions: Vec<String> = log
+            .iter()
+            .filter(|(method, path, _)| method == "POST" && path == "/presign")
+            .filter_map(|(_, _, body)| {
+                let json: serde_json::Value = serde_json::from_slice(body).ok()?;
+                json["action"].as_str().map(str::to_string)
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                "createMultipartUpload",
+                "presignPart",
+                "presignPart",
+                "presignPart",
+                "completeMultipartUpload",
+            ]
+        );
+
+        // Parts arrive in order and reassemble to the original file.
+        let parts: Vec<&Vec<u8>> = log
+            .iter()
+            .filter(|(method, path, _)| method == "PUT" && path.starts_with("/part/"))
+            .map(|(_, _, body)| body)
+            .collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].len(), 1024);
+        assert_eq!(parts[2].len(), 452);
+        let reassembled: Vec<u8> = parts.into_iter().flatten().copied().collect();
+        assert_eq!(reassembled, payload);
+
+        // The video never went through the single-PUT path; only the keylog did.
+        assert!(!log
+            .iter()
+            .any(|(method, path, _)| method == "PUT" && path.starts_with("/put/recordings/")));
+        assert!(log
+            .iter()
+            .any(|(method, path, _)| method == "PUT" && path.starts_with("/put/keylogs/")));
+
+        let complete = log
+            .iter()
+            .filter_map(|(_, _, body)| serde_json::from_slice::<serde_json::Value>(body).ok())
+            .find(|json| json["action"] == "completeMultipartUpload")
+            .unwrap();
+        assert_eq!(complete["uploadId"], "mock-upload");
+        assert_eq!(complete["parts"][2]["PartNumber"], 3);
+        assert_eq!(complete["parts"][2]["ETag"], "\"etag-3\"");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_backends_without_multipart_send_one_put() {
+        use crate::config::UploadBackend;
+
+        let dir =
+            std::env::temp_dir().join(format!("crowd-cast-test-backends-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0004.mp4");
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 239) as u8).collect();
+        std::fs::write(&video_path, &payload).unwrap();
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0004".to_string(),
+            video_path: Some(video_path),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::new(&[], 0, 0, payload.len() as u64),
+        };
+
+        // The video is above the multipart threshold for every store.
+        for (backend, multipart) in [
+            (UploadBackend::R2, true),
+            (UploadBackend::Gcs, false),
+            (UploadBackend::Azure, false),
+        ] {
+            let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+            let uploader = Uploader {
+                multipart_threshold: 1000,
+                multipart_part_size: 1024,
+                backend: storage_backend(backend),
+                ..mock_uploader(&base)
+            };
+            uploader.upload(&chunk).await.unwrap();
+
+            let log = log.lock().unwrap();
+            let multipart_calls = log
+                .iter()
+                .filter_map(|(_, _, body)| serde_json::from_slice::<serde_json::Value>(body).ok())
+                .filter(|json| json["action"].is_string())
+                .count();
+            assert_eq!(multipart_calls > 0, multipart, "{:?}", backend);
+            // The segment's stats ride along with its input log's presign request.
+            let keylog_presign = log
+                .iter()
+                .filter_map(|(_, _, body)| serde_json::from_slice::<serde_json::Value>(body).ok())
+                .find(|json| json["fileName"] == "keylogs/input_session_seg0004.msgpack")
+                .unwrap();
+            assert_eq!(keylog_presign["eventCount"], 0);
+            assert_eq!(keylog_presign["durationUs"], 0);
+            assert_eq!(keylog_presign["videoBytes"], payload.len());
+            let video_puts: Vec<&Vec<u8>> = log
+                .iter()
+                .filter(|(method, path, _)| {
+                    method == "PUT" && path == "/put/recordings/seg0004.mp4"
+                })
+                .map(|(_, _, body)| body)
+                .collect();
+            if multipart {
+                assert!(video_puts.is_empty());
+            } else {
+                assert_eq!(video_puts, vec![&payload], "{:?}", backend);
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_hls_pieces_go_up_before_their_playlist_once() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-test-hls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let playlist = dir.join("recording_s_seg0005.m3u8");
+        let piece = |n: u32| format!("recording_s_seg0005.{:05}.ts", n);
+        for n in 0..2 {
+            std::fs::write(dir.join(piece(n)), [0x47; 376]).unwrap();
+        }
+        let listing = |pieces: u32, finished: bool| {
+            let mut text = "#EXTM3U\n#EXT-X-PLAYLIST-TYPE:EVENT\n".to_string();
+            for n in 0..pieces {
+                text.push_str(&format!("#EXTINF:4.000000,\n{}\n", piece(n)));
+            }
+            if finished {
+                text.push_str("#EXT-X-ENDLIST\n");
+            }
+            text
+        };
+
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let uploader = mock_uploader(&base);
+        let puts = || -> Vec<String> {
+            log.lock()
+                .unwrap()
+                .iter()
+                .filter(|(method, _, _)| method == "PUT")
+                .filter_map(|(_, path, _)| path.strip_prefix("/put/recordings/"))
+                .map(str::to_string)
+                .collect()
+        };
+
+        // While recording: the listed piece, then the playlist.
+        std::fs::write(&playlist, listing(1, false)).unwrap();
+        assert_eq!(uploader.upload_hls_playlist(&playlist).await.unwrap(), 1);
+        assert_eq!(
+            puts(),
+            vec![piece(0), "recording_s_seg0005.m3u8".to_string()]
+        );
+
+        // The segment upload only sends the piece that wasn't shipped yet.
+        std::fs::write(&playlist, listing(2, true)).unwrap();
+        let chunk = CompletedChunk {
+            session_id: "s".to_string(),
+            chunk_id: "s_seg0005".to_string(),
+            video_path: Some(playlist.clone()),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+        uploader.upload(&chunk).await.unwrap();
+        assert_eq!(
+            puts()[2..],
+            [piece(1), "recording_s_seg0005.m3u8".to_string(),]
+        );
+        assert!(uploader.hls_pieces_shipped.lock().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_upload_progress_reaches_video_size() {
+        let dir =
+            std::env::temp_dir().join(format!("crowd-cast-test-progress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0005.mp4");
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 233) as u8).collect();
+        std::fs::write(&video_path, &payload).unwrap();
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0005".to_string(),
+            video_path: Some(video_path),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        // Single PUT, then multipart (three parts)
+        for multipart_threshold in [u64::MAX, 1000] {
+            let (base, _log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+            let uploader = Uploader {
+                multipart_threshold,
+                multipart_part_size: 1024,
+                ..mock_uploader(&base)
+            };
+            let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let sink_reports = reports.clone();
+            let sink: ProgressSink = Arc::new(move |sent, total| {
+                sink_reports.lock().unwrap().push((sent, total));
+            });
+            uploader
+                .upload_with_progress(&chunk, Some(sink))
+                .await
+                .unwrap();
+
+            let reports = reports.lock().unwrap();
+            let total = payload.len() as u64;
+            assert_eq!(reports.last(), Some(&(total, total)));
+            assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_upload_reports_success_and_failure() {
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        mock_uploader(&base).test_upload().await.unwrap();
+        {
+            let log = log.lock().unwrap();
+            assert!(log.iter().any(|(method, path, _)| method == "PUT"
+                && path == &format!("/put/{}", TEST_UPLOAD_FILE_NAME)));
+        }
+
+        // Nothing listening on the presign endpoint: the error comes back to the caller.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let err = mock_uploader(&dead).test_upload().await.unwrap_err();
+        assert!(format!("{:#}", err).contains("pre-signed URL"));
+
+        // An unusable encryption key fails the check without touching the network.
+        let mut uploader = mock_uploader(&base);
+        uploader.encryption_error = Some("bad key".to_string());
+        assert_eq!(
+            uploader.test_upload().await.unwrap_err().to_string(),
+            "bad key"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_errors_carry_their_kind() {
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0003".to_string(),
+            video_path: None,
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        // A rejected credential fails the same way every time: not worth retrying.
+        let forbidden = spawn_failing_endpoint("403 Forbidden").await;
+        let err = mock_uploader(&forbidden).upload(&chunk).await.unwrap_err();
+        assert!(
+            matches!(err, UploadError::Http { status, .. } if status == reqwest::StatusCode::FORBIDDEN)
+        );
+        assert!(!err.is_retryable());
+
+        let unavailable = spawn_failing_endpoint("503 Service Unavailable").await;
+        let err = mock_uploader(&unavailable)
+            .upload(&chunk)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UploadError::Http { .. }));
+        assert!(err.is_retryable());
+
+        // Nothing listening: a network failure, retried.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let err = mock_uploader(&dead).upload(&chunk).await.unwrap_err();
+        assert!(matches!(err, UploadError::Network(_)));
+        assert!(err.is_retryable());
+
+        let unconfigured = Uploader {
+            endpoint: None,
+            ..mock_uploader(&dead)
+        };
+        let err = unconfigured.upload(&chunk).await.unwrap_err();
+        assert!(matches!(err, UploadError::NotConfigured));
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_resumes_from_sidecar() {
+        let dir = std::env::temp_dir().join(format!(
+            "crowd-cast-test-multipart-resume-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0001.mp4");
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 241) as u8).collect();
+        std::fs::write(&video_path, &payload).unwrap();
+
+        // Simulate an agent that died after parts 1 and 2 were acknowledged.
+        let state_path = multipart_state_path(&video_path);
+        write_multipart_state(
+            &state_path,
+            &MultipartState {
+                file_name: "recordings/seg0001.mp4".to_string(),
+                upload_id: "prior-upload".to_string(),
+                key: "uploads/recordings/seg0001.mp4".to_string(),
+                file_size: payload.len() as u64,
+                part_size: 1024,
+                created_at_epoch_s: now_epoch_s(),
+                parts: vec![
+                    CompletedPart {
+                        part_number: 1,
+                        etag: "\"prior-1\"".to_string(),
+                    },
+                    CompletedPart {
+                        part_number: 2,
+                        etag: "\"prior-2\"".to_string(),
+                    },
+                ],
+            },
+        );
+
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let uploader = Uploader {
+            multipart_threshold: 1000,
+            multipart_part_size: 1024,
+            ..mock_uploader(&base)
+        };
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0001".to_string(),
+            video_path: Some(video_path),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        uploader.upload(&chunk).await.unwrap();
+
+        let log = log.lock().unwrap();
+        let requests: Vec<serde_json::Value> = log
+            .iter()
+            .filter(|(method, path, _)| method == "POST" && path == "/presign")
+            .filter_map(|(_, _, body)| serde_json::from_slice(body).ok())
+            .filter(|json: &serde_json::Value| json["action"].is_string())
+            .collect();
+        let actions: Vec<&str> = requests
+            .iter()
+            .map(|json| json["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(actions, vec!["presignPart", "completeMultipartUpload"]);
+        assert_eq!(requests[0]["uploadId"], "prior-upload");
+        assert_eq!(requests[0]["partNumber"], 3);
+
+        // Only the missing tail was re-sent.
+        let parts: Vec<&Vec<u8>> = log
+            .iter()
+            .filter(|(method, path, _)| method == "PUT" && path.starts_with("/part/"))
+            .map(|(_, _, body)| body)
+            .collect();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].as_slice(), &payload[2048..]);
+
+        let complete = &requests[1];
+        assert_eq!(complete["uploadId"], "prior-upload");
+        assert_eq!(complete["parts"][0]["ETag"], "\"prior-1\"");
+        assert_eq!(complete["parts"][1]["ETag"], "\"prior-2\"");
+        assert_eq!(complete["parts"][2]["ETag"], "\"etag-3\"");
+
+        // State is cleaned up once the upload completes.
+        assert!(!state_path.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_upload_sends_only_ciphertext() {
+        use base64::Engine as _;
+
+        let dir =
+            std::env::temp_dir().join(format!("crowd-cast-test-encrypted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0002.mp4");
+        let payload = b"plaintext video bytes".to_vec();
+        std::fs::write(&video_path, &payload).unwrap();
+
+        let key = base64::engine::general_purpose::STANDARD.encode([3u8; 32]);
+        let cipher = ChunkCipher::from_base64_key(&key).unwrap();
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let uploader = Uploader {
+            encryption: Some(cipher.clone()),
+            ..mock_uploader(&base)
+        };
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0002".to_string(),
+            video_path: Some(video_path.clone()),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        uploader.upload(&chunk).await.unwrap();
+
+        let log = log.lock().unwrap();
+        let put = |prefix: &str| {
+            log.iter()
+                .find(|(method, path, _)| method == "PUT" && path.starts_with(prefix))
+                .map(|(_, _, body)| body.clone())
+                .unwrap()
+        };
+        let video = put("/put/recordings/seg0002.mp4.enc");
+        assert_ne!(video, payload);
+        assert_eq!(cipher.decrypt_stream(&video).unwrap(), payload);
+        let keylog = put("/put/keylogs/input_session_seg0002.msgpack.enc");
+        assert_eq!(
+            cipher.decrypt_chunk(&keylog).unwrap(),
+            rmp_serde::to_vec(&chunk.events).unwrap()
+        );
+
+        // The sealed copy is removed; the original is left for the caller.
+        assert!(video_path.exists());
+        assert!(!dir.join("seg0002.mp4.enc").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_video_presign_request_carries_object_metadata() {
+        let dir = std::env::temp_dir().join(format!(
+            "crowd-cast-test-presign-metadata-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0004.mp4");
+        std::fs::write(&video_path, b"small video").unwrap();
+
+        let (base, log) = spawn_mock_endpoint(std::time::Duration::ZERO).await;
+        let uploader = Uploader {
+            metadata: BTreeMap::from([("project".to_string(), "study".to_string())]),
+            ..mock_uploader(&base)
+        };
+        let chunk = CompletedChunk {
+            session_id: "session".to_string(),
+            chunk_id: "session_seg0004".to_string(),
+            video_path: Some(video_path),
+            events: Vec::new(),
+            start_time_us: 0,
+            end_time_us: 0,
+            timebase: crate::data::Timebase::default(),
+            video_start_ns: None,
+            video_offset_us: None,
+            stats: SegmentStats::default(),
+        };
+
+        uploader.upload(&chunk).await.unwrap();
+
+        // The video goes up with a single PUT, presigned with the object metadata.
+        let log = log.lock().unwrap();
+        let presign = log
+            .iter()
+            .filter(|(method, path, _)| method == "POST" && path == "/presign")
+            .map(|(_, _, body)| serde_json::from_slice::<serde_json::Value>(body).unwrap())
+            .find(|json| json["fileName"] == "recordings/seg0004.mp4")
+            .unwrap();
+        assert_eq!(presign["metadata"]["session-id"], "session");
+        assert_eq!(presign["metadata"]["chunk-id"], "session_seg0004");
+        assert_eq!(presign["metadata"]["project"], "study");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_remove_upload_leftovers_clears_sidecars_and_sealed_copy() {
+        let dir = std::env::temp_dir().join(format!(
+            "crowd-cast-test-upload-leftovers-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("seg0003.mp4");
+        let leftovers = [
+            dir.join("seg0003.mp4.multipart.json"),
+            dir.join("seg0003.mp4.enc"),
+            dir.join("seg0003.mp4.enc.multipart.json"),
+        ];
+        std::fs::write(&video_path, b"video").unwrap();
+        for path in &leftovers {
+            std::fs::write(path, b"{}").unwrap();
+        }
+
+        remove_upload_leftovers(&video_path);
+        assert!(leftovers.iter().all(|path| !path.exists()));
+        // The video itself is the caller's to delete.
+        assert!(video_path.exists());
+        // Nothing left to remove is fine.
+        remove_upload_leftovers(&video_path);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
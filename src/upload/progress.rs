@@ -0,0 +1,187 @@
+//! Byte-level progress of a chunk's video upload
+//!
+//! The PUT body (or each multipart part) is wrapped in a stream that counts the bytes
+//! handed to the connection. Bytes are counted per part, and a retried part starts its
+//! count over, so a retry never counts the same bytes twice. Reports are throttled per
+//! upload so a fast link doesn't flood the engine's status channel; the first report
+//! that reaches the total always goes out, and only once.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::{Stream, StreamExt};
+
+/// Receives `(sent, total)` byte counts for one upload
+pub type ProgressSink = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// Minimum time between two reports for the same upload
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bytes sent so far for one upload, reported to a sink at most every `interval`
+pub(super) struct UploadProgress {
+    total: u64,
+    state: Mutex<ProgressState>,
+    interval: Duration,
+    sink: ProgressSink,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    /// Bytes sent of each part (a single PUT is part 0) in its latest attempt
+    parts: HashMap<u32, u64>,
+    /// When the last report went out, and the count it carried
+    last_report: Option<(Instant, u64)>,
+}
+
+impl UploadProgress {
+    pub(super) fn new(total: u64, sink: ProgressSink) -> Self {
+        Self::with_interval(total, sink, PROGRESS_REPORT_INTERVAL)
+    }
+
+    fn with_interval(total: u64, sink: ProgressSink, interval: Duration) -> Self {
+        Self {
+            total,
+            state: Mutex::default(),
+            interval,
+            sink,
+        }
+    }
+
+    /// An attempt at `part` is starting: whatever an earlier attempt sent no longer counts
+    pub(super) fn start_part(&self, part: u32) {
+        if let Ok(mut state) = self.state.lock() {
+            state.parts.insert(part, 0);
+        }
+    }
+
+    /// Count `bytes` more of `part` as sent
+    pub(super) fn advance(&self, part: u32, bytes: u64) {
+        let now = Instant::now();
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        *state.parts.entry(part).or_default() += bytes;
+        let sent = state.parts.values().sum::<u64>().min(self.total);
+        let due = match state.last_report {
+            None => true,
+            Some((_, reported)) if sent == self.total => reported != self.total,
+            Some((last, _)) => now.duration_since(last) >= self.interval,
+        };
+        if due {
+            state.last_report = Some((now, sent));
+            drop(state);
+            (self.sink)(sent, self.total);
+        }
+    }
+}
+
+/// Pass `stream` (one attempt at `part`) through unchanged, advancing `progress` by each
+/// chunk's length.
+pub(super) fn track<S, B>(
+    stream: S,
+    progress: Arc<UploadProgress>,
+    part: u32,
+) -> impl Stream<Item = S::Item>
+where
+    S: Stream<Item = std::io::Result<B>>,
+    B: AsRef<[u8]>,
+{
+    progress.start_part(part);
+    stream.map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            progress.advance(part, bytes.as_ref().len() as u64);
+        }
+        chunk
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Reports = Arc<Mutex<Vec<(u64, u64)>>>;
+
+    fn recording_sink() -> (ProgressSink, Reports) {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink_reports = reports.clone();
+        let sink: ProgressSink = Arc::new(move |sent, total| {
+            sink_reports.lock().unwrap().push((sent, total));
+        });
+        (sink, reports)
+    }
+
+    #[tokio::test]
+    async fn progress_reports_add_up_to_payload() {
+        let payload: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let chunks = || {
+            futures::stream::iter(
+                payload
+                    .chunks(1024)
+                    .map(|c| Ok::<_, std::io::Error>(c.to_vec()))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        // Unthrottled: one report per chunk, and the increments sum to the payload.
+        let (sink, reports) = recording_sink();
+        let progress = Arc::new(UploadProgress::with_interval(
+            payload.len() as u64,
+            sink,
+            Duration::ZERO,
+        ));
+        let forwarded: Vec<u8> = track(chunks(), progress, 0)
+            .map(|c| c.unwrap())
+            .concat()
+            .await;
+        assert_eq!(forwarded, payload);
+        let reports = reports.lock().unwrap().clone();
+        assert_eq!(reports.len(), payload.len().div_ceil(1024));
+        let mut previous = 0;
+        let increments: u64 = reports
+            .iter()
+            .map(|&(sent, _)| sent - std::mem::replace(&mut previous, sent))
+            .sum();
+        assert_eq!(increments, payload.len() as u64);
+        assert!(reports
+            .iter()
+            .all(|&(_, total)| total == payload.len() as u64));
+
+        // Throttled: the intermediate reports are dropped, the final one is not.
+        let (sink, reports) = recording_sink();
+        let progress = Arc::new(UploadProgress::with_interval(
+            payload.len() as u64,
+            sink,
+            Duration::from_secs(3600),
+        ));
+        track(chunks(), progress, 0).for_each(|_| async {}).await;
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![(1024, payload.len() as u64), (10_000, 10_000)]
+        );
+    }
+
+    #[test]
+    fn retried_part_counts_once_and_completion_reports_once() {
+        let (sink, reports) = recording_sink();
+        let progress = UploadProgress::with_interval(300, sink, Duration::ZERO);
+
+        progress.start_part(1);
+        progress.advance(1, 100);
+        progress.advance(1, 50);
+        // Part 1 failed halfway and is sent again from the start.
+        progress.start_part(1);
+        progress.advance(1, 100);
+        progress.advance(1, 100);
+        progress.start_part(2);
+        progress.advance(2, 100);
+        // Past the total (e.g. a resumed part counted again): no further reports.
+        progress.advance(2, 10);
+        progress.advance(2, 10);
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![(100, 300), (150, 300), (100, 300), (200, 300), (300, 300)]
+        );
+    }
+}
@@ -0,0 +1,601 @@
+//! Batch re-upload of orphaned local segments
+//!
+//! Segments the agent never managed to upload (retries exhausted, pending-uploads entry
+//! lost, files copied off another machine) stay on disk as `recording_<id>.<ext>` +
+//! `input_<id>.msgpack` pairs (other names can be matched with `recording.video_glob` and
+//! `recording.input_glob`). `crowd-cast-agent reupload` rebuilds a `CompletedChunk` for
+//! every complete pair in a directory and pushes it through the same [`Uploader`] the agent
+//! uses. A video without its input log, or a log without its video, is skipped: a segment
+//! is only useful to the backend with both halves. So is a segment the agent already
+//! uploaded and only keeps for local retention (`retained_uploads.json`). A segment still
+//! in the agent's pending-upload manifest does go up, and is then dropped from the manifest
+//! so the agent doesn't upload it a second time.
+
+use anyhow::{Context as _, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::hls::{playlist_pieces, video_bytes};
+use super::Uploader;
+use crate::auth::AuthManager;
+use crate::config::{glob_matches, Config, RecordingConfig};
+use crate::data::{decode_input_log, CompletedChunk, InputEvent, SegmentStats, Timebase};
+
+/// Container extensions OBS may have written a segment's video with, and the HLS playlist
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "flv", "ts", "m3u8"];
+
+/// A complete video + input pair found on disk, ready to upload
+#[derive(Debug)]
+pub struct OrphanedSegment {
+    pub chunk: CompletedChunk,
+    pub input_path: PathBuf,
+}
+
+/// Result of scanning a directory for orphaned segments
+#[derive(Debug, Default)]
+pub struct OrphanScan {
+    /// Complete pairs, in segment-id order
+    pub segments: Vec<OrphanedSegment>,
+    /// Files left alone: half of an incomplete pair, or an unreadable input log
+    pub skipped: Vec<PathBuf>,
+    /// Segments already uploaded and kept only for local retention, in segment-id order
+    pub retained: Vec<String>,
+}
+
+/// Outcome of a re-upload run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReuploadSummary {
+    pub uploaded: usize,
+    pub failed: usize,
+}
+
+/// Session id a segment id was derived from: the segment id minus its `_segNNNN` (and
+/// optional `_gNNNNNNNN`) suffix.
+fn session_id_for_segment(segment_id: &str) -> &str {
+    segment_id
+        .rsplit_once("_seg")
+        .map_or(segment_id, |(session, _)| session)
+}
+
+/// Placeholder in `recording.video_glob` for any of `VIDEO_EXTENSIONS`
+const EXTENSION_PLACEHOLDER: &str = "{ext}";
+
+/// File names that make up a segment on disk (`recording.video_glob` and
+/// `recording.input_glob`). The first `*` of each glob captures the segment id, which is
+/// what pairs a video with its input log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentFilePatterns {
+    pub video: String,
+    pub input: String,
+}
+
+impl SegmentFilePatterns {
+    pub fn from_config(config: &RecordingConfig) -> Self {
+        Self {
+            video: config.video_glob.clone(),
+            input: config.input_glob.clone(),
+        }
+    }
+
+    /// Segment id of a top-level video file. HLS pieces (`recording_<id>.<n>.ts`) aren't
+    /// videos of their own: they go up with their playlist.
+    fn video_segment_id<'a>(&self, file_name: &'a str) -> Option<&'a str> {
+        if !self.video.contains(EXTENSION_PLACEHOLDER) {
+            return segment_id_in(&self.video, file_name);
+        }
+        VIDEO_EXTENSIONS.iter().find_map(|extension| {
+            segment_id_in(
+                &self.video.replace(EXTENSION_PLACEHOLDER, extension),
+                file_name,
+            )
+        })
+    }
+
+    /// Segment id of a finished input log. Partial flush files belong to a segment that
+    /// never finalized, so they don't count.
+    fn input_segment_id<'a>(&self, file_name: &'a str) -> Option<&'a str> {
+        segment_id_in(&self.input, file_name).filter(|id| !id.contains("_partial"))
+    }
+}
+
+impl Default for SegmentFilePatterns {
+    fn default() -> Self {
+        Self::from_config(&RecordingConfig::default())
+    }
+}
+
+/// The text the first `*` of `glob` matches in `file_name`, taking the longest match. A
+/// segment id is never empty and never contains a `.`, which keeps extensions and HLS
+/// piece numbers out of it.
+fn segment_id_in<'a>(glob: &str, file_name: &'a str) -> Option<&'a str> {
+    let (prefix, suffix) = glob.split_once('*')?;
+    let boundaries: Vec<usize> = file_name
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(file_name.len()))
+        .collect();
+    boundaries
+        .iter()
+        .filter(|&&start| glob_matches(prefix, &file_name[..start]))
+        .find_map(|&start| {
+            boundaries
+                .iter()
+                .rev()
+                .filter(|&&end| end > start)
+                .map(|&end| &file_name[start..end])
+                .filter(|id| !id.contains('.'))
+                .find(|id| glob_matches(suffix, &file_name[start + id.len()..]))
+        })
+}
+
+/// Segment videos and input logs found directly under a directory, by segment id
+#[derive(Debug, Default)]
+struct SegmentFiles {
+    videos: BTreeMap<String, PathBuf>,
+    inputs: BTreeMap<String, PathBuf>,
+    /// Second video or input log claiming an already-seen segment id
+    duplicates: Vec<PathBuf>,
+}
+
+impl SegmentFiles {
+    /// Scan `dir` (not its subdirectories, such as `invalid/`). Names are taken in sorted
+    /// order, so which of two files with the same segment id wins doesn't depend on the
+    /// filesystem.
+    fn scan(dir: &Path, patterns: &SegmentFilePatterns) -> Result<Self> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                names.push(name);
+            }
+        }
+        names.sort();
+
+        let mut files = Self::default();
+        for name in names {
+            let (map, id) = if let Some(id) = patterns.video_segment_id(&name) {
+                (&mut files.videos, id)
+            } else if let Some(id) = patterns.input_segment_id(&name) {
+                (&mut files.inputs, id)
+            } else {
+                continue;
+            };
+            let path = dir.join(&name);
+            if map.contains_key(id) {
+                warn!("Skipping {:?}: segment {} already has a file", path, id);
+                files.duplicates.push(path);
+            } else {
+                map.insert(id.to_string(), path);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Video and input log of one segment found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentPair {
+    pub segment_id: String,
+    pub video: PathBuf,
+    pub input: PathBuf,
+}
+
+/// Segment files found directly under a directory
+#[derive(Debug, Default)]
+pub struct SegmentPairs {
+    /// Segments with both halves, in segment-id order
+    pub pairs: Vec<SegmentPair>,
+    /// Videos without an input log, input logs without a video, and second files
+    /// claiming an already-paired segment id
+    pub unmatched: Vec<PathBuf>,
+}
+
+/// Pair the segment videos and input logs directly under `dir` by segment id
+/// (subdirectories such as `invalid/` are not searched). Files matching neither pattern
+/// are ignored.
+pub fn pair_segments(dir: &Path, patterns: &SegmentFilePatterns) -> Result<SegmentPairs> {
+    let SegmentFiles {
+        mut videos,
+        inputs,
+        duplicates,
+    } = SegmentFiles::scan(dir, patterns)?;
+    let mut found = SegmentPairs {
+        pairs: Vec::new(),
+        unmatched: duplicates,
+    };
+    for (segment_id, input) in inputs {
+        match videos.remove(&segment_id) {
+            Some(video) => found.pairs.push(SegmentPair {
+                segment_id,
+                video,
+                input,
+            }),
+            None => {
+                warn!("Skipping {:?}: no video for segment {}", input, segment_id);
+                found.unmatched.push(input);
+            }
+        }
+    }
+    for (segment_id, video) in videos {
+        warn!(
+            "Skipping {:?}: no input log for segment {}",
+            video, segment_id
+        );
+        found.unmatched.push(video);
+    }
+    Ok(found)
+}
+
+/// Find every complete, readable video + input pair directly under `dir` that isn't one of
+/// the `retained` files of an already uploaded segment
+pub fn find_orphaned_segments(
+    dir: &Path,
+    patterns: &SegmentFilePatterns,
+    retained: &[PathBuf],
+) -> Result<OrphanScan> {
+    let SegmentPairs { pairs, unmatched } = pair_segments(dir, patterns)?;
+    // Compared canonically, since `--dir` may name the output directory another way.
+    let canonical =
+        |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let retained: HashSet<PathBuf> = retained.iter().map(|path| canonical(path)).collect();
+
+    let mut scan = OrphanScan {
+        skipped: unmatched,
+        ..OrphanScan::default()
+    };
+    for SegmentPair {
+        segment_id,
+        video: video_path,
+        input: input_path,
+    } in pairs
+    {
+        if retained.contains(&canonical(&video_path)) || retained.contains(&canonical(&input_path))
+        {
+            info!("Skipping segment {}: already uploaded", segment_id);
+            scan.retained.push(segment_id);
+            continue;
+        }
+        let events: Vec<InputEvent> = match std::fs::read(&input_path).and_then(|bytes| {
+            decode_input_log(&bytes)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }) {
+            Ok(events) => events,
+            Err(e) => {
+                warn!(
+                    "Skipping segment {}: can't read {:?}: {}",
+                    segment_id, input_path, e
+                );
+                scan.skipped.extend([input_path, video_path]);
+                continue;
+            }
+        };
+
+        let start_time_us = events.first().map(|e| e.timestamp_us).unwrap_or(0);
+        let end_time_us = events.last().map(|e| e.timestamp_us).unwrap_or(0);
+        let stats = SegmentStats::new(
+            &events,
+            start_time_us,
+            end_time_us,
+            video_bytes(&video_path),
+        );
+        scan.segments.push(OrphanedSegment {
+            chunk: CompletedChunk {
+                session_id: session_id_for_segment(&segment_id).to_string(),
+                chunk_id: segment_id,
+                video_path: Some(video_path),
+                events,
+                start_time_us,
+                end_time_us,
+                // Events were rebased when recorded; the clock origin died with the run
+                // that wrote them.
+                timebase: Timebase::default(),
+                video_start_ns: None,
+                video_offset_us: None,
+                stats,
+            },
+            input_path,
+        });
+    }
+    Ok(scan)
+}
+
+/// Upload each segment in turn, dropping it from the agent's pending-upload manifest and
+/// deleting its files afterwards when `delete` is set. A failed segment is reported and
+/// left on disk; the rest still go up.
+pub async fn reupload_segments(
+    uploader: &Uploader,
+    segments: &[OrphanedSegment],
+    delete: bool,
+) -> ReuploadSummary {
+    let mut summary = ReuploadSummary::default();
+    for segment in segments {
+        let chunk_id = &segment.chunk.chunk_id;
+        match uploader.upload(&segment.chunk).await {
+            Ok(()) => {
+                info!("Re-uploaded segment {}", chunk_id);
+                println!("Uploaded {}", chunk_id);
+                summary.uploaded += 1;
+                crate::sync::remove_pending_upload(chunk_id);
+                if delete {
+                    let pieces = segment
+                        .chunk
+                        .video_path
+                        .as_deref()
+                        .map(playlist_pieces)
+                        .unwrap_or_default();
+                    let files = pieces
+                        .iter()
+                        .chain(&segment.chunk.video_path)
+                        .chain(std::iter::once(&segment.input_path));
+                    for path in files {
+                        if let Err(e) = tokio::fs::remove_file(path).await {
+                            warn!("Failed to delete {:?}: {}", path, e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Re-upload of segment {} failed: {}", chunk_id, e);
+                eprintln!("Failed to upload {}: {}", chunk_id, e);
+                summary.failed += 1;
+            }
+        }
+    }
+    summary
+}
+
+/// `crowd-cast-agent reupload [--dir <path>] [--delete]`: upload the complete segments left
+/// in a directory (the configured output directory by default). Refuses to run alongside a
+/// live agent, which may be uploading the same files.
+pub fn run_reupload_command(
+    args: &[String],
+    auth: Option<Arc<tokio::sync::Mutex<AuthManager>>>,
+) -> i32 {
+    const USAGE: &str = "Usage: crowd-cast-agent reupload [--dir <path>] [--delete]";
+
+    let mut dir: Option<PathBuf> = None;
+    let mut delete = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => match args.next() {
+                Some(path) => dir = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return 2;
+                }
+            },
+            "--delete" => delete = true,
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load config: {}", e);
+            return 1;
+        }
+    };
+    let dir = dir.unwrap_or_else(|| config.output_directory());
+
+    // Held for the whole run, so an agent started meanwhile waits rather than racing us.
+    let _lock = match crate::instance::lock_path()
+        .map(|path| crate::instance::InstanceLock::acquire(&path, std::time::Duration::ZERO))
+    {
+        Some(Err(e)) if e.is::<crate::instance::AlreadyRunning>() => {
+            eprintln!("{}; quit it before re-uploading", e);
+            return 1;
+        }
+        Some(Err(e)) => {
+            warn!("Could not take the single-instance lock: {:#}", e);
+            None
+        }
+        Some(Ok(lock)) => Some(lock),
+        None => None,
+    };
+
+    let patterns = SegmentFilePatterns::from_config(&config.recording);
+    let retained = crate::sync::read_retained_uploads();
+    let scan = match find_orphaned_segments(&dir, &patterns, &retained) {
+        Ok(scan) => scan,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 1;
+        }
+    };
+    for path in &scan.skipped {
+        println!(
+            "Skipped {} (incomplete or unreadable segment)",
+            path.display()
+        );
+    }
+    for segment_id in &scan.retained {
+        println!("Skipped {} (already uploaded)", segment_id);
+    }
+    if scan.segments.is_empty() {
+        println!("No complete segments found in {}", dir.display());
+        return 0;
+    }
+
+    let uploader = Uploader::new(&config, auth);
+    if !uploader.is_configured() {
+        eprintln!("Uploads are not configured in this build");
+        return 1;
+    }
+    let summary = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(reupload_segments(&uploader, &scan.segments, delete)),
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+
+    println!(
+        "Re-uploaded {} segment(s), {} failed, {} file(s) skipped",
+        summary.uploaded,
+        summary.failed,
+        scan.skipped.len()
+    );
+    if summary.failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload::test_support::{mock_uploader, spawn_mock_endpoint};
+    use std::time::Duration;
+
+    fn write_pair(dir: &Path, segment_id: &str) {
+        std::fs::write(
+            dir.join(format!("recording_{}.mp4", segment_id)),
+            b"\0\0\0\x18ftypmp42",
+        )
+        .unwrap();
+        let events: Vec<InputEvent> = Vec::new();
+        std::fs::write(
+            dir.join(format!("input_{}.msgpack", segment_id)),
+            rmp_serde::to_vec(&events).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn pairs_segments_by_custom_globs() {
+        assert_eq!(
+            segment_id_in("screen_*_final.mp4", "screen_s_seg0001_final.mp4"),
+            Some("s_seg0001")
+        );
+        assert_eq!(
+            segment_id_in("screen_*_final.mp4", "screen__final.mp4"),
+            None
+        );
+
+        let dir = std::env::temp_dir().join(format!("cc_pair_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "cam-s_seg0001.mkv",
+            "keys-s_seg0001.msgpack",
+            // Second video for the same segment
+            "cam-s_seg0001.mp4",
+            // Halves without a partner
+            "cam-s_seg0002.mp4",
+            "keys-s_seg0003.msgpack",
+            // HLS playlist and one of its pieces
+            "cam-s_seg0005.m3u8",
+            "cam-s_seg0005.00000.ts",
+            "keys-s_seg0005.msgpack",
+            // Not segment files under these globs
+            "keys-s_seg0006_partial_0.msgpack",
+            "recording_s_seg0004.mp4",
+            "input_s_seg0004.msgpack",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let patterns = SegmentFilePatterns {
+            video: "cam-*.{ext}".to_string(),
+            input: "keys-*.msgpack".to_string(),
+        };
+
+        let found = pair_segments(&dir, &patterns).unwrap();
+        assert_eq!(
+            found.pairs,
+            vec![
+                SegmentPair {
+                    segment_id: "s_seg0001".to_string(),
+                    video: dir.join("cam-s_seg0001.mkv"),
+                    input: dir.join("keys-s_seg0001.msgpack"),
+                },
+                SegmentPair {
+                    segment_id: "s_seg0005".to_string(),
+                    video: dir.join("cam-s_seg0005.m3u8"),
+                    input: dir.join("keys-s_seg0005.msgpack"),
+                },
+            ]
+        );
+        assert_eq!(
+            found.unmatched,
+            vec![
+                dir.join("cam-s_seg0001.mp4"),
+                dir.join("keys-s_seg0003.msgpack"),
+                dir.join("cam-s_seg0002.mp4"),
+            ]
+        );
+
+        // The default globs pair only the agent's own names.
+        let found = pair_segments(&dir, &SegmentFilePatterns::default()).unwrap();
+        assert_eq!(found.pairs.len(), 1);
+        assert_eq!(found.pairs[0].segment_id, "s_seg0004");
+        assert!(found.unmatched.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn uploads_each_complete_pair_and_skips_the_rest() {
+        let dir = std::env::temp_dir().join(format!("crowd-cast-reupload-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_pair(&dir, "sess-a_seg0000");
+        write_pair(&dir, "sess-b_seg0003_g00000042");
+        // Half a pair, and a partial flush that never finalized
+        std::fs::write(
+            dir.join("recording_sess-c_seg0001.mkv"),
+            b"\x1a\x45\xdf\xa3",
+        )
+        .unwrap();
+        std::fs::write(dir.join("input_sess-a_seg0001_partial_0.msgpack"), b"").unwrap();
+
+        // Uploaded before and kept for local retention
+        write_pair(&dir, "sess-a_seg0002");
+        let retained = vec![
+            dir.join("recording_sess-a_seg0002.mp4"),
+            dir.join("input_sess-a_seg0002.msgpack"),
+        ];
+
+        let scan =
+            find_orphaned_segments(&dir, &SegmentFilePatterns::default(), &retained).unwrap();
+        assert_eq!(scan.segments.len(), 2);
+        assert_eq!(scan.skipped, vec![dir.join("recording_sess-c_seg0001.mkv")]);
+        assert_eq!(scan.retained, vec!["sess-a_seg0002".to_string()]);
+        assert_eq!(scan.segments[1].chunk.session_id, "sess-b");
+
+        let (base, log) = spawn_mock_endpoint(Duration::ZERO).await;
+        let summary = reupload_segments(&mock_uploader(&base), &scan.segments, true).await;
+        assert_eq!(
+            summary,
+            ReuploadSummary {
+                uploaded: 2,
+                failed: 0
+            }
+        );
+        let video_puts = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, path, _)| method == "PUT" && path.starts_with("/put/recordings/"))
+            .count();
+        assert_eq!(video_puts, 2);
+        // Uploaded pairs were deleted; the incomplete one is untouched.
+        assert!(!dir.join("recording_sess-a_seg0000.mp4").exists());
+        assert!(!dir.join("input_sess-b_seg0003_g00000042.msgpack").exists());
+        assert!(dir.join("recording_sess-c_seg0001.mkv").exists());
+        assert!(dir.join("recording_sess-a_seg0002.mp4").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}